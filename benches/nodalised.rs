@@ -0,0 +1,191 @@
+//! Micro-benchmarks for the nodalised backend's per-time-step hot paths.
+//!
+//! Run with `cargo bench --bench nodalised`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use rshgf::model::network::{AddNodesOptions, FitOptions, InputDataOptions, Network};
+
+fn report(label: &str, n_iter: usize, f: impl Fn()) {
+    // Warm-up pass so allocations and caches are in steady state.
+    f();
+    let start = Instant::now();
+    for _ in 0..n_iter {
+        f();
+    }
+    let per_iter = start.elapsed() / n_iter as u32;
+    println!("{label:<40} {per_iter:>12.2?} / iter");
+}
+
+/// Three-level continuous HGF: input <- value parent <- volatility parent.
+fn three_level_hgf() -> Network {
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                volatility_children: Some(1.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network.set_update_sequence();
+    network
+}
+
+/// Layered network of non-linear value parents above `n_inputs` leaves.
+fn layered_network(n_inputs: usize, hidden: &[usize], n_outputs: usize) -> Network {
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", n_inputs, AddNodesOptions::default())
+        .unwrap();
+    let mut sizes = hidden.to_vec();
+    sizes.push(n_outputs);
+    network
+        .add_layer_stack(
+            sizes,
+            "continuous-state",
+            None,
+            1.0,
+            Some("tanh".into()),
+            None,
+            true,
+        )
+        .unwrap();
+    network.set_update_sequence();
+    network
+}
+
+fn main() {
+    let n_time = 1_000;
+    let series: Vec<Vec<f64>> = (0..n_time).map(|t| vec![(t as f64 * 0.05).sin()]).collect();
+
+    report("input_data / 3-level HGF / 1000 steps", 50, || {
+        let mut network = three_level_hgf();
+        network
+            .input_data(
+                black_box(series.clone()),
+                None,
+                false,
+                InputDataOptions::default(),
+            )
+            .unwrap();
+        black_box(&network.attributes.states[2].mean);
+    });
+
+    // The buffer copies behind `node_trajectories` on a 1e5-step run: one bulk
+    // copy per node (the current layout) against one copy per contiguous
+    // per-key vector (the layout it replaced, rebuilt here outside the timed
+    // loop), and the strided per-key reads that `get_trajectory` does on the
+    // current layout. The numpy array creation is not included.
+    let long_series: Vec<Vec<f64>> = (0..100_000)
+        .map(|t| vec![(t as f64 * 0.05).sin()])
+        .collect();
+    let mut recorded = three_level_hgf();
+    recorded
+        .input_data(long_series, None, true, InputDataOptions::default())
+        .unwrap();
+    let trajectories = &recorded.node_trajectories.nodes;
+    let node_types: Vec<&str> = recorded
+        .edges
+        .iter()
+        .map(|edges| edges.node_type.as_str())
+        .collect();
+    let per_key_layout: Vec<Vec<Vec<f64>>> = trajectories
+        .iter()
+        .zip(&node_types)
+        .map(|(trajectory, node_type)| {
+            trajectory
+                .keys(node_type)
+                .into_iter()
+                .map(|key| match trajectory.column(key) {
+                    Some(column) => column.to_vec(),
+                    None => trajectory.vector(key).unwrap().data.clone(),
+                })
+                .collect()
+        })
+        .collect();
+    report("trajectories / bulk copy / 1e5 steps", 20, || {
+        for trajectory in trajectories {
+            black_box(trajectory.data.clone());
+        }
+    });
+    report(
+        "trajectories / old per-key layout copy / 1e5 steps",
+        20,
+        || {
+            for columns in &per_key_layout {
+                for column in columns {
+                    black_box(column.clone());
+                }
+            }
+        },
+    );
+    report(
+        "trajectories / strided per-key read / 1e5 steps",
+        20,
+        || {
+            for (trajectory, node_type) in trajectories.iter().zip(&node_types) {
+                for key in trajectory.keys(node_type) {
+                    match trajectory.column(key) {
+                        Some(column) => black_box(column.to_vec()),
+                        None => black_box(trajectory.vector(key).unwrap().data.clone()),
+                    };
+                }
+            }
+        },
+    );
+
+    // Predictors enter at the top of the stack (leafs), targets at the bottom
+    // (roots), as in `fit`'s defaults.
+    let (n_targets, n_predictors) = (8, 2);
+    let build = || layered_network(n_targets, &[16, 16], n_predictors);
+    let template = build();
+    let inputs_x_idxs: Vec<usize> = template.leafs.clone();
+    let inputs_y_idxs: Vec<usize> = template.roots.clone();
+    let x: Vec<Vec<f64>> = (0..n_time)
+        .map(|t| {
+            (0..inputs_x_idxs.len())
+                .map(|j| ((t + j) as f64 * 0.01).cos())
+                .collect()
+        })
+        .collect();
+    let y: Vec<Vec<f64>> = (0..n_time)
+        .map(|t| {
+            (0..inputs_y_idxs.len())
+                .map(|j| ((t * j) as f64 * 0.01).sin())
+                .collect()
+        })
+        .collect();
+    report("fit / layered 2-16-16-8 tanh / 1000 steps", 5, || {
+        let mut network = build();
+        network
+            .fit(
+                black_box(&x),
+                black_box(&y),
+                &inputs_x_idxs,
+                &inputs_y_idxs,
+                Some(0.01),
+                false,
+                "standard",
+                FitOptions::default(),
+            )
+            .unwrap();
+        black_box(&network.attributes.vectors);
+    });
+}
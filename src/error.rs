@@ -0,0 +1,353 @@
+//! Errors raised while running a [`Network`](crate::model::network::Network).
+//!
+//! The update kernels themselves index the network directly; every pass over
+//! the update sequence first checks that the nodes it is about to touch are
+//! well formed (see
+//! [`Network::check_node`](crate::model::network::Network::check_node)), so a
+//! malformed network surfaces as a [`NetworkError`] instead of a panic.
+
+use std::fmt;
+
+use pyo3::exceptions::{
+    PyFloatingPointError, PyIndexError, PyKeyError, PyRuntimeError, PyTypeError, PyValueError,
+};
+use pyo3::PyErr;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NetworkError {
+    /// A node lacks a value an update step reads, e.g. a coupling vector with
+    /// fewer entries than the matching adjacency list.
+    MissingAttribute {
+        node_idx: usize,
+        attribute: &'static str,
+        step: &'static str,
+    },
+    /// A node index that is not part of the network.
+    MissingNode { node_idx: usize, step: &'static str },
+    /// An adjacency list of `node_idx` pointing to a node it cannot point to:
+    /// one that does not exist, or one that would close a cycle.
+    InvalidEdge {
+        node_idx: usize,
+        edge: &'static str,
+        target_idx: usize,
+        step: &'static str,
+        /// Why the edge is rejected, e.g. "refers to missing node".
+        reason: &'static str,
+    },
+    /// Data whose shape does not match the network.
+    DimensionMismatch {
+        what: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    /// A well-formed structure that an update step does not support.
+    UnsupportedStructure {
+        node_idx: usize,
+        step: &'static str,
+        reason: &'static str,
+    },
+    /// A Python update step that raised an exception or does not fit the
+    /// update sequence.
+    PythonStep { node_idx: usize, message: String },
+    /// An initial parameter that nodes of `node_type` do not have.
+    InvalidParameter {
+        node_type: String,
+        parameter: String,
+        accepted: &'static [&'static str],
+    },
+    /// A value that a setting does not take, e.g. an unknown update type or an
+    /// autoconnection strength outside `(0, 1]`.
+    InvalidValue {
+        parameter: &'static str,
+        value: String,
+        accepted: &'static str,
+    },
+    /// A trajectory key that was not recorded for `node_idx`, either unknown
+    /// for its node type or left out by `record_keys`.
+    UnrecordedKey { node_idx: usize, key: String },
+    /// A belief of `node_idx` that became NaN or infinite at time step
+    /// `time_idx` of a run with `check_divergence` on. `recent_values` ends
+    /// with the offending value, preceded by the last recorded ones.
+    Diverged {
+        time_idx: usize,
+        node_idx: usize,
+        attribute: &'static str,
+        recent_values: Vec<f64>,
+    },
+    /// A posterior precision of `node_idx` that came out non-positive in
+    /// `step` under the `error` negative-precision policy. `precision` is
+    /// `expected_precision` plus the `contributions` of the children, by child
+    /// index.
+    NegativePrecision {
+        node_idx: usize,
+        step: &'static str,
+        expected_precision: f64,
+        precision: f64,
+        contributions: Vec<(usize, f64)>,
+    },
+    /// A network that cannot take observations: no nodes, no input node, or
+    /// no update step to run.
+    NotRunnable { reason: &'static str },
+    /// An observation that input node `node_idx` cannot take, e.g. anything
+    /// but 0 or 1 for a binary input.
+    InvalidObservation {
+        node_idx: usize,
+        value: f64,
+        accepted: &'static str,
+    },
+    /// A network spec (see [`crate::utils::spec`]) that cannot be parsed or
+    /// built, with one entry per problem found.
+    InvalidSpec { problems: Vec<String> },
+    /// A slice of time steps (Python-style, negative indices counting from the
+    /// end) that does not fit in the `n_time` recorded steps.
+    InvalidTimeRange {
+        start: isize,
+        stop: isize,
+        step: usize,
+        n_time: usize,
+    },
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingAttribute {
+                node_idx,
+                attribute,
+                step,
+            } => write!(
+                f,
+                "node {}: missing attribute `{}` (update step `{}`)",
+                node_idx, attribute, step
+            ),
+            Self::MissingNode { node_idx, step } => {
+                write!(
+                    f,
+                    "node {} does not exist (update step `{}`)",
+                    node_idx, step
+                )
+            }
+            Self::InvalidEdge {
+                node_idx,
+                edge,
+                target_idx,
+                step,
+                reason,
+            } => write!(
+                f,
+                "node {}: `{}` {} {} (update step `{}`)",
+                node_idx, edge, reason, target_idx, step
+            ),
+            Self::DimensionMismatch {
+                what,
+                expected,
+                found,
+            } => write!(f, "{}: expected {}, got {}", what, expected, found),
+            Self::UnsupportedStructure {
+                node_idx,
+                step,
+                reason,
+            } => write!(f, "node {}: {} (update step `{}`)", node_idx, reason, step),
+            Self::PythonStep { node_idx, message } => {
+                write!(f, "node {}: Python update step {}", node_idx, message)
+            }
+            Self::InvalidParameter {
+                node_type,
+                parameter,
+                accepted,
+            } => write!(
+                f,
+                "`{}` is not a parameter of {} nodes (accepted: {})",
+                parameter,
+                node_type,
+                if accepted.is_empty() {
+                    "none".to_string()
+                } else {
+                    accepted.join(", ")
+                }
+            ),
+            Self::InvalidValue {
+                parameter,
+                value,
+                accepted,
+            } => write!(
+                f,
+                "invalid `{}` {} (expected {})",
+                parameter, value, accepted
+            ),
+            Self::UnrecordedKey { node_idx, key } => {
+                write!(f, "node {}: no recorded trajectory for `{}`", node_idx, key)
+            }
+            Self::Diverged {
+                time_idx,
+                node_idx,
+                attribute,
+                recent_values,
+            } => write!(
+                f,
+                "the run diverged at time step {}: node {} `{}` became {} (last values: {:?})",
+                time_idx,
+                node_idx,
+                attribute,
+                recent_values.last().copied().unwrap_or(f64::NAN),
+                recent_values
+            ),
+            Self::NegativePrecision {
+                node_idx,
+                step,
+                expected_precision,
+                precision,
+                contributions,
+            } => {
+                write!(
+                    f,
+                    "node {}: non-positive posterior precision {} in `{}` (expected precision {}",
+                    node_idx, precision, step, expected_precision
+                )?;
+                for (child_idx, contribution) in contributions {
+                    write!(f, ", child {}: {:+}", child_idx, contribution)?;
+                }
+                write!(f, ")")
+            }
+            Self::NotRunnable { reason } => write!(f, "cannot run the network: {}", reason),
+            Self::InvalidObservation {
+                node_idx,
+                value,
+                accepted,
+            } => write!(
+                f,
+                "node {}: invalid observation {} (expected {})",
+                node_idx, value, accepted
+            ),
+            Self::InvalidSpec { problems } => {
+                write!(f, "invalid network spec:")?;
+                for problem in problems {
+                    write!(f, "\n  - {}", problem)?;
+                }
+                Ok(())
+            }
+            Self::InvalidTimeRange {
+                start,
+                stop,
+                step,
+                n_time,
+            } => write!(
+                f,
+                "time steps {}:{}:{} are not a valid slice of the {} recorded steps",
+                start, stop, step, n_time
+            ),
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+/// Lookups of nodes and attributes raise `KeyError`; badly shaped inputs,
+/// invalid setting values and invalid specs raise `ValueError`; adjacency
+/// lists that are inconsistent with the network or with an update step raise
+/// `RuntimeError`; unknown node parameters raise `TypeError`, like an
+/// unexpected keyword argument; out-of-range time slices raise `IndexError`;
+/// and diverging runs or negative posterior precisions raise
+/// `FloatingPointError`.
+impl From<NetworkError> for PyErr {
+    fn from(err: NetworkError) -> PyErr {
+        let msg = err.to_string();
+        match err {
+            NetworkError::MissingAttribute { .. }
+            | NetworkError::MissingNode { .. }
+            | NetworkError::UnrecordedKey { .. } => PyKeyError::new_err(msg),
+            NetworkError::DimensionMismatch { .. }
+            | NetworkError::InvalidObservation { .. }
+            | NetworkError::InvalidValue { .. }
+            | NetworkError::InvalidSpec { .. } => PyValueError::new_err(msg),
+            NetworkError::InvalidEdge { .. }
+            | NetworkError::UnsupportedStructure { .. }
+            | NetworkError::PythonStep { .. }
+            | NetworkError::NotRunnable { .. } => PyRuntimeError::new_err(msg),
+            NetworkError::InvalidParameter { .. } => PyTypeError::new_err(msg),
+            NetworkError::InvalidTimeRange { .. } => PyIndexError::new_err(msg),
+            NetworkError::Diverged { .. } | NetworkError::NegativePrecision { .. } => {
+                PyFloatingPointError::new_err(msg)
+            }
+        }
+    }
+}
+
+/// Failure of a strict [`set_coupling`](crate::utils::set_coupling::set_coupling) or
+/// [`set_volatility_coupling`](crate::utils::set_coupling::set_volatility_coupling).
+#[derive(Debug, Clone, PartialEq)]
+pub enum SetCouplingError {
+    /// `parent_idx` and `child_idx` are not connected by a value-coupling edge
+    /// (on either side of the adjacency lists).
+    NoSuchEdge { parent_idx: usize, child_idx: usize },
+    /// `parent_idx` and `child_idx` are not connected by a volatility-coupling
+    /// edge.
+    NoSuchVolatilityEdge { parent_idx: usize, child_idx: usize },
+    /// The coupling vector that should hold the edge is empty.
+    VectorMissing {
+        node_idx: usize,
+        attribute: &'static str,
+    },
+    /// The coupling vector has no entry at the edge's position.
+    VectorTooShort {
+        node_idx: usize,
+        attribute: &'static str,
+        position: usize,
+        len: usize,
+    },
+}
+
+impl fmt::Display for SetCouplingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoSuchEdge {
+                parent_idx,
+                child_idx,
+            } => write!(
+                f,
+                "node {} is not a value parent of node {}",
+                parent_idx, child_idx
+            ),
+            Self::NoSuchVolatilityEdge {
+                parent_idx,
+                child_idx,
+            } => write!(
+                f,
+                "node {} is not a volatility parent of node {}",
+                parent_idx, child_idx
+            ),
+            Self::VectorMissing {
+                node_idx,
+                attribute,
+            } => write!(f, "node {}: `{}` is empty", node_idx, attribute),
+            Self::VectorTooShort {
+                node_idx,
+                attribute,
+                position,
+                len,
+            } => write!(
+                f,
+                "node {}: `{}` has {} entries, no entry for edge {}",
+                node_idx, attribute, len, position
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SetCouplingError {}
+
+/// A missing edge is a bad argument (`ValueError`); a missing or short coupling
+/// vector means the network itself is inconsistent (`RuntimeError`).
+impl From<SetCouplingError> for PyErr {
+    fn from(err: SetCouplingError) -> PyErr {
+        let msg = err.to_string();
+        match err {
+            SetCouplingError::NoSuchEdge { .. } | SetCouplingError::NoSuchVolatilityEdge { .. } => {
+                PyValueError::new_err(msg)
+            }
+            SetCouplingError::VectorMissing { .. } | SetCouplingError::VectorTooShort { .. } => {
+                PyRuntimeError::new_err(msg)
+            }
+        }
+    }
+}
@@ -3,6 +3,7 @@
 #[cfg(feature = "blas")]
 extern crate blas_src;
 
+pub mod error;
 pub mod math;
 pub mod model;
 pub mod optimiser;
@@ -18,6 +19,9 @@ use pyo3::prelude::*;
 #[pymodule]
 fn rshgf(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<model::network::Network>()?;
+    m.add_class::<model::network::StateSnapshot>()?;
+    m.add_class::<model::network::NodeView>()?;
+    m.add_class::<model::network::NodeHandle>()?;
     m.add_class::<model::deep_network::DeepNetwork>()?;
     Ok(())
 }
@@ -33,6 +33,73 @@ pub fn logaddexp(a: f64, b: f64) -> f64 {
     m + ((a - m).exp() + (b - m).exp()).ln()
 }
 
+/// Differential entropy `0.5·ln(2πe/π)` of a Gaussian of precision `π`, in
+/// nats.
+#[inline]
+pub fn gaussian_entropy(precision: f64) -> f64 {
+    0.5 * (2.0 * std::f64::consts::PI * std::f64::consts::E / precision).ln()
+}
+
+/// Log-density of `x` under a Gaussian with the given mean and precision.
+#[inline]
+pub fn gaussian_log_density(x: f64, mean: f64, precision: f64) -> f64 {
+    0.5 * (precision.ln() - (2.0 * std::f64::consts::PI).ln() - precision * (x - mean).powi(2))
+}
+
+/// Log-density of `x` under a Laplace distribution with the given mean and
+/// the variance `1 / precision`, i.e. the scale `b = 1 / √(2·precision)`.
+#[inline]
+pub fn laplace_log_density(x: f64, mean: f64, precision: f64) -> f64 {
+    let inverse_scale = (2.0 * precision).sqrt();
+    (0.5 * inverse_scale).ln() - inverse_scale * (x - mean).abs()
+}
+
+/// Latent value of `x` on the bounded interval `[lower, upper]`:
+/// `logit((x − lower) / (upper − lower))`, with the proportion clamped to
+/// `[epsilon, 1 − epsilon]` so that the bounds map to finite values.
+#[inline]
+pub fn bounded_to_latent(x: f64, lower: f64, upper: f64, epsilon: f64) -> f64 {
+    let u = ((x - lower) / (upper - lower)).clamp(epsilon, 1.0 - epsilon);
+    (u / (1.0 - u)).ln()
+}
+
+/// Inverse of [`bounded_to_latent`]: `lower + (upper − lower) · σ(y)`.
+#[inline]
+pub fn latent_to_bounded(y: f64, lower: f64, upper: f64) -> f64 {
+    lower + (upper - lower) * sigmoid(y)
+}
+
+/// `ln |dx/dy|` of the bounded transform at the latent value `y`:
+/// `ln((upper − lower) · σ(y) (1 − σ(y)))`. Added to the Gaussian surprise
+/// of `y`, it gives the surprise of the observation on its own scale.
+#[inline]
+pub fn bounded_log_jacobian(y: f64, lower: f64, upper: f64) -> f64 {
+    let u = sigmoid(y);
+    ((upper - lower) * u * (1.0 - u)).ln()
+}
+
+/// Angle `x` wrapped into `(−π, π]`: `atan2(sin x, cos x)`, with `−π` sent to
+/// `π`. Applied to a difference of angles, it gives the shortest signed arc.
+#[inline]
+pub fn wrap_angle(x: f64) -> f64 {
+    let wrapped = x.sin().atan2(x.cos());
+    if wrapped <= -std::f64::consts::PI {
+        std::f64::consts::PI
+    } else {
+        wrapped
+    }
+}
+
+/// `KL(N(μ, 1/π) ‖ N(μ̂, 1/π̂))` between two univariate Gaussians given in
+/// mean/precision form, as `(μ, π, μ̂, π̂)`.
+#[inline]
+pub fn gaussian_kl(mean: f64, precision: f64, prior_mean: f64, prior_precision: f64) -> f64 {
+    0.5 * ((precision / prior_precision).ln()
+        + prior_precision / precision
+        + prior_precision * (mean - prior_mean).powi(2)
+        - 1.0)
+}
+
 /// Resolve a coupling-function name, erroring on unknown names.
 ///
 /// This is the validating counterpart of [`resolve_coupling_fn`], used at the
@@ -46,10 +113,13 @@ pub fn parse_coupling_fn(name: &str) -> Result<&'static CouplingFn, String> {
         "tanh" => Ok(&TANH),
         "leaky_relu" => Ok(&LEAKY_RELU),
         "gelu" => Ok(&GELU),
+        "gelu_tanh" => Ok(&GELU_TANH),
+        "logit" => Ok(&LOGIT),
+        "softplus" => Ok(&SOFTPLUS),
         other => Err(format!(
             "Unknown coupling function '{other}'. Choose from [\"linear\", \
              \"identity\", \"relu\", \"sigmoid\", \"tanh\", \"leaky_relu\", \
-             \"gelu\"]."
+             \"gelu\", \"gelu_tanh\", \"logit\", \"softplus\"]."
         )),
     }
 }
@@ -57,7 +127,7 @@ pub fn parse_coupling_fn(name: &str) -> Result<&'static CouplingFn, String> {
 /// A coupling (activation) function together with its first and second derivatives.
 ///
 /// Use the module-level constants ([`LINEAR`], [`RELU`], [`SIGMOID`], [`TANH`],
-/// [`LEAKY_RELU`], [`GELU`]) to obtain a `&'static CouplingFn`, or call
+/// [`LEAKY_RELU`], [`GELU`], [`GELU_TANH`], [`LOGIT`], [`SOFTPLUS`]) to obtain a `&'static CouplingFn`, or call
 /// [`resolve_coupling_fn`] to resolve from a string name at node-creation time.
 ///
 /// # Example
@@ -81,8 +151,15 @@ pub struct CouplingFn {
     pub d2f: fn(f64) -> f64,
 }
 
+/// Coupling functions compare by [`CouplingFn::kind`], i.e. by name.
+impl PartialEq for CouplingFn {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
+
 /// Tag identifying one of the named coupling functions.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum CouplingKind {
     /// Identity.
     Linear,
@@ -96,6 +173,29 @@ pub enum CouplingKind {
     LeakyRelu,
     /// Gaussian error linear unit.
     Gelu,
+    /// Tanh approximation of the Gaussian error linear unit.
+    GeluTanh,
+    /// Log-odds, the inverse of the sigmoid.
+    Logit,
+    /// Softplus, a smooth rectifier.
+    Softplus,
+}
+
+impl CouplingKind {
+    /// Name of the coupling function, as accepted by [`parse_coupling_fn`].
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Linear => "linear",
+            Self::Relu => "relu",
+            Self::Sigmoid => "sigmoid",
+            Self::Tanh => "tanh",
+            Self::LeakyRelu => "leaky_relu",
+            Self::Gelu => "gelu",
+            Self::GeluTanh => "gelu_tanh",
+            Self::Logit => "logit",
+            Self::Softplus => "softplus",
+        }
+    }
 }
 
 /// Run `$body` with `$f`/`$df`/`$d2f` bound to the *function items* of the
@@ -146,6 +246,24 @@ macro_rules! with_coupling {
                 let $d2f = $crate::math::gelu_d2;
                 $body
             }
+            $crate::math::CouplingKind::GeluTanh => {
+                let $f = $crate::math::gelu_tanh;
+                let $df = $crate::math::gelu_tanh_d1;
+                let $d2f = $crate::math::gelu_tanh_d2;
+                $body
+            }
+            $crate::math::CouplingKind::Logit => {
+                let $f = $crate::math::logit;
+                let $df = $crate::math::logit_d1;
+                let $d2f = $crate::math::logit_d2;
+                $body
+            }
+            $crate::math::CouplingKind::Softplus => {
+                let $f = $crate::math::softplus;
+                let $df = $crate::math::softplus_d1;
+                let $d2f = $crate::math::softplus_d2;
+                $body
+            }
         }
     }};
 }
@@ -248,6 +366,25 @@ pub const TANH: CouplingFn = CouplingFn {
     d2f: tanh_d2,
 };
 
+// ─── Scaled tanh ─────────────────────────────────────────────────────────────
+
+/// Scaled hyperbolic tangent: $f(x, a, b) = a\tanh(bx)$, with gain $a$ and
+/// slope $b$.
+///
+/// Note: like [`prelu`], the free parameters keep it out of the `CouplingFn`
+/// constants; `a = b = 1` recovers [`tanh`].
+pub fn scaled_tanh(x: f64, a: f64, b: f64) -> f64 {
+    a * (b * x).tanh()
+}
+/// First derivative of scaled tanh: $f'(x) = ab(1 - \tanh^2(bx))$.
+pub fn scaled_tanh_d1(x: f64, a: f64, b: f64) -> f64 {
+    a * b * tanh_d1(b * x)
+}
+/// Second derivative of scaled tanh: $f''(x) = -2ab^2\tanh(bx)(1 - \tanh^2(bx))$.
+pub fn scaled_tanh_d2(x: f64, a: f64, b: f64) -> f64 {
+    a * b * b * tanh_d2(b * x)
+}
+
 // ─── Leaky ReLU ──────────────────────────────────────────────────────────────
 
 /// Leaky ReLU with fixed slope $\alpha = 0.01$: $f(x) = x$ if $x \ge 0$, else $0.01x$.
@@ -333,7 +470,8 @@ fn erfc(x: f64) -> f64 {
 /// whose default is the *tanh* approximation — up to ~5 × 10⁻⁴ from the exact
 /// GELU — so GELU-coupled networks agree across backends only to that
 /// tolerance. Passing `approximate=False` on the JAX side tightens the
-/// agreement to the ~2 × 10⁻⁷ of this approximation. Every other coupling
+/// agreement to the ~2 × 10⁻⁷ of this approximation, and [`gelu_tanh`]
+/// evaluates the tanh approximation itself. Every other coupling
 /// function evaluates the same closed form in both backends and matches to
 /// machine precision.
 pub fn gelu(x: f64) -> f64 {
@@ -358,6 +496,100 @@ pub const GELU: CouplingFn = CouplingFn {
     d2f: gelu_d2,
 };
 
+/// √(2/π), the scale of the tanh argument in [`gelu_tanh`].
+const GELU_TANH_SCALE: f64 = 0.797_884_560_802_865_4;
+/// Cubic coefficient of the tanh argument in [`gelu_tanh`].
+const GELU_TANH_CUBIC: f64 = 0.044715;
+
+/// Tanh-approximated GELU: $f(x) = \tfrac12 x (1 + \tanh u)$ with
+/// $u = \sqrt{2/\pi}\,(x + 0.044715 x^3)$, the default of `jax.nn.gelu` and
+/// of several ML frameworks. Within ~5 × 10⁻⁴ of the exact [`gelu`].
+pub fn gelu_tanh(x: f64) -> f64 {
+    let u = GELU_TANH_SCALE * (x + GELU_TANH_CUBIC * x.powi(3));
+    0.5 * x * (1.0 + u.tanh())
+}
+/// First derivative of the tanh GELU:
+/// $f'(x) = \tfrac12 (1 + \tanh u) + \tfrac12 x (1 - \tanh^2 u)\, u'$.
+pub fn gelu_tanh_d1(x: f64) -> f64 {
+    let u = GELU_TANH_SCALE * (x + GELU_TANH_CUBIC * x.powi(3));
+    let du = GELU_TANH_SCALE * (1.0 + 3.0 * GELU_TANH_CUBIC * x * x);
+    let t = u.tanh();
+    0.5 * (1.0 + t) + 0.5 * x * (1.0 - t * t) * du
+}
+/// Second derivative of the tanh GELU:
+/// $f''(x) = (1 - \tanh^2 u)\,[u' + \tfrac12 x (u'' - 2 \tanh u\, u'^2)]$.
+pub fn gelu_tanh_d2(x: f64) -> f64 {
+    let u = GELU_TANH_SCALE * (x + GELU_TANH_CUBIC * x.powi(3));
+    let du = GELU_TANH_SCALE * (1.0 + 3.0 * GELU_TANH_CUBIC * x * x);
+    let d2u = GELU_TANH_SCALE * 6.0 * GELU_TANH_CUBIC * x;
+    let t = u.tanh();
+    (1.0 - t * t) * (du + 0.5 * x * (d2u - 2.0 * t * du * du))
+}
+/// [`CouplingFn`] constant for the tanh-approximated GELU coupling function.
+pub const GELU_TANH: CouplingFn = CouplingFn {
+    kind: CouplingKind::GeluTanh,
+    f: gelu_tanh,
+    df: gelu_tanh_d1,
+    d2f: gelu_tanh_d2,
+};
+
+// ─── Logit ───────────────────────────────────────────────────────────────────
+
+/// Inputs to [`logit`] and its derivatives are clamped into
+/// $(\varepsilon, 1 - \varepsilon)$ so that they stay finite at and beyond the
+/// edges of the unit interval.
+pub const LOGIT_EPSILON: f64 = 1e-6;
+
+fn logit_domain(x: f64) -> f64 {
+    x.clamp(LOGIT_EPSILON, 1.0 - LOGIT_EPSILON)
+}
+
+/// Logit (inverse sigmoid): $f(x) = \ln(x / (1 - x))$ for $x \in (0, 1)$.
+pub fn logit(x: f64) -> f64 {
+    let x = logit_domain(x);
+    (x / (1.0 - x)).ln()
+}
+/// First derivative of logit: $f'(x) = 1 / (x(1 - x))$.
+pub fn logit_d1(x: f64) -> f64 {
+    let x = logit_domain(x);
+    1.0 / (x * (1.0 - x))
+}
+/// Second derivative of logit: $f''(x) = (2x - 1) / (x(1 - x))^2$.
+pub fn logit_d2(x: f64) -> f64 {
+    let x = logit_domain(x);
+    (2.0 * x - 1.0) / (x * (1.0 - x)).powi(2)
+}
+/// [`CouplingFn`] constant for the logit coupling function.
+pub const LOGIT: CouplingFn = CouplingFn {
+    kind: CouplingKind::Logit,
+    f: logit,
+    df: logit_d1,
+    d2f: logit_d2,
+};
+
+// ─── Softplus ────────────────────────────────────────────────────────────────
+
+/// Softplus: $f(x) = \ln(1 + e^x)$, computed as $\max(x, 0) + \ln(1 + e^{-|x|})$
+/// so that it does not overflow for large inputs.
+pub fn softplus(x: f64) -> f64 {
+    x.max(0.0) + (-x.abs()).exp().ln_1p()
+}
+/// First derivative of softplus: $f'(x) = \sigma(x)$.
+pub fn softplus_d1(x: f64) -> f64 {
+    sigmoid(x)
+}
+/// Second derivative of softplus: $f''(x) = \sigma(x)(1 - \sigma(x))$.
+pub fn softplus_d2(x: f64) -> f64 {
+    sigmoid_d1(x)
+}
+/// [`CouplingFn`] constant for the softplus coupling function.
+pub const SOFTPLUS: CouplingFn = CouplingFn {
+    kind: CouplingKind::Softplus,
+    f: softplus,
+    df: softplus_d1,
+    d2f: softplus_d2,
+};
+
 // ─── Resolver ────────────────────────────────────────────────────────────────
 
 /// Resolve a coupling-function name to its [`CouplingFn`] constant.
@@ -375,6 +607,9 @@ pub const GELU: CouplingFn = CouplingFn {
 /// | `"tanh"` | [`TANH`] |
 /// | `"leaky_relu"` | [`LEAKY_RELU`] |
 /// | `"gelu"` | [`GELU`] |
+/// | `"gelu_tanh"` | [`GELU_TANH`] |
+/// | `"logit"` | [`LOGIT`] |
+/// | `"softplus"` | [`SOFTPLUS`] |
 ///
 /// Any unrecognised name falls back to [`LINEAR`]; use [`parse_coupling_fn`]
 /// where an unknown name should instead be an error.
@@ -382,6 +617,21 @@ pub fn resolve_coupling_fn(name: &str) -> &'static CouplingFn {
     parse_coupling_fn(name).unwrap_or(&LINEAR)
 }
 
+/// Name of `coupling_fn`, as accepted by [`parse_coupling_fn`] (the reverse
+/// lookup used to report the coupling of each edge).
+pub fn coupling_fn_name(coupling_fn: &CouplingFn) -> &'static str {
+    coupling_fn.kind.name()
+}
+
+/// Value, first and second derivative at `x` of an optional coupling
+/// function, `None` standing for the identity `(x, 1, 0)`.
+pub fn coupling_at(coupling_fn: Option<&CouplingFn>, x: f64) -> (f64, f64, f64) {
+    match coupling_fn {
+        Some(cf) => ((cf.f)(x), (cf.df)(x), (cf.d2f)(x)),
+        None => (x, 1.0, 0.0),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -400,6 +650,28 @@ mod tests {
         );
     }
 
+    // ── gaussian_kl ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_gaussian_kl() {
+        assert_close(
+            gaussian_kl(0.3, 2.0, 0.3, 2.0),
+            0.0,
+            "KL of identical Gaussians",
+        );
+        // KL(N(1, 1) ‖ N(0, 1)) = ½
+        assert_close(
+            gaussian_kl(1.0, 1.0, 0.0, 1.0),
+            0.5,
+            "KL for a unit mean shift",
+        );
+        assert_close(
+            gaussian_log_density(0.0, 0.0, 1.0),
+            -0.5 * (2.0 * std::f64::consts::PI).ln(),
+            "standard normal log-density at 0",
+        );
+    }
+
     // ── sufficient_statistics ─────────────────────────────────────────────────
 
     #[test]
@@ -486,6 +758,9 @@ mod tests {
             "tanh",
             "leaky_relu",
             "gelu",
+            "gelu_tanh",
+            "logit",
+            "softplus",
         ] {
             assert!(parse_coupling_fn(name).is_ok(), "'{name}' should parse");
         }
@@ -504,6 +779,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_coupling_kind_name_round_trip() {
+        for cf in [
+            &LINEAR,
+            &RELU,
+            &SIGMOID,
+            &TANH,
+            &LEAKY_RELU,
+            &GELU,
+            &GELU_TANH,
+            &LOGIT,
+            &SOFTPLUS,
+        ] {
+            assert_eq!(parse_coupling_fn(cf.kind.name()).unwrap().kind, cf.kind);
+        }
+        assert_eq!(coupling_fn_name(&SIGMOID), "sigmoid");
+        assert_eq!(coupling_fn_name(resolve_coupling_fn("identity")), "linear");
+    }
+
     #[test]
     fn test_resolve_coupling_fn_fallback() {
         assert_eq!(resolve_coupling_fn("tanh").kind, CouplingKind::Tanh);
@@ -514,8 +808,18 @@ mod tests {
 
     /// Check `d1`/`d2` against central differences of `f` at off-kink points.
     fn check_derivatives(f: fn(f64) -> f64, d1: fn(f64) -> f64, d2: fn(f64) -> f64, name: &str) {
+        check_derivatives_at(f, d1, d2, name, &[-2.0, -0.5, 0.3, 1.7]);
+    }
+
+    fn check_derivatives_at(
+        f: fn(f64) -> f64,
+        d1: fn(f64) -> f64,
+        d2: fn(f64) -> f64,
+        name: &str,
+        points: &[f64],
+    ) {
         let h = 1e-5;
-        for &x in &[-2.0, -0.5, 0.3, 1.7] {
+        for &x in points {
             let num_d1 = (f(x + h) - f(x - h)) / (2.0 * h);
             assert!(
                 (d1(x) - num_d1).abs() < 1e-4,
@@ -539,12 +843,26 @@ mod tests {
         check_derivatives(tanh, tanh_d1, tanh_d2, "tanh");
         check_derivatives(leaky_relu, leaky_relu_d1, leaky_relu_d2, "leaky_relu");
         check_derivatives(gelu, gelu_d1, gelu_d2, "gelu");
+        check_derivatives(gelu_tanh, gelu_tanh_d1, gelu_tanh_d2, "gelu_tanh");
+        // The logit is only defined on the unit interval.
+        check_derivatives_at(logit, logit_d1, logit_d2, "logit", &[0.1, 0.3, 0.5, 0.8]);
+        check_derivatives(softplus, softplus_d1, softplus_d2, "softplus");
     }
 
     #[test]
     fn test_with_coupling_matches_fn_pointers() {
         // The macro must bind the same functions the constants store as pointers.
-        for cf in [&LINEAR, &RELU, &SIGMOID, &TANH, &LEAKY_RELU, &GELU] {
+        for cf in [
+            &LINEAR,
+            &RELU,
+            &SIGMOID,
+            &TANH,
+            &LEAKY_RELU,
+            &GELU,
+            &GELU_TANH,
+            &LOGIT,
+            &SOFTPLUS,
+        ] {
             let x = 0.7;
             let (fv, dfv, d2fv) = with_coupling!(cf, |f, df, d2f| (f(x), df(x), d2f(x)));
             assert_close(fv, (cf.f)(x), "with_coupling f");
@@ -605,6 +923,50 @@ mod tests {
         assert_close(sigmoid(x) + sigmoid(-x), 1.0, "sigmoid symmetry");
     }
 
+    // ── logit ─────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_logit_half() {
+        assert_close(logit(0.5), 0.0, "logit(0.5)");
+    }
+
+    #[test]
+    fn test_logit_symmetry() {
+        // logit(p) = −logit(1 − p)
+        for &p in &[0.05, 0.2, 0.35, 0.9] {
+            assert_close(logit(p), -logit(1.0 - p), "logit symmetry");
+        }
+    }
+
+    #[test]
+    fn test_logit_inverts_sigmoid() {
+        assert_close(logit(sigmoid(1.3)), 1.3, "logit(sigmoid(1.3))");
+    }
+
+    #[test]
+    fn test_logit_clamps_its_domain() {
+        // Out-of-range inputs are clamped into (ε, 1 − ε) instead of giving
+        // NaN or infinities.
+        for &x in &[-1.0, 0.0, 1.0, 2.0] {
+            assert!(logit(x).is_finite(), "logit({x})");
+            assert!(logit_d1(x).is_finite(), "logit'({x})");
+            assert!(logit_d2(x).is_finite(), "logit''({x})");
+        }
+        assert_eq!(logit(0.0), logit(-1.0));
+        assert!((logit(1.0) + logit(0.0)).abs() < 1e-9);
+    }
+
+    // ── softplus ──────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_softplus_values() {
+        assert_close(softplus(0.0), std::f64::consts::LN_2, "softplus(0)");
+        assert_close(softplus(1.5), (1.0 + 1.5_f64.exp()).ln(), "softplus(1.5)");
+        // Stable far from zero: ≈ x for large x, ≈ eˣ for very negative x.
+        assert_close(softplus(800.0), 800.0, "softplus(800)");
+        assert!(softplus(-800.0) >= 0.0 && softplus(-800.0) < 1e-300);
+    }
+
     // ── tanh ──────────────────────────────────────────────────────────────────
 
     #[test]
@@ -633,6 +995,38 @@ mod tests {
         assert!(tanh(-20.0) < -0.99, "tanh(-20) < -0.99");
     }
 
+    // ── scaled_tanh ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_scaled_tanh_unit_parameters_match_tanh() {
+        for &x in &[-2.0, -0.5, 0.0, 0.3, 1.7] {
+            assert_close(scaled_tanh(x, 1.0, 1.0), tanh(x), "scaled_tanh(a=b=1)");
+            assert_close(scaled_tanh_d1(x, 1.0, 1.0), tanh_d1(x), "scaled_tanh'");
+            assert_close(scaled_tanh_d2(x, 1.0, 1.0), tanh_d2(x), "scaled_tanh''");
+        }
+    }
+
+    #[test]
+    fn test_scaled_tanh_derivatives_scale_with_parameters() {
+        // f' scales as a·b and f'' as a·b² of tanh's derivatives at b·x.
+        let (x, a, b) = (0.4, 2.5, 0.7);
+        assert_close(scaled_tanh_d1(x, a, b), a * b * tanh_d1(b * x), "f'");
+        assert_close(scaled_tanh_d2(x, a, b), a * b * b * tanh_d2(b * x), "f''");
+        assert_close(scaled_tanh(20.0, a, b), a, "saturates at the gain");
+
+        let h = 1e-5;
+        for &x in &[-2.0, -0.5, 0.3, 1.7] {
+            let f = |x: f64| scaled_tanh(x, a, b);
+            let num_d1 = (f(x + h) - f(x - h)) / (2.0 * h);
+            let num_d2 = (f(x + h) - 2.0 * f(x) + f(x - h)) / (h * h);
+            assert!((scaled_tanh_d1(x, a, b) - num_d1).abs() < 1e-4, "f' at {x}");
+            assert!(
+                (scaled_tanh_d2(x, a, b) - num_d2).abs() < 1e-4,
+                "f'' at {x}"
+            );
+        }
+    }
+
     // ── leaky_relu ────────────────────────────────────────────────────────────
 
     #[test]
@@ -732,4 +1126,33 @@ mod tests {
             gelu(-1.0)
         );
     }
+
+    // ── gelu_tanh ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_gelu_tanh_matches_exact_gelu() {
+        // The two variants differ by at most ~5e-4 (near |x| ≈ 2).
+        for i in -60..=60 {
+            let x = i as f64 / 10.0;
+            assert!(
+                (gelu_tanh(x) - gelu(x)).abs() < 1e-3,
+                "gelu_tanh({x}) = {}, gelu({x}) = {}",
+                gelu_tanh(x),
+                gelu(x)
+            );
+        }
+        assert_close(gelu_tanh(0.0), 0.0, "gelu_tanh(0)");
+        assert_close(gelu_tanh(10.0), 10.0, "gelu_tanh(10) ≈ 10");
+    }
+
+    #[test]
+    fn test_gelu_tanh_derivatives_off_the_default_points() {
+        check_derivatives_at(
+            gelu_tanh,
+            gelu_tanh_d1,
+            gelu_tanh_d2,
+            "gelu_tanh",
+            &[-4.0, -1.0, 0.0, 2.5, 5.0],
+        );
+    }
 }
@@ -1,17 +1,45 @@
+use crate::error::{NetworkError, SetCouplingError};
+use crate::math::coupling_fn_name;
 use crate::optimiser::AdamState;
 use crate::updates::nodalised::observations::{set_observation, set_predictors};
-use crate::utils::beliefs_propagation::belief_propagation;
-use crate::utils::function_pointer::UpdateStep;
-use crate::utils::set_learning_sequence::build_learning_sequence;
-use crate::utils::set_sequence::set_update_sequence;
+use crate::utils::beliefs_propagation::{
+    belief_propagation, check_observations, run_predictions, run_updates, set_observations,
+};
+use crate::utils::clusters::spawn_clusters;
+use crate::utils::compare;
+use crate::utils::conditions::{ConditionParameters, ConditionSchedule};
+use crate::utils::covariates::{CovariateDrive, CovariateWeights};
+use crate::utils::edge_lookup::{build_edge_lookup, EdgeLookup};
+use crate::utils::entropy::set_entropy;
+use crate::utils::free_energy::{free_energy, FreeEnergy};
+use crate::utils::function_pointer::{StepKind, UpdateStep};
+use crate::utils::gains::{GainSchedule, PeGains};
+use crate::utils::model_summary::{model_summary, ModelSummary};
+use crate::utils::pyhgf_spec::{from_pyhgf_spec, to_pyhgf_spec, PyhgfNode};
+use crate::utils::python_step::{PythonStep, StepPlacement};
+use crate::utils::sample::{sample, simulate_responses, Samples};
+use crate::utils::set_coupling::{
+    get_coupling, get_volatility_coupling, set_coupling, set_coupling_frozen, set_coupling_lag,
+    set_drift_coupling, set_volatility_coupling, try_set_coupling,
+};
+use crate::utils::set_learning_sequence::{build_learning_sequence, LearningSequence};
+use crate::utils::set_sequence::{
+    get_update_sequence_graph, get_updates_sequence, set_update_sequence,
+};
+use crate::utils::spec::{from_spec_str, to_spec_str};
+use crate::utils::steady_state::steady_state_precision;
+use crate::utils::surprise::{cv_surprise, set_surprise, surprise_by_node};
+use crate::utils::trajectory_summary::summary;
+use crate::utils::trajectory_table::{trajectory_matrix, trajectory_table, write_trajectories_csv};
 use crate::utils::weight_initialisation::weight_init_by_name;
-use numpy::{PyArray, PyArray1, PyArrayMethods};
-use pyo3::types::PyTuple;
+use numpy::{PyArray1, PyArrayMethods};
+use pyo3::types::{PyIterator, PySlice, PyTuple};
 use pyo3::{
     prelude::*,
     types::{PyDict, PyList},
 };
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::ControlFlow;
 
 /// Accepts either a single int or a list of ints from Python.
 /// Allows `value_children=0` or `value_children=[0, 1]`.
@@ -53,7 +81,113 @@ impl IntOrList {
     }
 }
 
+/// Accepts either a single float or a list of floats from Python.
+/// Allows `coupling=-0.5` or `coupling=[1.0, -0.5]`.
 #[derive(Debug, Clone)]
+pub enum FloatOrList {
+    Single(f64),
+    List(Vec<f64>),
+}
+
+impl<'a, 'py> FromPyObject<'a, 'py> for FloatOrList {
+    type Error = PyErr;
+    fn extract(ob: pyo3::Borrowed<'a, 'py, PyAny>) -> PyResult<Self> {
+        if let Ok(val) = ob.extract::<f64>() {
+            Ok(FloatOrList::Single(val))
+        } else {
+            Ok(FloatOrList::List(ob.extract::<Vec<f64>>()?))
+        }
+    }
+}
+
+impl From<Vec<f64>> for FloatOrList {
+    fn from(v: Vec<f64>) -> Self {
+        FloatOrList::List(v)
+    }
+}
+
+impl From<f64> for FloatOrList {
+    fn from(v: f64) -> Self {
+        FloatOrList::Single(v)
+    }
+}
+
+/// Edges and initial values of the nodes created by [`Network::add_nodes`].
+/// Every field is optional: `AddNodesOptions { value_children: Some(0.into()),
+/// ..Default::default() }` adds a value parent of node 0.
+#[derive(Debug, Clone, Default)]
+pub struct AddNodesOptions {
+    pub value_parents: Option<IntOrList>,
+    pub value_children: Option<IntOrList>,
+    pub volatility_parents: Option<IntOrList>,
+    pub volatility_children: Option<IntOrList>,
+    /// Coupling function of the value edges towards the children (linear
+    /// when omitted).
+    pub coupling_fn: Option<String>,
+    /// Value-coupling strengths: a scalar for every value edge, or one per
+    /// value child then per value parent (1 when omitted).
+    pub coupling: Option<FloatOrList>,
+    /// Volatility-coupling strengths, laid out like `coupling`.
+    pub volatility_coupling: Option<FloatOrList>,
+    /// Initial values of the parameters the kind accepts (see
+    /// [`add_nodes_parameters`]).
+    pub additional_parameters: Option<HashMap<String, f64>>,
+}
+
+/// Trial-by-trial inputs of [`Network::input_data`] besides the
+/// observations. Every field is optional: `InputDataOptions::default()` runs
+/// the observations alone.
+#[derive(Debug, Clone, Default)]
+pub struct InputDataOptions {
+    /// One condition label per time step, and the parameters each condition
+    /// sets (see [`crate::utils::conditions`]).
+    pub conditions: Option<(Vec<usize>, ConditionParameters)>,
+    /// Measured covariates, one row per time step, and the node and weight
+    /// each column drives (see [`crate::utils::covariates`]).
+    pub covariates: Option<(Vec<Vec<f64>>, CovariateWeights)>,
+    /// Trial-by-trial gains on the prediction errors of input nodes, one
+    /// value in `[0, 1]` per time step for each listed input: the posterior
+    /// updates of an input's parents scale its contributions by the step's
+    /// gain, so that an unattended observation (gain 0) leaves them at their
+    /// predictions (see [`crate::utils::gains`]). The applied gains are
+    /// recorded as `pe_gain`.
+    pub pe_gains: Option<PeGains>,
+    /// Scan the beliefs for divergence during this run, as when
+    /// [`Network::check_divergence`] is set; the setting is left unchanged.
+    pub check_divergence: bool,
+}
+
+/// Optional settings of [`Network::fit`]: `FitOptions::default()` trains
+/// with the default Adam hyper-parameters and the network's divergence
+/// setting.
+#[derive(Debug, Clone, Default)]
+pub struct FitOptions {
+    /// Adam hyper-parameters (only used when `lr == None`): `beta1` (default
+    /// 0.9), `beta2` (default 0.999), `epsilon` (default 1e-8), and `lr`
+    /// (default 1e-3, the Adam step size).
+    pub params: Option<HashMap<String, f64>>,
+    /// Scan the beliefs for divergence during this fit, as when
+    /// [`Network::check_divergence`] is set; the setting is left unchanged.
+    pub check_divergence: bool,
+}
+
+impl FloatOrList {
+    /// One value per edge: a scalar is broadcast to `n` entries, a list must
+    /// hold exactly `n`.
+    fn into_vec(self, n: usize, what: &'static str) -> Result<Vec<f64>, NetworkError> {
+        match self {
+            FloatOrList::Single(v) => Ok(vec![v; n]),
+            FloatOrList::List(v) if v.len() == n => Ok(v),
+            FloatOrList::List(v) => Err(NetworkError::DimensionMismatch {
+                what,
+                expected: n,
+                found: v.len(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 #[pyclass(skip_from_py_object)]
 pub struct AdjacencyLists {
     #[pyo3(get, set)]
@@ -68,9 +202,17 @@ pub struct AdjacencyLists {
     pub volatility_parents: Option<Vec<usize>>,
     #[pyo3(get, set)]
     pub volatility_children: Option<Vec<usize>>,
+    /// Nodes whose mean sets the log expected noise (variance) of this input.
+    #[pyo3(get, set)]
+    pub precision_parents: Option<Vec<usize>>,
+    #[pyo3(get, set)]
+    pub precision_children: Option<Vec<usize>>,
+    /// Observation model of an input node: `"gaussian"` or `"laplace"`.
+    #[pyo3(get, set)]
+    pub likelihood: String,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 pub struct UpdateSequence {
     pub predictions: Vec<(usize, UpdateStep)>,
     pub updates: Vec<(usize, UpdateStep)>,
@@ -97,10 +239,26 @@ pub struct NodeState {
     pub tonic_volatility: f64,
     pub tonic_drift: f64,
     pub autoconnection_strength: f64,
+    /// Node-level cap on the value-level posterior precision (infinite by
+    /// default), applied on top of `Network::max_posterior_precision`.
+    pub max_precision: f64,
     pub current_variance: f64,
+    /// Variance stored in `current_variance` by the first prediction instead
+    /// of `1 / precision` (NaN: none). Cleared once used.
+    pub initial_current_variance: f64,
+    /// `mean` as it stood before the latest update phase (NaN before the
+    /// first one), through which lagged value edges drive the children's
+    /// predictions (see [`Network::set_coupling_lag`]).
+    pub previous_mean: f64,
     pub effective_precision: f64,
     pub value_prediction_error: f64,
     pub volatility_prediction_error: f64,
+    /// Asymmetry `a ∈ (−1, 1)` of the value mean update: the children's
+    /// positive prediction errors are scaled by `1 + a`, negative ones by
+    /// `1 − a`.
+    pub pe_asymmetry: f64,
+    /// Scaling applied by `pe_asymmetry` to the last value mean update.
+    pub pe_scaling: f64,
     // Volatile-state internal volatility level
     pub mean_vol: f64,
     pub expected_mean_vol: f64,
@@ -108,11 +266,69 @@ pub struct NodeState {
     pub expected_precision_vol: f64,
     pub tonic_volatility_vol: f64,
     pub tonic_drift_vol: f64,
+    pub autoconnection_strength_vol: f64,
     pub effective_precision_vol: f64,
     // EF-state
     pub nus: f64,
+    /// Factor in `(0, 1]` discounting the pseudo-count `nus` before each
+    /// observation (1 keeps the fixed learning rate `1 / (1 + nus)`).
+    pub forgetting: f64,
+    /// 1.0 when an ef-state node accumulates its observations with Welford's
+    /// online mean/variance update instead of the raw `[x, x²]` statistics.
+    pub welford: f64,
+    /// Running mean and variance of the observations of an ef-state node,
+    /// read from `xis` (or tracked directly when `welford` is set).
+    pub tracked_mean: f64,
+    pub tracked_variance: f64,
+    // Softmax-state
+    pub temperature: f64,
+    // DP-state
+    /// Concentration α of the Chinese-restaurant prior over the clusters of a
+    /// dp-state node: the weight of cluster `k` is `n_k / (n + α)`.
+    pub concentration: f64,
+    /// Predictive density under which an observation opens a new cluster of
+    /// a dp-state node, when no existing cluster reaches it.
+    pub density_threshold: f64,
+    // Bounded-state
+    /// Bounds of the observations of a bounded-state node, which filters
+    /// their logit `ln(u / (1 − u))`, `u = (x − lower) / (upper − lower)`.
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+    /// Guard keeping `u` in `[ε, 1 − ε]`, so that observations on the bounds
+    /// have a finite logit.
+    pub bound_epsilon: f64,
+    /// Prediction of a bounded-state node on the scale of its observations:
+    /// `lower + (upper − lower) · σ(expected_mean)`.
+    pub expected_bounded_mean: f64,
+    // Lognormal-state
+    /// Median and mean of the log-normal prediction of the observations of a
+    /// lognormal-state node: `exp(μ̂)` and `exp(μ̂ + 1/(2π̂))`.
+    pub lognormal_median: f64,
+    pub lognormal_mean: f64,
+    /// Surprise of the last update: the categorical surprise of the observed
+    /// choice for softmax nodes, see [`crate::utils::surprise::node_surprise`]
+    /// for the others.
+    pub surprise: f64,
+    /// Gain in `[0, 1]` on the prediction errors this node sends to its
+    /// parents at the current step (1: unscaled), set per trial by
+    /// [`InputDataOptions::pe_gains`].
+    pub pe_gain: f64,
+    /// Entropy of the Gaussian belief of continuous and volatile nodes after
+    /// the last update, from the precision chosen by
+    /// [`Network::entropy_source`] (NaN for the other kinds).
+    pub entropy: f64,
     // Learning
     pub lr: f64,
+    /// Rate at which `learning_tonic_drift` moves `tonic_drift` toward the
+    /// observed change of the mean (NaN: the drift is not learned).
+    pub tonic_drift_lr: f64,
+    /// Bound on the magnitude of each coupling gradient of
+    /// `learning_weights`, applied before the learning rate (infinite: no
+    /// clipping).
+    pub coupling_grad_clip: f64,
+    /// Drift added by measured covariates at the current step (see
+    /// [`InputDataOptions::covariates`]).
+    pub covariate_drift: f64,
 }
 
 impl Default for NodeState {
@@ -127,49 +343,211 @@ impl Default for NodeState {
             tonic_volatility: 0.0,
             tonic_drift: 0.0,
             autoconnection_strength: 0.0,
+            max_precision: f64::INFINITY,
             current_variance: 1.0,
+            initial_current_variance: f64::NAN,
+            previous_mean: f64::NAN,
             effective_precision: 0.0,
             value_prediction_error: 0.0,
             volatility_prediction_error: 0.0,
+            pe_asymmetry: 0.0,
+            pe_scaling: 1.0,
             mean_vol: 0.0,
             expected_mean_vol: 0.0,
             precision_vol: 1.0,
             expected_precision_vol: 1.0,
             tonic_volatility_vol: -4.0,
             tonic_drift_vol: 0.0,
+            autoconnection_strength_vol: 1.0,
             effective_precision_vol: 0.0,
             nus: 0.0,
+            forgetting: 1.0,
+            welford: 0.0,
+            tracked_mean: f64::NAN,
+            tracked_variance: f64::NAN,
+            temperature: 1.0,
+            concentration: 1.0,
+            density_threshold: 1e-3,
+            lower_bound: 0.0,
+            upper_bound: 1.0,
+            bound_epsilon: 1e-6,
+            expected_bounded_mean: f64::NAN,
+            lognormal_median: f64::NAN,
+            lognormal_mean: f64::NAN,
+            surprise: 0.0,
+            pe_gain: 1.0,
+            entropy: f64::NAN,
             lr: f64::NAN,
+            tonic_drift_lr: f64::NAN,
+            coupling_grad_clip: f64::INFINITY,
+            covariate_drift: 0.0,
         }
     }
 }
 
 /// Per-node variable-length vector attributes.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct NodeVectors {
     pub value_coupling_parents: Vec<f64>,
     pub value_coupling_children: Vec<f64>,
     pub volatility_coupling_parents: Vec<f64>,
     pub volatility_coupling_children: Vec<f64>,
     pub xis: Vec<f64>,
+    /// Per value parent (in `value_parents` order), 1.0 when the coupling is
+    /// held fixed by `learning_weights`. May be shorter than
+    /// `value_coupling_parents`; missing entries count as not frozen.
+    pub coupling_frozen: Vec<f64>,
+    /// Per value parent (in `value_parents` order), 1.0 when the parent drives
+    /// this node's prediction with a one-step delay (see
+    /// [`Network::set_coupling_lag`]). May be shorter than
+    /// `value_coupling_parents`; missing entries count as not lagged.
+    pub coupling_lag: Vec<f64>,
+    /// Per value parent (in `value_parents` order), 1.0 when the parent is a
+    /// drift parent of this node (see [`Network::set_drift_coupling`]). May
+    /// be shorter than `value_coupling_parents`; missing entries count as
+    /// ordinary value edges.
+    pub coupling_drift: Vec<f64>,
+    /// DP-state: number of observations assigned to each cluster, one per
+    /// value parent (in `value_parents` order). May be shorter than the
+    /// parents; a missing entry counts as one observation.
+    pub cluster_counts: Vec<f64>,
+    /// Softmax-state: predicted probability of each alternative, one per value
+    /// parent (in `value_parents` order).
+    pub probabilities: Vec<f64>,
+    /// Per value parent (in `value_parents` order), the multiplier the
+    /// parent's last mean update applied to this node's value prediction
+    /// error: the effective learning rate of the edge. Filled by the parents'
+    /// posterior updates.
+    pub learning_rate_parents: Vec<f64>,
+    /// Aggregate-input: precision of each observation stream, in column
+    /// order (see [`Network::set_input_precision`]).
+    pub input_precision: Vec<f64>,
+}
+
+impl NodeVectors {
+    /// Every vector attribute with its name, in [`STATE_VECTOR_FIELDS`]
+    /// order. The destructuring is exhaustive, so a new field cannot be left
+    /// out of the lookups, the state export or the comparisons.
+    fn fields(&self) -> [(&'static str, &Vec<f64>); 12] {
+        let NodeVectors {
+            value_coupling_parents,
+            value_coupling_children,
+            volatility_coupling_parents,
+            volatility_coupling_children,
+            xis,
+            coupling_frozen,
+            coupling_lag,
+            coupling_drift,
+            cluster_counts,
+            probabilities,
+            learning_rate_parents,
+            input_precision,
+        } = self;
+        [
+            ("xis", xis),
+            ("value_coupling_parents", value_coupling_parents),
+            ("value_coupling_children", value_coupling_children),
+            ("volatility_coupling_parents", volatility_coupling_parents),
+            ("volatility_coupling_children", volatility_coupling_children),
+            ("probabilities", probabilities),
+            ("learning_rate_parents", learning_rate_parents),
+            ("coupling_frozen", coupling_frozen),
+            ("coupling_lag", coupling_lag),
+            ("coupling_drift", coupling_drift),
+            ("cluster_counts", cluster_counts),
+            ("input_precision", input_precision),
+        ]
+    }
+
+    /// [`NodeVectors::fields`] with mutable access.
+    fn fields_mut(&mut self) -> [(&'static str, &mut Vec<f64>); 12] {
+        let NodeVectors {
+            value_coupling_parents,
+            value_coupling_children,
+            volatility_coupling_parents,
+            volatility_coupling_children,
+            xis,
+            coupling_frozen,
+            coupling_lag,
+            coupling_drift,
+            cluster_counts,
+            probabilities,
+            learning_rate_parents,
+            input_precision,
+        } = self;
+        [
+            ("xis", xis),
+            ("value_coupling_parents", value_coupling_parents),
+            ("value_coupling_children", value_coupling_children),
+            ("volatility_coupling_parents", volatility_coupling_parents),
+            ("volatility_coupling_children", volatility_coupling_children),
+            ("probabilities", probabilities),
+            ("learning_rate_parents", learning_rate_parents),
+            ("coupling_frozen", coupling_frozen),
+            ("coupling_lag", coupling_lag),
+            ("coupling_drift", coupling_drift),
+            ("cluster_counts", cluster_counts),
+            ("input_precision", input_precision),
+        ]
+    }
+
+    /// Vector attribute looked up by name (one of [`STATE_VECTOR_FIELDS`]).
+    pub fn get(&self, field: &str) -> Option<&[f64]> {
+        self.fields()
+            .into_iter()
+            .find(|&(name, _)| name == field)
+            .map(|(_, vector)| vector.as_slice())
+    }
+
+    /// Mutable access to a vector attribute, looked up by name.
+    pub fn get_mut(&mut self, field: &str) -> Option<&mut Vec<f64>> {
+        self.fields_mut()
+            .into_iter()
+            .find(|(name, _)| *name == field)
+            .map(|(_, vector)| vector)
+    }
 }
 
 /// Per-node function pointer attributes.
 ///
-/// The coupling function is defined on the **parent** node and applies to all
-/// its value children.  `None` means linear coupling (the default) and avoids
-/// any function-pointer call overhead at runtime.
-#[derive(Debug, Clone, Copy)]
+/// The coupling functions are defined on the **parent** node and apply to all
+/// its value children (`coupling_fn`) or volatility children
+/// (`volatility_coupling_fn`).  `None` means linear coupling (the default) and
+/// avoids any function-pointer call overhead at runtime.
+///
+/// `value_coupling_fn_children` works in the other direction. `coupling_fn`
+/// is evaluated at the parent's mean and maps the parent into the space of
+/// its children (generative direction: `μ̂_c = κ g(μ_p)`). A child-side
+/// function `h` maps the child into the space of the parent before the two
+/// are compared (recognition direction): the parent's posterior update reads
+/// the child's prediction error as `h(μ_c) − h(μ̂_c)` instead of
+/// `μ_c − μ̂_c`, while the child's own prediction is unchanged.
+#[derive(Debug, Clone, PartialEq)]
 pub struct NodeFnPtrs {
     pub coupling_fn: Option<&'static crate::math::CouplingFn>,
+    pub volatility_coupling_fn: Option<&'static crate::math::CouplingFn>,
+    /// Child-side coupling function of each value child, in `value_children`
+    /// order (`None`, or a missing entry, means the identity). Set with
+    /// [`Network::set_child_coupling_fn`].
+    pub value_coupling_fn_children: Vec<Option<&'static crate::math::CouplingFn>>,
 }
 
 impl Default for NodeFnPtrs {
     fn default() -> Self {
-        NodeFnPtrs { coupling_fn: None }
+        NodeFnPtrs {
+            coupling_fn: None,
+            volatility_coupling_fn: None,
+            value_coupling_fn_children: Vec::new(),
+        }
     }
 }
 
+/// Per-node attribute storage, indexed by node id.
+///
+/// Every node owns a fixed-layout [`NodeState`] (all scalars), a
+/// [`NodeVectors`] (coupling vectors and other per-edge values) and a
+/// [`NodeFnPtrs`]. Update kernels read and write plain struct fields, so the
+/// belief-propagation loop does no hashing or string-keyed lookups.
 #[derive(Debug, Clone)]
 pub struct Attributes {
     pub states: Vec<NodeState>,
@@ -177,121 +555,759 @@ pub struct Attributes {
     pub fn_ptrs: Vec<NodeFnPtrs>,
 }
 
+/// A strided view of one recorded scalar field: entry `t` is the value at
+/// time step `t`.
+#[derive(Debug, Clone, Copy)]
+pub struct TrajectoryColumn<'a> {
+    data: &'a [f64],
+    offset: usize,
+    stride: usize,
+}
+
+impl<'a> TrajectoryColumn<'a> {
+    pub fn len(&self) -> usize {
+        self.data.len() / self.stride
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'a f64> {
+        self.data.iter().skip(self.offset).step_by(self.stride)
+    }
+
+    pub fn to_vec(&self) -> Vec<f64> {
+        self.iter().copied().collect()
+    }
+}
+
+impl std::ops::Index<usize> for TrajectoryColumn<'_> {
+    type Output = f64;
+
+    fn index(&self, t: usize) -> &f64 {
+        &self.data[t * self.stride + self.offset]
+    }
+}
+
+/// Time-major trajectory of a variable-length vector attribute, flattened with
+/// a fixed stride: the longest length the attribute had over the run, shorter
+/// rows being padded with NaN.
+#[derive(Debug, Clone, Default)]
+pub struct VectorTrajectory {
+    pub data: Vec<f64>,
+    pub stride: usize,
+    /// Left out of the recording (see [`Network::set_record_keys`]).
+    pub skipped: bool,
+}
+
+impl VectorTrajectory {
+    /// Append `v` as the row of time step `n_time`. A longer row than the
+    /// earlier ones widens them (see [`VectorTrajectory::widen`]), a shorter
+    /// one is padded with NaN, so every row keeps the same stride.
+    fn push(&mut self, v: &[f64], n_time: usize) {
+        if self.skipped || (v.is_empty() && self.stride == 0) {
+            return;
+        }
+        if v.len() > self.stride || self.len() != n_time {
+            self.widen(v.len().max(self.stride), n_time);
+        }
+        self.data.extend_from_slice(v);
+        self.data
+            .resize(self.data.len() + self.stride - v.len(), f64::NAN);
+    }
+
+    fn truncate(&mut self, n_time: usize) {
+        self.data.truncate(n_time * self.stride);
+    }
+
+    /// Lay the recording out for an attribute now `len` long, over `n_time`
+    /// recorded time steps: earlier rows are padded with NaN to the new
+    /// length, and the time steps before the attribute was first recorded
+    /// are filled with NaN rows.
+    fn widen(&mut self, len: usize, n_time: usize) {
+        if self.skipped || len == 0 || len < self.stride {
+            return;
+        }
+        if len == self.stride && self.len() == n_time {
+            return;
+        }
+        let n_recorded = self.len();
+        let mut data = vec![f64::NAN; n_time.saturating_sub(n_recorded) * len];
+        for t in 0..n_recorded {
+            data.extend_from_slice(&self[t]);
+            data.resize(data.len() + len - self.stride, f64::NAN);
+        }
+        self.data = data;
+        self.stride = len;
+    }
+
+    /// Copy of the recorded time steps `rows`, in order.
+    fn select(&self, rows: &[usize]) -> Self {
+        VectorTrajectory {
+            data: rows.iter().flat_map(|&t| &self[t]).copied().collect(),
+            stride: self.stride,
+            skipped: self.skipped,
+        }
+    }
+
+    /// Number of recorded time steps.
+    pub fn len(&self) -> usize {
+        self.data.len().checked_div(self.stride).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+impl std::ops::Index<usize> for VectorTrajectory {
+    type Output = [f64];
+
+    fn index(&self, t: usize) -> &[f64] {
+        &self.data[t * self.stride..(t + 1) * self.stride]
+    }
+}
+
+/// One recorded trajectory of a node, as returned by
+/// [`Network::get_trajectory`].
+#[derive(Debug, Clone, Copy)]
+pub enum RecordedTrajectory<'a> {
+    /// A scalar field, one value per time step.
+    Scalar(TrajectoryColumn<'a>),
+    /// A vector attribute, one row per time step.
+    Vector(&'a VectorTrajectory),
+}
+
 /// Trajectory recording for a single node.
-#[derive(Debug)]
+///
+/// Scalar fields are stored in a single time-major buffer: row `t` holds the
+/// recorded [`TRAJECTORY_FIELDS`] of time step `t`, and each field is read back
+/// as a strided [`TrajectoryColumn`] through its accessor (`traj.mean()[t]`).
+#[derive(Debug, Default)]
 pub struct NodeTrajectory {
-    pub mean: Vec<f64>,
-    pub expected_mean: Vec<f64>,
-    pub precision: Vec<f64>,
-    pub expected_precision: Vec<f64>,
-    pub observed: Vec<f64>,
-    pub tonic_volatility: Vec<f64>,
-    pub tonic_drift: Vec<f64>,
-    pub autoconnection_strength: Vec<f64>,
-    pub current_variance: Vec<f64>,
-    pub effective_precision: Vec<f64>,
-    pub value_prediction_error: Vec<f64>,
-    pub volatility_prediction_error: Vec<f64>,
-    pub mean_vol: Vec<f64>,
-    pub expected_mean_vol: Vec<f64>,
-    pub precision_vol: Vec<f64>,
-    pub expected_precision_vol: Vec<f64>,
-    pub tonic_volatility_vol: Vec<f64>,
-    pub tonic_drift_vol: Vec<f64>,
-    pub effective_precision_vol: Vec<f64>,
-    pub nus: Vec<f64>,
-    pub lr: Vec<f64>,
-    // Vector trajectory
-    pub xis: Vec<Vec<f64>>,
-    pub value_coupling_parents: Vec<Vec<f64>>,
-    pub value_coupling_children: Vec<Vec<f64>>,
-    pub volatility_coupling_parents: Vec<Vec<f64>>,
-    pub volatility_coupling_children: Vec<Vec<f64>>,
+    pub data: Vec<f64>,
+    pub n_time: usize,
+    /// Number of leading time steps recorded before the node was added during
+    /// the run (the clusters of dp-state nodes). Their rows are NaN, and the
+    /// free energy, surprise and summary statistics skip them.
+    pub born: usize,
+    /// Indices in [`TRAJECTORY_FIELDS`] of the columns of `data`, when only
+    /// some fields are recorded; `None` records all of them.
+    pub columns: Option<Vec<usize>>,
+    // Vector trajectories
+    pub xis: VectorTrajectory,
+    pub value_coupling_parents: VectorTrajectory,
+    pub value_coupling_children: VectorTrajectory,
+    pub volatility_coupling_parents: VectorTrajectory,
+    pub volatility_coupling_children: VectorTrajectory,
+    pub probabilities: VectorTrajectory,
+    pub learning_rate_parents: VectorTrajectory,
+}
+
+/// Declares the recorded scalar fields once: the column order, the row push
+/// and one column accessor per field are all generated from this list. The
+/// transient fields after `;` are not recorded but can still be looked up.
+macro_rules! trajectory_fields {
+    ($($field:ident),* ; $($transient:ident),* $(,)?) => {
+        #[allow(non_camel_case_types)]
+        enum TrajectoryField {
+            $($field),*
+        }
+
+        /// Scalar `NodeState` fields recorded at every time step, in column order.
+        pub const TRAJECTORY_FIELDS: &[&str] = &[$(stringify!($field)),*];
+
+        /// Every scalar `NodeState` field: the recorded ones, then the
+        /// transient ones.
+        pub const STATE_FIELDS: &[&str] =
+            &[$(stringify!($field),)* $(stringify!($transient)),*];
+
+        impl NodeState {
+            /// Value of a scalar field (one of [`STATE_FIELDS`]), looked up by
+            /// name.
+            pub fn get(&self, field: &str) -> Option<f64> {
+                match field {
+                    $(stringify!($field) => Some(self.$field),)*
+                    $(stringify!($transient) => Some(self.$transient),)*
+                    _ => None,
+                }
+            }
+
+            /// Mutable access to a scalar field, looked up by name.
+            pub fn get_mut(&mut self, field: &str) -> Option<&mut f64> {
+                match field {
+                    $(stringify!($field) => Some(&mut self.$field),)*
+                    $(stringify!($transient) => Some(&mut self.$transient),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl NodeTrajectory {
+            pub fn push_state(&mut self, s: &NodeState) {
+                let row = [$(s.$field),*];
+                match &self.columns {
+                    None => self.data.extend_from_slice(&row),
+                    Some(columns) => self.data.extend(columns.iter().map(|&col| row[col])),
+                }
+                self.n_time += 1;
+            }
+
+            $(
+                #[doc = concat!("Recorded `", stringify!($field), "`, one entry per time step.")]
+                pub fn $field(&self) -> TrajectoryColumn<'_> {
+                    self.column_at(TrajectoryField::$field as usize)
+                }
+            )*
+        }
+    };
 }
 
+trajectory_fields!(
+    mean,
+    expected_mean,
+    precision,
+    expected_precision,
+    observed,
+    tonic_volatility,
+    tonic_drift,
+    autoconnection_strength,
+    current_variance,
+    effective_precision,
+    value_prediction_error,
+    volatility_prediction_error,
+    pe_asymmetry,
+    pe_scaling,
+    mean_vol,
+    expected_mean_vol,
+    precision_vol,
+    expected_precision_vol,
+    tonic_volatility_vol,
+    tonic_drift_vol,
+    autoconnection_strength_vol,
+    effective_precision_vol,
+    nus,
+    forgetting,
+    temperature,
+    surprise,
+    pe_gain,
+    entropy,
+    expected_bounded_mean,
+    lognormal_median,
+    lognormal_mean,
+    tracked_mean,
+    tracked_variance,
+    lr;
+    conditional_expected_precision,
+    max_precision,
+    tonic_drift_lr,
+    coupling_grad_clip,
+    initial_current_variance,
+    covariate_drift,
+    previous_mean,
+    concentration,
+    density_threshold,
+    lower_bound,
+    upper_bound,
+    bound_epsilon,
+    welford,
+);
+
 impl NodeTrajectory {
     pub fn with_capacity(n: usize) -> Self {
         NodeTrajectory {
-            mean: Vec::with_capacity(n),
-            expected_mean: Vec::with_capacity(n),
-            precision: Vec::with_capacity(n),
-            expected_precision: Vec::with_capacity(n),
-            observed: Vec::with_capacity(n),
-            tonic_volatility: Vec::with_capacity(n),
-            tonic_drift: Vec::with_capacity(n),
-            autoconnection_strength: Vec::with_capacity(n),
-            current_variance: Vec::with_capacity(n),
-            effective_precision: Vec::with_capacity(n),
-            value_prediction_error: Vec::with_capacity(n),
-            volatility_prediction_error: Vec::with_capacity(n),
-            mean_vol: Vec::with_capacity(n),
-            expected_mean_vol: Vec::with_capacity(n),
-            precision_vol: Vec::with_capacity(n),
-            expected_precision_vol: Vec::with_capacity(n),
-            tonic_volatility_vol: Vec::with_capacity(n),
-            tonic_drift_vol: Vec::with_capacity(n),
-            effective_precision_vol: Vec::with_capacity(n),
-            nus: Vec::with_capacity(n),
-            lr: Vec::with_capacity(n),
-            xis: Vec::with_capacity(n),
-            value_coupling_parents: Vec::with_capacity(n),
-            value_coupling_children: Vec::with_capacity(n),
-            volatility_coupling_parents: Vec::with_capacity(n),
-            volatility_coupling_children: Vec::with_capacity(n),
-        }
-    }
-
-    pub fn push_state(&mut self, s: &NodeState) {
-        self.mean.push(s.mean);
-        self.expected_mean.push(s.expected_mean);
-        self.precision.push(s.precision);
-        self.expected_precision.push(s.expected_precision);
-        self.observed.push(s.observed);
-        self.tonic_volatility.push(s.tonic_volatility);
-        self.tonic_drift.push(s.tonic_drift);
-        self.autoconnection_strength.push(s.autoconnection_strength);
-        self.current_variance.push(s.current_variance);
-        self.effective_precision.push(s.effective_precision);
-        self.value_prediction_error.push(s.value_prediction_error);
-        self.volatility_prediction_error
-            .push(s.volatility_prediction_error);
-        self.mean_vol.push(s.mean_vol);
-        self.expected_mean_vol.push(s.expected_mean_vol);
-        self.precision_vol.push(s.precision_vol);
-        self.expected_precision_vol.push(s.expected_precision_vol);
-        self.tonic_volatility_vol.push(s.tonic_volatility_vol);
-        self.tonic_drift_vol.push(s.tonic_drift_vol);
-        self.effective_precision_vol.push(s.effective_precision_vol);
-        self.nus.push(s.nus);
-        self.lr.push(s.lr);
+            data: Vec::with_capacity(n * TRAJECTORY_FIELDS.len()),
+            ..Default::default()
+        }
     }
 
-    pub fn push_vectors(&mut self, v: &NodeVectors) {
-        if !v.xis.is_empty() {
-            self.xis.push(v.xis.clone());
+    /// Empty trajectory with room for `n` time steps, recording only the
+    /// fields named in `record_keys` (all of them when `None`).
+    pub fn with_keys(n: usize, record_keys: Option<&[String]>) -> Self {
+        let Some(keys) = record_keys else {
+            return Self::with_capacity(n);
+        };
+        let recorded = |field: &str| keys.iter().any(|key| key == field);
+        let columns: Vec<usize> = (0..TRAJECTORY_FIELDS.len())
+            .filter(|&col| recorded(TRAJECTORY_FIELDS[col]))
+            .collect();
+        let mut trajectory = NodeTrajectory {
+            data: Vec::with_capacity(n * columns.len()),
+            columns: Some(columns),
+            ..Default::default()
+        };
+        for &field in VECTOR_TRAJECTORY_FIELDS {
+            if let Some(vector) = trajectory.vector_mut(field) {
+                vector.skipped = !recorded(field);
+            }
+        }
+        trajectory
+    }
+
+    /// Number of scalar fields recorded per time step.
+    fn stride(&self) -> usize {
+        self.columns
+            .as_ref()
+            .map_or(TRAJECTORY_FIELDS.len(), Vec::len)
+    }
+
+    /// Position in a row of `data` of a recorded scalar field.
+    fn column_offset(&self, field: &str) -> Option<usize> {
+        let col = TRAJECTORY_FIELDS.iter().position(|&f| f == field)?;
+        match &self.columns {
+            None => Some(col),
+            Some(columns) => columns.iter().position(|&c| c == col),
         }
-        if !v.value_coupling_parents.is_empty() {
-            self.value_coupling_parents
-                .push(v.value_coupling_parents.clone());
+    }
+
+    /// Column of a recorded scalar field, looked up by name.
+    pub fn column(&self, field: &str) -> Option<TrajectoryColumn<'_>> {
+        self.column_offset(field).map(|offset| TrajectoryColumn {
+            data: &self.data,
+            offset,
+            stride: self.stride(),
+        })
+    }
+
+    /// Column of the field at `col` in [`TRAJECTORY_FIELDS`], empty when the
+    /// field is not recorded.
+    fn column_at(&self, col: usize) -> TrajectoryColumn<'_> {
+        self.column(TRAJECTORY_FIELDS[col])
+            .unwrap_or(TrajectoryColumn {
+                data: &[],
+                offset: 0,
+                stride: 1,
+            })
+    }
+
+    /// Keep the first `n_time` recorded time steps.
+    pub fn truncate(&mut self, n_time: usize) {
+        self.n_time = self.n_time.min(n_time);
+        self.born = self.born.min(self.n_time);
+        self.data.truncate(self.n_time * self.stride());
+        for vector in [
+            &mut self.xis,
+            &mut self.value_coupling_parents,
+            &mut self.value_coupling_children,
+            &mut self.volatility_coupling_parents,
+            &mut self.volatility_coupling_children,
+            &mut self.probabilities,
+            &mut self.learning_rate_parents,
+        ] {
+            vector.truncate(n_time);
         }
-        if !v.value_coupling_children.is_empty() {
-            self.value_coupling_children
-                .push(v.value_coupling_children.clone());
+    }
+
+    /// Copy of the recorded time steps `rows`, in order, with the same
+    /// recorded keys.
+    pub fn select(&self, rows: &[usize]) -> Self {
+        let stride = self.stride();
+        let mut selected = NodeTrajectory {
+            data: rows
+                .iter()
+                .flat_map(|&t| &self.data[t * stride..(t + 1) * stride])
+                .copied()
+                .collect(),
+            n_time: rows.len(),
+            // `rows` are in increasing order.
+            born: rows.iter().take_while(|&&t| t < self.born).count(),
+            columns: self.columns.clone(),
+            ..Default::default()
+        };
+        for &field in VECTOR_TRAJECTORY_FIELDS {
+            let vector = self.vector(field).expect("listed vector field");
+            let rows = if vector.is_empty() { &[][..] } else { rows };
+            *selected.vector_mut(field).expect("listed vector field") = vector.select(rows);
         }
-        if !v.volatility_coupling_parents.is_empty() {
-            self.volatility_coupling_parents
-                .push(v.volatility_coupling_parents.clone());
+        selected
+    }
+
+    /// Append the vector attributes of the time step last recorded by
+    /// [`NodeTrajectory::push_state`].
+    pub fn push_vectors(&mut self, v: &NodeVectors) {
+        let t = self.n_time.saturating_sub(1);
+        self.xis.push(&v.xis, t);
+        self.value_coupling_parents
+            .push(&v.value_coupling_parents, t);
+        self.value_coupling_children
+            .push(&v.value_coupling_children, t);
+        self.volatility_coupling_parents
+            .push(&v.volatility_coupling_parents, t);
+        self.volatility_coupling_children
+            .push(&v.volatility_coupling_children, t);
+        self.probabilities.push(&v.probabilities, t);
+        self.learning_rate_parents.push(&v.learning_rate_parents, t);
+    }
+
+    /// Vector trajectory looked up by name (one of [`VECTOR_TRAJECTORY_FIELDS`]).
+    pub fn vector(&self, field: &str) -> Option<&VectorTrajectory> {
+        match field {
+            "xis" => Some(&self.xis),
+            "value_coupling_parents" => Some(&self.value_coupling_parents),
+            "value_coupling_children" => Some(&self.value_coupling_children),
+            "volatility_coupling_parents" => Some(&self.volatility_coupling_parents),
+            "volatility_coupling_children" => Some(&self.volatility_coupling_children),
+            "probabilities" => Some(&self.probabilities),
+            "learning_rate_parents" => Some(&self.learning_rate_parents),
+            _ => None,
         }
-        if !v.volatility_coupling_children.is_empty() {
-            self.volatility_coupling_children
-                .push(v.volatility_coupling_children.clone());
+    }
+
+    fn vector_mut(&mut self, field: &str) -> Option<&mut VectorTrajectory> {
+        match field {
+            "xis" => Some(&mut self.xis),
+            "value_coupling_parents" => Some(&mut self.value_coupling_parents),
+            "value_coupling_children" => Some(&mut self.value_coupling_children),
+            "volatility_coupling_parents" => Some(&mut self.volatility_coupling_parents),
+            "volatility_coupling_children" => Some(&mut self.volatility_coupling_children),
+            "probabilities" => Some(&mut self.probabilities),
+            "learning_rate_parents" => Some(&mut self.learning_rate_parents),
+            _ => None,
         }
     }
+
+    /// Keys exported for this trajectory, in a fixed order: the recorded
+    /// scalar fields of `node_type` (in [`trajectory_fields_for_type`] order),
+    /// then the vector fields with a row length (in
+    /// [`VECTOR_TRAJECTORY_FIELDS`] order). A recording without time steps
+    /// has every key, zero rows long.
+    pub fn keys(&self, node_type: &str) -> Vec<&'static str> {
+        trajectory_fields_for_type(node_type)
+            .iter()
+            .copied()
+            .filter(|&f| self.column_offset(f).is_some())
+            .chain(
+                VECTOR_TRAJECTORY_FIELDS
+                    .iter()
+                    .copied()
+                    .filter(|&f| self.vector(f).is_some_and(|v| v.stride > 0)),
+            )
+            .collect()
+    }
 }
 
-#[derive(Debug)]
+/// Vector attributes recorded at every time step, in export order.
+pub const VECTOR_TRAJECTORY_FIELDS: &[&str] = &[
+    "xis",
+    "value_coupling_parents",
+    "value_coupling_children",
+    "volatility_coupling_parents",
+    "volatility_coupling_children",
+    "probabilities",
+    "learning_rate_parents",
+];
+
+/// Every key accepted by [`Network::set_record_keys`]: the scalar
+/// [`TRAJECTORY_FIELDS`], then the [`VECTOR_TRAJECTORY_FIELDS`].
+pub const RECORDABLE_KEYS: &[&str] = &{
+    let mut keys = [""; TRAJECTORY_FIELDS.len() + VECTOR_TRAJECTORY_FIELDS.len()];
+    let mut i = 0;
+    while i < TRAJECTORY_FIELDS.len() {
+        keys[i] = TRAJECTORY_FIELDS[i];
+        i += 1;
+    }
+    while i < keys.len() {
+        keys[i] = VECTOR_TRAJECTORY_FIELDS[i - TRAJECTORY_FIELDS.len()];
+        i += 1;
+    }
+    keys
+};
+
+/// Every vector attribute of a node: the recorded ones, then
+/// `coupling_frozen`, `coupling_lag`, `coupling_drift`, `cluster_counts` and
+/// `input_precision`.
+pub const STATE_VECTOR_FIELDS: &[&str] = &[
+    "xis",
+    "value_coupling_parents",
+    "value_coupling_children",
+    "volatility_coupling_parents",
+    "volatility_coupling_children",
+    "probabilities",
+    "learning_rate_parents",
+    "coupling_frozen",
+    "coupling_lag",
+    "coupling_drift",
+    "cluster_counts",
+    "input_precision",
+];
+
+#[derive(Debug, Default)]
 pub struct NodeTrajectories {
     pub nodes: Vec<NodeTrajectory>,
+    /// Time step (Δt) of every recorded time step.
+    pub time_steps: Vec<f64>,
+}
+
+impl NodeTrajectories {
+    /// Empty trajectories for `n_nodes` nodes, with room for `n_time` steps.
+    pub fn with_capacity(n_nodes: usize, n_time: usize) -> Self {
+        Self::with_keys(n_nodes, n_time, None)
+    }
+
+    /// Empty trajectories for `n_nodes` nodes, with room for `n_time` steps,
+    /// recording only the fields named in `record_keys` (all when `None`).
+    pub fn with_keys(n_nodes: usize, n_time: usize, record_keys: Option<&[String]>) -> Self {
+        NodeTrajectories {
+            nodes: (0..n_nodes)
+                .map(|_| NodeTrajectory::with_keys(n_time, record_keys))
+                .collect(),
+            time_steps: Vec::with_capacity(n_time),
+        }
+    }
+
+    /// Append the current state of every node as one new time step.
+    pub fn push(&mut self, attributes: &Attributes, time_step: f64) {
+        self.time_steps.push(time_step);
+        for (i, traj) in self.nodes.iter_mut().enumerate() {
+            traj.push_state(&attributes.states[i]);
+            traj.push_vectors(&attributes.vectors[i]);
+        }
+    }
+
+    /// Copy of the recorded time steps `rows` of every node, in order.
+    pub fn select(&self, rows: &[usize]) -> Self {
+        NodeTrajectories {
+            nodes: self.nodes.iter().map(|traj| traj.select(rows)).collect(),
+            time_steps: rows.iter().map(|&t| self.time_steps[t]).collect(),
+        }
+    }
+
+    /// Keep the first `n_time` recorded time steps.
+    pub fn truncate(&mut self, n_time: usize) {
+        self.time_steps.truncate(n_time);
+        for traj in &mut self.nodes {
+            traj.truncate(n_time);
+        }
+    }
+
+    /// Catch up with nodes added, or vector attributes lengthened, since the
+    /// recording started (e.g. the clusters of dp-state nodes): the time
+    /// steps recorded before are NaN for them, and counted in the new nodes'
+    /// [`NodeTrajectory::born`].
+    pub fn fit_attributes(&mut self, attributes: &Attributes, record_keys: Option<&[String]>) {
+        let n_time = self.time_steps.len();
+        let mut blank = NodeState::default();
+        for &field in TRAJECTORY_FIELDS {
+            *blank.get_mut(field).expect("recorded field") = f64::NAN;
+        }
+        while self.nodes.len() < attributes.states.len() {
+            let mut trajectory = NodeTrajectory::with_keys(n_time, record_keys);
+            for _ in 0..n_time {
+                trajectory.push_state(&blank);
+            }
+            trajectory.born = n_time;
+            self.nodes.push(trajectory);
+        }
+        for (trajectory, vectors) in self.nodes.iter_mut().zip(&attributes.vectors) {
+            for &field in VECTOR_TRAJECTORY_FIELDS {
+                let len = vectors.get(field).map_or(0, <[f64]>::len);
+                if let Some(vector) = trajectory.vector_mut(field) {
+                    vector.widen(len, n_time);
+                }
+            }
+        }
+    }
+}
+
+/// The attributes and metadata of a network at one point of a run, taken by
+/// [`Network::checkpoint`] and put back by [`Network::restore`]. Every other
+/// copy of the state goes through it: [`Network::get_state`] and
+/// [`Network::load_state`] read and write it field by field, and the runs
+/// that leave the network unchanged put one back when they are done.
+#[derive(Debug, Clone)]
+#[pyclass(skip_from_py_object)]
+pub struct StateSnapshot {
+    attributes: Attributes,
+    metadata: HashMap<usize, HashMap<String, String>>,
+    /// Number of time steps recorded when the snapshot was taken.
+    n_time: usize,
+}
+
+#[pymethods]
+impl StateSnapshot {
+    /// Number of time steps recorded when the snapshot was taken.
+    #[getter]
+    fn n_time_steps(&self) -> usize {
+        self.n_time
+    }
+}
+
+/// A read-only snapshot of one node, returned by [`Network::nodes`]: its
+/// index, kind, adjacency lists and the parameters `add_nodes` accepts for its
+/// kind.
+#[derive(Debug, Clone)]
+#[pyclass(skip_from_py_object)]
+pub struct NodeView {
+    #[pyo3(get)]
+    pub idx: usize,
+    #[pyo3(get)]
+    pub kind: String,
+    #[pyo3(get)]
+    pub value_parents: Option<Vec<usize>>,
+    #[pyo3(get)]
+    pub value_children: Option<Vec<usize>>,
+    #[pyo3(get)]
+    pub volatility_parents: Option<Vec<usize>>,
+    #[pyo3(get)]
+    pub volatility_children: Option<Vec<usize>>,
+    /// Current values of the parameters listed by [`parameters_for_type`].
+    #[pyo3(get)]
+    pub parameters: BTreeMap<String, f64>,
+}
+
+#[pymethods]
+impl NodeView {
+    fn __repr__(&self) -> String {
+        format!(
+            "NodeView(idx={}, kind='{}', value_parents={:?}, value_children={:?}, \
+             volatility_parents={:?}, volatility_children={:?})",
+            self.idx,
+            self.kind,
+            self.value_parents.as_deref().unwrap_or_default(),
+            self.value_children.as_deref().unwrap_or_default(),
+            self.volatility_parents.as_deref().unwrap_or_default(),
+            self.volatility_children.as_deref().unwrap_or_default(),
+        )
+    }
+}
+
+/// A live view of one node, returned by `network[idx]` in Python. Unlike
+/// [`NodeView`], every property reads the network at access time, and
+/// assignments go through [`Network::set_attribute`].
+#[pyclass]
+pub struct NodeHandle {
+    network: Py<Network>,
+    #[pyo3(get)]
+    idx: usize,
+}
+
+impl NodeHandle {
+    fn get(&self, py: Python<'_>, key: &str) -> PyResult<f64> {
+        Ok(self
+            .network
+            .bind(py)
+            .try_borrow()?
+            .get_attribute(self.idx, key)?)
+    }
+
+    fn set(&self, py: Python<'_>, key: &str, value: f64) -> PyResult<()> {
+        Ok(self
+            .network
+            .bind(py)
+            .try_borrow_mut()?
+            .set_attribute(self.idx, key, value)?)
+    }
+
+    /// Indices in the `parent`/`child` adjacency lists of the node, value
+    /// ones first, without duplicates.
+    fn neighbours(&self, py: Python<'_>, parents: bool) -> PyResult<Vec<usize>> {
+        let network = self.network.bind(py).try_borrow()?;
+        let edges = &network.edges[self.idx];
+        let lists = if parents {
+            [
+                &edges.value_parents,
+                &edges.volatility_parents,
+                &edges.precision_parents,
+            ]
+        } else {
+            [
+                &edges.value_children,
+                &edges.volatility_children,
+                &edges.precision_children,
+            ]
+        };
+        let mut idxs: Vec<usize> = Vec::new();
+        for &idx in lists.into_iter().flatten().flatten() {
+            if !idxs.contains(&idx) {
+                idxs.push(idx);
+            }
+        }
+        Ok(idxs)
+    }
+}
+
+#[pymethods]
+impl NodeHandle {
+    #[getter]
+    fn kind(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(self.network.bind(py).try_borrow()?.edges[self.idx]
+            .node_type
+            .clone())
+    }
+
+    #[getter]
+    fn mean(&self, py: Python<'_>) -> PyResult<f64> {
+        self.get(py, "mean")
+    }
+
+    #[setter]
+    fn set_mean(&self, py: Python<'_>, value: f64) -> PyResult<()> {
+        self.set(py, "mean", value)
+    }
+
+    #[getter]
+    fn precision(&self, py: Python<'_>) -> PyResult<f64> {
+        self.get(py, "precision")
+    }
+
+    #[setter]
+    fn set_precision(&self, py: Python<'_>, value: f64) -> PyResult<()> {
+        self.set(py, "precision", value)
+    }
+
+    #[getter]
+    fn tonic_volatility(&self, py: Python<'_>) -> PyResult<f64> {
+        self.get(py, "tonic_volatility")
+    }
+
+    #[setter]
+    fn set_tonic_volatility(&self, py: Python<'_>, value: f64) -> PyResult<()> {
+        self.set(py, "tonic_volatility", value)
+    }
+
+    /// Value, volatility and precision parents of the node.
+    #[getter]
+    fn parents(&self, py: Python<'_>) -> PyResult<Vec<usize>> {
+        self.neighbours(py, true)
+    }
+
+    /// Value, volatility and precision children of the node.
+    #[getter]
+    fn children(&self, py: Python<'_>) -> PyResult<Vec<usize>> {
+        self.neighbours(py, false)
+    }
+
+    /// Any scalar attribute of the node, by name.
+    fn __getitem__(&self, py: Python<'_>, key: &str) -> PyResult<f64> {
+        self.get(py, key)
+    }
+
+    /// Set a parameter of the node, by name (see `Network.set_attribute`).
+    fn __setitem__(&self, py: Python<'_>, key: &str, value: f64) -> PyResult<()> {
+        self.set(py, key, value)
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> PyResult<String> {
+        Ok(format!(
+            "NodeHandle(idx={}, kind='{}')",
+            self.idx,
+            self.kind(py)?
+        ))
+    }
+}
+
+/// The numeric state of every node, field by field, without the topology or
+/// the trajectories: what [`Network::get_state`] returns and
+/// [`Network::load_state`] puts back.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetworkState {
+    /// Scalar attributes of each node (see [`STATE_FIELDS`]).
+    pub floats: Vec<BTreeMap<String, f64>>,
+    /// Vector attributes of each node (see [`STATE_VECTOR_FIELDS`]).
+    pub vectors: Vec<BTreeMap<String, Vec<f64>>>,
+    /// Metadata of each node (see [`Network::set_metadata`]).
+    pub metadata: Vec<BTreeMap<String, String>>,
 }
 
 #[derive(Debug)]
@@ -303,6 +1319,9 @@ pub struct Network {
     pub volatility_updates: String,
     pub mean_field_updates: bool,
     pub update_sequence: UpdateSequence,
+    /// Per-edge value-coupling tables, resolved together with the update
+    /// sequence and read by the update and learning kernels.
+    pub edge_lookup: EdgeLookup,
     pub node_trajectories: NodeTrajectories,
     pub layers: Vec<Vec<usize>>,
     /// Optional Adam optimiser state, initialised when `fit()` is called with `optimizer="adam"`.
@@ -321,20 +1340,187 @@ pub struct Network {
     /// avoids flat, zero-gradient plateaus that hurt gradient-based inference. Shared
     /// with the JAX backends.
     pub precision_clipping_value: f64,
+    /// Python callables run by [`UpdateStep::Python`] steps, indexed by their
+    /// `slot`, in the order they were added. A rebuilt update sequence gets
+    /// them back at their [`StepPlacement`].
+    pub python_steps: Vec<PythonStep>,
+    /// Trajectory keys recorded by the runs; `None` records every scalar and
+    /// vector attribute. Set with [`Network::set_record_keys`].
+    pub record_keys: Option<Vec<String>>,
+    /// Scan the beliefs for NaN or infinite values after every time step of
+    /// `input_data` and `fit`, and stop with [`NetworkError::Diverged`] at the
+    /// first one. Off by default.
+    pub check_divergence: bool,
+    /// What the posterior updates do with a non-positive precision. Set with
+    /// [`Network::set_negative_precision`].
+    pub negative_precision: NegativePrecision,
+    /// Precision the recorded `entropy` is computed from. Set with
+    /// [`Network::set_entropy_source`].
+    pub entropy_source: EntropySource,
+    /// Error left by an update kernel, returned by [`UpdateStep::call`] once
+    /// the kernel is done (see [`NegativePrecision::Error`]).
+    pub step_error: Option<NetworkError>,
+    /// Dp-state nodes whose last observation no cluster explained, left by
+    /// their prediction errors and given a new cluster once the time step is
+    /// over (see [`crate::utils::clusters`]).
+    pub pending_clusters: Vec<usize>,
+    /// Free-form labels of each node (e.g. a brain region or a condition),
+    /// by node index then key. Never read by the updates, nor compared by
+    /// [`Network::approx_eq`]; saved and restored with the state.
+    pub metadata: HashMap<usize, HashMap<String, String>>,
 }
 
-/// Helper: get the list of trajectory field names to export for a given node type.
-fn trajectory_fields_for_type(node_type: &str) -> &'static [&'static str] {
-    match node_type {
-        "binary-state" => &[
-            "observed",
-            "mean",
-            "expected_mean",
+/// Exact comparison of structure and attributes; see [`Network::approx_eq`].
+impl PartialEq for Network {
+    fn eq(&self, other: &Self) -> bool {
+        self.approx_eq(other, 0.0)
+    }
+}
+
+/// Policy of the posterior updates for a posterior precision that comes out
+/// non-positive (or NaN), e.g. under strong nonlinear couplings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NegativePrecision {
+    /// Floor the precision at a tiny positive value and keep the update.
+    #[default]
+    Floor,
+    /// Stop the run with [`NetworkError::NegativePrecision`].
+    Error,
+    /// Drop the update: the node keeps its previous posterior, like the
+    /// MATLAB toolbox after its "negative posterior precision" warning.
+    Reject,
+}
+
+/// Precision of the belief whose entropy is recorded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EntropySource {
+    /// The posterior `precision`.
+    #[default]
+    Posterior,
+    /// The predicted `expected_precision`.
+    Prediction,
+}
+
+impl EntropySource {
+    /// Name of the source, as accepted by [`EntropySource::parse`].
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Posterior => "posterior",
+            Self::Prediction => "prediction",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self, NetworkError> {
+        match name {
+            "posterior" => Ok(Self::Posterior),
+            "prediction" => Ok(Self::Prediction),
+            other => Err(NetworkError::InvalidValue {
+                parameter: "entropy_source",
+                value: format!("'{}'", other),
+                accepted: "'posterior' or 'prediction'",
+            }),
+        }
+    }
+}
+
+/// Accepted values of [`Network::volatility_updates`].
+pub const UPDATE_TYPES: &[&str] = &["eHGF", "standard", "unbounded"];
+
+impl NegativePrecision {
+    /// Name of the policy, as accepted by [`NegativePrecision::parse`].
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Floor => "floor",
+            Self::Error => "error",
+            Self::Reject => "reject",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self, NetworkError> {
+        match name {
+            "floor" => Ok(Self::Floor),
+            "error" => Ok(Self::Error),
+            "reject" => Ok(Self::Reject),
+            other => Err(NetworkError::InvalidValue {
+                parameter: "negative_precision",
+                value: format!("'{}'", other),
+                accepted: "'floor', 'error' or 'reject'",
+            }),
+        }
+    }
+}
+
+/// Accessor of a scalar belief checked under `check_divergence`.
+type BeliefGetter = fn(&NodeState) -> f64;
+
+/// Beliefs scanned under `check_divergence`, the first values a diverging
+/// run corrupts, with their accessors.
+const DIVERGENCE_FIELDS: &[(&str, BeliefGetter)] = &[
+    ("mean", |s| s.mean),
+    ("expected_mean", |s| s.expected_mean),
+    ("precision", |s| s.precision),
+    ("expected_precision", |s| s.expected_precision),
+    ("mean_vol", |s| s.mean_vol),
+    ("expected_mean_vol", |s| s.expected_mean_vol),
+    ("precision_vol", |s| s.precision_vol),
+    ("expected_precision_vol", |s| s.expected_precision_vol),
+];
+
+/// Recorded values reported before the offending one in
+/// [`NetworkError::Diverged`].
+const DIVERGENCE_HISTORY: usize = 4;
+
+/// Whether `ancestor_idx` can be reached from `node_idx` by following the
+/// `parents` lists (one per node), i.e. whether an edge making `ancestor_idx`
+/// a child of `node_idx` would close a cycle.
+pub(crate) fn is_ancestor(parents: &[Vec<usize>], ancestor_idx: usize, node_idx: usize) -> bool {
+    let mut visited = vec![false; parents.len()];
+    let mut stack = vec![node_idx];
+    while let Some(idx) = stack.pop() {
+        if idx == ancestor_idx {
+            return true;
+        }
+        if !std::mem::replace(&mut visited[idx], true) {
+            stack.extend(&parents[idx]);
+        }
+    }
+    false
+}
+
+/// [`NetworkError::InvalidEdge`] reason of an edge to a node that does not
+/// exist.
+const MISSING_NODE: &str = "refers to missing node";
+
+/// [`NetworkError::InvalidEdge`] reason of a setter given two nodes that the
+/// adjacency list does not join.
+pub(crate) const NOT_AN_EDGE: &str = "does not list node";
+
+/// Coupling function called `name` (see [`crate::math::parse_coupling_fn`]).
+fn coupling_fn_by_name(name: &str) -> Result<&'static crate::math::CouplingFn, NetworkError> {
+    crate::math::parse_coupling_fn(name).map_err(|_| NetworkError::InvalidValue {
+        parameter: "coupling_fn",
+        value: format!("'{}'", name),
+        accepted: "one of linear, identity, relu, sigmoid, tanh, leaky_relu, gelu, \
+                   gelu_tanh, logit, softplus",
+    })
+}
+
+/// A belief checked under `check_divergence`: node, attribute and accessor.
+type DivergenceCheck = (usize, &'static str, BeliefGetter);
+
+/// Helper: get the list of trajectory field names to export for a given node type.
+pub fn trajectory_fields_for_type(node_type: &str) -> &'static [&'static str] {
+    match node_type {
+        "binary-state" | "binary-input" => &[
+            "observed",
+            "mean",
+            "expected_mean",
             "precision",
             "expected_precision",
             "value_prediction_error",
+            "surprise",
         ],
-        "continuous-state" => &[
+        "continuous-state" | "circular-state" => &[
             "mean",
             "expected_mean",
             "precision",
@@ -346,6 +1532,11 @@ fn trajectory_fields_for_type(node_type: &str) -> &'static [&'static str] {
             "effective_precision",
             "value_prediction_error",
             "volatility_prediction_error",
+            "pe_asymmetry",
+            "pe_scaling",
+            "surprise",
+            "entropy",
+            "pe_gain",
         ],
         "volatile-state" => &[
             "mean",
@@ -358,46 +1549,157 @@ fn trajectory_fields_for_type(node_type: &str) -> &'static [&'static str] {
             "effective_precision",
             "value_prediction_error",
             "volatility_prediction_error",
+            "pe_asymmetry",
+            "pe_scaling",
             "mean_vol",
             "expected_mean_vol",
             "precision_vol",
             "expected_precision_vol",
             "tonic_volatility_vol",
             "tonic_drift_vol",
+            "autoconnection_strength_vol",
             "effective_precision_vol",
             "observed",
+            "surprise",
+            "entropy",
+            "pe_gain",
+        ],
+        "ef-state" => &[
+            "mean",
+            "nus",
+            "forgetting",
+            "tracked_mean",
+            "tracked_variance",
+        ],
+        "softmax-state" => &["observed", "mean", "temperature", "surprise"],
+        "dp-state" => &["observed", "mean", "expected_mean", "precision", "surprise"],
+        "bounded-state" => &[
+            "observed",
+            "mean",
+            "expected_mean",
+            "precision",
+            "expected_precision",
+            "expected_bounded_mean",
+            "value_prediction_error",
+            "surprise",
+            "pe_gain",
+        ],
+        "lognormal-state" => &[
+            "observed",
+            "mean",
+            "expected_mean",
+            "precision",
+            "expected_precision",
+            "lognormal_median",
+            "lognormal_mean",
+            "value_prediction_error",
+            "surprise",
+            "pe_gain",
+        ],
+        "aggregate-input" => &[
+            "observed",
+            "mean",
+            "expected_mean",
+            "precision",
+            "expected_precision",
+            "value_prediction_error",
+            "surprise",
+            "pe_gain",
         ],
-        "ef-state" => &["mean", "nus"],
         "constant-state" => &["mean", "expected_mean"],
         _ => &[],
     }
 }
 
-/// Helper: get a reference to the trajectory Vec<f64> for a given field name.
-fn trajectory_field_ref<'a>(traj: &'a NodeTrajectory, field: &str) -> &'a Vec<f64> {
-    match field {
-        "mean" => &traj.mean,
-        "expected_mean" => &traj.expected_mean,
-        "precision" => &traj.precision,
-        "expected_precision" => &traj.expected_precision,
-        "observed" => &traj.observed,
-        "tonic_volatility" => &traj.tonic_volatility,
-        "tonic_drift" => &traj.tonic_drift,
-        "autoconnection_strength" => &traj.autoconnection_strength,
-        "current_variance" => &traj.current_variance,
-        "effective_precision" => &traj.effective_precision,
-        "value_prediction_error" => &traj.value_prediction_error,
-        "volatility_prediction_error" => &traj.volatility_prediction_error,
-        "mean_vol" => &traj.mean_vol,
-        "expected_mean_vol" => &traj.expected_mean_vol,
-        "precision_vol" => &traj.precision_vol,
-        "expected_precision_vol" => &traj.expected_precision_vol,
-        "tonic_volatility_vol" => &traj.tonic_volatility_vol,
-        "tonic_drift_vol" => &traj.tonic_drift_vol,
-        "effective_precision_vol" => &traj.effective_precision_vol,
-        "nus" => &traj.nus,
-        "lr" => &traj.lr,
-        _ => &traj.mean, // fallback
+/// Node kinds `add_nodes` can create.
+pub const NODE_KINDS: &[&str] = &[
+    "continuous-state",
+    "volatile-state",
+    "binary-state",
+    "binary-input",
+    "ef-state",
+    "softmax-state",
+    "dp-state",
+    "bounded-state",
+    "lognormal-state",
+    "circular-state",
+    "aggregate-input",
+    "constant-state",
+];
+
+/// Helper: get the initial parameters that `add_nodes` accepts as overrides for
+/// a given node type. Any other key is rejected with
+/// [`NetworkError::InvalidParameter`].
+pub fn parameters_for_type(node_type: &str) -> &'static [&'static str] {
+    match node_type {
+        "continuous-state" | "circular-state" => &[
+            "mean",
+            "expected_mean",
+            "precision",
+            "expected_precision",
+            "tonic_volatility",
+            "tonic_drift",
+            "autoconnection_strength",
+            "max_precision",
+            "current_variance",
+            "initial_current_variance",
+            "pe_asymmetry",
+            "tonic_drift_lr",
+            "coupling_grad_clip",
+        ],
+        "volatile-state" => &[
+            "mean",
+            "expected_mean",
+            "precision",
+            "expected_precision",
+            "tonic_drift",
+            "autoconnection_strength",
+            "max_precision",
+            "current_variance",
+            "initial_current_variance",
+            "pe_asymmetry",
+            "mean_vol",
+            "expected_mean_vol",
+            "precision_vol",
+            "expected_precision_vol",
+            "tonic_volatility_vol",
+            "tonic_drift_vol",
+            "autoconnection_strength_vol",
+            "coupling_grad_clip",
+        ],
+        "ef-state" => &["nus", "forgetting", "welford"],
+        "softmax-state" => &["temperature"],
+        "dp-state" => &["precision", "concentration", "density_threshold"],
+        "bounded-state" => &[
+            "mean",
+            "expected_mean",
+            "precision",
+            "expected_precision",
+            "lower_bound",
+            "upper_bound",
+            "bound_epsilon",
+        ],
+        "lognormal-state" => &[
+            "mean",
+            "expected_mean",
+            "precision",
+            "expected_precision",
+            "tonic_volatility",
+        ],
+        "aggregate-input" => &["mean", "expected_mean"],
+        _ => &[],
+    }
+}
+
+/// Helper: every key `add_nodes` accepts for a given node type: the
+/// parameters of [`parameters_for_type`] plus the build-only keys that shape
+/// the node rather than set a state field. An aggregate-input node's
+/// `n_streams` and `input_precision` are only read when the node is built;
+/// its streams are changed afterwards with [`Network::set_input_precision`].
+pub fn add_nodes_parameters(node_type: &str) -> &'static [&'static str] {
+    match node_type {
+        "aggregate-input" => &["mean", "expected_mean", "n_streams", "input_precision"],
+        _ => parameters_for_type(node_type),
     }
 }
 
@@ -418,36 +1720,511 @@ impl Network {
                 predictions: Vec::new(),
                 updates: Vec::new(),
             },
-            node_trajectories: NodeTrajectories { nodes: Vec::new() },
+            edge_lookup: EdgeLookup::default(),
+            node_trajectories: NodeTrajectories::default(),
             layers: Vec::new(),
             adam_state: None,
             roots: Vec::new(),
             leafs: Vec::new(),
             max_posterior_precision: 1e10,
             precision_clipping_value: 1e-6,
+            python_steps: Vec::new(),
+            record_keys: None,
+            check_divergence: false,
+            negative_precision: NegativePrecision::Floor,
+            entropy_source: EntropySource::Posterior,
+            step_error: None,
+            pending_clusters: Vec::new(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    /// Number of nodes in the network.
+    pub fn n_nodes(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Whether `other` has the same structure and attributes, floats being
+    /// compared up to the absolute tolerance `tol` (see
+    /// [`crate::utils::compare`]).
+    pub fn approx_eq(&self, other: &Network, tol: f64) -> bool {
+        compare::approx_eq(self, other, tol)
+    }
+
+    /// Hash of the structure of the network, ignoring parameter values (see
+    /// [`crate::utils::compare::structure_hash`]).
+    pub fn structure_hash(&self) -> u64 {
+        compare::structure_hash(self)
+    }
+
+    /// Snapshot of node `node_idx` (see [`NodeView`]).
+    pub fn node_view(&self, node_idx: usize) -> Result<NodeView, NetworkError> {
+        let (Some(edges), Some(state)) = (
+            self.edges.get(node_idx),
+            self.attributes.states.get(node_idx),
+        ) else {
+            return Err(NetworkError::MissingNode {
+                node_idx,
+                step: "node_view",
+            });
+        };
+        let parameters = parameters_for_type(&edges.node_type)
+            .iter()
+            .filter_map(|&key| Some((key.to_string(), state.get(key)?)))
+            .collect();
+        Ok(NodeView {
+            idx: node_idx,
+            kind: edges.node_type.clone(),
+            value_parents: edges.value_parents.clone(),
+            value_children: edges.value_children.clone(),
+            volatility_parents: edges.volatility_parents.clone(),
+            volatility_children: edges.volatility_children.clone(),
+            parameters,
+        })
+    }
+
+    /// Current value of the scalar attribute `key` of `node_idx` (any of
+    /// [`STATE_FIELDS`]).
+    pub fn get_attribute(&self, node_idx: usize, key: &str) -> Result<f64, NetworkError> {
+        let (Some(edges), Some(state)) = (
+            self.edges.get(node_idx),
+            self.attributes.states.get(node_idx),
+        ) else {
+            return Err(NetworkError::MissingNode {
+                node_idx,
+                step: "get_attribute",
+            });
+        };
+        state
+            .get(key)
+            .ok_or_else(|| NetworkError::InvalidParameter {
+                node_type: edges.node_type.clone(),
+                parameter: key.to_string(),
+                accepted: STATE_FIELDS,
+            })
+    }
+
+    /// Set the parameter `key` of `node_idx` to `value`. Only the parameters
+    /// of the node's kind (see [`parameters_for_type`]) can be set; the
+    /// build-only keys of [`add_nodes_parameters`] cannot, and the cached
+    /// update sequence is kept.
+    pub fn set_attribute(
+        &mut self,
+        node_idx: usize,
+        key: &str,
+        value: f64,
+    ) -> Result<(), NetworkError> {
+        let (Some(edges), Some(state)) = (
+            self.edges.get(node_idx),
+            self.attributes.states.get_mut(node_idx),
+        ) else {
+            return Err(NetworkError::MissingNode {
+                node_idx,
+                step: "set_attribute",
+            });
+        };
+        let accepted = parameters_for_type(&edges.node_type);
+        match state.get_mut(key) {
+            Some(field) if accepted.contains(&key) => {
+                *field = value;
+                Ok(())
+            }
+            _ => Err(NetworkError::InvalidParameter {
+                node_type: edges.node_type.clone(),
+                parameter: key.to_string(),
+                accepted,
+            }),
+        }
+    }
+
+    /// Set the precision of each observation stream of aggregate-input node
+    /// `node_idx`, in column order. The number of streams, and so the number
+    /// of observation columns the node reads, becomes `precisions.len()`, and
+    /// the precision of the fused observation their sum.
+    pub fn set_input_precision(
+        &mut self,
+        node_idx: usize,
+        precisions: Vec<f64>,
+    ) -> Result<(), NetworkError> {
+        let Some(edges) = self.edges.get(node_idx) else {
+            return Err(NetworkError::MissingNode {
+                node_idx,
+                step: "set_input_precision",
+            });
+        };
+        if edges.node_type != "aggregate-input" {
+            return Err(NetworkError::UnsupportedStructure {
+                node_idx,
+                step: "set_input_precision",
+                reason: "only aggregate-input nodes have per-stream precisions",
+            });
+        }
+        if precisions.is_empty() || precisions.iter().any(|&p| !(p.is_finite() && p > 0.0)) {
+            return Err(NetworkError::InvalidValue {
+                parameter: "input_precision",
+                value: format!("{:?}", precisions),
+                accepted: "one finite positive precision per stream, at least one stream",
+            });
+        }
+        let fused_precision = precisions.iter().sum();
+        let state = &mut self.attributes.states[node_idx];
+        state.precision = fused_precision;
+        state.expected_precision = fused_precision;
+        self.attributes.vectors[node_idx].input_precision = precisions;
+        Ok(())
+    }
+
+    /// Attach the label `key = value` to `node_idx`, replacing any previous
+    /// value of `key`. Metadata rides along with the node and is never read by
+    /// the updates.
+    pub fn set_metadata(
+        &mut self,
+        node_idx: usize,
+        key: &str,
+        value: &str,
+    ) -> Result<(), NetworkError> {
+        if node_idx >= self.n_nodes() {
+            return Err(NetworkError::MissingNode {
+                node_idx,
+                step: "set_metadata",
+            });
+        }
+        self.metadata
+            .entry(node_idx)
+            .or_default()
+            .insert(key.to_string(), value.to_string());
+        Ok(())
+    }
+
+    /// The label `key` of `node_idx`, if set (see [`Network::set_metadata`]).
+    pub fn get_metadata(&self, node_idx: usize, key: &str) -> Option<&str> {
+        self.metadata.get(&node_idx)?.get(key).map(String::as_str)
+    }
+
+    /// Snapshots of every node, by index.
+    pub fn nodes(&self) -> Vec<NodeView> {
+        (0..self.n_nodes())
+            .filter_map(|node_idx| self.node_view(node_idx).ok())
+            .collect()
+    }
+
+    /// Whether `node_idx` is one of the input nodes (`false` for indices out
+    /// of range).
+    pub fn is_input(&self, node_idx: usize) -> bool {
+        self.inputs.contains(&node_idx)
+    }
+
+    /// Recompute the inputs and roots (the nodes without children) and the
+    /// leafs (the nodes without parents) from the edges. `add_nodes` tracks
+    /// them as nodes come, but a node declaring a parent does not remove that
+    /// parent from the inputs, so the builders that add edges in both
+    /// directions call this once done.
+    pub(crate) fn recompute_roles(&mut self) {
+        let without = |has: fn(&AdjacencyLists) -> bool| -> Vec<usize> {
+            (0..self.edges.len())
+                .filter(|&idx| !has(&self.edges[idx]))
+                .collect()
+        };
+        let inputs = without(|edges| {
+            edges.value_children.is_some()
+                || edges.volatility_children.is_some()
+                || edges.precision_children.is_some()
+        });
+        self.leafs = without(|edges| {
+            edges.node_type == "constant-state"
+                || edges.value_parents.is_some()
+                || edges.volatility_parents.is_some()
+                || edges.precision_parents.is_some()
+        });
+        self.roots = inputs.clone();
+        self.inputs = inputs;
+    }
+
+    /// Build the canonical continuous HGF with `n_levels` hidden levels: the
+    /// input node 0, its value parent x₁ (node 1), then each level `k + 1` as
+    /// the volatility parent of level `k`. `initial_mean`, `initial_precision`
+    /// and `tonic_volatility` hold one entry per level, `volatility_coupling`
+    /// one per volatility edge (`n_levels - 1`, from the bottom up).
+    pub fn continuous_hgf(
+        volatility_updates: &str,
+        n_levels: usize,
+        initial_mean: &[f64],
+        initial_precision: &[f64],
+        tonic_volatility: &[f64],
+        volatility_coupling: &[f64],
+    ) -> Result<Self, NetworkError> {
+        if n_levels == 0 {
+            return Err(NetworkError::DimensionMismatch {
+                what: "levels (at least one)",
+                expected: 1,
+                found: 0,
+            });
+        }
+        for (what, values, expected) in [
+            ("initial means (one per level)", initial_mean, n_levels),
+            (
+                "initial precisions (one per level)",
+                initial_precision,
+                n_levels,
+            ),
+            (
+                "tonic volatilities (one per level)",
+                tonic_volatility,
+                n_levels,
+            ),
+            (
+                "volatility couplings (one per pair of levels)",
+                volatility_coupling,
+                n_levels - 1,
+            ),
+        ] {
+            if values.len() != expected {
+                return Err(NetworkError::DimensionMismatch {
+                    what,
+                    expected,
+                    found: values.len(),
+                });
+            }
+        }
+
+        let mut network = Network::new(volatility_updates);
+        network.add_nodes("continuous-state", 1, AddNodesOptions::default())?;
+        for level in 0..n_levels {
+            let (value_children, volatility_children) = if level == 0 {
+                (Some(IntOrList::Single(0)), None)
+            } else {
+                (None, Some(IntOrList::Single(level)))
+            };
+            network.add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children,
+                    volatility_children,
+                    additional_parameters: Some(HashMap::from([
+                        ("mean".to_string(), initial_mean[level]),
+                        ("precision".to_string(), initial_precision[level]),
+                        ("tonic_volatility".to_string(), tonic_volatility[level]),
+                    ])),
+                    ..Default::default()
+                },
+            )?;
+        }
+        for (level, &coupling) in volatility_coupling.iter().enumerate() {
+            network
+                .set_volatility_coupling(level + 2, level + 1, coupling)
+                .expect("the volatility edge was just added");
+        }
+        network.set_update_sequence();
+        Ok(network)
+    }
+
+    /// Add `n_branches` independent continuous HGF branches, e.g. the arms of
+    /// a restless bandit. Each branch is an input node with `levels` hidden
+    /// levels above it: level 1 is the value parent of the input, and each
+    /// further level the volatility parent of the level below. With
+    /// `shared_volatility`, one more node becomes a volatility parent of every
+    /// level 1 node, so that it learns from the volatility of all branches.
+    ///
+    /// Returns the node indices of each branch, from the input up, and the
+    /// index of the shared volatility parent. The inputs are added in branch
+    /// order, so the branches read consecutive columns of `input_data`.
+    pub fn parallel_branches(
+        &mut self,
+        n_branches: usize,
+        levels: usize,
+        shared_volatility: bool,
+    ) -> Result<(Vec<Vec<usize>>, Option<usize>), NetworkError> {
+        if levels == 0 {
+            return Err(NetworkError::DimensionMismatch {
+                what: "levels (at least one)",
+                expected: 1,
+                found: 0,
+            });
+        }
+        let mut branches = Vec::with_capacity(n_branches);
+        for _ in 0..n_branches {
+            let mut branch = vec![self.edges.len()];
+            self.add_nodes("continuous-state", 1, AddNodesOptions::default())?;
+            for level in 1..=levels {
+                let below = Some(IntOrList::Single(branch[level - 1]));
+                let (value_children, volatility_children) = if level == 1 {
+                    (below, None)
+                } else {
+                    (None, below)
+                };
+                branch.push(self.edges.len());
+                self.add_nodes(
+                    "continuous-state",
+                    1,
+                    AddNodesOptions {
+                        value_children,
+                        volatility_children,
+                        ..Default::default()
+                    },
+                )?;
+            }
+            branches.push(branch);
         }
+
+        let shared_idx = if shared_volatility {
+            let second_levels: Vec<usize> = branches.iter().map(|branch| branch[1]).collect();
+            let shared_idx = self.edges.len();
+            self.add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    volatility_children: Some(IntOrList::List(second_levels)),
+                    ..Default::default()
+                },
+            )?;
+            Some(shared_idx)
+        } else {
+            None
+        };
+        Ok((branches, shared_idx))
     }
 
+    /// Add `n_nodes` nodes of `kind`, wired and initialised as described by
+    /// `options` (see [`AddNodesOptions`]).
     pub fn add_nodes(
         &mut self,
         kind: &str,
         n_nodes: usize,
-        value_parents: Option<IntOrList>,
-        value_children: Option<IntOrList>,
-        volatility_parents: Option<IntOrList>,
-        volatility_children: Option<IntOrList>,
-        coupling_fn: Option<String>,
-        additional_parameters: Option<HashMap<String, f64>>,
-    ) {
+        options: AddNodesOptions,
+    ) -> Result<(), NetworkError> {
+        let AddNodesOptions {
+            value_parents,
+            value_children,
+            volatility_parents,
+            volatility_children,
+            coupling_fn,
+            coupling,
+            volatility_coupling,
+            additional_parameters,
+        } = options;
+        if !NODE_KINDS.contains(&kind) {
+            return Err(NetworkError::InvalidValue {
+                parameter: "kind",
+                value: format!("'{}'", kind),
+                accepted: "a node kind listed in `NODE_KINDS`",
+            });
+        }
+        if let Some(ref overrides) = additional_parameters {
+            let accepted = add_nodes_parameters(kind);
+            // Report the first offending key in sorted order so that the error
+            // does not depend on the map's iteration order.
+            if let Some(parameter) = overrides
+                .keys()
+                .filter(|key| !accepted.contains(&key.as_str()))
+                .min()
+            {
+                return Err(NetworkError::InvalidParameter {
+                    node_type: kind.to_string(),
+                    parameter: parameter.clone(),
+                    accepted,
+                });
+            }
+            // As in `set_autoconnection`, except that 0 (the input default,
+            // a belief that keeps no memory) is accepted at build time.
+            for parameter in ["autoconnection_strength", "autoconnection_strength_vol"] {
+                if let Some(&value) = overrides.get(parameter) {
+                    if !(0.0..=1.0).contains(&value) {
+                        return Err(NetworkError::InvalidValue {
+                            parameter,
+                            value: value.to_string(),
+                            accepted: "a value in [0, 1]",
+                        });
+                    }
+                }
+            }
+        }
+        let n_streams = additional_parameters
+            .as_ref()
+            .and_then(|overrides| overrides.get("n_streams").copied())
+            .unwrap_or(1.0);
+        if kind == "aggregate-input" && !(n_streams >= 1.0 && n_streams.fract() == 0.0) {
+            return Err(NetworkError::InvalidValue {
+                parameter: "n_streams",
+                value: n_streams.to_string(),
+                accepted: "a whole number of streams, at least one",
+            });
+        }
+        let input_precision = additional_parameters
+            .as_ref()
+            .and_then(|overrides| overrides.get("input_precision").copied())
+            .unwrap_or(1.0);
+        if kind == "aggregate-input" && !(input_precision.is_finite() && input_precision > 0.0) {
+            return Err(NetworkError::InvalidValue {
+                parameter: "input_precision",
+                value: input_precision.to_string(),
+                accepted: "a finite positive precision",
+            });
+        }
+
+        let value_parents = value_parents.map(|v| v.into_vec());
+        let value_children = value_children.map(|v| v.into_vec());
+        let volatility_parents = volatility_parents.map(|v| v.into_vec());
+        let volatility_children = volatility_children.map(|v| v.into_vec());
+
+        // Edges can only point to nodes that already exist: a forward reference
+        // would leave the edge without its coupling vector and reciprocal edge.
+        for (edge, idxs) in [
+            ("value_parents", &value_parents),
+            ("value_children", &value_children),
+            ("volatility_parents", &volatility_parents),
+            ("volatility_children", &volatility_children),
+        ] {
+            if let Some(&target_idx) = idxs.iter().flatten().find(|&&i| i >= self.edges.len()) {
+                return Err(NetworkError::InvalidEdge {
+                    node_idx: self.edges.len(),
+                    edge,
+                    target_idx,
+                    step: "add_nodes",
+                    reason: MISSING_NODE,
+                });
+            }
+        }
+
+        // Initial value-coupling strengths, one per declared value edge: the
+        // value children first, then the value parents.
+        let n_value_children = value_children.as_ref().map_or(0, Vec::len);
+        let n_value_edges = n_value_children + value_parents.as_ref().map_or(0, Vec::len);
+        let couplings = match coupling {
+            Some(coupling) => coupling.into_vec(
+                n_value_edges,
+                "coupling (one per value child, then per value parent)",
+            )?,
+            None => vec![1.0; n_value_edges],
+        };
+        let (children_coupling, parents_coupling) = couplings.split_at(n_value_children);
+        // Likewise for the volatility edges.
+        let n_volatility_children = volatility_children.as_ref().map_or(0, Vec::len);
+        let n_volatility_edges =
+            n_volatility_children + volatility_parents.as_ref().map_or(0, Vec::len);
+        let volatility_couplings = match volatility_coupling {
+            Some(coupling) => coupling.into_vec(
+                n_volatility_edges,
+                "volatility_coupling (one per volatility child, then per volatility parent)",
+            )?,
+            None => vec![1.0; n_volatility_edges],
+        };
+        let (volatility_children_coupling, volatility_parents_coupling) =
+            volatility_couplings.split_at(n_volatility_children);
+
+        // A cached update sequence (and its edge tables) is stale once the
+        // structure changes; `input_data` and `fit` rebuild it on demand.
+        self.update_sequence = UpdateSequence {
+            predictions: Vec::new(),
+            updates: Vec::new(),
+        };
+        self.edge_lookup = EdgeLookup::default();
+
         let coupling_fn_opt: Option<&'static crate::math::CouplingFn> =
             match coupling_fn.as_deref().unwrap_or("linear") {
                 "linear" => None,
                 name => Some(crate::math::resolve_coupling_fn(name)),
             };
-        let value_parents = value_parents.map(|v| v.into_vec());
-        let value_children = value_children.map(|v| v.into_vec());
-        let volatility_parents = volatility_parents.map(|v| v.into_vec());
-        let volatility_children = volatility_children.map(|v| v.into_vec());
 
         for _ in 0..n_nodes {
             let node_id = self.edges.len();
@@ -497,10 +2274,15 @@ impl Network {
                 value_children: value_children.clone(),
                 volatility_parents: volatility_parents.clone(),
                 volatility_children: volatility_children.clone(),
+                precision_parents: None,
+                precision_children: None,
+                likelihood: String::from("gaussian"),
             };
 
             match kind {
-                "continuous-state" => {
+                // Circular nodes are continuous nodes on the circle: they only
+                // differ by their update steps (see `set_update_sequence`).
+                "continuous-state" | "circular-state" => {
                     let (autoconnection, tonic_vol) =
                         if is_input { (0.0, 0.0) } else { (1.0, -4.0) };
 
@@ -527,14 +2309,15 @@ impl Network {
                     let mut vecs = NodeVectors::default();
                     let fns = NodeFnPtrs {
                         coupling_fn: coupling_fn_opt,
+                        ..Default::default()
                     };
 
-                    if let Some(ref vp) = value_parents {
-                        vecs.value_coupling_parents = vec![1.0; vp.len()];
+                    if value_parents.is_some() {
+                        vecs.value_coupling_parents = parents_coupling.to_vec();
                     }
                     if let Some(ref vc) = value_children {
-                        vecs.value_coupling_children = vec![1.0; vc.len()];
-                        for &child_idx in vc {
+                        vecs.value_coupling_children = children_coupling.to_vec();
+                        for (&child_idx, &strength) in vc.iter().zip(children_coupling) {
                             if let Some(child_edges) = self.edges.get_mut(child_idx) {
                                 match &mut child_edges.value_parents {
                                     Some(parents) => parents.push(node_id),
@@ -544,16 +2327,17 @@ impl Network {
                             if child_idx < self.attributes.vectors.len() {
                                 self.attributes.vectors[child_idx]
                                     .value_coupling_parents
-                                    .push(1.0);
+                                    .push(strength);
                             }
                         }
                     }
-                    if let Some(ref volp) = volatility_parents {
-                        vecs.volatility_coupling_parents = vec![1.0; volp.len()];
+                    if volatility_parents.is_some() {
+                        vecs.volatility_coupling_parents = volatility_parents_coupling.to_vec();
                     }
                     if let Some(ref volc) = volatility_children {
-                        vecs.volatility_coupling_children = vec![1.0; volc.len()];
-                        for &child_idx in volc {
+                        vecs.volatility_coupling_children = volatility_children_coupling.to_vec();
+                        for (&child_idx, &strength) in volc.iter().zip(volatility_children_coupling)
+                        {
                             if let Some(child_edges) = self.edges.get_mut(child_idx) {
                                 match &mut child_edges.volatility_parents {
                                     Some(parents) => parents.push(node_id),
@@ -563,7 +2347,7 @@ impl Network {
                             if child_idx < self.attributes.vectors.len() {
                                 self.attributes.vectors[child_idx]
                                     .volatility_coupling_parents
-                                    .push(1.0);
+                                    .push(strength);
                             }
                         }
                     }
@@ -572,11 +2356,16 @@ impl Network {
                     self.attributes.fn_ptrs.push(fns);
                 }
                 "ef-state" => {
-                    let state = NodeState {
+                    let mut state = NodeState {
                         mean: 0.0,
                         nus: 3.0,
+                        tracked_mean: 0.0,
+                        tracked_variance: 1.0,
                         ..Default::default()
                     };
+                    if let Some(ref overrides) = additional_parameters {
+                        apply_overrides_ef(&mut state, overrides);
+                    }
                     self.attributes.states.push(state);
                     self.edges.push(edges);
                     let vecs = NodeVectors {
@@ -594,6 +2383,9 @@ impl Network {
                         value_children: value_children.clone(),
                         volatility_parents: None,
                         volatility_children: None,
+                        precision_parents: None,
+                        precision_children: None,
+                        likelihood: String::from("gaussian"),
                     };
 
                     let mut state = NodeState {
@@ -629,12 +2421,12 @@ impl Network {
 
                     let mut vecs = NodeVectors::default();
 
-                    if let Some(ref vp) = value_parents {
-                        vecs.value_coupling_parents = vec![1.0; vp.len()];
+                    if value_parents.is_some() {
+                        vecs.value_coupling_parents = parents_coupling.to_vec();
                     }
                     if let Some(ref vc) = value_children {
-                        vecs.value_coupling_children = vec![1.0; vc.len()];
-                        for &child_idx in vc {
+                        vecs.value_coupling_children = children_coupling.to_vec();
+                        for (&child_idx, &strength) in vc.iter().zip(children_coupling) {
                             if let Some(child_edges) = self.edges.get_mut(child_idx) {
                                 match &mut child_edges.value_parents {
                                     Some(parents) => parents.push(node_id),
@@ -644,7 +2436,7 @@ impl Network {
                             if child_idx < self.attributes.vectors.len() {
                                 self.attributes.vectors[child_idx]
                                     .value_coupling_parents
-                                    .push(1.0);
+                                    .push(strength);
                             }
                         }
                     }
@@ -652,9 +2444,10 @@ impl Network {
                     self.attributes.vectors.push(vecs);
                     self.attributes.fn_ptrs.push(NodeFnPtrs {
                         coupling_fn: coupling_fn_opt,
+                        ..Default::default()
                     });
                 }
-                "binary-state" => {
+                "binary-state" | "binary-input" => {
                     let state = NodeState {
                         observed: 1.0,
                         mean: 0.0,
@@ -669,12 +2462,12 @@ impl Network {
 
                     let mut vecs = NodeVectors::default();
 
-                    if let Some(ref vp) = value_parents {
-                        vecs.value_coupling_parents = vec![1.0; vp.len()];
+                    if value_parents.is_some() {
+                        vecs.value_coupling_parents = parents_coupling.to_vec();
                     }
                     if let Some(ref vc) = value_children {
-                        vecs.value_coupling_children = vec![1.0; vc.len()];
-                        for &child_idx in vc {
+                        vecs.value_coupling_children = children_coupling.to_vec();
+                        for (&child_idx, &strength) in vc.iter().zip(children_coupling) {
                             if let Some(child_edges) = self.edges.get_mut(child_idx) {
                                 match &mut child_edges.value_parents {
                                     Some(parents) => parents.push(node_id),
@@ -684,7 +2477,7 @@ impl Network {
                             if child_idx < self.attributes.vectors.len() {
                                 self.attributes.vectors[child_idx]
                                     .value_coupling_parents
-                                    .push(1.0);
+                                    .push(strength);
                             }
                         }
                     }
@@ -692,21 +2485,163 @@ impl Network {
                     self.attributes.vectors.push(vecs);
                     self.attributes.fn_ptrs.push(NodeFnPtrs {
                         coupling_fn: coupling_fn_opt,
+                        ..Default::default()
                     });
                 }
-                "constant-state" => {
-                    // Constant state nodes are assumed to have mean = 1.0 and
-                    // precision = 1.0 (fully known bias). They are always wired to
-                    // their children linearly (no coupling function), regardless
-                    // of the layer's coupling_fn.
-                    //
-                    // ``expected_precision`` is set to infinity so that the piHGF
-                    // Laplace value-coupling term `(t · α · g'(µ̂))² / π̂_parent`
-                    // contributes zero for the bias parent — matching the JAX
-                    // vectorised backend, which concatenates an `inf` into the
-                    // parent-precision vector for the constant column. (The
-                    // posterior-level ``precision`` is kept at 1.0 because the
-                    // ``precision_ratio`` learning gain reads it directly.)
+                "softmax-state" => {
+                    // A readout over the K value parents: `expected_precision`
+                    // stays at 0 so that the parents' posterior updates receive
+                    // no Gaussian message from it.
+                    let mut state = NodeState {
+                        observed: 1.0,
+                        mean: 0.0,
+                        expected_mean: 0.0,
+                        precision: 0.0,
+                        expected_precision: 0.0,
+                        temperature: 1.0,
+                        ..Default::default()
+                    };
+                    if let Some(ref overrides) = additional_parameters {
+                        apply_overrides_softmax(&mut state, overrides);
+                    }
+                    self.attributes.states.push(state);
+                    self.edges.push(edges);
+
+                    let mut vecs = NodeVectors::default();
+                    if let Some(ref vp) = value_parents {
+                        vecs.value_coupling_parents = parents_coupling.to_vec();
+                        vecs.probabilities = vec![1.0 / vp.len() as f64; vp.len()];
+                    }
+
+                    self.attributes.vectors.push(vecs);
+                    self.attributes.fn_ptrs.push(NodeFnPtrs::default());
+                }
+                "dp-state" => {
+                    // An input over a mixture of clusters, its value parents:
+                    // `precision` is the precision of an observation around
+                    // its cluster, and the clusters are opened during the run
+                    // (see `crate::utils::clusters`).
+                    let mut state = NodeState {
+                        mean: 0.0,
+                        expected_mean: 0.0,
+                        precision: 1.0,
+                        expected_precision: 1.0,
+                        ..Default::default()
+                    };
+                    if let Some(ref overrides) = additional_parameters {
+                        apply_overrides_dp(&mut state, overrides);
+                    }
+                    self.attributes.states.push(state);
+                    self.edges.push(edges);
+
+                    let mut vecs = NodeVectors::default();
+                    if let Some(ref vp) = value_parents {
+                        vecs.value_coupling_parents = parents_coupling.to_vec();
+                        vecs.probabilities = vec![1.0 / vp.len() as f64; vp.len()];
+                        vecs.cluster_counts = vec![1.0; vp.len()];
+                    }
+                    self.attributes.vectors.push(vecs);
+                    self.attributes.fn_ptrs.push(NodeFnPtrs::default());
+                }
+                "bounded-state" => {
+                    // An input on the interval [lower_bound, upper_bound],
+                    // filtered as a continuous input on the logit scale: the
+                    // observations are transformed on entry (see
+                    // `crate::updates::nodalised::observations`).
+                    let mut state = NodeState {
+                        mean: 0.0,
+                        expected_mean: 0.0,
+                        precision: 1.0,
+                        expected_precision: 1.0,
+                        tonic_volatility: 0.0,
+                        autoconnection_strength: 0.0,
+                        current_variance: 1.0,
+                        ..Default::default()
+                    };
+                    if let Some(ref overrides) = additional_parameters {
+                        apply_overrides_bounded(&mut state, overrides);
+                    }
+                    self.attributes.states.push(state);
+                    self.edges.push(edges);
+
+                    let mut vecs = NodeVectors::default();
+                    if value_parents.is_some() {
+                        vecs.value_coupling_parents = parents_coupling.to_vec();
+                    }
+                    self.attributes.vectors.push(vecs);
+                    self.attributes.fn_ptrs.push(NodeFnPtrs::default());
+                }
+                "lognormal-state" => {
+                    // A positive input, filtered as a continuous input on the
+                    // log scale of its observations.
+                    let mut state = NodeState {
+                        mean: 0.0,
+                        expected_mean: 0.0,
+                        precision: 1.0,
+                        expected_precision: 1.0,
+                        tonic_volatility: 0.0,
+                        autoconnection_strength: 0.0,
+                        current_variance: 1.0,
+                        ..Default::default()
+                    };
+                    if let Some(ref overrides) = additional_parameters {
+                        apply_overrides_lognormal(&mut state, overrides);
+                    }
+                    self.attributes.states.push(state);
+                    self.edges.push(edges);
+
+                    let mut vecs = NodeVectors::default();
+                    if value_parents.is_some() {
+                        vecs.value_coupling_parents = parents_coupling.to_vec();
+                    }
+                    self.attributes.vectors.push(vecs);
+                    self.attributes.fn_ptrs.push(NodeFnPtrs::default());
+                }
+                "aggregate-input" => {
+                    // A continuous input fed by `n_streams` observation
+                    // columns, fused by precision weighting. Every stream
+                    // starts at `input_precision` (see
+                    // `Network::set_input_precision` for per-stream values).
+                    let fused_precision = n_streams * input_precision;
+                    let mut state = NodeState {
+                        mean: 0.0,
+                        expected_mean: 0.0,
+                        precision: fused_precision,
+                        expected_precision: fused_precision,
+                        tonic_volatility: 0.0,
+                        autoconnection_strength: 0.0,
+                        current_variance: 1.0,
+                        ..Default::default()
+                    };
+                    if let Some(ref overrides) = additional_parameters {
+                        apply_overrides_aggregate(&mut state, overrides);
+                    }
+                    self.attributes.states.push(state);
+                    self.edges.push(edges);
+
+                    let mut vecs = NodeVectors {
+                        input_precision: vec![input_precision; n_streams as usize],
+                        ..Default::default()
+                    };
+                    if value_parents.is_some() {
+                        vecs.value_coupling_parents = parents_coupling.to_vec();
+                    }
+                    self.attributes.vectors.push(vecs);
+                    self.attributes.fn_ptrs.push(NodeFnPtrs::default());
+                }
+                "constant-state" => {
+                    // Constant state nodes are assumed to have mean = 1.0 and
+                    // precision = 1.0 (fully known bias). They are always wired to
+                    // their children linearly (no coupling function), regardless
+                    // of the layer's coupling_fn.
+                    //
+                    // ``expected_precision`` is set to infinity so that the piHGF
+                    // Laplace value-coupling term `(t · α · g'(µ̂))² / π̂_parent`
+                    // contributes zero for the bias parent — matching the JAX
+                    // vectorised backend, which concatenates an `inf` into the
+                    // parent-precision vector for the constant column. (The
+                    // posterior-level ``precision`` is kept at 1.0 because the
+                    // ``precision_ratio`` learning gain reads it directly.)
                     let state = NodeState {
                         mean: 1.0,
                         expected_mean: 1.0,
@@ -720,8 +2655,8 @@ impl Network {
                     let mut vecs = NodeVectors::default();
 
                     if let Some(ref vc) = value_children {
-                        vecs.value_coupling_children = vec![1.0; vc.len()];
-                        for &child_idx in vc {
+                        vecs.value_coupling_children = children_coupling.to_vec();
+                        for (&child_idx, &strength) in vc.iter().zip(children_coupling) {
                             if let Some(child_edges) = self.edges.get_mut(child_idx) {
                                 match &mut child_edges.value_parents {
                                     Some(parents) => parents.push(node_id),
@@ -731,13 +2666,14 @@ impl Network {
                             if child_idx < self.attributes.vectors.len() {
                                 self.attributes.vectors[child_idx]
                                     .value_coupling_parents
-                                    .push(1.0);
+                                    .push(strength);
                             }
                         }
                     }
                     if let Some(ref volc) = volatility_children {
-                        vecs.volatility_coupling_children = vec![1.0; volc.len()];
-                        for &child_idx in volc {
+                        vecs.volatility_coupling_children = volatility_children_coupling.to_vec();
+                        for (&child_idx, &strength) in volc.iter().zip(volatility_children_coupling)
+                        {
                             if let Some(child_edges) = self.edges.get_mut(child_idx) {
                                 match &mut child_edges.volatility_parents {
                                     Some(parents) => parents.push(node_id),
@@ -747,7 +2683,7 @@ impl Network {
                             if child_idx < self.attributes.vectors.len() {
                                 self.attributes.vectors[child_idx]
                                     .volatility_coupling_parents
-                                    .push(1.0);
+                                    .push(strength);
                             }
                         }
                     }
@@ -755,22 +2691,22 @@ impl Network {
                     self.attributes.vectors.push(vecs);
                     // Force constant-state nodes to use no coupling (identity)
                     // regardless of what the caller passed.
-                    self.attributes
-                        .fn_ptrs
-                        .push(NodeFnPtrs { coupling_fn: None });
+                    self.attributes.fn_ptrs.push(NodeFnPtrs::default());
                 }
-                _ => {}
+                _ => unreachable!("node kinds are checked against NODE_KINDS"),
             }
 
             // Reciprocal updates: when value_parents or volatility_parents are
             // specified, update each parent's children list so the parent knows
             // about this new child.  (The reverse direction — value_children
             // updating the child's parents — is already handled above.)
-            let vp_clone = self.edges[node_id].value_parents.clone();
-            let volp_clone = self.edges[node_id].volatility_parents.clone();
+            // The new node's parent lists are moved out while the parents are
+            // mutated, and restored below.
+            let vp_taken = self.edges[node_id].value_parents.take();
+            let volp_taken = self.edges[node_id].volatility_parents.take();
 
-            if let Some(ref vp) = vp_clone {
-                for &parent_idx in vp {
+            if let Some(ref vp) = vp_taken {
+                for (&parent_idx, &strength) in vp.iter().zip(parents_coupling) {
                     // Skip if the parent node hasn't been created yet (it will
                     // perform the reciprocal update via its own value_children).
                     if parent_idx >= self.edges.len() {
@@ -798,12 +2734,12 @@ impl Network {
                     if parent_coupling_len < parent_n_children {
                         self.attributes.vectors[parent_idx]
                             .value_coupling_children
-                            .push(1.0);
+                            .push(strength);
                     }
                 }
             }
-            if let Some(ref volp) = volp_clone {
-                for &parent_idx in volp {
+            if let Some(ref volp) = volp_taken {
+                for (&parent_idx, &strength) in volp.iter().zip(volatility_parents_coupling) {
                     if parent_idx >= self.edges.len() {
                         continue;
                     }
@@ -826,160 +2762,1247 @@ impl Network {
                     if parent_coupling_len < parent_n_children {
                         self.attributes.vectors[parent_idx]
                             .volatility_coupling_children
-                            .push(1.0);
+                            .push(strength);
                     }
                 }
             }
+            self.edges[node_id].value_parents = vp_taken;
+            self.edges[node_id].volatility_parents = volp_taken;
         } // end for n_nodes
-    }
 
-    pub fn set_update_sequence(&mut self) {
-        self.update_sequence = set_update_sequence(self);
+        Ok(())
     }
 
-    pub fn input_data(
+    /// Make `parent_idx` a precision (noise) parent of the input `child_idx`:
+    /// the input's expected variance becomes `exp(μ̂_parent)`, i.e. its
+    /// expected precision `exp(−μ̂_parent)`, at every step. As for a
+    /// volatility parent, the parent tracks the log of a variance: its mean
+    /// rises with the observation noise, and its posterior is updated from
+    /// the input's squared prediction errors.
+    ///
+    /// Both nodes must be continuous-state; the parent cannot have value or
+    /// volatility children of its own. A node cannot be its own precision
+    /// parent, and an existing precision edge cannot be added twice.
+    pub fn add_precision_parent(
         &mut self,
-        input_data: Vec<Vec<f64>>,
-        time_steps: Option<Vec<f64>>,
-        record_trajectories: bool,
-    ) {
-        if self.update_sequence.predictions.is_empty() && self.update_sequence.updates.is_empty() {
-            self.set_update_sequence();
+        parent_idx: usize,
+        child_idx: usize,
+    ) -> Result<(), NetworkError> {
+        const STEP: &str = "add_precision_parent";
+        for node_idx in [parent_idx, child_idx] {
+            let Some(edge) = self.edges.get(node_idx) else {
+                return Err(NetworkError::MissingNode {
+                    node_idx,
+                    step: STEP,
+                });
+            };
+            if edge.node_type != "continuous-state" {
+                return Err(NetworkError::UnsupportedStructure {
+                    node_idx,
+                    step: STEP,
+                    reason: "precision edges join continuous-state nodes",
+                });
+            }
+        }
+        let invalid_edge = |reason| NetworkError::InvalidEdge {
+            node_idx: parent_idx,
+            edge: "precision_children",
+            target_idx: child_idx,
+            step: STEP,
+            reason,
+        };
+        if parent_idx == child_idx {
+            return Err(invalid_edge("cannot list its own node"));
+        }
+        if self.edges[parent_idx]
+            .precision_children
+            .as_ref()
+            .is_some_and(|children| children.contains(&child_idx))
+        {
+            return Err(invalid_edge("already lists node"));
+        }
+        if !self.inputs.contains(&child_idx) {
+            return Err(NetworkError::UnsupportedStructure {
+                node_idx: child_idx,
+                step: STEP,
+                reason: "only input nodes can have precision parents",
+            });
+        }
+        let parent = &self.edges[parent_idx];
+        if parent.value_children.is_some() || parent.volatility_children.is_some() {
+            return Err(NetworkError::UnsupportedStructure {
+                node_idx: parent_idx,
+                step: STEP,
+                reason: "a precision parent cannot have value or volatility children",
+            });
         }
 
-        let n_time = input_data.len();
-        let time_steps = time_steps.unwrap_or_else(|| vec![1.0; n_time]);
-        let predictions = self.update_sequence.predictions.clone();
-        let updates = self.update_sequence.updates.clone();
+        self.edges[parent_idx]
+            .precision_children
+            .get_or_insert_with(Vec::new)
+            .push(child_idx);
+        self.edges[child_idx]
+            .precision_parents
+            .get_or_insert_with(Vec::new)
+            .push(parent_idx);
+        // A parent added without children was created as an input; it is a
+        // latent node now and takes the latent defaults.
+        if self.inputs.contains(&parent_idx) {
+            let state = &mut self.attributes.states[parent_idx];
+            state.autoconnection_strength = 1.0;
+            state.tonic_volatility = -4.0;
+            self.inputs.retain(|&x| x != parent_idx);
+        }
+        self.roots.retain(|&x| x != parent_idx);
+        self.leafs.retain(|&x| x != child_idx);
 
-        let mut node_trajectories = NodeTrajectories { nodes: Vec::new() };
+        self.update_sequence = UpdateSequence {
+            predictions: Vec::new(),
+            updates: Vec::new(),
+        };
+        self.edge_lookup = EdgeLookup::default();
+        Ok(())
+    }
 
-        if record_trajectories {
-            for _ in 0..self.attributes.states.len() {
-                node_trajectories
-                    .nodes
-                    .push(NodeTrajectory::with_capacity(n_time));
+    /// Append the nodes of `other` after those of this network, then add the
+    /// value edges in `connect` as `(parent_idx, child_idx, coupling)`.
+    ///
+    /// The nodes of `other` keep their edges, attributes, coupling functions
+    /// and metadata, with their ids offset by the number of nodes already
+    /// here; `connect` uses the ids of the merged network, so node `i` of
+    /// `other` is `i + self.n_nodes()`. Parents in `connect` must be
+    /// continuous or volatile nodes; a continuous parent that was an input
+    /// takes the latent defaults, as in [`Network::add_precision_parent`]. An
+    /// edge whose child is already an ancestor of its parent would close a
+    /// cycle and is rejected with [`NetworkError::InvalidEdge`].
+    ///
+    /// Everything is checked before the network is modified. The update
+    /// sequence (including inserted Python steps), the trajectories and the
+    /// Adam state are reset; the network-wide settings of `other` are not
+    /// copied.
+    pub fn merge(
+        &mut self,
+        other: &Network,
+        connect: Vec<(usize, usize, f64)>,
+    ) -> Result<(), NetworkError> {
+        const STEP: &str = "merge";
+        let offset = self.edges.len();
+        let n_nodes = offset + other.edges.len();
+        let node_type = |node_idx: usize| match node_idx.checked_sub(offset) {
+            Some(other_idx) => other.edges[other_idx].node_type.as_str(),
+            None => self.edges[node_idx].node_type.as_str(),
+        };
+        let value_children = |node_idx: usize| match node_idx.checked_sub(offset) {
+            Some(other_idx) => other.edges[other_idx].value_children.as_ref(),
+            None => self.edges[node_idx].value_children.as_ref(),
+        };
+        // Parents of every node of the merged graph, extended with each new
+        // edge once it is accepted, to reject the edges that close a cycle.
+        let mut parents: Vec<Vec<usize>> = self
+            .edges
+            .iter()
+            .map(|edge| (edge, 0))
+            .chain(other.edges.iter().map(|edge| (edge, offset)))
+            .map(|(edge, shift)| {
+                [
+                    &edge.value_parents,
+                    &edge.volatility_parents,
+                    &edge.precision_parents,
+                ]
+                .into_iter()
+                .flatten()
+                .flatten()
+                .map(|&idx| idx + shift)
+                .collect()
+            })
+            .collect();
+        for (edge_idx, &(parent_idx, child_idx, _)) in connect.iter().enumerate() {
+            for node_idx in [parent_idx, child_idx] {
+                if node_idx >= n_nodes {
+                    return Err(NetworkError::MissingNode {
+                        node_idx,
+                        step: STEP,
+                    });
+                }
+            }
+            if !matches!(node_type(parent_idx), "continuous-state" | "volatile-state") {
+                return Err(NetworkError::UnsupportedStructure {
+                    node_idx: parent_idx,
+                    step: STEP,
+                    reason: "value parents must be continuous or volatile nodes",
+                });
             }
+            let repeated = connect[..edge_idx]
+                .iter()
+                .any(|&(parent, child, _)| (parent, child) == (parent_idx, child_idx));
+            let existing = value_children(parent_idx).is_some_and(|c| c.contains(&child_idx));
+            if parent_idx == child_idx || repeated || existing {
+                return Err(NetworkError::UnsupportedStructure {
+                    node_idx: child_idx,
+                    step: STEP,
+                    reason: "each new value edge must join two distinct nodes not yet coupled",
+                });
+            }
+            if is_ancestor(&parents, child_idx, parent_idx) {
+                return Err(NetworkError::InvalidEdge {
+                    node_idx: parent_idx,
+                    edge: "value_children",
+                    target_idx: child_idx,
+                    step: STEP,
+                    reason: "would close a cycle through node",
+                });
+            }
+            parents[child_idx].push(parent_idx);
         }
 
-        for (t, observations) in input_data.iter().enumerate() {
-            belief_propagation(self, observations, &predictions, &updates, time_steps[t]);
+        let shift = |ids: &Option<Vec<usize>>| {
+            ids.as_ref()
+                .map(|ids| ids.iter().map(|&idx| idx + offset).collect())
+        };
+        self.edges
+            .extend(other.edges.iter().map(|edge| AdjacencyLists {
+                value_parents: shift(&edge.value_parents),
+                value_children: shift(&edge.value_children),
+                volatility_parents: shift(&edge.volatility_parents),
+                volatility_children: shift(&edge.volatility_children),
+                precision_parents: shift(&edge.precision_parents),
+                precision_children: shift(&edge.precision_children),
+                ..edge.clone()
+            }));
+        self.attributes
+            .states
+            .extend_from_slice(&other.attributes.states);
+        self.attributes
+            .vectors
+            .extend_from_slice(&other.attributes.vectors);
+        self.attributes
+            .fn_ptrs
+            .extend_from_slice(&other.attributes.fn_ptrs);
+        self.layers.extend(
+            other
+                .layers
+                .iter()
+                .map(|layer| layer.iter().map(|&idx| idx + offset).collect()),
+        );
+        for (&node_idx, labels) in &other.metadata {
+            self.metadata.insert(node_idx + offset, labels.clone());
+        }
 
-            if record_trajectories {
-                for (i, state) in self.attributes.states.iter().enumerate() {
-                    node_trajectories.nodes[i].push_state(state);
-                    node_trajectories.nodes[i].push_vectors(&self.attributes.vectors[i]);
-                }
+        for (parent_idx, child_idx, coupling) in connect {
+            let parent = &self.edges[parent_idx];
+            let was_input = parent.value_children.is_none()
+                && parent.volatility_children.is_none()
+                && parent.precision_children.is_none();
+            if was_input && parent.node_type == "continuous-state" {
+                let state = &mut self.attributes.states[parent_idx];
+                state.autoconnection_strength = 1.0;
+                state.tonic_volatility = -4.0;
             }
+            self.edges[parent_idx]
+                .value_children
+                .get_or_insert_with(Vec::new)
+                .push(child_idx);
+            self.edges[child_idx]
+                .value_parents
+                .get_or_insert_with(Vec::new)
+                .push(parent_idx);
+            self.attributes.vectors[parent_idx]
+                .value_coupling_children
+                .push(coupling);
+            self.attributes.vectors[child_idx]
+                .value_coupling_parents
+                .push(coupling);
         }
+        self.recompute_roles();
 
-        if record_trajectories {
-            self.node_trajectories = node_trajectories;
+        self.update_sequence = UpdateSequence {
+            predictions: Vec::new(),
+            updates: Vec::new(),
+        };
+        self.python_steps.clear();
+        self.edge_lookup = EdgeLookup::default();
+        self.node_trajectories = NodeTrajectories::default();
+        self.adam_state = None;
+        Ok(())
+    }
+
+    /// Upper bound on the value-level posterior precision of `node_idx`: the
+    /// network-wide `max_posterior_precision`, tightened by the node's own
+    /// `max_precision`.
+    #[inline]
+    pub fn posterior_precision_cap(&self, node_idx: usize) -> f64 {
+        self.max_posterior_precision
+            .min(self.attributes.states[node_idx].max_precision)
+    }
+
+    pub fn set_update_sequence(&mut self) {
+        self.update_sequence = set_update_sequence(self);
+        self.edge_lookup = build_edge_lookup(self);
+        // A Python step whose place is gone (e.g. a replaced step renamed by
+        // another update type) stays out; the next run reports it (see
+        // `check_python_steps`).
+        for slot in 0..self.python_steps.len() {
+            let _ = self.place_python_step(slot);
         }
     }
 
-    pub fn add_layer(
+    /// Name of the prediction error and posterior update function each node
+    /// will be assigned by [`Network::set_update_sequence`], by node index.
+    /// Computed from the current structure and `volatility_updates`, without
+    /// touching the cached sequence.
+    pub fn describe_updates(&self) -> Vec<(usize, String)> {
+        let mut updates: Vec<(usize, String)> = get_updates_sequence(self)
+            .into_iter()
+            .map(|(node_idx, step)| (node_idx, step.name().to_string()))
+            .collect();
+        updates.sort_by_key(|&(node_idx, _)| node_idx);
+        updates
+    }
+
+    /// Replace the step `name` on `node_idx` with the Python callable
+    /// `function` (see [`crate::utils::python_step`]). The callable keeps the
+    /// stage of the step it replaces, and its place when the update sequence
+    /// is rebuilt.
+    pub fn replace_update_step(
         &mut self,
-        size: usize,
-        kind: &str,
-        value_children: Option<Vec<usize>>,
-        coupling_strengths: f64,
-        coupling_fn: Option<String>,
-        additional_parameters: Option<HashMap<String, f64>>,
-        add_constant_input: bool,
-    ) {
-        let n_nodes_before = self.edges.len();
+        node_idx: usize,
+        name: &str,
+        function: &Bound<'_, PyAny>,
+    ) -> Result<(), NetworkError> {
+        let placement = StepPlacement::Replace {
+            node_idx,
+            name: name.to_string(),
+        };
+        self.add_python_step(PythonStep::new(function, placement), "replace_update_step")
+    }
 
-        let children: Vec<usize> = match value_children {
-            Some(vc) => vc,
-            None => {
-                // If layers exist, connect to the last layer's nodes;
-                // otherwise connect to all leaf nodes (nodes without parents).
-                if let Some(last_layer) = self.layers.last() {
-                    last_layer.clone()
-                } else {
-                    self.leafs.clone()
-                }
+    /// Insert the Python callable `function` on `node_idx` at `position` in the
+    /// `"predictions"` or `"updates"` phase (at the end when `position` is
+    /// `None`), as a step of the given `kind`: prediction steps go in the
+    /// predictions phase, the other kinds in the updates phase. The step keeps
+    /// its place when the update sequence is rebuilt.
+    pub fn insert_update_step(
+        &mut self,
+        node_idx: usize,
+        function: &Bound<'_, PyAny>,
+        phase: &str,
+        position: Option<usize>,
+        kind: StepKind,
+    ) -> Result<(), NetworkError> {
+        let updates = match phase {
+            "predictions" => false,
+            "updates" => true,
+            _ => {
+                return Err(NetworkError::PythonStep {
+                    node_idx,
+                    message: format!(
+                        "cannot go in unknown phase `{}` (expected `predictions` or `updates`)",
+                        phase
+                    ),
+                })
             }
+        };
+        if updates == (kind == StepKind::Prediction) {
+            return Err(NetworkError::InvalidValue {
+                parameter: "kind",
+                value: format!("'{}' in the `{}` phase", kind.name(), phase),
+                accepted: "'prediction' in the predictions phase, or 'posterior', \
+                           'prediction_error' or 'learning' in the updates phase",
+            });
         }
-        .into_iter()
-        .filter(|&idx| self.edges[idx].node_type != "constant-state")
-        .collect();
-
-        let additional_parameters = {
-            let mut params = additional_parameters.unwrap_or_default();
-            params
-                .entry("autoconnection_strength".into())
-                .or_insert(0.0);
-            Some(params)
+        let placement = StepPlacement::Insert {
+            node_idx,
+            updates,
+            position,
+            kind,
         };
+        self.add_python_step(PythonStep::new(function, placement), "insert_update_step")
+    }
 
-        for _ in 0..size {
-            let vc = IntOrList::List(children.clone());
-            self.add_nodes(
-                kind,
-                1,
-                None,
-                Some(vc),
-                None,
-                None,
-                coupling_fn.clone(),
-                additional_parameters.clone(),
-            );
+    /// Add `function` to the update sequence, built if needed, at its
+    /// placement.
+    fn add_python_step(
+        &mut self,
+        function: PythonStep,
+        step: &'static str,
+    ) -> Result<(), NetworkError> {
+        let node_idx = function.placement.node_idx();
+        if node_idx >= self.edges.len() {
+            return Err(NetworkError::MissingNode { node_idx, step });
+        }
+        if self.update_sequence.predictions.is_empty() && self.update_sequence.updates.is_empty() {
+            self.set_update_sequence();
+        }
+        self.python_steps.push(function);
+        let result = self.place_python_step(self.python_steps.len() - 1);
+        if result.is_err() {
+            self.python_steps.pop();
+        }
+        result
+    }
 
-            let node_id = self.edges.len() - 1;
-            for v in self.attributes.vectors[node_id]
-                .value_coupling_children
-                .iter_mut()
-            {
-                *v = coupling_strengths;
-            }
-            for &child_idx in &children {
-                if let Some(last) = self.attributes.vectors[child_idx]
-                    .value_coupling_parents
-                    .last_mut()
-                {
-                    *last = coupling_strengths;
+    /// Put the Python step `slot` in the cached update sequence, at its
+    /// placement.
+    fn place_python_step(&mut self, slot: usize) -> Result<(), NetworkError> {
+        let step = &self.python_steps[slot];
+        let node_idx = step.placement.node_idx();
+        let misplaced = |message: String| NetworkError::PythonStep { node_idx, message };
+        match step.placement {
+            StepPlacement::Replace { ref name, .. } => {
+                let entry = self
+                    .update_sequence
+                    .predictions
+                    .iter_mut()
+                    .chain(self.update_sequence.updates.iter_mut())
+                    .find(|(idx, built_in)| *idx == node_idx && built_in.name() == name)
+                    .ok_or_else(|| {
+                        misplaced(format!(
+                            "`{}` cannot replace `{}`, the node has no such step",
+                            step.name, name
+                        ))
+                    })?;
+                entry.1 = UpdateStep::Python {
+                    slot,
+                    kind: entry.1.kind(),
+                };
+            }
+            StepPlacement::Insert {
+                updates,
+                position,
+                kind,
+                ..
+            } => {
+                let sequence = match updates {
+                    true => &mut self.update_sequence.updates,
+                    false => &mut self.update_sequence.predictions,
+                };
+                let position = position.unwrap_or(sequence.len());
+                if position > sequence.len() {
+                    return Err(misplaced(format!(
+                        "`{}` cannot go at position {} of a phase of {} steps",
+                        step.name,
+                        position,
+                        sequence.len()
+                    )));
                 }
+                sequence.insert(position, (node_idx, UpdateStep::Python { slot, kind }));
             }
         }
+        Ok(())
+    }
 
-        if add_constant_input {
-            let non_constant_children: Vec<usize> = children
-                .iter()
-                .filter(|&&idx| self.edges[idx].node_type != "constant-state")
-                .copied()
-                .collect();
-
-            if !non_constant_children.is_empty() {
-                let vc = IntOrList::List(non_constant_children);
-                self.add_nodes(
-                    "constant-state",
-                    1,
-                    None,
-                    Some(vc),
-                    None,
-                    None,
-                    coupling_fn.clone(),
-                    None,
-                );
+    /// Check that every Python step has its place in `sequence`: one that
+    /// could not be put back when the sequence was rebuilt is an error rather
+    /// than a step silently left out.
+    fn check_python_steps(&self, sequence: &UpdateSequence) -> Result<(), NetworkError> {
+        let placed: Vec<usize> = sequence
+            .predictions
+            .iter()
+            .chain(&sequence.updates)
+            .filter_map(|&(_, step)| match step {
+                UpdateStep::Python { slot, .. } => Some(slot),
+                _ => None,
+            })
+            .collect();
+        match (0..self.python_steps.len()).find(|slot| !placed.contains(slot)) {
+            Some(slot) => {
+                let step = &self.python_steps[slot];
+                Err(NetworkError::PythonStep {
+                    node_idx: step.placement.node_idx(),
+                    message: format!("`{}` no longer fits the rebuilt update sequence", step.name),
+                })
             }
+            None => Ok(()),
         }
-
-        let new_layer: Vec<usize> = (n_nodes_before..self.edges.len()).collect();
-        self.layers.push(new_layer);
     }
 
-    pub fn add_layer_stack(
-        &mut self,
-        layer_sizes: Vec<usize>,
-        kind: &str,
-        value_children: Option<Vec<usize>>,
-        coupling_strengths: f64,
-        coupling_fn: Option<String>,
-        additional_parameters: Option<HashMap<String, f64>>,
+    /// Check that `node_idx` exists, that its adjacency lists only refer to
+    /// existing nodes and that its coupling vectors hold one entry per edge.
+    /// `step` names the update step about to run, for the error message.
+    pub fn check_node(&self, node_idx: usize, step: &'static str) -> Result<(), NetworkError> {
+        let n_nodes = self.attributes.states.len();
+        let (Some(edge), Some(vectors)) = (
+            self.edges.get(node_idx).filter(|_| node_idx < n_nodes),
+            self.attributes.vectors.get(node_idx),
+        ) else {
+            return Err(NetworkError::MissingNode { node_idx, step });
+        };
+
+        for (name, list, attribute, coupling) in [
+            (
+                "value_parents",
+                &edge.value_parents,
+                "value_coupling_parents",
+                &vectors.value_coupling_parents,
+            ),
+            (
+                "value_children",
+                &edge.value_children,
+                "value_coupling_children",
+                &vectors.value_coupling_children,
+            ),
+            (
+                "volatility_parents",
+                &edge.volatility_parents,
+                "volatility_coupling_parents",
+                &vectors.volatility_coupling_parents,
+            ),
+            (
+                "volatility_children",
+                &edge.volatility_children,
+                "volatility_coupling_children",
+                &vectors.volatility_coupling_children,
+            ),
+        ] {
+            let list = list.as_deref().unwrap_or_default();
+            if let Some(&target_idx) = list.iter().find(|&&idx| idx >= n_nodes) {
+                return Err(NetworkError::InvalidEdge {
+                    node_idx,
+                    edge: name,
+                    target_idx,
+                    step,
+                    reason: MISSING_NODE,
+                });
+            }
+            if coupling.len() < list.len() {
+                return Err(NetworkError::MissingAttribute {
+                    node_idx,
+                    attribute,
+                    step,
+                });
+            }
+        }
+        for (name, list) in [
+            ("precision_parents", &edge.precision_parents),
+            ("precision_children", &edge.precision_children),
+        ] {
+            let list = list.as_deref().unwrap_or_default();
+            if let Some(&target_idx) = list.iter().find(|&&idx| idx >= n_nodes) {
+                return Err(NetworkError::InvalidEdge {
+                    node_idx,
+                    edge: name,
+                    target_idx,
+                    step,
+                    reason: MISSING_NODE,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// [`Self::check_node`] for every step of `steps`. The structure does not
+    /// change while the steps run, so this is done once per pass rather than
+    /// at every time step.
+    pub fn check_steps(&self, steps: &[(usize, UpdateStep)]) -> Result<(), NetworkError> {
+        steps.iter().try_for_each(|&(node_idx, step)| {
+            self.check_node(node_idx, step.name())?;
+            // The unbounded approximation is derived for a single volatility
+            // child and reads only that one.
+            let n_volatility_children = self.edges[node_idx]
+                .volatility_children
+                .as_ref()
+                .map_or(0, Vec::len);
+            if let UpdateStep::Python { slot, .. } = step {
+                if slot >= self.python_steps.len() {
+                    return Err(NetworkError::MissingAttribute {
+                        node_idx,
+                        attribute: "python_steps",
+                        step: step.name(),
+                    });
+                }
+            }
+            let edge = &self.edges[node_idx];
+            if step == UpdateStep::PosteriorPrecisionParent
+                && (edge.value_children.is_some() || n_volatility_children != 0)
+            {
+                return Err(NetworkError::UnsupportedStructure {
+                    node_idx,
+                    step: step.name(),
+                    reason: "a precision parent cannot have value or volatility children",
+                });
+            }
+            if step == UpdateStep::PosteriorContinuousUnbounded && n_volatility_children != 1 {
+                return Err(NetworkError::UnsupportedStructure {
+                    node_idx,
+                    step: step.name(),
+                    reason: "the unbounded update needs exactly one volatility child",
+                });
+            }
+            if step == UpdateStep::PosteriorContinuousUnbounded
+                && self.attributes.fn_ptrs[node_idx]
+                    .volatility_coupling_fn
+                    .is_some()
+            {
+                return Err(NetworkError::UnsupportedStructure {
+                    node_idx,
+                    step: step.name(),
+                    reason: "the unbounded update needs a linear volatility coupling",
+                });
+            }
+            Ok(())
+        })
+    }
+
+    /// The beliefs scanned when running `steps` with `enabled`: those of
+    /// [`DIVERGENCE_FIELDS`] that each node touched by the steps exports,
+    /// built once per run.
+    fn divergence_checks(
+        &self,
+        steps: &[&[(usize, UpdateStep)]],
+        enabled: bool,
+    ) -> Vec<DivergenceCheck> {
+        if !enabled {
+            return Vec::new();
+        }
+        let nodes: std::collections::BTreeSet<usize> = steps
+            .iter()
+            .flat_map(|steps| steps.iter().map(|&(idx, _)| idx))
+            .collect();
+        nodes
+            .into_iter()
+            .flat_map(|node_idx| {
+                let fields = trajectory_fields_for_type(&self.edges[node_idx].node_type);
+                DIVERGENCE_FIELDS
+                    .iter()
+                    .filter(|(attribute, _)| fields.contains(attribute))
+                    .map(move |&(attribute, get)| (node_idx, attribute, get))
+            })
+            .collect()
+    }
+
+    /// Fail with [`NetworkError::Diverged`] if one of the `checks` is not
+    /// finite after time step `time_idx`. The values recorded so far in
+    /// `history` give the context of the error.
+    fn check_diverged(
+        &self,
+        checks: &[DivergenceCheck],
+        time_idx: usize,
+        history: &NodeTrajectories,
+    ) -> Result<(), NetworkError> {
+        for &(node_idx, attribute, get) in checks {
+            let value = get(&self.attributes.states[node_idx]);
+            if value.is_finite() {
+                continue;
+            }
+            let mut recent_values: Vec<f64> = history
+                .nodes
+                .get(node_idx)
+                .and_then(|traj| traj.column(attribute))
+                .map(|column| {
+                    let skip = column.len().saturating_sub(DIVERGENCE_HISTORY);
+                    column.iter().skip(skip).copied().collect()
+                })
+                .unwrap_or_default();
+            recent_values.push(value);
+            return Err(NetworkError::Diverged {
+                time_idx,
+                node_idx,
+                attribute,
+                recent_values,
+            });
+        }
+        Ok(())
+    }
+
+    /// Empty trajectories for every node with room for `n_time` steps,
+    /// recording the network's `record_keys`.
+    fn empty_trajectories(&self, n_time: usize) -> NodeTrajectories {
+        let record_keys = self.record_keys.as_deref();
+        let mut trajectories = NodeTrajectories::with_keys(self.edges.len(), n_time, record_keys);
+        // Lay the vector recordings out up front, so that a run without time
+        // steps still exports them (with zero rows).
+        trajectories.fit_attributes(&self.attributes, record_keys);
+        trajectories
+    }
+
+    /// Run the observations `input_data`, one row per time step.
+    ///
+    /// With `options.conditions`, the parameters named in the condition
+    /// mapping take the values of each step's condition before its
+    /// predictions. A condition missing from the mapping, or one that does
+    /// not name a parameter, runs with the value the network had before the
+    /// run, which is put back afterwards. Switched parameters such as
+    /// `tonic_volatility` are recorded with the trajectories.
+    ///
+    /// With `options.covariates`, the continuous node driven by column `c`
+    /// (`c -> (node_idx, weight)`) has `weight · covariates[t][c]` added to
+    /// its drift rate before the predictions of step `t`. The weights are
+    /// fixed.
+    pub fn input_data(
+        &mut self,
+        input_data: Vec<Vec<f64>>,
+        time_steps: Option<Vec<f64>>,
+        record_trajectories: bool,
+        options: InputDataOptions,
+    ) -> Result<(), NetworkError> {
+        let InputDataOptions {
+            conditions,
+            covariates,
+            pe_gains,
+            check_divergence,
+        } = options;
+        let n_time = input_data.len();
+        let schedule = conditions
+            .as_ref()
+            .map(|(labels, parameters)| ConditionSchedule::new(self, labels, parameters, n_time))
+            .transpose()?;
+        let drive = covariates
+            .as_ref()
+            .map(|(rows, weights)| CovariateDrive::new(self, rows, weights, n_time))
+            .transpose()?;
+        let gains = pe_gains
+            .as_ref()
+            .map(|gains| GainSchedule::new(self, gains, n_time))
+            .transpose()?;
+        let result = self.run_input_data(
+            input_data,
+            time_steps,
+            record_trajectories,
+            check_divergence,
+            &|network, time_idx| {
+                if let Some(schedule) = &schedule {
+                    schedule.apply(network, time_idx);
+                }
+                if let Some(drive) = &drive {
+                    drive.apply(network, time_idx);
+                }
+                if let Some(gains) = &gains {
+                    gains.apply(network, time_idx);
+                }
+            },
+            &mut |_, _| ControlFlow::Continue(()),
+        );
+        if let Some(schedule) = &schedule {
+            schedule.restore(self);
+        }
+        if let Some(drive) = &drive {
+            drive.clear(self);
+        }
+        if let Some(gains) = &gains {
+            gains.clear(self);
+        }
+        result
+    }
+
+    /// [`Network::input_data`] calling `callback` with the network and the
+    /// time index after each step, e.g. to log diagnostics without recording
+    /// every attribute. The callback only gets a shared reference, so it
+    /// cannot change the network. Returning [`ControlFlow::Break`] stops the
+    /// run after that step: the trajectories hold the steps that ran.
+    pub fn input_data_with_callback(
+        &mut self,
+        input_data: Vec<Vec<f64>>,
+        time_steps: Option<Vec<f64>>,
+        record_trajectories: bool,
+        callback: &mut dyn FnMut(&Network, usize) -> ControlFlow<()>,
+    ) -> Result<(), NetworkError> {
+        self.run_input_data(
+            input_data,
+            time_steps,
+            record_trajectories,
+            false,
+            &|_, _| {},
+            callback,
+        )
+    }
+
+    /// The loop of [`Network::input_data`], calling `before_step` with the
+    /// time index before the predictions of each step and `after_step` once
+    /// the step is recorded (stopping the run on [`ControlFlow::Break`]).
+    /// `check_divergence` scans the beliefs as if the network setting were
+    /// on.
+    fn run_input_data(
+        &mut self,
+        input_data: Vec<Vec<f64>>,
+        time_steps: Option<Vec<f64>>,
+        record_trajectories: bool,
+        check_divergence: bool,
+        before_step: &dyn Fn(&mut Self, usize),
+        after_step: &mut dyn FnMut(&Self, usize) -> ControlFlow<()>,
+    ) -> Result<(), NetworkError> {
+        if self.update_sequence.predictions.is_empty() && self.update_sequence.updates.is_empty() {
+            self.set_update_sequence();
+        }
+        self.check_runnable(&self.update_sequence)?;
+        self.check_python_steps(&self.update_sequence)?;
+
+        let n_time = input_data.len();
+        let time_steps = time_steps.unwrap_or_else(|| vec![1.0; n_time]);
+        if time_steps.len() != n_time {
+            return Err(NetworkError::DimensionMismatch {
+                what: "time_steps (one per observation)",
+                expected: n_time,
+                found: time_steps.len(),
+            });
+        }
+        // Checked for every time step before any of them runs, so that a bad
+        // row cannot leave the network half way through the series.
+        for observations in &input_data {
+            check_observations(self, observations)?;
+        }
+        self.check_steps(&self.update_sequence.predictions)?;
+        self.check_steps(&self.update_sequence.updates)?;
+        // Borrowing the sequence while the steps mutate `self` is not possible, so
+        // it is moved out for the duration of the loop and put back afterwards.
+        let mut update_sequence = std::mem::take(&mut self.update_sequence);
+        let check_divergence = check_divergence || self.check_divergence;
+        let mut divergence_checks = self.divergence_checks(
+            &[&update_sequence.predictions, &update_sequence.updates],
+            check_divergence,
+        );
+
+        let mut node_trajectories = NodeTrajectories::default();
+        if record_trajectories {
+            node_trajectories = self.empty_trajectories(n_time);
+        }
+
+        let mut run = || -> Result<(), NetworkError> {
+            for (time_idx, (observations, &time_step)) in
+                input_data.iter().zip(&time_steps).enumerate()
+            {
+                before_step(self, time_idx);
+                belief_propagation(
+                    self,
+                    observations,
+                    &update_sequence.predictions,
+                    &update_sequence.updates,
+                    time_step,
+                )?;
+                // Clusters opened by dp-state nodes change the structure: the
+                // next time step runs a rebuilt sequence.
+                if spawn_clusters(self)? {
+                    self.set_update_sequence();
+                    update_sequence = std::mem::take(&mut self.update_sequence);
+                    self.check_python_steps(&update_sequence)?;
+                    divergence_checks = self.divergence_checks(
+                        &[&update_sequence.predictions, &update_sequence.updates],
+                        check_divergence,
+                    );
+                    if record_trajectories {
+                        node_trajectories
+                            .fit_attributes(&self.attributes, self.record_keys.as_deref());
+                    }
+                }
+                self.check_diverged(&divergence_checks, time_idx, &node_trajectories)?;
+
+                if record_trajectories {
+                    node_trajectories.push(&self.attributes, time_step);
+                }
+                if after_step(self, time_idx).is_break() {
+                    break;
+                }
+            }
+            Ok(())
+        };
+        let result = run();
+        self.update_sequence = update_sequence;
+        result?;
+
+        if record_trajectories {
+            self.node_trajectories = node_trajectories;
+        }
+        Ok(())
+    }
+
+    /// Run a single belief-propagation cycle for one set of `observations`
+    /// (one value per input node) and append the result to the recorded
+    /// trajectories, so that successive calls extend the same history.
+    ///
+    /// The trajectories are started afresh when their node count does not
+    /// match the network, e.g. on the first call or after adding nodes.
+    pub fn step(&mut self, observations: &[f64], time_step: f64) -> Result<(), NetworkError> {
+        let n_nodes_before = self.attributes.states.len();
+        self.run_step(observations, time_step)?;
+
+        let n_nodes = self.attributes.states.len();
+        if self.node_trajectories.nodes.len() == n_nodes_before && n_nodes > n_nodes_before {
+            // Clusters opened by this step extend the recorded history.
+            self.node_trajectories
+                .fit_attributes(&self.attributes, self.record_keys.as_deref());
+        } else if self.node_trajectories.nodes.len() != n_nodes {
+            self.node_trajectories = self.empty_trajectories(0);
+        }
+        self.node_trajectories.push(&self.attributes, time_step);
+        Ok(())
+    }
+
+    /// Run the prediction steps of the cached update sequence (built if
+    /// needed), leaving the observations and posteriors untouched.
+    pub fn run_predictions(&mut self, time_step: f64) -> Result<(), NetworkError> {
+        self.with_update_sequence(|network, sequence| {
+            run_predictions(network, &sequence.predictions, time_step)
+        })
+    }
+
+    /// Write one observation per input node, in `inputs` order, without
+    /// running any update step.
+    pub fn set_observations(&mut self, observations: &[f64]) -> Result<(), NetworkError> {
+        check_observations(self, observations)?;
+        set_observations(self, observations)
+    }
+
+    /// Run the prediction-error and posterior steps of the cached update
+    /// sequence (built if needed).
+    pub fn run_updates(&mut self, time_step: f64) -> Result<(), NetworkError> {
+        self.with_update_sequence(|network, sequence| {
+            run_updates(network, &sequence.updates, time_step)
+        })
+    }
+
+    /// [`Network::run_predictions`], [`Network::set_observations`] and
+    /// [`Network::run_updates`] in a row: one belief-propagation cycle, without
+    /// recording it (see [`Network::step`] for that).
+    pub fn run_step(&mut self, observations: &[f64], time_step: f64) -> Result<(), NetworkError> {
+        self.with_update_sequence(|network, sequence| {
+            network.check_runnable(sequence)?;
+            check_observations(network, observations)?;
+            belief_propagation(
+                network,
+                observations,
+                &sequence.predictions,
+                &sequence.updates,
+                time_step,
+            )
+        })
+    }
+
+    /// Snapshot the current attributes, the metadata and the number of
+    /// recorded time steps.
+    pub fn checkpoint(&self) -> StateSnapshot {
+        StateSnapshot {
+            attributes: self.attributes.clone(),
+            metadata: self.metadata.clone(),
+            n_time: self.node_trajectories.time_steps.len(),
+        }
+    }
+
+    /// Put back the attributes and metadata of `snapshot` and drop the time
+    /// steps recorded after it, so that [`Network::step`] or
+    /// [`Network::input_data`] continue from the checkpoint. The network must
+    /// have the same nodes.
+    pub fn restore(&mut self, snapshot: &StateSnapshot) -> Result<(), NetworkError> {
+        let n_nodes = self.attributes.states.len();
+        if snapshot.attributes.states.len() != n_nodes {
+            return Err(NetworkError::DimensionMismatch {
+                what: "nodes in the snapshot",
+                expected: n_nodes,
+                found: snapshot.attributes.states.len(),
+            });
+        }
+        self.restore_attributes(snapshot.clone());
+        self.node_trajectories.truncate(snapshot.n_time);
+        Ok(())
+    }
+
+    /// Put back the attributes and metadata of a snapshot of this network,
+    /// leaving the trajectories as they are.
+    pub(crate) fn restore_attributes(&mut self, snapshot: StateSnapshot) {
+        self.attributes = snapshot.attributes;
+        self.metadata = snapshot.metadata;
+    }
+
+    /// Every scalar and vector attribute of every node, by name, and their
+    /// metadata: the content of [`Network::checkpoint`] as plain data that
+    /// can be saved and loaded into another network with the same nodes.
+    pub fn get_state(&self) -> NetworkState {
+        let floats = self
+            .attributes
+            .states
+            .iter()
+            .map(|state| {
+                STATE_FIELDS
+                    .iter()
+                    .filter_map(|&field| Some((field.to_string(), state.get(field)?)))
+                    .collect()
+            })
+            .collect();
+        let vectors = self
+            .attributes
+            .vectors
+            .iter()
+            .map(|vectors| {
+                vectors
+                    .fields()
+                    .into_iter()
+                    .map(|(field, values)| (field.to_string(), values.clone()))
+                    .collect()
+            })
+            .collect();
+        let metadata = (0..self.n_nodes())
+            .map(|node_idx| {
+                self.metadata
+                    .get(&node_idx)
+                    .map(|labels| labels.clone().into_iter().collect())
+                    .unwrap_or_default()
+            })
+            .collect();
+        NetworkState {
+            floats,
+            vectors,
+            metadata,
+        }
+    }
+
+    /// Overwrite the attributes named in `state` (see [`Network::get_state`]).
+    /// The topology is assumed unchanged: `state` must hold one entry per
+    /// node and the coupling vectors, one entry per edge, must keep their
+    /// length; the other vectors, which the updates resize, are taken as they
+    /// are (e.g. the `learning_rate_parents` of a network that has not run
+    /// yet). Fields missing from `state`
+    /// are left as they are, and the trajectories are not touched. The
+    /// metadata is replaced unless `state` has none (a state saved without
+    /// it). Nothing is changed if `state` is rejected.
+    pub fn load_state(&mut self, state: &NetworkState) -> Result<(), NetworkError> {
+        let n_nodes = self.attributes.states.len();
+        let n_metadata = if state.metadata.is_empty() {
+            n_nodes
+        } else {
+            state.metadata.len()
+        };
+        for found in [state.floats.len(), state.vectors.len(), n_metadata] {
+            if found != n_nodes {
+                return Err(NetworkError::DimensionMismatch {
+                    what: "nodes in the state",
+                    expected: n_nodes,
+                    found,
+                });
+            }
+        }
+
+        let mut snapshot = self.checkpoint();
+        let attributes = &mut snapshot.attributes;
+        for (node_idx, (floats, vectors)) in state.floats.iter().zip(&state.vectors).enumerate() {
+            let invalid_parameter = |parameter: &str, accepted| NetworkError::InvalidParameter {
+                node_type: self.edges[node_idx].node_type.clone(),
+                parameter: parameter.to_string(),
+                accepted,
+            };
+            for (field, &value) in floats {
+                *attributes.states[node_idx]
+                    .get_mut(field)
+                    .ok_or_else(|| invalid_parameter(field, STATE_FIELDS))? = value;
+            }
+            for (field, values) in vectors {
+                let current = attributes.vectors[node_idx]
+                    .get_mut(field)
+                    .ok_or_else(|| invalid_parameter(field, STATE_VECTOR_FIELDS))?;
+                let per_edge = field.starts_with("value_coupling_")
+                    || field.starts_with("volatility_coupling_");
+                if per_edge && values.len() != current.len() {
+                    return Err(NetworkError::DimensionMismatch {
+                        what: "entries of a vector attribute in the state",
+                        expected: current.len(),
+                        found: values.len(),
+                    });
+                }
+                current.clone_from(values);
+            }
+        }
+        if !state.metadata.is_empty() {
+            snapshot.metadata = state
+                .metadata
+                .iter()
+                .enumerate()
+                .filter(|(_, labels)| !labels.is_empty())
+                .map(|(node_idx, labels)| (node_idx, labels.clone().into_iter().collect()))
+                .collect();
+        }
+        self.restore_attributes(snapshot);
+        Ok(())
+    }
+
+    /// Forecast `horizon` time steps ahead of the current state without
+    /// observations: each step runs the prediction steps only and takes the
+    /// prediction as the next state. Returns the forecast trajectories; the network's
+    /// attributes are restored afterwards. `time_steps` defaults to ones.
+    pub fn forecast(
+        &mut self,
+        horizon: usize,
+        time_steps: Option<&[f64]>,
+    ) -> Result<NodeTrajectories, NetworkError> {
+        if let Some(time_steps) = time_steps {
+            if time_steps.len() != horizon {
+                return Err(NetworkError::DimensionMismatch {
+                    what: "time steps (one per forecast step)",
+                    expected: horizon,
+                    found: time_steps.len(),
+                });
+            }
+        }
+        let saved = self.checkpoint();
+        let mut trajectories = self.empty_trajectories(horizon);
+        let time_steps = (0..horizon).map(|t| time_steps.map_or(1.0, |time_steps| time_steps[t]));
+        let result = self.propagate_prior(time_steps, &mut trajectories);
+        self.restore_attributes(saved);
+        result.map(|()| trajectories)
+    }
+
+    /// Run the prior dynamics alone for `n_time` steps of `time_step`: the
+    /// prediction steps only, each prediction becoming the next state as in
+    /// [`Network::forecast`]. Unlike `forecast`, the network keeps the final
+    /// state and the steps replace the recorded trajectories.
+    pub fn run_prior(&mut self, n_time: usize, time_step: f64) -> Result<(), NetworkError> {
+        let mut trajectories = self.empty_trajectories(n_time);
+        self.propagate_prior(std::iter::repeat_n(time_step, n_time), &mut trajectories)?;
+        self.node_trajectories = trajectories;
+        Ok(())
+    }
+
+    /// Run the prediction steps once per time step, take each prediction as
+    /// the next state (`mean ← expected_mean`, `precision ←
+    /// expected_precision`) and record it into `trajectories`.
+    fn propagate_prior(
+        &mut self,
+        time_steps: impl Iterator<Item = f64>,
+        trajectories: &mut NodeTrajectories,
+    ) -> Result<(), NetworkError> {
+        self.with_update_sequence(|network, sequence| {
+            for time_step in time_steps {
+                run_predictions(network, &sequence.predictions, time_step)?;
+                for &(node_idx, _) in &sequence.predictions {
+                    let state = &mut network.attributes.states[node_idx];
+                    state.mean = state.expected_mean;
+                    state.precision = state.expected_precision;
+                    if network.edges[node_idx].node_type == "volatile-state" {
+                        state.mean_vol = state.expected_mean_vol;
+                        state.precision_vol = state.expected_precision_vol;
+                    }
+                }
+                trajectories.push(&network.attributes, time_step);
+            }
+            Ok(())
+        })
+    }
+
+    /// Check that the network can take observations: it needs at least one
+    /// input node and a non-empty update `sequence`.
+    fn check_runnable(&self, sequence: &UpdateSequence) -> Result<(), NetworkError> {
+        let reason = if self.edges.is_empty() {
+            "the network has no nodes, add at least one input node first"
+        } else if self.inputs.is_empty() {
+            "the network has no input node, at least one is required"
+        } else if sequence.predictions.is_empty() && sequence.updates.is_empty() {
+            "the update sequence is empty, no node of the network can be updated"
+        } else {
+            return Ok(());
+        };
+        Err(NetworkError::NotRunnable { reason })
+    }
+
+    /// Validate the cached update sequence (built if needed) and run `f` with
+    /// it. The sequence is moved out while `f` mutates the network and put
+    /// back afterwards, whatever the outcome.
+    fn with_update_sequence(
+        &mut self,
+        f: impl FnOnce(&mut Self, &UpdateSequence) -> Result<(), NetworkError>,
+    ) -> Result<(), NetworkError> {
+        if self.update_sequence.predictions.is_empty() && self.update_sequence.updates.is_empty() {
+            self.set_update_sequence();
+        }
+        self.check_steps(&self.update_sequence.predictions)?;
+        self.check_steps(&self.update_sequence.updates)?;
+        self.check_python_steps(&self.update_sequence)?;
+
+        let update_sequence = std::mem::take(&mut self.update_sequence);
+        let result = f(self, &update_sequence);
+        self.update_sequence = update_sequence;
+        result?;
+        // Clusters opened by dp-state nodes reset the sequence, which the next
+        // call rebuilds.
+        spawn_clusters(self)?;
+        Ok(())
+    }
+
+    pub fn add_layer(
+        &mut self,
+        size: usize,
+        kind: &str,
+        value_children: Option<Vec<usize>>,
+        coupling_strengths: f64,
+        coupling_fn: Option<String>,
+        additional_parameters: Option<HashMap<String, f64>>,
+        add_constant_input: bool,
+    ) -> Result<(), NetworkError> {
+        let n_nodes_before = self.edges.len();
+
+        let children: Vec<usize> = match value_children {
+            Some(vc) => vc,
+            None => {
+                // If layers exist, connect to the last layer's nodes;
+                // otherwise connect to all leaf nodes (nodes without parents).
+                if let Some(last_layer) = self.layers.last() {
+                    last_layer.clone()
+                } else {
+                    self.leafs.clone()
+                }
+            }
+        }
+        .into_iter()
+        .filter(|&idx| self.edges[idx].node_type != "constant-state")
+        .collect();
+
+        let additional_parameters =
+            if parameters_for_type(kind).contains(&"autoconnection_strength") {
+                let mut params = additional_parameters.unwrap_or_default();
+                params
+                    .entry("autoconnection_strength".into())
+                    .or_insert(0.0);
+                Some(params)
+            } else {
+                additional_parameters
+            };
+
+        for _ in 0..size {
+            let vc = IntOrList::List(children.clone());
+            self.add_nodes(
+                kind,
+                1,
+                AddNodesOptions {
+                    value_children: Some(vc),
+                    coupling_fn: coupling_fn.clone(),
+                    coupling: Some(coupling_strengths.into()),
+                    additional_parameters: additional_parameters.clone(),
+                    ..Default::default()
+                },
+            )?;
+        }
+
+        if add_constant_input {
+            let non_constant_children: Vec<usize> = children
+                .iter()
+                .filter(|&&idx| self.edges[idx].node_type != "constant-state")
+                .copied()
+                .collect();
+
+            if !non_constant_children.is_empty() {
+                let vc = IntOrList::List(non_constant_children);
+                self.add_nodes(
+                    "constant-state",
+                    1,
+                    AddNodesOptions {
+                        value_children: Some(vc),
+                        coupling_fn: coupling_fn.clone(),
+                        ..Default::default()
+                    },
+                )?;
+            }
+        }
+
+        let new_layer: Vec<usize> = (n_nodes_before..self.edges.len()).collect();
+        self.layers.push(new_layer);
+        Ok(())
+    }
+
+    pub fn add_layer_stack(
+        &mut self,
+        layer_sizes: Vec<usize>,
+        kind: &str,
+        value_children: Option<Vec<usize>>,
+        coupling_strengths: f64,
+        coupling_fn: Option<String>,
+        additional_parameters: Option<HashMap<String, f64>>,
         add_constant_input: bool,
-    ) {
+    ) -> Result<(), NetworkError> {
         for (i, &size) in layer_sizes.iter().enumerate() {
             if i == 0 {
                 self.add_layer(
@@ -990,7 +4013,7 @@ impl Network {
                     coupling_fn.clone(),
                     additional_parameters.clone(),
                     add_constant_input,
-                );
+                )?;
             } else {
                 self.add_layer(
                     size,
@@ -1000,9 +4023,10 @@ impl Network {
                     coupling_fn.clone(),
                     additional_parameters.clone(),
                     add_constant_input,
-                );
+                )?;
             }
         }
+        Ok(())
     }
 
     /// Train the network on input/output pairs.
@@ -1017,12 +4041,11 @@ impl Network {
     /// * `lr` - Gradient application. `Some(f)` sets a fixed learning rate on all
     ///   non-input nodes. `None` triggers the Adam optimiser (equivalent to
     ///   `lr="adam"` from Python); the Adam step size is taken from
-    ///   `params["lr"]` (default 1e-3).
+    ///   `options.params["lr"]` (default 1e-3).
     /// * `record_trajectories` - When `true`, stores the full state history for
     ///   every node at each time step, accessible via `node_trajectories`.
-    /// * `params` - Optional dictionary of Adam hyper-parameters (only used when
-    ///   `lr == None`): `beta1` (default 0.9), `beta2` (default 0.999),
-    ///   `epsilon` (default 1e-8), and `lr` (default 1e-3, the Adam step size).
+    /// * `options` - Adam hyper-parameters and divergence checking (see
+    ///   [`FitOptions`]).
     pub fn fit(
         &mut self,
         x: &[Vec<f64>],
@@ -1031,13 +4054,40 @@ impl Network {
         inputs_y_idxs: &[usize],
         lr: Option<f64>,
         record_trajectories: bool,
-        params: Option<&HashMap<String, f64>>,
         learning_kind: &str,
-    ) {
+        options: FitOptions,
+    ) -> Result<(), NetworkError> {
+        let params = options.params.as_ref();
         if self.update_sequence.predictions.is_empty() && self.update_sequence.updates.is_empty() {
             self.set_update_sequence();
         }
 
+        if y.len() != x.len() {
+            return Err(NetworkError::DimensionMismatch {
+                what: "rows of y (one per row of x)",
+                expected: x.len(),
+                found: y.len(),
+            });
+        }
+        for (what, rows, idxs) in [
+            ("columns of x (one per predictor node)", x, inputs_x_idxs),
+            ("columns of y (one per observed node)", y, inputs_y_idxs),
+        ] {
+            if let Some(row) = rows.iter().find(|row| row.len() != idxs.len()) {
+                return Err(NetworkError::DimensionMismatch {
+                    what,
+                    expected: idxs.len(),
+                    found: row.len(),
+                });
+            }
+        }
+        for &node_idx in inputs_x_idxs {
+            self.check_node(node_idx, "set_predictors")?;
+        }
+        for &node_idx in inputs_y_idxs {
+            self.check_node(node_idx, "set_observation")?;
+        }
+
         // Set learning_kind on all non-input nodes
         for (node_idx, edge) in self.edges.iter_mut().enumerate() {
             if !inputs_x_idxs.contains(&node_idx) {
@@ -1078,121 +4128,916 @@ impl Network {
             self.adam_state = None;
         }
 
-        let learning_seq = build_learning_sequence(
-            &self.update_sequence.predictions,
-            &self.update_sequence.updates,
-            inputs_x_idxs,
-            &self.edges,
-        );
+        let learning_seq = self.learning_sequence(inputs_x_idxs);
+        self.check_steps(&learning_seq.prediction_steps)?;
+        self.check_steps(&learning_seq.update_steps)?;
+        self.check_steps(&learning_seq.learning_steps)?;
+
+        let n_time = x.len();
+        let time_step = 1.0;
+
+        let mut node_trajectories = NodeTrajectories::default();
+        if record_trajectories {
+            node_trajectories = self.empty_trajectories(n_time);
+        }
+        let divergence_checks = self.divergence_checks(
+            &[
+                &learning_seq.prediction_steps,
+                &learning_seq.update_steps,
+                &learning_seq.learning_steps,
+            ],
+            options.check_divergence || self.check_divergence,
+        );
+
+        for t in 0..n_time {
+            for (i, &node_idx) in inputs_x_idxs.iter().enumerate() {
+                set_predictors(self, node_idx, x[t][i]);
+            }
+
+            for &(idx, step) in &learning_seq.prediction_steps {
+                step.call(self, idx, time_step)?;
+            }
+
+            for (i, &node_idx) in inputs_y_idxs.iter().enumerate() {
+                set_observation(self, node_idx, y[t][i]);
+            }
+
+            for &(idx, step) in &learning_seq.update_steps {
+                step.call(self, idx, time_step)?;
+            }
+
+            // Increment Adam timestep once per iteration (before learning steps)
+            if let Some(ref mut adam) = self.adam_state {
+                adam.increment_timestep();
+            }
+
+            for &(idx, step) in &learning_seq.learning_steps {
+                step.call(self, idx, time_step)?;
+            }
+            set_surprise(self);
+            set_entropy(self);
+            self.check_diverged(&divergence_checks, t, &node_trajectories)?;
+
+            if record_trajectories {
+                node_trajectories.push(&self.attributes, time_step);
+            }
+        }
+
+        if record_trajectories {
+            self.node_trajectories = node_trajectories;
+        }
+        Ok(())
+    }
+
+    pub fn predict(
+        &self,
+        x: &[Vec<f64>],
+        inputs_x_idxs: &[usize],
+        inputs_y_idxs: &[usize],
+    ) -> Result<Vec<Vec<f64>>, NetworkError> {
+        let time_step = 1.0;
+
+        if let Some(row) = x.iter().find(|row| row.len() != inputs_x_idxs.len()) {
+            return Err(NetworkError::DimensionMismatch {
+                what: "columns of x (one per predictor node)",
+                expected: inputs_x_idxs.len(),
+                found: row.len(),
+            });
+        }
+        for &node_idx in inputs_x_idxs {
+            self.check_node(node_idx, "set_predictors")?;
+        }
+        for &node_idx in inputs_y_idxs {
+            self.check_node(node_idx, "predict")?;
+        }
+
+        let prediction_steps: Vec<(usize, UpdateStep)> = self
+            .update_sequence
+            .predictions
+            .iter()
+            .filter(|(idx, _)| !inputs_x_idxs.contains(idx))
+            .cloned()
+            .collect();
+        self.check_steps(&prediction_steps)?;
+
+        // Clone unchanging parts once (edges, vectors, fn_ptrs don't change
+        // during prediction-only passes).
+        let mut temp = Network {
+            attributes: self.attributes.clone(),
+            edges: self.edges.clone(),
+            inputs: Vec::new(),
+            volatility_updates: String::new(),
+            mean_field_updates: false,
+            update_sequence: UpdateSequence {
+                predictions: Vec::new(),
+                updates: Vec::new(),
+            },
+            edge_lookup: self.edge_lookup.clone(),
+            node_trajectories: NodeTrajectories::default(),
+            layers: Vec::new(),
+            adam_state: None,
+            roots: Vec::new(),
+            leafs: Vec::new(),
+            max_posterior_precision: self.max_posterior_precision,
+            precision_clipping_value: self.precision_clipping_value,
+            python_steps: self.python_steps.clone(),
+            record_keys: None,
+            check_divergence: false,
+            negative_precision: self.negative_precision,
+            entropy_source: self.entropy_source,
+            step_error: None,
+            pending_clusters: Vec::new(),
+            metadata: HashMap::new(),
+        };
+
+        x.iter()
+            .map(|x_row| {
+                // Only reset states per sample (plain f64 fields, no allocation).
+                temp.attributes.states.clone_from(&self.attributes.states);
+
+                for (i, &node_idx) in inputs_x_idxs.iter().enumerate() {
+                    set_predictors(&mut temp, node_idx, x_row[i]);
+                }
+
+                for &(idx, step) in &prediction_steps {
+                    step.call(&mut temp, idx, time_step)?;
+                }
+
+                Ok(inputs_y_idxs
+                    .iter()
+                    .map(|&idx| temp.attributes.states[idx].expected_mean)
+                    .collect())
+            })
+            .collect()
+    }
+
+    /// Free energy of the last recorded trajectory, split into accuracy and
+    /// complexity (see [`crate::utils::free_energy::free_energy`]). Fails with
+    /// [`NetworkError::UnrecordedKey`] when a column it reads was not recorded.
+    pub fn get_free_energy(&self) -> Result<FreeEnergy, NetworkError> {
+        free_energy(self)
+    }
+
+    /// Recorded surprise of every node summed over time, by node index: the
+    /// surprise of the observations for the inputs and of the posterior mean
+    /// under the prediction for the state nodes (see
+    /// [`crate::utils::surprise::node_surprise`]). Shows which level of the
+    /// hierarchy accounts for the total surprise.
+    pub fn surprise_by_node(&self) -> Result<Vec<f64>, NetworkError> {
+        surprise_by_node(self)
+    }
+
+    /// Precision `node_idx` settles at under its current volatility and
+    /// children's precisions, solved in closed form (see
+    /// [`crate::utils::steady_state::steady_state_precision`]).
+    pub fn steady_state_precision(&self, node_idx: usize) -> Result<f64, NetworkError> {
+        steady_state_precision(self, node_idx)
+    }
+
+    /// Out-of-sample surprise of `input_data` for each of `train_sizes`: the
+    /// surprise of the `horizon` steps after the first `k`, predicted without
+    /// learning from them (see [`crate::utils::surprise::cv_surprise`]). The
+    /// network is left as it was; networks with dp-state nodes are rejected.
+    pub fn cv_surprise(
+        &mut self,
+        input_data: &[Vec<f64>],
+        time_steps: Option<&[f64]>,
+        train_sizes: &[usize],
+        horizon: usize,
+    ) -> Result<Vec<f64>, NetworkError> {
+        cv_surprise(self, input_data, time_steps, train_sizes, horizon)
+    }
+
+    /// Build the network equivalent to the pyhgf (JAX) network `nodes` (see
+    /// [`crate::utils::pyhgf_spec::from_pyhgf_spec`]).
+    pub fn from_pyhgf_spec(
+        nodes: &[PyhgfNode],
+        volatility_updates: &str,
+    ) -> Result<Self, NetworkError> {
+        from_pyhgf_spec(nodes, volatility_updates)
+    }
+
+    /// Describe the network in the pyhgf (JAX) layout (see
+    /// [`crate::utils::pyhgf_spec::to_pyhgf_spec`]).
+    pub fn to_pyhgf_spec(&self) -> Result<Vec<PyhgfNode>, NetworkError> {
+        to_pyhgf_spec(self)
+    }
+
+    /// Build a network from its JSON spec (see [`crate::utils::spec`]). All
+    /// the problems of an invalid spec are reported at once in
+    /// [`NetworkError::InvalidSpec`].
+    pub fn from_spec_str(json: &str) -> Result<Self, NetworkError> {
+        from_spec_str(json)
+    }
+
+    /// The JSON spec of the network: settings, node parameters and edges with
+    /// their couplings. [`Network::from_spec_str`] rebuilds the same network.
+    pub fn to_spec_str(&self) -> String {
+        to_spec_str(self)
+    }
+
+    /// Structure of the network: node kinds, edge counts, update type, inputs
+    /// and coupling functions (see [`ModelSummary`]). Prints as a short text
+    /// report.
+    pub fn model_summary(&self) -> ModelSummary {
+        model_summary(self)
+    }
+
+    /// Summary statistics of the recorded trajectories, one map per node
+    /// index (see [`crate::utils::trajectory_summary::summary`]).
+    pub fn summary(&self) -> HashMap<usize, HashMap<String, f64>> {
+        summary(self)
+    }
+
+    /// Copy of the recorded time steps `start:stop:step`, resolved like a
+    /// Python slice: negative indices count from the end and `stop = None`
+    /// means the last recorded step. Out-of-range bounds, `start > stop` or
+    /// a zero step are rejected instead of being clamped.
+    pub fn get_node_trajectories_slice(
+        &self,
+        start: isize,
+        stop: Option<isize>,
+        step: usize,
+    ) -> Result<NodeTrajectories, NetworkError> {
+        let n_time = self.node_trajectories.time_steps.len();
+        let resolve = |idx: isize| {
+            let idx = if idx < 0 { idx + n_time as isize } else { idx };
+            usize::try_from(idx).ok().filter(|&idx| idx <= n_time)
+        };
+        let stop_idx = stop.unwrap_or(n_time as isize);
+        match (resolve(start), resolve(stop_idx)) {
+            (Some(first), Some(last)) if first <= last && step > 0 => {
+                let rows: Vec<usize> = (first..last).step_by(step).collect();
+                Ok(self.node_trajectories.select(&rows))
+            }
+            _ => Err(NetworkError::InvalidTimeRange {
+                start,
+                stop: stop_idx,
+                step,
+                n_time,
+            }),
+        }
+    }
+
+    /// Total surprise of the recorded observations, `-accuracy` of
+    /// [`Network::get_free_energy`]; fails with [`NetworkError::UnrecordedKey`]
+    /// when `record_keys` left out a column it reads.
+    fn recorded_surprise(&self) -> Result<f64, NetworkError> {
+        Ok(-free_energy(self)?.accuracy)
+    }
+
+    /// Fail for networks with a dp-state node: the clusters it opens during a
+    /// run add nodes that [`Network::restore`] cannot take back, so `step`
+    /// cannot replay the data from a checkpoint.
+    pub(crate) fn reject_clusters(&self, step: &'static str) -> Result<(), NetworkError> {
+        match self.edges.iter().position(|edge| edge.node_type == "dp-state") {
+            Some(node_idx) => Err(NetworkError::UnsupportedStructure {
+                node_idx,
+                step,
+                reason: "dp-state nodes open clusters that a replay cannot take back",
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Copy of the network without its recorded trajectories, for runs that
+    /// must leave the network itself untouched.
+    fn scratch(&self) -> Network {
+        Network {
+            attributes: self.attributes.clone(),
+            edges: self.edges.clone(),
+            inputs: self.inputs.clone(),
+            volatility_updates: self.volatility_updates.clone(),
+            mean_field_updates: self.mean_field_updates,
+            update_sequence: self.update_sequence.clone(),
+            edge_lookup: self.edge_lookup.clone(),
+            node_trajectories: NodeTrajectories::default(),
+            layers: self.layers.clone(),
+            adam_state: self.adam_state.clone(),
+            roots: self.roots.clone(),
+            leafs: self.leafs.clone(),
+            max_posterior_precision: self.max_posterior_precision,
+            precision_clipping_value: self.precision_clipping_value,
+            python_steps: self.python_steps.clone(),
+            record_keys: self.record_keys.clone(),
+            check_divergence: self.check_divergence,
+            negative_precision: self.negative_precision,
+            entropy_source: self.entropy_source,
+            step_error: self.step_error.clone(),
+            pending_clusters: self.pending_clusters.clone(),
+            metadata: self.metadata.clone(),
+        }
+    }
+
+    /// Central finite-difference gradient of the total surprise of
+    /// `input_data` with respect to every value coupling, keyed by
+    /// `(parent_idx, child_idx)`.
+    ///
+    /// Every run starts from the current attributes, with one coupling moved
+    /// by ±`eps`. The runs use a copy of the network, which is left
+    /// unchanged. They are recorded with the network's `record_keys`, which
+    /// must keep the columns of the free energy. `eps` must be finite and
+    /// positive, and networks with dp-state nodes are rejected.
+    pub fn coupling_sensitivity(
+        &self,
+        input_data: &[Vec<f64>],
+        time_steps: Option<&[f64]>,
+        eps: f64,
+    ) -> Result<BTreeMap<(usize, usize), f64>, NetworkError> {
+        if !(eps.is_finite() && eps > 0.0) {
+            return Err(NetworkError::InvalidValue {
+                parameter: "eps",
+                value: eps.to_string(),
+                accepted: "a finite positive step",
+            });
+        }
+        self.reject_clusters("coupling_sensitivity")?;
+        let initial = self.checkpoint();
+        let mut scratch = self.scratch();
+        let edges: Vec<(usize, usize, f64)> = self
+            .edges
+            .iter()
+            .enumerate()
+            .flat_map(|(child_idx, edge)| {
+                let couplings = &self.attributes.vectors[child_idx].value_coupling_parents;
+                edge.value_parents
+                    .iter()
+                    .flatten()
+                    .zip(couplings)
+                    .map(move |(&parent_idx, &coupling)| (parent_idx, child_idx, coupling))
+            })
+            .collect();
+
+        let mut run = |(parent_idx, child_idx, coupling): (usize, usize, f64)| {
+            scratch.restore_attributes(initial.clone());
+            scratch
+                .set_coupling(parent_idx, child_idx, coupling)
+                .map_err(|_| NetworkError::InvalidEdge {
+                    node_idx: child_idx,
+                    edge: "value_parents",
+                    target_idx: parent_idx,
+                    step: "coupling_sensitivity",
+                    reason: "has no coupling entry for node",
+                })?;
+            scratch.input_data(
+                input_data.to_vec(),
+                time_steps.map(<[f64]>::to_vec),
+                true,
+                InputDataOptions::default(),
+            )?;
+            scratch.recorded_surprise()
+        };
+
+        let mut sensitivity = BTreeMap::new();
+        for (parent_idx, child_idx, coupling) in edges {
+            let above = run((parent_idx, child_idx, coupling + eps))?;
+            let below = run((parent_idx, child_idx, coupling - eps))?;
+            sensitivity.insert((parent_idx, child_idx), (above - below) / (2.0 * eps));
+        }
+        Ok(sensitivity)
+    }
+
+    /// Sequence run by [`Network::fit`] when `inputs_x_idxs` are the predictor
+    /// nodes: the update sequence without the predictors' steps, plus one
+    /// learning step per learnable prediction error.
+    pub fn learning_sequence(&self, inputs_x_idxs: &[usize]) -> LearningSequence {
+        let drift_learners: Vec<usize> = self
+            .attributes
+            .states
+            .iter()
+            .enumerate()
+            .filter(|(_, state)| !state.tonic_drift_lr.is_nan())
+            .map(|(node_idx, _)| node_idx)
+            .collect();
+        build_learning_sequence(
+            &self.update_sequence.predictions,
+            &self.update_sequence.updates,
+            inputs_x_idxs,
+            &self.edges,
+            &drift_learners,
+        )
+    }
+
+    /// Recorded effective precision γ = Ω · π̂ of a node, where Ω is the
+    /// predicted volatility and π̂ the predicted precision of the step.
+    ///
+    /// γ is the weight the node's prediction error carries in the
+    /// volatility-coupling posterior update of its volatility parents. For a
+    /// volatile-state node the second series is `effective_precision_vol`, the
+    /// same quantity for the implicit volatility level; it is `None` for every
+    /// other kind. Both series are empty when no trajectory was recorded.
+    pub fn get_effective_precision(
+        &self,
+        node_idx: usize,
+    ) -> Result<(Vec<f64>, Option<Vec<f64>>), NetworkError> {
+        let edges = self.edges.get(node_idx).ok_or(NetworkError::MissingNode {
+            node_idx,
+            step: "get_effective_precision",
+        })?;
+        let is_volatile = edges.node_type == "volatile-state";
+        match self.node_trajectories.nodes.get(node_idx) {
+            Some(traj) => Ok((
+                traj.effective_precision().to_vec(),
+                is_volatile.then(|| traj.effective_precision_vol().to_vec()),
+            )),
+            None => Ok((Vec::new(), is_volatile.then(Vec::new))),
+        }
+    }
+
+    /// Recorded entropy of a node, in nats, one entry per time step (see
+    /// [`Network::entropy_source`]). Empty when no trajectory was recorded.
+    pub fn get_entropy(&self, node_idx: usize) -> Result<Vec<f64>, NetworkError> {
+        if node_idx >= self.edges.len() {
+            return Err(NetworkError::MissingNode {
+                node_idx,
+                step: "get_entropy",
+            });
+        }
+        match self.node_trajectories.nodes.get(node_idx) {
+            Some(traj) => traj
+                .column("entropy")
+                .map(|entropy| entropy.to_vec())
+                .ok_or_else(|| NetworkError::UnrecordedKey {
+                    node_idx,
+                    key: "entropy".to_string(),
+                }),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Keys recorded for `node_idx`, in the order of `node_trajectories`.
+    /// Empty when nothing was recorded yet.
+    pub fn recorded_keys(&self, node_idx: usize) -> Result<Vec<&'static str>, NetworkError> {
+        self.recorded_keys_for(node_idx, "recorded_keys")
+    }
 
-        let n_time = x.len();
-        let time_step = 1.0;
+    fn recorded_keys_for(
+        &self,
+        node_idx: usize,
+        step: &'static str,
+    ) -> Result<Vec<&'static str>, NetworkError> {
+        let edges = self
+            .edges
+            .get(node_idx)
+            .ok_or(NetworkError::MissingNode { node_idx, step })?;
+        Ok(self
+            .node_trajectories
+            .nodes
+            .get(node_idx)
+            .map_or_else(Vec::new, |traj| traj.keys(&edges.node_type)))
+    }
 
-        let mut node_trajectories = NodeTrajectories { nodes: Vec::new() };
+    /// The recorded trajectory of `key` for `node_idx` alone, without
+    /// exporting the other fields.
+    pub fn get_trajectory(
+        &self,
+        node_idx: usize,
+        key: &str,
+    ) -> Result<RecordedTrajectory<'_>, NetworkError> {
+        if !self
+            .recorded_keys_for(node_idx, "get_trajectory")?
+            .contains(&key)
+        {
+            return Err(NetworkError::UnrecordedKey {
+                node_idx,
+                key: key.to_string(),
+            });
+        }
+        let traj = &self.node_trajectories.nodes[node_idx];
+        Ok(match traj.column(key) {
+            Some(column) => RecordedTrajectory::Scalar(column),
+            None => RecordedTrajectory::Vector(traj.vector(key).expect("recorded key")),
+        })
+    }
 
-        if record_trajectories {
-            for _ in 0..self.attributes.states.len() {
-                node_trajectories
-                    .nodes
-                    .push(NodeTrajectory::with_capacity(n_time));
-            }
+    /// Predictive interval of `node_idx` at every recorded time step:
+    /// `expected_mean ∓ z / sqrt(expected_precision)`, as the lower and upper
+    /// bounds. `z = 1.96` gives the 95% interval of the Gaussian prediction.
+    /// Both fields must have been recorded.
+    pub fn predictive_intervals(
+        &self,
+        node_idx: usize,
+        z: f64,
+    ) -> Result<(Vec<f64>, Vec<f64>), NetworkError> {
+        let keys = self.recorded_keys_for(node_idx, "predictive_intervals")?;
+        if let Some(&key) = ["expected_mean", "expected_precision"]
+            .iter()
+            .find(|key| !keys.contains(key))
+        {
+            return Err(NetworkError::UnrecordedKey {
+                node_idx,
+                key: key.to_string(),
+            });
         }
+        let traj = &self.node_trajectories.nodes[node_idx];
+        Ok(traj
+            .expected_mean()
+            .iter()
+            .zip(traj.expected_precision().iter())
+            .map(|(&mean, &precision)| {
+                let half_width = z / precision.sqrt();
+                (mean - half_width, mean + half_width)
+            })
+            .unzip())
+    }
 
-        for t in 0..n_time {
-            for (i, &node_idx) in inputs_x_idxs.iter().enumerate() {
-                set_predictors(self, node_idx, x[t][i]);
-            }
+    /// Simulate `n_steps` time steps of observations and latent states from
+    /// the network (see [`crate::utils::sample::sample`]). `time_steps`
+    /// defaults to ones; `seed` makes the draws reproducible;
+    /// `observation_noise` is the standard deviation of the Gaussian noise
+    /// added to the observations (0 for none).
+    pub fn sample(
+        &mut self,
+        n_steps: usize,
+        time_steps: Option<&[f64]>,
+        seed: Option<u64>,
+        observation_noise: f64,
+    ) -> Result<Samples, NetworkError> {
+        sample(self, n_steps, time_steps, seed, observation_noise)
+    }
 
-            for &(idx, step) in &learning_seq.prediction_steps {
-                step.call(self, idx, time_step);
-            }
+    /// Filter `observations` while drawing the responses of the softmax-state
+    /// input nodes from their predictions instead of reading them (see
+    /// [`crate::utils::sample::simulate_responses`]). Returns the simulated
+    /// choices and records the belief trajectories.
+    pub fn simulate_responses(
+        &mut self,
+        observations: &[Vec<f64>],
+        time_steps: Option<&[f64]>,
+        seed: Option<u64>,
+    ) -> Result<Vec<Vec<f64>>, NetworkError> {
+        simulate_responses(self, observations, time_steps, seed)
+    }
 
-            for (i, &node_idx) in inputs_y_idxs.iter().enumerate() {
-                set_observation(self, node_idx, y[t][i]);
-            }
+    /// Learning rate of every node that has one, as `(node_idx, lr)` pairs in
+    /// node order. `fit` sets it on every node that is not a predictor.
+    pub fn get_learning_rates(&self) -> Vec<(usize, f64)> {
+        self.attributes
+            .states
+            .iter()
+            .enumerate()
+            .filter(|(_, state)| !state.lr.is_nan())
+            .map(|(node_idx, state)| (node_idx, state.lr))
+            .collect()
+    }
 
-            for &(idx, step) in &learning_seq.update_steps {
-                step.call(self, idx, time_step);
-            }
+    /// Recorded scalar trajectories as one time-major matrix: row-major values
+    /// and column names `node{node}::{attribute}` (see
+    /// [`crate::utils::trajectory_table::trajectory_matrix`]).
+    pub fn trajectory_matrix(&self) -> (Vec<f64>, Vec<String>) {
+        trajectory_matrix(self)
+    }
 
-            // Increment Adam timestep once per iteration (before learning steps)
-            if let Some(ref mut adam) = self.adam_state {
-                adam.increment_timestep();
-            }
+    /// Write the recorded trajectories to `path` as a CSV table (one row per
+    /// time step, columns as in `to_dataframe_dict`).
+    pub fn write_trajectories_csv(&self, path: &std::path::Path) -> std::io::Result<()> {
+        write_trajectories_csv(self, path)
+    }
 
-            for &(idx, step) in &learning_seq.learning_steps {
-                step.call(self, idx, time_step);
-            }
+    /// Set the value-coupling strength of the edge `parent_idx → child_idx`
+    /// (see [`crate::utils::set_coupling::set_coupling`]).
+    pub fn set_coupling(
+        &mut self,
+        parent_idx: usize,
+        child_idx: usize,
+        coupling: f64,
+    ) -> Result<(), SetCouplingError> {
+        set_coupling(self, parent_idx, child_idx, coupling)
+    }
 
-            if record_trajectories {
-                for (i, state) in self.attributes.states.iter().enumerate() {
-                    node_trajectories.nodes[i].push_state(state);
-                    node_trajectories.nodes[i].push_vectors(&self.attributes.vectors[i]);
-                }
-            }
+    /// Value-coupling strength of the edge `parent_idx → child_idx`, `None`
+    /// when there is no such edge (see
+    /// [`crate::utils::set_coupling::get_coupling`]).
+    pub fn get_coupling(&self, parent_idx: usize, child_idx: usize) -> Option<f64> {
+        get_coupling(self, parent_idx, child_idx)
+    }
+
+    /// Volatility-coupling strength κ of the edge `parent_idx → child_idx`,
+    /// `None` when there is no such edge.
+    pub fn get_volatility_coupling(&self, parent_idx: usize, child_idx: usize) -> Option<f64> {
+        get_volatility_coupling(self, parent_idx, child_idx)
+    }
+
+    /// Set the volatility-coupling strength κ of the edge `parent_idx → child_idx`
+    /// (see [`crate::utils::set_coupling::set_volatility_coupling`]).
+    pub fn set_volatility_coupling(
+        &mut self,
+        parent_idx: usize,
+        child_idx: usize,
+        coupling: f64,
+    ) -> Result<(), SetCouplingError> {
+        set_volatility_coupling(self, parent_idx, child_idx, coupling)
+    }
+
+    /// Hold the value coupling `parent_idx → child_idx` fixed during learning.
+    pub fn freeze_coupling(
+        &mut self,
+        parent_idx: usize,
+        child_idx: usize,
+    ) -> Result<(), NetworkError> {
+        set_coupling_frozen(self, parent_idx, child_idx, true)
+    }
+
+    /// Let `learning_weights` update the value coupling `parent_idx → child_idx`
+    /// again.
+    pub fn unfreeze_coupling(
+        &mut self,
+        parent_idx: usize,
+        child_idx: usize,
+    ) -> Result<(), NetworkError> {
+        set_coupling_frozen(self, parent_idx, child_idx, false)
+    }
+
+    /// Delay the value coupling `parent_idx → child_idx` by `lag` steps (0 or
+    /// 1). A lagged parent drives the child's drift with its posterior mean
+    /// one step earlier than an ordinary edge does (its `previous_mean`
+    /// instead of its `expected_mean`), e.g. for carry-over effects; the
+    /// posterior updates of the parent are unchanged.
+    pub fn set_coupling_lag(
+        &mut self,
+        parent_idx: usize,
+        child_idx: usize,
+        lag: usize,
+    ) -> Result<(), NetworkError> {
+        set_coupling_lag(self, parent_idx, child_idx, lag)
+    }
+
+    /// Make `parent_idx` a drift parent of `child_idx`: the child integrates
+    /// the parent (`expected_mean = mean + Δt · κ · parent`, its
+    /// autoconnection strength is set to 1), and the parent's posterior
+    /// update scales the child's prediction errors by `Δt · κ` to match.
+    /// Drift parents can have drift parents of their own, e.g. position ←
+    /// velocity ← acceleration.
+    pub fn set_drift_coupling(
+        &mut self,
+        parent_idx: usize,
+        child_idx: usize,
+        drift: bool,
+    ) -> Result<(), NetworkError> {
+        set_drift_coupling(self, parent_idx, child_idx, drift)
+    }
+
+    /// Set the autoconnection strength λ of a node's value level.
+    ///
+    /// λ scales the previous posterior mean in the prediction step
+    /// (`expected_mean = λ · mean + Δt · drift`), so values below 1 make the
+    /// belief decay towards the drift between observations (leaky
+    /// accumulation). Only values in `(0, 1]` are accepted.
+    pub fn set_autoconnection(&mut self, node_idx: usize, value: f64) -> Result<(), NetworkError> {
+        let state = self.autoconnected_state_mut(node_idx, value, "set_autoconnection")?;
+        state.autoconnection_strength = value;
+        Ok(())
+    }
+
+    /// Set the autoconnection strength of a volatile node's implicit volatility
+    /// level, validated to `(0, 1]` like [`Network::set_autoconnection`].
+    pub fn set_autoconnection_vol(
+        &mut self,
+        node_idx: usize,
+        value: f64,
+    ) -> Result<(), NetworkError> {
+        let node_type = self.edges.get(node_idx).map(|e| e.node_type.as_str());
+        if let Some(node_type) = node_type.filter(|&t| t != "volatile-state") {
+            return Err(NetworkError::InvalidParameter {
+                node_type: node_type.to_string(),
+                parameter: "autoconnection_strength_vol".into(),
+                accepted: parameters_for_type(node_type),
+            });
         }
+        let state = self.autoconnected_state_mut(node_idx, value, "set_autoconnection_vol")?;
+        state.autoconnection_strength_vol = value;
+        Ok(())
+    }
 
-        if record_trajectories {
-            self.node_trajectories = node_trajectories;
+    /// Set the asymmetry of the value mean update of `node_idx`: positive
+    /// prediction errors of its value children are scaled by `1 + value` and
+    /// negative ones by `1 − value`, so a positive asymmetry learns more from
+    /// better-than-expected outcomes. Only values in `(−1, 1)` are accepted.
+    pub fn set_pe_asymmetry(&mut self, node_idx: usize, value: f64) -> Result<(), NetworkError> {
+        if !(value > -1.0 && value < 1.0) {
+            return Err(NetworkError::InvalidValue {
+                parameter: "pe_asymmetry",
+                value: value.to_string(),
+                accepted: "a value in (-1, 1)",
+            });
         }
+        let state = self
+            .attributes
+            .states
+            .get_mut(node_idx)
+            .ok_or(NetworkError::MissingNode {
+                node_idx,
+                step: "set_pe_asymmetry",
+            })?;
+        state.pe_asymmetry = value;
+        Ok(())
     }
 
-    pub fn predict(
-        &self,
-        x: &[Vec<f64>],
-        inputs_x_idxs: &[usize],
-        inputs_y_idxs: &[usize],
-    ) -> Vec<Vec<f64>> {
-        let time_step = 1.0;
+    /// Set the observation model of the continuous input `node_idx`:
+    /// `"gaussian"` (the default) or `"laplace"`.
+    ///
+    /// Under a Laplace likelihood with the same variance `1 / π̂`, the value
+    /// prediction error passed to the parents is clipped at `±√(2 / π̂)`: the
+    /// parents move in proportion to the error while it is small, and by a
+    /// constant step beyond, so large deviations pull them less.
+    pub fn set_likelihood(
+        &mut self,
+        node_idx: usize,
+        likelihood: &str,
+    ) -> Result<(), NetworkError> {
+        if !["gaussian", "laplace"].contains(&likelihood) {
+            return Err(NetworkError::InvalidValue {
+                parameter: "likelihood",
+                value: format!("'{}'", likelihood),
+                accepted: "'gaussian' or 'laplace'",
+            });
+        }
+        let edge = self
+            .edges
+            .get_mut(node_idx)
+            .ok_or(NetworkError::MissingNode {
+                node_idx,
+                step: "set_likelihood",
+            })?;
+        if edge.node_type != "continuous-state" || !self.inputs.contains(&node_idx) {
+            return Err(NetworkError::InvalidParameter {
+                node_type: format!("non-input {}", edge.node_type),
+                parameter: "likelihood".into(),
+                accepted: parameters_for_type(&edge.node_type),
+            });
+        }
+        edge.likelihood = likelihood.to_string();
+        Ok(())
+    }
 
-        let prediction_steps: Vec<(usize, UpdateStep)> = self
-            .update_sequence
-            .predictions
-            .iter()
-            .filter(|(idx, _)| !inputs_x_idxs.contains(idx))
-            .cloned()
-            .collect();
+    /// Set the function through which the continuous node `node_idx` couples
+    /// to its volatility children: they predict their volatility from
+    /// `κ · g(μ)` instead of `κ · μ`. `"linear"` restores the default.
+    ///
+    /// Like the value coupling function, `g` is defined on the parent and
+    /// applies to all its volatility children; the posterior updates use its
+    /// local slope and curvature. The unbounded update only supports the
+    /// linear coupling: such nodes get the eHGF update when the sequence is
+    /// built.
+    pub fn set_volatility_coupling_fn(
+        &mut self,
+        node_idx: usize,
+        name: &str,
+    ) -> Result<(), NetworkError> {
+        let coupling_fn = coupling_fn_by_name(name)?;
+        let edge = self.edges.get(node_idx).ok_or(NetworkError::MissingNode {
+            node_idx,
+            step: "set_volatility_coupling_fn",
+        })?;
+        if edge.node_type != "continuous-state" {
+            return Err(NetworkError::InvalidParameter {
+                node_type: edge.node_type.clone(),
+                parameter: "volatility_coupling_fn".into(),
+                accepted: parameters_for_type(&edge.node_type),
+            });
+        }
+        self.attributes.fn_ptrs[node_idx].volatility_coupling_fn =
+            (coupling_fn.kind != crate::math::CouplingKind::Linear).then_some(coupling_fn);
+        // The cached sequence may hold an unbounded update for this node; the
+        // next run rebuilds it with the eHGF fallback.
+        self.update_sequence = UpdateSequence {
+            predictions: Vec::new(),
+            updates: Vec::new(),
+        };
+        self.edge_lookup = EdgeLookup::default();
+        Ok(())
+    }
 
-        // Clone unchanging parts once (edges, vectors, fn_ptrs don't change
-        // during prediction-only passes).
-        let mut temp = Network {
-            attributes: self.attributes.clone(),
-            edges: self.edges.clone(),
-            inputs: Vec::new(),
-            volatility_updates: String::new(),
-            mean_field_updates: false,
-            update_sequence: UpdateSequence {
-                predictions: Vec::new(),
-                updates: Vec::new(),
-            },
-            node_trajectories: NodeTrajectories { nodes: Vec::new() },
-            layers: Vec::new(),
-            adam_state: None,
-            roots: Vec::new(),
-            leafs: Vec::new(),
-            max_posterior_precision: self.max_posterior_precision,
-            precision_clipping_value: self.precision_clipping_value,
+    /// Apply the coupling function `name` on the child side of the value edge
+    /// `parent_idx → child_idx`: the parent's posterior update then reads the
+    /// child's prediction error as `h(μ_c) − h(μ̂_c)` (see [`NodeFnPtrs`]).
+    /// `"linear"` restores the identity. Only continuous and volatile children,
+    /// whose means live on the real line, are accepted.
+    pub fn set_child_coupling_fn(
+        &mut self,
+        parent_idx: usize,
+        child_idx: usize,
+        name: &str,
+    ) -> Result<(), NetworkError> {
+        const STEP: &str = "set_child_coupling_fn";
+        let coupling_fn = coupling_fn_by_name(name)?;
+        for node_idx in [parent_idx, child_idx] {
+            if node_idx >= self.edges.len() {
+                return Err(NetworkError::MissingNode {
+                    node_idx,
+                    step: STEP,
+                });
+            }
+        }
+        let position = self.edges[parent_idx]
+            .value_children
+            .as_ref()
+            .and_then(|children| children.iter().position(|&c| c == child_idx))
+            .ok_or(NetworkError::InvalidEdge {
+                node_idx: parent_idx,
+                edge: "value_children",
+                target_idx: child_idx,
+                step: STEP,
+                reason: NOT_AN_EDGE,
+            })?;
+        let child_type = &self.edges[child_idx].node_type;
+        if !matches!(child_type.as_str(), "continuous-state" | "volatile-state") {
+            return Err(NetworkError::InvalidParameter {
+                node_type: child_type.clone(),
+                parameter: "child_coupling_fn".into(),
+                accepted: parameters_for_type(child_type),
+            });
+        }
+        let fns = &mut self.attributes.fn_ptrs[parent_idx].value_coupling_fn_children;
+        if fns.len() <= position {
+            fns.resize(position + 1, None);
+        }
+        fns[position] =
+            (coupling_fn.kind != crate::math::CouplingKind::Linear).then_some(coupling_fn);
+        if let Some(edge) = self
+            .edge_lookup
+            .value_children
+            .get_mut(parent_idx)
+            .and_then(|edges| edges.get_mut(position))
+        {
+            edge.child_coupling_fn = fns[position];
+        }
+        Ok(())
+    }
+
+    /// Switch the posterior updates to `update_type` (`"eHGF"`, `"standard"` or
+    /// `"unbounded"`). The cached update sequence is dropped so that the next
+    /// run picks the matching update functions.
+    pub fn set_update_type(&mut self, update_type: &str) -> Result<(), NetworkError> {
+        if !UPDATE_TYPES.contains(&update_type) {
+            return Err(NetworkError::InvalidValue {
+                parameter: "update_type",
+                value: format!("'{}'", update_type),
+                accepted: "'eHGF', 'standard' or 'unbounded'",
+            });
+        }
+        self.volatility_updates = String::from(update_type);
+        self.update_sequence = UpdateSequence {
+            predictions: Vec::new(),
+            updates: Vec::new(),
         };
+        self.edge_lookup = EdgeLookup::default();
+        Ok(())
+    }
 
-        x.iter()
-            .map(|x_row| {
-                // Only reset states per sample (plain f64 fields, no allocation).
-                temp.attributes.states.clone_from(&self.attributes.states);
+    /// Policy of the posterior updates for a non-positive posterior precision:
+    /// `"floor"` (the default), `"error"` or `"reject"` (see
+    /// [`NegativePrecision`]).
+    pub fn set_negative_precision(&mut self, policy: &str) -> Result<(), NetworkError> {
+        self.negative_precision = NegativePrecision::parse(policy)?;
+        Ok(())
+    }
 
-                for (i, &node_idx) in inputs_x_idxs.iter().enumerate() {
-                    set_predictors(&mut temp, node_idx, x_row[i]);
-                }
+    /// Precision the recorded `entropy` is computed from: `"posterior"` (the
+    /// default) or `"prediction"` (see [`EntropySource`]).
+    pub fn set_entropy_source(&mut self, source: &str) -> Result<(), NetworkError> {
+        self.entropy_source = EntropySource::parse(source)?;
+        Ok(())
+    }
 
-                for &(idx, step) in &prediction_steps {
-                    step.call(&mut temp, idx, time_step);
-                }
+    /// Record only the trajectory keys in `keys` (scalar fields of
+    /// [`TRAJECTORY_FIELDS`] or vector fields of [`VECTOR_TRAJECTORY_FIELDS`])
+    /// in the following runs, or every key again with `None`. The other
+    /// attributes are still updated, just not stored at every time step.
+    pub fn set_record_keys(&mut self, keys: Option<Vec<String>>) -> Result<(), NetworkError> {
+        if let Some(unknown) = keys
+            .iter()
+            .flatten()
+            .find(|key| !RECORDABLE_KEYS.contains(&key.as_str()))
+        {
+            return Err(NetworkError::InvalidParameter {
+                node_type: "any".into(),
+                parameter: unknown.clone(),
+                accepted: RECORDABLE_KEYS,
+            });
+        }
+        self.record_keys = keys;
+        Ok(())
+    }
 
-                inputs_y_idxs
-                    .iter()
-                    .map(|&idx| temp.attributes.states[idx].expected_mean)
-                    .collect()
-            })
-            .collect()
+    fn autoconnected_state_mut(
+        &mut self,
+        node_idx: usize,
+        value: f64,
+        step: &'static str,
+    ) -> Result<&mut NodeState, NetworkError> {
+        // Written as a negated range check so that NaN is rejected too.
+        if !(value > 0.0 && value <= 1.0) {
+            return Err(NetworkError::InvalidValue {
+                parameter: "autoconnection_strength",
+                value: value.to_string(),
+                accepted: "a value in (0, 1]",
+            });
+        }
+        self.attributes
+            .states
+            .get_mut(node_idx)
+            .ok_or(NetworkError::MissingNode { node_idx, step })
     }
 
     pub fn weight_initialisation(
@@ -1249,9 +5094,7 @@ impl Network {
                     for (p_local, &parent_idx) in parent_nodes.iter().enumerate() {
                         for (c_local, &child_idx) in pre_layer.iter().enumerate() {
                             let w = weights[p_local * n_children + c_local];
-                            crate::utils::set_coupling::set_coupling(
-                                self, parent_idx, child_idx, w,
-                            );
+                            try_set_coupling(self, parent_idx, child_idx, w);
                         }
                     }
                 }
@@ -1278,7 +5121,7 @@ impl Network {
             for (p_local, &parent_idx) in parent_nodes.iter().enumerate() {
                 for (c_local, &child_idx) in current_nodes.iter().enumerate() {
                     let w = weights[p_local * n_current + c_local];
-                    crate::utils::set_coupling::set_coupling(self, parent_idx, child_idx, w);
+                    try_set_coupling(self, parent_idx, child_idx, w);
                 }
             }
         }
@@ -1297,7 +5140,12 @@ fn apply_overrides_continuous(state: &mut NodeState, overrides: &HashMap<String,
             "tonic_volatility" => state.tonic_volatility = value,
             "tonic_drift" => state.tonic_drift = value,
             "autoconnection_strength" => state.autoconnection_strength = value,
+            "max_precision" => state.max_precision = value,
             "current_variance" => state.current_variance = value,
+            "initial_current_variance" => state.initial_current_variance = value,
+            "pe_asymmetry" => state.pe_asymmetry = value,
+            "tonic_drift_lr" => state.tonic_drift_lr = value,
+            "coupling_grad_clip" => state.coupling_grad_clip = value,
             _ => {}
         }
     }
@@ -1316,34 +5164,431 @@ fn apply_overrides_volatile(state: &mut NodeState, overrides: &HashMap<String, f
             "expected_precision" => state.expected_precision = value,
             "tonic_drift" => state.tonic_drift = value,
             "autoconnection_strength" => state.autoconnection_strength = value,
+            "max_precision" => state.max_precision = value,
             "current_variance" => state.current_variance = value,
+            "initial_current_variance" => state.initial_current_variance = value,
+            "pe_asymmetry" => state.pe_asymmetry = value,
             "mean_vol" => state.mean_vol = value,
             "expected_mean_vol" => state.expected_mean_vol = value,
             "precision_vol" => state.precision_vol = value,
             "expected_precision_vol" => state.expected_precision_vol = value,
             "tonic_volatility_vol" => state.tonic_volatility_vol = value,
             "tonic_drift_vol" => state.tonic_drift_vol = value,
+            "autoconnection_strength_vol" => state.autoconnection_strength_vol = value,
+            "coupling_grad_clip" => state.coupling_grad_clip = value,
+            _ => {}
+        }
+    }
+}
+
+/// Apply parameter overrides for ef-state nodes
+fn apply_overrides_ef(state: &mut NodeState, overrides: &HashMap<String, f64>) {
+    for (key, &value) in overrides {
+        match key.as_str() {
+            "nus" => state.nus = value,
+            "forgetting" => state.forgetting = value,
+            "welford" => state.welford = value,
+            _ => {}
+        }
+    }
+}
+
+/// Apply parameter overrides for softmax-state nodes
+fn apply_overrides_softmax(state: &mut NodeState, overrides: &HashMap<String, f64>) {
+    for (key, &value) in overrides {
+        if key == "temperature" {
+            state.temperature = value;
+        }
+    }
+}
+
+fn apply_overrides_dp(state: &mut NodeState, overrides: &HashMap<String, f64>) {
+    for (key, &value) in overrides {
+        match key.as_str() {
+            "precision" => state.precision = value,
+            "concentration" => state.concentration = value,
+            "density_threshold" => state.density_threshold = value,
+            _ => {}
+        }
+    }
+}
+
+/// Apply parameter overrides for bounded-state nodes
+fn apply_overrides_bounded(state: &mut NodeState, overrides: &HashMap<String, f64>) {
+    for (key, &value) in overrides {
+        match key.as_str() {
+            "mean" => state.mean = value,
+            "expected_mean" => state.expected_mean = value,
+            "precision" => state.precision = value,
+            "expected_precision" => state.expected_precision = value,
+            "lower_bound" => state.lower_bound = value,
+            "upper_bound" => state.upper_bound = value,
+            "bound_epsilon" => state.bound_epsilon = value,
+            _ => {}
+        }
+    }
+}
+
+/// Apply parameter overrides for lognormal-state nodes
+fn apply_overrides_lognormal(state: &mut NodeState, overrides: &HashMap<String, f64>) {
+    for (key, &value) in overrides {
+        match key.as_str() {
+            "mean" => state.mean = value,
+            "expected_mean" => state.expected_mean = value,
+            "precision" => state.precision = value,
+            "expected_precision" => state.expected_precision = value,
+            "tonic_volatility" => state.tonic_volatility = value,
+            _ => {}
+        }
+    }
+}
+
+/// Apply parameter overrides for aggregate-input nodes (`n_streams` and
+/// `input_precision` shape the `input_precision` vector instead)
+fn apply_overrides_aggregate(state: &mut NodeState, overrides: &HashMap<String, f64>) {
+    for (key, &value) in overrides {
+        match key.as_str() {
+            "mean" => state.mean = value,
+            "expected_mean" => state.expected_mean = value,
             _ => {}
         }
     }
 }
 
-// Python interface
+// Python interface
+/// Convert `trajectories` to the `node_trajectories` layout: one dict of
+/// arrays per node.
+fn trajectories_to_py<'py>(
+    py: Python<'py>,
+    edges: &[AdjacencyLists],
+    trajectories: &NodeTrajectories,
+) -> PyResult<Py<PyList>> {
+    let py_list = PyList::empty(py);
+    let all_rows = PySlice::full(py);
+
+    for (i, traj) in trajectories.nodes.iter().enumerate() {
+        let py_dict = PyDict::new(py);
+        let node_type = &edges[i].node_type;
+
+        // One bulk copy of the node's time-major buffer; every field is then
+        // exposed as a (strided) column view of that array. Keys are inserted
+        // in the fixed `NodeTrajectory::keys` order.
+        let table = PyArray1::from_slice(py, &traj.data).reshape([traj.n_time, traj.stride()])?;
+        for key in traj.keys(node_type) {
+            if let Some(col) = traj.column_offset(key) {
+                py_dict.set_item(key, table.get_item((&all_rows, col))?)?;
+            } else if let Some(vector) = traj.vector(key) {
+                py_dict.set_item(
+                    key,
+                    PyArray1::from_slice(py, &vector.data)
+                        .reshape([vector.len(), vector.stride])?,
+                )?;
+            }
+        }
+
+        py_list.append(py_dict)?;
+    }
+
+    Ok(py_list.into())
+}
+
+/// Observations from Python: a 1D sequence (one input node) or a 2D one (one
+/// column per input node).
+fn extract_input_data(input_data: &Bound<'_, PyAny>) -> PyResult<Vec<Vec<f64>>> {
+    if let Ok(flat) = input_data.extract::<Vec<f64>>() {
+        Ok(flat.into_iter().map(|v| vec![v]).collect())
+    } else {
+        input_data.extract::<Vec<Vec<f64>>>()
+    }
+}
+
+/// Nodes of a pyhgf (JAX) network from the `(attributes, edges, ...)` tuple
+/// returned by its `get_network()`, or a dict with `attributes` and `edges`
+/// keys. Coupling functions are read by name (`__name__` of the callables).
+fn extract_pyhgf_nodes(spec: &Bound<'_, PyAny>) -> PyResult<Vec<PyhgfNode>> {
+    let (attributes, edges) = match spec.cast::<PyDict>() {
+        Ok(spec) => (
+            spec.get_item("attributes")?
+                .ok_or_else(|| pyo3::exceptions::PyKeyError::new_err("attributes"))?,
+            spec.get_item("edges")?
+                .ok_or_else(|| pyo3::exceptions::PyKeyError::new_err("edges"))?,
+        ),
+        Err(_) => (spec.get_item(0)?, spec.get_item(1)?),
+    };
+    let idxs = |edge: &Bound<'_, PyAny>, name: &str| -> PyResult<Vec<usize>> {
+        Ok(edge
+            .getattr(name)?
+            .extract::<Option<Vec<usize>>>()?
+            .unwrap_or_default())
+    };
+    let mut nodes = Vec::new();
+    for (node_idx, edge) in edges.try_iter()?.enumerate() {
+        let edge = edge?;
+        let mut coupling_fns = Vec::new();
+        for coupling_fn in edge.getattr("coupling_fn")?.try_iter()? {
+            let coupling_fn = coupling_fn?;
+            coupling_fns.push(if coupling_fn.is_none() {
+                None
+            } else if let Ok(name) = coupling_fn.extract::<String>() {
+                Some(name)
+            } else {
+                Some(coupling_fn.getattr("__name__")?.extract::<String>()?)
+            });
+        }
+
+        let node_attributes = attributes.get_item(node_idx)?;
+        let node_attributes = node_attributes.cast::<PyDict>()?;
+        let couplings = |key: &str| -> PyResult<Vec<f64>> {
+            match node_attributes.get_item(key)? {
+                Some(values) => Ok(values.extract::<Option<Vec<f64>>>()?.unwrap_or_default()),
+                None => Ok(Vec::new()),
+            }
+        };
+        // Only the scalar attributes are kept (the coupling tuples and the
+        // `temp` dict are not parameters).
+        let parameters = node_attributes
+            .iter()
+            .filter_map(|(key, value)| Some((key.extract::<String>().ok()?, value.extract().ok()?)))
+            .collect();
+
+        nodes.push(PyhgfNode {
+            node_type: edge.getattr("node_type")?.extract()?,
+            value_parents: idxs(&edge, "value_parents")?,
+            value_children: idxs(&edge, "value_children")?,
+            volatility_parents: idxs(&edge, "volatility_parents")?,
+            volatility_children: idxs(&edge, "volatility_children")?,
+            coupling_fns,
+            value_coupling_children: couplings("value_coupling_children")?,
+            volatility_coupling_children: couplings("volatility_coupling_children")?,
+            value_coupling_parents: couplings("value_coupling_parents")?,
+            volatility_coupling_parents: couplings("volatility_coupling_parents")?,
+            parameters,
+        });
+    }
+    Ok(nodes)
+}
+
 #[pymethods]
 impl Network {
     #[new]
-    #[pyo3(signature = (volatility_updates="unbounded", max_posterior_precision=1e10, mean_field_updates=false, precision_clipping_value=1e-6))]
+    #[pyo3(signature = (volatility_updates="unbounded", max_posterior_precision=1e10, mean_field_updates=false, precision_clipping_value=1e-6, negative_precision="floor"))]
     fn py_new(
         volatility_updates: &str,
         max_posterior_precision: f64,
         mean_field_updates: bool,
         precision_clipping_value: f64,
-    ) -> Self {
+        negative_precision: &str,
+    ) -> PyResult<Self> {
         let mut net = Network::new(volatility_updates);
+        net.set_update_type(volatility_updates)?;
         net.max_posterior_precision = max_posterior_precision;
         net.mean_field_updates = mean_field_updates;
         net.precision_clipping_value = precision_clipping_value;
-        net
+        net.set_negative_precision(negative_precision)?;
+        Ok(net)
+    }
+
+    /// Build the canonical continuous HGF with `n_levels` hidden levels above
+    /// an input node: level 1 is the value parent of the input, and each
+    /// further level the volatility parent of the level below. The lists hold
+    /// one entry per level, except `volatility_coupling` (one per pair of
+    /// consecutive levels).
+    #[staticmethod]
+    #[pyo3(
+        name = "continuous_hgf",
+        signature = (n_levels, initial_mean, initial_precision, tonic_volatility, volatility_coupling, volatility_updates="unbounded")
+    )]
+    fn py_continuous_hgf(
+        n_levels: usize,
+        initial_mean: Vec<f64>,
+        initial_precision: Vec<f64>,
+        tonic_volatility: Vec<f64>,
+        volatility_coupling: Vec<f64>,
+        volatility_updates: &str,
+    ) -> PyResult<Self> {
+        Ok(Network::continuous_hgf(
+            volatility_updates,
+            n_levels,
+            &initial_mean,
+            &initial_precision,
+            &tonic_volatility,
+            &volatility_coupling,
+        )?)
+    }
+
+    /// Build the network defined with the JAX backend: a `pyhgf.model.Network`,
+    /// whose update settings are then reused, or the `(attributes, edges,
+    /// update_sequence)` tuple returned by its `get_network()`. Dirichlet
+    /// process and categorical nodes are not supported and raise
+    /// `RuntimeError`.
+    #[staticmethod]
+    #[pyo3(
+        name = "from_pyhgf_spec",
+        signature = (spec, volatility_updates=None)
+    )]
+    fn py_from_pyhgf_spec(
+        spec: &Bound<'_, PyAny>,
+        volatility_updates: Option<String>,
+    ) -> PyResult<Self> {
+        let is_network = spec.hasattr("get_network")?;
+        let setting = |name: &str| -> PyResult<Option<Bound<'_, PyAny>>> {
+            if is_network {
+                Ok(Some(spec.getattr(name)?))
+            } else {
+                Ok(None)
+            }
+        };
+        let volatility_updates = match volatility_updates {
+            Some(volatility_updates) => volatility_updates,
+            None => match setting("volatility_updates")? {
+                Some(value) => value.extract()?,
+                None => String::from("unbounded"),
+            },
+        };
+        let nodes = if is_network {
+            extract_pyhgf_nodes(&spec.call_method0("get_network")?)?
+        } else {
+            extract_pyhgf_nodes(spec)?
+        };
+
+        let mut net = Network::from_pyhgf_spec(&nodes, &volatility_updates)?;
+        net.set_update_type(&volatility_updates)?;
+        if let Some(value) = setting("mean_field_updates")? {
+            net.mean_field_updates = value.extract()?;
+        }
+        if let Some(value) = setting("max_posterior_precision")? {
+            net.max_posterior_precision = value.extract()?;
+        }
+        if let Some(value) = setting("precision_clipping_value")? {
+            net.precision_clipping_value = value.extract()?;
+        }
+        net.set_update_sequence();
+        Ok(net)
+    }
+
+    /// Build a network from a JSON spec, given as a string or as the path of a
+    /// file holding it. Every problem of an invalid spec is listed in the
+    /// `ValueError`.
+    #[staticmethod]
+    #[pyo3(name = "from_spec")]
+    fn py_from_spec(path_or_str: &str) -> PyResult<Self> {
+        let json = if path_or_str.trim_start().starts_with('{') {
+            path_or_str.to_string()
+        } else {
+            std::fs::read_to_string(path_or_str).map_err(|err| {
+                pyo3::exceptions::PyOSError::new_err(format!(
+                    "Cannot read network spec '{}': {}",
+                    path_or_str, err
+                ))
+            })?
+        };
+        Ok(Network::from_spec_str(&json)?)
+    }
+
+    /// The JSON spec of the network, accepted back by `Network.from_spec`.
+    #[pyo3(name = "to_spec")]
+    fn py_to_spec(&self) -> String {
+        self.to_spec_str()
+    }
+
+    /// Describe the network in the layout of pyhgf's `get_network()`: an
+    /// `(attributes, edges, update_sequence)` tuple, with one
+    /// `pyhgf.typing.AdjacencyLists` per node and the coupling functions given
+    /// by name, for pyhgf's plotting and analysis utilities. Volatile-state
+    /// nodes are expanded into a value node and an explicit volatility parent
+    /// appended after the other nodes. The update sequence is `None`.
+    #[pyo3(name = "to_pyhgf_spec")]
+    fn py_to_pyhgf_spec<'py>(&self, py: Python<'py>) -> PyResult<Py<PyTuple>> {
+        let adjacency_lists = py.import("pyhgf.typing")?.getattr("AdjacencyLists")?;
+        // pyhgf leaves the empty edge and coupling lists as `None`.
+        fn tuple_or_none<'py, T>(py: Python<'py>, values: &[T]) -> PyResult<Py<PyAny>>
+        where
+            T: IntoPyObject<'py> + Copy,
+        {
+            if values.is_empty() {
+                Ok(py.None())
+            } else {
+                Ok(PyTuple::new(py, values.iter().copied())?
+                    .into_any()
+                    .unbind())
+            }
+        }
+
+        let attributes = PyDict::new(py);
+        let network_attributes = PyDict::new(py);
+        network_attributes.set_item("time_step", 0.0)?;
+        network_attributes.set_item("precision_clipping_value", self.precision_clipping_value)?;
+        attributes.set_item(-1, network_attributes)?;
+        let mut edges = Vec::new();
+        for (node_idx, node) in self.to_pyhgf_spec()?.into_iter().enumerate() {
+            let node_attributes = PyDict::new(py);
+            for (key, value) in &node.parameters {
+                node_attributes.set_item(key, value)?;
+            }
+            node_attributes.set_item(
+                "value_coupling_parents",
+                tuple_or_none(py, &node.value_coupling_parents)?,
+            )?;
+            node_attributes.set_item(
+                "value_coupling_children",
+                tuple_or_none(py, &node.value_coupling_children)?,
+            )?;
+            node_attributes.set_item(
+                "volatility_coupling_parents",
+                tuple_or_none(py, &node.volatility_coupling_parents)?,
+            )?;
+            node_attributes.set_item(
+                "volatility_coupling_children",
+                tuple_or_none(py, &node.volatility_coupling_children)?,
+            )?;
+            attributes.set_item(node_idx, node_attributes)?;
+            edges.push(adjacency_lists.call1((
+                node.node_type,
+                tuple_or_none(py, &node.value_parents)?,
+                tuple_or_none(py, &node.volatility_parents)?,
+                tuple_or_none(py, &node.value_children)?,
+                tuple_or_none(py, &node.volatility_children)?,
+                PyTuple::new(py, &node.coupling_fns)?,
+            ))?);
+        }
+        Ok(PyTuple::new(
+            py,
+            [
+                attributes.into_any(),
+                PyTuple::new(py, edges)?.into_any(),
+                py.None().into_bound(py),
+            ],
+        )?
+        .unbind())
+    }
+
+    /// Add `n_branches` continuous HGF branches with `levels` hidden levels
+    /// each, optionally sharing a volatility parent over their first levels
+    /// (see [`Network::parallel_branches`]). Returns the node indices of each
+    /// branch and the index of the shared parent (or `None`).
+    #[pyo3(
+        name = "parallel_branches",
+        signature = (n_branches, levels=1, shared_volatility=true)
+    )]
+    fn py_parallel_branches(
+        &mut self,
+        n_branches: usize,
+        levels: usize,
+        shared_volatility: bool,
+    ) -> PyResult<(Vec<Vec<usize>>, Option<usize>)> {
+        Ok(self.parallel_branches(n_branches, levels, shared_volatility)?)
+    }
+
+    /// The posterior updates in use (`"eHGF"`, `"standard"` or `"unbounded"`).
+    /// Setting it rebuilds the update sequence on the next run.
+    #[getter]
+    fn get_update_type(&self) -> &str {
+        &self.volatility_updates
+    }
+
+    #[setter(update_type)]
+    fn py_set_update_type(&mut self, update_type: &str) -> PyResult<()> {
+        Ok(self.set_update_type(update_type)?)
     }
 
     #[getter]
@@ -1361,7 +5606,24 @@ impl Network {
         self.max_posterior_precision = value;
     }
 
-    #[pyo3(name = "add_nodes", signature = (kind="continuous-state", n_nodes=1, value_parents=None, value_children=None, volatility_parents=None, volatility_children=None, coupling_fn=None, **kwargs))]
+    /// Add `n_nodes` nodes of `kind`. Keyword arguments set initial parameters
+    /// on top of the kind defaults (e.g. `mean`, `precision`,
+    /// `tonic_volatility`, or `tonic_volatility_vol` for volatile-state nodes);
+    /// a parameter the kind does not have raises `TypeError`.
+    /// `precision_children` makes each new node a precision parent of the
+    /// given inputs (see [`Network::add_precision_parent`]), and
+    /// `volatility_coupling_fn` sets the function through which the new nodes
+    /// couple to their volatility children (see
+    /// [`Network::set_volatility_coupling_fn`]). `autoconnection_strength`
+    /// sets how much of the previous mean the nodes carry into their
+    /// prediction, from the very first one: 1 (the latent default) is a pure
+    /// random walk, 0 (the input default) keeps no memory, and values outside
+    /// `[0, 1]` raise `ValueError`.
+    /// `volatility_coupling` seeds the strengths of the volatility edges like
+    /// `coupling` does for the value edges: a scalar for all of them, or one
+    /// value per volatility child then per volatility parent.
+    #[pyo3(name = "add_nodes", signature = (kind="continuous-state", n_nodes=1, value_parents=None, value_children=None, volatility_parents=None, volatility_children=None, coupling_fn=None, coupling=None, precision_children=None, volatility_coupling_fn=None, autoconnection_strength=None, volatility_coupling=None, **kwargs))]
+    #[allow(clippy::too_many_arguments)]
     fn py_add_nodes<'py>(
         mut slf: PyRefMut<'py, Self>,
         kind: &str,
@@ -1371,35 +5633,56 @@ impl Network {
         volatility_parents: Option<IntOrList>,
         volatility_children: Option<IntOrList>,
         coupling_fn: Option<String>,
+        coupling: Option<FloatOrList>,
+        precision_children: Option<IntOrList>,
+        volatility_coupling_fn: Option<String>,
+        autoconnection_strength: Option<f64>,
+        volatility_coupling: Option<FloatOrList>,
         kwargs: Option<&Bound<'py, PyDict>>,
     ) -> PyResult<PyRefMut<'py, Self>> {
-        let additional_parameters = match kwargs {
-            Some(dict) => {
-                let mut map = HashMap::new();
-                for (key, value) in dict.iter() {
-                    let key_str: String = key.extract()?;
-                    if let Ok(val) = value.extract::<f64>() {
-                        map.insert(key_str, val);
-                    }
-                }
-                if map.is_empty() {
-                    None
-                } else {
-                    Some(map)
-                }
+        let mut map = HashMap::new();
+        if let Some(dict) = kwargs {
+            for (key, value) in dict.iter() {
+                map.insert(key.extract::<String>()?, value.extract::<f64>()?);
             }
-            None => None,
-        };
+        }
+        if let Some(value) = autoconnection_strength {
+            map.insert("autoconnection_strength".to_string(), value);
+        }
+        let additional_parameters = if map.is_empty() { None } else { Some(map) };
         slf.add_nodes(
             kind,
             n_nodes,
-            value_parents,
-            value_children,
-            volatility_parents,
-            volatility_children,
-            coupling_fn,
-            additional_parameters,
-        );
+            AddNodesOptions {
+                value_parents,
+                value_children,
+                volatility_parents,
+                volatility_children,
+                coupling_fn,
+                coupling,
+                volatility_coupling,
+                additional_parameters: additional_parameters.clone(),
+            },
+        )?;
+        if let Some(children) = precision_children {
+            let n_total = slf.edges.len();
+            let children = children.into_vec();
+            for parent_idx in n_total - n_nodes..n_total {
+                for &child_idx in &children {
+                    slf.add_precision_parent(parent_idx, child_idx)?;
+                }
+                // The overrides win over the latent defaults set above.
+                if let Some(ref overrides) = additional_parameters {
+                    apply_overrides_continuous(&mut slf.attributes.states[parent_idx], overrides);
+                }
+            }
+        }
+        if let Some(name) = volatility_coupling_fn {
+            let n_total = slf.edges.len();
+            for node_idx in n_total - n_nodes..n_total {
+                slf.set_volatility_coupling_fn(node_idx, &name)?;
+            }
+        }
         Ok(slf)
     }
 
@@ -1409,76 +5692,426 @@ impl Network {
         Ok(slf)
     }
 
-    #[pyo3(name = "input_data", signature = (input_data, time_steps=None, record_trajectories=true))]
+    /// Replace the step `name` on `node_idx` with `function(network, node_idx,
+    /// time_step)`, where `network` exposes `get`, `set` and `edges`.
+    /// Rebuilding the update sequence puts the Python steps back in place.
+    #[pyo3(name = "replace_update_step")]
+    fn py_replace_update_step<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        node_idx: usize,
+        name: &str,
+        function: Bound<'py, PyAny>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.replace_update_step(node_idx, name, &function)?;
+        Ok(slf)
+    }
+
+    /// Insert `function(network, node_idx, time_step)` in the `phase` sequence
+    /// (`"predictions"` or `"updates"`) at `position`, or at the end. `kind` is
+    /// one of `"prediction"`, `"posterior"`, `"prediction_error"` or
+    /// `"learning"` and defaults to the phase's own stage.
+    #[pyo3(
+        name = "insert_update_step",
+        signature = (node_idx, function, phase="updates", position=None, kind=None)
+    )]
+    fn py_insert_update_step<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        node_idx: usize,
+        function: Bound<'py, PyAny>,
+        phase: &str,
+        position: Option<usize>,
+        kind: Option<&str>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let kind = match kind {
+            None if phase == "predictions" => StepKind::Prediction,
+            None => StepKind::Posterior,
+            Some("prediction") => StepKind::Prediction,
+            Some("posterior") => StepKind::Posterior,
+            Some("prediction_error") => StepKind::PredictionError,
+            Some("learning") => StepKind::Learning,
+            Some(other) => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "unknown step kind `{}`",
+                    other
+                )))
+            }
+        };
+        slf.insert_update_step(node_idx, &function, phase, position, kind)?;
+        Ok(slf)
+    }
+
+    /// `record_keys` restricts the recorded trajectories of this call to the
+    /// given keys (see `set_record_keys`); `None` keeps the network setting.
+    /// `check_divergence` stops the run with a `FloatingPointError` at the
+    /// first NaN or infinite belief, naming the time step, node and attribute.
+    /// `conditions` (one label per time step) and `condition_parameters`
+    /// (`{condition: {(node_idx, parameter): value}}`) switch node parameters
+    /// trial by trial (see `Network::input_data`). `covariates`
+    /// (one row per time step) and `covariate_weights` (`{column: (node_idx,
+    /// weight)}`) add measured regressors to the drift of continuous nodes
+    /// (see `Network::input_data`). `pe_gains` (`{node_idx:
+    /// gains}`, one gain in `[0, 1]` per time step) weights the prediction
+    /// errors of input nodes in their parents' updates (see
+    /// `Network::input_data`).
+    #[pyo3(
+        name = "input_data",
+        signature = (input_data, time_steps=None, record_trajectories=true, record_keys=None, check_divergence=false, conditions=None, condition_parameters=None, covariates=None, covariate_weights=None, pe_gains=None)
+    )]
+    #[allow(clippy::too_many_arguments)]
     fn py_input_data<'py>(
         mut slf: PyRefMut<'py, Self>,
         input_data: Bound<'py, PyAny>,
         time_steps: Option<Bound<'py, PyAny>>,
         record_trajectories: bool,
+        record_keys: Option<Vec<String>>,
+        check_divergence: bool,
+        conditions: Option<Vec<usize>>,
+        condition_parameters: Option<ConditionParameters>,
+        covariates: Option<Bound<'py, PyAny>>,
+        covariate_weights: Option<CovariateWeights>,
+        pe_gains: Option<PeGains>,
     ) -> PyResult<PyRefMut<'py, Self>> {
-        // Accept both 1D (Vec<f64>) and 2D (Vec<Vec<f64>>) input
-        let data: Vec<Vec<f64>> = if let Ok(flat) = input_data.extract::<Vec<f64>>() {
-            flat.into_iter().map(|v| vec![v]).collect()
-        } else {
-            input_data.extract::<Vec<Vec<f64>>>()?
-        };
+        let data = extract_input_data(&input_data)?;
         let ts: Option<Vec<f64>> = match time_steps {
             Some(ref obj) => Some(obj.extract()?),
             None => None,
         };
-        slf.input_data(data, ts, record_trajectories);
+        let conditions = match (conditions, condition_parameters) {
+            (None, None) => None,
+            (Some(conditions), Some(parameters)) => Some((conditions, parameters)),
+            _ => {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "`conditions` and `condition_parameters` must be given together",
+                ))
+            }
+        };
+        let covariates = match (covariates, covariate_weights) {
+            (None, None) => None,
+            (Some(covariates), Some(weights)) => Some((extract_input_data(&covariates)?, weights)),
+            _ => {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    "`covariates` and `covariate_weights` must be given together",
+                ))
+            }
+        };
+
+        let network = &mut *slf;
+        let network_keys = network.record_keys.clone();
+        if record_keys.is_some() {
+            network.set_record_keys(record_keys)?;
+        }
+        let result = network.input_data(
+            data,
+            ts,
+            record_trajectories,
+            InputDataOptions {
+                conditions,
+                covariates,
+                pe_gains,
+                check_divergence,
+            },
+        );
+        network.record_keys = network_keys;
+        result?;
         Ok(slf)
     }
 
-    #[getter]
-    pub fn get_node_trajectories<'py>(&self, py: Python<'py>) -> PyResult<Py<PyList>> {
-        let py_list = PyList::empty(py);
+    /// `input_data` calling `callback(time_idx)` after each time step, or
+    /// `callback(time_idx, attributes)` with `snapshot=True`, where
+    /// `attributes` is a copy of the node attributes in the layout of
+    /// `get_attributes`. A callback returning `True` stops the run after that
+    /// step (the trajectories hold the steps that ran); an exception it raises
+    /// stops the run and is raised again. The network is borrowed for the
+    /// whole run, so using it from the callback raises `RuntimeError`.
+    #[pyo3(
+        name = "input_data_with_callback",
+        signature = (input_data, callback, time_steps=None, record_trajectories=true, snapshot=false)
+    )]
+    fn py_input_data_with_callback<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        py: Python<'py>,
+        input_data: Bound<'py, PyAny>,
+        callback: Bound<'py, PyAny>,
+        time_steps: Option<Vec<f64>>,
+        record_trajectories: bool,
+        snapshot: bool,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let data = extract_input_data(&input_data)?;
+        let mut error = None;
+        let result = slf.input_data_with_callback(
+            data,
+            time_steps,
+            record_trajectories,
+            &mut |network, time_idx| {
+                let returned = if snapshot {
+                    network
+                        .py_get_attributes(py)
+                        .and_then(|attributes| callback.call1((time_idx, attributes)))
+                } else {
+                    callback.call1((time_idx,))
+                };
+                match returned {
+                    Ok(value) if value.extract::<bool>().is_ok_and(|stop| stop) => {
+                        ControlFlow::Break(())
+                    }
+                    Ok(_) => ControlFlow::Continue(()),
+                    Err(err) => {
+                        error = Some(err);
+                        ControlFlow::Break(())
+                    }
+                }
+            },
+        );
+        if let Some(err) = error {
+            return Err(err);
+        }
+        result?;
+        Ok(slf)
+    }
 
-        for (i, traj) in self.node_trajectories.nodes.iter().enumerate() {
-            let py_dict = PyDict::new(py);
-            let node_type = &self.edges[i].node_type;
-            let fields = trajectory_fields_for_type(node_type);
+    #[pyo3(name = "step", signature = (observations, time_step=1.0))]
+    fn py_step<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        observations: Vec<f64>,
+        time_step: f64,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.step(&observations, time_step)?;
+        Ok(slf)
+    }
 
-            for &field in fields {
-                let data = trajectory_field_ref(traj, field);
-                if !data.is_empty() {
-                    py_dict.set_item(field, PyArray1::from_vec(py, data.clone()).to_owned())?;
-                }
-            }
+    /// Run the prediction steps only (see `run_step`).
+    #[pyo3(name = "run_predictions", signature = (time_step=1.0))]
+    fn py_run_predictions<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        time_step: f64,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.run_predictions(time_step)?;
+        Ok(slf)
+    }
 
-            // Vector trajectories
-            if !traj.xis.is_empty() {
-                py_dict.set_item("xis", PyArray::from_vec2(py, &traj.xis).unwrap())?;
-            }
-            if !traj.value_coupling_parents.is_empty() {
-                py_dict.set_item(
-                    "value_coupling_parents",
-                    PyArray::from_vec2(py, &traj.value_coupling_parents).unwrap(),
-                )?;
-            }
-            if !traj.value_coupling_children.is_empty() {
-                py_dict.set_item(
-                    "value_coupling_children",
-                    PyArray::from_vec2(py, &traj.value_coupling_children).unwrap(),
-                )?;
+    /// Write one observation per input node without running any update.
+    #[pyo3(name = "set_observations")]
+    fn py_set_observations<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        observations: Vec<f64>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.set_observations(&observations)?;
+        Ok(slf)
+    }
+
+    /// Run the prediction-error and posterior steps only (see `run_step`).
+    #[pyo3(name = "run_updates", signature = (time_step=1.0))]
+    fn py_run_updates<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        time_step: f64,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.run_updates(time_step)?;
+        Ok(slf)
+    }
+
+    /// Run `run_predictions`, `set_observations` and `run_updates` in a row.
+    /// Unlike `step`, nothing is recorded; inspect the state with
+    /// `get_attributes`.
+    #[pyo3(name = "run_step", signature = (observations, time_step=1.0))]
+    fn py_run_step<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        observations: Vec<f64>,
+        time_step: f64,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.run_step(&observations, time_step)?;
+        Ok(slf)
+    }
+
+    /// Current attributes of every node, one dict per node with the same keys
+    /// as `node_trajectories` (scalars as floats, vectors as 1D arrays).
+    #[pyo3(name = "get_attributes")]
+    fn py_get_attributes<'py>(&self, py: Python<'py>) -> PyResult<Py<PyList>> {
+        let py_list = PyList::empty(py);
+        for (node_idx, state) in self.attributes.states.iter().enumerate() {
+            let py_dict = PyDict::new(py);
+            for &key in trajectory_fields_for_type(&self.edges[node_idx].node_type) {
+                if let Some(value) = state.get(key) {
+                    py_dict.set_item(key, value)?;
+                }
             }
-            if !traj.volatility_coupling_parents.is_empty() {
-                py_dict.set_item(
-                    "volatility_coupling_parents",
-                    PyArray::from_vec2(py, &traj.volatility_coupling_parents).unwrap(),
-                )?;
+            let vectors = &self.attributes.vectors[node_idx];
+            for &key in VECTOR_TRAJECTORY_FIELDS {
+                if let Some(values) = vectors.get(key).filter(|v| !v.is_empty()) {
+                    py_dict.set_item(key, PyArray1::from_slice(py, values))?;
+                }
             }
-            if !traj.volatility_coupling_children.is_empty() {
-                py_dict.set_item(
-                    "volatility_coupling_children",
-                    PyArray::from_vec2(py, &traj.volatility_coupling_children).unwrap(),
-                )?;
+            py_list.append(py_dict)?;
+        }
+        Ok(py_list.into())
+    }
+
+    #[getter]
+    pub fn get_node_trajectories<'py>(&self, py: Python<'py>) -> PyResult<Py<PyList>> {
+        trajectories_to_py(py, &self.edges, &self.node_trajectories)
+    }
+
+    /// Recorded trajectory of `key` for `node_idx`: a 1D array for scalar
+    /// fields and a 2D array (time steps × entries) for vector attributes.
+    /// Raises `KeyError` when the node or the key was not recorded.
+    #[pyo3(name = "get_trajectory")]
+    fn py_get_trajectory<'py>(
+        &self,
+        py: Python<'py>,
+        node_idx: usize,
+        key: &str,
+    ) -> PyResult<Py<PyAny>> {
+        Ok(match self.get_trajectory(node_idx, key)? {
+            RecordedTrajectory::Scalar(column) => {
+                PyArray1::from_vec(py, column.to_vec()).into_any().unbind()
             }
+            RecordedTrajectory::Vector(vector) => PyArray1::from_slice(py, &vector.data)
+                .reshape([vector.len(), vector.stride])?
+                .into_any()
+                .unbind(),
+        })
+    }
 
-            py_list.append(py_dict)?;
+    /// Keys `get_trajectory` accepts for `node_idx`.
+    #[pyo3(name = "list_recorded_keys")]
+    fn py_list_recorded_keys(&self, node_idx: usize) -> PyResult<Vec<&'static str>> {
+        Ok(self.recorded_keys(node_idx)?)
+    }
+
+    /// Recorded time steps `start:stop:step` of every trajectory, in the
+    /// layout of `node_trajectories`. Negative indices count from the end;
+    /// bounds outside the recorded steps raise `IndexError`.
+    #[pyo3(name = "get_node_trajectories_slice", signature = (start, stop=None, step=1))]
+    fn py_get_node_trajectories_slice<'py>(
+        &self,
+        py: Python<'py>,
+        start: isize,
+        stop: Option<isize>,
+        step: usize,
+    ) -> PyResult<Py<PyList>> {
+        let sliced = self.get_node_trajectories_slice(start, stop, step)?;
+        trajectories_to_py(py, &self.edges, &sliced)
+    }
+
+    /// Snapshot the current attributes, to go back to them with `restore`.
+    #[pyo3(name = "checkpoint")]
+    fn py_checkpoint(&self) -> StateSnapshot {
+        self.checkpoint()
+    }
+
+    /// Put back the attributes of a `checkpoint` snapshot and drop the time
+    /// steps recorded after it.
+    #[pyo3(name = "restore")]
+    fn py_restore<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        snapshot: PyRef<'py, StateSnapshot>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.restore(&snapshot)?;
+        Ok(slf)
+    }
+
+    /// The numeric state of every node as a JSON-serialisable dict:
+    /// `{"floats": [...], "vectors": [...]}`, one dict of attributes per node
+    /// in each list. The topology and the trajectories are not included.
+    #[pyo3(name = "get_state")]
+    fn py_get_state<'py>(&self, py: Python<'py>) -> PyResult<Py<PyDict>> {
+        let state = self.get_state();
+        let py_dict = PyDict::new(py);
+        py_dict.set_item("floats", state.floats)?;
+        py_dict.set_item("vectors", state.vectors)?;
+        py_dict.set_item("metadata", state.metadata)?;
+        Ok(py_dict.into())
+    }
+
+    /// Load a state returned by `get_state` into a network with the same
+    /// nodes.
+    #[pyo3(name = "load_state")]
+    fn py_load_state<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        state: &Bound<'py, PyDict>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let item = |key: &str| {
+            state
+                .get_item(key)?
+                .ok_or_else(|| pyo3::exceptions::PyKeyError::new_err(key.to_string()))
+        };
+        let state = NetworkState {
+            floats: item("floats")?.extract()?,
+            vectors: item("vectors")?.extract()?,
+            // States saved before the metadata was recorded have none.
+            metadata: match state.get_item("metadata")? {
+                Some(metadata) => metadata.extract()?,
+                None => Vec::new(),
+            },
+        };
+        slf.load_state(&state)?;
+        Ok(slf)
+    }
+
+    /// Alias of `load_state`, the counterpart of `get_state` when resuming a
+    /// run in a new network (warm restart).
+    #[pyo3(name = "set_state")]
+    fn py_set_state<'py>(
+        slf: PyRefMut<'py, Self>,
+        state: &Bound<'py, PyDict>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        Self::py_load_state(slf, state)
+    }
+
+    /// Run the prediction steps only for `n_time` steps, each prediction
+    /// becoming the next state, and record them as the trajectories. Isolates
+    /// the prior dynamics from the filtering.
+    #[pyo3(name = "run_prior", signature = (n_time, time_step=1.0))]
+    fn py_run_prior<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        n_time: usize,
+        time_step: f64,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.run_prior(n_time, time_step)?;
+        Ok(slf)
+    }
+
+    /// Forecast `horizon` time steps ahead without observations, running the
+    /// prediction steps only and taking each prediction as the next state.
+    /// Returns the forecast in the `node_trajectories` layout; the network's
+    /// state is left unchanged.
+    #[pyo3(name = "forecast", signature = (horizon, time_steps=None))]
+    fn py_forecast<'py>(
+        &mut self,
+        py: Python<'py>,
+        horizon: usize,
+        time_steps: Option<Vec<f64>>,
+    ) -> PyResult<Py<PyList>> {
+        let trajectories = self.forecast(horizon, time_steps.as_deref())?;
+        trajectories_to_py(py, &self.edges, &trajectories)
+    }
+
+    /// Return the recorded trajectories as one flat dict of 1D arrays, ready
+    /// for `pandas.DataFrame(...)`. Columns are `time_steps`, `time` and
+    /// `x_{node}_{attribute}` (vector attributes get a trailing `_{k}`), as in
+    /// pyhgf's `to_pandas`.
+    #[pyo3(name = "to_dataframe_dict")]
+    fn py_to_dataframe_dict<'py>(&self, py: Python<'py>) -> PyResult<Py<PyDict>> {
+        let py_dict = PyDict::new(py);
+        for (name, values) in trajectory_table(self) {
+            py_dict.set_item(name, PyArray1::from_vec(py, values))?;
         }
+        Ok(py_dict.into())
+    }
 
-        Ok(py_list.into())
+    /// Return the recorded scalar trajectories as a `(n_time, n_columns)`
+    /// array and the list of its column names, `node{node}::{attribute}`,
+    /// sorted by node and then by attribute.
+    #[pyo3(name = "get_trajectory_matrix")]
+    fn py_get_trajectory_matrix<'py>(&self, py: Python<'py>) -> PyResult<Py<PyTuple>> {
+        let (values, names) = self.trajectory_matrix();
+        let n_time = self.node_trajectories.time_steps.len();
+        let matrix = PyArray1::from_vec(py, values).reshape([n_time, names.len()])?;
+        Ok(PyTuple::new(py, [matrix.into_any(), PyList::new(py, names)?.into_any()])?.unbind())
+    }
+
+    #[pyo3(name = "write_trajectories_csv")]
+    fn py_write_trajectories_csv(&self, path: std::path::PathBuf) -> PyResult<()> {
+        Ok(self.write_trajectories_csv(&path)?)
     }
 
     #[getter]
@@ -1486,15 +6119,176 @@ impl Network {
         Ok(PyList::new(py, &self.inputs)?.into())
     }
 
+    #[getter]
+    pub fn get_n_nodes(&self) -> usize {
+        self.n_nodes()
+    }
+
+    #[pyo3(name = "is_input")]
+    fn py_is_input(&self, node_idx: usize) -> bool {
+        self.is_input(node_idx)
+    }
+
+    fn __len__(&self) -> usize {
+        self.n_nodes()
+    }
+
+    /// Same structure and attributes (trajectories are not compared).
+    fn __eq__(&self, other: PyRef<'_, Self>) -> bool {
+        *self == *other
+    }
+
+    /// Like `==`, with floats compared up to the absolute tolerance `tol`.
+    #[pyo3(name = "equals", signature = (other, tol=0.0))]
+    fn py_equals(&self, other: PyRef<'_, Self>, tol: f64) -> bool {
+        self.approx_eq(&other, tol)
+    }
+
+    /// Hash of the network structure (node kinds, edges, inputs, coupling
+    /// functions and update options), ignoring every parameter value. The
+    /// value is stable across versions and platforms, e.g. to key a cache.
+    #[pyo3(name = "structure_hash")]
+    fn py_structure_hash(&self) -> u64 {
+        self.structure_hash()
+    }
+
+    /// Live view of node `idx` (negative indices count from the end), whose
+    /// properties read and write the network's attributes.
+    fn __getitem__(slf: &Bound<'_, Self>, idx: isize) -> PyResult<NodeHandle> {
+        let n_nodes = slf.borrow().n_nodes();
+        let resolved = if idx < 0 { idx + n_nodes as isize } else { idx };
+        if resolved < 0 || resolved >= n_nodes as isize {
+            return Err(pyo3::exceptions::PyIndexError::new_err(format!(
+                "node index {} out of range for a network of {} nodes",
+                idx, n_nodes
+            )));
+        }
+        Ok(NodeHandle {
+            network: slf.clone().unbind(),
+            idx: resolved as usize,
+        })
+    }
+
+    /// Set the parameter `key` of node `node_idx` (one `add_nodes` accepts for
+    /// its kind, otherwise `TypeError`).
+    #[pyo3(name = "set_attribute")]
+    fn py_set_attribute<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        node_idx: usize,
+        key: &str,
+        value: f64,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.set_attribute(node_idx, key, value)?;
+        Ok(slf)
+    }
+
+    /// Set the precision of each observation stream of aggregate-input node
+    /// `node_idx`, in column order: the node then reads one column per entry
+    /// of `precisions`.
+    #[pyo3(name = "set_input_precision")]
+    fn py_set_input_precision<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        node_idx: usize,
+        precisions: Vec<f64>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.set_input_precision(node_idx, precisions)?;
+        Ok(slf)
+    }
+
+    /// Attach the label `key = value` to node `node_idx` (e.g. a brain region
+    /// or a condition id). Metadata is not used by the updates and is saved
+    /// with `get_state`.
+    #[pyo3(name = "set_metadata")]
+    fn py_set_metadata<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        node_idx: usize,
+        key: &str,
+        value: &str,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.set_metadata(node_idx, key, value)?;
+        Ok(slf)
+    }
+
+    /// The label `key` of node `node_idx`, or all its labels as a dict when
+    /// `key` is omitted. Raises `KeyError` for a label that is not set.
+    #[pyo3(name = "get_metadata", signature = (node_idx, key=None))]
+    fn py_get_metadata<'py>(
+        &self,
+        py: Python<'py>,
+        node_idx: usize,
+        key: Option<&str>,
+    ) -> PyResult<Py<PyAny>> {
+        if node_idx >= self.n_nodes() {
+            return Err(NetworkError::MissingNode {
+                node_idx,
+                step: "get_metadata",
+            }
+            .into());
+        }
+        match key {
+            Some(key) => match self.get_metadata(node_idx, key) {
+                Some(value) => Ok(value.into_pyobject(py)?.into_any().unbind()),
+                None => Err(pyo3::exceptions::PyKeyError::new_err(key.to_string())),
+            },
+            None => {
+                let labels: BTreeMap<&String, &String> =
+                    self.metadata.get(&node_idx).into_iter().flatten().collect();
+                Ok(labels.into_pyobject(py)?.into_any().unbind())
+            }
+        }
+    }
+
+    /// Node count, node kinds (with their counts, in order of first
+    /// appearance), input nodes and volatility update type.
+    fn __repr__(&self) -> String {
+        let kinds: Vec<String> = self
+            .model_summary()
+            .nodes_by_kind
+            .iter()
+            .map(|(kind, count)| format!("'{}': {}", kind, count))
+            .collect();
+        format!(
+            "Network(n_nodes={}, kinds={{{}}}, inputs={:?}, volatility_updates='{}')",
+            self.n_nodes(),
+            kinds.join(", "),
+            self.inputs,
+            self.volatility_updates
+        )
+    }
+
+    /// Iterate over the nodes as [`NodeView`]s, by index.
+    fn __iter__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyIterator>> {
+        PyList::new(py, self.nodes())?.try_iter()
+    }
+
+    /// Return one [`NodeView`] per node (index, kind, adjacency lists and
+    /// parameters), by index.
+    #[pyo3(name = "nodes")]
+    fn py_nodes(&self) -> Vec<NodeView> {
+        self.nodes()
+    }
+
     #[getter]
     pub fn get_edges<'py>(&self, py: Python<'py>) -> PyResult<Py<PyList>> {
         let py_list = PyList::empty(py);
         for edge in &self.edges {
             let py_dict = PyDict::new(py);
             py_dict.set_item("value_parents", &edge.value_parents)?;
+            // Name of the coupling function of each value parent -> node edge,
+            // which is held by the parent.
+            let coupling_fns = PyDict::new(py);
+            for &parent_idx in edge.value_parents.iter().flatten() {
+                let name = self.attributes.fn_ptrs[parent_idx]
+                    .coupling_fn
+                    .map_or("linear", coupling_fn_name);
+                coupling_fns.set_item(parent_idx, name)?;
+            }
+            py_dict.set_item("coupling_fns", coupling_fns)?;
             py_dict.set_item("value_children", &edge.value_children)?;
             py_dict.set_item("volatility_parents", &edge.volatility_parents)?;
             py_dict.set_item("volatility_children", &edge.volatility_children)?;
+            py_dict.set_item("precision_parents", &edge.precision_parents)?;
+            py_dict.set_item("precision_children", &edge.precision_children)?;
             py_list.append(py_dict)?;
         }
         Ok(py_list.into())
@@ -1509,7 +6303,11 @@ impl Network {
             &self.update_sequence.updates,
         ] {
             for &(num, step) in sequence {
-                let py_func_name = step.name().into_pyobject(py)?.into_any().unbind();
+                let name = match step {
+                    UpdateStep::Python { slot, .. } => self.python_steps[slot].name.as_str(),
+                    _ => step.name(),
+                };
+                let py_func_name = name.into_pyobject(py)?.into_any().unbind();
                 let py_num = num.into_pyobject(py)?.into_any().unbind();
                 py_list.append(PyTuple::new(py, &[py_num, py_func_name])?)?;
             }
@@ -1518,6 +6316,56 @@ impl Network {
         Ok(py_list.into())
     }
 
+    /// Return the sequence `fit` runs with `inputs_x_idxs` as predictors (the
+    /// leaf nodes by default) as `(node_idx, function_name)` tuples: the
+    /// predictions, then the updates, then the learning steps.
+    #[pyo3(name = "get_learning_sequence", signature = (inputs_x_idxs=None))]
+    fn py_get_learning_sequence<'py>(
+        &mut self,
+        py: Python<'py>,
+        inputs_x_idxs: Option<Vec<usize>>,
+    ) -> PyResult<Py<PyList>> {
+        if self.update_sequence.predictions.is_empty() && self.update_sequence.updates.is_empty() {
+            self.set_update_sequence();
+        }
+        let inputs_x_idxs = inputs_x_idxs.unwrap_or_else(|| self.leafs.clone());
+        let sequence = self.learning_sequence(&inputs_x_idxs);
+
+        let py_list = PyList::empty(py);
+        for &(num, step) in sequence
+            .prediction_steps
+            .iter()
+            .chain(&sequence.update_steps)
+            .chain(&sequence.learning_steps)
+        {
+            py_list.append((num, step.name()))?;
+        }
+        Ok(py_list.into())
+    }
+
+    /// Return `(node_idx, function)` for the prediction error and posterior
+    /// update each node will use, without building the update sequence.
+    #[pyo3(name = "describe_updates")]
+    fn py_describe_updates(&self) -> Vec<(usize, String)> {
+        self.describe_updates()
+    }
+
+    /// Return the update sequence with, for each step, the node index, the
+    /// update function and the nodes it depended on (parents for predictions,
+    /// children for posterior updates).
+    #[pyo3(name = "get_update_sequence_graph")]
+    fn py_get_update_sequence_graph<'py>(&self, py: Python<'py>) -> PyResult<Py<PyList>> {
+        let py_list = PyList::empty(py);
+        for step in get_update_sequence_graph(self) {
+            let py_dict = PyDict::new(py);
+            py_dict.set_item("node_idx", step.node_idx)?;
+            py_dict.set_item("function", step.step.name())?;
+            py_dict.set_item("dependencies", step.dependencies)?;
+            py_list.append(py_dict)?;
+        }
+        Ok(py_list.into())
+    }
+
     #[pyo3(name = "add_layer", signature = (size=1, kind="volatile-state", value_children=None, coupling_strengths=1.0, coupling_fn=None, add_constant_input=true, **kwargs))]
     fn py_add_layer<'py>(
         mut slf: PyRefMut<'py, Self>,
@@ -1554,7 +6402,7 @@ impl Network {
             coupling_fn,
             additional_parameters,
             add_constant_input,
-        );
+        )?;
         Ok(slf)
     }
 
@@ -1594,11 +6442,14 @@ impl Network {
             coupling_fn,
             additional_parameters,
             add_constant_input,
-        );
+        )?;
         Ok(slf)
     }
 
-    #[pyo3(name = "fit", signature = (x, y, inputs_x_idxs=None, inputs_y_idxs=None, lr=None, record_trajectories=true, params=None, learning_kind="precision_weighted"))]
+    /// `check_divergence` stops the fit with a `FloatingPointError` at the
+    /// first NaN or infinite belief (see `input_data`).
+    #[pyo3(name = "fit", signature = (x, y, inputs_x_idxs=None, inputs_y_idxs=None, lr=None, record_trajectories=true, params=None, learning_kind="precision_weighted", check_divergence=false))]
+    #[allow(clippy::too_many_arguments)]
     fn py_fit<'py>(
         mut slf: PyRefMut<'py, Self>,
         x: Bound<'py, PyAny>,
@@ -1609,6 +6460,7 @@ impl Network {
         record_trajectories: bool,
         params: Option<&Bound<'py, PyDict>>,
         learning_kind: &str,
+        check_divergence: bool,
     ) -> PyResult<PyRefMut<'py, Self>> {
         // lr can be a non-negative float (fixed step size) or the string "adam"
         // (triggers the Adam optimiser).  When omitted, defaults to 0.2.
@@ -1679,9 +6531,12 @@ impl Network {
             &y_idxs,
             lr_option,
             record_trajectories,
-            params_map.as_ref(),
             learning_kind,
-        );
+            FitOptions {
+                params: params_map,
+                check_divergence,
+            },
+        )?;
         Ok(slf)
     }
 
@@ -1713,7 +6568,7 @@ impl Network {
             flat.into_iter().map(|v| vec![v]).collect()
         };
 
-        let predictions = slf.predict(&x_data, &x_idxs, &y_idxs);
+        let predictions = slf.predict(&x_data, &x_idxs, &y_idxs)?;
 
         let n_samples = predictions.len();
         let n_outputs = if n_samples > 0 {
@@ -1737,6 +6592,419 @@ impl Network {
         Ok(slf)
     }
 
+    /// Return the free energy of the last recorded trajectory as a dict with
+    /// `accuracy`, `complexity` and `total` (`complexity - accuracy`). Raises
+    /// `KeyError` when a column it reads was not recorded.
+    #[pyo3(name = "get_free_energy")]
+    fn py_get_free_energy<'py>(&self, py: Python<'py>) -> PyResult<Py<PyDict>> {
+        let fe = self.get_free_energy()?;
+        let py_dict = PyDict::new(py);
+        py_dict.set_item("accuracy", fe.accuracy)?;
+        py_dict.set_item("complexity", fe.complexity)?;
+        py_dict.set_item("total", fe.total)?;
+        Ok(py_dict.into())
+    }
+
+    /// Recorded surprise of every node summed over time, as a list indexed by
+    /// node. Raises `KeyError` when `surprise` was not recorded.
+    #[pyo3(name = "surprise_by_node")]
+    fn py_surprise_by_node(&self) -> PyResult<Vec<f64>> {
+        Ok(self.surprise_by_node()?)
+    }
+
+    /// Precision the posterior of `node_idx` converges to under its current
+    /// volatility and children's precisions, without running the network.
+    #[pyo3(name = "steady_state_precision")]
+    fn py_steady_state_precision(&self, node_idx: usize) -> PyResult<f64> {
+        Ok(self.steady_state_precision(node_idx)?)
+    }
+
+    /// Leave-future-out surprise, one value per entry of `train_sizes`: the
+    /// network filters the first `k` time steps, then the surprise of the
+    /// next `horizon` observations is summed without updating on them. The
+    /// network is left as it was.
+    #[pyo3(
+        name = "cv_surprise",
+        signature = (input_data, train_sizes, horizon, time_steps=None)
+    )]
+    fn py_cv_surprise(
+        &mut self,
+        input_data: Bound<'_, PyAny>,
+        train_sizes: Vec<usize>,
+        horizon: usize,
+        time_steps: Option<Vec<f64>>,
+    ) -> PyResult<Vec<f64>> {
+        let data = extract_input_data(&input_data)?;
+        Ok(self.cv_surprise(&data, time_steps.as_deref(), &train_sizes, horizon)?)
+    }
+
+    /// Describe the structure of the network: node counts by kind, value and
+    /// volatility edge counts, update type, input nodes and coupling functions
+    /// in use. Returns a text report, or a dict with `as_dict=True`.
+    #[pyo3(name = "get_model_summary", signature = (as_dict=false))]
+    fn py_get_model_summary<'py>(&self, py: Python<'py>, as_dict: bool) -> PyResult<Py<PyAny>> {
+        let summary = self.model_summary();
+        if !as_dict {
+            return Ok(summary.to_string().into_pyobject(py)?.into_any().unbind());
+        }
+        let py_dict = PyDict::new(py);
+        py_dict.set_item("n_nodes", summary.n_nodes)?;
+        let nodes_by_kind = PyDict::new(py);
+        for (kind, count) in &summary.nodes_by_kind {
+            nodes_by_kind.set_item(kind, count)?;
+        }
+        py_dict.set_item("nodes_by_kind", nodes_by_kind)?;
+        py_dict.set_item("n_value_edges", summary.n_value_edges)?;
+        py_dict.set_item("n_volatility_edges", summary.n_volatility_edges)?;
+        py_dict.set_item("update_type", summary.update_type)?;
+        py_dict.set_item("inputs", summary.inputs)?;
+        py_dict.set_item("value_coupling_fns", summary.value_coupling_fns)?;
+        py_dict.set_item("volatility_coupling_fns", summary.volatility_coupling_fns)?;
+        Ok(py_dict.into_any().unbind())
+    }
+
+    /// Per-node summary statistics of the recorded trajectories, as a list of
+    /// dicts parallel to `node_trajectories` (see `Network::summary`).
+    #[pyo3(name = "summary")]
+    fn py_summary<'py>(&self, py: Python<'py>) -> PyResult<Py<PyList>> {
+        let mut stats = self.summary();
+        let py_list = PyList::empty(py);
+        for node_idx in 0..self.edges.len() {
+            let py_dict = PyDict::new(py);
+            let mut node_stats: Vec<(String, f64)> = stats
+                .remove(&node_idx)
+                .unwrap_or_default()
+                .into_iter()
+                .collect();
+            node_stats.sort_by(|a, b| a.0.cmp(&b.0));
+            for (key, value) in node_stats {
+                py_dict.set_item(key, value)?;
+            }
+            py_list.append(py_dict)?;
+        }
+        Ok(py_list.into())
+    }
+
+    /// Simulate `n_steps` time steps from the network and return
+    /// `(observations, states)`: a `(n_steps, n_inputs)` array of the input
+    /// nodes' values and a `(n_steps, n_nodes)` array of every node's value.
+    /// The network's attributes are left unchanged. `observation_noise` is the
+    /// standard deviation of Gaussian noise added to the observations only.
+    #[pyo3(
+        name = "sample",
+        signature = (n_steps, time_steps=None, seed=None, observation_noise=0.0)
+    )]
+    fn py_sample<'py>(
+        &mut self,
+        py: Python<'py>,
+        n_steps: usize,
+        time_steps: Option<Vec<f64>>,
+        seed: Option<u64>,
+        observation_noise: f64,
+    ) -> PyResult<Py<PyTuple>> {
+        let samples = self.sample(n_steps, time_steps.as_deref(), seed, observation_noise)?;
+        let to_array = |rows: Vec<Vec<f64>>, n_columns: usize| {
+            let flat: Vec<f64> = rows.into_iter().flatten().collect();
+            PyArray1::from_vec(py, flat).reshape([n_steps, n_columns])
+        };
+        let observations = to_array(samples.observations, self.inputs.len())?;
+        let states = to_array(samples.states, self.edges.len())?;
+        Ok(PyTuple::new(py, [observations.into_any(), states.into_any()])?.unbind())
+    }
+
+    /// Filter `observations` (one row per time step, one value per input that
+    /// is not a softmax-state response node) while drawing the responses from
+    /// the response nodes' predicted probabilities, for posterior predictive
+    /// checks. Returns the simulated choices as a `(n_steps, n_responses)`
+    /// array; the belief trajectories are in `node_trajectories`.
+    #[pyo3(
+        name = "simulate_responses",
+        signature = (observations, time_steps=None, seed=None)
+    )]
+    fn py_simulate_responses<'py>(
+        &mut self,
+        py: Python<'py>,
+        observations: Bound<'py, PyAny>,
+        time_steps: Option<Vec<f64>>,
+        seed: Option<u64>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let observations = extract_input_data(&observations)?;
+        let responses = self.simulate_responses(&observations, time_steps.as_deref(), seed)?;
+        let n_responses = responses.first().map_or(0, Vec::len);
+        let flat: Vec<f64> = responses.into_iter().flatten().collect();
+        Ok(PyArray1::from_vec(py, flat)
+            .reshape([observations.len(), n_responses])?
+            .into_any())
+    }
+
+    /// Return the finite-difference gradient of the total surprise of
+    /// `input_data` with respect to every value coupling, as a dict keyed by
+    /// `(parent_idx, child_idx)`. The network is left unchanged.
+    #[pyo3(
+        name = "coupling_sensitivity",
+        signature = (input_data, eps=1e-4, time_steps=None)
+    )]
+    fn py_coupling_sensitivity<'py>(
+        &self,
+        py: Python<'py>,
+        input_data: Bound<'py, PyAny>,
+        eps: f64,
+        time_steps: Option<Vec<f64>>,
+    ) -> PyResult<Py<PyDict>> {
+        let data = extract_input_data(&input_data)?;
+        let sensitivity = self.coupling_sensitivity(&data, time_steps.as_deref(), eps)?;
+        let py_dict = PyDict::new(py);
+        for (edge, gradient) in sensitivity {
+            py_dict.set_item(edge, gradient)?;
+        }
+        Ok(py_dict.into())
+    }
+
+    /// Return the learning rate set by `fit` as a dict of node index → lr.
+    /// Nodes without a learning rate (the predictors) are omitted.
+    #[pyo3(name = "get_learning_rates")]
+    fn py_get_learning_rates<'py>(&self, py: Python<'py>) -> PyResult<Py<PyDict>> {
+        let py_dict = PyDict::new(py);
+        for (node_idx, lr) in self.get_learning_rates() {
+            py_dict.set_item(node_idx, lr)?;
+        }
+        Ok(py_dict.into())
+    }
+
+    /// Return the effective precision γ of a node as a dict holding the
+    /// `effective_precision` array and, for volatile-state nodes, the
+    /// `effective_precision_vol` array of the implicit volatility level.
+    #[pyo3(name = "get_effective_precision")]
+    fn py_get_effective_precision<'py>(
+        &self,
+        py: Python<'py>,
+        node_idx: usize,
+    ) -> PyResult<Py<PyDict>> {
+        let (gamma, gamma_vol) = self.get_effective_precision(node_idx)?;
+        let py_dict = PyDict::new(py);
+        py_dict.set_item("effective_precision", PyArray1::from_vec(py, gamma))?;
+        if let Some(gamma_vol) = gamma_vol {
+            py_dict.set_item("effective_precision_vol", PyArray1::from_vec(py, gamma_vol))?;
+        }
+        Ok(py_dict.into())
+    }
+
+    /// Return the recorded entropy of a node, in nats, as an array with one
+    /// entry per time step.
+    #[pyo3(name = "get_entropy")]
+    fn py_get_entropy<'py>(&self, py: Python<'py>, node_idx: usize) -> PyResult<Py<PyArray1<f64>>> {
+        Ok(PyArray1::from_vec(py, self.get_entropy(node_idx)?).unbind())
+    }
+
+    /// Lower and upper bounds of the predictive interval of a node,
+    /// `expected_mean ∓ z / sqrt(expected_precision)`, one entry per recorded
+    /// time step.
+    #[pyo3(name = "predictive_intervals", signature = (node_idx, z=1.96))]
+    fn py_predictive_intervals<'py>(
+        &self,
+        py: Python<'py>,
+        node_idx: usize,
+        z: f64,
+    ) -> PyResult<Py<PyTuple>> {
+        let (lower, upper) = self.predictive_intervals(node_idx, z)?;
+        let (lower, upper) = (PyArray1::from_vec(py, lower), PyArray1::from_vec(py, upper));
+        Ok(PyTuple::new(py, [lower, upper])?.unbind())
+    }
+
+    /// Append the nodes of `other` to this network, their ids offset by the
+    /// current number of nodes, and add the value edges in `connect` as
+    /// `(parent_idx, child_idx, coupling)` tuples using the merged ids (see
+    /// [`Network::merge`]).
+    #[pyo3(name = "merge", signature = (other, connect=None))]
+    fn py_merge<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        other: PyRef<'py, Network>,
+        connect: Option<Vec<(usize, usize, f64)>>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.merge(&other, connect.unwrap_or_default())?;
+        Ok(slf)
+    }
+
+    #[pyo3(name = "set_coupling")]
+    fn py_set_coupling<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        parent_idx: usize,
+        child_idx: usize,
+        coupling: f64,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.set_coupling(parent_idx, child_idx, coupling)?;
+        Ok(slf)
+    }
+
+    #[pyo3(name = "set_volatility_coupling")]
+    fn py_set_volatility_coupling<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        parent_idx: usize,
+        child_idx: usize,
+        coupling: f64,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.set_volatility_coupling(parent_idx, child_idx, coupling)?;
+        Ok(slf)
+    }
+
+    /// Value-coupling strength of the edge `parent_idx → child_idx`, or
+    /// `None` when the nodes are not connected.
+    #[pyo3(name = "get_coupling")]
+    fn py_get_coupling(&self, parent_idx: usize, child_idx: usize) -> Option<f64> {
+        self.get_coupling(parent_idx, child_idx)
+    }
+
+    /// Volatility-coupling strength of the edge `parent_idx → child_idx`, or
+    /// `None` when the nodes are not connected.
+    #[pyo3(name = "get_volatility_coupling")]
+    fn py_get_volatility_coupling(&self, parent_idx: usize, child_idx: usize) -> Option<f64> {
+        self.get_volatility_coupling(parent_idx, child_idx)
+    }
+
+    #[pyo3(name = "freeze_coupling")]
+    fn py_freeze_coupling<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        parent_idx: usize,
+        child_idx: usize,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.freeze_coupling(parent_idx, child_idx)?;
+        Ok(slf)
+    }
+
+    #[pyo3(name = "unfreeze_coupling")]
+    fn py_unfreeze_coupling<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        parent_idx: usize,
+        child_idx: usize,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.unfreeze_coupling(parent_idx, child_idx)?;
+        Ok(slf)
+    }
+
+    /// Delay the value coupling `parent_idx → child_idx` by `lag` steps (0 or
+    /// 1; see `Network::set_coupling_lag`).
+    #[pyo3(name = "set_coupling_lag", signature = (parent_idx, child_idx, lag=1))]
+    fn py_set_coupling_lag<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        parent_idx: usize,
+        child_idx: usize,
+        lag: usize,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.set_coupling_lag(parent_idx, child_idx, lag)?;
+        Ok(slf)
+    }
+
+    /// Make `parent_idx` a drift parent of `child_idx`, or an ordinary value
+    /// parent again with `drift=False` (see `Network::set_drift_coupling`).
+    #[pyo3(name = "set_drift_coupling", signature = (parent_idx, child_idx, drift=true))]
+    fn py_set_drift_coupling<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        parent_idx: usize,
+        child_idx: usize,
+        drift: bool,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.set_drift_coupling(parent_idx, child_idx, drift)?;
+        Ok(slf)
+    }
+
+    /// Apply the coupling function `name` on the child side of the value edge
+    /// `parent_idx → child_idx`: the parent then reads the child's prediction
+    /// error as `h(mean) - h(expected_mean)`, where the parent-side
+    /// `coupling_fn` maps the parent's mean into the child's prediction.
+    #[pyo3(name = "set_child_coupling_fn")]
+    fn py_set_child_coupling_fn<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        parent_idx: usize,
+        child_idx: usize,
+        name: &str,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.set_child_coupling_fn(parent_idx, child_idx, name)?;
+        Ok(slf)
+    }
+
+    #[pyo3(name = "set_volatility_coupling_fn")]
+    fn py_set_volatility_coupling_fn<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        node_idx: usize,
+        name: &str,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.set_volatility_coupling_fn(node_idx, name)?;
+        Ok(slf)
+    }
+
+    /// What the posterior updates do with a non-positive precision: `"floor"`
+    /// it at a tiny positive value, raise an `"error"` naming the node and its
+    /// children's contributions, or `"reject"` the update and keep the
+    /// previous posterior.
+    #[pyo3(name = "set_negative_precision")]
+    fn py_set_negative_precision<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        policy: &str,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.set_negative_precision(policy)?;
+        Ok(slf)
+    }
+
+    /// Compute the recorded `entropy` from the `"posterior"` precision (the
+    /// default) or from the `"prediction"` (`expected_precision`).
+    #[pyo3(name = "set_entropy_source")]
+    fn py_set_entropy_source<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        source: &str,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.set_entropy_source(source)?;
+        Ok(slf)
+    }
+
+    #[pyo3(name = "set_record_keys", signature = (keys=None))]
+    fn py_set_record_keys<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        keys: Option<Vec<String>>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.set_record_keys(keys)?;
+        Ok(slf)
+    }
+
+    #[pyo3(name = "set_autoconnection")]
+    fn py_set_autoconnection<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        node_idx: usize,
+        value: f64,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.set_autoconnection(node_idx, value)?;
+        Ok(slf)
+    }
+
+    #[pyo3(name = "set_pe_asymmetry")]
+    fn py_set_pe_asymmetry<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        node_idx: usize,
+        value: f64,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.set_pe_asymmetry(node_idx, value)?;
+        Ok(slf)
+    }
+
+    #[pyo3(name = "set_likelihood")]
+    fn py_set_likelihood<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        node_idx: usize,
+        likelihood: &str,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.set_likelihood(node_idx, likelihood)?;
+        Ok(slf)
+    }
+
+    #[pyo3(name = "set_autoconnection_vol")]
+    fn py_set_autoconnection_vol<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        node_idx: usize,
+        value: f64,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.set_autoconnection_vol(node_idx, value)?;
+        Ok(slf)
+    }
+
     #[getter]
     pub fn get_layers<'py>(&self, py: Python<'py>) -> PyResult<Py<PyList>> {
         let py_list = PyList::empty(py);
@@ -1764,63 +7032,432 @@ impl Network {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_state_copies_reach_every_vector_attribute() {
+        let names = NodeVectors::default().fields().map(|(name, _)| name);
+        assert_eq!(names, STATE_VECTOR_FIELDS);
+
+        // A vector filled by the updates only survives the state round trip.
+        let mut network = Network::new("eHGF");
+        network
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        network.attributes.vectors[0].learning_rate_parents = vec![0.5];
+        let state = network.get_state();
+        let snapshot = network.checkpoint();
+        network.attributes.vectors[0].learning_rate_parents = vec![0.0];
+        network.load_state(&state).unwrap();
+        assert_eq!(network.attributes.vectors[0].learning_rate_parents, [0.5]);
+        network.attributes.vectors[0].learning_rate_parents = vec![0.0];
+        network.restore(&snapshot).unwrap();
+        assert_eq!(network.attributes.vectors[0].learning_rate_parents, [0.5]);
+    }
+
+    #[test]
+    fn test_vector_trajectory_rows_of_changing_length() {
+        let mut trajectory = VectorTrajectory::default();
+        for (t, row) in [&[][..], &[1.0], &[2.0, 3.0], &[4.0], &[]]
+            .into_iter()
+            .enumerate()
+        {
+            trajectory.push(row, t);
+        }
+        assert_eq!(trajectory.len(), 5);
+        assert_eq!(trajectory.stride, 2);
+        let rows: Vec<Vec<Option<f64>>> = (0..5)
+            .map(|t| {
+                trajectory[t]
+                    .iter()
+                    .map(|&x| (!x.is_nan()).then_some(x))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                vec![None, None],
+                vec![Some(1.0), None],
+                vec![Some(2.0), Some(3.0)],
+                vec![Some(4.0), None],
+                vec![None, None],
+            ]
+        );
+    }
+
     #[test]
     fn test_exponential_family_gaussian() {
         let mut network = Network::new("eHGF");
-        network.add_nodes("ef-state", 1, None, None, None, None, None, None);
+        network
+            .add_nodes("ef-state", 1, AddNodesOptions::default())
+            .unwrap();
 
         let input_data: Vec<Vec<f64>> = vec![vec![1.0], vec![1.3], vec![1.5], vec![1.7]];
         network.set_update_sequence();
-        network.input_data(input_data, None, true);
+        network
+            .input_data(input_data, None, true, InputDataOptions::default())
+            .unwrap();
+    }
+
+    #[test]
+    fn test_exponential_family_forgetting_tracks_a_mean_shift() {
+        // The mean of the observations jumps from 0 to 5 after 50 steps.
+        let input_data: Vec<Vec<f64>> = (0..70)
+            .map(|t| vec![if t < 50 { 0.0 } else { 5.0 }])
+            .collect();
+        let mean_estimate = |forgetting: f64| {
+            let mut network = Network::new("eHGF");
+            network
+                .add_nodes(
+                    "ef-state",
+                    1,
+                    AddNodesOptions {
+                        additional_parameters: Some(HashMap::from([
+                            ("nus".to_string(), 50.0),
+                            ("forgetting".to_string(), forgetting),
+                        ])),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            network
+                .input_data(input_data.clone(), None, true, InputDataOptions::default())
+                .unwrap();
+            network.node_trajectories.nodes[0].xis[69][0]
+        };
+
+        // With its 50 pseudo-observations the default filter has barely
+        // moved 20 steps after the shift, while forgetting re-adapts.
+        let default = mean_estimate(1.0);
+        let forgetful = mean_estimate(0.05);
+        assert!(default < 2.0, "{default}");
+        assert!(forgetful > 4.5, "{forgetful}");
+    }
+
+    #[test]
+    fn test_exponential_family_welford_variance_survives_a_large_offset() {
+        let observations = |offset: f64| -> Vec<Vec<f64>> {
+            (0..5000)
+                .map(|t| vec![offset + (1.7 * t as f64).sin()])
+                .collect()
+        };
+        let tracked = |offset: f64, welford: f64| {
+            let mut network = Network::new("eHGF");
+            network
+                .add_nodes(
+                    "ef-state",
+                    1,
+                    AddNodesOptions {
+                        additional_parameters: Some(HashMap::from([(
+                            "welford".to_string(),
+                            welford,
+                        )])),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            network
+                .input_data(
+                    observations(offset),
+                    None,
+                    true,
+                    InputDataOptions::default(),
+                )
+                .unwrap();
+            let trajectory = &network.node_trajectories.nodes[0];
+            (
+                trajectory.column("tracked_mean").unwrap()[4999],
+                trajectory.column("tracked_variance").unwrap()[4999],
+            )
+        };
+
+        // The variance is shift-invariant: the centred run is the reference.
+        let (_, reference) = tracked(0.0, 0.0);
+        assert!(reference > 0.1, "{reference}");
+
+        let (mean, variance) = tracked(1e8, 1.0);
+        assert!((mean - 1e8 - tracked(0.0, 1.0).0).abs() < 1e-6, "{mean}");
+        assert!(
+            (variance - reference).abs() < 1e-6,
+            "{variance} vs {reference}"
+        );
+
+        // `E[x²] − E[x]²` near 1e16 is only resolved to a few units.
+        let (_, naive) = tracked(1e8, 0.0);
+        assert!((naive - reference).abs() > 0.1, "{naive} vs {reference}");
+    }
+
+    #[test]
+    fn test_add_nodes_coupling() {
+        let mut network = Network::new("eHGF");
+        network
+            .add_nodes("continuous-state", 2, AddNodesOptions::default())
+            .unwrap();
+        // Node 2 declares its children, node 3 its parent.
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(vec![0, 1].into()),
+                    coupling: Some((-0.5).into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_parents: Some(2.into()),
+                    coupling: Some(vec![-0.5].into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        let vectors = &network.attributes.vectors;
+        assert_eq!(vectors[0].value_coupling_parents, vec![-0.5]);
+        assert_eq!(vectors[1].value_coupling_parents, vec![-0.5]);
+        assert_eq!(vectors[3].value_coupling_parents, vec![-0.5]);
+        // Children 0 and 1 from node 2, child 3 from node 3.
+        assert_eq!(vectors[2].value_coupling_children, vec![-0.5; 3]);
+
+        // A list must hold one strength per declared value edge.
+        let err = network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(vec![0, 1].into()),
+                    coupling: Some(vec![1.0].into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            NetworkError::DimensionMismatch {
+                expected: 2,
+                found: 1,
+                ..
+            }
+        ));
+        assert_eq!(network.edges.len(), 4);
+    }
+
+    #[test]
+    fn test_trajectory_buffer_layout() {
+        let mut network = Network::new("eHGF");
+        network
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                2,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+            .input_data(
+                vec![vec![0.5], vec![-0.2], vec![0.1]],
+                None,
+                true,
+                InputDataOptions::default(),
+            )
+            .unwrap();
+
+        let traj = &network.node_trajectories.nodes[0];
+        assert_eq!(traj.n_time, 3);
+        assert_eq!(traj.data.len(), 3 * TRAJECTORY_FIELDS.len());
+        assert_eq!(traj.mean().to_vec(), vec![0.5, -0.2, 0.1]);
+        assert_eq!(
+            traj.column("expected_precision").unwrap().to_vec(),
+            traj.expected_precision().to_vec()
+        );
+        assert!(traj.column("unknown").is_none());
+
+        // The input node has two value parents, recorded with stride 2.
+        assert_eq!(traj.value_coupling_parents.stride, 2);
+        assert_eq!(traj.value_coupling_parents.len(), 3);
+        assert_eq!(&traj.value_coupling_parents[2], &[1.0, 1.0]);
+        assert!(traj.xis.is_empty());
+    }
+
+    #[test]
+    fn test_record_keys_filters_the_trajectories() {
+        let mut network = Network::new("eHGF");
+        network
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                2,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let data = vec![vec![0.5], vec![-0.2], vec![0.1]];
+        network
+            .input_data(data.clone(), None, true, InputDataOptions::default())
+            .unwrap();
+        let full_mean = network.node_trajectories.nodes[1].mean().to_vec();
+
+        assert!(matches!(
+            network.set_record_keys(Some(vec!["mean".into(), "nonsense".into()])),
+            Err(NetworkError::InvalidParameter { parameter, .. }) if parameter == "nonsense"
+        ));
+        network
+            .set_record_keys(Some(vec![
+                "expected_precision".into(),
+                "mean".into(),
+                "value_coupling_children".into(),
+            ]))
+            .unwrap();
+        network.attributes.states[1].mean = 0.0;
+        network.attributes.states[2].mean = 0.0;
+        network.attributes.states[1].precision = 1.0;
+        network.attributes.states[2].precision = 1.0;
+        network
+            .input_data(data, None, true, InputDataOptions::default())
+            .unwrap();
+
+        // Only the requested columns are stored, in TRAJECTORY_FIELDS order.
+        let traj = &network.node_trajectories.nodes[1];
+        assert_eq!(traj.data.len(), 3 * 2);
+        assert_eq!(traj.mean().to_vec(), full_mean);
+        assert!(traj.column("precision").is_none());
+        assert!(traj.precision().is_empty());
+        assert_eq!(
+            traj.keys("continuous-state"),
+            vec!["mean", "expected_precision", "value_coupling_children"]
+        );
+        assert_eq!(traj.value_coupling_children.len(), 3);
+        let input = &network.node_trajectories.nodes[0];
+        assert!(input.value_coupling_parents.is_empty());
+        assert!(input.learning_rate_parents.is_empty());
+
+        network.set_record_keys(None).unwrap();
+        network
+            .input_data(vec![vec![0.3]], None, true, InputDataOptions::default())
+            .unwrap();
+        let traj = &network.node_trajectories.nodes[1];
+        assert_eq!(traj.data.len(), TRAJECTORY_FIELDS.len());
+    }
+
+    #[test]
+    fn test_trajectory_key_order_is_stable() {
+        let build = || {
+            let mut network = Network::new("eHGF");
+            network
+                .add_nodes("continuous-state", 1, AddNodesOptions::default())
+                .unwrap();
+            network
+                .add_nodes(
+                    "volatile-state",
+                    1,
+                    AddNodesOptions {
+                        value_children: Some(0.into()),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            network
+                .input_data(
+                    vec![vec![0.1], vec![0.4]],
+                    None,
+                    true,
+                    InputDataOptions::default(),
+                )
+                .unwrap();
+            network
+        };
+        let keys = |network: &Network| -> Vec<Vec<&'static str>> {
+            network
+                .node_trajectories
+                .nodes
+                .iter()
+                .zip(&network.edges)
+                .map(|(traj, edge)| traj.keys(&edge.node_type))
+                .collect()
+        };
+
+        let (first, second) = (build(), build());
+        assert_eq!(keys(&first), keys(&second));
+
+        let input_keys = &keys(&first)[0];
+        assert_eq!(&input_keys[..2], &["mean", "expected_mean"]);
+        assert_eq!(input_keys.last(), Some(&"learning_rate_parents"));
+        assert_eq!(keys(&first)[1].last(), Some(&"value_coupling_children"));
     }
 
     #[test]
     fn test_volatile_node_ehgf_matches_explicit() {
         let mut volatile_net = Network::new("eHGF");
-        volatile_net.add_nodes("continuous-state", 1, None, None, None, None, None, None);
-        volatile_net.add_nodes(
-            "volatile-state",
-            1,
-            None,
-            Some(0.into()),
-            None,
-            None,
-            None,
-            Some(HashMap::from([("autoconnection_strength".into(), 1.0)])),
-        );
+        volatile_net
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        volatile_net
+            .add_nodes(
+                "volatile-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    additional_parameters: Some(HashMap::from([(
+                        "autoconnection_strength".into(),
+                        1.0,
+                    )])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
         volatile_net.set_update_sequence();
 
         let input_data: Vec<Vec<f64>> = (0..20).map(|i| vec![(i as f64) * 0.1]).collect();
-        volatile_net.input_data(input_data.clone(), None, true);
+        volatile_net
+            .input_data(input_data.clone(), None, true, InputDataOptions::default())
+            .unwrap();
 
         let mut explicit_net = Network::new("eHGF");
-        explicit_net.add_nodes("continuous-state", 1, None, None, None, None, None, None);
-        explicit_net.add_nodes(
-            "continuous-state",
-            1,
-            None,
-            Some(0.into()),
-            None,
-            None,
-            None,
-            // The fused volatile node's value level has no tonic volatility, so
-            // the explicit value node must set tonic_volatility = 0.0 to match
-            // (its volatility parent keeps the default, mirroring the volatility
-            // level's tonic_volatility_vol).
-            Some(HashMap::from([("tonic_volatility".into(), 0.0)])),
-        );
-        explicit_net.add_nodes(
-            "continuous-state",
-            1,
-            None,
-            None,
-            None,
-            Some(1.into()),
-            None,
-            None,
-        );
+        explicit_net
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        explicit_net
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    // The fused volatile node's value level has no tonic volatility, so
+                    // the explicit value node must set tonic_volatility = 0.0 to match
+                    // (its volatility parent keeps the default, mirroring the volatility
+                    // level's tonic_volatility_vol).
+                    additional_parameters: Some(HashMap::from([("tonic_volatility".into(), 0.0)])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        explicit_net
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    volatility_children: Some(1.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
         explicit_net.set_update_sequence();
-        explicit_net.input_data(input_data, None, true);
+        explicit_net
+            .input_data(input_data, None, true, InputDataOptions::default())
+            .unwrap();
 
         assert_volatile_matches_explicit(&volatile_net, &explicit_net);
     }
@@ -1828,50 +7465,63 @@ mod tests {
     #[test]
     fn test_volatile_node_standard_matches_explicit() {
         let mut volatile_net = Network::new("standard");
-        volatile_net.add_nodes("continuous-state", 1, None, None, None, None, None, None);
-        volatile_net.add_nodes(
-            "volatile-state",
-            1,
-            None,
-            Some(0.into()),
-            None,
-            None,
-            None,
-            Some(HashMap::from([("autoconnection_strength".into(), 1.0)])),
-        );
+        volatile_net
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        volatile_net
+            .add_nodes(
+                "volatile-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    additional_parameters: Some(HashMap::from([(
+                        "autoconnection_strength".into(),
+                        1.0,
+                    )])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
         volatile_net.set_update_sequence();
 
         let input_data: Vec<Vec<f64>> = (0..20).map(|i| vec![(i as f64) * 0.1]).collect();
-        volatile_net.input_data(input_data.clone(), None, true);
+        volatile_net
+            .input_data(input_data.clone(), None, true, InputDataOptions::default())
+            .unwrap();
 
         let mut explicit_net = Network::new("standard");
-        explicit_net.add_nodes("continuous-state", 1, None, None, None, None, None, None);
-        explicit_net.add_nodes(
-            "continuous-state",
-            1,
-            None,
-            Some(0.into()),
-            None,
-            None,
-            None,
-            // The fused volatile node's value level has no tonic volatility, so
-            // the explicit value node must set tonic_volatility = 0.0 to match
-            // (its volatility parent keeps the default, mirroring the volatility
-            // level's tonic_volatility_vol).
-            Some(HashMap::from([("tonic_volatility".into(), 0.0)])),
-        );
-        explicit_net.add_nodes(
-            "continuous-state",
-            1,
-            None,
-            None,
-            None,
-            Some(1.into()),
-            None,
-            None,
-        );
+        explicit_net
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        explicit_net
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    // The fused volatile node's value level has no tonic volatility, so
+                    // the explicit value node must set tonic_volatility = 0.0 to match
+                    // (its volatility parent keeps the default, mirroring the volatility
+                    // level's tonic_volatility_vol).
+                    additional_parameters: Some(HashMap::from([("tonic_volatility".into(), 0.0)])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        explicit_net
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    volatility_children: Some(1.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
         explicit_net.set_update_sequence();
-        explicit_net.input_data(input_data, None, true);
+        explicit_net
+            .input_data(input_data, None, true, InputDataOptions::default())
+            .unwrap();
 
         assert_volatile_matches_explicit(&volatile_net, &explicit_net);
     }
@@ -1879,50 +7529,63 @@ mod tests {
     #[test]
     fn test_volatile_node_unbounded_matches_explicit() {
         let mut volatile_net = Network::new("unbounded");
-        volatile_net.add_nodes("continuous-state", 1, None, None, None, None, None, None);
-        volatile_net.add_nodes(
-            "volatile-state",
-            1,
-            None,
-            Some(0.into()),
-            None,
-            None,
-            None,
-            Some(HashMap::from([("autoconnection_strength".into(), 1.0)])),
-        );
+        volatile_net
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        volatile_net
+            .add_nodes(
+                "volatile-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    additional_parameters: Some(HashMap::from([(
+                        "autoconnection_strength".into(),
+                        1.0,
+                    )])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
         volatile_net.set_update_sequence();
 
         let input_data: Vec<Vec<f64>> = (0..20).map(|i| vec![(i as f64) * 0.1]).collect();
-        volatile_net.input_data(input_data.clone(), None, true);
+        volatile_net
+            .input_data(input_data.clone(), None, true, InputDataOptions::default())
+            .unwrap();
 
         let mut explicit_net = Network::new("unbounded");
-        explicit_net.add_nodes("continuous-state", 1, None, None, None, None, None, None);
-        explicit_net.add_nodes(
-            "continuous-state",
-            1,
-            None,
-            Some(0.into()),
-            None,
-            None,
-            None,
-            // The fused volatile node's value level has no tonic volatility, so
-            // the explicit value node must set tonic_volatility = 0.0 to match
-            // (its volatility parent keeps the default, mirroring the volatility
-            // level's tonic_volatility_vol).
-            Some(HashMap::from([("tonic_volatility".into(), 0.0)])),
-        );
-        explicit_net.add_nodes(
-            "continuous-state",
-            1,
-            None,
-            None,
-            None,
-            Some(1.into()),
-            None,
-            None,
-        );
+        explicit_net
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        explicit_net
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    // The fused volatile node's value level has no tonic volatility, so
+                    // the explicit value node must set tonic_volatility = 0.0 to match
+                    // (its volatility parent keeps the default, mirroring the volatility
+                    // level's tonic_volatility_vol).
+                    additional_parameters: Some(HashMap::from([("tonic_volatility".into(), 0.0)])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        explicit_net
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    volatility_children: Some(1.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
         explicit_net.set_update_sequence();
-        explicit_net.input_data(input_data, None, true);
+        explicit_net
+            .input_data(input_data, None, true, InputDataOptions::default())
+            .unwrap();
 
         assert_volatile_matches_explicit(&volatile_net, &explicit_net);
     }
@@ -1933,17 +7596,17 @@ mod tests {
         let exp_traj = &explicit_net.node_trajectories.nodes[1];
 
         // Value-level keys
-        let vol_fields: Vec<(&Vec<f64>, &str)> = vec![
-            (&vol_traj.mean, "mean"),
-            (&vol_traj.expected_mean, "expected_mean"),
-            (&vol_traj.precision, "precision"),
-            (&vol_traj.expected_precision, "expected_precision"),
+        let vol_fields: Vec<(TrajectoryColumn, &str)> = vec![
+            (vol_traj.mean(), "mean"),
+            (vol_traj.expected_mean(), "expected_mean"),
+            (vol_traj.precision(), "precision"),
+            (vol_traj.expected_precision(), "expected_precision"),
         ];
-        let exp_fields: Vec<(&Vec<f64>, &str)> = vec![
-            (&exp_traj.mean, "mean"),
-            (&exp_traj.expected_mean, "expected_mean"),
-            (&exp_traj.precision, "precision"),
-            (&exp_traj.expected_precision, "expected_precision"),
+        let exp_fields: Vec<(TrajectoryColumn, &str)> = vec![
+            (exp_traj.mean(), "mean"),
+            (exp_traj.expected_mean(), "expected_mean"),
+            (exp_traj.precision(), "precision"),
+            (exp_traj.expected_precision(), "expected_precision"),
         ];
 
         for ((vol, key), (exp, _)) in vol_fields.iter().zip(exp_fields.iter()) {
@@ -1960,23 +7623,23 @@ mod tests {
         }
 
         let exp2_traj = &explicit_net.node_trajectories.nodes[2];
-        let vol_key_map: Vec<(&Vec<f64>, &Vec<f64>, &str, &str)> = vec![
-            (&vol_traj.mean_vol, &exp2_traj.mean, "mean_vol", "mean"),
+        let vol_key_map: Vec<(TrajectoryColumn, TrajectoryColumn, &str, &str)> = vec![
+            (vol_traj.mean_vol(), exp2_traj.mean(), "mean_vol", "mean"),
             (
-                &vol_traj.expected_mean_vol,
-                &exp2_traj.expected_mean,
+                vol_traj.expected_mean_vol(),
+                exp2_traj.expected_mean(),
                 "expected_mean_vol",
                 "expected_mean",
             ),
             (
-                &vol_traj.precision_vol,
-                &exp2_traj.precision,
+                vol_traj.precision_vol(),
+                exp2_traj.precision(),
                 "precision_vol",
                 "precision",
             ),
             (
-                &vol_traj.expected_precision_vol,
-                &exp2_traj.expected_precision,
+                vol_traj.expected_precision_vol(),
+                exp2_traj.expected_precision(),
                 "expected_precision_vol",
                 "expected_precision",
             ),
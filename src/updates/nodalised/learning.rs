@@ -1,18 +1,39 @@
 use crate::model::network::Network;
-use crate::utils::set_coupling::set_coupling;
+
+/// Tonic drift update.
+///
+/// Moves `tonic_drift` by `tonic_drift_lr` times the value prediction error
+/// per unit of time, `(μ − μ̂) / Δt`. For a node that drifts on its own
+/// (autoconnection 1, no value parents), `μ̂ = μ_{t−1} + drift · Δt`, so the
+/// step is `tonic_drift_lr · (Δμ / Δt − drift)`: the drift follows an
+/// exponential average of the observed change of the posterior mean. Nodes
+/// with a NaN `tonic_drift_lr` are left untouched.
+pub fn learning_tonic_drift(network: &mut Network, node_idx: usize, time_step: f64) {
+    let state = &mut network.attributes.states[node_idx];
+    if state.tonic_drift_lr.is_nan() {
+        return;
+    }
+    let tonic_drift =
+        state.tonic_drift + state.tonic_drift_lr * (state.mean - state.expected_mean) / time_step;
+    if tonic_drift.is_finite() {
+        state.tonic_drift = tonic_drift;
+    }
+}
 
 /// Unified weights update.
 ///
 /// Computes a gradient according to `learning_kind` (standard /
 /// precision_weighted / precision_ratio), then scales it by `lr` uniformly.
 /// When Adam state is present, the gradient is filtered through Adam instead.
+/// Each gradient is first clipped to `±coupling_grad_clip` of the child, so
+/// that a single outlying prediction error cannot move a coupling by more
+/// than `lr · coupling_grad_clip`. Couplings flagged in `coupling_frozen` are left untouched.
 pub fn learning_weights(network: &mut Network, node_idx: usize, _time_step: f64) {
     let is_binary = network.edges[node_idx].node_type == "binary-state";
 
-    let n_parents = match &network.edges[node_idx].value_parents {
-        Some(vp) => vp.len(),
-        None => return,
-    };
+    if network.edges[node_idx].value_parents.is_none() {
+        return;
+    }
 
     // --- read-only phase: copy all the scalars we need ---------------
     let child_mean = network.attributes.states[node_idx].mean;
@@ -20,18 +41,25 @@ pub fn learning_weights(network: &mut Network, node_idx: usize, _time_step: f64)
     let child_precision = network.attributes.states[node_idx].precision;
 
     let lr_val = network.attributes.states[node_idx].lr;
+    let grad_clip = network.attributes.states[node_idx].coupling_grad_clip;
     // NaN lr means "no lr set" → skip update for this node.
     if lr_val.is_nan() {
         return;
     }
 
-    let learning_kind = network.edges[node_idx].learning_kind.clone();
+    let precision_ratio = network.edges[node_idx].learning_kind == "precision_ratio";
+    let standard = network.edges[node_idx].learning_kind == "standard";
 
     let pe = child_mean - child_expected_mean;
 
     // --- per-parent update -------------------------------------------
-    for i in 0..n_parents {
-        let parent_idx = network.edges[node_idx].value_parents.as_ref().unwrap()[i];
+    for i in 0..network.edge_lookup.value_parents[node_idx].len() {
+        let edge = network.edge_lookup.value_parents[node_idx][i];
+        let parent_idx = edge.parent_idx;
+
+        if network.attributes.vectors[node_idx].coupling_frozen.get(i) == Some(&1.0) {
+            continue;
+        }
 
         let coupling = network.attributes.vectors[node_idx]
             .value_coupling_parents
@@ -45,8 +73,7 @@ pub fn learning_weights(network: &mut Network, node_idx: usize, _time_step: f64)
         let prosp_act = if is_binary {
             crate::math::sigmoid(parent_mean)
         } else {
-            let coupling_fn = network.attributes.fn_ptrs[parent_idx].coupling_fn;
-            match coupling_fn {
+            match edge.coupling_fn {
                 Some(cf) => (cf.f)(parent_mean),
                 None => parent_mean,
             }
@@ -55,16 +82,21 @@ pub fn learning_weights(network: &mut Network, node_idx: usize, _time_step: f64)
         // Compute the gradient according to learning_kind.
         // Binary nodes skip precision multiplication — the Bernoulli
         // variance is already embedded in the binary prediction-error formula.
-        let gradient = if learning_kind == "precision_ratio" {
+        let gradient = if precision_ratio {
             let parent_precision = network.attributes.states[parent_idx].precision;
             let kalman_gain = child_precision / (parent_precision + child_precision);
             kalman_gain * pe * prosp_act
-        } else if learning_kind == "standard" || is_binary {
+        } else if standard || is_binary {
             pe * prosp_act
         } else {
             // "precision_weighted" (default)
             pe * child_precision * prosp_act
         };
+        let gradient = if gradient.abs() > grad_clip {
+            grad_clip.copysign(gradient)
+        } else {
+            gradient
+        };
 
         // Apply lr uniformly: Adam filter if state is present, otherwise
         // direct scaling by lr_val.
@@ -83,6 +115,20 @@ pub fn learning_weights(network: &mut Network, node_idx: usize, _time_step: f64)
             new_value_coupling
         };
 
-        set_coupling(network, parent_idx, node_idx, new_value_coupling);
+        // Write both sides of the edge through the precomputed positions
+        // (same effect as `set_coupling`, without re-scanning the adjacency lists).
+        if let Some(c) = network.attributes.vectors[node_idx]
+            .value_coupling_parents
+            .get_mut(i)
+        {
+            *c = new_value_coupling;
+        }
+        if let Some(c) = edge.coupling_idx.and_then(|pos| {
+            network.attributes.vectors[parent_idx]
+                .value_coupling_children
+                .get_mut(pos)
+        }) {
+            *c = new_value_coupling;
+        }
     }
 }
@@ -1,8 +1,105 @@
-use crate::model::network::Network;
+use crate::math::{bounded_to_latent, wrap_angle};
+use crate::{error::NetworkError, model::network::Network};
+
+/// Check that input node `node_idx` can take `observation`: binary inputs only
+/// accept 0 or 1, bounded inputs values within their bounds and log-normal
+/// inputs positive values.
+pub fn check_observation(
+    network: &Network,
+    node_idx: usize,
+    observation: f64,
+) -> Result<(), NetworkError> {
+    let kind = node_type(network, node_idx);
+    if kind == "binary-input" && observation != 0.0 && observation != 1.0 {
+        return Err(NetworkError::InvalidObservation {
+            node_idx,
+            value: observation,
+            accepted: "0 or 1",
+        });
+    }
+    if kind == "bounded-state" && !observation.is_nan() {
+        let state = &network.attributes.states[node_idx];
+        if !(state.lower_bound..=state.upper_bound).contains(&observation) {
+            return Err(NetworkError::InvalidObservation {
+                node_idx,
+                value: observation,
+                accepted: "a value within the node's bounds",
+            });
+        }
+    }
+    if kind == "lognormal-state" && observation <= 0.0 {
+        return Err(NetworkError::InvalidObservation {
+            node_idx,
+            value: observation,
+            accepted: "a positive value",
+        });
+    }
+    Ok(())
+}
 
 /// Inject new observations into an input node
-pub fn observation_update(network: &mut Network, node_idx: usize, observations: f64) {
-    network.attributes.states[node_idx].mean = observations;
+pub fn observation_update(
+    network: &mut Network,
+    node_idx: usize,
+    observations: f64,
+) -> Result<(), NetworkError> {
+    check_observation(network, node_idx, observations)?;
+    let state = &network.attributes.states[node_idx];
+    let latent = match node_type(network, node_idx) {
+        "bounded-state" => bounded_to_latent(
+            observations,
+            state.lower_bound,
+            state.upper_bound,
+            state.bound_epsilon,
+        ),
+        "lognormal-state" => observations.ln(),
+        "circular-state" => wrap_angle(observations),
+        _ => observations,
+    };
+    network.attributes.states[node_idx].mean = latent;
+    Ok(())
+}
+
+/// Fuse the streams of aggregate-input node `node_idx`, one value per entry
+/// of its `input_precision`, into a single observation.
+///
+/// The streams are independent Gaussian measurements of the same quantity,
+/// so the fused observation is their precision-weighted average
+/// `x = Σ_i π_i x_i / Σ_i π_i`, of precision `Σ_i π_i`. Missing streams (NaN,
+/// or beyond the end of `observations`) are left out of both sums, which
+/// renormalises the weights of the others. When every stream is missing, the
+/// node is marked as not `observed` and its mean is held at the prediction.
+pub fn aggregate_observation_update(network: &mut Network, node_idx: usize, observations: &[f64]) {
+    let (mut weighted_sum, mut fused_precision) = (0.0, 0.0);
+    let input_precision = &network.attributes.vectors[node_idx].input_precision;
+    for (&observation, &precision) in observations.iter().zip(input_precision) {
+        if !observation.is_nan() {
+            weighted_sum += precision * observation;
+            fused_precision += precision;
+        }
+    }
+
+    let state = &mut network.attributes.states[node_idx];
+    if fused_precision > 0.0 {
+        state.mean = weighted_sum / fused_precision;
+        state.precision = fused_precision;
+        state.expected_precision = fused_precision;
+        state.conditional_expected_precision = fused_precision;
+        state.observed = 1.0;
+    } else {
+        state.mean = state.expected_mean;
+        state.observed = 0.0;
+    }
+}
+
+/// Kind of `node_idx`: bounded-state nodes filter their observations on the
+/// logit scale of their interval, lognormal-state nodes on the log scale and
+/// circular-state nodes wrapped into `(−π, π]`.
+fn node_type(network: &Network, node_idx: usize) -> &str {
+    network
+        .edges
+        .get(node_idx)
+        .map_or("", |e| e.node_type.as_str())
 }
 
 /// Set predictor values on top-layer nodes.
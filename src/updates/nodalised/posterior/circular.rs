@@ -0,0 +1,33 @@
+use crate::math::wrap_angle;
+use crate::model::network::Network;
+use crate::updates::nodalised::posterior::continuous::{
+    posterior_update_continuous_state_node, posterior_update_continuous_state_node_mean_field,
+};
+
+/// Posterior update for a circular-state node with children
+///
+/// The update of a continuous node, from the wrapped prediction errors of the
+/// children, with the posterior mean wrapped back into `(−π, π]`.
+pub fn posterior_update_circular_state_node(
+    network: &mut Network,
+    node_idx: usize,
+    time_step: f64,
+) {
+    posterior_update_continuous_state_node(network, node_idx, time_step);
+    wrap_mean(network, node_idx);
+}
+
+/// Mean-field posterior update for a circular-state node.
+pub fn posterior_update_circular_state_node_mean_field(
+    network: &mut Network,
+    node_idx: usize,
+    time_step: f64,
+) {
+    posterior_update_continuous_state_node_mean_field(network, node_idx, time_step);
+    wrap_mean(network, node_idx);
+}
+
+fn wrap_mean(network: &mut Network, node_idx: usize) {
+    let state = &mut network.attributes.states[node_idx];
+    state.mean = wrap_angle(state.mean);
+}
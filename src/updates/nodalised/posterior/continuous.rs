@@ -1,3 +1,9 @@
+use super::{
+    applied_pe_scaling, child_pe_weight, child_value_pe, pe_asymmetry_scaling,
+    record_learning_rate, resolve_posterior_precision, value_child_coupling,
+};
+use crate::error::NetworkError;
+use crate::math::{coupling_at, CouplingFn};
 use crate::model::network::Network;
 
 /// Principal branch of the Lambert W function for z >= 0.
@@ -41,26 +47,30 @@ fn lambert_w0(z: f64) -> f64 {
 /// contribution.
 ///
 /// Volatility coupling is unchanged.
-fn precision_update_from_children(network: &Network, node_idx: usize) -> f64 {
+fn precision_update_from_children(
+    network: &Network,
+    node_idx: usize,
+    time_step: f64,
+    on_child: &mut impl FnMut(usize, f64),
+) -> f64 {
     let mut precision_wpe = 0.0;
 
     // --- Value coupling ---
     if let Some(ref vc_idxs) = network.edges[node_idx].value_children {
-        let coupling_strengths = &network.attributes.vectors[node_idx].value_coupling_children;
         let parent_mean = network.attributes.states[node_idx].mean;
         let coupling_fn = network.attributes.fn_ptrs[node_idx].coupling_fn;
 
         for (i, &child_idx) in vc_idxs.iter().enumerate() {
             let child_state = &network.attributes.states[child_idx];
             let child_expected_precision = child_state.expected_precision;
-            let observed = child_state.observed;
-            let kappa = coupling_strengths.get(i).copied().unwrap_or(1.0);
+            let weight = child_pe_weight(child_state);
+            let kappa = value_child_coupling(network, node_idx, i, time_step);
 
             let (coupling_fn_prime_sq, coupling_fn_second_term) = match coupling_fn {
                 Some(cf) => {
                     let g_prime = (cf.df)(parent_mean);
                     let g_second = (cf.d2f)(parent_mean);
-                    let child_vape = child_state.value_prediction_error;
+                    let child_vape = child_value_pe(network, node_idx, i, child_state);
                     (g_prime.powi(2), kappa * g_second * child_vape)
                 }
                 None => (1.0, 0.0),
@@ -74,11 +84,8 @@ fn precision_update_from_children(network: &Network, node_idx: usize) -> f64 {
             // exponential-family, and Dirichlet children, plus any Gaussian
             // leaf, fall back to the canonical predicted-precision factor π̃_a
             // (paper's Limit 3, π_a → ∞).
-            let child_node_type = network.edges[child_idx].node_type.as_str();
             let child_is_gaussian_interior =
-                matches!(child_node_type, "continuous-state" | "volatile-state")
-                    && (network.edges[child_idx].value_children.is_some()
-                        || network.edges[child_idx].volatility_children.is_some());
+                network.edge_lookup.value_children[node_idx][i].gaussian_interior;
             let effective_child_precision = if child_is_gaussian_interior {
                 // π_y = π_a − π̃_a; the Schur complement carries the *conditional*
                 // predicted precision π̂_a (stored on the child). Using the
@@ -91,9 +98,11 @@ fn precision_update_from_children(network: &Network, node_idx: usize) -> f64 {
                 child_expected_precision
             };
 
-            precision_wpe += (effective_child_precision
+            let contribution = (effective_child_precision
                 * (kappa.powi(2) * coupling_fn_prime_sq - coupling_fn_second_term))
-                * observed;
+                * weight;
+            precision_wpe += contribution;
+            on_child(child_idx, contribution);
         }
     }
 
@@ -101,18 +110,28 @@ fn precision_update_from_children(network: &Network, node_idx: usize) -> f64 {
     if let Some(ref volc_idxs) = network.edges[node_idx].volatility_children {
         let vol_coupling_strengths =
             &network.attributes.vectors[node_idx].volatility_coupling_children;
+        let (_, g_prime, g_second) = coupling_at(
+            network.attributes.fn_ptrs[node_idx].volatility_coupling_fn,
+            network.attributes.states[node_idx].mean,
+        );
 
         for (i, &child_idx) in volc_idxs.iter().enumerate() {
             let child_state = &network.attributes.states[child_idx];
             let effective_precision = child_state.effective_precision;
             let volatility_pe = child_state.volatility_prediction_error;
-            let observed = child_state.observed;
+            let weight = child_pe_weight(child_state);
             let kappa = vol_coupling_strengths.get(i).copied().unwrap_or(1.0);
-
-            precision_wpe += (0.5 * (kappa * effective_precision).powi(2)
-                + (kappa * effective_precision).powi(2) * volatility_pe
-                - 0.5 * kappa.powi(2) * effective_precision * volatility_pe)
-                * observed;
+            // Through a volatility coupling function g the coupling acts with
+            // the local slope κ g', plus a curvature term in κ g''.
+            let slope = kappa * g_prime;
+
+            let contribution = (0.5 * (slope * effective_precision).powi(2)
+                + (slope * effective_precision).powi(2) * volatility_pe
+                - 0.5 * slope.powi(2) * effective_precision * volatility_pe
+                - 0.5 * kappa * g_second * effective_precision * volatility_pe)
+                * weight;
+            precision_wpe += contribution;
+            on_child(child_idx, contribution);
         }
     }
 
@@ -131,21 +150,32 @@ fn precision_update_from_children(network: &Network, node_idx: usize) -> f64 {
 /// what makes the multi-child mean exact rather than a sum of independent
 /// single-child RTS gains. For leaves and non-Gaussian children π_y = 0 and g_a
 /// collapses to π̃_a (= `child.expected_precision`), recovering the canonical gain.
-fn mean_update_from_children(network: &Network, node_idx: usize, node_precision: f64) -> f64 {
+///
+/// There is no normalisation by the number of children: independent children
+/// each contribute their evidence, so `n` children observing the same value
+/// move the parent like one child with `n` times their precision.
+fn mean_update_from_children(
+    network: &mut Network,
+    node_idx: usize,
+    time_step: f64,
+    node_precision: f64,
+) -> (f64, f64) {
+    let pe_asymmetry = network.attributes.states[node_idx].pe_asymmetry;
     let mut value_pwpe = 0.0;
+    let mut unscaled_value_pwpe = 0.0;
     let mut volatility_pwpe = 0.0;
 
     // --- Value coupling mean update ---
     if let Some(ref vc_idxs) = network.edges[node_idx].value_children {
-        let coupling_strengths = &network.attributes.vectors[node_idx].value_coupling_children;
         let parent_mean = network.attributes.states[node_idx].mean;
         let coupling_fn = network.attributes.fn_ptrs[node_idx].coupling_fn;
 
         for (i, &child_idx) in vc_idxs.iter().enumerate() {
             let child_state = &network.attributes.states[child_idx];
             let child_expected_precision = child_state.expected_precision;
-            let child_vape = child_state.value_prediction_error * child_state.observed;
-            let kappa = coupling_strengths.get(i).copied().unwrap_or(1.0);
+            let child_vape =
+                child_value_pe(network, node_idx, i, child_state) * child_pe_weight(child_state);
+            let kappa = value_child_coupling(network, node_idx, i, time_step);
 
             let coupling_fn_prime = match coupling_fn {
                 Some(cf) => (cf.df)(parent_mean),
@@ -156,11 +186,8 @@ fn mean_update_from_children(network: &Network, node_idx: usize, node_precision:
             // π_y = π_a − π̃_a; summed over children then divided once by node_precision
             // (exact multi-child marginal mean). Leaves / non-Gaussian children have
             // π_y = 0 so g_a collapses to the marginal, recovering the canonical gain.
-            let child_node_type = network.edges[child_idx].node_type.as_str();
             let child_is_gaussian_interior =
-                matches!(child_node_type, "continuous-state" | "volatile-state")
-                    && (network.edges[child_idx].value_children.is_some()
-                        || network.edges[child_idx].volatility_children.is_some());
+                network.edge_lookup.value_children[node_idx][i].gaussian_interior;
             let gain_precision = if child_is_gaussian_interior {
                 let child_precision = child_state.precision;
                 let pi_y = child_precision - child_expected_precision;
@@ -170,8 +197,19 @@ fn mean_update_from_children(network: &Network, node_idx: usize, node_precision:
                 child_expected_precision
             };
 
-            value_pwpe +=
-                (kappa * coupling_fn_prime * gain_precision / node_precision) * child_vape;
+            let gain = kappa * coupling_fn_prime * gain_precision / node_precision;
+            let learning_rate = pe_asymmetry_scaling(pe_asymmetry, child_vape) * gain;
+            value_pwpe += learning_rate * child_vape;
+            unscaled_value_pwpe += gain * child_vape;
+            record_learning_rate(
+                &mut network.attributes.vectors[child_idx],
+                network.edges[child_idx]
+                    .value_parents
+                    .as_ref()
+                    .map_or(0, Vec::len),
+                network.edge_lookup.value_children[node_idx][i].coupling_idx,
+                learning_rate,
+            );
         }
     }
 
@@ -179,20 +217,28 @@ fn mean_update_from_children(network: &Network, node_idx: usize, node_precision:
     if let Some(ref volc_idxs) = network.edges[node_idx].volatility_children {
         let vol_coupling_strengths =
             &network.attributes.vectors[node_idx].volatility_coupling_children;
+        let (_, g_prime, _) = coupling_at(
+            network.attributes.fn_ptrs[node_idx].volatility_coupling_fn,
+            network.attributes.states[node_idx].mean,
+        );
 
         for (i, &child_idx) in volc_idxs.iter().enumerate() {
             let child_state = &network.attributes.states[child_idx];
             let effective_precision = child_state.effective_precision;
             let volatility_pe = child_state.volatility_prediction_error;
-            let observed = child_state.observed;
+            let weight = child_pe_weight(child_state);
             let kappa = vol_coupling_strengths.get(i).copied().unwrap_or(1.0);
 
-            volatility_pwpe +=
-                (kappa * effective_precision * volatility_pe) / (2.0 * node_precision) * observed;
+            volatility_pwpe += (kappa * g_prime * effective_precision * volatility_pe)
+                / (2.0 * node_precision)
+                * weight;
         }
     }
 
-    value_pwpe + volatility_pwpe
+    (
+        value_pwpe + volatility_pwpe,
+        applied_pe_scaling(value_pwpe, unscaled_value_pwpe),
+    )
 }
 
 // =============================================================================
@@ -202,23 +248,42 @@ fn mean_update_from_children(network: &Network, node_idx: usize, node_precision:
 pub fn posterior_update_continuous_state_node(
     network: &mut Network,
     node_idx: usize,
-    _time_step: f64,
+    time_step: f64,
 ) {
     let expected_precision = network.attributes.states[node_idx].expected_precision;
     let expected_mean = network.attributes.states[node_idx].expected_mean;
-    let max_posterior_precision = network.max_posterior_precision;
+    let max_posterior_precision = network.posterior_precision_cap(node_idx);
 
-    let precision_wpe = precision_update_from_children(network, node_idx);
-    let posterior_precision = (expected_precision + precision_wpe)
-        .max(1e-128)
-        .min(max_posterior_precision);
+    let precision_wpe =
+        precision_update_from_children(network, node_idx, time_step, &mut |_, _| {});
+    let Some(posterior_precision) = resolve_posterior_precision(
+        network,
+        node_idx,
+        "posterior_update_continuous_state_node",
+        expected_precision + precision_wpe,
+        |network| {
+            let mut contributions = Vec::new();
+            precision_update_from_children(
+                network,
+                node_idx,
+                time_step,
+                &mut |child_idx, contribution| contributions.push((child_idx, contribution)),
+            );
+            contributions
+        },
+    ) else {
+        return;
+    };
+    let posterior_precision = posterior_precision.min(max_posterior_precision);
 
-    let mean_wpe = mean_update_from_children(network, node_idx, posterior_precision);
+    let (mean_wpe, pe_scaling) =
+        mean_update_from_children(network, node_idx, time_step, posterior_precision);
     let posterior_mean = expected_mean + mean_wpe;
 
     let state = &mut network.attributes.states[node_idx];
     state.precision = posterior_precision;
     state.mean = posterior_mean;
+    state.pe_scaling = pe_scaling;
 }
 
 // =============================================================================
@@ -232,34 +297,70 @@ pub fn posterior_update_continuous_state_node_ehgf(
 ) {
     let expected_precision = network.attributes.states[node_idx].expected_precision;
     let expected_mean = network.attributes.states[node_idx].expected_mean;
-    let max_posterior_precision = network.max_posterior_precision;
+    let max_posterior_precision = network.posterior_precision_cap(node_idx);
 
-    let mean_wpe = mean_update_from_children(network, node_idx, expected_precision);
+    let previous = network.attributes.states[node_idx];
+    let (mean_wpe, pe_scaling) =
+        mean_update_from_children(network, node_idx, time_step, expected_precision);
     let posterior_mean = expected_mean + mean_wpe;
     network.attributes.states[node_idx].mean = posterior_mean;
+    network.attributes.states[node_idx].pe_scaling = pe_scaling;
 
     // eHGF safe precision update: recompute the effective precision from the
     // posterior mean and floor the volatility increment at zero.
-    let precision_wpe = precision_update_from_children_ehgf(network, node_idx, time_step);
-    let posterior_precision = (expected_precision + precision_wpe)
-        .max(1e-128)
-        .min(max_posterior_precision);
-    network.attributes.states[node_idx].precision = posterior_precision;
+    let precision_wpe =
+        precision_update_from_children_ehgf(network, node_idx, time_step, &mut |_, _| {});
+    let Some(posterior_precision) = resolve_posterior_precision(
+        network,
+        node_idx,
+        "posterior_update_continuous_state_node_ehgf",
+        expected_precision + precision_wpe,
+        |network| {
+            let mut contributions = Vec::new();
+            precision_update_from_children_ehgf(
+                network,
+                node_idx,
+                time_step,
+                &mut |child_idx, contribution| contributions.push((child_idx, contribution)),
+            );
+            contributions
+        },
+    ) else {
+        let state = &mut network.attributes.states[node_idx];
+        state.mean = previous.mean;
+        state.pe_scaling = previous.pe_scaling;
+        return;
+    };
+    network.attributes.states[node_idx].precision =
+        posterior_precision.min(max_posterior_precision);
 }
 
 // =============================================================================
 // Unbounded posterior update
 // =============================================================================
 
+/// Posterior update with the unbounded approximation, for a node with exactly
+/// one volatility child. `set_update_sequence` routes other nodes to the eHGF
+/// update, and [`Network::check_steps`] rejects a sequence that does not; a
+/// node without one still leaves a [`NetworkError::MissingAttribute`] in
+/// `network.step_error` rather than panicking.
 pub fn posterior_update_continuous_state_node_unbounded(
     network: &mut Network,
     node_idx: usize,
     time_step: f64,
 ) {
-    let volatility_child_idx = network.edges[node_idx]
+    let Some(&volatility_child_idx) = network.edges[node_idx]
         .volatility_children
         .as_ref()
-        .expect("No volatility children found")[0];
+        .and_then(|children| children.first())
+    else {
+        network.step_error = Some(NetworkError::MissingAttribute {
+            node_idx,
+            attribute: "volatility_children",
+            step: "posterior_update_continuous_state_node_unbounded",
+        });
+        return;
+    };
 
     let volatility_coupling = network.attributes.vectors[node_idx]
         .volatility_coupling_children
@@ -344,13 +445,73 @@ pub fn posterior_update_continuous_state_node_unbounded(
     // Gaussian mixture moment matching
     let posterior_mean = (1.0 - b) * mu1 + b * mu2;
     let sig2 = (1.0 - b) / pi1 + b / pi2 + b * (1.0 - b) * (mu1 - mu2).powi(2);
-    let posterior_precision = (1.0 / sig2).min(network.max_posterior_precision);
+    let posterior_precision = (1.0 / sig2).min(network.posterior_precision_cap(node_idx));
 
     let state = &mut network.attributes.states[node_idx];
     state.precision = posterior_precision;
     state.mean = posterior_mean;
 }
 
+// =============================================================================
+// Precision-parent posterior update
+// =============================================================================
+
+/// Posterior update of a precision (noise) parent, whose mean sets the log
+/// expected observation noise of its children: `1/π̂_c = exp(μ̂)`.
+///
+/// As a function of the log noise `x`, the Gaussian log-likelihood of a
+/// child prediction error `δ_c` is `−x/2 − exp(−x) δ_c²/2`. Its gradient and
+/// negative curvature at `x = μ̂` give the Laplace update
+///
+/// ```text
+/// π = π̂ + Σ_c π̂_c δ_c² / 2,    μ = μ̂ + Σ_c (π̂_c δ_c² − 1) / (2 π)
+/// ```
+///
+/// so the mean rises when the errors are larger than the predicted noise.
+pub fn posterior_update_precision_parent(network: &mut Network, node_idx: usize, _time_step: f64) {
+    // Weighted squared prediction error π̂_c δ_c² of each child, with the
+    // weight of its prediction errors (see `child_pe_weight`).
+    let weighted_pes = |network: &Network| {
+        let states = &network.attributes.states;
+        network.edges[node_idx]
+            .precision_children
+            .iter()
+            .flatten()
+            .map(|&child_idx| {
+                let child_state = &states[child_idx];
+                let weighted_pe =
+                    child_state.expected_precision * child_state.value_prediction_error.powi(2);
+                (child_idx, weighted_pe, child_pe_weight(child_state))
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut posterior_precision = network.attributes.states[node_idx].expected_precision;
+    let mut gradient = 0.0;
+    for (_, weighted_pe, weight) in weighted_pes(network) {
+        posterior_precision += 0.5 * weighted_pe * weight;
+        gradient += 0.5 * (weighted_pe - 1.0) * weight;
+    }
+    let Some(posterior_precision) = resolve_posterior_precision(
+        network,
+        node_idx,
+        "posterior_update_precision_parent",
+        posterior_precision,
+        |network| {
+            weighted_pes(network)
+                .into_iter()
+                .map(|(child_idx, weighted_pe, weight)| (child_idx, 0.5 * weighted_pe * weight))
+                .collect()
+        },
+    ) else {
+        return;
+    };
+    let posterior_precision = posterior_precision.min(network.posterior_precision_cap(node_idx));
+
+    let state = &mut network.attributes.states[node_idx];
+    state.precision = posterior_precision;
+    state.mean = state.expected_mean + gradient / posterior_precision;
+}
+
 // =============================================================================
 // Mean-field (v0.2.11) building blocks
 // =============================================================================
@@ -358,51 +519,67 @@ pub fn posterior_update_continuous_state_node_unbounded(
 /// Mean-field precision update from children.
 ///
 /// Uses `expected_precision` directly as the child-precision factor.
-fn precision_update_from_children_mean_field(network: &Network, node_idx: usize) -> f64 {
+fn precision_update_from_children_mean_field(
+    network: &Network,
+    node_idx: usize,
+    time_step: f64,
+    on_child: &mut impl FnMut(usize, f64),
+) -> f64 {
     let mut precision_wpe = 0.0;
 
     if let Some(ref vc_idxs) = network.edges[node_idx].value_children {
-        let coupling_strengths = &network.attributes.vectors[node_idx].value_coupling_children;
         let parent_mean = network.attributes.states[node_idx].mean;
         let coupling_fn = network.attributes.fn_ptrs[node_idx].coupling_fn;
 
         for (i, &child_idx) in vc_idxs.iter().enumerate() {
             let child_state = &network.attributes.states[child_idx];
             let child_expected_precision = child_state.expected_precision;
-            let observed = child_state.observed;
-            let kappa = coupling_strengths.get(i).copied().unwrap_or(1.0);
+            let weight = child_pe_weight(child_state);
+            let kappa = value_child_coupling(network, node_idx, i, time_step);
 
             let (coupling_fn_prime_sq, coupling_fn_second_term) = match coupling_fn {
                 Some(cf) => {
                     let g_prime = (cf.df)(parent_mean);
                     let g_second = (cf.d2f)(parent_mean);
-                    let child_vape = child_state.value_prediction_error;
+                    let child_vape = child_value_pe(network, node_idx, i, child_state);
                     (g_prime.powi(2), kappa * g_second * child_vape)
                 }
                 None => (1.0, 0.0),
             };
 
-            precision_wpe += (child_expected_precision
+            let contribution = (child_expected_precision
                 * (kappa.powi(2) * coupling_fn_prime_sq - coupling_fn_second_term))
-                * observed;
+                * weight;
+            precision_wpe += contribution;
+            on_child(child_idx, contribution);
         }
     }
 
     if let Some(ref volc_idxs) = network.edges[node_idx].volatility_children {
         let vol_coupling_strengths =
             &network.attributes.vectors[node_idx].volatility_coupling_children;
+        let (_, g_prime, g_second) = coupling_at(
+            network.attributes.fn_ptrs[node_idx].volatility_coupling_fn,
+            network.attributes.states[node_idx].mean,
+        );
 
         for (i, &child_idx) in volc_idxs.iter().enumerate() {
             let child_state = &network.attributes.states[child_idx];
             let effective_precision = child_state.effective_precision;
             let volatility_pe = child_state.volatility_prediction_error;
-            let observed = child_state.observed;
+            let weight = child_pe_weight(child_state);
             let kappa = vol_coupling_strengths.get(i).copied().unwrap_or(1.0);
-
-            precision_wpe += (0.5 * (kappa * effective_precision).powi(2)
-                + (kappa * effective_precision).powi(2) * volatility_pe
-                - 0.5 * kappa.powi(2) * effective_precision * volatility_pe)
-                * observed;
+            // Through a volatility coupling function g the coupling acts with
+            // the local slope κ g', plus a curvature term in κ g''.
+            let slope = kappa * g_prime;
+
+            let contribution = (0.5 * (slope * effective_precision).powi(2)
+                + (slope * effective_precision).powi(2) * volatility_pe
+                - 0.5 * slope.powi(2) * effective_precision * volatility_pe
+                - 0.5 * kappa * g_second * effective_precision * volatility_pe)
+                * weight;
+            precision_wpe += contribution;
+            on_child(child_idx, contribution);
         }
     }
 
@@ -418,14 +595,16 @@ fn precision_update_from_children_mean_field(network: &Network, node_idx: usize)
 fn ehgf_volatility_increment(
     child_state: &crate::model::network::NodeState,
     volatility_coupling: f64,
+    volatility_coupling_fn: Option<&CouplingFn>,
     mean: f64,
     time_step: f64,
 ) -> f64 {
+    let (g, g_prime, g_second) = coupling_at(volatility_coupling_fn, mean);
     // Child posterior variance at the previous step (σ = 1 / π).
     let previous_variance = child_state.current_variance;
     // Re-predict the child's volatility and precision from the parent posterior mean.
     let predicted_volatility =
-        time_step * (volatility_coupling * mean + child_state.tonic_volatility).exp();
+        time_step * (volatility_coupling * g + child_state.tonic_volatility).exp();
     let expected_precision = 1.0 / (previous_variance + predicted_volatility);
     let effective_precision = predicted_volatility * expected_precision;
     let volatility_error_weight = (predicted_volatility - previous_variance) * expected_precision;
@@ -433,44 +612,47 @@ fn ehgf_volatility_increment(
         + (child_state.mean - child_state.expected_mean).powi(2))
         * expected_precision
         - 1.0;
-    (0.5 * volatility_coupling.powi(2)
+    let slope = volatility_coupling * g_prime;
+    (0.5 * slope.powi(2)
         * effective_precision
-        * (effective_precision + volatility_error_weight * volatility_prediction_error))
+        * (effective_precision + volatility_error_weight * volatility_prediction_error)
+        - 0.5 * volatility_coupling * g_second * effective_precision * volatility_prediction_error)
         .max(0.0)
-        * child_state.observed
+        * child_pe_weight(child_state)
 }
 
 /// Enhanced-HGF precision update from children (relaxed value coupling).
-fn precision_update_from_children_ehgf(network: &Network, node_idx: usize, time_step: f64) -> f64 {
+fn precision_update_from_children_ehgf(
+    network: &Network,
+    node_idx: usize,
+    time_step: f64,
+    on_child: &mut impl FnMut(usize, f64),
+) -> f64 {
     let mut precision_wpe = 0.0;
 
     // --- Value coupling (identical to the relaxed standard update) ---
     if let Some(ref vc_idxs) = network.edges[node_idx].value_children {
-        let coupling_strengths = &network.attributes.vectors[node_idx].value_coupling_children;
         let parent_mean = network.attributes.states[node_idx].mean;
         let coupling_fn = network.attributes.fn_ptrs[node_idx].coupling_fn;
 
         for (i, &child_idx) in vc_idxs.iter().enumerate() {
             let child_state = &network.attributes.states[child_idx];
             let child_expected_precision = child_state.expected_precision;
-            let observed = child_state.observed;
-            let kappa = coupling_strengths.get(i).copied().unwrap_or(1.0);
+            let weight = child_pe_weight(child_state);
+            let kappa = value_child_coupling(network, node_idx, i, time_step);
 
             let (coupling_fn_prime_sq, coupling_fn_second_term) = match coupling_fn {
                 Some(cf) => {
                     let g_prime = (cf.df)(parent_mean);
                     let g_second = (cf.d2f)(parent_mean);
-                    let child_vape = child_state.value_prediction_error;
+                    let child_vape = child_value_pe(network, node_idx, i, child_state);
                     (g_prime.powi(2), kappa * g_second * child_vape)
                 }
                 None => (1.0, 0.0),
             };
 
-            let child_node_type = network.edges[child_idx].node_type.as_str();
             let child_is_gaussian_interior =
-                matches!(child_node_type, "continuous-state" | "volatile-state")
-                    && (network.edges[child_idx].value_children.is_some()
-                        || network.edges[child_idx].volatility_children.is_some());
+                network.edge_lookup.value_children[node_idx][i].gaussian_interior;
             let effective_child_precision = if child_is_gaussian_interior {
                 let child_precision = child_state.precision;
                 let pi_y = child_precision - child_expected_precision;
@@ -480,9 +662,11 @@ fn precision_update_from_children_ehgf(network: &Network, node_idx: usize, time_
                 child_expected_precision
             };
 
-            precision_wpe += (effective_child_precision
+            let contribution = (effective_child_precision
                 * (kappa.powi(2) * coupling_fn_prime_sq - coupling_fn_second_term))
-                * observed;
+                * weight;
+            precision_wpe += contribution;
+            on_child(child_idx, contribution);
         }
     }
 
@@ -491,11 +675,20 @@ fn precision_update_from_children_ehgf(network: &Network, node_idx: usize, time_
         let vol_coupling_strengths =
             &network.attributes.vectors[node_idx].volatility_coupling_children;
         let parent_mean = network.attributes.states[node_idx].mean;
+        let volatility_coupling_fn = network.attributes.fn_ptrs[node_idx].volatility_coupling_fn;
 
         for (i, &child_idx) in volc_idxs.iter().enumerate() {
             let child_state = &network.attributes.states[child_idx];
             let kappa = vol_coupling_strengths.get(i).copied().unwrap_or(1.0);
-            precision_wpe += ehgf_volatility_increment(child_state, kappa, parent_mean, time_step);
+            let contribution = ehgf_volatility_increment(
+                child_state,
+                kappa,
+                volatility_coupling_fn,
+                parent_mean,
+                time_step,
+            );
+            precision_wpe += contribution;
+            on_child(child_idx, contribution);
         }
     }
 
@@ -507,34 +700,36 @@ fn precision_update_from_children_ehgf_mean_field(
     network: &Network,
     node_idx: usize,
     time_step: f64,
+    on_child: &mut impl FnMut(usize, f64),
 ) -> f64 {
     let mut precision_wpe = 0.0;
 
     // --- Value coupling (identical to the mean-field standard update) ---
     if let Some(ref vc_idxs) = network.edges[node_idx].value_children {
-        let coupling_strengths = &network.attributes.vectors[node_idx].value_coupling_children;
         let parent_mean = network.attributes.states[node_idx].mean;
         let coupling_fn = network.attributes.fn_ptrs[node_idx].coupling_fn;
 
         for (i, &child_idx) in vc_idxs.iter().enumerate() {
             let child_state = &network.attributes.states[child_idx];
             let child_expected_precision = child_state.expected_precision;
-            let observed = child_state.observed;
-            let kappa = coupling_strengths.get(i).copied().unwrap_or(1.0);
+            let weight = child_pe_weight(child_state);
+            let kappa = value_child_coupling(network, node_idx, i, time_step);
 
             let (coupling_fn_prime_sq, coupling_fn_second_term) = match coupling_fn {
                 Some(cf) => {
                     let g_prime = (cf.df)(parent_mean);
                     let g_second = (cf.d2f)(parent_mean);
-                    let child_vape = child_state.value_prediction_error;
+                    let child_vape = child_value_pe(network, node_idx, i, child_state);
                     (g_prime.powi(2), kappa * g_second * child_vape)
                 }
                 None => (1.0, 0.0),
             };
 
-            precision_wpe += (child_expected_precision
+            let contribution = (child_expected_precision
                 * (kappa.powi(2) * coupling_fn_prime_sq - coupling_fn_second_term))
-                * observed;
+                * weight;
+            precision_wpe += contribution;
+            on_child(child_idx, contribution);
         }
     }
 
@@ -543,11 +738,20 @@ fn precision_update_from_children_ehgf_mean_field(
         let vol_coupling_strengths =
             &network.attributes.vectors[node_idx].volatility_coupling_children;
         let parent_mean = network.attributes.states[node_idx].mean;
+        let volatility_coupling_fn = network.attributes.fn_ptrs[node_idx].volatility_coupling_fn;
 
         for (i, &child_idx) in volc_idxs.iter().enumerate() {
             let child_state = &network.attributes.states[child_idx];
             let kappa = vol_coupling_strengths.get(i).copied().unwrap_or(1.0);
-            precision_wpe += ehgf_volatility_increment(child_state, kappa, parent_mean, time_step);
+            let contribution = ehgf_volatility_increment(
+                child_state,
+                kappa,
+                volatility_coupling_fn,
+                parent_mean,
+                time_step,
+            );
+            precision_wpe += contribution;
+            on_child(child_idx, contribution);
         }
     }
 
@@ -558,51 +762,73 @@ fn precision_update_from_children_ehgf_mean_field(
 ///
 /// Uses `expected_precision` directly as the value-coupling gain.
 fn mean_update_from_children_mean_field(
-    network: &Network,
+    network: &mut Network,
     node_idx: usize,
+    time_step: f64,
     node_precision: f64,
-) -> f64 {
+) -> (f64, f64) {
+    let pe_asymmetry = network.attributes.states[node_idx].pe_asymmetry;
     let mut value_pwpe = 0.0;
+    let mut unscaled_value_pwpe = 0.0;
     let mut volatility_pwpe = 0.0;
 
     if let Some(ref vc_idxs) = network.edges[node_idx].value_children {
-        let coupling_strengths = &network.attributes.vectors[node_idx].value_coupling_children;
         let parent_mean = network.attributes.states[node_idx].mean;
         let coupling_fn = network.attributes.fn_ptrs[node_idx].coupling_fn;
 
         for (i, &child_idx) in vc_idxs.iter().enumerate() {
             let child_state = &network.attributes.states[child_idx];
             let child_expected_precision = child_state.expected_precision;
-            let child_vape = child_state.value_prediction_error * child_state.observed;
-            let kappa = coupling_strengths.get(i).copied().unwrap_or(1.0);
+            let child_vape =
+                child_value_pe(network, node_idx, i, child_state) * child_pe_weight(child_state);
+            let kappa = value_child_coupling(network, node_idx, i, time_step);
 
             let coupling_fn_prime = match coupling_fn {
                 Some(cf) => (cf.df)(parent_mean),
                 None => 1.0,
             };
 
-            value_pwpe += (kappa * coupling_fn_prime * child_expected_precision / node_precision)
-                * child_vape;
+            let gain = kappa * coupling_fn_prime * child_expected_precision / node_precision;
+            let learning_rate = pe_asymmetry_scaling(pe_asymmetry, child_vape) * gain;
+            value_pwpe += learning_rate * child_vape;
+            unscaled_value_pwpe += gain * child_vape;
+            record_learning_rate(
+                &mut network.attributes.vectors[child_idx],
+                network.edges[child_idx]
+                    .value_parents
+                    .as_ref()
+                    .map_or(0, Vec::len),
+                network.edge_lookup.value_children[node_idx][i].coupling_idx,
+                learning_rate,
+            );
         }
     }
 
     if let Some(ref volc_idxs) = network.edges[node_idx].volatility_children {
         let vol_coupling_strengths =
             &network.attributes.vectors[node_idx].volatility_coupling_children;
+        let (_, g_prime, _) = coupling_at(
+            network.attributes.fn_ptrs[node_idx].volatility_coupling_fn,
+            network.attributes.states[node_idx].mean,
+        );
 
         for (i, &child_idx) in volc_idxs.iter().enumerate() {
             let child_state = &network.attributes.states[child_idx];
             let effective_precision = child_state.effective_precision;
             let volatility_pe = child_state.volatility_prediction_error;
-            let observed = child_state.observed;
+            let weight = child_pe_weight(child_state);
             let kappa = vol_coupling_strengths.get(i).copied().unwrap_or(1.0);
 
-            volatility_pwpe +=
-                (kappa * effective_precision * volatility_pe) / (2.0 * node_precision) * observed;
+            volatility_pwpe += (kappa * g_prime * effective_precision * volatility_pe)
+                / (2.0 * node_precision)
+                * weight;
         }
     }
 
-    value_pwpe + volatility_pwpe
+    (
+        value_pwpe + volatility_pwpe,
+        applied_pe_scaling(value_pwpe, unscaled_value_pwpe),
+    )
 }
 
 // =============================================================================
@@ -612,23 +838,42 @@ fn mean_update_from_children_mean_field(
 pub fn posterior_update_continuous_state_node_mean_field(
     network: &mut Network,
     node_idx: usize,
-    _time_step: f64,
+    time_step: f64,
 ) {
     let expected_precision = network.attributes.states[node_idx].expected_precision;
     let expected_mean = network.attributes.states[node_idx].expected_mean;
-    let max_posterior_precision = network.max_posterior_precision;
+    let max_posterior_precision = network.posterior_precision_cap(node_idx);
 
-    let precision_wpe = precision_update_from_children_mean_field(network, node_idx);
-    let posterior_precision = (expected_precision + precision_wpe)
-        .max(1e-128)
-        .min(max_posterior_precision);
+    let precision_wpe =
+        precision_update_from_children_mean_field(network, node_idx, time_step, &mut |_, _| {});
+    let Some(posterior_precision) = resolve_posterior_precision(
+        network,
+        node_idx,
+        "posterior_update_continuous_state_node_mean_field",
+        expected_precision + precision_wpe,
+        |network| {
+            let mut contributions = Vec::new();
+            precision_update_from_children_mean_field(
+                network,
+                node_idx,
+                time_step,
+                &mut |child_idx, contribution| contributions.push((child_idx, contribution)),
+            );
+            contributions
+        },
+    ) else {
+        return;
+    };
+    let posterior_precision = posterior_precision.min(max_posterior_precision);
 
-    let mean_wpe = mean_update_from_children_mean_field(network, node_idx, posterior_precision);
+    let (mean_wpe, pe_scaling) =
+        mean_update_from_children_mean_field(network, node_idx, time_step, posterior_precision);
     let posterior_mean = expected_mean + mean_wpe;
 
     let state = &mut network.attributes.states[node_idx];
     state.precision = posterior_precision;
     state.mean = posterior_mean;
+    state.pe_scaling = pe_scaling;
 }
 
 pub fn posterior_update_continuous_state_node_ehgf_mean_field(
@@ -638,17 +883,43 @@ pub fn posterior_update_continuous_state_node_ehgf_mean_field(
 ) {
     let expected_precision = network.attributes.states[node_idx].expected_precision;
     let expected_mean = network.attributes.states[node_idx].expected_mean;
-    let max_posterior_precision = network.max_posterior_precision;
+    let max_posterior_precision = network.posterior_precision_cap(node_idx);
 
-    let mean_wpe = mean_update_from_children_mean_field(network, node_idx, expected_precision);
+    let previous = network.attributes.states[node_idx];
+    let (mean_wpe, pe_scaling) =
+        mean_update_from_children_mean_field(network, node_idx, time_step, expected_precision);
     let posterior_mean = expected_mean + mean_wpe;
     network.attributes.states[node_idx].mean = posterior_mean;
+    network.attributes.states[node_idx].pe_scaling = pe_scaling;
 
     // eHGF safe precision update (mean-field value coupling).
-    let precision_wpe =
-        precision_update_from_children_ehgf_mean_field(network, node_idx, time_step);
-    let posterior_precision = (expected_precision + precision_wpe)
-        .max(1e-128)
-        .min(max_posterior_precision);
-    network.attributes.states[node_idx].precision = posterior_precision;
+    let precision_wpe = precision_update_from_children_ehgf_mean_field(
+        network,
+        node_idx,
+        time_step,
+        &mut |_, _| {},
+    );
+    let Some(posterior_precision) = resolve_posterior_precision(
+        network,
+        node_idx,
+        "posterior_update_continuous_state_node_ehgf_mean_field",
+        expected_precision + precision_wpe,
+        |network| {
+            let mut contributions = Vec::new();
+            precision_update_from_children_ehgf_mean_field(
+                network,
+                node_idx,
+                time_step,
+                &mut |child_idx, contribution| contributions.push((child_idx, contribution)),
+            );
+            contributions
+        },
+    ) else {
+        let state = &mut network.attributes.states[node_idx];
+        state.mean = previous.mean;
+        state.pe_scaling = previous.pe_scaling;
+        return;
+    };
+    network.attributes.states[node_idx].precision =
+        posterior_precision.min(max_posterior_precision);
 }
@@ -0,0 +1,53 @@
+use crate::model::network::Network;
+
+use super::child_pe_weight;
+
+/// Posterior update of a cluster of dp-state nodes
+///
+/// The cluster is a Gaussian belief about the centre of the observations it
+/// is responsible for. Each dp-state child `c` contributes its observation
+/// `x_c` with the precision `π_c` of an observation around its cluster,
+/// weighted by the responsibility `r_c` of this cluster:
+///
+/// * π = π̂ + Σ_c r_c · π_c
+/// * μ = μ̂ + Σ_c r_c · π_c · (x_c − μ̂) / π
+///
+/// A responsibility of 0 leaves the cluster at its prediction.
+pub fn posterior_update_dp_cluster(network: &mut Network, node_idx: usize, _time_step: f64) {
+    let state = &network.attributes.states[node_idx];
+    let (expected_mean, expected_precision) = (state.expected_mean, state.expected_precision);
+
+    let mut precision_gain = 0.0;
+    let mut weighted_error = 0.0;
+    for &child_idx in network.edges[node_idx]
+        .value_children
+        .as_deref()
+        .unwrap_or_default()
+    {
+        if network.edges[child_idx].node_type != "dp-state" {
+            continue;
+        }
+        let Some(k) = network.edges[child_idx]
+            .value_parents
+            .as_ref()
+            .and_then(|parents| parents.iter().position(|&p| p == node_idx))
+        else {
+            continue;
+        };
+        let child = &network.attributes.states[child_idx];
+        let responsibility = network.attributes.vectors[child_idx]
+            .probabilities
+            .get(k)
+            .copied()
+            .unwrap_or(0.0);
+        let weight = responsibility * child_pe_weight(child) * child.precision;
+        precision_gain += weight;
+        weighted_error += weight * (child.mean - expected_mean);
+    }
+
+    let precision =
+        (expected_precision + precision_gain).min(network.posterior_precision_cap(node_idx));
+    let state = &mut network.attributes.states[node_idx];
+    state.precision = precision;
+    state.mean = expected_mean + weighted_error / precision;
+}
@@ -1,2 +1,153 @@
+use crate::error::NetworkError;
+use crate::model::network::{NegativePrecision, Network, NodeState, NodeVectors};
+
+pub mod circular;
 pub mod continuous;
+pub mod dp;
 pub mod volatile;
+
+/// Smallest posterior precision kept by the `floor` negative-precision policy.
+pub(crate) const MIN_POSTERIOR_PRECISION: f64 = 1e-128;
+
+/// Posterior precision to store for `node_idx` under the network's
+/// [`NegativePrecision`] policy, or `None` when the kernel must leave the
+/// previous posterior in place: under `reject`, or under `error` once the
+/// error is left in `network.step_error`. Precisions below
+/// [`MIN_POSTERIOR_PRECISION`] that are still positive are floored whatever
+/// the policy. `contributions` lists the children's shares of the update, and
+/// is only evaluated for the error.
+pub(crate) fn resolve_posterior_precision(
+    network: &mut Network,
+    node_idx: usize,
+    step: &'static str,
+    precision: f64,
+    contributions: impl FnOnce(&Network) -> Vec<(usize, f64)>,
+) -> Option<f64> {
+    if precision >= MIN_POSTERIOR_PRECISION {
+        return Some(precision);
+    }
+    match network.negative_precision {
+        _ if precision > 0.0 => Some(MIN_POSTERIOR_PRECISION),
+        NegativePrecision::Floor => Some(MIN_POSTERIOR_PRECISION),
+        NegativePrecision::Reject => None,
+        NegativePrecision::Error => {
+            network.step_error = Some(NetworkError::NegativePrecision {
+                node_idx,
+                step,
+                expected_precision: network.attributes.states[node_idx].expected_precision,
+                precision,
+                contributions: contributions(network),
+            });
+            None
+        }
+    }
+}
+
+/// Value prediction error of the `position`-th value child of `node_idx`, as
+/// read by the parent's posterior update: the recorded `μ_c − μ̂_c`, or
+/// `h(μ_c) − h(μ̂_c)` when the edge has a child-side coupling function `h`
+/// (see [`crate::model::network::NodeFnPtrs`]).
+pub(crate) fn child_value_pe(
+    network: &Network,
+    node_idx: usize,
+    position: usize,
+    child_state: &NodeState,
+) -> f64 {
+    let child_coupling_fn = network
+        .edge_lookup
+        .value_children
+        .get(node_idx)
+        .and_then(|edges| edges.get(position))
+        .and_then(|edge| edge.child_coupling_fn);
+    match child_coupling_fn {
+        Some(cf) => (cf.f)(child_state.mean) - (cf.f)(child_state.expected_mean),
+        None => child_state.value_prediction_error,
+    }
+}
+
+/// Strength of the value coupling of `node_idx` to its `position`-th value
+/// child, as read by the parent's posterior update: the edge's
+/// `value_coupling_children` entry κ, times `time_step` when the child lists
+/// the parent as a drift parent (see [`Network::set_drift_coupling`]). The
+/// child's prediction moves by `Δt · κ` per unit of the parent's mean, so its
+/// prediction errors reach the parent scaled by the same factor.
+pub(crate) fn value_child_coupling(
+    network: &Network,
+    node_idx: usize,
+    position: usize,
+    time_step: f64,
+) -> f64 {
+    let kappa = network.attributes.vectors[node_idx]
+        .value_coupling_children
+        .get(position)
+        .copied()
+        .unwrap_or(1.0);
+    let drift = network
+        .edge_lookup
+        .value_children
+        .get(node_idx)
+        .and_then(|edges| edges.get(position))
+        .and_then(|edge| {
+            let flags = &network.attributes.vectors[edge.child_idx].coupling_drift;
+            flags.get(edge.coupling_idx?)
+        })
+        .is_some_and(|&flag| flag > 0.0);
+    if drift {
+        kappa * time_step
+    } else {
+        kappa
+    }
+}
+
+/// Weight of a child's prediction errors in its parents' posterior updates:
+/// `observed` (0 for a missing observation) times the trial gain `pe_gain`
+/// (see [`crate::model::network::InputDataOptions::pe_gains`]). Scales both
+/// the child's precision and mean contributions, so a zero weight leaves the
+/// parents at their predictions.
+#[inline]
+pub(crate) fn child_pe_weight(child_state: &NodeState) -> f64 {
+    child_state.observed * child_state.pe_gain
+}
+
+/// Scaling of a child's value prediction error under the parent's
+/// `pe_asymmetry` `a`: `1 + a` when the error is positive (better than
+/// expected), `1 − a` when it is negative.
+pub(crate) fn pe_asymmetry_scaling(pe_asymmetry: f64, value_prediction_error: f64) -> f64 {
+    if value_prediction_error > 0.0 {
+        1.0 + pe_asymmetry
+    } else if value_prediction_error < 0.0 {
+        1.0 - pe_asymmetry
+    } else {
+        1.0
+    }
+}
+
+/// Overall scaling of the value mean update, recorded as `pe_scaling`: the
+/// scaled over the unscaled sum of precision-weighted prediction errors (1
+/// when there is none).
+pub(crate) fn applied_pe_scaling(scaled_pwpe: f64, unscaled_pwpe: f64) -> f64 {
+    if unscaled_pwpe == 0.0 {
+        1.0
+    } else {
+        scaled_pwpe / unscaled_pwpe
+    }
+}
+
+/// Store the learning rate of a value edge in the child's
+/// `learning_rate_parents`, at the parent's position `coupling_idx` among the
+/// child's `n_value_parents` value parents. The vector is sized on first use;
+/// entries of parents that do not record one stay NaN.
+pub(crate) fn record_learning_rate(
+    child_vectors: &mut NodeVectors,
+    n_value_parents: usize,
+    coupling_idx: Option<usize>,
+    learning_rate: f64,
+) {
+    let Some(position) = coupling_idx else {
+        return;
+    };
+    if child_vectors.learning_rate_parents.len() != n_value_parents {
+        child_vectors.learning_rate_parents = vec![f64::NAN; n_value_parents];
+    }
+    child_vectors.learning_rate_parents[position] = learning_rate;
+}
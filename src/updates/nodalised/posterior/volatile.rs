@@ -1,3 +1,7 @@
+use super::{
+    applied_pe_scaling, child_value_pe, pe_asymmetry_scaling, record_learning_rate,
+    resolve_posterior_precision, value_child_coupling,
+};
 use crate::model::network::Network;
 
 // =============================================================================
@@ -26,25 +30,29 @@ use crate::model::network::Network;
 /// factor `π̃_a` — pyhgf keeps `precision = expected_precision` for clamped
 /// observations, so π_y = 0 and the harmonic form would otherwise zero out their
 /// contribution.
-fn precision_update_value_level(network: &Network, node_idx: usize) -> f64 {
+fn precision_update_value_level(
+    network: &Network,
+    node_idx: usize,
+    time_step: f64,
+    on_child: &mut impl FnMut(usize, f64),
+) -> f64 {
     let expected_precision = network.attributes.states[node_idx].expected_precision;
     let mut posterior_precision = expected_precision;
 
     if let Some(ref vc_idxs) = network.edges[node_idx].value_children {
-        let coupling_strengths = &network.attributes.vectors[node_idx].value_coupling_children;
         let parent_expected_mean = network.attributes.states[node_idx].expected_mean;
         let coupling_fn = network.attributes.fn_ptrs[node_idx].coupling_fn;
 
         for (i, &child_idx) in vc_idxs.iter().enumerate() {
             let child_state = &network.attributes.states[child_idx];
             let child_expected_precision = child_state.expected_precision;
-            let kappa = coupling_strengths.get(i).copied().unwrap_or(1.0);
+            let kappa = value_child_coupling(network, node_idx, i, time_step);
 
             let (coupling_fn_prime_sq, coupling_fn_second_term) = match coupling_fn {
                 Some(cf) => {
                     let g_prime = (cf.df)(parent_expected_mean);
                     let g_second = (cf.d2f)(parent_expected_mean);
-                    let child_vape = child_state.value_prediction_error;
+                    let child_vape = child_value_pe(network, node_idx, i, child_state);
                     (g_prime.powi(2), kappa * g_second * child_vape)
                 }
                 None => (1.0, 0.0),
@@ -58,11 +66,8 @@ fn precision_update_value_level(network: &Network, node_idx: usize) -> f64 {
             // exponential-family, and Dirichlet children, plus any Gaussian leaf,
             // fall back to the canonical predicted-precision factor π̃_a
             // (= `child.expected_precision`).
-            let child_node_type = network.edges[child_idx].node_type.as_str();
             let child_is_gaussian_interior =
-                matches!(child_node_type, "continuous-state" | "volatile-state")
-                    && (network.edges[child_idx].value_children.is_some()
-                        || network.edges[child_idx].volatility_children.is_some());
+                network.edge_lookup.value_children[node_idx][i].gaussian_interior;
             let effective_child_precision = if child_is_gaussian_interior {
                 // Bottom-up evidence precision π_y = π_a − π̃_a, measured against the
                 // child's *marginal* predicted precision π̃_a.
@@ -78,27 +83,35 @@ fn precision_update_value_level(network: &Network, node_idx: usize) -> f64 {
                 child_expected_precision
             };
 
-            posterior_precision += effective_child_precision
+            let contribution = effective_child_precision
                 * (kappa.powi(2) * coupling_fn_prime_sq - coupling_fn_second_term);
+            posterior_precision += contribution;
+            on_child(child_idx, contribution);
         }
     }
 
     posterior_precision
 }
 
-fn mean_update_value_level(network: &Network, node_idx: usize, node_precision: f64) -> f64 {
+fn mean_update_value_level(
+    network: &mut Network,
+    node_idx: usize,
+    time_step: f64,
+    node_precision: f64,
+) -> (f64, f64) {
     let expected_mean = network.attributes.states[node_idx].expected_mean;
+    let pe_asymmetry = network.attributes.states[node_idx].pe_asymmetry;
     let mut value_pwpe = 0.0;
+    let mut unscaled_value_pwpe = 0.0;
 
     if let Some(ref vc_idxs) = network.edges[node_idx].value_children {
-        let coupling_strengths = &network.attributes.vectors[node_idx].value_coupling_children;
         let parent_expected_mean = network.attributes.states[node_idx].expected_mean;
         let coupling_fn = network.attributes.fn_ptrs[node_idx].coupling_fn;
 
         for (i, &child_idx) in vc_idxs.iter().enumerate() {
             let child_state = &network.attributes.states[child_idx];
             let child_expected_precision = child_state.expected_precision;
-            let kappa = coupling_strengths.get(i).copied().unwrap_or(1.0);
+            let kappa = value_child_coupling(network, node_idx, i, time_step);
 
             let coupling_fn_prime = match coupling_fn {
                 Some(cf) => (cf.df)(parent_expected_mean),
@@ -113,11 +126,8 @@ fn mean_update_value_level(network: &Network, node_idx: usize, node_precision: f
             // than a sum of independent RTS gains. For leaves / non-Gaussian children
             // π_y = 0 and g_a collapses to the marginal π̃_a, recovering the canonical
             // gain.
-            let child_node_type = network.edges[child_idx].node_type.as_str();
             let child_is_gaussian_interior =
-                matches!(child_node_type, "continuous-state" | "volatile-state")
-                    && (network.edges[child_idx].value_children.is_some()
-                        || network.edges[child_idx].volatility_children.is_some());
+                network.edge_lookup.value_children[node_idx][i].gaussian_interior;
             let gain_precision = if child_is_gaussian_interior {
                 let child_precision = child_state.precision;
                 let pi_y = child_precision - child_expected_precision;
@@ -127,12 +137,27 @@ fn mean_update_value_level(network: &Network, node_idx: usize, node_precision: f
                 child_expected_precision
             };
 
-            value_pwpe += (kappa * coupling_fn_prime * gain_precision / node_precision)
-                * child_state.value_prediction_error;
+            let child_vape = child_value_pe(network, node_idx, i, child_state);
+            let gain = kappa * coupling_fn_prime * gain_precision / node_precision;
+            let learning_rate = pe_asymmetry_scaling(pe_asymmetry, child_vape) * gain;
+            value_pwpe += learning_rate * child_vape;
+            unscaled_value_pwpe += gain * child_vape;
+            record_learning_rate(
+                &mut network.attributes.vectors[child_idx],
+                network.edges[child_idx]
+                    .value_parents
+                    .as_ref()
+                    .map_or(0, Vec::len),
+                network.edge_lookup.value_children[node_idx][i].coupling_idx,
+                learning_rate,
+            );
         }
     }
 
-    expected_mean + value_pwpe
+    (
+        expected_mean + value_pwpe,
+        applied_pe_scaling(value_pwpe, unscaled_value_pwpe),
+    )
 }
 
 // =============================================================================
@@ -142,47 +167,74 @@ fn mean_update_value_level(network: &Network, node_idx: usize, node_precision: f
 pub fn posterior_update_volatile_state_node(
     network: &mut Network,
     node_idx: usize,
-    _time_step: f64,
+    time_step: f64,
 ) {
     // POSTERIOR UPDATE VALUE LEVEL
     let precision_value =
-        precision_update_value_level(network, node_idx).min(network.max_posterior_precision);
+        precision_update_value_level(network, node_idx, time_step, &mut |_, _| {});
+    let Some(precision_value) = resolve_posterior_precision(
+        network,
+        node_idx,
+        "posterior_update_volatile_state_node",
+        precision_value,
+        |network| {
+            let mut contributions = Vec::new();
+            precision_update_value_level(
+                network,
+                node_idx,
+                time_step,
+                &mut |child_idx, contribution| contributions.push((child_idx, contribution)),
+            );
+            contributions
+        },
+    ) else {
+        return;
+    };
+    let precision_value = precision_value.min(network.posterior_precision_cap(node_idx));
     network.attributes.states[node_idx].precision = precision_value;
 
-    let mean_value = mean_update_value_level(network, node_idx, precision_value);
+    let (mean_value, pe_scaling) =
+        mean_update_value_level(network, node_idx, time_step, precision_value);
     network.attributes.states[node_idx].mean = mean_value;
+    network.attributes.states[node_idx].pe_scaling = pe_scaling;
 }
 
 // =============================================================================
 // Mean-field value-level building blocks
 // =============================================================================
 
-fn precision_update_value_level_mean_field(network: &Network, node_idx: usize) -> f64 {
+fn precision_update_value_level_mean_field(
+    network: &Network,
+    node_idx: usize,
+    time_step: f64,
+    on_child: &mut impl FnMut(usize, f64),
+) -> f64 {
     let expected_precision = network.attributes.states[node_idx].expected_precision;
     let mut posterior_precision = expected_precision;
 
     if let Some(ref vc_idxs) = network.edges[node_idx].value_children {
-        let coupling_strengths = &network.attributes.vectors[node_idx].value_coupling_children;
         let parent_expected_mean = network.attributes.states[node_idx].expected_mean;
         let coupling_fn = network.attributes.fn_ptrs[node_idx].coupling_fn;
 
         for (i, &child_idx) in vc_idxs.iter().enumerate() {
             let child_state = &network.attributes.states[child_idx];
             let child_expected_precision = child_state.expected_precision;
-            let kappa = coupling_strengths.get(i).copied().unwrap_or(1.0);
+            let kappa = value_child_coupling(network, node_idx, i, time_step);
 
             let (coupling_fn_prime_sq, coupling_fn_second_term) = match coupling_fn {
                 Some(cf) => {
                     let g_prime = (cf.df)(parent_expected_mean);
                     let g_second = (cf.d2f)(parent_expected_mean);
-                    let child_vape = child_state.value_prediction_error;
+                    let child_vape = child_value_pe(network, node_idx, i, child_state);
                     (g_prime.powi(2), kappa * g_second * child_vape)
                 }
                 None => (1.0, 0.0),
             };
 
-            posterior_precision += child_expected_precision
+            let contribution = child_expected_precision
                 * (kappa.powi(2) * coupling_fn_prime_sq - coupling_fn_second_term);
+            posterior_precision += contribution;
+            on_child(child_idx, contribution);
         }
     }
 
@@ -190,34 +242,51 @@ fn precision_update_value_level_mean_field(network: &Network, node_idx: usize) -
 }
 
 fn mean_update_value_level_mean_field(
-    network: &Network,
+    network: &mut Network,
     node_idx: usize,
+    time_step: f64,
     node_precision: f64,
-) -> f64 {
+) -> (f64, f64) {
     let expected_mean = network.attributes.states[node_idx].expected_mean;
+    let pe_asymmetry = network.attributes.states[node_idx].pe_asymmetry;
     let mut value_pwpe = 0.0;
+    let mut unscaled_value_pwpe = 0.0;
 
     if let Some(ref vc_idxs) = network.edges[node_idx].value_children {
-        let coupling_strengths = &network.attributes.vectors[node_idx].value_coupling_children;
         let parent_expected_mean = network.attributes.states[node_idx].expected_mean;
         let coupling_fn = network.attributes.fn_ptrs[node_idx].coupling_fn;
 
         for (i, &child_idx) in vc_idxs.iter().enumerate() {
             let child_state = &network.attributes.states[child_idx];
             let child_expected_precision = child_state.expected_precision;
-            let kappa = coupling_strengths.get(i).copied().unwrap_or(1.0);
+            let kappa = value_child_coupling(network, node_idx, i, time_step);
 
             let coupling_fn_prime = match coupling_fn {
                 Some(cf) => (cf.df)(parent_expected_mean),
                 None => 1.0,
             };
 
-            value_pwpe += (kappa * coupling_fn_prime * child_expected_precision / node_precision)
-                * child_state.value_prediction_error;
+            let child_vape = child_value_pe(network, node_idx, i, child_state);
+            let gain = kappa * coupling_fn_prime * child_expected_precision / node_precision;
+            let learning_rate = pe_asymmetry_scaling(pe_asymmetry, child_vape) * gain;
+            value_pwpe += learning_rate * child_vape;
+            unscaled_value_pwpe += gain * child_vape;
+            record_learning_rate(
+                &mut network.attributes.vectors[child_idx],
+                network.edges[child_idx]
+                    .value_parents
+                    .as_ref()
+                    .map_or(0, Vec::len),
+                network.edge_lookup.value_children[node_idx][i].coupling_idx,
+                learning_rate,
+            );
         }
     }
 
-    expected_mean + value_pwpe
+    (
+        expected_mean + value_pwpe,
+        applied_pe_scaling(value_pwpe, unscaled_value_pwpe),
+    )
 }
 
 // =============================================================================
@@ -227,12 +296,33 @@ fn mean_update_value_level_mean_field(
 pub fn posterior_update_volatile_state_node_mean_field(
     network: &mut Network,
     node_idx: usize,
-    _time_step: f64,
+    time_step: f64,
 ) {
-    let precision_value = precision_update_value_level_mean_field(network, node_idx)
-        .min(network.max_posterior_precision);
+    let precision_value =
+        precision_update_value_level_mean_field(network, node_idx, time_step, &mut |_, _| {});
+    let Some(precision_value) = resolve_posterior_precision(
+        network,
+        node_idx,
+        "posterior_update_volatile_state_node_mean_field",
+        precision_value,
+        |network| {
+            let mut contributions = Vec::new();
+            precision_update_value_level_mean_field(
+                network,
+                node_idx,
+                time_step,
+                &mut |child_idx, contribution| contributions.push((child_idx, contribution)),
+            );
+            contributions
+        },
+    ) else {
+        return;
+    };
+    let precision_value = precision_value.min(network.posterior_precision_cap(node_idx));
     network.attributes.states[node_idx].precision = precision_value;
 
-    let mean_value = mean_update_value_level_mean_field(network, node_idx, precision_value);
+    let (mean_value, pe_scaling) =
+        mean_update_value_level_mean_field(network, node_idx, time_step, precision_value);
     network.attributes.states[node_idx].mean = mean_value;
+    network.attributes.states[node_idx].pe_scaling = pe_scaling;
 }
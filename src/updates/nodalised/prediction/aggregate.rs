@@ -0,0 +1,20 @@
+use crate::model::network::Network;
+use crate::updates::nodalised::prediction::continuous::prediction_continuous_state_node;
+
+/// Prediction step for an aggregate-input node
+///
+/// The mean is predicted as for a continuous input. The observation noise is
+/// that of the fused observation: the streams are independent, so their
+/// precisions add up, π̂ = Σ_i π_i over the `input_precision` of every stream.
+/// The observation step renormalises it over the streams that are observed
+/// (see [`crate::updates::nodalised::observations::aggregate_observation_update`]).
+pub fn prediction_aggregate_input_node(network: &mut Network, node_idx: usize, time_step: f64) {
+    prediction_continuous_state_node(network, node_idx, time_step);
+    let fused_precision: f64 = network.attributes.vectors[node_idx]
+        .input_precision
+        .iter()
+        .sum();
+    let state = &mut network.attributes.states[node_idx];
+    state.expected_precision = fused_precision;
+    state.conditional_expected_precision = fused_precision;
+}
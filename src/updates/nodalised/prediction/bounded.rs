@@ -0,0 +1,31 @@
+use crate::math::latent_to_bounded;
+use crate::model::network::Network;
+use crate::updates::nodalised::prediction::continuous::{
+    prediction_continuous_state_node, prediction_continuous_state_node_mean_field,
+};
+
+/// Prediction step for a bounded-state node
+///
+/// The node is a continuous input on the logit scale of its interval, so the
+/// prediction is that of a continuous node; the predicted mean is also mapped
+/// back to the interval, in `expected_bounded_mean`.
+pub fn prediction_bounded_state_node(network: &mut Network, node_idx: usize, time_step: f64) {
+    prediction_continuous_state_node(network, node_idx, time_step);
+    set_expected_bounded_mean(network, node_idx);
+}
+
+/// Mean-field prediction step for a bounded-state node.
+pub fn prediction_bounded_state_node_mean_field(
+    network: &mut Network,
+    node_idx: usize,
+    time_step: f64,
+) {
+    prediction_continuous_state_node_mean_field(network, node_idx, time_step);
+    set_expected_bounded_mean(network, node_idx);
+}
+
+fn set_expected_bounded_mean(network: &mut Network, node_idx: usize) {
+    let state = &mut network.attributes.states[node_idx];
+    state.expected_bounded_mean =
+        latent_to_bounded(state.expected_mean, state.lower_bound, state.upper_bound);
+}
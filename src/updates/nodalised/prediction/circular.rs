@@ -0,0 +1,29 @@
+use crate::math::wrap_angle;
+use crate::model::network::Network;
+use crate::updates::nodalised::prediction::continuous::{
+    prediction_continuous_state_node, prediction_continuous_state_node_mean_field,
+};
+
+/// Prediction step for a circular-state node
+///
+/// The node is a continuous node on the circle: the prediction is that of a
+/// continuous node, with the predicted mean wrapped into `(−π, π]`.
+pub fn prediction_circular_state_node(network: &mut Network, node_idx: usize, time_step: f64) {
+    prediction_continuous_state_node(network, node_idx, time_step);
+    wrap_expected_mean(network, node_idx);
+}
+
+/// Mean-field prediction step for a circular-state node.
+pub fn prediction_circular_state_node_mean_field(
+    network: &mut Network,
+    node_idx: usize,
+    time_step: f64,
+) {
+    prediction_continuous_state_node_mean_field(network, node_idx, time_step);
+    wrap_expected_mean(network, node_idx);
+}
+
+fn wrap_expected_mean(network: &mut Network, node_idx: usize) {
+    let state = &mut network.attributes.states[node_idx];
+    state.expected_mean = wrap_angle(state.expected_mean);
+}
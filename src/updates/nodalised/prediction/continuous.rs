@@ -1,4 +1,6 @@
+use crate::math::coupling_at;
 use crate::model::network::Network;
+use crate::updates::nodalised::prediction::{take_current_variance, value_parent_moments};
 
 /// Prediction step for a continuous state node.
 ///
@@ -18,40 +20,47 @@ pub fn prediction_continuous_state_node(network: &mut Network, node_idx: usize,
     // Copy own scalar state (f64 is Copy — no borrow held)
     let mean = network.attributes.states[node_idx].mean;
     let tonic_drift = network.attributes.states[node_idx].tonic_drift;
+    let covariate_drift = network.attributes.states[node_idx].covariate_drift;
     let autoconnection_strength = network.attributes.states[node_idx].autoconnection_strength;
     let precision = network.attributes.states[node_idx].precision;
     let tonic_volatility = network.attributes.states[node_idx].tonic_volatility;
 
     // -------------------------------------------------------
-    // 1. Predict the mean: μ̂ = λ · μ + Δt · driftrate.
-    //    Also accumulate the piHGF Laplace value-coupling variance
+    // 1. Predict the mean: μ̂ = λ · μ + Δt · driftrate, with
+    //        driftrate = ρ + c + Σ_b α_b · g(μ̂_b)
+    //    where c is the drift of the measured covariates (zero without them)
+    //    and g is the edge's coupling function (identity when linear), so a
+    //    nonlinear value parent drives the drift through g, as in the posterior
+    //    updates. Also accumulate the piHGF Laplace value-coupling variance
     //        Σ_b (Δt · α · g'(μ̂_b))² / π̃_b
     //    using each parent's marginal predicted precision π̃_b
     //    (= `parent.expected_precision`). The constant-bias parent has infinite
-    //    precision and contributes zero.
+    //    precision and contributes zero. A lagged edge reads the parent's
+    //    previous posterior instead (see `value_parent_moments`).
     // -------------------------------------------------------
-    let mut driftrate = tonic_drift;
+    let mut driftrate = tonic_drift + covariate_drift;
     let mut value_coupling_variance = 0.0_f64;
 
-    if let Some(ref vp_idxs) = network.edges[node_idx].value_parents {
-        let couplings = &network.attributes.vectors[node_idx].value_coupling_parents;
-
-        for (i, &parent_idx) in vp_idxs.iter().enumerate() {
-            let parent_expected_mean = network.attributes.states[parent_idx].expected_mean;
-            let parent_expected_precision =
-                network.attributes.states[parent_idx].expected_precision;
-            let psi = couplings.get(i).copied().unwrap_or(1.0);
-            let (parent_value, g_prime) = match network.attributes.fn_ptrs[parent_idx].coupling_fn {
-                Some(cf) => ((cf.f)(parent_expected_mean), (cf.df)(parent_expected_mean)),
-                None => (parent_expected_mean, 1.0),
-            };
-            driftrate += psi * parent_value;
-            // First-order Taylor expansion of g around μ̂_b yields a
-            // (Δt · α · g'(μ̂_b))² / π̃_b contribution to the marginal
-            // predictive variance of x_a. Vanishes as π̃_b → ∞.
-            let coeff = time_step * psi * g_prime;
-            value_coupling_variance += coeff * coeff / parent_expected_precision;
-        }
+    let couplings = &network.attributes.vectors[node_idx].value_coupling_parents;
+
+    for (i, edge) in network.edge_lookup.value_parents[node_idx]
+        .iter()
+        .enumerate()
+    {
+        let parent_idx = edge.parent_idx;
+        let (parent_expected_mean, parent_expected_precision) =
+            value_parent_moments(network, node_idx, i, parent_idx);
+        let psi = couplings.get(i).copied().unwrap_or(1.0);
+        let (parent_value, g_prime) = match edge.coupling_fn {
+            Some(cf) => ((cf.f)(parent_expected_mean), (cf.df)(parent_expected_mean)),
+            None => (parent_expected_mean, 1.0),
+        };
+        driftrate += psi * parent_value;
+        // First-order Taylor expansion of g around μ̂_b yields a
+        // (Δt · α · g'(μ̂_b))² / π̃_b contribution to the marginal
+        // predictive variance of x_a. Vanishes as π̃_b → ∞.
+        let coeff = time_step * psi * g_prime;
+        value_coupling_variance += coeff * coeff / parent_expected_precision;
     }
 
     let expected_mean = autoconnection_strength * mean + time_step * driftrate;
@@ -60,9 +69,10 @@ pub fn prediction_continuous_state_node(network: &mut Network, node_idx: usize,
     // 2. Predict the two precisions:
     //        π̂ = 1 / (1/π + Ω)
     //        π̃ = 1 / (1/π̂ + value-coupling variance)
-    //    Ω = Δt · exp(ω + Σ_j κ_j μ_j + Σ_j κ_j²/(2 π̂_vol_j)); the MGF correction
-    //    κ²/(2 π̂_vol) marginalises over each volatility parent's Gaussian rather
-    //    than collapsing it to a point estimate.
+    //    Ω = Δt · exp(ω + Σ_j κ_j g_j(μ_j) + Σ_j (κ_j g_j'(μ_j))²/(2 π̂_vol_j)); the
+    //    MGF correction marginalises over each volatility parent's Gaussian
+    //    (through the linearisation of its volatility coupling function g_j)
+    //    rather than collapsing it to a point estimate.
     // -------------------------------------------------------
     let mut total_volatility = tonic_volatility;
 
@@ -74,8 +84,12 @@ pub fn prediction_continuous_state_node(network: &mut Network, node_idx: usize,
             let parent_expected_precision =
                 network.attributes.states[parent_idx].expected_precision;
             let kappa = vol_couplings.get(i).copied().unwrap_or(1.0);
-            total_volatility += kappa * parent_mean;
-            total_volatility += (kappa * kappa) / (2.0 * parent_expected_precision);
+            let (g, g_prime, _) = coupling_at(
+                network.attributes.fn_ptrs[parent_idx].volatility_coupling_fn,
+                parent_mean,
+            );
+            total_volatility += kappa * g;
+            total_volatility += (kappa * g_prime).powi(2) / (2.0 * parent_expected_precision);
         }
     }
 
@@ -95,15 +109,21 @@ pub fn prediction_continuous_state_node(network: &mut Network, node_idx: usize,
     // 3. Store results
     // -------------------------------------------------------
     let is_input = network.edges[node_idx].value_children.is_none()
-        && network.edges[node_idx].volatility_children.is_none();
+        && network.edges[node_idx].volatility_children.is_none()
+        && network.edges[node_idx].precision_children.is_none();
     let has_volatility_parents = network.edges[node_idx].volatility_parents.is_some();
+    let log_noise = precision_parents_log_noise(network, node_idx);
 
     let state = &mut network.attributes.states[node_idx];
-    state.current_variance = 1.0 / precision;
+    state.current_variance = take_current_variance(state);
     state.expected_mean = expected_mean;
     state.effective_precision = effective_precision;
 
-    if !(is_input && !has_volatility_parents) {
+    if let Some(log_noise) = log_noise {
+        // The observation noise is set by the precision parents.
+        state.expected_precision = (-log_noise).exp();
+        state.conditional_expected_precision = state.expected_precision;
+    } else if !is_input || has_volatility_parents {
         state.expected_precision = expected_precision;
         state.conditional_expected_precision = conditional_expected_precision;
     } else {
@@ -120,23 +140,26 @@ pub fn prediction_continuous_state_node_mean_field(
 ) {
     let mean = network.attributes.states[node_idx].mean;
     let tonic_drift = network.attributes.states[node_idx].tonic_drift;
+    let covariate_drift = network.attributes.states[node_idx].covariate_drift;
     let autoconnection_strength = network.attributes.states[node_idx].autoconnection_strength;
     let precision = network.attributes.states[node_idx].precision;
     let tonic_volatility = network.attributes.states[node_idx].tonic_volatility;
 
-    let mut driftrate = tonic_drift;
-
-    if let Some(ref vp_idxs) = network.edges[node_idx].value_parents {
-        let couplings = &network.attributes.vectors[node_idx].value_coupling_parents;
-        for (i, &parent_idx) in vp_idxs.iter().enumerate() {
-            let parent_expected_mean = network.attributes.states[parent_idx].expected_mean;
-            let psi = couplings.get(i).copied().unwrap_or(1.0);
-            let parent_value = match network.attributes.fn_ptrs[parent_idx].coupling_fn {
-                Some(cf) => (cf.f)(parent_expected_mean),
-                None => parent_expected_mean,
-            };
-            driftrate += psi * parent_value;
-        }
+    let mut driftrate = tonic_drift + covariate_drift;
+
+    let couplings = &network.attributes.vectors[node_idx].value_coupling_parents;
+    for (i, edge) in network.edge_lookup.value_parents[node_idx]
+        .iter()
+        .enumerate()
+    {
+        let parent_idx = edge.parent_idx;
+        let (parent_expected_mean, _) = value_parent_moments(network, node_idx, i, parent_idx);
+        let psi = couplings.get(i).copied().unwrap_or(1.0);
+        let parent_value = match edge.coupling_fn {
+            Some(cf) => (cf.f)(parent_expected_mean),
+            None => parent_expected_mean,
+        };
+        driftrate += psi * parent_value;
     }
 
     let expected_mean = autoconnection_strength * mean + time_step * driftrate;
@@ -147,7 +170,11 @@ pub fn prediction_continuous_state_node_mean_field(
         for (i, &parent_idx) in vol_parent_idxs.iter().enumerate() {
             let parent_mean = network.attributes.states[parent_idx].mean;
             let kappa = vol_couplings.get(i).copied().unwrap_or(1.0);
-            total_volatility += kappa * parent_mean;
+            let (g, _, _) = coupling_at(
+                network.attributes.fn_ptrs[parent_idx].volatility_coupling_fn,
+                parent_mean,
+            );
+            total_volatility += kappa * g;
         }
     }
 
@@ -157,18 +184,36 @@ pub fn prediction_continuous_state_node_mean_field(
     let effective_precision = predicted_volatility * expected_precision;
 
     let is_input = network.edges[node_idx].value_children.is_none()
-        && network.edges[node_idx].volatility_children.is_none();
+        && network.edges[node_idx].volatility_children.is_none()
+        && network.edges[node_idx].precision_children.is_none();
     let has_volatility_parents = network.edges[node_idx].volatility_parents.is_some();
+    let log_noise = precision_parents_log_noise(network, node_idx);
 
     let state = &mut network.attributes.states[node_idx];
-    state.current_variance = 1.0 / precision;
+    state.current_variance = take_current_variance(state);
     state.expected_mean = expected_mean;
     state.effective_precision = effective_precision;
 
-    if !(is_input && !has_volatility_parents) {
+    if let Some(log_noise) = log_noise {
+        state.expected_precision = (-log_noise).exp();
+        state.conditional_expected_precision = state.expected_precision;
+    } else if !is_input || has_volatility_parents {
         state.expected_precision = expected_precision;
         state.conditional_expected_precision = expected_precision;
     } else {
         state.conditional_expected_precision = precision;
     }
 }
+
+/// Sum of the expected means of the precision parents of `node_idx`, i.e. the
+/// log of its expected observation noise (variance), or `None` if it has no
+/// precision parents.
+fn precision_parents_log_noise(network: &Network, node_idx: usize) -> Option<f64> {
+    let parent_idxs = network.edges[node_idx].precision_parents.as_ref()?;
+    Some(
+        parent_idxs
+            .iter()
+            .map(|&parent_idx| network.attributes.states[parent_idx].expected_mean)
+            .sum(),
+    )
+}
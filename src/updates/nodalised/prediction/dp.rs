@@ -0,0 +1,57 @@
+use crate::model::network::Network;
+
+/// Prior weight, predicted mean and predictive variance of each cluster of a
+/// dp-state node, in `value_parents` order.
+///
+/// The weight of cluster `k` is the Chinese-restaurant probability
+/// `n_k / (n + α)` of an observation joining it, with `n_k` its count in
+/// `cluster_counts` (1 when missing), `n` the total count and α the node's
+/// `concentration`. An observation of cluster `k` is predicted at the
+/// cluster's `expected_mean`, with the variance of the cluster's prediction
+/// plus that of an observation around it (`1 / π̂_k + 1 / π`).
+pub(crate) fn cluster_predictions(network: &Network, node_idx: usize) -> Vec<(f64, f64, f64)> {
+    let parents = network.edges[node_idx]
+        .value_parents
+        .as_deref()
+        .unwrap_or_default();
+    let state = &network.attributes.states[node_idx];
+    let counts = &network.attributes.vectors[node_idx].cluster_counts;
+    let count = |k: usize| counts.get(k).copied().unwrap_or(1.0);
+    let total: f64 = (0..parents.len()).map(count).sum();
+    parents
+        .iter()
+        .enumerate()
+        .map(|(k, &parent_idx)| {
+            let cluster = &network.attributes.states[parent_idx];
+            (
+                count(k) / (total + state.concentration),
+                cluster.expected_mean,
+                1.0 / cluster.expected_precision + 1.0 / state.precision,
+            )
+        })
+        .collect()
+}
+
+/// Prediction from a dp-state node
+///
+/// The expected observation is the mean of the clusters' predictions under
+/// their prior weights (see [`cluster_predictions`]), or 0 while the node has
+/// no cluster; the expected precision stays at the precision of an
+/// observation around its cluster.
+pub fn prediction_dp_state_node(network: &mut Network, node_idx: usize, _time_step: f64) {
+    let clusters = cluster_predictions(network, node_idx);
+    let total: f64 = clusters.iter().map(|&(weight, _, _)| weight).sum();
+    let expected_mean = if clusters.is_empty() {
+        0.0
+    } else {
+        clusters
+            .iter()
+            .map(|&(weight, mean, _)| weight * mean)
+            .sum::<f64>()
+            / total
+    };
+
+    let state = &mut network.attributes.states[node_idx];
+    state.expected_mean = expected_mean;
+    state.expected_precision = state.precision;
+}
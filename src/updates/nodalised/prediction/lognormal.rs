@@ -0,0 +1,32 @@
+use crate::model::network::Network;
+use crate::updates::nodalised::prediction::continuous::{
+    prediction_continuous_state_node, prediction_continuous_state_node_mean_field,
+};
+
+/// Prediction step for a lognormal-state node
+///
+/// The node is a continuous input on the log scale of its observations, so
+/// the prediction is that of a continuous node. The Gaussian prediction
+/// `N(μ̂, 1/π̂)` of the log implies a log-normal prediction of the
+/// observation, whose median `exp(μ̂)` and mean `exp(μ̂ + 1/(2π̂))` are
+/// stored in `lognormal_median` and `lognormal_mean`.
+pub fn prediction_lognormal_state_node(network: &mut Network, node_idx: usize, time_step: f64) {
+    prediction_continuous_state_node(network, node_idx, time_step);
+    set_lognormal_moments(network, node_idx);
+}
+
+/// Mean-field prediction step for a lognormal-state node.
+pub fn prediction_lognormal_state_node_mean_field(
+    network: &mut Network,
+    node_idx: usize,
+    time_step: f64,
+) {
+    prediction_continuous_state_node_mean_field(network, node_idx, time_step);
+    set_lognormal_moments(network, node_idx);
+}
+
+fn set_lognormal_moments(network: &mut Network, node_idx: usize) {
+    let state = &mut network.attributes.states[node_idx];
+    state.lognormal_median = state.expected_mean.exp();
+    state.lognormal_mean = (state.expected_mean + 0.5 / state.expected_precision).exp();
+}
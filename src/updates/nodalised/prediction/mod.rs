@@ -1,3 +1,52 @@
+pub mod aggregate;
 pub mod binary;
+pub mod bounded;
+pub mod circular;
 pub mod continuous;
+pub mod dp;
+pub mod lognormal;
+pub mod softmax;
 pub mod volatile;
+
+use crate::model::network::{Network, NodeState};
+
+/// Variance of the last posterior, which the prediction steps store in
+/// `current_variance` for the unbounded updates: `initial_current_variance`
+/// at the first prediction after it is set (it is cleared on use), `1 /
+/// precision` otherwise. Lets the first step of a node initialised with a
+/// very high precision start from a sensible variance.
+pub(crate) fn take_current_variance(state: &mut NodeState) -> f64 {
+    let initial = std::mem::replace(&mut state.initial_current_variance, f64::NAN);
+    if initial.is_nan() {
+        1.0 / state.precision
+    } else {
+        initial
+    }
+}
+
+/// Mean and precision of the value parent `parent_idx`, at `position` in the
+/// `value_parents` of `node_idx`, as they enter the child's prediction. An
+/// ordinary edge passes the parent's prediction for the step
+/// (`expected_mean`, `expected_precision`); an edge flagged in
+/// `coupling_lag` passes the posterior one step earlier (`previous_mean`, or
+/// `mean` before the first update) with the parent's posterior precision.
+#[inline]
+pub(crate) fn value_parent_moments(
+    network: &Network,
+    node_idx: usize,
+    position: usize,
+    parent_idx: usize,
+) -> (f64, f64) {
+    let parent = &network.attributes.states[parent_idx];
+    let lagged = network.attributes.vectors[node_idx]
+        .coupling_lag
+        .get(position)
+        == Some(&1.0);
+    if !lagged {
+        (parent.expected_mean, parent.expected_precision)
+    } else if parent.previous_mean.is_nan() {
+        (parent.mean, parent.precision)
+    } else {
+        (parent.previous_mean, parent.precision)
+    }
+}
@@ -0,0 +1,52 @@
+use crate::{error::NetworkError, model::network::Network};
+
+/// Prediction from a softmax state node
+///
+/// The logits are the coupling-weighted expected means of the K value parents,
+/// divided by the node's `temperature`; their softmax is stored in the node's
+/// `probabilities` vector (one entry per parent, in `value_parents` order).
+/// A node without one coupling per parent leaves a
+/// [`NetworkError::DimensionMismatch`] in `network.step_error`.
+pub fn prediction_softmax_state_node(network: &mut Network, node_idx: usize, _time_step: f64) {
+    let parents = network.edges[node_idx]
+        .value_parents
+        .as_deref()
+        .unwrap_or_default();
+    let n_couplings = network.attributes.vectors[node_idx]
+        .value_coupling_parents
+        .len();
+    if n_couplings != parents.len() {
+        network.step_error = Some(NetworkError::DimensionMismatch {
+            what: "value_coupling_parents (one per value parent)",
+            expected: parents.len(),
+            found: n_couplings,
+        });
+        return;
+    }
+    let states = &network.attributes.states;
+    let temperature = states[node_idx].temperature;
+    let vectors = &mut network.attributes.vectors[node_idx];
+
+    // Logits first, then exponentiated in place after subtracting the largest
+    // one for numerical stability.
+    let probabilities = &mut vectors.probabilities;
+    probabilities.clear();
+    probabilities.extend(
+        parents
+            .iter()
+            .zip(&vectors.value_coupling_parents)
+            .map(|(&parent_idx, &kappa)| kappa * states[parent_idx].expected_mean / temperature),
+    );
+    let max_logit = probabilities
+        .iter()
+        .copied()
+        .fold(f64::NEG_INFINITY, f64::max);
+    let mut total = 0.0;
+    for p in probabilities.iter_mut() {
+        *p = (*p - max_logit).exp();
+        total += *p;
+    }
+    for p in probabilities.iter_mut() {
+        *p /= total;
+    }
+}
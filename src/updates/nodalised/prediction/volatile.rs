@@ -1,4 +1,5 @@
 use crate::model::network::Network;
+use crate::updates::nodalised::prediction::{take_current_variance, value_parent_moments};
 
 /// Prediction step for a volatile state node.
 ///
@@ -14,7 +15,8 @@ use crate::model::network::Network;
 ///   contribution from each value parent (using the parent's marginal predicted
 ///   precision π̃_b).
 ///
-/// Ω = Δt · exp(μ_vol + 1 / (2 π̂_vol)); the volatility coupling is fixed at 1,
+/// Ω = Δt · exp(μ̂_vol + 1 / (2 π̂_vol)) with μ̂_vol = λ_vol · μ_vol (λ_vol is
+/// `autoconnection_strength_vol`, 1 by default); the volatility coupling is fixed at 1,
 /// the value level has no tonic volatility of its own, and the MGF correction
 /// 1 / (2 π̂_vol) marginalises over the implicit volatility level's Gaussian
 /// rather than collapsing it to a point estimate.
@@ -24,12 +26,11 @@ pub fn prediction_volatile_state_node(network: &mut Network, node_idx: usize, ti
     let mean = network.attributes.states[node_idx].mean;
     let autoconnection_strength = network.attributes.states[node_idx].autoconnection_strength;
     let mean_vol = network.attributes.states[node_idx].mean_vol;
+    let autoconnection_strength_vol =
+        network.attributes.states[node_idx].autoconnection_strength_vol;
     let precision_vol = network.attributes.states[node_idx].precision_vol;
     let tonic_volatility_vol = network.attributes.states[node_idx].tonic_volatility_vol;
 
-    // Store current variance for unbounded updates
-    let current_variance = 1.0 / precision;
-
     // ===================================================================
     // 1. PREDICT VOLATILITY LEVEL (implicit internal state)
     // ===================================================================
@@ -37,6 +38,7 @@ pub fn prediction_volatile_state_node(network: &mut Network, node_idx: usize, ti
     let predicted_volatility_vol = if pvv_raw > 1e-128 { pvv_raw } else { f64::NAN };
     let expected_precision_vol = 1.0 / ((1.0 / precision_vol) + predicted_volatility_vol);
     let effective_precision_vol = predicted_volatility_vol * expected_precision_vol;
+    let expected_mean_vol = autoconnection_strength_vol * mean_vol;
 
     // ===================================================================
     // 2. PREDICT VALUE LEVEL (external facing)
@@ -49,22 +51,23 @@ pub fn prediction_volatile_state_node(network: &mut Network, node_idx: usize, ti
     //         (= `parent.expected_precision`).
     let mut driftrate = 0.0;
     let mut value_coupling_variance = 0.0_f64;
-    if let Some(ref vp_idxs) = network.edges[node_idx].value_parents {
-        let couplings = &network.attributes.vectors[node_idx].value_coupling_parents;
-
-        for (i, &parent_idx) in vp_idxs.iter().enumerate() {
-            let parent_expected_mean = network.attributes.states[parent_idx].expected_mean;
-            let parent_expected_precision =
-                network.attributes.states[parent_idx].expected_precision;
-            let value_coupling_parent = couplings.get(i).copied().unwrap_or(1.0);
-            let (parent_value, g_prime) = match network.attributes.fn_ptrs[parent_idx].coupling_fn {
-                Some(cf) => ((cf.f)(parent_expected_mean), (cf.df)(parent_expected_mean)),
-                None => (parent_expected_mean, 1.0),
-            };
-            driftrate += value_coupling_parent * parent_value;
-            let coeff = time_step * value_coupling_parent * g_prime;
-            value_coupling_variance += coeff * coeff / parent_expected_precision;
-        }
+    let couplings = &network.attributes.vectors[node_idx].value_coupling_parents;
+
+    for (i, edge) in network.edge_lookup.value_parents[node_idx]
+        .iter()
+        .enumerate()
+    {
+        let parent_idx = edge.parent_idx;
+        let (parent_expected_mean, parent_expected_precision) =
+            value_parent_moments(network, node_idx, i, parent_idx);
+        let value_coupling_parent = couplings.get(i).copied().unwrap_or(1.0);
+        let (parent_value, g_prime) = match edge.coupling_fn {
+            Some(cf) => ((cf.f)(parent_expected_mean), (cf.df)(parent_expected_mean)),
+            None => (parent_expected_mean, 1.0),
+        };
+        driftrate += value_coupling_parent * parent_value;
+        let coeff = time_step * value_coupling_parent * g_prime;
+        value_coupling_variance += coeff * coeff / parent_expected_precision;
     }
 
     let expected_mean = autoconnection_strength * mean + time_step * driftrate;
@@ -76,7 +79,7 @@ pub fn prediction_volatile_state_node(network: &mut Network, node_idx: usize, ti
     //         volatility level's Gaussian yields the closed-form
     //         moment-generating-function correction 1 / (2 · π̂_vol) inside the
     //         log-volatility exponent.
-    let total_volatility = mean_vol + 1.0 / (2.0 * expected_precision_vol);
+    let total_volatility = expected_mean_vol + 1.0 / (2.0 * expected_precision_vol);
     let pv_raw = time_step * total_volatility.exp();
     let predicted_volatility = if pv_raw > 1e-128 { pv_raw } else { f64::NAN };
     // Conditional predicted precision π̂_a — precision of x_a given its value
@@ -100,8 +103,8 @@ pub fn prediction_volatile_state_node(network: &mut Network, node_idx: usize, ti
 
     // Store all results
     let state = &mut network.attributes.states[node_idx];
-    state.current_variance = current_variance;
-    state.expected_mean_vol = mean_vol;
+    state.current_variance = take_current_variance(state);
+    state.expected_mean_vol = expected_mean_vol;
     state.expected_precision_vol = expected_precision_vol;
     state.effective_precision_vol = effective_precision_vol;
     state.expected_mean = expected_mean;
@@ -128,35 +131,38 @@ pub fn prediction_volatile_state_node_mean_field(
     let mean = network.attributes.states[node_idx].mean;
     let autoconnection_strength = network.attributes.states[node_idx].autoconnection_strength;
     let mean_vol = network.attributes.states[node_idx].mean_vol;
+    let autoconnection_strength_vol =
+        network.attributes.states[node_idx].autoconnection_strength_vol;
     let precision_vol = network.attributes.states[node_idx].precision_vol;
     let tonic_volatility_vol = network.attributes.states[node_idx].tonic_volatility_vol;
 
-    let current_variance = 1.0 / precision;
-
     // Volatility level (unchanged)
     let pvv_raw = time_step * tonic_volatility_vol.exp();
     let predicted_volatility_vol = if pvv_raw > 1e-128 { pvv_raw } else { f64::NAN };
     let expected_precision_vol = 1.0 / ((1.0 / precision_vol) + predicted_volatility_vol);
     let effective_precision_vol = predicted_volatility_vol * expected_precision_vol;
+    let expected_mean_vol = autoconnection_strength_vol * mean_vol;
 
     // Value level mean (no change)
     let mut driftrate = 0.0;
-    if let Some(ref vp_idxs) = network.edges[node_idx].value_parents {
-        let couplings = &network.attributes.vectors[node_idx].value_coupling_parents;
-        for (i, &parent_idx) in vp_idxs.iter().enumerate() {
-            let parent_expected_mean = network.attributes.states[parent_idx].expected_mean;
-            let psi = couplings.get(i).copied().unwrap_or(1.0);
-            let parent_value = match network.attributes.fn_ptrs[parent_idx].coupling_fn {
-                Some(cf) => (cf.f)(parent_expected_mean),
-                None => parent_expected_mean,
-            };
-            driftrate += psi * parent_value;
-        }
+    let couplings = &network.attributes.vectors[node_idx].value_coupling_parents;
+    for (i, edge) in network.edge_lookup.value_parents[node_idx]
+        .iter()
+        .enumerate()
+    {
+        let parent_idx = edge.parent_idx;
+        let (parent_expected_mean, _) = value_parent_moments(network, node_idx, i, parent_idx);
+        let psi = couplings.get(i).copied().unwrap_or(1.0);
+        let parent_value = match edge.coupling_fn {
+            Some(cf) => (cf.f)(parent_expected_mean),
+            None => parent_expected_mean,
+        };
+        driftrate += psi * parent_value;
     }
     let expected_mean = autoconnection_strength * mean + time_step * driftrate;
 
     // Value level precision — no MGF, no Laplace correction (coupling fixed at 1)
-    let total_volatility = mean_vol;
+    let total_volatility = expected_mean_vol;
     let pv_raw = time_step * total_volatility.exp();
     let predicted_volatility = if pv_raw > 1e-128 { pv_raw } else { f64::NAN };
     let expected_precision = 1.0 / ((1.0 / precision) + predicted_volatility);
@@ -165,8 +171,8 @@ pub fn prediction_volatile_state_node_mean_field(
     let is_input = network.edges[node_idx].value_children.is_none();
 
     let state = &mut network.attributes.states[node_idx];
-    state.current_variance = current_variance;
-    state.expected_mean_vol = mean_vol;
+    state.current_variance = take_current_variance(state);
+    state.expected_mean_vol = expected_mean_vol;
     state.expected_precision_vol = expected_precision_vol;
     state.effective_precision_vol = effective_precision_vol;
     state.expected_mean = expected_mean;
@@ -13,3 +13,28 @@ pub fn prediction_error_binary_state_node(network: &mut Network, node_idx: usize
     state.value_prediction_error = value_prediction_error;
     state.precision = expected_precision;
 }
+
+/// Prediction error for a binary input node
+///
+/// The node has no prediction step of its own: the Bernoulli prediction is the
+/// sigmoid of its value parents' summed expected mean, clipped like
+/// [`prediction_binary_state_node`](crate::updates::nodalised::prediction::binary::prediction_binary_state_node),
+/// and the 0/1 observation is compared against it here.
+pub fn prediction_error_binary_input(network: &mut Network, node_idx: usize, _time_step: f64) {
+    let mut expected_mean: f64 = 0.0;
+    if let Some(ref vp_idxs) = network.edges[node_idx].value_parents {
+        for &parent_idx in vp_idxs {
+            expected_mean += network.attributes.states[parent_idx].expected_mean;
+        }
+    }
+    let v = network.precision_clipping_value;
+    let expected_mean = (1.0 / (1.0 + (-expected_mean).exp())).clamp(v, 1.0 - v);
+    let expected_precision = expected_mean * (1.0 - expected_mean);
+
+    let state = &mut network.attributes.states[node_idx];
+    state.expected_mean = expected_mean;
+    state.expected_precision = expected_precision;
+    state.value_prediction_error =
+        (state.mean - expected_mean) * state.observed / expected_precision;
+    state.precision = expected_precision;
+}
@@ -0,0 +1,32 @@
+use crate::math::wrap_angle;
+use crate::model::network::Network;
+
+/// Prediction error from a circular-state node
+///
+/// As for a continuous node, but the value prediction error is the wrapped
+/// difference `δ = atan2(sin(μ − μ̂), cos(μ − μ̂))`, the shortest arc from the
+/// prediction to the mean, so that crossing `±π` is not read as a jump of
+/// `2π`. The volatility prediction error stays Gaussian on the wrapped error,
+/// `Δ = π̂ / π + π̂·δ² − 1`, as an approximation of the von Mises update.
+pub fn prediction_error_circular_state_node(
+    network: &mut Network,
+    node_idx: usize,
+    _time_step: f64,
+) {
+    let n_volatility_parents = network.edges[node_idx]
+        .volatility_parents
+        .as_ref()
+        .map(|vp| vp.len());
+
+    let state = &mut network.attributes.states[node_idx];
+    let value_prediction_error = wrap_angle(state.mean - state.expected_mean);
+    let mut volatility_prediction_error = (state.expected_precision / state.precision)
+        + state.expected_precision * value_prediction_error.powi(2)
+        - 1.0;
+    if let Some(n) = n_volatility_parents {
+        volatility_prediction_error /= n as f64;
+    }
+
+    state.value_prediction_error = value_prediction_error;
+    state.volatility_prediction_error = volatility_prediction_error;
+}
@@ -1,6 +1,13 @@
 use crate::model::network::Network;
 
 /// Prediction error from a continuous state node
+///
+/// Only the volatility prediction error is split across the volatility
+/// parents. The value prediction error is not normalised: each value parent
+/// weighs it by the child's precision, and a parent with several children adds
+/// up their weighted errors (see `mean_update_from_children`). Inputs with a
+/// Laplace likelihood pass on a clipped value prediction error (see
+/// [`Network::set_likelihood`]).
 pub fn prediction_error_continuous_state_node(
     network: &mut Network,
     node_idx: usize,
@@ -16,8 +23,14 @@ pub fn prediction_error_continuous_state_node(
     let precision = network.attributes.states[node_idx].precision;
     let expected_precision = network.attributes.states[node_idx].expected_precision;
 
-    // Value prediction error: δ = μ - μ̂
-    let value_prediction_error = mean - expected_mean;
+    // Value prediction error: δ = μ - μ̂. Under a Laplace likelihood of
+    // variance 1/π̂ its score √(2π̂)·sign(δ) bounds the weighted error, which
+    // amounts to clipping δ at ±√(2/π̂).
+    let mut value_prediction_error = mean - expected_mean;
+    if network.edges[node_idx].likelihood == "laplace" {
+        let bound = (2.0 / expected_precision).sqrt();
+        value_prediction_error = value_prediction_error.clamp(-bound, bound);
+    }
 
     // Volatility prediction error: Δ = (π̂ / π) + π̂ · δ² - 1
     let mut volatility_prediction_error = (expected_precision / precision)
@@ -0,0 +1,66 @@
+use crate::math::gaussian_log_density;
+use crate::model::network::Network;
+use crate::updates::nodalised::prediction::dp::cluster_predictions;
+
+/// Prediction error for a dp-state node
+///
+/// Evaluates the predictive density of the observation (`mean`) under every
+/// cluster. When the largest one reaches the node's `density_threshold`, the
+/// responsibilities `r_k ∝ w_k · N(x; μ̂_k, σ²_k)` are stored in
+/// `probabilities`, added to `cluster_counts` (weighted by `observed`), and
+/// read by the clusters' posterior updates. Otherwise no cluster explains the
+/// observation: every responsibility is 0 and the node is queued in
+/// `Network::pending_clusters`, so that a new cluster seeded by the
+/// observation is added once the time step is over (see
+/// [`crate::utils::clusters::spawn_clusters`]).
+///
+/// The surprise is that of the observation under the mixture of clusters,
+/// capped at `-ln(density_threshold)` when a cluster is opened.
+pub fn prediction_error_dp_state_node(network: &mut Network, node_idx: usize, _time_step: f64) {
+    let clusters = cluster_predictions(network, node_idx);
+    let state = &network.attributes.states[node_idx];
+    let (x, threshold, observed) = (state.mean, state.density_threshold, state.observed);
+
+    let densities: Vec<f64> = clusters
+        .iter()
+        .map(|&(_, mean, variance)| gaussian_log_density(x, mean, 1.0 / variance).exp())
+        .collect();
+    let best = densities.iter().copied().fold(0.0, f64::max);
+    let opens_cluster = observed > 0.0 && best < threshold;
+
+    let joint: Vec<f64> = clusters
+        .iter()
+        .zip(&densities)
+        .map(|(&(weight, _, _), &density)| weight * density)
+        .collect();
+    let evidence: f64 = joint.iter().sum();
+    let total_weight: f64 = clusters.iter().map(|&(weight, _, _)| weight).sum();
+
+    let vectors = &mut network.attributes.vectors[node_idx];
+    vectors.probabilities.clear();
+    if opens_cluster || evidence <= 0.0 {
+        vectors.probabilities.resize(clusters.len(), 0.0);
+    } else {
+        vectors
+            .probabilities
+            .extend(joint.iter().map(|&j| j / evidence));
+    }
+    vectors.cluster_counts.resize(clusters.len(), 1.0);
+    for (count, &r) in vectors
+        .cluster_counts
+        .iter_mut()
+        .zip(&vectors.probabilities)
+    {
+        *count += observed * r;
+    }
+
+    let state = &mut network.attributes.states[node_idx];
+    state.surprise = if opens_cluster {
+        -threshold.ln()
+    } else {
+        observed * -(evidence / total_weight).ln()
+    };
+    if opens_cluster {
+        network.pending_clusters.push(node_idx);
+    }
+}
@@ -2,17 +2,47 @@ use crate::math::sufficient_statistics;
 use crate::model::network::Network;
 
 /// Updating an exponential family state node
+///
+/// The expected sufficient statistics `xis` move towards those of the new
+/// observation with the learning rate `1 / (1 + φ·nus)`. The forgetting
+/// factor φ discounts the pseudo-count `nus` (and so the weight of the
+/// accumulated `xis`) before the observation is incorporated: this is
+/// conjugate updating with exponential forgetting, and φ = 1 keeps the fixed
+/// learning rate `1 / (1 + nus)`.
+///
+/// The variance read from `xis` as `E[x²] − E[x]²` cancels catastrophically
+/// when the observations sit far from zero. With the `welford` flag set, the
+/// node instead tracks the mean `m` and variance `v` directly with the same
+/// learning rate α (Welford's weighted update):
+///
+/// `δ = x − m`, `m ← m + α·δ`, `v ← (1 − α)·(v + α·δ²)`,
+///
+/// and `xis` is rebuilt as `[m, v + m²]`. Both paths expose the running
+/// moments as `tracked_mean` and `tracked_variance`.
 pub fn prediction_error_exponential_state_node(
     network: &mut Network,
     node_idx: usize,
     _time_step: f64,
 ) {
-    let mean = network.attributes.states[node_idx].mean;
-    let nus = network.attributes.states[node_idx].nus;
+    let state = &mut network.attributes.states[node_idx];
+    let mean = state.mean;
+    let nus = state.forgetting * state.nus;
+    let alpha = 1.0 / (1.0 + nus);
+    let xis = &mut network.attributes.vectors[node_idx].xis;
+
+    if state.welford != 0.0 {
+        let delta = mean - state.tracked_mean;
+        state.tracked_mean += alpha * delta;
+        state.tracked_variance = (1.0 - alpha) * (state.tracked_variance + alpha * delta * delta);
+        xis[0] = state.tracked_mean;
+        xis[1] = state.tracked_variance + state.tracked_mean * state.tracked_mean;
+        return;
+    }
 
     let suf_stats = sufficient_statistics(mean);
-    let xis = &mut network.attributes.vectors[node_idx].xis;
     for i in 0..suf_stats.len() {
-        xis[i] = xis[i] + (1.0 / (1.0 + nus)) * (suf_stats[i] - xis[i]);
+        xis[i] = xis[i] + alpha * (suf_stats[i] - xis[i]);
     }
+    state.tracked_mean = xis[0];
+    state.tracked_variance = xis[1] - xis[0] * xis[0];
 }
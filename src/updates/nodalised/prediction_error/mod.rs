@@ -1,4 +1,7 @@
 pub mod binary;
+pub mod circular;
 pub mod continuous;
+pub mod dp;
 pub mod exponential;
+pub mod softmax;
 pub mod volatile;
@@ -0,0 +1,28 @@
+use crate::model::network::Network;
+
+/// Prediction error for a softmax state node
+///
+/// The observed choice is the index of the selected alternative, stored in
+/// `mean`; its categorical surprise `-ln p[choice]` is stored in `surprise`
+/// (NaN when the index does not name one of the alternatives). The node is a
+/// readout: it sends no Gaussian message back to its value parents.
+pub fn prediction_error_softmax_state_node(
+    network: &mut Network,
+    node_idx: usize,
+    _time_step: f64,
+) {
+    let choice = network.attributes.states[node_idx].mean;
+    let probability = if choice >= 0.0 && choice.fract() == 0.0 {
+        network.attributes.vectors[node_idx]
+            .probabilities
+            .get(choice as usize)
+            .copied()
+    } else {
+        None
+    };
+
+    let state = &mut network.attributes.states[node_idx];
+    state.surprise = probability.map_or(f64::NAN, |p| -p.ln());
+    state.value_prediction_error = 0.0;
+    state.precision = state.expected_precision;
+}
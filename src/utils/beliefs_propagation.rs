@@ -1,9 +1,18 @@
 use crate::{
-    model::network::Network, updates::nodalised::observations::observation_update,
-    utils::function_pointer::UpdateStep,
+    error::NetworkError,
+    model::network::Network,
+    updates::nodalised::observations::{
+        aggregate_observation_update, check_observation, observation_update,
+    },
+    utils::{entropy::set_entropy, function_pointer::UpdateStep, surprise::set_surprise},
 };
 
 /// Single time slice belief propagation.
+///
+/// `observations_set` holds one value per input node, in `network.inputs`
+/// order, or one per stream for an aggregate-input node (see
+/// [`input_columns`]); it may be shorter than that, but not longer. The row
+/// must have passed [`check_observations`], which callers run once up front.
 #[inline(always)]
 pub fn belief_propagation(
     network: &mut Network,
@@ -11,20 +20,126 @@ pub fn belief_propagation(
     predictions: &[(usize, UpdateStep)],
     updates: &[(usize, UpdateStep)],
     time_step: f64,
-) {
-    // 1. prediction steps
+) -> Result<(), NetworkError> {
+    run_predictions(network, predictions, time_step)?;
+    set_observations(network, observations_set)?;
+    run_updates(network, updates, time_step)
+}
+
+/// Number of observation columns input node `node_idx` reads: one per
+/// stream (`input_precision` entry) for an aggregate-input node, one
+/// otherwise.
+pub(crate) fn input_columns(network: &Network, node_idx: usize) -> usize {
+    match network.edges.get(node_idx) {
+        Some(edges) if edges.node_type == "aggregate-input" => {
+            network.attributes.vectors[node_idx].input_precision.len()
+        }
+        _ => 1,
+    }
+}
+
+/// Number of input nodes that read at least one value of a row of
+/// `n_columns` observations.
+pub(crate) fn observed_inputs(network: &Network, n_columns: usize) -> usize {
+    let mut offset = 0;
+    network
+        .inputs
+        .iter()
+        .take_while(|&&idx| {
+            let observed = offset < n_columns;
+            offset += input_columns(network, idx);
+            observed
+        })
+        .count()
+}
+
+/// Reject observation sets with more values than the input nodes read, or
+/// with a value an input node cannot take.
+pub(crate) fn check_observations(
+    network: &Network,
+    observations_set: &[f64],
+) -> Result<(), NetworkError> {
+    let n_columns: usize = network
+        .inputs
+        .iter()
+        .map(|&idx| input_columns(network, idx))
+        .sum();
+    if observations_set.len() > n_columns {
+        return Err(NetworkError::DimensionMismatch {
+            what:
+                "observations per time step (at most one per input node or aggregate-input stream)",
+            expected: n_columns,
+            found: observations_set.len(),
+        });
+    }
+    let mut offset = 0;
+    for &idx in &network.inputs {
+        let width = input_columns(network, idx);
+        if width == 1 && offset < observations_set.len() {
+            check_observation(network, idx, observations_set[offset])?;
+        }
+        offset += width;
+    }
+    Ok(())
+}
+
+/// Prediction phase of [`belief_propagation`].
+#[inline(always)]
+pub fn run_predictions(
+    network: &mut Network,
+    predictions: &[(usize, UpdateStep)],
+    time_step: f64,
+) -> Result<(), NetworkError> {
     for &(idx, step) in predictions {
-        step.call(network, idx, time_step);
+        step.call(network, idx, time_step)?;
     }
+    Ok(())
+}
 
-    // 2. observation steps
-    for (i, &observation) in observations_set.iter().enumerate() {
+/// Observation phase of [`belief_propagation`]: write one value per input
+/// node, in `network.inputs` order, and fuse the streams of the
+/// aggregate-input nodes. The row must have passed [`check_observations`].
+#[inline(always)]
+pub fn set_observations(
+    network: &mut Network,
+    observations_set: &[f64],
+) -> Result<(), NetworkError> {
+    let mut offset = 0;
+    for i in 0..network.inputs.len() {
+        if offset >= observations_set.len() {
+            break;
+        }
         let idx = network.inputs[i];
-        observation_update(network, idx, observation);
+        network.check_node(idx, "observation_update")?;
+        let end = (offset + input_columns(network, idx)).min(observations_set.len());
+        if network.edges[idx].node_type == "aggregate-input" {
+            aggregate_observation_update(network, idx, &observations_set[offset..end]);
+        } else {
+            observation_update(network, idx, observations_set[offset])?;
+        }
+        offset = end;
     }
+    Ok(())
+}
 
-    // 3. update steps
+/// Update phase of [`belief_propagation`] (prediction errors and posteriors),
+/// starting with the `previous_mean` of every node (read by lagged value
+/// edges at the next prediction) and ending with the surprise (see [`crate::utils::surprise::node_surprise`])
+/// and the entropy (see [`crate::utils::entropy::set_entropy`]) of every
+/// node.
+#[inline(always)]
+pub fn run_updates(
+    network: &mut Network,
+    updates: &[(usize, UpdateStep)],
+    time_step: f64,
+) -> Result<(), NetworkError> {
+    for state in &mut network.attributes.states {
+        state.previous_mean = state.mean;
+    }
     for &(idx, step) in updates {
-        step.call(network, idx, time_step);
+        step.call(network, idx, time_step)?;
     }
+    set_surprise(network);
+    set_entropy(network);
+    Ok(())
 }
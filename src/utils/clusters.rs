@@ -0,0 +1,43 @@
+//! Clusters opened during a run by dp-state nodes, when an observation is
+//! unlikely under every existing cluster.
+
+use std::collections::HashMap;
+
+use crate::error::NetworkError;
+use crate::model::network::{AddNodesOptions, Network};
+
+/// Give every dp-state node of `network.pending_clusters` a new cluster: a
+/// continuous-state value parent centred on the node's last observation, with
+/// the precision of one observation. The new cluster takes the full
+/// responsibility for that observation and a count of 1.
+///
+/// Adding the clusters resets the update sequence; the runs rebuild it, and
+/// the recorded trajectories, before the next time step. Returns whether any
+/// cluster was added.
+pub(crate) fn spawn_clusters(network: &mut Network) -> Result<bool, NetworkError> {
+    let mut pending = std::mem::take(&mut network.pending_clusters);
+    pending.sort_unstable();
+    pending.dedup();
+    for &node_idx in &pending {
+        let state = &network.attributes.states[node_idx];
+        let (observation, precision) = (state.mean, state.precision);
+        network.add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(node_idx.into()),
+                additional_parameters: Some(HashMap::from([
+                    ("mean".to_string(), observation),
+                    ("expected_mean".to_string(), observation),
+                    ("precision".to_string(), precision),
+                    ("expected_precision".to_string(), precision),
+                ])),
+                ..Default::default()
+            },
+        )?;
+        let vectors = &mut network.attributes.vectors[node_idx];
+        vectors.probabilities.push(1.0);
+        vectors.cluster_counts.push(1.0);
+    }
+    Ok(!pending.is_empty())
+}
@@ -0,0 +1,242 @@
+//! Comparison and structural hashing of networks, e.g. to cache fitted
+//! models or to check regressions.
+//!
+//! Two networks are equal when they have the same structure (node kinds,
+//! adjacency lists, inputs, coupling functions by name and update options)
+//! and the same scalar and vector attributes. Recorded trajectories, the
+//! cached update sequence and Python steps are not compared.
+
+use crate::math::CouplingFn;
+use crate::model::network::{
+    AdjacencyLists, Network, NodeFnPtrs, STATE_FIELDS, STATE_VECTOR_FIELDS,
+};
+
+/// Whether `a` and `b` differ by at most `tol`. NaN equals NaN, so that unset
+/// optional fields (e.g. `lr`) compare equal.
+fn close(a: f64, b: f64, tol: f64) -> bool {
+    a == b || (a.is_nan() && b.is_nan()) || (a - b).abs() <= tol
+}
+
+/// Whether `a` and `b` have the same structure.
+pub fn same_structure(a: &Network, b: &Network) -> bool {
+    a.edges == b.edges
+        && a.inputs == b.inputs
+        && a.attributes.fn_ptrs == b.attributes.fn_ptrs
+        && a.volatility_updates == b.volatility_updates
+        && a.mean_field_updates == b.mean_field_updates
+}
+
+/// Whether `a` and `b` have the same structure and attributes, floats being
+/// compared up to the absolute tolerance `tol`.
+pub fn approx_eq(a: &Network, b: &Network, tol: f64) -> bool {
+    if !same_structure(a, b) {
+        return false;
+    }
+    let states = a.attributes.states.iter().zip(&b.attributes.states);
+    let states_eq = states.into_iter().all(|(sa, sb)| {
+        STATE_FIELDS
+            .iter()
+            .all(|&field| match (sa.get(field), sb.get(field)) {
+                (Some(x), Some(y)) => close(x, y, tol),
+                _ => true,
+            })
+    });
+    let vectors = a.attributes.vectors.iter().zip(&b.attributes.vectors);
+    let vectors_eq = vectors.into_iter().all(|(va, vb)| {
+        STATE_VECTOR_FIELDS
+            .iter()
+            .all(|&field| match (va.get(field), vb.get(field)) {
+                (Some(x), Some(y)) => {
+                    x.len() == y.len() && x.iter().zip(y).all(|(&x, &y)| close(x, y, tol))
+                }
+                _ => true,
+            })
+    });
+    states_eq && vectors_eq
+}
+
+/// 64-bit FNV-1a over an explicit byte encoding of the structure: unlike
+/// `std`'s `DefaultHasher` and derived `Hash` impls, neither the algorithm
+/// nor the encoding depends on the Rust release or the platform.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= u64::from(byte);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn str(&mut self, value: &str) {
+        self.u64(value.len() as u64);
+        self.bytes(value.as_bytes());
+    }
+
+    fn idxs(&mut self, idxs: &[usize]) {
+        self.u64(idxs.len() as u64);
+        for &idx in idxs {
+            self.u64(idx as u64);
+        }
+    }
+
+    /// A missing list and an empty one hash differently, as they compare.
+    fn opt_idxs(&mut self, idxs: &Option<Vec<usize>>) {
+        match idxs {
+            Some(idxs) => self.idxs(idxs),
+            None => self.u64(u64::MAX),
+        }
+    }
+
+    /// Coupling functions by name, the identity being `""` when unset.
+    fn coupling_fn(&mut self, coupling_fn: Option<&CouplingFn>) {
+        self.str(coupling_fn.map_or("", |cf| cf.kind.name()));
+    }
+}
+
+/// Hash of the structure of `network`, ignoring every parameter value: two
+/// networks with the same structure (see [`same_structure`]) hash the same.
+///
+/// The hash is stable: the same structure hashes to the same value across
+/// Rust releases, platforms and runs, so it can key caches kept on disk. It
+/// only changes when the hashed structure itself gains a field.
+pub fn structure_hash(network: &Network) -> u64 {
+    let mut hasher = Fnv1a::new();
+    hasher.u64(network.edges.len() as u64);
+    for edges in &network.edges {
+        // Destructured, so that a new adjacency field cannot be left out.
+        let AdjacencyLists {
+            node_type,
+            learning_kind,
+            value_parents,
+            value_children,
+            volatility_parents,
+            volatility_children,
+            precision_parents,
+            precision_children,
+            likelihood,
+        } = edges;
+        hasher.str(node_type);
+        hasher.str(learning_kind);
+        for idxs in [
+            value_parents,
+            value_children,
+            volatility_parents,
+            volatility_children,
+            precision_parents,
+            precision_children,
+        ] {
+            hasher.opt_idxs(idxs);
+        }
+        hasher.str(likelihood);
+    }
+    hasher.idxs(&network.inputs);
+    for fn_ptrs in &network.attributes.fn_ptrs {
+        let NodeFnPtrs {
+            coupling_fn,
+            volatility_coupling_fn,
+            value_coupling_fn_children,
+        } = fn_ptrs;
+        hasher.coupling_fn(*coupling_fn);
+        hasher.coupling_fn(*volatility_coupling_fn);
+        hasher.u64(value_coupling_fn_children.len() as u64);
+        for &child_fn in value_coupling_fn_children {
+            hasher.coupling_fn(child_fn);
+        }
+    }
+    hasher.str(&network.volatility_updates);
+    hasher.bytes(&[u8::from(network.mean_field_updates)]);
+    hasher.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::network::{AddNodesOptions, InputDataOptions};
+
+    fn two_level_network() -> Network {
+        let mut network = Network::new("eHGF");
+        network
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+    }
+
+    #[test]
+    fn test_identical_networks_compare_equal() {
+        let (a, mut b) = (two_level_network(), two_level_network());
+        assert!(a == b);
+        assert_eq!(a.structure_hash(), b.structure_hash());
+
+        // Trajectories are not compared.
+        b.input_data(vec![vec![0.0]], None, true, InputDataOptions::default())
+            .unwrap();
+        b.attributes = a.attributes.clone();
+        assert!(a == b);
+    }
+
+    #[test]
+    fn test_set_coupling_changes_the_parameters_only() {
+        let (a, mut b) = (two_level_network(), two_level_network());
+        b.set_coupling(1, 0, 1.0 + 1e-9).unwrap();
+        assert!(a != b);
+        assert!(a.approx_eq(&b, 1e-6));
+        assert_eq!(a.structure_hash(), b.structure_hash());
+
+        b.set_coupling(1, 0, 0.5).unwrap();
+        assert!(!a.approx_eq(&b, 1e-6));
+        assert_eq!(a.structure_hash(), b.structure_hash());
+    }
+
+    #[test]
+    fn test_structure_hash_is_pinned() {
+        // FNV-1a reference vector.
+        let mut hasher = Fnv1a::new();
+        hasher.bytes(b"a");
+        assert_eq!(hasher.0, 0xaf63_dc4c_8601_ec8c);
+
+        // A change of this value breaks the caches keyed by it.
+        assert_eq!(two_level_network().structure_hash(), 0x7576_0b60_5492_01d5);
+    }
+
+    #[test]
+    fn test_structure_changes_the_hash() {
+        let a = two_level_network();
+
+        let mut b = two_level_network();
+        b.set_volatility_coupling_fn(1, "softplus").unwrap();
+        assert!(a != b);
+        assert_ne!(a.structure_hash(), b.structure_hash());
+
+        let mut c = two_level_network();
+        c.add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                volatility_children: Some(1.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert!(a != c);
+        assert_ne!(a.structure_hash(), c.structure_hash());
+    }
+}
@@ -0,0 +1,118 @@
+//! Per-trial condition labels that switch node parameters, e.g. the tonic
+//! volatility of a cued stable or volatile block.
+
+use std::collections::{BTreeSet, HashMap};
+
+use crate::error::NetworkError;
+use crate::model::network::{parameters_for_type, Network, STATE_FIELDS};
+
+/// Parameter values by condition label, then by `(node_idx, parameter)`.
+pub type ConditionParameters = HashMap<usize, HashMap<(usize, String), f64>>;
+
+/// The parameters a run switches, and their value under each condition.
+///
+/// Every parameter named by any condition is set at every time step: to the
+/// value of the step's condition when it names it, and otherwise to the value
+/// the network had before the run, so that a condition never inherits the
+/// values of the previous one.
+pub(crate) struct ConditionSchedule<'a> {
+    labels: &'a [usize],
+    keys: Vec<(usize, &'static str)>,
+    baseline: Vec<f64>,
+    values: HashMap<usize, Vec<f64>>,
+}
+
+impl<'a> ConditionSchedule<'a> {
+    /// Check `labels` (one per time step) and `parameters` against the
+    /// network: every node must exist and every parameter must be one that
+    /// `add_nodes` accepts for its kind.
+    pub(crate) fn new(
+        network: &Network,
+        labels: &'a [usize],
+        parameters: &ConditionParameters,
+        n_time: usize,
+    ) -> Result<Self, NetworkError> {
+        if labels.len() != n_time {
+            return Err(NetworkError::DimensionMismatch {
+                what: "conditions (one per observation)",
+                expected: n_time,
+                found: labels.len(),
+            });
+        }
+        let named: BTreeSet<&(usize, String)> = parameters
+            .values()
+            .flat_map(|values| values.keys())
+            .collect();
+        let mut keys = Vec::with_capacity(named.len());
+        let mut baseline = Vec::with_capacity(named.len());
+        for (node_idx, parameter) in named {
+            let Some(edges) = network.edges.get(*node_idx) else {
+                return Err(NetworkError::MissingNode {
+                    node_idx: *node_idx,
+                    step: "input_data",
+                });
+            };
+            let accepted = parameters_for_type(&edges.node_type);
+            let field = STATE_FIELDS
+                .iter()
+                .find(|&&field| field == parameter && accepted.contains(&field));
+            let Some(&field) = field else {
+                return Err(NetworkError::InvalidParameter {
+                    node_type: edges.node_type.clone(),
+                    parameter: parameter.clone(),
+                    accepted,
+                });
+            };
+            keys.push((*node_idx, field));
+            baseline.push(
+                network.attributes.states[*node_idx]
+                    .get(field)
+                    .expect("checked above"),
+            );
+        }
+        let values = parameters
+            .iter()
+            .map(|(&label, values)| {
+                let row = keys
+                    .iter()
+                    .zip(&baseline)
+                    .map(|(&(node_idx, field), &base)| {
+                        values
+                            .get(&(node_idx, field.to_string()))
+                            .copied()
+                            .unwrap_or(base)
+                    })
+                    .collect();
+                (label, row)
+            })
+            .collect();
+        Ok(Self {
+            labels,
+            keys,
+            baseline,
+            values,
+        })
+    }
+
+    /// Set the parameters of the condition of time step `time_idx`.
+    pub(crate) fn apply(&self, network: &mut Network, time_idx: usize) {
+        let values = self
+            .values
+            .get(&self.labels[time_idx])
+            .unwrap_or(&self.baseline);
+        self.write(network, values);
+    }
+
+    /// Put back the values the network had before the run.
+    pub(crate) fn restore(&self, network: &mut Network) {
+        self.write(network, &self.baseline);
+    }
+
+    fn write(&self, network: &mut Network, values: &[f64]) {
+        for (&(node_idx, field), &value) in self.keys.iter().zip(values) {
+            if let Some(slot) = network.attributes.states[node_idx].get_mut(field) {
+                *slot = value;
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Measured covariates (e.g. stimulus intensity) added to the drift of
+//! continuous nodes without modelling them as nodes.
+
+use std::collections::HashMap;
+
+use crate::error::NetworkError;
+use crate::model::network::Network;
+
+/// The node each covariate column drives, and the weight of that column:
+/// `column -> (node_idx, weight)`.
+pub type CovariateWeights = HashMap<usize, (usize, f64)>;
+
+/// The covariates of a run, applied before the predictions of each step.
+///
+/// The `covariate_drift` of every driven node is set to `Σ weight ·
+/// covariate[t]` over the columns that drive it, and the continuous-state
+/// prediction adds it to the drift rate, so the node's predicted mean moves
+/// by `Δt · covariate_drift` on top of its own drift and value parents.
+pub(crate) struct CovariateDrive<'a> {
+    covariates: &'a [Vec<f64>],
+    weights: Vec<(usize, usize, f64)>,
+    nodes: Vec<usize>,
+}
+
+impl<'a> CovariateDrive<'a> {
+    /// Check `covariates` (one row per time step, all of the same width) and
+    /// `weights` against the network: every column must exist and drive a
+    /// continuous-state node.
+    pub(crate) fn new(
+        network: &Network,
+        covariates: &'a [Vec<f64>],
+        weights: &CovariateWeights,
+        n_time: usize,
+    ) -> Result<Self, NetworkError> {
+        if covariates.len() != n_time {
+            return Err(NetworkError::DimensionMismatch {
+                what: "covariate rows (one per observation)",
+                expected: n_time,
+                found: covariates.len(),
+            });
+        }
+        let n_columns = covariates.first().map_or(0, Vec::len);
+        if let Some(row) = covariates.iter().find(|row| row.len() != n_columns) {
+            return Err(NetworkError::DimensionMismatch {
+                what: "covariate columns (the same in every row)",
+                expected: n_columns,
+                found: row.len(),
+            });
+        }
+        let mut sorted: Vec<(usize, usize, f64)> = weights
+            .iter()
+            .map(|(&column, &(node_idx, weight))| (column, node_idx, weight))
+            .collect();
+        sorted.sort_by_key(|&(column, node_idx, _)| (column, node_idx));
+        for &(column, node_idx, _) in &sorted {
+            if column >= n_columns {
+                return Err(NetworkError::DimensionMismatch {
+                    what: "covariate columns (at least one more than the largest weighted column)",
+                    expected: column + 1,
+                    found: n_columns,
+                });
+            }
+            let Some(edges) = network.edges.get(node_idx) else {
+                return Err(NetworkError::MissingNode {
+                    node_idx,
+                    step: "input_data",
+                });
+            };
+            if edges.node_type != "continuous-state" {
+                return Err(NetworkError::UnsupportedStructure {
+                    node_idx,
+                    step: "input_data",
+                    reason: "covariates drive continuous-state nodes",
+                });
+            }
+        }
+        let mut nodes: Vec<usize> = sorted.iter().map(|&(_, node_idx, _)| node_idx).collect();
+        nodes.sort_unstable();
+        nodes.dedup();
+        Ok(Self {
+            covariates,
+            weights: sorted,
+            nodes,
+        })
+    }
+
+    /// Set the `covariate_drift` of the driven nodes for time step `time_idx`.
+    pub(crate) fn apply(&self, network: &mut Network, time_idx: usize) {
+        self.clear(network);
+        let row = &self.covariates[time_idx];
+        for &(column, node_idx, weight) in &self.weights {
+            network.attributes.states[node_idx].covariate_drift += weight * row[column];
+        }
+    }
+
+    /// Reset the `covariate_drift` of the driven nodes once the run is over.
+    pub(crate) fn clear(&self, network: &mut Network) {
+        for &node_idx in &self.nodes {
+            network.attributes.states[node_idx].covariate_drift = 0.0;
+        }
+    }
+}
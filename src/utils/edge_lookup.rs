@@ -0,0 +1,177 @@
+//! Per-edge lookup tables for the value-coupling hot paths.
+//!
+//! The update and learning kernels repeatedly need, for every value-coupling
+//! edge, the position of the edge in the *other* node's adjacency list, the
+//! parent's coupling function, and whether the child is a Gaussian interior
+//! node. These only depend on the network structure, so they are resolved once
+//! by [`Network::set_update_sequence`] instead of at every time step.
+
+use crate::math::CouplingFn;
+use crate::model::network::{AdjacencyLists, Network};
+
+/// A value parent, seen from its child. Entries are stored in the child's
+/// `value_parents` order, so entry `i` pairs with `value_coupling_parents[i]`.
+#[derive(Debug, Clone, Copy)]
+pub struct ValueParentEdge {
+    pub parent_idx: usize,
+    /// Position of the child in the parent's `value_children` — the index of
+    /// this edge in the parent's `value_coupling_children`. `None` when the
+    /// parent does not list the child back.
+    pub coupling_idx: Option<usize>,
+    /// Coupling function carried by the parent (`None` means linear).
+    pub coupling_fn: Option<&'static CouplingFn>,
+}
+
+/// A value child, seen from its parent. Entries are stored in the parent's
+/// `value_children` order, so entry `i` pairs with `value_coupling_children[i]`.
+#[derive(Debug, Clone, Copy)]
+pub struct ValueChildEdge {
+    pub child_idx: usize,
+    /// Position of the parent in the child's `value_parents` — the index of
+    /// this edge in the child's `value_coupling_parents`. `None` when the
+    /// child does not list the parent back.
+    pub coupling_idx: Option<usize>,
+    /// Whether the child carries a Gaussian belief (continuous-state or
+    /// volatile-state) and has children of its own — the only case where the
+    /// Schur-complement smoothing correction of the posterior step applies.
+    pub gaussian_interior: bool,
+    /// Child-side coupling function of the edge (`None` means the identity,
+    /// see [`crate::model::network::NodeFnPtrs`]).
+    pub child_coupling_fn: Option<&'static CouplingFn>,
+}
+
+/// Value-coupling edge tables, indexed by node.
+#[derive(Debug, Clone, Default)]
+pub struct EdgeLookup {
+    pub value_parents: Vec<Vec<ValueParentEdge>>,
+    pub value_children: Vec<Vec<ValueChildEdge>>,
+}
+
+fn is_gaussian_interior(edge: &AdjacencyLists) -> bool {
+    matches!(
+        edge.node_type.as_str(),
+        "continuous-state" | "volatile-state"
+    ) && (edge.value_children.is_some() || edge.volatility_children.is_some())
+}
+
+/// Resolve the value-coupling edge tables from the network's adjacency lists.
+pub fn build_edge_lookup(network: &Network) -> EdgeLookup {
+    let edges = &network.edges;
+
+    let value_parents = (0..edges.len())
+        .map(|child_idx| {
+            edges[child_idx]
+                .value_parents
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .map(|&parent_idx| ValueParentEdge {
+                    parent_idx,
+                    coupling_idx: edges.get(parent_idx).and_then(|parent| {
+                        parent
+                            .value_children
+                            .as_ref()
+                            .and_then(|vc| vc.iter().position(|&c| c == child_idx))
+                    }),
+                    coupling_fn: network
+                        .attributes
+                        .fn_ptrs
+                        .get(parent_idx)
+                        .and_then(|f| f.coupling_fn),
+                })
+                .collect()
+        })
+        .collect();
+
+    let value_children = edges
+        .iter()
+        .enumerate()
+        .map(|(parent_idx, edge)| {
+            let child_coupling_fns = network
+                .attributes
+                .fn_ptrs
+                .get(parent_idx)
+                .map(|f| f.value_coupling_fn_children.as_slice())
+                .unwrap_or_default();
+            edge.value_children
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .enumerate()
+                .map(|(position, &child_idx)| ValueChildEdge {
+                    child_idx,
+                    coupling_idx: edges.get(child_idx).and_then(|child| {
+                        child
+                            .value_parents
+                            .as_ref()
+                            .and_then(|vp| vp.iter().position(|&p| p == parent_idx))
+                    }),
+                    gaussian_interior: edges.get(child_idx).is_some_and(is_gaussian_interior),
+                    child_coupling_fn: child_coupling_fns.get(position).copied().flatten(),
+                })
+                .collect()
+        })
+        .collect();
+
+    EdgeLookup {
+        value_parents,
+        value_children,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::network::AddNodesOptions;
+
+    #[test]
+    fn test_lookup_matches_adjacency_lists() {
+        // input (0) <- value parent (1, tanh) <- value parent (2)
+        //           \- value parent (3)
+        let mut net = Network::new("eHGF");
+        net.add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        net.add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                coupling_fn: Some("tanh".into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        net.add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(1.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        net.add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        net.set_update_sequence();
+
+        let lookup = &net.edge_lookup;
+        let parents_of_0: Vec<(usize, Option<usize>, bool)> = lookup.value_parents[0]
+            .iter()
+            .map(|e| (e.parent_idx, e.coupling_idx, e.coupling_fn.is_some()))
+            .collect();
+        assert_eq!(parents_of_0, vec![(1, Some(0), true), (3, Some(0), false)]);
+
+        // The input node is a leaf, node 1 is an interior Gaussian node.
+        assert!(!lookup.value_children[1][0].gaussian_interior);
+        assert_eq!(lookup.value_children[3][0].coupling_idx, Some(1));
+        assert!(lookup.value_children[2][0].gaussian_interior);
+        assert!(lookup.value_parents[2].is_empty());
+    }
+}
@@ -0,0 +1,25 @@
+//! Entropy of the Gaussian beliefs, to follow the uncertainty of each node in
+//! nats.
+
+use crate::math::gaussian_entropy;
+use crate::model::network::{EntropySource, Network};
+
+/// Write the entropy of every continuous, circular and volatile node into its
+/// `entropy`
+/// attribute, from the precision chosen by [`Network::entropy_source`], so
+/// that it is recorded with the trajectories.
+pub fn set_entropy(network: &mut Network) {
+    for (node_idx, state) in network.attributes.states.iter_mut().enumerate() {
+        if !matches!(
+            network.edges[node_idx].node_type.as_str(),
+            "continuous-state" | "circular-state" | "volatile-state"
+        ) {
+            continue;
+        }
+        let precision = match network.entropy_source {
+            EntropySource::Posterior => state.precision,
+            EntropySource::Prediction => state.expected_precision,
+        };
+        state.entropy = gaussian_entropy(precision);
+    }
+}
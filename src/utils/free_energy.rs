@@ -0,0 +1,186 @@
+//! Variational free energy of a recorded trajectory, split into accuracy and
+//! complexity.
+
+use crate::error::NetworkError;
+use crate::math::{gaussian_kl, gaussian_log_density, laplace_log_density, wrap_angle};
+use crate::model::network::{AdjacencyLists, Network, NodeTrajectory};
+
+/// Free-energy components summed over time steps and nodes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FreeEnergy {
+    /// Log-likelihood of the observations under the predictive distribution of
+    /// the input nodes.
+    pub accuracy: f64,
+    /// KL divergence between the posterior and the prior (prediction) of every
+    /// Gaussian state node that is not an input.
+    pub complexity: f64,
+    /// `complexity - accuracy`: the quantity minimised by the updates.
+    pub total: f64,
+}
+
+/// Compute the free-energy components from `network.node_trajectories`.
+///
+/// Inputs contribute their log-likelihood — Gaussian for continuous and
+/// volatile inputs, or Laplace for continuous inputs set to that likelihood
+/// (see [`Network::set_likelihood`]), Bernoulli for binary inputs, minus the recorded surprise
+/// for softmax inputs (categorical), bounded inputs (logit-Gaussian) and
+/// log-normal inputs — weighted by `observed`.
+/// Time steps recorded before a node was added (see
+/// [`NodeTrajectory::born`]) are skipped.
+///
+/// Continuous and volatile state nodes contribute the closed-form Gaussian KL
+/// between posterior (`mean`, `precision`) and prior (`expected_mean`,
+/// `expected_precision`); volatile nodes also contribute the KL of their
+/// implicit volatility level. Circular nodes are scored as continuous ones on
+/// the wrapped difference between mean and prediction. Other node kinds are
+/// ignored.
+///
+/// Fails with [`NetworkError::UnrecordedKey`] naming the first missing column
+/// when nothing was recorded or when `record_keys` left out a field it reads.
+pub fn free_energy(network: &Network) -> Result<FreeEnergy, NetworkError> {
+    let trajectories = &network.node_trajectories.nodes;
+    if trajectories.is_empty() {
+        if let Some((node_idx, key)) = network.edges.iter().enumerate().find_map(|(i, e)| {
+            required_columns(&e.node_type, network.inputs.contains(&i))
+                .first()
+                .map(|&key| (i, key))
+        }) {
+            return Err(NetworkError::UnrecordedKey {
+                node_idx,
+                key: key.to_string(),
+            });
+        }
+    }
+
+    let mut accuracy = 0.0;
+    let mut complexity = 0.0;
+
+    for (node_idx, traj) in trajectories.iter().enumerate() {
+        let node_type = network.edges[node_idx].node_type.as_str();
+        let is_input = network.inputs.contains(&node_idx);
+        if traj.n_time > 0 {
+            require_columns(traj, node_idx, required_columns(node_type, is_input))?;
+        }
+
+        if is_input {
+            for t in traj.born..traj.n_time {
+                if let Some(log_likelihood) =
+                    input_log_likelihood(&network.edges[node_idx], traj, t)
+                {
+                    accuracy += traj.observed()[t] * log_likelihood;
+                }
+            }
+            continue;
+        }
+
+        if matches!(node_type, "continuous-state" | "volatile-state") {
+            for t in traj.born..traj.n_time {
+                complexity += gaussian_kl(
+                    traj.mean()[t],
+                    traj.precision()[t],
+                    traj.expected_mean()[t],
+                    traj.expected_precision()[t],
+                );
+            }
+        }
+        if node_type == "circular-state" {
+            for t in traj.born..traj.n_time {
+                complexity += gaussian_kl(
+                    wrap_angle(traj.mean()[t] - traj.expected_mean()[t]),
+                    traj.precision()[t],
+                    0.0,
+                    traj.expected_precision()[t],
+                );
+            }
+        }
+        if node_type == "volatile-state" {
+            for t in traj.born..traj.n_time {
+                complexity += gaussian_kl(
+                    traj.mean_vol()[t],
+                    traj.precision_vol()[t],
+                    traj.expected_mean_vol()[t],
+                    traj.expected_precision_vol()[t],
+                );
+            }
+        }
+    }
+
+    Ok(FreeEnergy {
+        accuracy,
+        complexity,
+        total: complexity - accuracy,
+    })
+}
+
+/// Log-likelihood of the observation of input `traj` at time step `t` under
+/// its predictive distribution, before weighting by `observed`; `None` for
+/// node kinds without one. `edge` holds the node's kind and likelihood.
+pub(crate) fn input_log_likelihood(
+    edge: &AdjacencyLists,
+    traj: &NodeTrajectory,
+    t: usize,
+) -> Option<f64> {
+    match edge.node_type.as_str() {
+        "continuous-state" if edge.likelihood == "laplace" => Some(laplace_log_density(
+            traj.mean()[t],
+            traj.expected_mean()[t],
+            traj.expected_precision()[t],
+        )),
+        "continuous-state" | "volatile-state" | "aggregate-input" => Some(gaussian_log_density(
+            traj.mean()[t],
+            traj.expected_mean()[t],
+            traj.expected_precision()[t],
+        )),
+        "circular-state" => Some(gaussian_log_density(
+            wrap_angle(traj.mean()[t] - traj.expected_mean()[t]),
+            0.0,
+            traj.expected_precision()[t],
+        )),
+        "binary-state" | "binary-input" => {
+            let (u, p) = (traj.mean()[t], traj.expected_mean()[t]);
+            Some(u * p.ln() + (1.0 - u) * (1.0 - p).ln())
+        }
+        "softmax-state" | "bounded-state" | "lognormal-state" => Some(-traj.surprise()[t]),
+        _ => None,
+    }
+}
+
+/// Recorded fields the free energy reads for a node of `node_type`.
+pub(crate) fn required_columns(node_type: &str, is_input: bool) -> &'static [&'static str] {
+    match (node_type, is_input) {
+        ("continuous-state" | "volatile-state" | "circular-state" | "aggregate-input", true) => {
+            &["observed", "mean", "expected_mean", "expected_precision"]
+        }
+        ("binary-state" | "binary-input", true) => &["observed", "mean", "expected_mean"],
+        ("softmax-state" | "bounded-state" | "lognormal-state", true) => &["observed", "surprise"],
+        ("continuous-state" | "circular-state", false) => {
+            &["mean", "precision", "expected_mean", "expected_precision"]
+        }
+        ("volatile-state", false) => &[
+            "mean",
+            "precision",
+            "expected_mean",
+            "expected_precision",
+            "mean_vol",
+            "precision_vol",
+            "expected_mean_vol",
+            "expected_precision_vol",
+        ],
+        _ => &[],
+    }
+}
+
+/// Fail when one of `keys` was left out of the recording of `traj`.
+fn require_columns(
+    traj: &NodeTrajectory,
+    node_idx: usize,
+    keys: &[&str],
+) -> Result<(), NetworkError> {
+    match keys.iter().find(|&&key| traj.column(key).is_none()) {
+        Some(key) => Err(NetworkError::UnrecordedKey {
+            node_idx,
+            key: key.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
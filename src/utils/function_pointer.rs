@@ -1,29 +1,49 @@
 use std::collections::HashMap;
 
-use crate::updates::nodalised::learning::learning_weights;
+use crate::error::NetworkError;
+use crate::updates::nodalised::learning::{learning_tonic_drift, learning_weights};
 use crate::{
     model::network::Network,
     updates::nodalised::{
+        posterior::circular::{
+            posterior_update_circular_state_node, posterior_update_circular_state_node_mean_field,
+        },
         posterior::continuous::{
             posterior_update_continuous_state_node, posterior_update_continuous_state_node_ehgf,
             posterior_update_continuous_state_node_ehgf_mean_field,
             posterior_update_continuous_state_node_mean_field,
-            posterior_update_continuous_state_node_unbounded,
+            posterior_update_continuous_state_node_unbounded, posterior_update_precision_parent,
         },
+        posterior::dp::posterior_update_dp_cluster,
         posterior::volatile::{
             posterior_update_volatile_state_node, posterior_update_volatile_state_node_mean_field,
         },
+        prediction::aggregate::prediction_aggregate_input_node,
         prediction::binary::prediction_binary_state_node,
+        prediction::bounded::{
+            prediction_bounded_state_node, prediction_bounded_state_node_mean_field,
+        },
+        prediction::circular::{
+            prediction_circular_state_node, prediction_circular_state_node_mean_field,
+        },
         prediction::continuous::{
             prediction_continuous_state_node, prediction_continuous_state_node_mean_field,
         },
+        prediction::dp::prediction_dp_state_node,
+        prediction::lognormal::{
+            prediction_lognormal_state_node, prediction_lognormal_state_node_mean_field,
+        },
+        prediction::softmax::prediction_softmax_state_node,
         prediction::volatile::{
             prediction_volatile_state_node, prediction_volatile_state_node_mean_field,
         },
         prediction_error::{
-            binary::prediction_error_binary_state_node,
+            binary::{prediction_error_binary_input, prediction_error_binary_state_node},
+            circular::prediction_error_circular_state_node,
             continuous::prediction_error_continuous_state_node,
+            dp::prediction_error_dp_state_node,
             exponential::prediction_error_exponential_state_node,
+            softmax::prediction_error_softmax_state_node,
             volatile::{
                 prediction_error_volatile_state_node, prediction_error_volatile_state_node_ehgf,
                 prediction_error_volatile_state_node_unbounded,
@@ -32,7 +52,14 @@ use crate::{
     },
 };
 
-// Create a default signature for update functions
+/// Signature shared by every update kernel: `(network, node_idx, time_step)`.
+///
+/// A kernel reads and writes the attributes of `node_idx` and of its
+/// neighbours in place. `time_step` is the interval since the previous
+/// observation, passed to every kernel even when it does not use it (prefix
+/// the argument with `_` then). Kernels do not return errors: the structure
+/// they rely on is validated beforehand by [`Network::check_steps`], and the
+/// few numerical failures they detect are left in `Network::step_error`.
 pub type FnType = for<'a> fn(&'a mut Network, usize, f64);
 
 /// Enum-based dispatch for update steps.
@@ -45,25 +72,136 @@ pub enum UpdateStep {
     PredictionVolatile,
     PredictionVolatileMeanField,
     PredictionBinary,
+    PredictionSoftmax,
+    PredictionDp,
+    PredictionBounded,
+    PredictionBoundedMeanField,
+    PredictionLognormal,
+    PredictionLognormalMeanField,
+    PredictionCircular,
+    PredictionCircularMeanField,
+    PredictionAggregate,
     PosteriorContinuous,
     PosteriorContinuousMeanField,
     PosteriorContinuousEhgf,
     PosteriorContinuousEhgfMeanField,
     PosteriorContinuousUnbounded,
+    PosteriorPrecisionParent,
     PosteriorVolatile,
     PosteriorVolatileMeanField,
+    PosteriorDpCluster,
+    PosteriorCircular,
+    PosteriorCircularMeanField,
     PredictionErrorContinuous,
     PredictionErrorVolatile,
     PredictionErrorVolatileEhgf,
     PredictionErrorVolatileUnbounded,
     PredictionErrorExponential,
     PredictionErrorBinary,
+    PredictionErrorBinaryInput,
+    PredictionErrorSoftmax,
+    PredictionErrorDp,
+    PredictionErrorCircular,
     LearningWeights,
+    LearningTonicDrift,
+    /// A Python callable from `Network::python_steps`, run at the `kind` stage
+    /// (see [`crate::utils::python_step`]).
+    Python {
+        slot: usize,
+        kind: StepKind,
+    },
 }
 
 impl UpdateStep {
+    /// Every built-in update step, in declaration order.
+    pub const ALL: [UpdateStep; 37] = [
+        Self::PredictionContinuous,
+        Self::PredictionContinuousMeanField,
+        Self::PredictionVolatile,
+        Self::PredictionVolatileMeanField,
+        Self::PredictionBinary,
+        Self::PredictionSoftmax,
+        Self::PredictionDp,
+        Self::PredictionBounded,
+        Self::PredictionBoundedMeanField,
+        Self::PredictionLognormal,
+        Self::PredictionLognormalMeanField,
+        Self::PredictionCircular,
+        Self::PredictionCircularMeanField,
+        Self::PredictionAggregate,
+        Self::PosteriorContinuous,
+        Self::PosteriorContinuousMeanField,
+        Self::PosteriorContinuousEhgf,
+        Self::PosteriorContinuousEhgfMeanField,
+        Self::PosteriorContinuousUnbounded,
+        Self::PosteriorPrecisionParent,
+        Self::PosteriorVolatile,
+        Self::PosteriorVolatileMeanField,
+        Self::PosteriorDpCluster,
+        Self::PosteriorCircular,
+        Self::PosteriorCircularMeanField,
+        Self::PredictionErrorContinuous,
+        Self::PredictionErrorVolatile,
+        Self::PredictionErrorVolatileEhgf,
+        Self::PredictionErrorVolatileUnbounded,
+        Self::PredictionErrorExponential,
+        Self::PredictionErrorBinary,
+        Self::PredictionErrorBinaryInput,
+        Self::PredictionErrorSoftmax,
+        Self::PredictionErrorDp,
+        Self::PredictionErrorCircular,
+        Self::LearningWeights,
+        Self::LearningTonicDrift,
+    ];
+}
+
+// Every built-in step has a kernel.
+const _: () = {
+    let mut i = 0;
+    while i < UpdateStep::ALL.len() {
+        assert!(UpdateStep::ALL[i].kernel().is_some());
+        i += 1;
+    }
+};
+
+/// Stage of the belief-propagation cycle an update step belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepKind {
+    Prediction,
+    Posterior,
+    PredictionError,
+    Learning,
+}
+
+impl StepKind {
+    /// Name of the stage, as accepted by `insert_update_step`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Prediction => "prediction",
+            Self::Posterior => "posterior",
+            Self::PredictionError => "prediction_error",
+            Self::Learning => "learning",
+        }
+    }
+}
+
+impl UpdateStep {
+    /// Run the step on `node_idx`. Only the node index is checked here; the
+    /// rest of the neighbourhood is validated once per pass by
+    /// [`Network::check_steps`].
     #[inline(always)]
-    pub fn call(self, network: &mut Network, node_idx: usize, time_step: f64) {
+    pub fn call(
+        self,
+        network: &mut Network,
+        node_idx: usize,
+        time_step: f64,
+    ) -> Result<(), NetworkError> {
+        if node_idx >= network.attributes.states.len() {
+            return Err(NetworkError::MissingNode {
+                node_idx,
+                step: self.name(),
+            });
+        }
         match self {
             Self::PredictionContinuous => {
                 prediction_continuous_state_node(network, node_idx, time_step)
@@ -78,6 +216,27 @@ impl UpdateStep {
                 prediction_volatile_state_node_mean_field(network, node_idx, time_step)
             }
             Self::PredictionBinary => prediction_binary_state_node(network, node_idx, time_step),
+            Self::PredictionSoftmax => prediction_softmax_state_node(network, node_idx, time_step),
+            Self::PredictionDp => prediction_dp_state_node(network, node_idx, time_step),
+            Self::PredictionBounded => prediction_bounded_state_node(network, node_idx, time_step),
+            Self::PredictionBoundedMeanField => {
+                prediction_bounded_state_node_mean_field(network, node_idx, time_step)
+            }
+            Self::PredictionLognormal => {
+                prediction_lognormal_state_node(network, node_idx, time_step)
+            }
+            Self::PredictionLognormalMeanField => {
+                prediction_lognormal_state_node_mean_field(network, node_idx, time_step)
+            }
+            Self::PredictionCircular => {
+                prediction_circular_state_node(network, node_idx, time_step)
+            }
+            Self::PredictionCircularMeanField => {
+                prediction_circular_state_node_mean_field(network, node_idx, time_step)
+            }
+            Self::PredictionAggregate => {
+                prediction_aggregate_input_node(network, node_idx, time_step)
+            }
             Self::PosteriorContinuous => {
                 posterior_update_continuous_state_node(network, node_idx, time_step)
             }
@@ -93,12 +252,22 @@ impl UpdateStep {
             Self::PosteriorContinuousUnbounded => {
                 posterior_update_continuous_state_node_unbounded(network, node_idx, time_step)
             }
+            Self::PosteriorPrecisionParent => {
+                posterior_update_precision_parent(network, node_idx, time_step)
+            }
             Self::PosteriorVolatile => {
                 posterior_update_volatile_state_node(network, node_idx, time_step)
             }
             Self::PosteriorVolatileMeanField => {
                 posterior_update_volatile_state_node_mean_field(network, node_idx, time_step)
             }
+            Self::PosteriorDpCluster => posterior_update_dp_cluster(network, node_idx, time_step),
+            Self::PosteriorCircular => {
+                posterior_update_circular_state_node(network, node_idx, time_step)
+            }
+            Self::PosteriorCircularMeanField => {
+                posterior_update_circular_state_node_mean_field(network, node_idx, time_step)
+            }
             Self::PredictionErrorContinuous => {
                 prediction_error_continuous_state_node(network, node_idx, time_step)
             }
@@ -117,7 +286,128 @@ impl UpdateStep {
             Self::PredictionErrorBinary => {
                 prediction_error_binary_state_node(network, node_idx, time_step)
             }
+            Self::PredictionErrorBinaryInput => {
+                prediction_error_binary_input(network, node_idx, time_step)
+            }
+            Self::PredictionErrorSoftmax => {
+                prediction_error_softmax_state_node(network, node_idx, time_step)
+            }
+            Self::PredictionErrorDp => prediction_error_dp_state_node(network, node_idx, time_step),
+            Self::PredictionErrorCircular => {
+                prediction_error_circular_state_node(network, node_idx, time_step)
+            }
             Self::LearningWeights => learning_weights(network, node_idx, time_step),
+            Self::LearningTonicDrift => learning_tonic_drift(network, node_idx, time_step),
+            Self::Python { slot, .. } => {
+                let step = network.python_steps[slot].clone();
+                return step.call(network, node_idx, time_step);
+            }
+        }
+        match network.step_error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    pub fn kind(self) -> StepKind {
+        match self {
+            Self::PredictionContinuous
+            | Self::PredictionContinuousMeanField
+            | Self::PredictionVolatile
+            | Self::PredictionVolatileMeanField
+            | Self::PredictionBinary
+            | Self::PredictionSoftmax
+            | Self::PredictionDp
+            | Self::PredictionBounded
+            | Self::PredictionBoundedMeanField
+            | Self::PredictionLognormal
+            | Self::PredictionLognormalMeanField
+            | Self::PredictionCircular
+            | Self::PredictionCircularMeanField
+            | Self::PredictionAggregate => StepKind::Prediction,
+            Self::PosteriorContinuous
+            | Self::PosteriorContinuousMeanField
+            | Self::PosteriorContinuousEhgf
+            | Self::PosteriorContinuousEhgfMeanField
+            | Self::PosteriorContinuousUnbounded
+            | Self::PosteriorPrecisionParent
+            | Self::PosteriorVolatile
+            | Self::PosteriorVolatileMeanField
+            | Self::PosteriorDpCluster
+            | Self::PosteriorCircular
+            | Self::PosteriorCircularMeanField => StepKind::Posterior,
+            Self::PredictionErrorContinuous
+            | Self::PredictionErrorVolatile
+            | Self::PredictionErrorVolatileEhgf
+            | Self::PredictionErrorVolatileUnbounded
+            | Self::PredictionErrorExponential
+            | Self::PredictionErrorBinary
+            | Self::PredictionErrorBinaryInput
+            | Self::PredictionErrorSoftmax
+            | Self::PredictionErrorDp
+            | Self::PredictionErrorCircular => StepKind::PredictionError,
+            Self::LearningWeights | Self::LearningTonicDrift => StepKind::Learning,
+            Self::Python { kind, .. } => kind,
+        }
+    }
+
+    /// The kernel run by a built-in step (`None` for a Python step). Each
+    /// kernel is coerced to [`FnType`] here, so one with another signature
+    /// fails to compile.
+    pub const fn kernel(self) -> Option<FnType> {
+        match self {
+            Self::PredictionContinuous => Some(prediction_continuous_state_node),
+            Self::PredictionContinuousMeanField => {
+                Some(prediction_continuous_state_node_mean_field)
+            }
+            Self::PredictionVolatile => Some(prediction_volatile_state_node),
+            Self::PredictionVolatileMeanField => Some(prediction_volatile_state_node_mean_field),
+            Self::PredictionBinary => Some(prediction_binary_state_node),
+            Self::PredictionSoftmax => Some(prediction_softmax_state_node),
+            Self::PredictionDp => Some(prediction_dp_state_node),
+            Self::PredictionBounded => Some(prediction_bounded_state_node),
+            Self::PredictionBoundedMeanField => Some(prediction_bounded_state_node_mean_field),
+            Self::PredictionLognormal => Some(prediction_lognormal_state_node),
+            Self::PredictionLognormalMeanField => Some(prediction_lognormal_state_node_mean_field),
+            Self::PredictionCircular => Some(prediction_circular_state_node),
+            Self::PredictionCircularMeanField => Some(prediction_circular_state_node_mean_field),
+            Self::PredictionAggregate => Some(prediction_aggregate_input_node),
+            Self::PosteriorContinuous => Some(posterior_update_continuous_state_node),
+            Self::PosteriorContinuousMeanField => {
+                Some(posterior_update_continuous_state_node_mean_field)
+            }
+            Self::PosteriorContinuousEhgf => Some(posterior_update_continuous_state_node_ehgf),
+            Self::PosteriorContinuousEhgfMeanField => {
+                Some(posterior_update_continuous_state_node_ehgf_mean_field)
+            }
+            Self::PosteriorContinuousUnbounded => {
+                Some(posterior_update_continuous_state_node_unbounded)
+            }
+            Self::PosteriorPrecisionParent => Some(posterior_update_precision_parent),
+            Self::PosteriorVolatile => Some(posterior_update_volatile_state_node),
+            Self::PosteriorVolatileMeanField => {
+                Some(posterior_update_volatile_state_node_mean_field)
+            }
+            Self::PosteriorDpCluster => Some(posterior_update_dp_cluster),
+            Self::PosteriorCircular => Some(posterior_update_circular_state_node),
+            Self::PosteriorCircularMeanField => {
+                Some(posterior_update_circular_state_node_mean_field)
+            }
+            Self::PredictionErrorContinuous => Some(prediction_error_continuous_state_node),
+            Self::PredictionErrorVolatile => Some(prediction_error_volatile_state_node),
+            Self::PredictionErrorVolatileEhgf => Some(prediction_error_volatile_state_node_ehgf),
+            Self::PredictionErrorVolatileUnbounded => {
+                Some(prediction_error_volatile_state_node_unbounded)
+            }
+            Self::PredictionErrorExponential => Some(prediction_error_exponential_state_node),
+            Self::PredictionErrorBinary => Some(prediction_error_binary_state_node),
+            Self::PredictionErrorBinaryInput => Some(prediction_error_binary_input),
+            Self::PredictionErrorSoftmax => Some(prediction_error_softmax_state_node),
+            Self::PredictionErrorDp => Some(prediction_error_dp_state_node),
+            Self::PredictionErrorCircular => Some(prediction_error_circular_state_node),
+            Self::LearningWeights => Some(learning_weights),
+            Self::LearningTonicDrift => Some(learning_tonic_drift),
+            Self::Python { .. } => None,
         }
     }
 
@@ -128,6 +418,15 @@ impl UpdateStep {
             Self::PredictionVolatile => "prediction_volatile_state_node",
             Self::PredictionVolatileMeanField => "prediction_volatile_state_node_mean_field",
             Self::PredictionBinary => "prediction_binary_state_node",
+            Self::PredictionSoftmax => "prediction_softmax_state_node",
+            Self::PredictionDp => "prediction_dp_state_node",
+            Self::PredictionBounded => "prediction_bounded_state_node",
+            Self::PredictionBoundedMeanField => "prediction_bounded_state_node_mean_field",
+            Self::PredictionLognormal => "prediction_lognormal_state_node",
+            Self::PredictionLognormalMeanField => "prediction_lognormal_state_node_mean_field",
+            Self::PredictionCircular => "prediction_circular_state_node",
+            Self::PredictionCircularMeanField => "prediction_circular_state_node_mean_field",
+            Self::PredictionAggregate => "prediction_aggregate_input_node",
             Self::PosteriorContinuous => "posterior_update_continuous_state_node",
             Self::PosteriorContinuousMeanField => {
                 "posterior_update_continuous_state_node_mean_field"
@@ -139,8 +438,12 @@ impl UpdateStep {
             Self::PosteriorContinuousUnbounded => {
                 "posterior_update_continuous_state_node_unbounded"
             }
+            Self::PosteriorPrecisionParent => "posterior_update_precision_parent",
             Self::PosteriorVolatile => "posterior_update_volatile_state_node",
             Self::PosteriorVolatileMeanField => "posterior_update_volatile_state_node_mean_field",
+            Self::PosteriorDpCluster => "posterior_update_dp_cluster",
+            Self::PosteriorCircular => "posterior_update_circular_state_node",
+            Self::PosteriorCircularMeanField => "posterior_update_circular_state_node_mean_field",
             Self::PredictionErrorContinuous => "prediction_error_continuous_state_node",
             Self::PredictionErrorVolatile => "prediction_error_volatile_state_node",
             Self::PredictionErrorVolatileEhgf => "prediction_error_volatile_state_node_ehgf",
@@ -149,68 +452,39 @@ impl UpdateStep {
             }
             Self::PredictionErrorExponential => "prediction_error_exponential_state_node",
             Self::PredictionErrorBinary => "prediction_error_binary_state_node",
+            Self::PredictionErrorBinaryInput => "prediction_error_binary_input",
+            Self::PredictionErrorSoftmax => "prediction_error_softmax_state_node",
+            Self::PredictionErrorDp => "prediction_error_dp_state_node",
+            Self::PredictionErrorCircular => "prediction_error_circular_state_node",
             Self::LearningWeights => "learning_weights",
+            Self::LearningTonicDrift => "learning_tonic_drift",
+            Self::Python { .. } => "python_step",
         }
     }
 }
 
+/// Map every built-in kernel to the name of its step.
 pub fn get_func_map() -> HashMap<FnType, &'static str> {
-    let function_map: HashMap<FnType, &str> = [
-        (
-            posterior_update_continuous_state_node as FnType,
-            "posterior_update_continuous_state_node",
-        ),
-        (
-            posterior_update_continuous_state_node_ehgf as FnType,
-            "posterior_update_continuous_state_node_ehgf",
-        ),
-        (
-            posterior_update_continuous_state_node_unbounded as FnType,
-            "posterior_update_continuous_state_node_unbounded",
-        ),
-        (
-            prediction_continuous_state_node as FnType,
-            "prediction_continuous_state_node",
-        ),
-        (
-            prediction_error_continuous_state_node as FnType,
-            "prediction_error_continuous_state_node",
-        ),
-        (
-            prediction_error_exponential_state_node as FnType,
-            "prediction_error_exponential_state_node",
-        ),
-        (
-            prediction_volatile_state_node as FnType,
-            "prediction_volatile_state_node",
-        ),
-        (
-            posterior_update_volatile_state_node as FnType,
-            "posterior_update_volatile_state_node",
-        ),
-        (
-            prediction_error_volatile_state_node as FnType,
-            "prediction_error_volatile_state_node",
-        ),
-        (
-            prediction_error_volatile_state_node_ehgf as FnType,
-            "prediction_error_volatile_state_node_ehgf",
-        ),
-        (
-            prediction_error_volatile_state_node_unbounded as FnType,
-            "prediction_error_volatile_state_node_unbounded",
-        ),
-        (learning_weights as FnType, "learning_weights"),
-        (
-            prediction_binary_state_node as FnType,
-            "prediction_binary_state_node",
-        ),
-        (
-            prediction_error_binary_state_node as FnType,
-            "prediction_error_binary_state_node",
-        ),
-    ]
-    .into_iter()
-    .collect();
-    function_map
+    UpdateStep::ALL
+        .into_iter()
+        .filter_map(|step| Some((step.kernel()?, step.name())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_func_map_names_every_step() {
+        let func_map = get_func_map();
+        assert_eq!(func_map.len(), UpdateStep::ALL.len());
+        for step in UpdateStep::ALL {
+            assert!(
+                func_map.values().any(|&name| name == step.name()),
+                "{:?} is missing from the function map",
+                step
+            );
+        }
+    }
 }
@@ -0,0 +1,81 @@
+//! Per-trial gains on the prediction errors of input nodes, e.g. attention or
+//! salience weighting how much each observation teaches the network.
+
+use std::collections::HashMap;
+
+use crate::error::NetworkError;
+use crate::model::network::Network;
+
+/// One gain in `[0, 1]` per time step, by input node: `node_idx -> gains`.
+pub type PeGains = HashMap<usize, Vec<f64>>;
+
+/// The prediction-error gains of a run, applied before the predictions of
+/// each step.
+///
+/// The `pe_gain` of every listed input is set to its gain for the step, and
+/// the posterior updates of its parents scale the input's precision and mean
+/// contributions by it: a gain of 0 leaves the parents at their predictions,
+/// a gain of 1 is the ordinary update.
+pub(crate) struct GainSchedule<'a> {
+    gains: Vec<(usize, &'a [f64])>,
+}
+
+impl<'a> GainSchedule<'a> {
+    /// Check `gains` against the network: every node must be an input with
+    /// one gain in `[0, 1]` per time step.
+    pub(crate) fn new(
+        network: &Network,
+        gains: &'a PeGains,
+        n_time: usize,
+    ) -> Result<Self, NetworkError> {
+        let mut sorted: Vec<(usize, &'a [f64])> = gains
+            .iter()
+            .map(|(&node_idx, values)| (node_idx, values.as_slice()))
+            .collect();
+        sorted.sort_by_key(|&(node_idx, _)| node_idx);
+        for &(node_idx, values) in &sorted {
+            if node_idx >= network.edges.len() {
+                return Err(NetworkError::MissingNode {
+                    node_idx,
+                    step: "input_data",
+                });
+            }
+            if !network.inputs.contains(&node_idx) {
+                return Err(NetworkError::UnsupportedStructure {
+                    node_idx,
+                    step: "input_data",
+                    reason: "prediction-error gains apply to input nodes",
+                });
+            }
+            if values.len() != n_time {
+                return Err(NetworkError::DimensionMismatch {
+                    what: "pe_gains (one per observation)",
+                    expected: n_time,
+                    found: values.len(),
+                });
+            }
+            if let Some(&value) = values.iter().find(|value| !(0.0..=1.0).contains(*value)) {
+                return Err(NetworkError::InvalidValue {
+                    parameter: "gain",
+                    value: format!("{} (node {})", value, node_idx),
+                    accepted: "a value in [0, 1]",
+                });
+            }
+        }
+        Ok(Self { gains: sorted })
+    }
+
+    /// Set the `pe_gain` of the listed inputs for time step `time_idx`.
+    pub(crate) fn apply(&self, network: &mut Network, time_idx: usize) {
+        for &(node_idx, values) in &self.gains {
+            network.attributes.states[node_idx].pe_gain = values[time_idx];
+        }
+    }
+
+    /// Reset the `pe_gain` of the listed inputs once the run is over.
+    pub(crate) fn clear(&self, network: &mut Network) {
+        for &(node_idx, _) in &self.gains {
+            network.attributes.states[node_idx].pe_gain = 1.0;
+        }
+    }
+}
@@ -1,6 +1,23 @@
 pub mod beliefs_propagation;
+pub mod clusters;
+pub mod compare;
+pub mod conditions;
+pub mod covariates;
+pub mod edge_lookup;
+pub mod entropy;
+pub mod free_energy;
 pub mod function_pointer;
+pub mod gains;
+pub mod model_summary;
+pub mod pyhgf_spec;
+pub mod python_step;
+pub mod sample;
 pub mod set_coupling;
 pub mod set_learning_sequence;
 pub mod set_sequence;
+pub mod spec;
+pub mod steady_state;
+pub mod surprise;
+pub mod trajectory_summary;
+pub mod trajectory_table;
 pub mod weight_initialisation;
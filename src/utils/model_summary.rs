@@ -0,0 +1,174 @@
+//! One-glance description of a network's structure, for logs and reports.
+
+use std::fmt;
+
+use crate::math::{coupling_fn_name, CouplingFn};
+use crate::model::network::Network;
+
+/// Structure of a network, as returned by [`Network::model_summary`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelSummary {
+    /// Number of nodes.
+    pub n_nodes: usize,
+    /// Number of nodes of each kind, in order of first appearance.
+    pub nodes_by_kind: Vec<(String, usize)>,
+    /// Number of value edges (parent → child).
+    pub n_value_edges: usize,
+    /// Number of volatility edges (parent → child).
+    pub n_volatility_edges: usize,
+    /// The `volatility_updates` the posterior updates are selected with.
+    pub update_type: String,
+    /// Input node indices.
+    pub inputs: Vec<usize>,
+    /// Names of the coupling functions used by the value parents, sorted.
+    pub value_coupling_fns: Vec<&'static str>,
+    /// Names of the coupling functions used by the volatility parents, sorted.
+    pub volatility_coupling_fns: Vec<&'static str>,
+}
+
+/// Sorted, deduplicated names of `coupling_fns` (`None` being linear).
+fn coupling_names<'a>(
+    coupling_fns: impl Iterator<Item = Option<&'a CouplingFn>>,
+) -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = coupling_fns
+        .map(|cf| cf.map_or("linear", coupling_fn_name))
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+}
+
+/// Describe the structure of `network`.
+pub fn model_summary(network: &Network) -> ModelSummary {
+    let mut nodes_by_kind: Vec<(String, usize)> = Vec::new();
+    for edges in &network.edges {
+        match nodes_by_kind
+            .iter_mut()
+            .find(|(kind, _)| *kind == edges.node_type)
+        {
+            Some((_, count)) => *count += 1,
+            None => nodes_by_kind.push((edges.node_type.clone(), 1)),
+        }
+    }
+
+    let n_edges = |children: fn(&_) -> &Option<Vec<usize>>| {
+        network
+            .edges
+            .iter()
+            .map(|edges| children(edges).as_ref().map_or(0, Vec::len))
+            .sum()
+    };
+
+    let fn_ptrs = network.attributes.fn_ptrs.iter().zip(&network.edges);
+    let value_coupling_fns = coupling_names(
+        fn_ptrs
+            .clone()
+            .filter(|(_, edges)| edges.value_children.is_some())
+            .map(|(fn_ptrs, _)| fn_ptrs.coupling_fn),
+    );
+    let volatility_coupling_fns = coupling_names(
+        fn_ptrs
+            .filter(|(_, edges)| edges.volatility_children.is_some())
+            .map(|(fn_ptrs, _)| fn_ptrs.volatility_coupling_fn),
+    );
+
+    ModelSummary {
+        n_nodes: network.edges.len(),
+        nodes_by_kind,
+        n_value_edges: n_edges(|edges| &edges.value_children),
+        n_volatility_edges: n_edges(|edges| &edges.volatility_children),
+        update_type: network.volatility_updates.clone(),
+        inputs: network.inputs.clone(),
+        value_coupling_fns,
+        volatility_coupling_fns,
+    }
+}
+
+impl fmt::Display for ModelSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kinds: Vec<String> = self
+            .nodes_by_kind
+            .iter()
+            .map(|(kind, count)| format!("{}: {}", kind, count))
+            .collect();
+        writeln!(f, "Nodes: {} ({})", self.n_nodes, kinds.join(", "))?;
+        writeln!(
+            f,
+            "Edges: {} value, {} volatility",
+            self.n_value_edges, self.n_volatility_edges
+        )?;
+        writeln!(f, "Update type: {}", self.update_type)?;
+        writeln!(f, "Inputs: {:?}", self.inputs)?;
+        write!(
+            f,
+            "Coupling functions: value [{}], volatility [{}]",
+            self.value_coupling_fns.join(", "),
+            self.volatility_coupling_fns.join(", ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::network::AddNodesOptions;
+
+    fn three_level_network(coupling_fn: Option<&str>) -> Network {
+        let mut network = Network::new("eHGF");
+        network
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    coupling_fn: coupling_fn.map(String::from),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    volatility_children: Some(1.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+    }
+
+    #[test]
+    fn test_model_summary() {
+        let summary = model_summary(&three_level_network(None));
+        assert_eq!(
+            summary,
+            ModelSummary {
+                n_nodes: 3,
+                nodes_by_kind: vec![("continuous-state".into(), 3)],
+                n_value_edges: 1,
+                n_volatility_edges: 1,
+                update_type: "eHGF".into(),
+                inputs: vec![0],
+                value_coupling_fns: vec!["linear"],
+                volatility_coupling_fns: vec!["linear"],
+            }
+        );
+        assert_eq!(
+            summary.to_string(),
+            "Nodes: 3 (continuous-state: 3)\n\
+             Edges: 1 value, 1 volatility\n\
+             Update type: eHGF\n\
+             Inputs: [0]\n\
+             Coupling functions: value [linear], volatility [linear]"
+        );
+
+        let summary = model_summary(&three_level_network(Some("sigmoid")));
+        assert_eq!(summary.value_coupling_fns, vec!["sigmoid"]);
+        assert_eq!(summary.volatility_coupling_fns, vec!["linear"]);
+    }
+}
@@ -0,0 +1,256 @@
+//! Import and export of network definitions in the layout of the JAX backend
+//! (`pyhgf.model.Network`), as exposed by its `get_network()`: one attributes
+//! dict and one `AdjacencyLists` per node.
+
+use std::collections::HashMap;
+
+use crate::error::NetworkError;
+use crate::math::{coupling_fn_name, parse_coupling_fn};
+use crate::model::network::{
+    parameters_for_type, trajectory_fields_for_type, AddNodesOptions, IntOrList, Network,
+};
+
+/// One node of a pyhgf network: its `AdjacencyLists` entry and the scalar
+/// and coupling entries of its attributes dict.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PyhgfNode {
+    /// pyhgf node type code (`0` constant, `1` binary, `2` continuous, `3`
+    /// exponential family, `4` Dirichlet process, `5` categorical, `6`
+    /// volatile).
+    pub node_type: i64,
+    pub value_parents: Vec<usize>,
+    pub value_children: Vec<usize>,
+    pub volatility_parents: Vec<usize>,
+    pub volatility_children: Vec<usize>,
+    /// Coupling function name of each value child (`None` being linear).
+    pub coupling_fns: Vec<Option<String>>,
+    /// Value-coupling strength of each value child.
+    pub value_coupling_children: Vec<f64>,
+    /// Volatility-coupling strength of each volatility child.
+    pub volatility_coupling_children: Vec<f64>,
+    /// Value-coupling strength of each value parent (the import reads the
+    /// strengths from the parents' side).
+    pub value_coupling_parents: Vec<f64>,
+    /// Volatility-coupling strength of each volatility parent.
+    pub volatility_coupling_parents: Vec<f64>,
+    /// Scalar attributes (`mean`, `precision`, `tonic_volatility`, ...). Keys
+    /// the Rust node kind does not have are ignored.
+    pub parameters: HashMap<String, f64>,
+}
+
+/// Rust node kind of the pyhgf `node_type` code of `node_idx`.
+pub fn pyhgf_kind(node_idx: usize, node_type: i64) -> Result<&'static str, NetworkError> {
+    let unsupported = |reason| NetworkError::UnsupportedStructure {
+        node_idx,
+        step: "from_pyhgf_spec",
+        reason,
+    };
+    match node_type {
+        0 => Ok("constant-state"),
+        1 => Ok("binary-state"),
+        2 => Ok("continuous-state"),
+        3 => Ok("ef-state"),
+        4 => Err(unsupported(
+            "Dirichlet process nodes (pyhgf node_type 4) are not supported",
+        )),
+        5 => Err(unsupported(
+            "categorical nodes (pyhgf node_type 5) are not supported",
+        )),
+        6 => Ok("volatile-state"),
+        _ => Err(unsupported("unknown pyhgf node_type")),
+    }
+}
+
+/// The single coupling function a pyhgf node applies to its value children:
+/// the Rust nodes hold one coupling function for all of them.
+fn node_coupling_fn(node_idx: usize, node: &PyhgfNode) -> Result<Option<String>, NetworkError> {
+    let mut names = node.coupling_fns.iter().map(|name| match name.as_deref() {
+        None | Some("linear") | Some("identity") => Ok("linear"),
+        Some(name) => parse_coupling_fn(name)
+            .map(|cf| cf.kind.name())
+            .map_err(|_| NetworkError::UnsupportedStructure {
+                node_idx,
+                step: "from_pyhgf_spec",
+                reason: "unknown coupling function",
+            }),
+    });
+    let first = names.next().transpose()?;
+    for name in names {
+        if Some(name?) != first {
+            return Err(NetworkError::UnsupportedStructure {
+                node_idx,
+                step: "from_pyhgf_spec",
+                reason: "value children with different coupling functions",
+            });
+        }
+    }
+    Ok(first.filter(|&name| name != "linear").map(String::from))
+}
+
+/// Build the Rust network equivalent to the pyhgf network `nodes`.
+///
+/// Nodes are added in index order, each declaring its edges to the nodes
+/// added before it, then the coupling strengths are set edge by edge. As in
+/// pyhgf, continuous input nodes (no children) keep an autoconnection strength
+/// and a tonic volatility of zero.
+pub fn from_pyhgf_spec(
+    nodes: &[PyhgfNode],
+    volatility_updates: &str,
+) -> Result<Network, NetworkError> {
+    let mut network = Network::new(volatility_updates);
+    for (node_idx, node) in nodes.iter().enumerate() {
+        let kind = pyhgf_kind(node_idx, node.node_type)?;
+        let earlier = |idxs: &[usize]| {
+            let idxs: Vec<usize> = idxs.iter().copied().filter(|&i| i < node_idx).collect();
+            (!idxs.is_empty()).then_some(IntOrList::List(idxs))
+        };
+        let is_input = node.value_children.is_empty() && node.volatility_children.is_empty();
+        let accepted = parameters_for_type(kind);
+        let parameters: HashMap<String, f64> = node
+            .parameters
+            .iter()
+            .filter(|(key, _)| accepted.contains(&key.as_str()))
+            .filter(|(key, _)| {
+                !(is_input
+                    && kind == "continuous-state"
+                    && matches!(key.as_str(), "autoconnection_strength" | "tonic_volatility"))
+            })
+            .map(|(key, &value)| (key.clone(), value))
+            .collect();
+        network.add_nodes(
+            kind,
+            1,
+            AddNodesOptions {
+                value_parents: earlier(&node.value_parents),
+                value_children: earlier(&node.value_children),
+                volatility_parents: earlier(&node.volatility_parents),
+                volatility_children: earlier(&node.volatility_children),
+                coupling_fn: node_coupling_fn(node_idx, node)?,
+                additional_parameters: Some(parameters),
+                ..Default::default()
+            },
+        )?;
+    }
+
+    for (parent_idx, node) in nodes.iter().enumerate() {
+        for (edge, children, couplings) in [
+            (
+                "value_children",
+                &node.value_children,
+                &node.value_coupling_children,
+            ),
+            (
+                "volatility_children",
+                &node.volatility_children,
+                &node.volatility_coupling_children,
+            ),
+        ] {
+            for (&child_idx, &coupling) in children.iter().zip(couplings) {
+                let set = if edge == "value_children" {
+                    network.set_coupling(parent_idx, child_idx, coupling)
+                } else {
+                    network.set_volatility_coupling(parent_idx, child_idx, coupling)
+                };
+                set.map_err(|_| NetworkError::InvalidEdge {
+                    node_idx: parent_idx,
+                    edge,
+                    target_idx: child_idx,
+                    step: "from_pyhgf_spec",
+                    reason: "does not couple to node",
+                })?;
+            }
+        }
+    }
+
+    network.recompute_roles();
+    network.set_update_sequence();
+    Ok(network)
+}
+
+/// pyhgf `node_type` code of the Rust node kind of `node_idx` (volatile-state
+/// nodes are exported as two continuous nodes, see [`to_pyhgf_spec`]).
+fn pyhgf_node_type(node_idx: usize, kind: &str) -> Result<i64, NetworkError> {
+    match kind {
+        "constant-state" => Ok(0),
+        "binary-state" | "binary-input" => Ok(1),
+        "continuous-state" | "volatile-state" => Ok(2),
+        "ef-state" => Ok(3),
+        _ => Err(NetworkError::UnsupportedStructure {
+            node_idx,
+            step: "to_pyhgf_spec",
+            reason: "this node kind has no pyhgf counterpart",
+        }),
+    }
+}
+
+/// Describe `network` in the pyhgf layout, one [`PyhgfNode`] per node.
+///
+/// Coupling functions are exported by name. A volatile-state node is expanded
+/// into its explicit equivalent: the node keeps its index and its value level
+/// (with no tonic volatility of its own), and its implicit volatility level is
+/// appended after the other nodes as a continuous volatility parent coupled
+/// with strength 1. Precision edges and node kinds pyhgf does not have are
+/// rejected with [`NetworkError::UnsupportedStructure`].
+pub fn to_pyhgf_spec(network: &Network) -> Result<Vec<PyhgfNode>, NetworkError> {
+    let mut nodes = Vec::with_capacity(network.edges.len());
+    let mut volatility_levels = Vec::new();
+    for (node_idx, edges) in network.edges.iter().enumerate() {
+        if edges.precision_parents.is_some() || edges.precision_children.is_some() {
+            return Err(NetworkError::UnsupportedStructure {
+                node_idx,
+                step: "to_pyhgf_spec",
+                reason: "precision edges have no pyhgf counterpart",
+            });
+        }
+        let kind = edges.node_type.as_str();
+        let state = &network.attributes.states[node_idx];
+        let vectors = &network.attributes.vectors[node_idx];
+        let value_children = edges.value_children.clone().unwrap_or_default();
+        let coupling_fn = network.attributes.fn_ptrs[node_idx]
+            .coupling_fn
+            .map(|cf| coupling_fn_name(cf).to_string());
+        let mut node = PyhgfNode {
+            node_type: pyhgf_node_type(node_idx, kind)?,
+            value_parents: edges.value_parents.clone().unwrap_or_default(),
+            volatility_parents: edges.volatility_parents.clone().unwrap_or_default(),
+            volatility_children: edges.volatility_children.clone().unwrap_or_default(),
+            coupling_fns: vec![coupling_fn; value_children.len()],
+            value_children,
+            value_coupling_children: vectors.value_coupling_children.clone(),
+            volatility_coupling_children: vectors.volatility_coupling_children.clone(),
+            value_coupling_parents: vectors.value_coupling_parents.clone(),
+            volatility_coupling_parents: vectors.volatility_coupling_parents.clone(),
+            parameters: HashMap::new(),
+        };
+
+        if kind == "volatile-state" {
+            let level_idx = network.edges.len() + volatility_levels.len();
+            let mut level = PyhgfNode {
+                node_type: 2,
+                volatility_children: vec![node_idx],
+                volatility_coupling_children: vec![1.0],
+                ..Default::default()
+            };
+            for &key in trajectory_fields_for_type(kind) {
+                let value = state.get(key).unwrap_or_default();
+                match key.strip_suffix("_vol") {
+                    Some(key) => level.parameters.insert(key.to_string(), value),
+                    None => node.parameters.insert(key.to_string(), value),
+                };
+            }
+            node.parameters.insert("tonic_volatility".to_string(), 0.0);
+            node.volatility_parents = vec![level_idx];
+            node.volatility_coupling_parents = vec![1.0];
+            volatility_levels.push(level);
+        } else {
+            for &key in trajectory_fields_for_type(kind) {
+                if let Some(value) = state.get(key) {
+                    node.parameters.insert(key.to_string(), value);
+                }
+            }
+        }
+        nodes.push(node);
+    }
+    nodes.extend(volatility_levels);
+    Ok(nodes)
+}
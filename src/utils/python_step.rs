@@ -0,0 +1,214 @@
+//! Update steps implemented by a Python callable, for prototyping new update
+//! rules before porting them to Rust.
+//!
+//! The callable is invoked as `function(network, node_idx, time_step)`, where
+//! `network` is a [`NetworkProxy`] giving read and write access to the node
+//! attributes by name. The attributes are moved into the proxy for the
+//! duration of the call and moved back afterwards, so the proxy should not be
+//! kept beyond the call.
+//!
+//! The callable is stored behind a type-erased [`PythonStep`], so the
+//! inference loop does not depend on the Python runtime unless such a step
+//! was actually added. Each step keeps its [`StepPlacement`], so that a
+//! rebuilt update sequence gets it back in the same place.
+
+use std::fmt;
+use std::mem;
+use std::sync::Arc;
+
+use pyo3::exceptions::{PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::error::NetworkError;
+use crate::model::network::{AdjacencyLists, Network, NodeState, NodeVectors};
+use crate::utils::function_pointer::StepKind;
+
+type StepFn = dyn Fn(&mut Network, usize, f64) -> Result<(), NetworkError> + Send + Sync;
+
+/// Where a Python step goes in the update sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepPlacement {
+    /// In place of the built-in step `name` of `node_idx`, at its stage.
+    Replace { node_idx: usize, name: String },
+    /// On `node_idx` at `position` of the predictions (or, with `updates`,
+    /// of the updates), at the end when `position` is `None`.
+    Insert {
+        node_idx: usize,
+        updates: bool,
+        position: Option<usize>,
+        kind: StepKind,
+    },
+}
+
+impl StepPlacement {
+    pub fn node_idx(&self) -> usize {
+        match *self {
+            Self::Replace { node_idx, .. } | Self::Insert { node_idx, .. } => node_idx,
+        }
+    }
+}
+
+/// A Python callable stored in `Network::python_steps`.
+#[derive(Clone)]
+pub struct PythonStep {
+    /// `__qualname__` of the callable, reported by `get_update_sequence`.
+    pub name: String,
+    pub placement: StepPlacement,
+    function: Arc<StepFn>,
+}
+
+impl fmt::Debug for PythonStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PythonStep")
+            .field("name", &self.name)
+            .field("placement", &self.placement)
+            .finish()
+    }
+}
+
+impl PythonStep {
+    pub fn new(function: &Bound<'_, PyAny>, placement: StepPlacement) -> Self {
+        let name = function
+            .getattr("__qualname__")
+            .and_then(|name| name.extract::<String>())
+            .unwrap_or_else(|_| "python_step".to_string());
+        let function = function.clone().unbind();
+        Self {
+            name,
+            placement,
+            function: Arc::new(move |network, node_idx, time_step| {
+                call_python_step(&function, network, node_idx, time_step)
+            }),
+        }
+    }
+
+    #[inline]
+    pub fn call(
+        &self,
+        network: &mut Network,
+        node_idx: usize,
+        time_step: f64,
+    ) -> Result<(), NetworkError> {
+        (self.function)(network, node_idx, time_step)
+    }
+}
+
+/// Node attributes handed to a Python update step.
+#[pyclass]
+#[derive(Default)]
+pub struct NetworkProxy {
+    states: Vec<NodeState>,
+    vectors: Vec<NodeVectors>,
+    edges: Vec<AdjacencyLists>,
+}
+
+impl NetworkProxy {
+    fn state(&mut self, node_idx: usize) -> PyResult<(&mut NodeState, &mut NodeVectors)> {
+        match (
+            self.states.get_mut(node_idx),
+            self.vectors.get_mut(node_idx),
+        ) {
+            (Some(state), Some(vectors)) => Ok((state, vectors)),
+            _ => Err(PyKeyError::new_err(format!(
+                "node {} does not exist",
+                node_idx
+            ))),
+        }
+    }
+}
+
+fn unknown_attribute(node_idx: usize, key: &str) -> PyErr {
+    PyKeyError::new_err(format!("node {}: unknown attribute `{}`", node_idx, key))
+}
+
+#[pymethods]
+impl NetworkProxy {
+    /// Value of attribute `key` of node `node_idx`: a float for scalar
+    /// attributes, a list of floats for vector attributes.
+    fn get<'py>(&mut self, py: Python<'py>, node_idx: usize, key: &str) -> PyResult<Py<PyAny>> {
+        let (state, vectors) = self.state(node_idx)?;
+        if let Some(value) = state.get(key) {
+            Ok(value.into_pyobject(py)?.into_any().unbind())
+        } else if let Some(values) = vectors.get(key) {
+            Ok(values.into_pyobject(py)?.into_any().unbind())
+        } else {
+            Err(unknown_attribute(node_idx, key))
+        }
+    }
+
+    /// Set attribute `key` of node `node_idx` (a float, or a sequence of floats
+    /// for vector attributes). A vector keeps its length, which the update
+    /// steps rely on.
+    fn set(&mut self, node_idx: usize, key: &str, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        let (state, vectors) = self.state(node_idx)?;
+        if let Some(field) = state.get_mut(key) {
+            *field = value.extract()?;
+        } else if let Some(field) = vectors.get_mut(key) {
+            let values: Vec<f64> = value.extract()?;
+            if values.len() != field.len() {
+                return Err(PyValueError::new_err(format!(
+                    "node {}: `{}` holds {} values, got {}",
+                    node_idx,
+                    key,
+                    field.len(),
+                    values.len()
+                )));
+            }
+            *field = values;
+        } else {
+            return Err(unknown_attribute(node_idx, key));
+        }
+        Ok(())
+    }
+
+    /// Adjacency lists of node `node_idx` as a dict (`None` for empty lists).
+    fn edges<'py>(&self, py: Python<'py>, node_idx: usize) -> PyResult<Py<PyDict>> {
+        let edges = self
+            .edges
+            .get(node_idx)
+            .ok_or_else(|| PyKeyError::new_err(format!("node {} does not exist", node_idx)))?;
+        let py_dict = PyDict::new(py);
+        py_dict.set_item("node_type", &edges.node_type)?;
+        py_dict.set_item("value_parents", &edges.value_parents)?;
+        py_dict.set_item("value_children", &edges.value_children)?;
+        py_dict.set_item("volatility_parents", &edges.volatility_parents)?;
+        py_dict.set_item("volatility_children", &edges.volatility_children)?;
+        py_dict.set_item("precision_parents", &edges.precision_parents)?;
+        py_dict.set_item("precision_children", &edges.precision_children)?;
+        Ok(py_dict.into())
+    }
+}
+
+/// Run `function` on `node_idx`. A Python exception is returned as
+/// [`NetworkError::PythonStep`].
+fn call_python_step(
+    function: &Py<PyAny>,
+    network: &mut Network,
+    node_idx: usize,
+    time_step: f64,
+) -> Result<(), NetworkError> {
+    let raised = |err: PyErr| NetworkError::PythonStep {
+        node_idx,
+        message: format!("raised {}", err),
+    };
+    Python::attach(|py| {
+        // Allocated empty, so that the attributes are only moved once it
+        // exists.
+        let proxy = Bound::new(py, NetworkProxy::default()).map_err(raised)?;
+        {
+            let mut proxy = proxy.borrow_mut();
+            proxy.states = mem::take(&mut network.attributes.states);
+            proxy.vectors = mem::take(&mut network.attributes.vectors);
+            proxy.edges = mem::take(&mut network.edges);
+        }
+
+        let result = function.call1(py, (proxy.clone(), node_idx, time_step));
+
+        let mut proxy = proxy.borrow_mut();
+        network.attributes.states = mem::take(&mut proxy.states);
+        network.attributes.vectors = mem::take(&mut proxy.vectors);
+        network.edges = mem::take(&mut proxy.edges);
+        result.map(|_| ()).map_err(raised)
+    })
+}
@@ -0,0 +1,269 @@
+//! Generative mode: simulate latent states and observations from the
+//! network's own prediction model, e.g. for parameter-recovery studies.
+
+use rand::Rng;
+use rand_distr::StandardNormal;
+
+use crate::error::NetworkError;
+use crate::model::network::{Network, NodeTrajectories};
+use crate::updates::nodalised::observations::check_observation;
+use crate::utils::beliefs_propagation::{
+    input_columns, run_predictions, run_updates, set_observations,
+};
+use crate::utils::weight_initialisation::make_rng;
+
+/// Sampled data: one row per time step.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Samples {
+    /// Values of the input nodes, in `network.inputs` order.
+    pub observations: Vec<Vec<f64>>,
+    /// Values of every node, in node order.
+    pub states: Vec<Vec<f64>>,
+}
+
+/// Draw `n_steps` time steps from the generative model of `network`.
+///
+/// Each step runs the prediction sequence, and each node is drawn from
+/// `Normal(expected_mean, 1 / expected_precision)` right after its own
+/// prediction, so its children are predicted from the drawn value. Latent
+/// states are points: their precision is set to infinity, so the predictive
+/// variance of a node reduces to its volatility `Δt · exp(ω + Σ κ x)` (plus
+/// the observation noise `1 / precision` for the inputs). The drawn value
+/// becomes the node's `mean` for the next step.
+///
+/// `observation_noise` adds Gaussian noise of that standard deviation to the
+/// returned observations only: the latent states, and the input values they
+/// are predicted from, are the same as without it. The noise is drawn after
+/// every latent value, so a given `seed` yields the same latent trajectory
+/// whatever the noise scale. It must be finite and non-negative.
+///
+/// The network starts from its current means and its attributes are restored
+/// afterwards. Only continuous-state nodes can be sampled.
+pub fn sample(
+    network: &mut Network,
+    n_steps: usize,
+    time_steps: Option<&[f64]>,
+    seed: Option<u64>,
+    observation_noise: f64,
+) -> Result<Samples, NetworkError> {
+    if !(observation_noise.is_finite() && observation_noise >= 0.0) {
+        return Err(NetworkError::InvalidValue {
+            parameter: "observation_noise",
+            value: observation_noise.to_string(),
+            accepted: "a finite non-negative standard deviation",
+        });
+    }
+    if let Some(time_steps) = time_steps {
+        if time_steps.len() != n_steps {
+            return Err(NetworkError::DimensionMismatch {
+                what: "time steps (one per sampled step)",
+                expected: n_steps,
+                found: time_steps.len(),
+            });
+        }
+    }
+    if let Some(node_idx) = network
+        .edges
+        .iter()
+        .position(|edge| edge.node_type != "continuous-state")
+    {
+        return Err(NetworkError::UnsupportedStructure {
+            node_idx,
+            step: "sample",
+            reason: "sampling is only implemented for continuous-state nodes",
+        });
+    }
+    if network.update_sequence.predictions.is_empty() && network.update_sequence.updates.is_empty()
+    {
+        network.set_update_sequence();
+    }
+    let predictions = network.update_sequence.predictions.clone();
+    network.check_steps(&predictions)?;
+
+    let mut rng = make_rng(seed);
+    let saved = network.checkpoint();
+    let is_input: Vec<bool> = (0..network.edges.len())
+        .map(|node_idx| network.inputs.contains(&node_idx))
+        .collect();
+    for (state, &is_input) in network.attributes.states.iter_mut().zip(&is_input) {
+        if !is_input {
+            state.precision = f64::INFINITY;
+        }
+    }
+
+    let mut samples = Samples {
+        observations: Vec::with_capacity(n_steps),
+        states: Vec::with_capacity(n_steps),
+    };
+    let result = (0..n_steps).try_for_each(|t| {
+        let time_step = time_steps.map_or(1.0, |time_steps| time_steps[t]);
+        for &(node_idx, step) in &predictions {
+            step.call(network, node_idx, time_step)?;
+            let state = &mut network.attributes.states[node_idx];
+            let noise: f64 = rng.sample(StandardNormal);
+            let value = state.expected_mean + noise / state.expected_precision.sqrt();
+            state.mean = value;
+            if !is_input[node_idx] {
+                state.expected_mean = value;
+                state.expected_precision = f64::INFINITY;
+            }
+        }
+        let states = &network.attributes.states;
+        samples
+            .observations
+            .push(network.inputs.iter().map(|&idx| states[idx].mean).collect());
+        samples
+            .states
+            .push(states.iter().map(|state| state.mean).collect());
+        Ok(())
+    });
+
+    network.restore_attributes(saved);
+    result?;
+
+    if observation_noise != 0.0 {
+        for value in samples.observations.iter_mut().flatten() {
+            let noise: f64 = rng.sample(StandardNormal);
+            *value += observation_noise * noise;
+        }
+    }
+    Ok(samples)
+}
+
+/// Simulate the responses of the agent modelled by `network` to
+/// `observations`, e.g. for posterior predictive checks.
+///
+/// The response nodes are the softmax-state inputs; `observations` holds one
+/// row per time step with one value per other input (one per stream for an
+/// aggregate-input node), in `network.inputs` order. At each time step the
+/// predictions run, a choice is drawn from the predicted `probabilities` of
+/// every response node and stands in for the observed response, then the
+/// updates run as in
+/// [`Network::input_data`]. `seed` makes the draws reproducible.
+///
+/// Returns the simulated choices, one row per time step with one entry per
+/// response node; the belief trajectories are recorded in
+/// `network.node_trajectories`.
+pub fn simulate_responses(
+    network: &mut Network,
+    observations: &[Vec<f64>],
+    time_steps: Option<&[f64]>,
+    seed: Option<u64>,
+) -> Result<Vec<Vec<f64>>, NetworkError> {
+    let n_time = observations.len();
+    if let Some(time_steps) = time_steps {
+        if time_steps.len() != n_time {
+            return Err(NetworkError::DimensionMismatch {
+                what: "time_steps (one per observation)",
+                expected: n_time,
+                found: time_steps.len(),
+            });
+        }
+    }
+    let is_response: Vec<bool> = network
+        .inputs
+        .iter()
+        .map(|&idx| network.edges[idx].node_type == "softmax-state")
+        .collect();
+    let n_responses = is_response.iter().filter(|&&response| response).count();
+    let n_columns: usize = network
+        .inputs
+        .iter()
+        .zip(&is_response)
+        .filter(|&(_, &response)| !response)
+        .map(|(&idx, _)| input_columns(network, idx))
+        .sum();
+    if n_responses == 0 {
+        return Err(NetworkError::NotRunnable {
+            reason: "the network has no response (softmax-state input) node to simulate",
+        });
+    }
+    // Checked for every time step before any of them runs, as in
+    // `input_data`; the drawn choices are valid by construction.
+    for row in observations {
+        if row.len() != n_columns {
+            return Err(NetworkError::DimensionMismatch {
+                what: "observations per time step (one per input that is not a response node)",
+                expected: n_columns,
+                found: row.len(),
+            });
+        }
+        let mut row = row.iter();
+        for (&idx, _) in network
+            .inputs
+            .iter()
+            .zip(&is_response)
+            .filter(|&(_, &response)| !response)
+        {
+            let width = input_columns(network, idx);
+            if width == 1 {
+                check_observation(network, idx, *row.next().unwrap())?;
+            } else {
+                row.nth(width - 1);
+            }
+        }
+    }
+    if network.update_sequence.predictions.is_empty() && network.update_sequence.updates.is_empty()
+    {
+        network.set_update_sequence();
+    }
+    network.check_steps(&network.update_sequence.predictions)?;
+    network.check_steps(&network.update_sequence.updates)?;
+
+    let mut rng = make_rng(seed);
+    let mut trajectories =
+        NodeTrajectories::with_keys(network.edges.len(), n_time, network.record_keys.as_deref());
+    let mut responses = Vec::with_capacity(n_time);
+    let mut inputs_set = Vec::with_capacity(is_response.len());
+    // Moved out while the steps mutate the network, as in `input_data`.
+    let sequence = std::mem::take(&mut network.update_sequence);
+    let result = observations.iter().enumerate().try_for_each(|(t, row)| {
+        let time_step = time_steps.map_or(1.0, |time_steps| time_steps[t]);
+        run_predictions(network, &sequence.predictions, time_step)?;
+
+        inputs_set.clear();
+        let mut row = row.iter();
+        for (&idx, &response) in network.inputs.iter().zip(&is_response) {
+            if response {
+                inputs_set.push(draw_choice(
+                    &network.attributes.vectors[idx].probabilities,
+                    &mut rng,
+                ));
+            } else {
+                inputs_set.extend(row.by_ref().take(input_columns(network, idx)));
+            }
+        }
+        set_observations(network, &inputs_set)?;
+        run_updates(network, &sequence.updates, time_step)?;
+
+        responses.push(
+            inputs_set
+                .iter()
+                .zip(&is_response)
+                .filter(|(_, &response)| response)
+                .map(|(&choice, _)| choice)
+                .collect(),
+        );
+        trajectories.push(&network.attributes, time_step);
+        Ok(())
+    });
+    network.update_sequence = sequence;
+    result?;
+
+    network.node_trajectories = trajectories;
+    Ok(responses)
+}
+
+/// Index drawn from the categorical distribution `probabilities`.
+fn draw_choice(probabilities: &[f64], rng: &mut impl Rng) -> f64 {
+    let u: f64 = rng.random();
+    let mut cumulative = 0.0;
+    for (choice, &p) in probabilities.iter().enumerate() {
+        cumulative += p;
+        if u < cumulative {
+            return choice as f64;
+        }
+    }
+    // Rounding can leave the total just under 1.
+    probabilities.len().saturating_sub(1) as f64
+}
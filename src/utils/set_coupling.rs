@@ -1,7 +1,224 @@
-use crate::model::network::Network;
+use crate::error::{NetworkError, SetCouplingError};
+use crate::model::network::{
+    parameters_for_type, AdjacencyLists, Network, NodeVectors, NOT_AN_EDGE,
+};
 
-/// Update the value-coupling strength for a single `(parent, child)` pair.
-pub fn set_coupling(network: &mut Network, parent_idx: usize, child_idx: usize, coupling: f64) {
+/// Update the value-coupling strength for a single `(parent, child)` pair, on
+/// both the child side (`value_coupling_parents`) and the parent side
+/// (`value_coupling_children`).
+///
+/// Fails without writing anything when the two nodes are not connected by a
+/// value-coupling edge, or when one of the coupling vectors has no entry for
+/// it.
+pub fn set_coupling(
+    network: &mut Network,
+    parent_idx: usize,
+    child_idx: usize,
+    coupling: f64,
+) -> Result<(), SetCouplingError> {
+    set_edge_coupling(network, EdgeKind::Value, parent_idx, child_idx, coupling)
+}
+
+/// Update the volatility-coupling strength κ for a single `(parent, child)`
+/// pair, on both the child side (`volatility_coupling_parents`) and the parent
+/// side (`volatility_coupling_children`). Fails like [`set_coupling`].
+pub fn set_volatility_coupling(
+    network: &mut Network,
+    parent_idx: usize,
+    child_idx: usize,
+    coupling: f64,
+) -> Result<(), SetCouplingError> {
+    set_edge_coupling(
+        network,
+        EdgeKind::Volatility,
+        parent_idx,
+        child_idx,
+        coupling,
+    )
+}
+
+/// Value-coupling strength of the edge `parent_idx → child_idx`, read from
+/// the child side (`value_coupling_parents`, what the predictions use) or,
+/// when it has no entry, from the parent side. `None` when the two nodes are
+/// not connected by a value-coupling edge.
+pub fn get_coupling(network: &Network, parent_idx: usize, child_idx: usize) -> Option<f64> {
+    get_edge_coupling(network, EdgeKind::Value, parent_idx, child_idx)
+}
+
+/// Volatility-coupling strength κ of the edge `parent_idx → child_idx`, read
+/// like [`get_coupling`].
+pub fn get_volatility_coupling(
+    network: &Network,
+    parent_idx: usize,
+    child_idx: usize,
+) -> Option<f64> {
+    get_edge_coupling(network, EdgeKind::Volatility, parent_idx, child_idx)
+}
+
+/// The two kinds of coupled edges, with the adjacency lists and coupling
+/// vectors that hold each of them.
+#[derive(Debug, Clone, Copy)]
+enum EdgeKind {
+    Value,
+    Volatility,
+}
+
+impl EdgeKind {
+    fn parents(self, edges: &AdjacencyLists) -> Option<&Vec<usize>> {
+        match self {
+            Self::Value => edges.value_parents.as_ref(),
+            Self::Volatility => edges.volatility_parents.as_ref(),
+        }
+    }
+
+    fn children(self, edges: &AdjacencyLists) -> Option<&Vec<usize>> {
+        match self {
+            Self::Value => edges.value_children.as_ref(),
+            Self::Volatility => edges.volatility_children.as_ref(),
+        }
+    }
+
+    fn parents_coupling_ref(self, vectors: &NodeVectors) -> &[f64] {
+        match self {
+            Self::Value => &vectors.value_coupling_parents,
+            Self::Volatility => &vectors.volatility_coupling_parents,
+        }
+    }
+
+    fn children_coupling_ref(self, vectors: &NodeVectors) -> &[f64] {
+        match self {
+            Self::Value => &vectors.value_coupling_children,
+            Self::Volatility => &vectors.volatility_coupling_children,
+        }
+    }
+
+    fn parents_coupling(self, vectors: &mut NodeVectors) -> (&'static str, &mut Vec<f64>) {
+        match self {
+            Self::Value => (
+                "value_coupling_parents",
+                &mut vectors.value_coupling_parents,
+            ),
+            Self::Volatility => (
+                "volatility_coupling_parents",
+                &mut vectors.volatility_coupling_parents,
+            ),
+        }
+    }
+
+    fn children_coupling(self, vectors: &mut NodeVectors) -> (&'static str, &mut Vec<f64>) {
+        match self {
+            Self::Value => (
+                "value_coupling_children",
+                &mut vectors.value_coupling_children,
+            ),
+            Self::Volatility => (
+                "volatility_coupling_children",
+                &mut vectors.volatility_coupling_children,
+            ),
+        }
+    }
+
+    fn no_such_edge(self, parent_idx: usize, child_idx: usize) -> SetCouplingError {
+        match self {
+            Self::Value => SetCouplingError::NoSuchEdge {
+                parent_idx,
+                child_idx,
+            },
+            Self::Volatility => SetCouplingError::NoSuchVolatilityEdge {
+                parent_idx,
+                child_idx,
+            },
+        }
+    }
+}
+
+fn get_edge_coupling(
+    network: &Network,
+    kind: EdgeKind,
+    parent_idx: usize,
+    child_idx: usize,
+) -> Option<f64> {
+    let child_pos = network
+        .edges
+        .get(child_idx)
+        .and_then(|e| kind.parents(e))
+        .and_then(|parents| parents.iter().position(|&p| p == parent_idx))?;
+    let from_child = network
+        .attributes
+        .vectors
+        .get(child_idx)
+        .and_then(|vectors| kind.parents_coupling_ref(vectors).get(child_pos).copied());
+    from_child.or_else(|| {
+        let parent_pos = network
+            .edges
+            .get(parent_idx)
+            .and_then(|e| kind.children(e))
+            .and_then(|children| children.iter().position(|&c| c == child_idx))?;
+        network
+            .attributes
+            .vectors
+            .get(parent_idx)
+            .and_then(|vectors| kind.children_coupling_ref(vectors).get(parent_pos).copied())
+    })
+}
+
+fn set_edge_coupling(
+    network: &mut Network,
+    kind: EdgeKind,
+    parent_idx: usize,
+    child_idx: usize,
+    coupling: f64,
+) -> Result<(), SetCouplingError> {
+    let child_pos = network
+        .edges
+        .get(child_idx)
+        .and_then(|e| kind.parents(e))
+        .and_then(|parents| parents.iter().position(|&p| p == parent_idx))
+        .ok_or_else(|| kind.no_such_edge(parent_idx, child_idx))?;
+    let parent_pos = network
+        .edges
+        .get(parent_idx)
+        .and_then(|e| kind.children(e))
+        .and_then(|children| children.iter().position(|&c| c == child_idx))
+        .ok_or_else(|| kind.no_such_edge(parent_idx, child_idx))?;
+
+    // Check both sides before writing either.
+    for (node_idx, position, is_child_side) in [
+        (child_idx, child_pos, true),
+        (parent_idx, parent_pos, false),
+    ] {
+        let vectors = &mut network.attributes.vectors[node_idx];
+        let (attribute, couplings) = if is_child_side {
+            kind.parents_coupling(vectors)
+        } else {
+            kind.children_coupling(vectors)
+        };
+        if couplings.is_empty() {
+            return Err(SetCouplingError::VectorMissing {
+                node_idx,
+                attribute,
+            });
+        }
+        if position >= couplings.len() {
+            return Err(SetCouplingError::VectorTooShort {
+                node_idx,
+                attribute,
+                position,
+                len: couplings.len(),
+            });
+        }
+    }
+
+    kind.parents_coupling(&mut network.attributes.vectors[child_idx])
+        .1[child_pos] = coupling;
+    kind.children_coupling(&mut network.attributes.vectors[parent_idx])
+        .1[parent_pos] = coupling;
+    Ok(())
+}
+
+/// Lenient [`set_coupling`]: writes whichever side of the edge exists and
+/// silently skips the rest (unconnected pairs, short coupling vectors).
+pub fn try_set_coupling(network: &mut Network, parent_idx: usize, child_idx: usize, coupling: f64) {
     // 1. Child side: value_coupling_parents[pos of parent in child's value_parents]
     if let Some(pos) = network.edges[child_idx]
         .value_parents
@@ -27,7 +244,131 @@ pub fn set_coupling(network: &mut Network, parent_idx: usize, child_idx: usize,
     }
 }
 
-/// Update the value-coupling strength for every combination of parents and children.
+/// Mark the value coupling `parent_idx → child_idx` as frozen (or not) for
+/// learning. The flag lives on the child, next to `value_coupling_parents`.
+pub fn set_coupling_frozen(
+    network: &mut Network,
+    parent_idx: usize,
+    child_idx: usize,
+    frozen: bool,
+) -> Result<(), NetworkError> {
+    let step = if frozen {
+        "freeze_coupling"
+    } else {
+        "unfreeze_coupling"
+    };
+    let pos = value_parent_position(network, parent_idx, child_idx, step)?;
+
+    let flags = &mut network.attributes.vectors[child_idx].coupling_frozen;
+    if flags.len() <= pos {
+        flags.resize(pos + 1, 0.0);
+    }
+    flags[pos] = if frozen { 1.0 } else { 0.0 };
+    Ok(())
+}
+
+/// Position of `parent_idx` in the value parents of `child_idx`, for the
+/// setters of the per-edge flags.
+fn value_parent_position(
+    network: &Network,
+    parent_idx: usize,
+    child_idx: usize,
+    step: &'static str,
+) -> Result<usize, NetworkError> {
+    let edge = network
+        .edges
+        .get(child_idx)
+        .ok_or(NetworkError::MissingNode {
+            node_idx: child_idx,
+            step,
+        })?;
+    edge.value_parents
+        .as_ref()
+        .and_then(|vp| vp.iter().position(|&p| p == parent_idx))
+        .ok_or(NetworkError::InvalidEdge {
+            node_idx: child_idx,
+            edge: "value_parents",
+            target_idx: parent_idx,
+            step,
+            reason: NOT_AN_EDGE,
+        })
+}
+
+/// Fail unless `child_idx` is a continuous or volatile node, the only kinds
+/// whose predictions read the `parameter` flag of their value parents.
+fn check_flag_child(
+    network: &Network,
+    child_idx: usize,
+    parameter: &str,
+) -> Result<(), NetworkError> {
+    let node_type = &network.edges[child_idx].node_type;
+    if node_type != "continuous-state" && node_type != "volatile-state" {
+        return Err(NetworkError::InvalidParameter {
+            node_type: node_type.clone(),
+            parameter: parameter.to_string(),
+            accepted: parameters_for_type(node_type),
+        });
+    }
+    Ok(())
+}
+
+/// Delay the value coupling `parent_idx → child_idx` by `lag` steps: 1 makes
+/// the child's prediction read the parent's `previous_mean`, 0 its
+/// `expected_mean` again. The flag lives on the child, next to
+/// `value_coupling_parents`; only continuous and volatile children read it.
+pub fn set_coupling_lag(
+    network: &mut Network,
+    parent_idx: usize,
+    child_idx: usize,
+    lag: usize,
+) -> Result<(), NetworkError> {
+    if lag > 1 {
+        return Err(NetworkError::InvalidValue {
+            parameter: "coupling_lag",
+            value: lag.to_string(),
+            accepted: "0 or 1",
+        });
+    }
+    let pos = value_parent_position(network, parent_idx, child_idx, "set_coupling_lag")?;
+    check_flag_child(network, child_idx, "coupling_lag")?;
+
+    let lags = &mut network.attributes.vectors[child_idx].coupling_lag;
+    if lags.len() <= pos {
+        lags.resize(pos + 1, 0.0);
+    }
+    lags[pos] = lag as f64;
+    Ok(())
+}
+
+/// Make `parent_idx` a drift parent of `child_idx` (or an ordinary value
+/// parent again): the child integrates the parent, `μ̂ = μ + Δt · κ · μ̂_b`,
+/// and the parent's posterior update reads the child's prediction errors
+/// through `Δt · κ`. Setting the flag also sets the child's
+/// `autoconnection_strength` to 1. The flag lives on the child, next to
+/// `value_coupling_parents`; only continuous and volatile children take
+/// drift parents.
+pub fn set_drift_coupling(
+    network: &mut Network,
+    parent_idx: usize,
+    child_idx: usize,
+    drift: bool,
+) -> Result<(), NetworkError> {
+    let pos = value_parent_position(network, parent_idx, child_idx, "set_drift_coupling")?;
+    check_flag_child(network, child_idx, "coupling_drift")?;
+
+    let flags = &mut network.attributes.vectors[child_idx].coupling_drift;
+    if flags.len() <= pos {
+        flags.resize(pos + 1, 0.0);
+    }
+    flags[pos] = if drift { 1.0 } else { 0.0 };
+    if drift {
+        network.attributes.states[child_idx].autoconnection_strength = 1.0;
+    }
+    Ok(())
+}
+
+/// Update the value-coupling strength for every combination of parents and
+/// children, skipping the pairs that are not connected.
 pub fn set_coupling_vec(
     network: &mut Network,
     parent_idxs: &[usize],
@@ -36,7 +377,7 @@ pub fn set_coupling_vec(
 ) {
     for &parent_idx in parent_idxs {
         for &child_idx in child_idxs {
-            set_coupling(network, parent_idx, child_idx, coupling);
+            try_set_coupling(network, parent_idx, child_idx, coupling);
         }
     }
 }
@@ -45,9 +386,10 @@ pub fn set_coupling_vec(
 mod tests {
     use super::*;
     use crate::model::network::{
-        AdjacencyLists, Attributes, Network, NodeFnPtrs, NodeState, NodeTrajectories, NodeVectors,
-        UpdateSequence,
+        AddNodesOptions, AdjacencyLists, Attributes, Network, NodeFnPtrs, NodeState,
+        NodeTrajectories, NodeVectors, UpdateSequence,
     };
+    use std::collections::HashMap;
 
     /// Build a minimal 3-node network:
     ///   node 0 (child)  — value_parents: [1, 2]
@@ -89,6 +431,9 @@ mod tests {
                     value_children: None,
                     volatility_parents: None,
                     volatility_children: None,
+                    precision_parents: None,
+                    precision_children: None,
+                    likelihood: "gaussian".into(),
                 },
                 AdjacencyLists {
                     node_type: "continuous-state".into(),
@@ -97,6 +442,9 @@ mod tests {
                     value_children: Some(vec![0]),
                     volatility_parents: None,
                     volatility_children: None,
+                    precision_parents: None,
+                    precision_children: None,
+                    likelihood: "gaussian".into(),
                 },
                 AdjacencyLists {
                     node_type: "continuous-state".into(),
@@ -105,6 +453,9 @@ mod tests {
                     value_children: Some(vec![0]),
                     volatility_parents: None,
                     volatility_children: None,
+                    precision_parents: None,
+                    precision_children: None,
+                    likelihood: "gaussian".into(),
                 },
             ],
             inputs: vec![0],
@@ -114,20 +465,29 @@ mod tests {
                 predictions: Vec::new(),
                 updates: Vec::new(),
             },
-            node_trajectories: NodeTrajectories { nodes: Vec::new() },
+            edge_lookup: Default::default(),
+            node_trajectories: NodeTrajectories::default(),
             layers: Vec::new(),
             adam_state: None,
             roots: vec![1],
             leafs: vec![0],
             max_posterior_precision: 1e10,
             precision_clipping_value: 1e-6,
+            python_steps: Vec::new(),
+            record_keys: None,
+            check_divergence: false,
+            negative_precision: Default::default(),
+            entropy_source: Default::default(),
+            step_error: None,
+            pending_clusters: Vec::new(),
+            metadata: HashMap::new(),
         }
     }
 
     #[test]
     fn test_set_coupling_updates_both_sides() {
         let mut net = make_test_network();
-        set_coupling(&mut net, 1, 0, 0.42);
+        set_coupling(&mut net, 1, 0, 0.42).unwrap();
 
         assert_eq!(net.attributes.vectors[0].value_coupling_parents[0], 0.42);
         assert_eq!(net.attributes.vectors[0].value_coupling_parents[1], 1.0);
@@ -137,7 +497,7 @@ mod tests {
     #[test]
     fn test_set_coupling_second_parent() {
         let mut net = make_test_network();
-        set_coupling(&mut net, 2, 0, 3.5);
+        set_coupling(&mut net, 2, 0, 3.5).unwrap();
 
         assert_eq!(net.attributes.vectors[0].value_coupling_parents[0], 1.0);
         assert_eq!(net.attributes.vectors[0].value_coupling_parents[1], 3.5);
@@ -145,9 +505,17 @@ mod tests {
     }
 
     #[test]
-    fn test_set_coupling_nonexistent_edge_is_noop() {
+    fn test_set_coupling_nonexistent_edge_errors() {
         let mut net = make_test_network();
-        set_coupling(&mut net, 1, 2, 99.0);
+        assert_eq!(
+            set_coupling(&mut net, 1, 2, 99.0),
+            Err(SetCouplingError::NoSuchEdge {
+                parent_idx: 1,
+                child_idx: 2
+            })
+        );
+        assert!(set_coupling(&mut net, 7, 0, 99.0).is_err());
+        try_set_coupling(&mut net, 1, 2, 99.0);
 
         assert_eq!(
             net.attributes.vectors[0].value_coupling_parents,
@@ -157,6 +525,127 @@ mod tests {
         assert_eq!(net.attributes.vectors[2].value_coupling_children, vec![1.0]);
     }
 
+    #[test]
+    fn test_set_coupling_short_vectors() {
+        let mut net = make_test_network();
+        net.attributes.vectors[1].value_coupling_children.clear();
+        assert_eq!(
+            set_coupling(&mut net, 1, 0, 0.5),
+            Err(SetCouplingError::VectorMissing {
+                node_idx: 1,
+                attribute: "value_coupling_children"
+            })
+        );
+        // Nothing was written on the child side either.
+        assert_eq!(net.attributes.vectors[0].value_coupling_parents[0], 1.0);
+
+        net.attributes.vectors[0].value_coupling_parents.pop();
+        assert_eq!(
+            set_coupling(&mut net, 2, 0, 0.5),
+            Err(SetCouplingError::VectorTooShort {
+                node_idx: 0,
+                attribute: "value_coupling_parents",
+                position: 1,
+                len: 1
+            })
+        );
+
+        // The lenient variant writes the side that exists.
+        try_set_coupling(&mut net, 2, 0, 0.5);
+        assert_eq!(net.attributes.vectors[2].value_coupling_children, vec![0.5]);
+    }
+
+    #[test]
+    fn test_get_coupling_reads_back_set_coupling() {
+        let mut net = make_test_network();
+        set_coupling(&mut net, 2, 0, 3.5).unwrap();
+        assert_eq!(get_coupling(&net, 2, 0), Some(3.5));
+        assert_eq!(get_coupling(&net, 1, 0), Some(1.0));
+
+        // Not edges: wrong direction, unconnected pair, missing node.
+        assert_eq!(get_coupling(&net, 0, 2), None);
+        assert_eq!(get_coupling(&net, 1, 2), None);
+        assert_eq!(get_coupling(&net, 7, 0), None);
+        assert_eq!(get_volatility_coupling(&net, 1, 0), None);
+
+        // Falls back to the parent side when the child side has no entry.
+        net.attributes.vectors[0].value_coupling_parents.clear();
+        assert_eq!(get_coupling(&net, 2, 0), Some(3.5));
+    }
+
+    #[test]
+    fn test_get_volatility_coupling_reads_back_set_volatility_coupling() {
+        let mut net = make_test_network();
+        net.edges[0].volatility_parents = Some(vec![2]);
+        net.edges[2].volatility_children = Some(vec![0]);
+        net.attributes.vectors[0].volatility_coupling_parents = vec![1.0];
+        net.attributes.vectors[2].volatility_coupling_children = vec![1.0];
+
+        set_volatility_coupling(&mut net, 2, 0, 0.25).unwrap();
+        assert_eq!(get_volatility_coupling(&net, 2, 0), Some(0.25));
+        assert_eq!(get_volatility_coupling(&net, 1, 0), None);
+        assert_eq!(get_coupling(&net, 2, 0), Some(1.0));
+    }
+
+    #[test]
+    fn test_set_coupling_frozen_flags() {
+        let mut net = make_test_network();
+        set_coupling_frozen(&mut net, 2, 0, true).unwrap();
+        assert_eq!(net.attributes.vectors[0].coupling_frozen, vec![0.0, 1.0]);
+
+        set_coupling_frozen(&mut net, 2, 0, false).unwrap();
+        assert_eq!(net.attributes.vectors[0].coupling_frozen, vec![0.0, 0.0]);
+
+        assert!(matches!(
+            set_coupling_frozen(&mut net, 1, 2, true),
+            Err(NetworkError::InvalidEdge {
+                node_idx: 2,
+                target_idx: 1,
+                step: "freeze_coupling",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_set_coupling_lag_flags() {
+        let mut net = make_test_network();
+        set_coupling_lag(&mut net, 2, 0, 1).unwrap();
+        assert_eq!(net.attributes.vectors[0].coupling_lag, vec![0.0, 1.0]);
+
+        set_coupling_lag(&mut net, 2, 0, 0).unwrap();
+        assert_eq!(net.attributes.vectors[0].coupling_lag, vec![0.0, 0.0]);
+
+        assert!(matches!(
+            set_coupling_lag(&mut net, 1, 0, 2),
+            Err(NetworkError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            set_coupling_lag(&mut net, 1, 2, 1),
+            Err(NetworkError::InvalidEdge {
+                node_idx: 2,
+                target_idx: 1,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_set_drift_coupling_flags() {
+        let mut net = make_test_network();
+        set_drift_coupling(&mut net, 2, 0, true).unwrap();
+        assert_eq!(net.attributes.vectors[0].coupling_drift, vec![0.0, 1.0]);
+        assert_eq!(net.attributes.states[0].autoconnection_strength, 1.0);
+
+        set_drift_coupling(&mut net, 2, 0, false).unwrap();
+        assert_eq!(net.attributes.vectors[0].coupling_drift, vec![0.0, 0.0]);
+
+        assert!(matches!(
+            set_drift_coupling(&mut net, 1, 2, true),
+            Err(NetworkError::InvalidEdge { .. })
+        ));
+    }
+
     #[test]
     fn test_set_coupling_vec_all_combinations() {
         let mut net = make_test_network();
@@ -202,4 +691,97 @@ mod tests {
         assert_eq!(net.attributes.vectors[0].value_coupling_parents[0], 0.3);
         assert_eq!(net.attributes.vectors[2].value_coupling_children, vec![1.0]);
     }
+
+    /// Input node 0 with a value parent (1); node 2 is the volatility parent of
+    /// both 0 and 1.
+    fn make_volatility_network() -> Network {
+        let mut net = Network::new("eHGF");
+        net.add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        net.add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        net.add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                volatility_children: Some(vec![0, 1].into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        net
+    }
+
+    #[test]
+    fn test_set_volatility_coupling_updates_both_sides() {
+        let mut net = make_volatility_network();
+        set_volatility_coupling(&mut net, 2, 1, 0.42).unwrap();
+
+        assert_eq!(
+            net.attributes.vectors[1].volatility_coupling_parents,
+            vec![0.42]
+        );
+        assert_eq!(
+            net.attributes.vectors[2].volatility_coupling_children,
+            vec![1.0, 0.42]
+        );
+        assert_eq!(
+            net.attributes.vectors[0].volatility_coupling_parents,
+            vec![1.0]
+        );
+        // The value coupling of 1 → 0 is untouched.
+        assert_eq!(net.attributes.vectors[0].value_coupling_parents, vec![1.0]);
+    }
+
+    #[test]
+    fn test_set_volatility_coupling_nonexistent_edge_errors() {
+        let mut net = make_volatility_network();
+        // 1 → 0 is a value edge, not a volatility edge.
+        assert_eq!(
+            set_volatility_coupling(&mut net, 1, 0, 0.5),
+            Err(SetCouplingError::NoSuchVolatilityEdge {
+                parent_idx: 1,
+                child_idx: 0
+            })
+        );
+        assert_eq!(
+            set_coupling(&mut net, 2, 0, 0.5),
+            Err(SetCouplingError::NoSuchEdge {
+                parent_idx: 2,
+                child_idx: 0
+            })
+        );
+        assert!(set_volatility_coupling(&mut net, 7, 0, 0.5).is_err());
+        assert_eq!(
+            net.attributes.vectors[2].volatility_coupling_children,
+            vec![1.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn test_set_volatility_coupling_short_vectors() {
+        let mut net = make_volatility_network();
+        net.attributes.vectors[2].volatility_coupling_children.pop();
+        assert_eq!(
+            set_volatility_coupling(&mut net, 2, 1, 0.5),
+            Err(SetCouplingError::VectorTooShort {
+                node_idx: 2,
+                attribute: "volatility_coupling_children",
+                position: 1,
+                len: 1
+            })
+        );
+        // Nothing was written on the child side either.
+        assert_eq!(
+            net.attributes.vectors[1].volatility_coupling_parents,
+            vec![1.0]
+        );
+    }
 }
@@ -1,5 +1,5 @@
 use crate::model::network::AdjacencyLists;
-use crate::utils::function_pointer::UpdateStep;
+use crate::utils::function_pointer::{StepKind, UpdateStep};
 
 pub struct LearningSequence {
     pub prediction_steps: Vec<(usize, UpdateStep)>,
@@ -8,11 +8,18 @@ pub struct LearningSequence {
 }
 
 /// Build a learning sequence from the network's standard update sequence.
+///
+/// Steps of the predictor nodes `inputs_x_idxs` are dropped, and every
+/// prediction-error step ([`StepKind::PredictionError`]) of a learnable node
+/// gets a matching [`UpdateStep::LearningWeights`] step. The continuous-state
+/// nodes of `drift_learners` then get an [`UpdateStep::LearningTonicDrift`]
+/// step, in the order of their posterior updates.
 pub fn build_learning_sequence(
     predictions: &[(usize, UpdateStep)],
     updates: &[(usize, UpdateStep)],
     inputs_x_idxs: &[usize],
     edges: &[AdjacencyLists],
+    drift_learners: &[usize],
 ) -> LearningSequence {
     let prediction_steps: Vec<(usize, UpdateStep)> = predictions
         .iter()
@@ -29,7 +36,7 @@ pub fn build_learning_sequence(
     let learning_steps: Vec<(usize, UpdateStep)> = update_steps
         .iter()
         .filter_map(|&(idx, step)| {
-            if step.name().contains("prediction_error") {
+            if step.kind() == StepKind::PredictionError {
                 let is_learnable = edges
                     .get(idx)
                     .map(|e| {
@@ -47,6 +54,14 @@ pub fn build_learning_sequence(
                 None
             }
         })
+        .chain(update_steps.iter().filter_map(|&(idx, step)| {
+            let learns_drift = step.kind() == StepKind::Posterior
+                && drift_learners.contains(&idx)
+                && edges
+                    .get(idx)
+                    .is_some_and(|e| e.node_type == "continuous-state");
+            learns_drift.then_some((idx, UpdateStep::LearningTonicDrift))
+        }))
         .collect();
 
     LearningSequence {
@@ -59,7 +74,7 @@ pub fn build_learning_sequence(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::network::Network;
+    use crate::model::network::{AddNodesOptions, Network};
 
     fn make_edges(idxs: &[usize]) -> Vec<AdjacencyLists> {
         let max_idx = idxs.iter().copied().max().unwrap_or(0);
@@ -76,6 +91,9 @@ mod tests {
                 value_children: None,
                 volatility_parents: None,
                 volatility_children: None,
+                precision_parents: None,
+                precision_children: None,
+                likelihood: String::from("gaussian"),
             });
         }
         edges
@@ -92,7 +110,7 @@ mod tests {
         let inputs_x = [1, 2];
         let edges = make_edges(&[0, 1, 2]);
 
-        let seq = build_learning_sequence(&predictions, &updates, &inputs_x, &edges);
+        let seq = build_learning_sequence(&predictions, &updates, &inputs_x, &edges, &[]);
         assert_eq!(seq.prediction_steps.len(), 1);
         assert_eq!(seq.prediction_steps[0].0, 0);
     }
@@ -108,7 +126,7 @@ mod tests {
         let inputs_x = [1];
         let edges = make_edges(&[0, 1]);
 
-        let seq = build_learning_sequence(&[], &updates, &inputs_x, &edges);
+        let seq = build_learning_sequence(&[], &updates, &inputs_x, &edges, &[]);
         let result_idxs: Vec<usize> = seq.update_steps.iter().map(|(idx, _)| *idx).collect();
         assert!(result_idxs.iter().all(|&idx| idx != 1));
         assert_eq!(seq.update_steps.len(), 2);
@@ -123,7 +141,7 @@ mod tests {
             (2, UpdateStep::PosteriorContinuous),
         ];
 
-        let seq = build_learning_sequence(&[], &updates, &[], &edges);
+        let seq = build_learning_sequence(&[], &updates, &[], &edges, &[]);
         assert_eq!(seq.update_steps.len(), 3);
         assert_eq!(seq.learning_steps.len(), 2);
 
@@ -132,6 +150,28 @@ mod tests {
         assert_eq!(seq.learning_steps[1].0, 1);
     }
 
+    #[test]
+    fn test_drift_learning_follows_the_posterior_steps() {
+        let edges = make_edges(&[0, 1, 2, 3]);
+        let updates = vec![
+            (0, UpdateStep::PredictionErrorContinuous),
+            (3, UpdateStep::PosteriorContinuous),
+            (1, UpdateStep::PredictionErrorContinuous),
+            (2, UpdateStep::PosteriorContinuous),
+        ];
+
+        let seq = build_learning_sequence(&[], &updates, &[], &edges, &[2, 3, 0]);
+        assert_eq!(
+            seq.learning_steps,
+            vec![
+                (0, UpdateStep::LearningWeights),
+                (1, UpdateStep::LearningWeights),
+                (3, UpdateStep::LearningTonicDrift),
+                (2, UpdateStep::LearningTonicDrift),
+            ]
+        );
+    }
+
     #[test]
     fn test_learning_steps_with_multiple_pe_groups() {
         let edges = make_edges(&[0, 1, 2, 3]);
@@ -142,7 +182,7 @@ mod tests {
             (3, UpdateStep::PosteriorContinuous),
         ];
 
-        let seq = build_learning_sequence(&[], &updates, &[], &edges);
+        let seq = build_learning_sequence(&[], &updates, &[], &edges, &[]);
         assert_eq!(seq.update_steps.len(), 4);
         assert_eq!(seq.learning_steps.len(), 2);
         assert_eq!(seq.learning_steps[0].0, 0);
@@ -154,16 +194,41 @@ mod tests {
         let edges = make_edges(&[0]);
         let updates = vec![(0, UpdateStep::PredictionErrorContinuous)];
 
-        let seq = build_learning_sequence(&[], &updates, &[], &edges);
+        let seq = build_learning_sequence(&[], &updates, &[], &edges, &[]);
         assert_eq!(seq.update_steps.len(), 1);
         assert_eq!(seq.learning_steps.len(), 1);
         assert_eq!(seq.learning_steps[0].0, 0);
     }
 
+    #[test]
+    fn test_learning_sequence_steps_are_named() {
+        let func_map = crate::utils::function_pointer::get_func_map();
+        let mut network = Network::new("eHGF");
+        network
+            .add_layer_stack(vec![1, 2], "volatile-state", None, 1.0, None, None, true)
+            .unwrap();
+        network.set_update_sequence();
+
+        let seq = network.learning_sequence(&network.leafs.clone());
+        assert!(!seq.learning_steps.is_empty());
+        for (_, step) in seq
+            .prediction_steps
+            .iter()
+            .chain(&seq.update_steps)
+            .chain(&seq.learning_steps)
+        {
+            assert!(func_map.values().any(|&name| name == step.name()));
+        }
+        assert!(seq
+            .learning_steps
+            .iter()
+            .all(|(_, step)| step.name() == "learning_weights"));
+    }
+
     #[test]
     fn test_empty_sequences() {
         let edges: Vec<AdjacencyLists> = Vec::new();
-        let seq = build_learning_sequence(&[], &[], &[], &edges);
+        let seq = build_learning_sequence(&[], &[], &[], &edges, &[]);
         assert!(seq.prediction_steps.is_empty());
         assert!(seq.update_steps.is_empty());
         assert!(seq.learning_steps.is_empty());
@@ -177,7 +242,7 @@ mod tests {
         ];
         let edges = make_edges(&[0, 1]);
 
-        let seq = build_learning_sequence(&[], &updates, &[], &edges);
+        let seq = build_learning_sequence(&[], &updates, &[], &edges, &[]);
         assert_eq!(seq.update_steps.len(), 2);
         assert!(seq.learning_steps.is_empty());
     }
@@ -195,7 +260,7 @@ mod tests {
         let inputs_x = [0, 1];
         let edges = make_edges(&[0, 1]);
 
-        let seq = build_learning_sequence(&predictions, &updates, &inputs_x, &edges);
+        let seq = build_learning_sequence(&predictions, &updates, &inputs_x, &edges, &[]);
         assert!(seq.prediction_steps.is_empty());
         assert!(seq.update_steps.is_empty());
         assert!(seq.learning_steps.is_empty());
@@ -210,7 +275,7 @@ mod tests {
             (0, UpdateStep::PredictionErrorBinary),
             (1, UpdateStep::PredictionErrorContinuous),
         ];
-        let seq = build_learning_sequence(&[], &updates, &[], &edges);
+        let seq = build_learning_sequence(&[], &updates, &[], &edges, &[]);
 
         // Both binary-state (0) and continuous-state (1) are now learnable.
         assert_eq!(seq.learning_steps.len(), 2);
@@ -221,7 +286,8 @@ mod tests {
     #[test]
     fn test_from_real_network_2layer() {
         let mut net = Network::new("eHGF");
-        net.add_nodes("continuous-state", 2, None, None, None, None, None, None);
+        net.add_nodes("continuous-state", 2, AddNodesOptions::default())
+            .unwrap();
         net.add_layer(
             2,
             "continuous-state",
@@ -230,7 +296,8 @@ mod tests {
             None,
             None,
             true,
-        );
+        )
+        .unwrap();
         net.set_update_sequence();
 
         let inputs_x = [2_usize, 3];
@@ -240,6 +307,7 @@ mod tests {
             &net.update_sequence.updates,
             &inputs_x,
             &net.edges,
+            &[],
         );
 
         for (idx, _) in &seq.prediction_steps {
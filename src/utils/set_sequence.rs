@@ -1,5 +1,5 @@
 use crate::model::network::{AdjacencyLists, Network, UpdateSequence};
-use crate::utils::function_pointer::UpdateStep;
+use crate::utils::function_pointer::{StepKind, UpdateStep};
 
 pub fn set_update_sequence(network: &Network) -> UpdateSequence {
     let predictions = get_predictions_sequence(network);
@@ -23,21 +23,13 @@ pub fn get_predictions_sequence(network: &Network) -> Vec<(usize, UpdateStep)> {
             let idx = nodes_idxs[i];
             let edge = &network.edges[idx];
 
-            let parents_idxs = match (&edge.value_parents, &edge.volatility_parents) {
-                (Some(ref vec1), Some(ref vec2)) => Some(
-                    vec1.iter()
-                        .chain(vec2.iter())
-                        .copied()
-                        .collect::<Vec<usize>>(),
-                ),
-                (Some(vec), None) | (None, Some(vec)) => Some(vec.clone()),
-                (None, None) => None,
-            };
-
-            let contains_common = match parents_idxs {
-                Some(vec) => vec.iter().any(|item| nodes_idxs.contains(item)),
-                None => false,
-            };
+            let contains_common = edge
+                .value_parents
+                .iter()
+                .chain(edge.volatility_parents.iter())
+                .chain(edge.precision_parents.iter())
+                .flatten()
+                .any(|item| nodes_idxs.contains(item));
 
             if !contains_common {
                 let mf = network.mean_field_updates;
@@ -59,6 +51,33 @@ pub fn get_predictions_sequence(network: &Network) -> Vec<(usize, UpdateStep)> {
                         },
                     )),
                     "binary-state" => predictions.push((idx, UpdateStep::PredictionBinary)),
+                    "softmax-state" => predictions.push((idx, UpdateStep::PredictionSoftmax)),
+                    "dp-state" => predictions.push((idx, UpdateStep::PredictionDp)),
+                    "bounded-state" => predictions.push((
+                        idx,
+                        if mf {
+                            UpdateStep::PredictionBoundedMeanField
+                        } else {
+                            UpdateStep::PredictionBounded
+                        },
+                    )),
+                    "lognormal-state" => predictions.push((
+                        idx,
+                        if mf {
+                            UpdateStep::PredictionLognormalMeanField
+                        } else {
+                            UpdateStep::PredictionLognormal
+                        },
+                    )),
+                    "circular-state" => predictions.push((
+                        idx,
+                        if mf {
+                            UpdateStep::PredictionCircularMeanField
+                        } else {
+                            UpdateStep::PredictionCircular
+                        },
+                    )),
+                    "aggregate-input" => predictions.push((idx, UpdateStep::PredictionAggregate)),
                     _ => (),
                 }
 
@@ -76,9 +95,20 @@ pub fn get_predictions_sequence(network: &Network) -> Vec<(usize, UpdateStep)> {
     predictions
 }
 
+/// Posterior updates and prediction errors of `network`, in alternating
+/// batches: the posterior updates of the nodes whose children have all
+/// computed their prediction errors, then the prediction errors of the nodes
+/// whose posterior is up to date.
+///
+/// Within a batch, nodes are always processed in ascending index order,
+/// whatever the order their edges were declared in. The order matters: the
+/// eHGF mean update reads the children's updated means, so the same network
+/// must always give the same sequence.
 pub fn get_updates_sequence(network: &Network) -> Vec<(usize, UpdateStep)> {
     let mut updates: Vec<(usize, UpdateStep)> = Vec::new();
 
+    // Both lists are kept in ascending order (`retain` preserves it), which
+    // fixes the order of the nodes within each batch.
     let mut pe_nodes_idxs: Vec<usize> = (0..network.edges.len()).collect();
     let mut po_nodes_idxs: Vec<usize> = (0..network.edges.len()).collect();
 
@@ -92,19 +122,42 @@ pub fn get_updates_sequence(network: &Network) -> Vec<(usize, UpdateStep)> {
         let eligible_po: Vec<usize> = po_nodes_idxs
             .iter()
             .copied()
-            .filter(|&idx| {
-                let children = get_all_children(&network.edges[idx]);
-                children.iter().all(|c| !pe_nodes_idxs.contains(c))
-            })
+            .filter(|&idx| all_children(&network.edges[idx]).all(|c| !pe_nodes_idxs.contains(c)))
             .collect();
 
         let mf = network.mean_field_updates;
         for &idx in &eligible_po {
             let edge = &network.edges[idx];
+            let clusters_dp_node = edge
+                .value_children
+                .iter()
+                .flatten()
+                .any(|&child| network.edges[child].node_type == "dp-state");
             match edge.node_type.as_str() {
+                // A cluster of a dp-state node learns from its responsibilities.
+                "continuous-state" if clusters_dp_node => {
+                    updates.push((idx, UpdateStep::PosteriorDpCluster));
+                }
                 "continuous-state" => {
-                    if edge.volatility_children.is_some() {
-                        match network.volatility_updates.as_str() {
+                    if edge.precision_children.is_some() {
+                        updates.push((idx, UpdateStep::PosteriorPrecisionParent));
+                    } else if let Some(volatility_children) = &edge.volatility_children {
+                        // The unbounded approximation is derived for a single
+                        // linear volatility child; other nodes fall back to eHGF.
+                        let nonlinear = network.attributes.fn_ptrs[idx]
+                            .volatility_coupling_fn
+                            .is_some();
+                        // Circular children pass on wrapped prediction errors,
+                        // which only the standard update reads as they are.
+                        let circular_child = volatility_children
+                            .iter()
+                            .any(|&child| network.edges[child].node_type == "circular-state");
+                        let volatility_updates = match network.volatility_updates.as_str() {
+                            _ if circular_child => "standard",
+                            "unbounded" if volatility_children.len() != 1 || nonlinear => "eHGF",
+                            volatility_updates => volatility_updates,
+                        };
+                        match volatility_updates {
                             "eHGF" => updates.push((
                                 idx,
                                 if mf {
@@ -146,6 +199,16 @@ pub fn get_updates_sequence(network: &Network) -> Vec<(usize, UpdateStep)> {
                         },
                     ));
                 }
+                "circular-state" => {
+                    updates.push((
+                        idx,
+                        if mf {
+                            UpdateStep::PosteriorCircularMeanField
+                        } else {
+                            UpdateStep::PosteriorCircular
+                        },
+                    ));
+                }
                 _ => (),
             }
             has_update = true;
@@ -161,10 +224,15 @@ pub fn get_updates_sequence(network: &Network) -> Vec<(usize, UpdateStep)> {
 
         for &idx in &eligible_pe {
             let edge = &network.edges[idx];
-            let has_parents = edge.value_parents.is_some() || edge.volatility_parents.is_some();
+            let has_parents = edge.value_parents.is_some()
+                || edge.volatility_parents.is_some()
+                || edge.precision_parents.is_some();
 
             match (edge.node_type.as_str(), has_parents) {
-                ("continuous-state", true) => {
+                (
+                    "continuous-state" | "bounded-state" | "lognormal-state" | "aggregate-input",
+                    true,
+                ) => {
                     updates.push((idx, UpdateStep::PredictionErrorContinuous));
                     has_update = true;
                 }
@@ -178,6 +246,10 @@ pub fn get_updates_sequence(network: &Network) -> Vec<(usize, UpdateStep)> {
                     }
                     has_update = true;
                 }
+                ("circular-state", true) => {
+                    updates.push((idx, UpdateStep::PredictionErrorCircular));
+                    has_update = true;
+                }
                 ("ef-state", _) => {
                     updates.push((idx, UpdateStep::PredictionErrorExponential));
                     has_update = true;
@@ -186,6 +258,18 @@ pub fn get_updates_sequence(network: &Network) -> Vec<(usize, UpdateStep)> {
                     updates.push((idx, UpdateStep::PredictionErrorBinary));
                     has_update = true;
                 }
+                ("binary-input", true) => {
+                    updates.push((idx, UpdateStep::PredictionErrorBinaryInput));
+                    has_update = true;
+                }
+                ("softmax-state", true) => {
+                    updates.push((idx, UpdateStep::PredictionErrorSoftmax));
+                    has_update = true;
+                }
+                ("dp-state", _) => {
+                    updates.push((idx, UpdateStep::PredictionErrorDp));
+                    has_update = true;
+                }
                 _ => (),
             }
         }
@@ -201,52 +285,110 @@ pub fn get_updates_sequence(network: &Network) -> Vec<(usize, UpdateStep)> {
     updates
 }
 
-fn get_all_children(adj: &AdjacencyLists) -> Vec<usize> {
-    match (&adj.value_children, &adj.volatility_children) {
-        (Some(v), Some(vol)) => v.iter().chain(vol.iter()).copied().collect(),
-        (Some(v), None) => v.clone(),
-        (None, Some(vol)) => vol.clone(),
-        (None, None) => vec![],
-    }
+/// A step of the update sequence together with the nodes that gated it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SequenceStep {
+    pub node_idx: usize,
+    pub step: UpdateStep,
+    /// Nodes whose steps had to run first: the parents for a prediction, the
+    /// children for a posterior update, and the node itself for a prediction
+    /// error that follows its own posterior update.
+    pub dependencies: Vec<usize>,
+}
+
+/// Annotate the network's current update sequence (predictions, then updates)
+/// with the dependencies that fixed its order.
+pub fn get_update_sequence_graph(network: &Network) -> Vec<SequenceStep> {
+    let sequence = &network.update_sequence;
+    sequence
+        .predictions
+        .iter()
+        .chain(sequence.updates.iter())
+        .enumerate()
+        .map(|(position, &(node_idx, step))| {
+            let edge = &network.edges[node_idx];
+            let mut dependencies: Vec<usize> = match step.kind() {
+                StepKind::Prediction => edge
+                    .value_parents
+                    .iter()
+                    .chain(edge.volatility_parents.iter())
+                    .chain(edge.precision_parents.iter())
+                    .flatten()
+                    .copied()
+                    .collect(),
+                StepKind::Posterior => all_children(edge).copied().collect(),
+                StepKind::PredictionError | StepKind::Learning => {
+                    // Only the updates that ran before this step count; a
+                    // step of this kind among the predictions has none.
+                    let n_predictions = sequence.predictions.len();
+                    let before = position.saturating_sub(n_predictions);
+                    let own_posterior = sequence.updates[..before]
+                        .iter()
+                        .any(|&(idx, s)| idx == node_idx && s.kind() == StepKind::Posterior);
+                    if own_posterior {
+                        vec![node_idx]
+                    } else {
+                        vec![]
+                    }
+                }
+            };
+            // An edge declared from both ends can appear twice in the lists.
+            let mut seen = Vec::with_capacity(dependencies.len());
+            dependencies.retain(|&idx| {
+                let first = !seen.contains(&idx);
+                seen.push(idx);
+                first
+            });
+            SequenceStep {
+                node_idx,
+                step,
+                dependencies,
+            }
+        })
+        .collect()
+}
+
+/// Value, volatility then precision children of a node, borrowed from its
+/// adjacency lists.
+fn all_children(adj: &AdjacencyLists) -> impl Iterator<Item = &usize> {
+    adj.value_children
+        .iter()
+        .chain(adj.volatility_children.iter())
+        .chain(adj.precision_children.iter())
+        .flatten()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::network::AddNodesOptions;
 
     #[test]
     fn test_get_update_order() {
         let mut hgf_network = Network::new("eHGF");
-        hgf_network.add_nodes(
-            "continuous-state",
-            1,
-            Some(vec![1].into()),
-            None,
-            Some(vec![2].into()),
-            None,
-            None,
-            None,
-        );
-        hgf_network.add_nodes(
-            "continuous-state",
-            1,
-            None,
-            Some(vec![0].into()),
-            None,
-            None,
-            None,
-            None,
-        );
-        hgf_network.add_nodes(
-            "continuous-state",
-            1,
-            None,
-            None,
-            None,
-            Some(vec![0].into()),
-            None,
-            None,
-        );
+        hgf_network
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        hgf_network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(vec![0].into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        hgf_network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    volatility_children: Some(vec![0].into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
         hgf_network.set_update_sequence();
 
         println!("Prediction sequence ----------");
@@ -258,8 +400,40 @@ mod tests {
             println!("Node: {} - Function name: {}", node, step.name());
         }
 
+        let graph = get_update_sequence_graph(&hgf_network);
+        let summary: Vec<(usize, StepKind, Vec<usize>)> = graph
+            .iter()
+            .map(|s| (s.node_idx, s.step.kind(), s.dependencies.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, StepKind::Prediction, vec![]),
+                (2, StepKind::Prediction, vec![]),
+                (0, StepKind::Prediction, vec![1, 2]),
+                (0, StepKind::PredictionError, vec![]),
+                (1, StepKind::Posterior, vec![0]),
+                (2, StepKind::Posterior, vec![0]),
+            ]
+        );
+
+        // A prediction-error step among the predictions has no update before
+        // it.
+        hgf_network.update_sequence.predictions.push((
+            0,
+            UpdateStep::Python {
+                slot: 0,
+                kind: StepKind::PredictionError,
+            },
+        ));
+        let graph = get_update_sequence_graph(&hgf_network);
+        assert_eq!(graph[3].step.kind(), StepKind::PredictionError);
+        assert!(graph[3].dependencies.is_empty());
+
         let mut exp_network = Network::new("eHGF");
-        exp_network.add_nodes("ef-state", 1, None, None, None, None, None, None);
+        exp_network
+            .add_nodes("ef-state", 1, AddNodesOptions::default())
+            .unwrap();
         exp_network.set_update_sequence();
         println!(
             "Node: {} - Function name: {}",
@@ -267,4 +441,98 @@ mod tests {
             exp_network.update_sequence.updates[0].1.name()
         );
     }
+
+    /// Two inputs (0, 1) sharing a value parent (2), itself under a volatility
+    /// parent (3). The parent declares its children in descending order.
+    fn diamond_network() -> Network {
+        let mut network = Network::new("eHGF");
+        network
+            .add_nodes("continuous-state", 2, AddNodesOptions::default())
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(vec![1, 0].into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    volatility_children: Some(2.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+    }
+
+    #[test]
+    fn test_update_order_is_deterministic() {
+        let sequence = get_updates_sequence(&diamond_network());
+        for _ in 0..10 {
+            assert_eq!(get_updates_sequence(&diamond_network()), sequence);
+        }
+        assert_eq!(
+            sequence,
+            vec![
+                (0, UpdateStep::PredictionErrorContinuous),
+                (1, UpdateStep::PredictionErrorContinuous),
+                (2, UpdateStep::PosteriorContinuous),
+                (2, UpdateStep::PredictionErrorContinuous),
+                (3, UpdateStep::PosteriorContinuousEhgf),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_describe_updates() {
+        // Input (0) with a value parent (1) and a volatility parent (2).
+        let mut network = Network::new("eHGF");
+        network
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(vec![0].into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    volatility_children: Some(vec![0].into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        assert_eq!(
+            network.describe_updates(),
+            vec![
+                (0, "prediction_error_continuous_state_node".to_string()),
+                (1, "posterior_update_continuous_state_node".to_string()),
+                (2, "posterior_update_continuous_state_node_ehgf".to_string()),
+            ]
+        );
+        // No side effect on the cached sequence.
+        assert!(network.update_sequence.updates.is_empty());
+
+        network.volatility_updates = "standard".to_string();
+        assert_eq!(
+            network.describe_updates()[2].1,
+            "posterior_update_continuous_state_node"
+        );
+    }
 }
@@ -0,0 +1,568 @@
+//! Networks described by a JSON spec, for models defined in configuration
+//! files rather than in code.
+//!
+//! ```json
+//! {
+//!   "update_type": "eHGF",
+//!   "nodes": [
+//!     {"kind": "continuous-state"},
+//!     {"kind": "continuous-state", "parameters": {"tonic_volatility": -3.0}},
+//!     {"kind": "continuous-state", "coupling_fn": "sigmoid"}
+//!   ],
+//!   "edges": [
+//!     {"parent": 1, "child": 0, "coupling": 0.5},
+//!     {"parent": 2, "child": 1, "kind": "volatility"}
+//!   ]
+//! }
+//! ```
+//!
+//! Every field but `nodes` can be left out. Nodes take the `add_nodes`
+//! defaults for the parameters they do not set, edges a coupling of 1.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::NetworkError;
+use crate::math::{coupling_fn_name, parse_coupling_fn};
+use crate::model::network::{
+    add_nodes_parameters, is_ancestor, parameters_for_type, AddNodesOptions, EntropySource, IntOrList,
+    NegativePrecision, Network, NODE_KINDS, UPDATE_TYPES,
+};
+
+/// A whole network: settings, nodes and edges.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkSpec {
+    /// `"eHGF"`, `"standard"` or `"unbounded"` (the default).
+    #[serde(default = "default_update_type")]
+    pub update_type: String,
+    #[serde(default)]
+    pub mean_field_updates: bool,
+    #[serde(default = "default_max_posterior_precision")]
+    pub max_posterior_precision: f64,
+    #[serde(default = "default_precision_clipping_value")]
+    pub precision_clipping_value: f64,
+    /// `"floor"` (the default), `"error"` or `"reject"`.
+    #[serde(default = "default_negative_precision")]
+    pub negative_precision: String,
+    /// Precision the recorded `entropy` is computed from (see
+    /// [`Network::set_entropy_source`]; `"posterior"` when absent).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entropy_source: Option<String>,
+    /// The nodes, by index.
+    pub nodes: Vec<NodeSpec>,
+    #[serde(default)]
+    pub edges: Vec<EdgeSpec>,
+}
+
+/// One node: its kind, the initial parameters overriding the kind defaults,
+/// the coupling functions applied towards its children and its metadata.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NodeSpec {
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub parameters: BTreeMap<String, f64>,
+    /// Coupling function towards the value children (linear when absent).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coupling_fn: Option<String>,
+    /// Coupling function towards the volatility children (linear when
+    /// absent).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub volatility_coupling_fn: Option<String>,
+    /// Observation model of a continuous input (see
+    /// [`Network::set_likelihood`]; Gaussian when absent).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub likelihood: Option<String>,
+    /// Precision of each observation stream of an aggregate-input node (see
+    /// [`Network::set_input_precision`]; one stream of the node's
+    /// `input_precision` parameter when absent).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub input_precision: Vec<f64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// The kinds of edges a spec can declare.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EdgeKind {
+    #[default]
+    Value,
+    Volatility,
+    /// See [`Network::add_precision_parent`]; the coupling is not used.
+    Precision,
+}
+
+/// One edge from `parent` to `child`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EdgeSpec {
+    pub parent: usize,
+    pub child: usize,
+    #[serde(default)]
+    pub kind: EdgeKind,
+    #[serde(default = "default_coupling")]
+    pub coupling: f64,
+    /// Hold the value coupling fixed during learning (see
+    /// [`Network::freeze_coupling`]).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub frozen: bool,
+    /// Delay of a value edge in steps, 0 or 1 (see
+    /// [`Network::set_coupling_lag`]).
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub lag: usize,
+    /// Make the parent a drift parent of the child (see
+    /// [`Network::set_drift_coupling`]).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub drift: bool,
+    /// Coupling function on the child side of a value edge (see
+    /// [`Network::set_child_coupling_fn`]; linear when absent).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub child_coupling_fn: Option<String>,
+}
+
+fn default_update_type() -> String {
+    String::from("unbounded")
+}
+
+fn default_max_posterior_precision() -> f64 {
+    1e10
+}
+
+fn default_precision_clipping_value() -> f64 {
+    1e-6
+}
+
+fn default_negative_precision() -> String {
+    String::from("floor")
+}
+
+fn default_coupling() -> f64 {
+    1.0
+}
+
+fn is_zero(lag: &usize) -> bool {
+    *lag == 0
+}
+
+impl NetworkSpec {
+    /// Every problem of the spec, one line each; empty for a valid spec.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !UPDATE_TYPES.contains(&self.update_type.as_str()) {
+            problems.push(format!(
+                "update_type: unknown update type '{}' (expected one of {})",
+                self.update_type,
+                UPDATE_TYPES.join(", ")
+            ));
+        }
+        if let Err(err) = NegativePrecision::parse(&self.negative_precision) {
+            problems.push(format!("negative_precision: {}", err));
+        }
+        if let Some(Err(err)) = self.entropy_source.as_deref().map(EntropySource::parse) {
+            problems.push(format!("entropy_source: {}", err));
+        }
+
+        for (node_idx, node) in self.nodes.iter().enumerate() {
+            if !NODE_KINDS.contains(&node.kind.as_str()) {
+                problems.push(format!(
+                    "nodes[{}]: unknown kind '{}' (expected one of {})",
+                    node_idx,
+                    node.kind,
+                    NODE_KINDS.join(", ")
+                ));
+                continue;
+            }
+            let accepted = add_nodes_parameters(&node.kind);
+            for key in node.parameters.keys() {
+                if !accepted.contains(&key.as_str()) {
+                    problems.push(format!(
+                        "nodes[{}]: '{}' nodes have no parameter '{}'",
+                        node_idx, node.kind, key
+                    ));
+                }
+            }
+            for (field, name) in [
+                ("coupling_fn", &node.coupling_fn),
+                ("volatility_coupling_fn", &node.volatility_coupling_fn),
+            ] {
+                if let Some(Err(err)) = name.as_deref().map(parse_coupling_fn) {
+                    problems.push(format!("nodes[{}].{}: {}", node_idx, field, err));
+                }
+            }
+            if let Some(likelihood) = &node.likelihood {
+                if !["gaussian", "laplace"].contains(&likelihood.as_str()) {
+                    problems.push(format!(
+                        "nodes[{}].likelihood: unknown likelihood '{}' (expected gaussian, laplace)",
+                        node_idx, likelihood
+                    ));
+                }
+            }
+            if !node.input_precision.is_empty() {
+                if node.kind != "aggregate-input" {
+                    problems.push(format!(
+                        "nodes[{}].input_precision: only aggregate-input nodes have per-stream precisions",
+                        node_idx
+                    ));
+                } else if node
+                    .input_precision
+                    .iter()
+                    .any(|&p| !(p.is_finite() && p > 0.0))
+                {
+                    problems.push(format!(
+                        "nodes[{}].input_precision: stream precisions must be finite and positive",
+                        node_idx
+                    ));
+                }
+            }
+        }
+
+        let n_nodes = self.nodes.len();
+        // Parents of every node through the edges checked so far, to reject
+        // the edges that close a cycle, as `Network::merge` does.
+        let mut parents = vec![Vec::new(); n_nodes];
+        for (edge_idx, edge) in self.edges.iter().enumerate() {
+            let mut exists = true;
+            for (end, node_idx) in [("parent", edge.parent), ("child", edge.child)] {
+                if node_idx >= n_nodes {
+                    exists = false;
+                    problems.push(format!(
+                        "edges[{}]: {} {} does not exist ({} nodes)",
+                        edge_idx, end, node_idx, n_nodes
+                    ));
+                }
+            }
+            if edge.parent == edge.child {
+                problems.push(format!(
+                    "edges[{}]: node {} cannot be its own parent",
+                    edge_idx, edge.parent
+                ));
+            } else if exists {
+                if is_ancestor(&parents, edge.child, edge.parent) {
+                    problems.push(format!(
+                        "edges[{}]: {} -> {} closes a cycle",
+                        edge_idx, edge.parent, edge.child
+                    ));
+                } else {
+                    parents[edge.child].push(edge.parent);
+                }
+            }
+            if let Some(first) = self.edges[..edge_idx]
+                .iter()
+                .position(|other| (other.parent, other.child) == (edge.parent, edge.child))
+            {
+                problems.push(format!(
+                    "edges[{}]: duplicate of edges[{}] ({} -> {})",
+                    edge_idx, first, edge.parent, edge.child
+                ));
+            }
+            if edge.frozen && edge.kind != EdgeKind::Value {
+                problems.push(format!(
+                    "edges[{}]: only value couplings can be frozen",
+                    edge_idx
+                ));
+            }
+            if edge.lag > 1 {
+                problems.push(format!(
+                    "edges[{}]: only one-step lags are supported, got {}",
+                    edge_idx, edge.lag
+                ));
+            } else if edge.lag == 1 && edge.kind != EdgeKind::Value {
+                problems.push(format!(
+                    "edges[{}]: only value couplings can be lagged",
+                    edge_idx
+                ));
+            }
+            if edge.drift && edge.kind != EdgeKind::Value {
+                problems.push(format!(
+                    "edges[{}]: only value edges can be drift edges",
+                    edge_idx
+                ));
+            }
+            if let Some(name) = &edge.child_coupling_fn {
+                if edge.kind != EdgeKind::Value {
+                    problems.push(format!(
+                        "edges[{}]: only value edges take a child_coupling_fn",
+                        edge_idx
+                    ));
+                } else if let Err(err) = parse_coupling_fn(name) {
+                    problems.push(format!("edges[{}].child_coupling_fn: {}", edge_idx, err));
+                }
+            }
+        }
+        problems
+    }
+
+    /// Indices of the nodes `node_idx` is linked to by `kind` edges, as a
+    /// parent (`as_parent`) or as a child, restricted to the nodes before it.
+    fn earlier(&self, node_idx: usize, kind: EdgeKind, as_parent: bool) -> Option<IntOrList> {
+        let idxs: Vec<usize> = self
+            .edges
+            .iter()
+            .filter(|edge| edge.kind == kind)
+            .filter_map(|edge| match as_parent {
+                true if edge.parent == node_idx => Some(edge.child),
+                false if edge.child == node_idx => Some(edge.parent),
+                _ => None,
+            })
+            .filter(|&idx| idx < node_idx)
+            .collect();
+        (!idxs.is_empty()).then_some(IntOrList::List(idxs))
+    }
+
+    /// Build the network, after [`NetworkSpec::validate`].
+    ///
+    /// Nodes are added in index order, each declaring its value and volatility
+    /// edges to the nodes added before it, then the couplings, precision
+    /// edges, frozen flags, lags, drift flags and child-side coupling
+    /// functions are set edge by edge, and last the likelihoods
+    /// of the inputs.
+    pub fn build(&self) -> Result<Network, NetworkError> {
+        let problems = self.validate();
+        if !problems.is_empty() {
+            return Err(NetworkError::InvalidSpec { problems });
+        }
+
+        let mut network = Network::new(&self.update_type);
+        network.mean_field_updates = self.mean_field_updates;
+        network.max_posterior_precision = self.max_posterior_precision;
+        network.precision_clipping_value = self.precision_clipping_value;
+        network.negative_precision =
+            NegativePrecision::parse(&self.negative_precision).expect("validated above");
+        if let Some(source) = &self.entropy_source {
+            network.entropy_source = EntropySource::parse(source).expect("validated above");
+        }
+
+        for (node_idx, node) in self.nodes.iter().enumerate() {
+            let mut parameters = node.parameters.clone();
+            // A node whose children all come after it starts as an input;
+            // give it the defaults of a node with children instead.
+            let has_children = self
+                .edges
+                .iter()
+                .any(|edge| edge.parent == node_idx && edge.kind != EdgeKind::Precision);
+            let has_earlier_children = self.edges.iter().any(|edge| {
+                edge.parent == node_idx && edge.child < node_idx && edge.kind != EdgeKind::Precision
+            });
+            if node.kind == "continuous-state" && has_children && !has_earlier_children {
+                parameters
+                    .entry("autoconnection_strength".to_string())
+                    .or_insert(1.0);
+                parameters
+                    .entry("tonic_volatility".to_string())
+                    .or_insert(-4.0);
+            }
+            network.add_nodes(
+                &node.kind,
+                1,
+                AddNodesOptions {
+                    value_parents: self.earlier(node_idx, EdgeKind::Value, false),
+                    value_children: self.earlier(node_idx, EdgeKind::Value, true),
+                    volatility_parents: self.earlier(node_idx, EdgeKind::Volatility, false),
+                    volatility_children: self.earlier(node_idx, EdgeKind::Volatility, true),
+                    coupling_fn: node.coupling_fn.clone(),
+                    additional_parameters: Some(parameters.into_iter().collect()),
+                    ..Default::default()
+                },
+            )?;
+            if let Some(name) = &node.volatility_coupling_fn {
+                network
+                    .set_volatility_coupling_fn(node_idx, name)
+                    .map_err(|err| NetworkError::InvalidSpec {
+                        problems: vec![format!(
+                            "nodes[{}].volatility_coupling_fn: {}",
+                            node_idx, err
+                        )],
+                    })?;
+            }
+            if !node.input_precision.is_empty() {
+                network
+                    .set_input_precision(node_idx, node.input_precision.clone())
+                    .map_err(|err| NetworkError::InvalidSpec {
+                        problems: vec![format!("nodes[{}].input_precision: {}", node_idx, err)],
+                    })?;
+            }
+            for (key, value) in &node.metadata {
+                network.set_metadata(node_idx, key, value)?;
+            }
+        }
+
+        for (edge_idx, edge) in self.edges.iter().enumerate() {
+            let invalid = |err: String| NetworkError::InvalidSpec {
+                problems: vec![format!("edges[{}]: {}", edge_idx, err)],
+            };
+            match edge.kind {
+                EdgeKind::Value => {
+                    network
+                        .set_coupling(edge.parent, edge.child, edge.coupling)
+                        .map_err(|err| invalid(err.to_string()))?;
+                    if edge.frozen {
+                        network
+                            .freeze_coupling(edge.parent, edge.child)
+                            .map_err(|err| invalid(err.to_string()))?;
+                    }
+                    if edge.lag > 0 {
+                        network
+                            .set_coupling_lag(edge.parent, edge.child, edge.lag)
+                            .map_err(|err| invalid(err.to_string()))?;
+                    }
+                    if edge.drift {
+                        network
+                            .set_drift_coupling(edge.parent, edge.child, true)
+                            .map_err(|err| invalid(err.to_string()))?;
+                    }
+                    if let Some(name) = &edge.child_coupling_fn {
+                        network
+                            .set_child_coupling_fn(edge.parent, edge.child, name)
+                            .map_err(|err| invalid(err.to_string()))?;
+                    }
+                }
+                EdgeKind::Volatility => network
+                    .set_volatility_coupling(edge.parent, edge.child, edge.coupling)
+                    .map_err(|err| invalid(err.to_string()))?,
+                EdgeKind::Precision => network.add_precision_parent(edge.parent, edge.child)?,
+            }
+        }
+        network.recompute_roles();
+        for (node_idx, node) in self.nodes.iter().enumerate() {
+            if let Some(likelihood) = &node.likelihood {
+                network
+                    .set_likelihood(node_idx, likelihood)
+                    .map_err(|err| NetworkError::InvalidSpec {
+                        problems: vec![format!("nodes[{}].likelihood: {}", node_idx, err)],
+                    })?;
+            }
+        }
+        network.set_update_sequence();
+        Ok(network)
+    }
+
+    /// Describe `network`: its settings, the finite parameters, coupling
+    /// functions, likelihood, stream precisions and metadata of each node (see
+    /// [`parameters_for_type`]), and one edge per value, volatility and
+    /// precision link with its coupling and options.
+    ///
+    /// [`NetworkSpec::build`] rebuilds a network equal to `network` up to
+    /// float rounding (see [`Network::approx_eq`]), trajectories aside.
+    pub fn from_network(network: &Network) -> Self {
+        let nodes = network
+            .edges
+            .iter()
+            .enumerate()
+            .map(|(node_idx, edges)| {
+                let state = &network.attributes.states[node_idx];
+                let fn_ptrs = &network.attributes.fn_ptrs[node_idx];
+                NodeSpec {
+                    kind: edges.node_type.clone(),
+                    parameters: parameters_for_type(&edges.node_type)
+                        .iter()
+                        .filter_map(|&key| Some((key.to_string(), state.get(key)?)))
+                        // JSON has no infinities: an unbounded `max_precision`
+                        // is left to its default.
+                        .filter(|(_, value)| value.is_finite())
+                        .collect(),
+                    coupling_fn: fn_ptrs
+                        .coupling_fn
+                        .map(|cf| coupling_fn_name(cf).to_string()),
+                    volatility_coupling_fn: fn_ptrs
+                        .volatility_coupling_fn
+                        .map(|cf| coupling_fn_name(cf).to_string()),
+                    likelihood: (edges.likelihood != "gaussian").then(|| edges.likelihood.clone()),
+                    input_precision: network.attributes.vectors[node_idx].input_precision.clone(),
+                    metadata: network
+                        .metadata
+                        .get(&node_idx)
+                        .map(|labels| labels.clone().into_iter().collect())
+                        .unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        let mut edges = Vec::new();
+        for (parent, adjacency) in network.edges.iter().enumerate() {
+            let vectors = &network.attributes.vectors[parent];
+            for (kind, children, couplings) in [
+                (
+                    EdgeKind::Value,
+                    &adjacency.value_children,
+                    &vectors.value_coupling_children,
+                ),
+                (
+                    EdgeKind::Volatility,
+                    &adjacency.volatility_children,
+                    &vectors.volatility_coupling_children,
+                ),
+                (
+                    EdgeKind::Precision,
+                    &adjacency.precision_children,
+                    &Vec::new(),
+                ),
+            ] {
+                for (position, &child) in children.iter().flatten().enumerate() {
+                    // Value-edge flags live on the child, by value parent.
+                    let parent_pos = network.edges[child]
+                        .value_parents
+                        .iter()
+                        .flatten()
+                        .position(|&p| p == parent)
+                        .filter(|_| kind == EdgeKind::Value);
+                    let child_flag = |flags: &[f64]| {
+                        parent_pos
+                            .and_then(|pos| flags.get(pos))
+                            .is_some_and(|&flag| flag == 1.0)
+                    };
+                    let child_vectors = &network.attributes.vectors[child];
+                    let child_coupling_fn = match kind {
+                        EdgeKind::Value => network.attributes.fn_ptrs[parent]
+                            .value_coupling_fn_children
+                            .get(position)
+                            .copied()
+                            .flatten()
+                            .map(|cf| coupling_fn_name(cf).to_string()),
+                        _ => None,
+                    };
+                    edges.push(EdgeSpec {
+                        parent,
+                        child,
+                        kind,
+                        coupling: couplings.get(position).copied().unwrap_or(1.0),
+                        frozen: child_flag(&child_vectors.coupling_frozen),
+                        lag: usize::from(child_flag(&child_vectors.coupling_lag)),
+                        drift: child_flag(&child_vectors.coupling_drift),
+                        child_coupling_fn,
+                    });
+                }
+            }
+        }
+
+        NetworkSpec {
+            update_type: network.volatility_updates.clone(),
+            mean_field_updates: network.mean_field_updates,
+            max_posterior_precision: network.max_posterior_precision,
+            precision_clipping_value: network.precision_clipping_value,
+            negative_precision: network.negative_precision.name().to_string(),
+            entropy_source: (network.entropy_source != EntropySource::Posterior)
+                .then(|| network.entropy_source.name().to_string()),
+            nodes,
+            edges,
+        }
+    }
+}
+
+/// Build a network from a JSON spec. Syntax errors report their line and
+/// column; every other problem is listed in [`NetworkError::InvalidSpec`].
+pub fn from_spec_str(json: &str) -> Result<Network, NetworkError> {
+    let spec: NetworkSpec =
+        serde_json::from_str(json).map_err(|err| NetworkError::InvalidSpec {
+            problems: vec![err.to_string()],
+        })?;
+    spec.build()
+}
+
+/// The JSON spec of `network` (see [`NetworkSpec::from_network`]).
+pub fn to_spec_str(network: &Network) -> String {
+    serde_json::to_string_pretty(&NetworkSpec::from_network(network))
+        .expect("a network spec is always serializable")
+}
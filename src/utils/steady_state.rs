@@ -0,0 +1,76 @@
+//! Analytic steady state of the precision recursion of continuous nodes, to
+//! see where a node's precision settles under a stationary volatility without
+//! running a simulation.
+
+use crate::error::NetworkError;
+use crate::math::coupling_at;
+use crate::model::network::Network;
+
+/// Precision the posterior of `node_idx` converges to when its volatility
+/// and its children's precisions stay as they currently are.
+///
+/// With `Ω = exp(ω + Σ_j κ_j g(μ_j) + Σ_j (κ_j g'(μ_j))² / (2 π̂_j))` the
+/// volatility of a unit time step (over the current means of the volatility
+/// parents, as in the prediction) and `P = Σ_c (κ_c g'(μ))² π̃_c` the
+/// precision the value children add at each update (from their current
+/// `expected_precision`), the recursion `π̂ = 1 / (1/π + Ω)`, `π = π̂ + P`
+/// has the positive fixed point
+///
+/// ```text
+/// π* = P/2 + sqrt(P²/4 + P/Ω)
+/// ```
+///
+/// (the root of `Ω π² − P Ω π − P = 0`), and the prediction settles at
+/// `π* − P`. A node without value children loses precision every step and
+/// converges to 0. The contributions of volatility children depend on their
+/// prediction errors and are left out. Input nodes, whose precision is that
+/// of their observations, return their `expected_precision`.
+pub fn steady_state_precision(network: &Network, node_idx: usize) -> Result<f64, NetworkError> {
+    let step = "steady_state_precision";
+    let edges = network
+        .edges
+        .get(node_idx)
+        .ok_or(NetworkError::MissingNode { node_idx, step })?;
+    if edges.node_type != "continuous-state" {
+        return Err(NetworkError::UnsupportedStructure {
+            node_idx,
+            step,
+            reason: "the steady-state precision is defined for continuous-state nodes",
+        });
+    }
+    let state = &network.attributes.states[node_idx];
+    if network.is_input(node_idx) {
+        return Ok(state.expected_precision);
+    }
+
+    let mut log_volatility = state.tonic_volatility;
+    if let Some(ref vol_parent_idxs) = edges.volatility_parents {
+        let couplings = &network.attributes.vectors[node_idx].volatility_coupling_parents;
+        for (i, &parent_idx) in vol_parent_idxs.iter().enumerate() {
+            let parent = &network.attributes.states[parent_idx];
+            let kappa = couplings.get(i).copied().unwrap_or(1.0);
+            let (g, g_prime, _) = coupling_at(
+                network.attributes.fn_ptrs[parent_idx].volatility_coupling_fn,
+                parent.mean,
+            );
+            log_volatility +=
+                kappa * g + (kappa * g_prime).powi(2) / (2.0 * parent.expected_precision);
+        }
+    }
+    let volatility = log_volatility.exp();
+
+    let mut children_precision = 0.0;
+    if let Some(ref child_idxs) = edges.value_children {
+        let couplings = &network.attributes.vectors[node_idx].value_coupling_children;
+        let (_, g_prime, _) =
+            coupling_at(network.attributes.fn_ptrs[node_idx].coupling_fn, state.mean);
+        for (i, &child_idx) in child_idxs.iter().enumerate() {
+            let kappa = couplings.get(i).copied().unwrap_or(1.0);
+            children_precision +=
+                (kappa * g_prime).powi(2) * network.attributes.states[child_idx].expected_precision;
+        }
+    }
+
+    let half = children_precision / 2.0;
+    Ok(half + (half * half + children_precision / volatility).sqrt())
+}
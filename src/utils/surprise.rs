@@ -0,0 +1,213 @@
+//! Per-node surprise, to locate the part of a hierarchy that fails to
+//! predict the data.
+
+use crate::error::NetworkError;
+use crate::math::{bounded_log_jacobian, gaussian_log_density, laplace_log_density, wrap_angle};
+use crate::model::network::{AdjacencyLists, Network, NodeState};
+use crate::utils::beliefs_propagation::{check_observations, observed_inputs};
+
+/// Surprise of node `state` at the current time step, `None` for the kinds
+/// that set their own (softmax) or have none. `edge` holds the node's kind
+/// and likelihood.
+///
+/// Continuous and volatile nodes, and aggregate inputs with their fused
+/// observation, take the Gaussian surprise of their posterior mean under
+/// their prediction, `0.5·ln(2π/π̂) + 0.5·π̂·(μ − μ̂)²`, which for
+/// an input is the surprise of the observation. Continuous inputs with a
+/// Laplace likelihood (see [`Network::set_likelihood`]) take the Laplace
+/// surprise `ln(2b) + |μ − μ̂| / b` of scale `b = 1/√(2π̂)` instead. Binary inputs take the
+/// Bernoulli surprise of the observation. Bounded inputs take the Gaussian
+/// surprise of the logit of the observation plus the log-Jacobian of the
+/// transform (see [`crate::math::bounded_log_jacobian`]), i.e. the surprise
+/// of the observation on its own scale, so that it compares across bounds
+/// and with other likelihoods. Log-normal inputs likewise add `ln x = y`, the
+/// log-Jacobian of `x = exp(y)`. Circular nodes take the Gaussian surprise of
+/// the wrapped difference between mean and prediction. Inputs are weighted by
+/// `observed`, as in the
+/// accuracy of [`crate::utils::free_energy::free_energy`].
+pub fn node_surprise(edge: &AdjacencyLists, is_input: bool, state: &NodeState) -> Option<f64> {
+    let surprise = match (edge.node_type.as_str(), is_input) {
+        ("continuous-state", true) if edge.likelihood == "laplace" => {
+            -laplace_log_density(state.mean, state.expected_mean, state.expected_precision)
+        }
+        ("continuous-state" | "volatile-state" | "aggregate-input", _) => {
+            -gaussian_log_density(state.mean, state.expected_mean, state.expected_precision)
+        }
+        ("binary-state" | "binary-input", true) => {
+            let (u, p) = (state.mean, state.expected_mean);
+            -(u * p.ln() + (1.0 - u) * (1.0 - p).ln())
+        }
+        ("bounded-state", _) => {
+            -gaussian_log_density(state.mean, state.expected_mean, state.expected_precision)
+                + bounded_log_jacobian(state.mean, state.lower_bound, state.upper_bound)
+        }
+        ("circular-state", _) => -gaussian_log_density(
+            wrap_angle(state.mean - state.expected_mean),
+            0.0,
+            state.expected_precision,
+        ),
+        ("lognormal-state", _) => {
+            -gaussian_log_density(state.mean, state.expected_mean, state.expected_precision)
+                + state.mean
+        }
+        _ => return None,
+    };
+    Some(if is_input {
+        state.observed * surprise
+    } else {
+        surprise
+    })
+}
+
+/// Write the surprise of the last update into the `surprise` attribute of
+/// every node that has one (see [`node_surprise`]), so that it is recorded
+/// with the trajectories.
+pub fn set_surprise(network: &mut Network) {
+    for (node_idx, state) in network.attributes.states.iter_mut().enumerate() {
+        let is_input = network.inputs.contains(&node_idx);
+        if let Some(surprise) = node_surprise(&network.edges[node_idx], is_input, state) {
+            state.surprise = surprise;
+        }
+    }
+}
+
+/// Recorded surprise of every node summed over time, by node index, from the
+/// time step the node was added (see
+/// [`crate::model::network::NodeTrajectory::born`]).
+pub fn surprise_by_node(network: &Network) -> Result<Vec<f64>, NetworkError> {
+    network
+        .node_trajectories
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(node_idx, traj)| {
+            traj.column("surprise")
+                .map(|surprise| surprise.iter().skip(traj.born).sum())
+                .ok_or_else(|| NetworkError::UnrecordedKey {
+                    node_idx,
+                    key: "surprise".to_string(),
+                })
+        })
+        .collect()
+}
+
+/// Leave-future-out surprise of `input_data`, one value per entry of
+/// `train_sizes`.
+///
+/// For a train size `k`, the network filters the first `k` time steps from
+/// its current state, then scores the next `horizon` steps (fewer at the end
+/// of the series) without updating on them: each step runs the predictions
+/// with its own time step, adds the surprise of its observations (see
+/// [`node_surprise`]) to the fold, and carries the predictions over as the
+/// posteriors of the Gaussian state nodes, as for a step with no data. The
+/// beliefs thus keep drifting and widening through the held-out block, but
+/// never see it.
+///
+/// The network is restored afterwards (see [`Network::checkpoint`]), which
+/// rules out networks with dp-state nodes: the clusters they open could not
+/// be taken back.
+pub fn cv_surprise(
+    network: &mut Network,
+    input_data: &[Vec<f64>],
+    time_steps: Option<&[f64]>,
+    train_sizes: &[usize],
+    horizon: usize,
+) -> Result<Vec<f64>, NetworkError> {
+    network.reject_clusters("cv_surprise")?;
+    let n_time = input_data.len();
+    let default_time_steps = vec![1.0; n_time];
+    let time_steps = time_steps.unwrap_or(&default_time_steps);
+    if time_steps.len() != n_time {
+        return Err(NetworkError::DimensionMismatch {
+            what: "time_steps (one per observation)",
+            expected: n_time,
+            found: time_steps.len(),
+        });
+    }
+    if let Some(&train_size) = train_sizes
+        .iter()
+        .find(|&&train_size| horizon == 0 || train_size >= n_time)
+    {
+        return Err(NetworkError::InvalidTimeRange {
+            start: train_size as isize,
+            stop: (train_size + horizon) as isize,
+            step: 1,
+            n_time,
+        });
+    }
+    for observations in input_data {
+        check_observations(network, observations)?;
+    }
+
+    let initial = network.checkpoint();
+    let result = train_sizes
+        .iter()
+        .map(|&train_size| {
+            network.restore(&initial)?;
+            fold_surprise(network, input_data, time_steps, train_size, horizon)
+        })
+        .collect();
+    network.restore(&initial)?;
+    result
+}
+
+/// Surprise of the `horizon` steps after the first `train_size` ones, for
+/// [`cv_surprise`].
+fn fold_surprise(
+    network: &mut Network,
+    input_data: &[Vec<f64>],
+    time_steps: &[f64],
+    train_size: usize,
+    horizon: usize,
+) -> Result<f64, NetworkError> {
+    for (observations, &time_step) in input_data.iter().zip(time_steps).take(train_size) {
+        network.run_step(observations, time_step)?;
+    }
+
+    let stop = (train_size + horizon).min(input_data.len());
+    let mut surprise = 0.0;
+    for time_idx in train_size..stop {
+        network.run_predictions(time_steps[time_idx])?;
+        network.set_observations(&input_data[time_idx])?;
+        surprise += network
+            .inputs
+            .iter()
+            .take(observed_inputs(network, input_data[time_idx].len()))
+            .filter_map(|&node_idx| {
+                node_surprise(
+                    &network.edges[node_idx],
+                    true,
+                    &network.attributes.states[node_idx],
+                )
+            })
+            .sum::<f64>();
+        hold_predictions(network);
+    }
+    Ok(surprise)
+}
+
+/// Take the predictions of the last step as posteriors, skipping the
+/// prediction-error and posterior steps: the means of the inputs, and the
+/// means and precisions of the continuous and volatile state nodes. Other
+/// kinds keep their posteriors.
+fn hold_predictions(network: &mut Network) {
+    for (node_idx, state) in network.attributes.states.iter_mut().enumerate() {
+        if network.inputs.contains(&node_idx) {
+            state.mean = state.expected_mean;
+            continue;
+        }
+        match network.edges[node_idx].node_type.as_str() {
+            "continuous-state" => {
+                state.mean = state.expected_mean;
+                state.precision = state.expected_precision;
+            }
+            "volatile-state" => {
+                state.mean = state.expected_mean;
+                state.precision = state.expected_precision;
+                state.mean_vol = state.expected_mean_vol;
+                state.precision_vol = state.expected_precision_vol;
+            }
+            _ => {}
+        }
+    }
+}
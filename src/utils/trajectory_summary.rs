@@ -0,0 +1,148 @@
+//! Per-node summary statistics of the recorded trajectories.
+
+use std::collections::HashMap;
+
+use crate::model::network::{Network, TrajectoryColumn};
+use crate::utils::free_energy::{input_log_likelihood, required_columns};
+
+/// Summarise `network.node_trajectories`, one map per node index.
+///
+/// Every node gets, when the underlying fields were recorded:
+///
+/// - `average_mean` and `variance_mean`: average and (population) variance
+///   of the belief mean over time,
+/// - `average_precision`: average posterior precision,
+/// - `final_mean` and `final_precision`: the last recorded values,
+/// - `total_surprise` (inputs only): the summed surprise of the observations,
+///   the node's share of `-accuracy` in
+///   [`crate::utils::free_energy::free_energy`].
+///
+/// Statistics start at the time step the node was added (see
+/// [`crate::model::network::NodeTrajectory::born`]). Nodes without recorded
+/// time steps get an empty map, and statistics whose fields were left out by
+/// `record_keys` are skipped.
+pub fn summary(network: &Network) -> HashMap<usize, HashMap<String, f64>> {
+    let trajectories = &network.node_trajectories.nodes;
+    (0..network.edges.len())
+        .map(|node_idx| {
+            let mut stats = HashMap::new();
+            let Some(traj) = trajectories
+                .get(node_idx)
+                .filter(|traj| traj.n_time > traj.born)
+            else {
+                return (node_idx, stats);
+            };
+            let since_born = |column: TrajectoryColumn<'_>| -> Vec<f64> {
+                column.iter().skip(traj.born).copied().collect()
+            };
+
+            if let Some(mean) = traj.column("mean").map(since_born) {
+                let average = average(&mean);
+                let variance =
+                    mean.iter().map(|&x| (x - average).powi(2)).sum::<f64>() / mean.len() as f64;
+                stats.insert("average_mean".to_string(), average);
+                stats.insert("variance_mean".to_string(), variance);
+                stats.insert("final_mean".to_string(), mean[mean.len() - 1]);
+            }
+            if let Some(precision) = traj.column("precision").map(since_born) {
+                stats.insert("average_precision".to_string(), average(&precision));
+                stats.insert(
+                    "final_precision".to_string(),
+                    precision[precision.len() - 1],
+                );
+            }
+
+            let node_type = network.edges[node_idx].node_type.as_str();
+            let required = required_columns(node_type, true);
+            if network.inputs.contains(&node_idx)
+                && !required.is_empty()
+                && required.iter().all(|key| traj.column(key).is_some())
+            {
+                let total_surprise = (traj.born..traj.n_time)
+                    .filter_map(|t| {
+                        input_log_likelihood(&network.edges[node_idx], traj, t)
+                            .map(|log_likelihood| -traj.observed()[t] * log_likelihood)
+                    })
+                    .sum();
+                stats.insert("total_surprise".to_string(), total_surprise);
+            }
+            (node_idx, stats)
+        })
+        .collect()
+}
+
+fn average(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::network::{AddNodesOptions, InputDataOptions};
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-12, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_summary_matches_the_trajectories() {
+        let mut network = Network::new("eHGF");
+        network
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(summary(&network).values().all(HashMap::is_empty));
+
+        network
+            .input_data(
+                vec![vec![0.2], vec![0.6], vec![-0.1]],
+                None,
+                true,
+                InputDataOptions::default(),
+            )
+            .unwrap();
+        let stats = summary(&network);
+        let parent = &network.node_trajectories.nodes[1];
+        let means = parent.mean().to_vec();
+        let average_mean = means.iter().sum::<f64>() / 3.0;
+        assert_close(stats[&1]["average_mean"], average_mean);
+        assert_close(
+            stats[&1]["variance_mean"],
+            means
+                .iter()
+                .map(|x| (x - average_mean).powi(2))
+                .sum::<f64>()
+                / 3.0,
+        );
+        assert_close(stats[&1]["final_mean"], means[2]);
+        assert_close(
+            stats[&1]["average_precision"],
+            parent.precision().iter().sum::<f64>() / 3.0,
+        );
+        assert!(!stats[&1].contains_key("total_surprise"));
+        // The input carries the whole surprise of the observations.
+        assert_close(
+            stats[&0]["total_surprise"],
+            -network.get_free_energy().unwrap().accuracy,
+        );
+
+        network.set_record_keys(Some(vec!["mean".into()])).unwrap();
+        network
+            .input_data(vec![vec![0.4]], None, true, InputDataOptions::default())
+            .unwrap();
+        let stats = summary(&network);
+        let mut keys: Vec<&str> = stats[&1].keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, vec!["average_mean", "final_mean", "variance_mean"]);
+        assert!(!stats[&0].contains_key("total_surprise"));
+    }
+}
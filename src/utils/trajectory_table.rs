@@ -0,0 +1,274 @@
+//! Flat, one-column-per-attribute view of the recorded trajectories, and its
+//! CSV export.
+//!
+//! Column names follow pyhgf's `to_pandas`: `time_steps` and `time` first,
+//! then `x_{node}_{attribute}` for every exported key of every node, with
+//! vector attributes expanded into `x_{node}_{attribute}_{k}`.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::model::network::Network;
+
+/// Build the flat trajectory table as ordered `(column name, values)` pairs,
+/// every column holding one value per recorded time step.
+pub fn trajectory_table(network: &Network) -> Vec<(String, Vec<f64>)> {
+    let trajectories = &network.node_trajectories;
+    let time_steps = trajectories.time_steps.clone();
+    let time = time_steps
+        .iter()
+        .scan(0.0, |elapsed, dt| {
+            *elapsed += dt;
+            Some(*elapsed)
+        })
+        .collect();
+
+    let mut columns = vec![
+        ("time_steps".to_string(), time_steps),
+        ("time".to_string(), time),
+    ];
+
+    for (node_idx, traj) in trajectories.nodes.iter().enumerate() {
+        for key in traj.keys(&network.edges[node_idx].node_type) {
+            if let Some(column) = traj.column(key) {
+                columns.push((format!("x_{}_{}", node_idx, key), column.to_vec()));
+            } else if let Some(vector) = traj.vector(key) {
+                for k in 0..vector.stride {
+                    columns.push((
+                        format!("x_{}_{}_{}", node_idx, key, k),
+                        (0..vector.len()).map(|t| vector[t][k]).collect(),
+                    ));
+                }
+            }
+        }
+    }
+
+    columns
+}
+
+/// Scalar trajectories of every node as one time-major matrix, returned as
+/// its row-major values (`n_time × n_columns`) and column names. Columns are
+/// named `node{node}::{attribute}`, ordered by node index and then by
+/// attribute name; vector attributes are left out.
+pub fn trajectory_matrix(network: &Network) -> (Vec<f64>, Vec<String>) {
+    let trajectories = &network.node_trajectories;
+    let mut columns = Vec::new();
+    let mut names = Vec::new();
+    for (node_idx, traj) in trajectories.nodes.iter().enumerate() {
+        let mut keys: Vec<&str> = traj
+            .keys(&network.edges[node_idx].node_type)
+            .into_iter()
+            .filter(|key| traj.column(key).is_some())
+            .collect();
+        keys.sort_unstable();
+        for key in keys {
+            columns.extend(traj.column(key));
+            names.push(format!("node{}::{}", node_idx, key));
+        }
+    }
+
+    let n_time = trajectories.time_steps.len();
+    let mut values = Vec::with_capacity(n_time * columns.len());
+    for t in 0..n_time {
+        values.extend(columns.iter().map(|column| column[t]));
+    }
+    (values, names)
+}
+
+/// Write the flat trajectory table to `path` as CSV: a header row with the
+/// column names, then one row per time step.
+pub fn write_trajectories_csv(network: &Network, path: &Path) -> io::Result<()> {
+    let table = trajectory_table(network);
+    let n_time = network.node_trajectories.time_steps.len();
+    let mut writer = BufWriter::new(File::create(path)?);
+
+    let header: Vec<&str> = table.iter().map(|(name, _)| name.as_str()).collect();
+    writeln!(writer, "{}", header.join(","))?;
+    for t in 0..n_time {
+        let row: Vec<String> = table
+            .iter()
+            .map(|(_, values)| values[t].to_string())
+            .collect();
+        writeln!(writer, "{}", row.join(","))?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::network::{AddNodesOptions, InputDataOptions};
+
+    #[test]
+    fn test_trajectory_table_columns() {
+        // input (0) <- value parent (1) <- volatility parent (2)
+        let mut network = Network::new("eHGF");
+        network
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    volatility_children: Some(1.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+            .input_data(
+                vec![vec![0.1], vec![0.3], vec![-0.2]],
+                Some(vec![1.0, 0.5, 2.0]),
+                true,
+                InputDataOptions::default(),
+            )
+            .unwrap();
+
+        let table = trajectory_table(&network);
+        let names: Vec<&str> = table.iter().map(|(name, _)| name.as_str()).collect();
+
+        assert_eq!(&names[..2], &["time_steps", "time"]);
+        assert_eq!(table[1].1, vec![1.0, 1.5, 3.5]);
+        for name in [
+            "x_0_mean",
+            "x_0_value_coupling_parents_0",
+            "x_1_expected_mean",
+            "x_1_value_coupling_children_0",
+            "x_1_volatility_coupling_parents_0",
+            "x_2_precision",
+            "x_2_volatility_coupling_children_0",
+        ] {
+            assert!(names.contains(&name), "missing column {}", name);
+        }
+        assert!(table.iter().all(|(_, values)| values.len() == 3));
+        assert_eq!(
+            table.iter().find(|(name, _)| name == "x_0_mean").unwrap().1,
+            vec![0.1, 0.3, -0.2]
+        );
+    }
+
+    #[test]
+    fn test_write_trajectories_csv_round_trip() {
+        let mut network = Network::new("eHGF");
+        network
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        network
+            .add_nodes(
+                "volatile-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+            .input_data(
+                vec![vec![0.25], vec![-1.0 / 3.0], vec![0.7]],
+                None,
+                true,
+                InputDataOptions::default(),
+            )
+            .unwrap();
+
+        let path =
+            std::env::temp_dir().join(format!("rshgf_trajectories_{}.csv", std::process::id()));
+        network.write_trajectories_csv(&path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut lines = content.lines();
+        let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+        let rows: Vec<Vec<f64>> = lines
+            .map(|line| line.split(',').map(|v| v.parse().unwrap()).collect())
+            .collect();
+        assert_eq!(rows.len(), 3);
+
+        let table = trajectory_table(&network);
+        assert_eq!(header.len(), table.len());
+        for (col, (name, values)) in table.iter().enumerate() {
+            assert_eq!(header[col], name);
+            for (t, row) in rows.iter().enumerate() {
+                assert_eq!(row[col], values[t], "{} t={}", name, t);
+            }
+        }
+        assert_eq!(
+            rows.iter()
+                .map(|r| r[header.iter().position(|&h| h == "x_1_mean").unwrap()])
+                .collect::<Vec<_>>(),
+            network.node_trajectories.nodes[1].mean().to_vec()
+        );
+    }
+
+    #[test]
+    fn test_trajectory_matrix() {
+        let mut network = Network::new("eHGF");
+        network
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        network
+            .add_nodes(
+                "volatile-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+            .input_data(
+                vec![vec![0.25], vec![-0.5], vec![0.7]],
+                None,
+                true,
+                InputDataOptions::default(),
+            )
+            .unwrap();
+
+        let (values, names) = trajectory_matrix(&network);
+        let n_scalars: usize = (0..2)
+            .map(|node_idx| {
+                let traj = &network.node_trajectories.nodes[node_idx];
+                traj.keys(&network.edges[node_idx].node_type)
+                    .into_iter()
+                    .filter(|key| traj.column(key).is_some())
+                    .count()
+            })
+            .sum();
+        assert_eq!(names.len(), n_scalars);
+        assert_eq!(values.len(), 3 * n_scalars);
+
+        // Sorted by node, then by attribute.
+        assert_eq!(names[0], "node0::autoconnection_strength");
+        let node1 = names
+            .iter()
+            .position(|name| name.starts_with("node1::"))
+            .unwrap();
+        assert!(names[..node1].windows(2).all(|w| w[0] < w[1]));
+        assert!(names[node1..].windows(2).all(|w| w[0] < w[1]));
+
+        let col = names
+            .iter()
+            .position(|name| name == "node1::mean_vol")
+            .unwrap();
+        let mean_vol = network.node_trajectories.nodes[1]
+            .column("mean_vol")
+            .unwrap();
+        for t in 0..3 {
+            assert_eq!(values[t * names.len() + col], mean_vol[t]);
+        }
+    }
+}
@@ -15,7 +15,7 @@ use rand::{Rng, SeedableRng};
 use rand_distr::{Normal, Uniform};
 
 /// Build a [`SmallRng`] from an optional seed.
-fn make_rng(seed: Option<u64>) -> SmallRng {
+pub(crate) fn make_rng(seed: Option<u64>) -> SmallRng {
     match seed {
         Some(s) => SmallRng::seed_from_u64(s),
         None => SmallRng::from_os_rng(),
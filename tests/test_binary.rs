@@ -1,4 +1,5 @@
-use rshgf::model::network::Network;
+use rshgf::error::NetworkError;
+use rshgf::model::network::{AddNodesOptions, InputDataOptions, Network};
 
 /// Helper to check approximate equality of f64 values.
 fn assert_close(actual: f64, expected: f64, label: &str) {
@@ -22,44 +23,51 @@ fn test_binary_2_levels_single_obs() {
     // Node 0: binary-state input
     // Node 1: continuous-state value parent of node 0
     let mut network = Network::new("eHGF");
-    network.add_nodes("binary-state", 1, None, None, None, None, None, None);
-    network.add_nodes(
-        "continuous-state",
-        1,
-        None,
-        Some(vec![0].into()),
-        None,
-        None,
-        None,
-        Some([("mean".into(), 1.0), ("tonic_volatility".into(), 1.0)].into()),
-    );
+    network
+        .add_nodes("binary-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(vec![0].into()),
+                additional_parameters: Some(
+                    [("mean".into(), 1.0), ("tonic_volatility".into(), 1.0)].into(),
+                ),
+                ..Default::default()
+            },
+        )
+        .unwrap();
     network.set_update_sequence();
-    network.input_data(vec![vec![1.0]], None, true);
+    network
+        .input_data(vec![vec![1.0]], None, true, InputDataOptions::default())
+        .unwrap();
 
     // Node 0 — binary state
     let n0 = &network.node_trajectories.nodes[0];
-    assert_close(n0.mean[0], 1.0, "n0 mean");
-    assert_close(n0.expected_mean[0], 0.731058578630005, "n0 expected_mean");
-    assert_close(n0.precision[0], 0.196611933241482, "n0 precision");
+    assert_close(n0.mean()[0], 1.0, "n0 mean");
+    assert_close(n0.expected_mean()[0], 0.731058578630005, "n0 expected_mean");
+    assert_close(n0.precision()[0], 0.196611933241482, "n0 precision");
     assert_close(
-        n0.expected_precision[0],
+        n0.expected_precision()[0],
         0.196611933241482,
         "n0 expected_precision",
     );
-    assert_close(n0.observed[0], 1.0, "n0 observed");
+    assert_close(n0.observed()[0], 1.0, "n0 observed");
     assert_close(
-        n0.value_prediction_error[0],
+        n0.value_prediction_error()[0],
         1.367879441171442,
         "n0 value_pe",
     );
 
     // Node 1 — continuous value parent
     let n1 = &network.node_trajectories.nodes[1];
-    assert_close(n1.mean[0], 1.577681201748482, "n1 mean");
-    assert_close(n1.expected_mean[0], 1.0, "n1 expected_mean");
-    assert_close(n1.precision[0], 0.465553354611477, "n1 precision");
+    assert_close(n1.mean()[0], 1.577681201748482, "n1 mean");
+    assert_close(n1.expected_mean()[0], 1.0, "n1 expected_mean");
+    assert_close(n1.precision()[0], 0.465553354611477, "n1 precision");
     assert_close(
-        n1.expected_precision[0],
+        n1.expected_precision()[0],
         0.268941421369995,
         "n1 expected_precision",
     );
@@ -81,59 +89,69 @@ fn test_binary_3_levels_single_obs() {
     // Node 1: continuous-state value parent of node 0
     // Node 2: continuous-state volatility parent of node 1
     let mut network = Network::new("eHGF");
-    network.add_nodes("binary-state", 1, None, None, None, None, None, None);
-    network.add_nodes(
-        "continuous-state",
-        1,
-        None,
-        Some(vec![0].into()),
-        None,
-        None,
-        None,
-        Some([("mean".into(), 1.0), ("tonic_volatility".into(), 1.0)].into()),
-    );
-    network.add_nodes(
-        "continuous-state",
-        1,
-        None,
-        None,
-        None,
-        Some(vec![1].into()),
-        None,
-        Some([("mean".into(), 1.0), ("tonic_volatility".into(), 1.0)].into()),
-    );
+    network
+        .add_nodes("binary-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(vec![0].into()),
+                additional_parameters: Some(
+                    [("mean".into(), 1.0), ("tonic_volatility".into(), 1.0)].into(),
+                ),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                volatility_children: Some(vec![1].into()),
+                additional_parameters: Some(
+                    [("mean".into(), 1.0), ("tonic_volatility".into(), 1.0)].into(),
+                ),
+                ..Default::default()
+            },
+        )
+        .unwrap();
     network.set_update_sequence();
-    network.input_data(vec![vec![1.0]], None, true);
+    network
+        .input_data(vec![vec![1.0]], None, true, InputDataOptions::default())
+        .unwrap();
 
     // Node 0 — binary state (no volatility parent → unchanged from canonical)
     let n0 = &network.node_trajectories.nodes[0];
-    assert_close(n0.mean[0], 1.0, "n0 mean");
-    assert_close(n0.expected_mean[0], 0.731058578630005, "n0 expected_mean");
-    assert_close(n0.precision[0], 0.196611933241482, "n0 precision");
+    assert_close(n0.mean()[0], 1.0, "n0 mean");
+    assert_close(n0.expected_mean()[0], 0.731058578630005, "n0 expected_mean");
+    assert_close(n0.precision()[0], 0.196611933241482, "n0 precision");
     assert_close(
-        n0.expected_precision[0],
+        n0.expected_precision()[0],
         0.196611933241482,
         "n0 expected_precision",
     );
 
     // Node 1 — continuous value parent (has volatility parent node 2 → piHGF correction applies)
     let n1 = &network.node_trajectories.nodes[1];
-    assert_close(n1.mean[0], 2.237863416338325, "n1 mean");
-    assert_close(n1.expected_mean[0], 1.0, "n1 expected_mean");
-    assert_close(n1.precision[0], 0.217262597650345, "n1 precision");
+    assert_close(n1.mean()[0], 2.237863416338325, "n1 mean");
+    assert_close(n1.expected_mean()[0], 1.0, "n1 expected_mean");
+    assert_close(n1.precision()[0], 0.217262597650345, "n1 precision");
     assert_close(
-        n1.expected_precision[0],
+        n1.expected_precision()[0],
         0.020650664408863,
         "n1 expected_precision",
     );
 
     // Node 2 — continuous volatility parent (top of chain, no parent above → expected_precision unchanged)
     let n2 = &network.node_trajectories.nodes[2];
-    assert_close(n2.mean[0], -0.590073315695407, "n2 mean");
-    assert_close(n2.expected_mean[0], 1.0, "n2 expected_mean");
-    assert_close(n2.precision[0], 0.537517033851118, "n2 precision");
+    assert_close(n2.mean()[0], -0.590073315695407, "n2 mean");
+    assert_close(n2.expected_mean()[0], 1.0, "n2 expected_mean");
+    assert_close(n2.precision()[0], 0.537517033851118, "n2 precision");
     assert_close(
-        n2.expected_precision[0],
+        n2.expected_precision()[0],
         0.268941421369995,
         "n2 expected_precision",
     );
@@ -143,65 +161,80 @@ fn test_binary_3_levels_single_obs() {
 fn test_binary_3_levels_two_obs() {
     // Feed two observations [1.0, 0.0] and verify both time steps.
     let mut network = Network::new("eHGF");
-    network.add_nodes("binary-state", 1, None, None, None, None, None, None);
-    network.add_nodes(
-        "continuous-state",
-        1,
-        None,
-        Some(vec![0].into()),
-        None,
-        None,
-        None,
-        Some([("mean".into(), 1.0), ("tonic_volatility".into(), 1.0)].into()),
-    );
-    network.add_nodes(
-        "continuous-state",
-        1,
-        None,
-        None,
-        None,
-        Some(vec![1].into()),
-        None,
-        Some([("mean".into(), 1.0), ("tonic_volatility".into(), 1.0)].into()),
-    );
+    network
+        .add_nodes("binary-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(vec![0].into()),
+                additional_parameters: Some(
+                    [("mean".into(), 1.0), ("tonic_volatility".into(), 1.0)].into(),
+                ),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                volatility_children: Some(vec![1].into()),
+                additional_parameters: Some(
+                    [("mean".into(), 1.0), ("tonic_volatility".into(), 1.0)].into(),
+                ),
+                ..Default::default()
+            },
+        )
+        .unwrap();
     network.set_update_sequence();
-    network.input_data(vec![vec![1.0], vec![0.0]], None, true);
+    network
+        .input_data(
+            vec![vec![1.0], vec![0.0]],
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
 
     // ---- Step 0 (observation = 1.0) ----
 
     // Node 0
     let n0 = &network.node_trajectories.nodes[0];
-    assert_close(n0.mean[0], 1.0, "s0 n0 mean");
+    assert_close(n0.mean()[0], 1.0, "s0 n0 mean");
     assert_close(
-        n0.expected_mean[0],
+        n0.expected_mean()[0],
         0.731058578630005,
         "s0 n0 expected_mean",
     );
-    assert_close(n0.precision[0], 0.196611933241482, "s0 n0 precision");
+    assert_close(n0.precision()[0], 0.196611933241482, "s0 n0 precision");
     assert_close(
-        n0.expected_precision[0],
+        n0.expected_precision()[0],
         0.196611933241482,
         "s0 n0 expected_precision",
     );
 
     // Node 1
     let n1 = &network.node_trajectories.nodes[1];
-    assert_close(n1.mean[0], 2.237863416338325, "s0 n1 mean");
-    assert_close(n1.expected_mean[0], 1.0, "s0 n1 expected_mean");
-    assert_close(n1.precision[0], 0.217262597650345, "s0 n1 precision");
+    assert_close(n1.mean()[0], 2.237863416338325, "s0 n1 mean");
+    assert_close(n1.expected_mean()[0], 1.0, "s0 n1 expected_mean");
+    assert_close(n1.precision()[0], 0.217262597650345, "s0 n1 precision");
     assert_close(
-        n1.expected_precision[0],
+        n1.expected_precision()[0],
         0.020650664408863,
         "s0 n1 expected_precision",
     );
 
     // Node 2
     let n2 = &network.node_trajectories.nodes[2];
-    assert_close(n2.mean[0], -0.590073315695407, "s0 n2 mean");
-    assert_close(n2.expected_mean[0], 1.0, "s0 n2 expected_mean");
-    assert_close(n2.precision[0], 0.537517033851118, "s0 n2 precision");
+    assert_close(n2.mean()[0], -0.590073315695407, "s0 n2 mean");
+    assert_close(n2.expected_mean()[0], 1.0, "s0 n2 expected_mean");
+    assert_close(n2.precision()[0], 0.537517033851118, "s0 n2 precision");
     assert_close(
-        n2.expected_precision[0],
+        n2.expected_precision()[0],
         0.268941421369995,
         "s0 n2 expected_precision",
     );
@@ -209,44 +242,157 @@ fn test_binary_3_levels_two_obs() {
     // ---- Step 1 (observation = 0.0) ----
 
     // Node 0
-    assert_close(n0.mean[1], 0.0, "s1 n0 mean");
+    assert_close(n0.mean()[1], 0.0, "s1 n0 mean");
     assert_close(
-        n0.expected_mean[1],
+        n0.expected_mean()[1],
         0.903598504654528,
         "s1 n0 expected_mean",
     );
-    assert_close(n0.precision[1], 0.087108247040629, "s1 n0 precision");
+    assert_close(n0.precision()[1], 0.087108247040629, "s1 n0 precision");
     assert_close(
-        n0.expected_precision[1],
+        n0.expected_precision()[1],
         0.087108247040629,
         "s1 n0 expected_precision",
     );
 
     // Node 1
-    assert_close(n1.mean[1], -4.287959232795414, "s1 n1 mean");
+    assert_close(n1.mean()[1], -4.287959232795414, "s1 n1 mean");
     assert_close(
-        n1.expected_mean[1],
+        n1.expected_mean()[1],
         2.237863416338325,
         "s1 n1 expected_mean",
     );
-    assert_close(n1.precision[1], 0.138465072257898, "s1 n1 precision");
+    assert_close(n1.precision()[1], 0.138465072257898, "s1 n1 precision");
     assert_close(
-        n1.expected_precision[1],
+        n1.expected_precision()[1],
         0.051356825217268,
         "s1 n1 expected_precision",
     );
 
     // Node 2
-    assert_close(n2.mean[1], 2.133602312876886, "s1 n2 mean");
+    assert_close(n2.mean()[1], 2.133602312876886, "s1 n2 mean");
     assert_close(
-        n2.expected_mean[1],
+        n2.expected_mean()[1],
         -0.590073315695407,
         "s1 n2 expected_mean",
     );
-    assert_close(n2.precision[1], 0.789268236136739, "s1 n2 precision");
+    assert_close(n2.precision()[1], 0.789268236136739, "s1 n2 precision");
     assert_close(
-        n2.expected_precision[1],
+        n2.expected_precision()[1],
         0.218403176385631,
         "s1 n2 expected_precision",
     );
 }
+
+// ---------------------------------------------------------------------------
+// Binary input node  (binary-input → continuous-state value parent)
+// ---------------------------------------------------------------------------
+
+fn binary_input_network() -> Network {
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("binary-input", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(vec![0].into()),
+                additional_parameters: Some([("tonic_volatility".into(), -2.0)].into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network.set_update_sequence();
+    network
+}
+
+#[test]
+fn test_binary_input_tracks_observed_proportion() {
+    // 80% of ones: the sigmoid of the parent's mean should move from 0.5
+    // toward the observed proportion.
+    let observations: Vec<Vec<f64>> = (0..200)
+        .map(|t| vec![if t % 5 == 4 { 0.0 } else { 1.0 }])
+        .collect();
+    let mut network = binary_input_network();
+    network
+        .input_data(observations, None, true, InputDataOptions::default())
+        .unwrap();
+
+    let n0 = &network.node_trajectories.nodes[0];
+    let n1 = &network.node_trajectories.nodes[1];
+    let last = n1.mean().len() - 1;
+    let p = 1.0 / (1.0 + (-n1.mean()[last]).exp());
+    assert!((p - 0.8).abs() < 0.1, "sigmoid(parent mean) = {}", p);
+    assert!(n0.expected_mean()[last] > 0.5);
+    assert!(n1.precision().iter().all(|&pi| pi > 0.0));
+}
+
+#[test]
+fn test_binary_input_matches_binary_state() {
+    // With the prediction folded into its prediction error step, a binary input
+    // updates its parent exactly like a binary-state leaf.
+    let observations = vec![vec![1.0], vec![0.0], vec![1.0], vec![1.0]];
+    let mut input = binary_input_network();
+    input
+        .input_data(
+            observations.clone(),
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+
+    let mut state = Network::new("eHGF");
+    state
+        .add_nodes("binary-state", 1, AddNodesOptions::default())
+        .unwrap();
+    state
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(vec![0].into()),
+                additional_parameters: Some([("tonic_volatility".into(), -2.0)].into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    state.set_update_sequence();
+    state
+        .input_data(observations, None, true, InputDataOptions::default())
+        .unwrap();
+
+    for t in 0..4 {
+        assert_close(
+            input.node_trajectories.nodes[1].mean()[t],
+            state.node_trajectories.nodes[1].mean()[t],
+            "n1 mean",
+        );
+        assert_close(
+            input.node_trajectories.nodes[0].value_prediction_error()[t],
+            state.node_trajectories.nodes[0].value_prediction_error()[t],
+            "n0 value_pe",
+        );
+    }
+}
+
+#[test]
+fn test_binary_input_rejects_non_binary_observations() {
+    let mut network = binary_input_network();
+    let err = network
+        .input_data(
+            vec![vec![1.0], vec![0.5]],
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        NetworkError::InvalidObservation { node_idx: 0, value, .. } if value == 0.5
+    ));
+    // Validated before the run starts: nothing was recorded.
+    assert!(network.node_trajectories.nodes.is_empty());
+}
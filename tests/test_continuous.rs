@@ -1,4 +1,12 @@
-use rshgf::model::network::Network;
+use rshgf::error::NetworkError;
+use rshgf::model::network::{
+    trajectory_fields_for_type, AddNodesOptions, EntropySource, FloatOrList, InputDataOptions,
+    Network, NetworkState, RecordedTrajectory, TRAJECTORY_FIELDS,
+};
+use rshgf::utils::function_pointer::UpdateStep;
+use rshgf::utils::pyhgf_spec::PyhgfNode;
+use std::collections::HashMap;
+use std::ops::ControlFlow;
 
 /// Helper to check approximate equality of f64 values
 fn assert_close(actual: f64, expected: f64, label: &str) {
@@ -21,39 +29,47 @@ fn test_one_node_hgf() {
     let mut network = Network::new("eHGF");
 
     // Node 0: input node (no parents or children specified)
-    network.add_nodes("continuous-state", 1, None, None, None, None, None, None);
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
     // Node 1: value parent of node 0
-    network.add_nodes(
-        "continuous-state",
-        1,
-        None,
-        Some(vec![0].into()),
-        None,
-        None,
-        None,
-        None,
-    );
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(vec![0].into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
 
     network.set_update_sequence();
-    network.input_data(vec![vec![0.2]], None, true);
+    network
+        .input_data(vec![vec![0.2]], None, true, InputDataOptions::default())
+        .unwrap();
 
     // Check node 0 trajectories
     let node0 = &network.node_trajectories.nodes[0];
-    assert_close(node0.precision[0], 1.0, "node0 precision");
-    assert_close(node0.expected_precision[0], 1.0, "node0 expected_precision");
-    assert_close(node0.mean[0], 0.2, "node0 mean");
-    assert_close(node0.expected_mean[0], 0.0, "node0 expected_mean");
+    assert_close(node0.precision()[0], 1.0, "node0 precision");
+    assert_close(
+        node0.expected_precision()[0],
+        1.0,
+        "node0 expected_precision",
+    );
+    assert_close(node0.mean()[0], 0.2, "node0 mean");
+    assert_close(node0.expected_mean()[0], 0.0, "node0 expected_mean");
 
     // Check node 1 trajectories (no volatility parent → piHGF correction inactive)
     let node1 = &network.node_trajectories.nodes[1];
-    assert_close(node1.precision[0], 1.9820137, "node1 precision");
+    assert_close(node1.precision()[0], 1.9820137, "node1 precision");
     assert_close(
-        node1.expected_precision[0],
+        node1.expected_precision()[0],
         0.98201376,
         "node1 expected_precision",
     );
-    assert_close(node1.mean[0], 0.10090748, "node1 mean");
-    assert_close(node1.expected_mean[0], 0.0, "node1 expected_mean");
+    assert_close(node1.mean()[0], 0.10090748, "node1 mean");
+    assert_close(node1.expected_mean()[0], 0.0, "node1 expected_mean");
 }
 
 #[test]
@@ -71,63 +87,4013 @@ fn test_two_nodes_hgf() {
     let mut network = Network::new("eHGF");
 
     // Node 0: input node
-    network.add_nodes("continuous-state", 1, None, None, None, None, None, None);
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
     // Node 1: value parent of node 0
-    network.add_nodes(
-        "continuous-state",
-        1,
-        None,
-        Some(vec![0].into()),
-        None,
-        None,
-        None,
-        None,
-    );
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(vec![0].into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
     // Node 2: volatility parent of node 0
-    network.add_nodes(
-        "continuous-state",
-        1,
-        None,
-        None,
-        None,
-        Some(vec![0].into()),
-        None,
-        None,
-    );
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                volatility_children: Some(vec![0].into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
 
     network.set_update_sequence();
-    network.input_data(vec![vec![0.2]], None, true);
+    network
+        .input_data(vec![vec![0.2]], None, true, InputDataOptions::default())
+        .unwrap();
 
     // Check node 0 trajectories
     let node0 = &network.node_trajectories.nodes[0];
-    assert_close(node0.precision[0], 1.0, "node0 precision");
+    assert_close(node0.precision()[0], 1.0, "node0 precision");
     assert_close(
-        node0.expected_precision[0],
+        node0.expected_precision()[0],
         0.27157641,
         "node0 expected_precision",
     );
-    assert_close(node0.mean[0], 0.2, "node0 mean");
-    assert_close(node0.expected_mean[0], 0.0, "node0 expected_mean");
+    assert_close(node0.mean()[0], 0.2, "node0 mean");
+    assert_close(node0.expected_mean()[0], 0.0, "node0 expected_mean");
 
     // Check node 1 trajectories
     let node1 = &network.node_trajectories.nodes[1];
-    assert_close(node1.precision[0], 1.25359020, "node1 precision");
+    assert_close(node1.precision()[0], 1.25359020, "node1 precision");
     assert_close(
-        node1.expected_precision[0],
+        node1.expected_precision()[0],
         0.98201376,
         "node1 expected_precision",
     );
-    assert_close(node1.mean[0], 0.04332778, "node1 mean");
-    assert_close(node1.expected_mean[0], 0.0, "node1 expected_mean");
+    assert_close(node1.mean()[0], 0.04332778, "node1 mean");
+    assert_close(node1.expected_mean()[0], 0.0, "node1 expected_mean");
 
     // Check node 2 trajectories
     let node2 = &network.node_trajectories.nodes[2];
-    assert_close(node2.precision[0], 1.09553182, "node2 precision");
+    assert_close(node2.precision()[0], 1.09553182, "node2 precision");
     assert_close(
-        node2.expected_precision[0],
+        node2.expected_precision()[0],
         0.98201376,
         "node2 expected_precision",
     );
-    assert_close(node2.mean[0], -0.16509254, "node2 mean");
-    assert_close(node2.expected_mean[0], 0.0, "node2 expected_mean");
+    assert_close(node2.mean()[0], -0.16509254, "node2 mean");
+    assert_close(node2.expected_mean()[0], 0.0, "node2 expected_mean");
+}
+
+#[test]
+fn test_autoconnection_decay_without_input() {
+    // Node 1 is a value parent of the input node 0 with λ = 0.9. The input is
+    // never observed, so the parent's posterior equals its prediction and the
+    // mean decays geometrically towards zero.
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(vec![0].into()),
+                additional_parameters: Some(HashMap::from([("mean".into(), 1.0)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network.set_autoconnection(1, 0.9).unwrap();
+    network.attributes.states[0].observed = 0.0;
+
+    network
+        .input_data(vec![vec![]; 5], None, true, InputDataOptions::default())
+        .unwrap();
+
+    let node1 = &network.node_trajectories.nodes[1];
+    let mut previous = 1.0;
+    for t in 0..5 {
+        assert_close(
+            node1.expected_mean()[t],
+            0.9 * previous,
+            &format!("node1 expected_mean t={}", t),
+        );
+        assert_close(
+            node1.mean()[t],
+            0.9 * previous,
+            &format!("node1 mean t={}", t),
+        );
+        previous = node1.mean()[t];
+    }
+}
+
+#[test]
+fn test_set_autoconnection_validation() {
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+
+    assert!(network.set_autoconnection(0, 1.0).is_ok());
+    assert!(network.set_autoconnection(0, 0.0).is_err());
+    assert!(matches!(
+        network.set_autoconnection(0, 1.5),
+        Err(NetworkError::InvalidValue {
+            parameter: "autoconnection_strength",
+            ..
+        })
+    ));
+    assert!(network.set_autoconnection(0, f64::NAN).is_err());
+    assert_eq!(
+        network.set_autoconnection(1, 0.5),
+        Err(NetworkError::MissingNode {
+            node_idx: 1,
+            step: "set_autoconnection"
+        })
+    );
+    // Continuous nodes have no implicit volatility level.
+    assert!(matches!(
+        network.set_autoconnection_vol(0, 0.5),
+        Err(NetworkError::InvalidParameter { ref parameter, .. })
+            if parameter == "autoconnection_strength_vol"
+    ));
+    assert_eq!(network.attributes.states[0].autoconnection_strength, 1.0);
+}
+
+#[test]
+fn test_free_energy_one_node_hgf() {
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(vec![0].into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        network.get_free_energy(),
+        Err(NetworkError::UnrecordedKey {
+            node_idx: 0,
+            key: "observed".into()
+        })
+    );
+
+    network
+        .input_data(vec![vec![0.2]], None, true, InputDataOptions::default())
+        .unwrap();
+    let fe = network.get_free_energy().unwrap();
+
+    // Accuracy: log N(0.2; μ̂ = 0, π̂ = 1) = -½ ln 2π - ½ · 0.2².
+    assert_close(fe.accuracy, -0.93893853, "accuracy");
+    // Complexity: ½ (ln(π/π̂) + π̂/π + π̂ μ² - 1) with the node-1 posterior
+    // μ = 0.10090748, π = 1.9820137 and prior μ̂ = 0, π̂ = 0.98201376.
+    assert_close(fe.complexity, 0.10386257, "complexity");
+    assert_close(fe.total, 1.04280110, "total");
+
+    // The free energy refuses trajectories recorded without the fields it
+    // reads.
+    network.set_record_keys(Some(vec!["mean".into()])).unwrap();
+    network
+        .input_data(vec![vec![0.2]], None, true, InputDataOptions::default())
+        .unwrap();
+    assert_eq!(
+        network.get_free_energy(),
+        Err(NetworkError::UnrecordedKey {
+            node_idx: 0,
+            key: "observed".into()
+        })
+    );
+}
+
+#[test]
+fn test_surprise_by_node() {
+    let mut network =
+        Network::continuous_hgf("eHGF", 2, &[0.0, 1.0], &[1.0, 1.0], &[-3.0, -4.0], &[0.5])
+            .unwrap();
+    let data = [0.1, 0.5, -0.3, 1.2, 0.8, -0.6];
+    network
+        .input_data(
+            data.iter().map(|&x| vec![x]).collect(),
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+    let n_nodes = network.n_nodes();
+
+    // Inputs record the surprise of their observation, as in the accuracy of
+    // the free energy and the total surprise of the summary.
+    let input = &network.node_trajectories.nodes[0];
+    for (t, &observation) in data.iter().enumerate() {
+        let pi_hat = input.expected_precision()[t];
+        let expected = 0.5 * (2.0 * std::f64::consts::PI / pi_hat).ln()
+            + 0.5 * pi_hat * (observation - input.expected_mean()[t]).powi(2);
+        assert_close(input.surprise()[t], expected, "input surprise");
+    }
+    let by_node = network.surprise_by_node().unwrap();
+    assert_eq!(by_node.len(), n_nodes);
+    assert_close(
+        by_node[0],
+        -network.get_free_energy().unwrap().accuracy,
+        "input total",
+    );
+    assert_close(
+        by_node[0],
+        network.summary()[&0]["total_surprise"],
+        "summary total",
+    );
+
+    // State nodes record the surprise of their posterior mean under their
+    // prediction, and the sums add up the recorded steps.
+    for (traj, &node_total) in network.node_trajectories.nodes[1..n_nodes]
+        .iter()
+        .zip(&by_node[1..])
+    {
+        let mut total = 0.0;
+        for t in 0..data.len() {
+            let pi_hat = traj.expected_precision()[t];
+            let expected = 0.5 * (2.0 * std::f64::consts::PI / pi_hat).ln()
+                + 0.5 * pi_hat * (traj.mean()[t] - traj.expected_mean()[t]).powi(2);
+            assert_close(traj.surprise()[t], expected, "state surprise");
+            total += traj.surprise()[t];
+        }
+        assert_eq!(node_total, total);
+    }
+
+    // The column is exported with the other recorded keys.
+    match network.get_trajectory(0, "surprise").unwrap() {
+        RecordedTrajectory::Scalar(column) => {
+            assert_eq!(column.to_vec(), input.surprise().to_vec())
+        }
+        RecordedTrajectory::Vector(_) => panic!("surprise is a scalar"),
+    }
+    for node_idx in 0..n_nodes {
+        assert!(network
+            .recorded_keys(node_idx)
+            .unwrap()
+            .contains(&"surprise"));
+    }
+
+    network.set_record_keys(Some(vec!["mean".into()])).unwrap();
+    network
+        .input_data(vec![vec![0.2]], None, true, InputDataOptions::default())
+        .unwrap();
+    assert!(matches!(
+        network.surprise_by_node(),
+        Err(NetworkError::UnrecordedKey { node_idx: 0, .. })
+    ));
+}
+
+#[test]
+fn test_entropy_matches_gaussian_entropy() {
+    let entropy =
+        |precision: f64| 0.5 * (2.0 * std::f64::consts::PI * std::f64::consts::E / precision).ln();
+    let data: Vec<Vec<f64>> = [0.1, 0.5, -0.3, 1.2, 0.8, -0.6]
+        .iter()
+        .map(|&x| vec![x])
+        .collect();
+
+    let mut network =
+        Network::continuous_hgf("eHGF", 2, &[0.0, 1.0], &[1.0, 1.0], &[-3.0, -4.0], &[0.5])
+            .unwrap();
+    network
+        .input_data(data.clone(), None, true, InputDataOptions::default())
+        .unwrap();
+    for node_idx in 0..network.n_nodes() {
+        let recorded = network.get_entropy(node_idx).unwrap();
+        let traj = &network.node_trajectories.nodes[node_idx];
+        assert_eq!(recorded.len(), data.len());
+        for (t, &value) in recorded.iter().enumerate() {
+            assert_close(value, entropy(traj.precision()[t]), "posterior entropy");
+        }
+    }
+
+    network.set_entropy_source("prediction").unwrap();
+    network
+        .input_data(data, None, true, InputDataOptions::default())
+        .unwrap();
+    let traj = &network.node_trajectories.nodes[1];
+    for (t, &value) in network.get_entropy(1).unwrap().iter().enumerate() {
+        assert_close(
+            value,
+            entropy(traj.expected_precision()[t]),
+            "prior entropy",
+        );
+    }
+
+    for node_idx in 0..network.n_nodes() {
+        assert!(network
+            .recorded_keys(node_idx)
+            .unwrap()
+            .contains(&"entropy"));
+    }
+    assert!(matches!(
+        network.set_entropy_source("likelihood"),
+        Err(NetworkError::InvalidValue {
+            parameter: "entropy_source",
+            ..
+        })
+    ));
+    assert!(matches!(
+        network.get_entropy(7),
+        Err(NetworkError::MissingNode { node_idx: 7, .. })
+    ));
+}
+
+#[test]
+fn test_child_coupling_fn_changes_the_parent_update() {
+    let build = || {
+        let mut network = Network::new("eHGF");
+        network
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+    };
+    let observation = 2.0;
+
+    let mut identity = build();
+    identity
+        .input_data(
+            vec![vec![observation]],
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+    let mut squashed = build();
+    squashed.set_child_coupling_fn(1, 0, "tanh").unwrap();
+    squashed
+        .input_data(
+            vec![vec![observation]],
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+
+    // The child's own belief is untouched; the parent reads its prediction
+    // error through tanh, tanh(2) - tanh(0) instead of 2 - 0, with the same
+    // gain.
+    let child = |network: &Network| network.node_trajectories.nodes[0].expected_mean()[0];
+    assert_eq!(child(&identity), child(&squashed));
+    let step = |network: &Network| {
+        let parent = &network.node_trajectories.nodes[1];
+        parent.mean()[0] - parent.expected_mean()[0]
+    };
+    assert!(step(&squashed) < step(&identity));
+    assert_close(
+        step(&squashed) / step(&identity),
+        observation.tanh() / observation,
+        "parent step ratio",
+    );
+
+    // "linear" restores the identity.
+    let mut restored = build();
+    restored.set_child_coupling_fn(1, 0, "tanh").unwrap();
+    restored.set_child_coupling_fn(1, 0, "linear").unwrap();
+    restored
+        .input_data(
+            vec![vec![observation]],
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(step(&restored), step(&identity));
+
+    assert!(matches!(
+        squashed.set_child_coupling_fn(0, 1, "tanh"),
+        Err(NetworkError::InvalidEdge {
+            node_idx: 0,
+            target_idx: 1,
+            ..
+        })
+    ));
+    assert!(matches!(
+        squashed.set_child_coupling_fn(1, 0, "cubic"),
+        Err(NetworkError::InvalidValue {
+            parameter: "coupling_fn",
+            ..
+        })
+    ));
+    assert!(matches!(
+        squashed.set_child_coupling_fn(1, 5, "tanh"),
+        Err(NetworkError::MissingNode { node_idx: 5, .. })
+    ));
+}
+
+#[test]
+fn test_max_precision_caps_posterior_precision() {
+    // A parent with negligible volatility accumulates roughly one unit of
+    // precision per observation; the cap stops it at 5.
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(vec![0].into()),
+                additional_parameters: Some(HashMap::from([
+                    ("tonic_volatility".into(), -20.0),
+                    ("max_precision".into(), 5.0),
+                ])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    network
+        .input_data(vec![vec![0.5]; 20], None, true, InputDataOptions::default())
+        .unwrap();
+
+    let precision = network.node_trajectories.nodes[1].precision().to_vec();
+    assert!(precision[0] < 5.0);
+    assert!(precision.iter().all(|&p| p <= 5.0));
+    for (t, &p) in precision.iter().enumerate().skip(10) {
+        assert_close(p, 5.0, &format!("node1 precision t={}", t));
+    }
+}
+
+#[test]
+fn test_max_precision_caps_dp_cluster_precision() {
+    // A cluster given to a dp-state node gains the observation precision (4)
+    // at every step it explains; the cap stops it at 5.
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes(
+            "dp-state",
+            1,
+            AddNodesOptions {
+                additional_parameters: Some(HashMap::from([("precision".into(), 4.0)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(vec![0].into()),
+                additional_parameters: Some(HashMap::from([
+                    ("tonic_volatility".into(), -20.0),
+                    ("max_precision".into(), 5.0),
+                ])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    network
+        .input_data(vec![vec![0.0]; 5], None, true, InputDataOptions::default())
+        .unwrap();
+
+    assert_eq!(network.n_nodes(), 2);
+    let precision = network.node_trajectories.nodes[1].precision().to_vec();
+    assert!(precision.iter().all(|&p| p <= 5.0), "{:?}", precision);
+    assert_close(precision[4], 5.0, "cluster precision");
+}
+
+#[test]
+fn test_step_matches_input_data() {
+    // input (0) <- value parent (1), volatility parent (2)
+    fn build() -> Network {
+        let mut network = Network::new("eHGF");
+        network
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(vec![0].into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    volatility_children: Some(vec![0].into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+    }
+
+    let observations = [0.2, -0.4, 1.3, 0.7, 0.1];
+    let time_steps = [1.0, 0.5, 2.0, 1.0, 0.25];
+
+    let mut batch = build();
+    batch
+        .input_data(
+            observations.iter().map(|&u| vec![u]).collect(),
+            Some(time_steps.to_vec()),
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+
+    let mut online = build();
+    for (&u, &dt) in observations.iter().zip(&time_steps) {
+        online.step(&[u], dt).unwrap();
+    }
+
+    assert_eq!(
+        online.node_trajectories.time_steps,
+        batch.node_trajectories.time_steps
+    );
+    for (online_traj, batch_traj) in online
+        .node_trajectories
+        .nodes
+        .iter()
+        .zip(&batch.node_trajectories.nodes)
+    {
+        assert_eq!(online_traj.n_time, observations.len());
+        // Compare bit patterns: unused fields are recorded as NaN.
+        let bits = |data: &[f64]| data.iter().map(|v| v.to_bits()).collect::<Vec<_>>();
+        assert_eq!(bits(&online_traj.data), bits(&batch_traj.data));
+        assert_eq!(
+            online_traj.value_coupling_parents.data,
+            batch_traj.value_coupling_parents.data
+        );
+    }
+}
+
+#[test]
+fn test_add_nodes_initial_parameters() {
+    // Three-level HGF with the MATLAB toolbox defaults (tapas_hgf_config):
+    // μ₀ = (0, 1), σ₀ = (0.1, 1), ω = (-3, -6), set through add_nodes only.
+    let mut network = Network::new("standard");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                additional_parameters: Some(HashMap::from([
+                    ("mean".into(), 0.0),
+                    ("precision".into(), 10.0),
+                    ("tonic_volatility".into(), -3.0),
+                ])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                volatility_children: Some(1.into()),
+                additional_parameters: Some(HashMap::from([
+                    ("mean".into(), 1.0),
+                    ("precision".into(), 1.0),
+                    ("tonic_volatility".into(), -6.0),
+                ])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    let states = &network.attributes.states;
+    assert_eq!(
+        (
+            states[1].mean,
+            states[1].precision,
+            states[1].tonic_volatility
+        ),
+        (0.0, 10.0, -3.0)
+    );
+    assert_eq!(
+        (
+            states[2].mean,
+            states[2].precision,
+            states[2].tonic_volatility
+        ),
+        (1.0, 1.0, -6.0)
+    );
+
+    // Same network with the defaults patched after insertion.
+    let mut patched = Network::new("standard");
+    patched
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    patched
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    patched
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                volatility_children: Some(1.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    patched.attributes.states[1].precision = 10.0;
+    patched.attributes.states[1].tonic_volatility = -3.0;
+    patched.attributes.states[2].mean = 1.0;
+    patched.attributes.states[2].tonic_volatility = -6.0;
+
+    let data: Vec<Vec<f64>> = (0..30).map(|i| vec![(i as f64 * 0.4).sin()]).collect();
+    network
+        .input_data(data.clone(), None, true, InputDataOptions::default())
+        .unwrap();
+    patched
+        .input_data(data, None, true, InputDataOptions::default())
+        .unwrap();
+    // Compare bit patterns: unused fields are recorded as NaN.
+    let bits = |data: &[f64]| data.iter().map(|v| v.to_bits()).collect::<Vec<_>>();
+    for (a, b) in network
+        .node_trajectories
+        .nodes
+        .iter()
+        .zip(&patched.node_trajectories.nodes)
+    {
+        assert_eq!(bits(&a.data), bits(&b.data));
+    }
+}
+
+#[test]
+fn test_add_nodes_rejects_irrelevant_parameters() {
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+
+    // Volatile-state nodes have no value-level tonic volatility.
+    let err = network
+        .add_nodes(
+            "volatile-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                additional_parameters: Some(HashMap::from([
+                    ("tonic_volatility".into(), -2.0),
+                    ("tonic_volatility_vol".into(), -2.0),
+                ])),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+    let msg = err.to_string();
+    assert!(msg.contains("`tonic_volatility`"), "{}", msg);
+    assert!(msg.contains("volatile-state"), "{}", msg);
+
+    // Nothing was inserted by the failed call.
+    assert_eq!(network.edges.len(), 1);
+    assert!(network.edges[0].value_parents.is_none());
+
+    assert!(network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                additional_parameters: Some(HashMap::from([("temperature".into(), 2.0)])),
+                ..Default::default()
+            }
+        )
+        .is_err());
+}
+
+#[test]
+fn test_sigmoid_coupled_parent_drift_saturates() {
+    // Input node 0 with a value parent (1) held at mean m. The parent drives the
+    // child's drift through g, so the child's prediction is Δt · α · g(m).
+    let predicted_child_mean = |coupling_fn: Option<String>, m: f64| {
+        let mut network = Network::new("eHGF");
+        network
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    coupling_fn,
+                    additional_parameters: Some(HashMap::from([
+                        ("mean".into(), m),
+                        ("expected_mean".into(), m),
+                    ])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network.step(&[0.0], 1.0).unwrap();
+        network.node_trajectories.nodes[0].expected_mean()[0]
+    };
+
+    let mut previous = 0.0;
+    for m in [1.0, 5.0, 10.0, 30.0] {
+        let sigmoid = predicted_child_mean(Some("sigmoid".into()), m);
+        assert_close(sigmoid, 1.0 / (1.0 + (-m).exp()), "sigmoid drift");
+        assert!(sigmoid > previous && sigmoid < 1.0);
+        previous = sigmoid;
+
+        // The linear coupling keeps growing with the parent.
+        assert_close(predicted_child_mean(None, m), m, "linear drift");
+    }
+    assert_close(previous, 1.0, "saturated drift");
+}
+
+#[test]
+fn test_phased_step_matches_input_data() {
+    // input (0) <- value parent (1) <- volatility parent (2)
+    fn build() -> Network {
+        let mut network = Network::new("standard");
+        network
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    volatility_children: Some(1.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+    }
+    // Compare bit patterns: unused fields are NaN.
+    let bits = |network: &Network| -> Vec<u64> {
+        network
+            .attributes
+            .states
+            .iter()
+            .flat_map(|state| TRAJECTORY_FIELDS.iter().map(|f| state.get(f).unwrap()))
+            .map(f64::to_bits)
+            .collect()
+    };
+
+    let mut batch = build();
+    batch
+        .input_data(
+            vec![vec![0.8]],
+            Some(vec![2.0]),
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+
+    let mut phased = build();
+    phased.run_predictions(2.0).unwrap();
+    // The predictions are in place, the observation is not yet.
+    let recorded = &batch.node_trajectories.nodes[1];
+    assert_eq!(
+        phased.attributes.states[1].expected_mean,
+        recorded.expected_mean()[0]
+    );
+    assert_eq!(
+        phased.attributes.states[1].expected_precision,
+        recorded.expected_precision()[0]
+    );
+    assert_eq!(phased.attributes.states[0].mean, 0.0);
+
+    phased.set_observations(&[0.8]).unwrap();
+    assert_eq!(phased.attributes.states[0].mean, 0.8);
+    assert_eq!(phased.attributes.states[1].mean, 0.0);
+
+    phased.run_updates(2.0).unwrap();
+    assert_eq!(bits(&phased), bits(&batch));
+
+    let mut one_call = build();
+    one_call.run_step(&[0.8], 2.0).unwrap();
+    assert_eq!(bits(&one_call), bits(&batch));
+    assert!(one_call.node_trajectories.nodes.is_empty());
+
+    assert!(one_call.set_observations(&[0.1, 0.2]).is_err());
+}
+
+/// `n_branches` inputs, each with its own value parent, all sharing one
+/// volatility parent (the last node).
+fn build_shared_volatility_parent(volatility_updates: &str, n_branches: usize) -> Network {
+    let mut network = Network::new(volatility_updates);
+    network
+        .add_nodes("continuous-state", n_branches, AddNodesOptions::default())
+        .unwrap();
+    for input_idx in 0..n_branches {
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(input_idx.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+    }
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                volatility_children: Some((n_branches..2 * n_branches).collect::<Vec<_>>().into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+}
+
+#[test]
+fn test_volatility_parent_with_two_children() {
+    for volatility_updates in ["standard", "eHGF"] {
+        let mut single = build_shared_volatility_parent(volatility_updates, 1);
+        let mut shared = build_shared_volatility_parent(volatility_updates, 2);
+        single
+            .input_data(vec![vec![0.9]], None, true, InputDataOptions::default())
+            .unwrap();
+        // Identical data on both branches: each child contributes what the
+        // single child does, and each keeps its full volatility PE.
+        shared
+            .input_data(
+                vec![vec![0.9, 0.9]],
+                None,
+                true,
+                InputDataOptions::default(),
+            )
+            .unwrap();
+
+        let single_child = &single.node_trajectories.nodes[1];
+        let shared_child = &shared.node_trajectories.nodes[2];
+        assert_eq!(
+            shared_child.volatility_prediction_error()[0],
+            single_child.volatility_prediction_error()[0]
+        );
+
+        let single_parent = &single.node_trajectories.nodes[2];
+        let shared_parent = &shared.node_trajectories.nodes[4];
+        assert_eq!(
+            shared_parent.expected_precision()[0],
+            single_parent.expected_precision()[0]
+        );
+        if volatility_updates == "standard" {
+            let prior_precision = single_parent.expected_precision()[0];
+            assert_close(
+                shared_parent.precision()[0] - prior_precision,
+                2.0 * (single_parent.precision()[0] - prior_precision),
+                "standard precision increment",
+            );
+        } else {
+            // The eHGF mean step is weighted by the prior precision, so the two
+            // contributions add up; its precision step is then re-expanded at
+            // the moved mean and is not additive.
+            let prior_mean = single_parent.expected_mean()[0];
+            assert_close(
+                shared_parent.mean()[0] - prior_mean,
+                2.0 * (single_parent.mean()[0] - prior_mean),
+                "eHGF mean increment",
+            );
+        }
+    }
+
+    // Distinct data: the standard posterior is the prior plus the sum of the
+    // two children's volatility contributions (κ = 1).
+    let mut shared = build_shared_volatility_parent("standard", 2);
+    shared
+        .input_data(
+            vec![vec![0.9, -0.3]],
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+    let parent = &shared.node_trajectories.nodes[4];
+    let (mut precision, mut weighted_pe) = (parent.expected_precision()[0], 0.0);
+    for child_idx in [2, 3] {
+        let child = &shared.node_trajectories.nodes[child_idx];
+        let gamma = child.effective_precision()[0];
+        let delta = child.volatility_prediction_error()[0];
+        precision += 0.5 * gamma * gamma + gamma * gamma * delta - 0.5 * gamma * delta;
+        weighted_pe += gamma * delta;
+    }
+    assert_close(parent.precision()[0], precision, "posterior precision");
+    assert_close(
+        parent.mean()[0],
+        parent.expected_mean()[0] + weighted_pe / (2.0 * precision),
+        "posterior mean",
+    );
+}
+
+#[test]
+fn test_unbounded_falls_back_to_ehgf_with_two_volatility_children() {
+    // Under "unbounded", a volatility parent with two children gets the eHGF
+    // posterior update, while the other nodes keep the unbounded scheme.
+    let mut unbounded = build_shared_volatility_parent("unbounded", 2);
+    let mut ehgf = build_shared_volatility_parent("eHGF", 2);
+    unbounded.set_update_sequence();
+    assert!(unbounded
+        .update_sequence
+        .updates
+        .contains(&(4, UpdateStep::PosteriorContinuousEhgf)));
+    for network in [&mut unbounded, &mut ehgf] {
+        network
+            .input_data(
+                vec![vec![0.9, -0.3], vec![0.1, 0.4]],
+                None,
+                true,
+                InputDataOptions::default(),
+            )
+            .unwrap();
+    }
+    for node_idx in 0..5 {
+        let (a, b) = (
+            &unbounded.node_trajectories.nodes[node_idx],
+            &ehgf.node_trajectories.nodes[node_idx],
+        );
+        assert_eq!(
+            a.mean().to_vec(),
+            b.mean().to_vec(),
+            "node {} mean",
+            node_idx
+        );
+        assert_eq!(
+            a.precision().to_vec(),
+            b.precision().to_vec(),
+            "node {} precision",
+            node_idx
+        );
+    }
+
+    let mut single = build_shared_volatility_parent("unbounded", 1);
+    single.set_update_sequence();
+    assert!(single
+        .update_sequence
+        .updates
+        .contains(&(2, UpdateStep::PosteriorContinuousUnbounded)));
+}
+
+#[test]
+fn test_set_update_type_matches_fresh_networks() {
+    // Flipping the update type on one network gives the same trajectories as
+    // networks built with that type from the start.
+    let data = [0.2, -0.4, 1.1, 0.3, 2.5, -0.8, 0.0, 0.6];
+    let build = |update_type: &str| {
+        Network::continuous_hgf(
+            update_type,
+            2,
+            &[0.0, 0.0],
+            &[1.0, 1.0],
+            &[-2.0, -4.0],
+            &[1.0],
+        )
+        .unwrap()
+    };
+
+    let mut network = build("eHGF");
+    let initial_state = network.get_state();
+    let mut means = Vec::new();
+    for update_type in ["standard", "unbounded", "eHGF"] {
+        network.load_state(&initial_state).unwrap();
+        network.set_update_type(update_type).unwrap();
+        network
+            .input_data(
+                data.iter().map(|&x| vec![x]).collect(),
+                None,
+                true,
+                InputDataOptions::default(),
+            )
+            .unwrap();
+
+        let mut fresh = build(update_type);
+        fresh
+            .input_data(
+                data.iter().map(|&x| vec![x]).collect(),
+                None,
+                true,
+                InputDataOptions::default(),
+            )
+            .unwrap();
+        for node_idx in 0..3 {
+            let (a, b) = (
+                &network.node_trajectories.nodes[node_idx],
+                &fresh.node_trajectories.nodes[node_idx],
+            );
+            assert_eq!(a.mean().to_vec(), b.mean().to_vec(), "{update_type} mean");
+            assert_eq!(
+                a.precision().to_vec(),
+                b.precision().to_vec(),
+                "{update_type} precision"
+            );
+        }
+        means.push(network.node_trajectories.nodes[2].mean().to_vec());
+    }
+    assert_ne!(means[0], means[2], "standard and eHGF should differ");
+
+    assert!(matches!(
+        network.set_update_type("ehgf"),
+        Err(NetworkError::InvalidValue {
+            parameter: "update_type",
+            ..
+        })
+    ));
+    assert_eq!(network.volatility_updates, "eHGF");
+}
+
+#[test]
+fn test_unbounded_rejects_two_volatility_children() {
+    // A hand-built sequence that runs the unbounded update on a shared
+    // volatility parent is rejected before any step runs.
+    let mut shared = build_shared_volatility_parent("unbounded", 2);
+    shared.set_update_sequence();
+    for (idx, step) in shared.update_sequence.updates.iter_mut() {
+        if *idx == 4 {
+            *step = UpdateStep::PosteriorContinuousUnbounded;
+        }
+    }
+    let err = shared
+        .input_data(
+            vec![vec![0.9, -0.3]],
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        NetworkError::UnsupportedStructure {
+            node_idx: 4,
+            step: "posterior_update_continuous_state_node_unbounded",
+            reason: "the unbounded update needs exactly one volatility child",
+        }
+    );
+}
+
+/// Three-level HGF: input 0 (precision 100) ← value parent 1 (tonic
+/// volatility `omega`) ← volatility parent 2.
+fn build_sampling_network(omega: f64) -> Network {
+    let mut network = Network::new("standard");
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                additional_parameters: Some(HashMap::from([("precision".into(), 100.0)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                additional_parameters: Some(HashMap::from([("tonic_volatility".into(), omega)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                volatility_children: Some(1.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+}
+
+#[test]
+fn test_sample_is_reproducible_and_leaves_network_unchanged() {
+    let mut network = build_sampling_network(-2.0);
+    network.set_update_sequence();
+    let before = format!("{:?}", network.attributes);
+
+    let samples = network.sample(50, None, Some(7), 0.0).unwrap();
+    assert_eq!(format!("{:?}", network.attributes), before);
+    assert_eq!(samples, network.sample(50, None, Some(7), 0.0).unwrap());
+    assert_ne!(samples, network.sample(50, None, Some(8), 0.0).unwrap());
+
+    assert_eq!(samples.observations.len(), 50);
+    assert_eq!(samples.states.len(), 50);
+    for (observation, states) in samples.observations.iter().zip(&samples.states) {
+        assert_eq!(observation, &vec![states[0]]);
+        assert_eq!(states.len(), 3);
+        assert!(states.iter().all(|value| value.is_finite()));
+    }
+
+    assert_eq!(
+        network.sample(3, Some(&[1.0, 1.0]), None, 0.0).unwrap_err(),
+        NetworkError::DimensionMismatch {
+            what: "time steps (one per sampled step)",
+            expected: 3,
+            found: 2,
+        }
+    );
+}
+
+#[test]
+fn test_sample_observation_noise() {
+    let mut network = build_sampling_network(-2.0);
+    let variance = |values: Vec<f64>| {
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64
+    };
+
+    let clean = network.sample(500, None, Some(3), 0.0).unwrap();
+    let mut previous = 0.0;
+    for noise in [0.5, 2.0, 8.0] {
+        let noisy = network.sample(500, None, Some(3), noise).unwrap();
+        // Same latent trajectory, observations offset by the noise only.
+        assert_eq!(noisy.states, clean.states);
+        let residuals: Vec<f64> = noisy
+            .observations
+            .iter()
+            .zip(&clean.observations)
+            .map(|(noisy, clean)| noisy[0] - clean[0])
+            .collect();
+        let residual_variance = variance(residuals);
+        assert!(
+            (residual_variance / noise.powi(2) - 1.0).abs() < 0.2,
+            "noise {}: residual variance {}",
+            noise,
+            residual_variance
+        );
+
+        let observed_variance = variance(noisy.observations.iter().map(|o| o[0]).collect());
+        assert!(observed_variance > previous);
+        previous = observed_variance;
+    }
+
+    for noise in [-1.0, f64::NAN, f64::INFINITY] {
+        assert!(matches!(
+            network.sample(10, None, Some(3), noise),
+            Err(NetworkError::InvalidValue {
+                parameter: "observation_noise",
+                ..
+            })
+        ));
+    }
+}
+
+#[test]
+fn test_sampled_data_is_best_explained_by_the_generating_parameters() {
+    let samples = build_sampling_network(-2.0)
+        .sample(300, None, Some(42), 0.0)
+        .unwrap();
+
+    let surprise = |omega: f64| {
+        let mut network = build_sampling_network(omega);
+        network
+            .input_data(
+                samples.observations.clone(),
+                None,
+                true,
+                InputDataOptions::default(),
+            )
+            .unwrap();
+        -network.get_free_energy().unwrap().accuracy
+    };
+    let generating = surprise(-2.0);
+    for perturbed in [-6.0, 2.0] {
+        assert!(
+            generating < surprise(perturbed),
+            "ω = {}: {} >= {}",
+            perturbed,
+            generating,
+            surprise(perturbed)
+        );
+    }
+}
+
+#[test]
+fn test_continuous_hgf_builds_the_canonical_chain() {
+    let network = Network::continuous_hgf(
+        "standard",
+        3,
+        &[0.0, 1.0, 2.0],
+        &[10.0, 1.0, 0.5],
+        &[-3.0, -6.0, -7.0],
+        &[0.5, 2.0],
+    )
+    .unwrap();
+
+    assert_eq!(network.edges.len(), 4);
+    assert_eq!(network.inputs, vec![0]);
+    assert_eq!(network.edges[0].value_parents, Some(vec![1]));
+    assert_eq!(network.edges[1].volatility_parents, Some(vec![2]));
+    assert_eq!(network.edges[2].volatility_parents, Some(vec![3]));
+    assert_eq!(network.edges[3].volatility_parents, None);
+    for node_idx in 1..4 {
+        assert_eq!(network.edges[node_idx].value_parents, None);
+    }
+
+    let states = &network.attributes.states;
+    for (level, node_idx) in (1..4).enumerate() {
+        assert_eq!(states[node_idx].mean, [0.0, 1.0, 2.0][level]);
+        assert_eq!(states[node_idx].precision, [10.0, 1.0, 0.5][level]);
+        assert_eq!(states[node_idx].tonic_volatility, [-3.0, -6.0, -7.0][level]);
+    }
+    let vectors = &network.attributes.vectors;
+    assert_eq!(vectors[1].volatility_coupling_parents, vec![0.5]);
+    assert_eq!(vectors[2].volatility_coupling_children, vec![0.5]);
+    assert_eq!(vectors[2].volatility_coupling_parents, vec![2.0]);
+    assert_eq!(vectors[3].volatility_coupling_children, vec![2.0]);
+
+    assert_eq!(
+        Network::continuous_hgf("standard", 2, &[0.0, 1.0], &[1.0, 1.0], &[-4.0, -4.0], &[])
+            .unwrap_err(),
+        NetworkError::DimensionMismatch {
+            what: "volatility couplings (one per pair of levels)",
+            expected: 1,
+            found: 0,
+        }
+    );
+}
+
+#[test]
+fn test_forecast_matches_the_next_prediction() {
+    let data: Vec<Vec<f64>> = (0..20).map(|t| vec![(t as f64 * 0.4).sin()]).collect();
+
+    let mut network = build_sampling_network(-2.0);
+    network
+        .input_data(data[..19].to_vec(), None, true, InputDataOptions::default())
+        .unwrap();
+    let attributes = format!("{:?}", network.attributes);
+    let n_recorded = network.node_trajectories.time_steps.len();
+
+    let forecast = network.forecast(3, Some(&[1.0, 0.5, 2.0])).unwrap();
+    assert_eq!(format!("{:?}", network.attributes), attributes);
+    assert_eq!(network.node_trajectories.time_steps.len(), n_recorded);
+    assert_eq!(forecast.time_steps, vec![1.0, 0.5, 2.0]);
+
+    // The one-step forecast is the prediction of the next step of a normal
+    // run, which does not depend on the observation yet.
+    let mut full = build_sampling_network(-2.0);
+    full.input_data(data, None, true, InputDataOptions::default())
+        .unwrap();
+    for node_idx in 0..3 {
+        let (forecast, full) = (
+            &forecast.nodes[node_idx],
+            &full.node_trajectories.nodes[node_idx],
+        );
+        assert_eq!(forecast.expected_mean()[0], full.expected_mean()[19]);
+        assert_eq!(
+            forecast.expected_precision()[0],
+            full.expected_precision()[19]
+        );
+        // The prediction becomes the state the next step starts from.
+        assert_eq!(forecast.mean()[0], forecast.expected_mean()[0]);
+        assert_eq!(forecast.precision()[0], forecast.expected_precision()[0]);
+    }
+    // Without new observations the belief about x₁ only loses precision.
+    let x1 = &forecast.nodes[1];
+    assert!(x1.precision()[2] < x1.precision()[1] && x1.precision()[1] < x1.precision()[0]);
+}
+
+#[test]
+fn test_checkpoint_replays_and_counterfactuals() {
+    let data: Vec<f64> = (0..30).map(|t| (t as f64 * 0.3).sin() * 2.0).collect();
+    let run_tail = |network: &mut Network| {
+        for &observation in &data[15..] {
+            network.step(&[observation], 1.0).unwrap();
+        }
+    };
+
+    let mut network = build_sampling_network(-2.0);
+    for &observation in &data[..15] {
+        network.step(&[observation], 1.0).unwrap();
+    }
+    let snapshot = network.checkpoint();
+    run_tail(&mut network);
+    let original: Vec<Vec<f64>> = (0..3)
+        .map(|node_idx| network.node_trajectories.nodes[node_idx].mean().to_vec())
+        .collect();
+
+    // Replaying the same tail from the checkpoint reproduces the run.
+    network.restore(&snapshot).unwrap();
+    assert_eq!(network.node_trajectories.time_steps.len(), 15);
+    run_tail(&mut network);
+    for (node, original_mean) in network.node_trajectories.nodes[..3].iter().zip(&original) {
+        assert_eq!(&node.mean().to_vec(), original_mean);
+    }
+
+    // A different tonic volatility only changes the beliefs after the
+    // checkpoint.
+    network.restore(&snapshot).unwrap();
+    network.attributes.states[1].tonic_volatility = 1.0;
+    run_tail(&mut network);
+    let counterfactual = network.node_trajectories.nodes[1].mean().to_vec();
+    assert_eq!(counterfactual[..15], original[1][..15]);
+    assert!(counterfactual[15..]
+        .iter()
+        .zip(&original[1][15..])
+        .all(|(a, b)| a != b));
+
+    let other = build_sampling_network(-2.0).checkpoint();
+    let mut smaller = Network::new("standard");
+    assert!(smaller.restore(&other).is_err());
+}
+
+#[test]
+fn test_load_state_resumes_from_a_saved_state() {
+    let data: Vec<f64> = (0..10).map(|t| (t as f64 * 0.7).cos()).collect();
+    let mut network = build_sampling_network(-2.0);
+    for &observation in &data[..5] {
+        network.step(&[observation], 1.0).unwrap();
+    }
+    let state = network.get_state();
+    let run_last_five = |network: &mut Network| -> Vec<Vec<f64>> {
+        for &observation in &data[5..] {
+            network.step(&[observation], 1.0).unwrap();
+        }
+        (0..3)
+            .map(|node_idx| {
+                let mean = network.node_trajectories.nodes[node_idx].mean().to_vec();
+                mean[mean.len() - 5..].to_vec()
+            })
+            .collect()
+    };
+    let original = run_last_five(&mut network);
+
+    // The state goes back to the checkpoint and the last five steps replay
+    // identically; the trajectories are kept and grow on.
+    network.load_state(&state).unwrap();
+    assert_eq!(network.attributes.states[2].mean, state.floats[2]["mean"]);
+    assert_eq!(run_last_five(&mut network), original);
+    assert_eq!(network.node_trajectories.time_steps.len(), 15);
+
+    // A network with other nodes is rejected and left unchanged.
+    let mut smaller = Network::new("standard");
+    assert!(matches!(
+        smaller.load_state(&state),
+        Err(NetworkError::DimensionMismatch { .. })
+    ));
+    let mut unknown = state.clone();
+    unknown.floats[1].insert("not_a_field".into(), 1.0);
+    assert!(matches!(
+        network.load_state(&unknown),
+        Err(NetworkError::InvalidParameter { .. })
+    ));
+    let mut resized = state.clone();
+    resized.vectors[1].insert("value_coupling_children".into(), vec![1.0; 3]);
+    let mean_before = network.attributes.states[1].mean;
+    resized.floats[1].insert("mean".into(), mean_before + 1.0);
+    assert!(network.load_state(&resized).is_err());
+    assert_eq!(network.attributes.states[1].mean, mean_before);
+}
+
+#[test]
+fn test_metadata_round_trip() {
+    let data: Vec<Vec<f64>> = (0..20).map(|t| vec![(t as f64 * 0.4).sin()]).collect();
+    let mut network = build_sampling_network(-2.0);
+    let mut unlabelled = build_sampling_network(-2.0);
+    network.set_metadata(1, "region", "insula").unwrap();
+    network.set_metadata(1, "condition", "A").unwrap();
+    network.set_metadata(2, "region", "ACC").unwrap();
+    network.set_metadata(2, "region", "dlPFC").unwrap();
+    assert_eq!(network.get_metadata(1, "region"), Some("insula"));
+    assert_eq!(network.get_metadata(2, "region"), Some("dlPFC"));
+    assert_eq!(network.get_metadata(0, "region"), None);
+    assert!(matches!(
+        network.set_metadata(3, "region", "V1"),
+        Err(NetworkError::MissingNode { node_idx: 3, .. })
+    ));
+
+    // The labels do not change the beliefs.
+    network
+        .input_data(data.clone(), None, true, InputDataOptions::default())
+        .unwrap();
+    unlabelled
+        .input_data(data, None, true, InputDataOptions::default())
+        .unwrap();
+    assert!(network == unlabelled);
+
+    // They are saved with the state and restored into a fresh network.
+    let state = network.get_state();
+    assert_eq!(state.metadata.len(), 3);
+    assert!(state.metadata[0].is_empty());
+    assert_eq!(state.metadata[1]["condition"], "A");
+    let mut restored = build_sampling_network(-2.0);
+    restored.load_state(&state).unwrap();
+    assert_eq!(restored.metadata, network.metadata);
+
+    // A state without metadata keeps the current labels.
+    let without_metadata = NetworkState {
+        metadata: Vec::new(),
+        ..state
+    };
+    restored.load_state(&without_metadata).unwrap();
+    assert_eq!(restored.get_metadata(1, "condition"), Some("A"));
+}
+
+#[test]
+fn test_warm_restart_matches_a_single_run() {
+    let data: Vec<Vec<f64>> = (0..200)
+        .map(|t| vec![(t as f64 * 0.1).sin() + 0.3 * (t as f64 * 1.3).cos()])
+        .collect();
+    let mut single_run = build_sampling_network(-2.0);
+    single_run
+        .input_data(data.clone(), None, true, InputDataOptions::default())
+        .unwrap();
+
+    // Process the first half, keep the final beliefs, and continue the second
+    // half in a freshly built network.
+    let mut first_half = build_sampling_network(-2.0);
+    first_half
+        .input_data(
+            data[..100].to_vec(),
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+    let state = first_half.get_state();
+    let mut second_half = build_sampling_network(-2.0);
+    second_half.load_state(&state).unwrap();
+    second_half
+        .input_data(
+            data[100..].to_vec(),
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+
+    for node_idx in 0..3 {
+        let single = &single_run.node_trajectories.nodes[node_idx];
+        let resumed = &second_half.node_trajectories.nodes[node_idx];
+        for &field in TRAJECTORY_FIELDS {
+            // Compared bit for bit, so that unused NaN fields match too.
+            let bits = |column: Vec<f64>| column.into_iter().map(f64::to_bits).collect::<Vec<_>>();
+            let single = bits(single.column(field).unwrap().to_vec());
+            let resumed = bits(resumed.column(field).unwrap().to_vec());
+            assert_eq!(single[100..], resumed[..], "node {} {}", node_idx, field);
+        }
+    }
+}
+
+#[test]
+fn test_trajectory_slice_copies_the_requested_rows() {
+    let data: Vec<Vec<f64>> = (0..20).map(|t| vec![(t as f64 * 0.3).sin()]).collect();
+    let mut network = build_sampling_network(-2.0);
+    network
+        .input_data(data, None, true, InputDataOptions::default())
+        .unwrap();
+
+    let sliced = network
+        .get_node_trajectories_slice(-15, Some(-2), 3)
+        .unwrap();
+    let rows = [5, 8, 11, 14, 17];
+    assert_eq!(sliced.time_steps.len(), rows.len());
+    for node_idx in 0..3 {
+        let full = &network.node_trajectories.nodes[node_idx];
+        let part = &sliced.nodes[node_idx];
+        assert_eq!(part.n_time, rows.len());
+        for &field in TRAJECTORY_FIELDS {
+            let full = full.column(field).unwrap().to_vec();
+            let part = part.column(field).unwrap().to_vec();
+            for (i, &t) in rows.iter().enumerate() {
+                assert_eq!(part[i].to_bits(), full[t].to_bits(), "{}", field);
+            }
+        }
+        let full = full.vector("value_coupling_children").unwrap();
+        let part = part.vector("value_coupling_children").unwrap();
+        assert_eq!(part.is_empty(), full.is_empty());
+        for (i, &t) in rows.iter().enumerate().filter(|_| !full.is_empty()) {
+            assert_eq!(part[i], full[t]);
+        }
+    }
+
+    assert_eq!(
+        network
+            .get_node_trajectories_slice(0, None, 1)
+            .unwrap()
+            .time_steps
+            .len(),
+        20
+    );
+    assert!(network
+        .get_node_trajectories_slice(4, Some(4), 1)
+        .unwrap()
+        .time_steps
+        .is_empty());
+    for (start, stop, step) in [
+        (0, Some(21), 1),
+        (-21, None, 1),
+        (5, Some(2), 1),
+        (0, None, 0),
+    ] {
+        assert!(matches!(
+            network.get_node_trajectories_slice(start, stop, step),
+            Err(NetworkError::InvalidTimeRange { n_time: 20, .. })
+        ));
+    }
+}
+
+#[test]
+fn test_get_trajectory_matches_the_full_export() {
+    let mut network = build_sampling_network(-2.0);
+    assert_eq!(network.recorded_keys(1).unwrap(), Vec::<&str>::new());
+    network
+        .input_data(
+            vec![vec![0.3], vec![-0.2], vec![0.5]],
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+
+    let keys = network.recorded_keys(1).unwrap();
+    assert!(keys.contains(&"expected_mean"));
+    assert!(keys.contains(&"value_coupling_children"));
+    let traj = &network.node_trajectories.nodes[1];
+    match network.get_trajectory(1, "expected_mean").unwrap() {
+        RecordedTrajectory::Scalar(column) => {
+            assert_eq!(column.to_vec(), traj.expected_mean().to_vec())
+        }
+        RecordedTrajectory::Vector(_) => panic!("expected_mean is a scalar"),
+    }
+    match network
+        .get_trajectory(1, "value_coupling_children")
+        .unwrap()
+    {
+        RecordedTrajectory::Vector(vector) => {
+            assert_eq!(vector.len(), 3);
+            assert_eq!(
+                vector.data,
+                traj.vector("value_coupling_children").unwrap().data
+            );
+        }
+        RecordedTrajectory::Scalar(_) => panic!("value_coupling_children is a vector"),
+    }
+
+    assert!(matches!(
+        network.get_trajectory(1, "not_a_key"),
+        Err(NetworkError::UnrecordedKey { node_idx: 1, .. })
+    ));
+    assert!(matches!(
+        network.get_trajectory(7, "mean"),
+        Err(NetworkError::MissingNode { node_idx: 7, .. })
+    ));
+    network.set_record_keys(Some(vec!["mean".into()])).unwrap();
+    network
+        .input_data(vec![vec![0.1]], None, true, InputDataOptions::default())
+        .unwrap();
+    assert_eq!(network.recorded_keys(1).unwrap(), vec!["mean"]);
+    assert!(network.get_trajectory(1, "expected_mean").is_err());
+}
+
+#[test]
+fn test_predictive_intervals_half_width() {
+    let mut network = build_sampling_network(-2.0);
+    assert!(network.predictive_intervals(1, 1.96).is_err());
+    let data: Vec<Vec<f64>> = (0..40).map(|t| vec![(t as f64 * 0.4).sin()]).collect();
+    network
+        .input_data(data, None, true, InputDataOptions::default())
+        .unwrap();
+
+    for node_idx in 0..3 {
+        let (lower, upper) = network.predictive_intervals(node_idx, 1.96).unwrap();
+        let traj = &network.node_trajectories.nodes[node_idx];
+        assert_eq!(lower.len(), 40);
+        for t in [0, 7, 23, 39] {
+            let half_width = 1.96 / traj.expected_precision()[t].sqrt();
+            assert_close((upper[t] - lower[t]) / 2.0, half_width, "half width");
+            assert_close(
+                (upper[t] + lower[t]) / 2.0,
+                traj.expected_mean()[t],
+                "centre",
+            );
+        }
+    }
+
+    assert!(matches!(
+        network.predictive_intervals(9, 1.0),
+        Err(NetworkError::MissingNode { node_idx: 9, .. })
+    ));
+    network
+        .set_record_keys(Some(vec!["expected_mean".into()]))
+        .unwrap();
+    network
+        .input_data(vec![vec![0.1]], None, true, InputDataOptions::default())
+        .unwrap();
+    assert!(matches!(
+        network.predictive_intervals(1, 1.0),
+        Err(NetworkError::UnrecordedKey { ref key, .. }) if key == "expected_precision"
+    ));
+}
+
+#[test]
+fn test_run_prior_follows_the_drift() {
+    let mut network = Network::new("standard");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                additional_parameters: Some(HashMap::from([
+                    ("mean".into(), 1.0),
+                    ("tonic_drift".into(), 0.5),
+                ])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    network.run_prior(5, 2.0).unwrap();
+    assert_eq!(network.node_trajectories.time_steps, vec![2.0; 5]);
+    let drifting = &network.node_trajectories.nodes[1];
+    for t in 0..5 {
+        assert_close(
+            drifting.expected_mean()[t],
+            1.0 + (t + 1) as f64 * 2.0 * 0.5,
+            "expected mean",
+        );
+    }
+    // The child follows its parent's prediction and the state is kept.
+    assert_close(
+        network.node_trajectories.nodes[0].expected_mean()[4],
+        2.0 * drifting.expected_mean()[4],
+        "child expected mean",
+    );
+    assert_eq!(
+        network.attributes.states[1].mean,
+        drifting.expected_mean()[4]
+    );
+}
+
+#[test]
+fn test_precision_parent_tracks_the_observation_noise() {
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                additional_parameters: Some(HashMap::from([("tonic_volatility".into(), -2.0)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network.add_precision_parent(1, 0).unwrap();
+    assert_eq!(network.inputs, vec![0]);
+    assert_eq!(network.edges[0].precision_parents, Some(vec![1]));
+
+    // Self-loops and repeated edges are rejected, leaving the edges as they
+    // were.
+    assert_eq!(
+        network.add_precision_parent(0, 0),
+        Err(NetworkError::InvalidEdge {
+            node_idx: 0,
+            edge: "precision_children",
+            target_idx: 0,
+            step: "add_precision_parent",
+            reason: "cannot list its own node",
+        })
+    );
+    assert_eq!(
+        network.add_precision_parent(1, 0),
+        Err(NetworkError::InvalidEdge {
+            node_idx: 1,
+            edge: "precision_children",
+            target_idx: 0,
+            step: "add_precision_parent",
+            reason: "already lists node",
+        })
+    );
+    assert_eq!(network.edges[0].precision_parents, Some(vec![1]));
+    assert_eq!(network.edges[1].precision_children, Some(vec![0]));
+
+    // Zero-mean observations whose spread jumps from 0.1 to 2 and back.
+    let amplitude = |t: usize| if (100..200).contains(&t) { 2.0 } else { 0.1 };
+    let input_data: Vec<Vec<f64>> = (0..300)
+        .map(|t| {
+            vec![if t % 2 == 0 {
+                amplitude(t)
+            } else {
+                -amplitude(t)
+            }]
+        })
+        .collect();
+    network
+        .input_data(input_data, None, true, InputDataOptions::default())
+        .unwrap();
+
+    assert!(network
+        .update_sequence
+        .updates
+        .contains(&(1, UpdateStep::PosteriorPrecisionParent)));
+
+    // The parent tracks the log variance of the noise, so its mean rises
+    // while the noise is high and falls back once it is low again.
+    let noise_parent = &network.node_trajectories.nodes[1];
+    let input = &network.node_trajectories.nodes[0];
+    let (low, high, low_again) = (
+        noise_parent.mean()[99],
+        noise_parent.mean()[199],
+        noise_parent.mean()[299],
+    );
+    assert!(high > low + 2.0, "{high} should be well above {low}");
+    assert!(
+        low_again < high - 2.0,
+        "{low_again} should be well below {high}"
+    );
+    assert_close(
+        input.expected_precision()[150],
+        (-noise_parent.expected_mean()[150]).exp(),
+        "input expected precision",
+    );
+}
+
+/// A parent of `n_children` inputs with the given observation precision.
+fn parent_of_inputs(n_children: usize, input_precision: f64) -> Network {
+    let mut network = Network::new("standard");
+    network
+        .add_nodes(
+            "continuous-state",
+            n_children,
+            AddNodesOptions {
+                additional_parameters: Some(HashMap::from([(
+                    "expected_precision".into(),
+                    input_precision,
+                )])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some((0..n_children).collect::<Vec<_>>().into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+}
+
+#[test]
+fn test_value_prediction_errors_add_up_over_children() {
+    // Each child contributes its own precision-weighted prediction error,
+    // and the sum is divided once by the parent's posterior precision.
+    let mut network = parent_of_inputs(3, 1.0);
+    network
+        .input_data(
+            vec![vec![0.5, -0.2, 1.1]],
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+    let parent = &network.node_trajectories.nodes[3];
+    let expected_precision = parent.expected_precision()[0];
+    let expected_mean = parent.expected_mean()[0];
+    let precision = expected_precision + 3.0;
+    assert_close(parent.precision()[0], precision, "parent precision");
+    assert_close(
+        parent.mean()[0],
+        expected_mean + (0.5 + -0.2 + 1.1 - 3.0 * expected_mean) / precision,
+        "parent mean",
+    );
+
+    // Three children seeing the same value update the parent like a single
+    // child with three times their precision.
+    let mut three_children = parent_of_inputs(3, 1.0);
+    let mut one_child = parent_of_inputs(1, 3.0);
+    let values = [0.3, 0.8, -0.4, 0.1, 0.6];
+    three_children
+        .input_data(
+            values.iter().map(|&x| vec![x; 3]).collect(),
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+    one_child
+        .input_data(
+            values.iter().map(|&x| vec![x]).collect(),
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+    let three = &three_children.node_trajectories.nodes[3];
+    let one = &one_child.node_trajectories.nodes[1];
+    for t in 0..values.len() {
+        assert_close(three.mean()[t], one.mean()[t], "mean");
+        assert_close(three.precision()[t], one.precision()[t], "precision");
+    }
+}
+
+#[test]
+fn test_parallel_branches_share_a_volatility_parent() {
+    let mut network = Network::new("standard");
+    let (branches, shared_idx) = network.parallel_branches(4, 1, true).unwrap();
+    assert_eq!(
+        branches,
+        vec![vec![0, 1], vec![2, 3], vec![4, 5], vec![6, 7]]
+    );
+    assert_eq!(shared_idx, Some(8));
+    assert_eq!(network.inputs, vec![0, 2, 4, 6]);
+    assert_eq!(network.edges[8].volatility_children, Some(vec![1, 3, 5, 7]));
+
+    let input_data = vec![
+        vec![0.5, -0.3, 1.2, 0.1],
+        vec![0.7, -0.1, 2.0, 0.0],
+        vec![0.2, 0.4, -1.5, 0.3],
+    ];
+    network
+        .input_data(input_data, None, true, InputDataOptions::default())
+        .unwrap();
+
+    // The shared parent sums the volatility PEs of the four first levels.
+    let trajectories = &network.node_trajectories.nodes;
+    let shared = &trajectories[8];
+    for t in 0..3 {
+        let weighted_pe: f64 = [1, 3, 5, 7]
+            .iter()
+            .map(|&idx| {
+                trajectories[idx].effective_precision()[t]
+                    * trajectories[idx].volatility_prediction_error()[t]
+            })
+            .sum();
+        assert_close(
+            shared.mean()[t],
+            shared.expected_mean()[t] + weighted_pe / (2.0 * shared.precision()[t]),
+            "shared volatility parent mean",
+        );
+    }
+
+    let mut separate = Network::new("standard");
+    let (branches, shared_idx) = separate.parallel_branches(3, 2, false).unwrap();
+    assert_eq!(shared_idx, None);
+    assert_eq!(branches[2], vec![6, 7, 8]);
+    assert_eq!(separate.edges[8].volatility_children, Some(vec![7]));
+    assert!(matches!(
+        separate.parallel_branches(1, 0, true),
+        Err(NetworkError::DimensionMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_laplace_likelihood_attenuates_outliers() {
+    let parent_mean_after = |likelihood: &str, observation: f64| {
+        let mut network = parent_of_inputs(1, 1.0);
+        network.set_likelihood(0, likelihood).unwrap();
+        network
+            .input_data(
+                vec![vec![observation]],
+                None,
+                true,
+                InputDataOptions::default(),
+            )
+            .unwrap();
+        network.node_trajectories.nodes[1].mean()[0]
+    };
+
+    // Small errors are within the clipping bound √2 and update alike...
+    assert_close(
+        parent_mean_after("laplace", 0.5),
+        parent_mean_after("gaussian", 0.5),
+        "small error",
+    );
+    // ...while a moderate outlier moves the parent less under Laplace.
+    let gaussian = parent_mean_after("gaussian", 4.0);
+    let laplace = parent_mean_after("laplace", 4.0);
+    assert!(0.0 < laplace && laplace < gaussian);
+    assert_close(laplace / gaussian, 2.0_f64.sqrt() / 4.0, "attenuation");
+
+    let mut network = parent_of_inputs(1, 1.0);
+    assert!(matches!(
+        network.set_likelihood(0, "cauchy"),
+        Err(NetworkError::InvalidValue {
+            parameter: "likelihood",
+            ..
+        })
+    ));
+    assert!(matches!(
+        network.set_likelihood(1, "laplace"),
+        Err(NetworkError::InvalidParameter { .. })
+    ));
+    assert!(matches!(
+        network.set_likelihood(5, "laplace"),
+        Err(NetworkError::MissingNode { node_idx: 5, .. })
+    ));
+
+    // The accuracy scores the observations under the Laplace likelihood of
+    // scale b = 1/√(2π̂) that was filtered.
+    network.set_likelihood(0, "laplace").unwrap();
+    let data = [0.5, 4.0, -1.0];
+    network
+        .input_data(
+            data.iter().map(|&x| vec![x]).collect(),
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+    let input = &network.node_trajectories.nodes[0];
+    let accuracy: f64 = data
+        .iter()
+        .enumerate()
+        .map(|(t, &observation)| {
+            let scale = 1.0 / (2.0 * input.expected_precision()[t]).sqrt();
+            -(2.0 * scale).ln() - (observation - input.expected_mean()[t]).abs() / scale
+        })
+        .sum();
+    assert_close(
+        network.get_free_energy().unwrap().accuracy,
+        accuracy,
+        "laplace accuracy",
+    );
+    // So does the recorded surprise of the input.
+    assert_close(
+        network.surprise_by_node().unwrap()[0],
+        -accuracy,
+        "laplace surprise",
+    );
+}
+
+#[test]
+fn test_pe_asymmetry_biases_the_belief_upward() {
+    // Zero-mean noise around 0.
+    let input_data: Vec<Vec<f64>> = (0..60)
+        .map(|t| vec![if t % 2 == 0 { 1.0 } else { -1.0 }])
+        .collect();
+    let average_mean = |volatility_updates: &str, kind: &str, pe_asymmetry: f64| {
+        let mut network = Network::new(volatility_updates);
+        network
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        network
+            .add_nodes(
+                kind,
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    volatility_children: Some(1.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network.set_pe_asymmetry(1, pe_asymmetry).unwrap();
+        network
+            .input_data(input_data.clone(), None, true, InputDataOptions::default())
+            .unwrap();
+        let parent = &network.node_trajectories.nodes[1];
+        // The first error is positive, the second negative.
+        assert_close(parent.pe_scaling()[0], 1.0 + pe_asymmetry, "pe scaling");
+        assert_close(parent.pe_scaling()[1], 1.0 - pe_asymmetry, "pe scaling");
+        parent.mean().iter().sum::<f64>() / 60.0
+    };
+
+    for (volatility_updates, kind) in [
+        ("standard", "continuous-state"),
+        ("eHGF", "continuous-state"),
+        ("standard", "volatile-state"),
+    ] {
+        let symmetric = average_mean(volatility_updates, kind, 0.0);
+        let optimistic = average_mean(volatility_updates, kind, 0.5);
+        let pessimistic = average_mean(volatility_updates, kind, -0.5);
+        assert!(
+            optimistic > symmetric + 0.1 && optimistic > 0.0,
+            "{volatility_updates} {kind}: {optimistic} vs {symmetric}"
+        );
+        assert!(
+            pessimistic < symmetric - 0.1,
+            "{volatility_updates} {kind}: {pessimistic} vs {symmetric}"
+        );
+    }
+
+    let mut network = parent_of_inputs(1, 1.0);
+    assert!(matches!(
+        network.set_pe_asymmetry(1, 1.0),
+        Err(NetworkError::InvalidValue {
+            parameter: "pe_asymmetry",
+            ..
+        })
+    ));
+    assert!(network.set_pe_asymmetry(1, f64::NAN).is_err());
+    assert!(matches!(
+        network.set_pe_asymmetry(5, 0.5),
+        Err(NetworkError::MissingNode { node_idx: 5, .. })
+    ));
+}
+
+#[test]
+fn test_learning_rate_times_prediction_error_is_the_mean_increment() {
+    // An input with two value parents: each parent records its learning rate
+    // in the input's `learning_rate_parents`, at its position among the
+    // input's value parents.
+    let mut network = parent_of_inputs(1, 2.0);
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(vec![0].into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .input_data(
+            [0.4, 1.2, -0.3, 0.9].iter().map(|&x| vec![x]).collect(),
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+
+    let input = &network.node_trajectories.nodes[0];
+    let learning_rates = input.vector("learning_rate_parents").unwrap();
+    assert_eq!(learning_rates.len(), 4);
+    for (position, parent_idx) in [(0, 1), (1, 2)] {
+        let parent = &network.node_trajectories.nodes[parent_idx];
+        for t in 0..4 {
+            let learning_rate = learning_rates[t][position];
+            assert!(
+                learning_rate > 0.0 && learning_rate < 1.0,
+                "{learning_rate}"
+            );
+            assert_close(
+                learning_rate * input.value_prediction_error()[t],
+                parent.mean()[t] - parent.expected_mean()[t],
+                "mean increment",
+            );
+        }
+    }
+}
+
+#[test]
+fn test_softplus_volatility_coupling_prediction() {
+    // input (0) <- value parent (1) <- volatility parent (2), coupled to node 1
+    // through a softplus.
+    let mut network = Network::new("standard");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(vec![0].into()),
+                additional_parameters: Some(HashMap::from([("precision".into(), 2.0)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                volatility_children: Some(vec![1].into()),
+                additional_parameters: Some(HashMap::from([("mean".into(), 0.5)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network.set_volatility_coupling_fn(2, "softplus").unwrap();
+    assert!(matches!(
+        network.set_volatility_coupling_fn(2, "unknown"),
+        Err(NetworkError::InvalidValue {
+            parameter: "coupling_fn",
+            ..
+        })
+    ));
+    network
+        .input_data(vec![vec![0.3]], None, true, InputDataOptions::default())
+        .unwrap();
+
+    // Ω = Δt · exp(ω + κ g(μ₂) + (κ g'(μ₂))² / (2 π̂₂)) with g = softplus,
+    // g' = sigmoid, κ = 1, ω = -4 and Δt = 1.
+    let parent_expected_precision = network.node_trajectories.nodes[2].expected_precision()[0];
+    let softplus = (1.0 + 0.5_f64.exp()).ln();
+    let sigmoid = 1.0 / (1.0 + (-0.5_f64).exp());
+    let predicted_volatility =
+        (-4.0 + softplus + sigmoid.powi(2) / (2.0 * parent_expected_precision)).exp();
+    assert_close(
+        network.node_trajectories.nodes[1].expected_precision()[0],
+        1.0 / (1.0 / 2.0 + predicted_volatility),
+        "expected precision",
+    );
+
+    // Linear coupling again: the prediction goes back to κ · μ₂.
+    network.set_volatility_coupling_fn(2, "linear").unwrap();
+    assert!(network.attributes.fn_ptrs[2]
+        .volatility_coupling_fn
+        .is_none());
+}
+
+#[test]
+fn test_volatility_coupling_fn_after_an_unbounded_run() {
+    // Setting a nonlinear volatility coupling after a run drops the cached
+    // unbounded update: the next run falls back to eHGF for that node.
+    let mut network = build_shared_volatility_parent("unbounded", 1);
+    let data: Vec<Vec<f64>> = [0.3, -0.2, 0.8].iter().map(|&x| vec![x]).collect();
+    network
+        .input_data(data.clone(), None, true, InputDataOptions::default())
+        .unwrap();
+    assert!(network
+        .update_sequence
+        .updates
+        .contains(&(2, UpdateStep::PosteriorContinuousUnbounded)));
+
+    network.set_volatility_coupling_fn(2, "tanh").unwrap();
+    network
+        .input_data(data, None, true, InputDataOptions::default())
+        .unwrap();
+    assert!(network
+        .update_sequence
+        .updates
+        .contains(&(2, UpdateStep::PosteriorContinuousEhgf)));
+}
+
+#[test]
+fn test_node_views() {
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(vec![0].into()),
+                additional_parameters: Some(HashMap::from([("tonic_volatility".into(), -2.0)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes("binary-state", 1, AddNodesOptions::default())
+        .unwrap();
+
+    let nodes = network.nodes();
+    let idxs: Vec<usize> = nodes.iter().map(|view| view.idx).collect();
+    assert_eq!(idxs, vec![0, 1, 2]);
+    assert_eq!(nodes[0].value_parents, Some(vec![1]));
+    assert_eq!(nodes[1].kind, "continuous-state");
+    assert_eq!(nodes[1].value_children, Some(vec![0]));
+    assert_eq!(nodes[1].parameters["tonic_volatility"], -2.0);
+    assert_eq!(nodes[2].kind, "binary-state");
+    assert!(nodes[2].parameters.is_empty());
+
+    let view = network.node_view(1).unwrap();
+    assert_eq!((view.idx, view.value_children), (1, Some(vec![0])));
+    assert!(matches!(
+        network.node_view(3),
+        Err(NetworkError::MissingNode { node_idx: 3, .. })
+    ));
+}
+
+#[test]
+fn test_set_attribute() {
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(vec![0].into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    network.set_attribute(1, "tonic_volatility", -1.5).unwrap();
+    assert_eq!(network.get_attribute(1, "tonic_volatility").unwrap(), -1.5);
+    assert_eq!(network.attributes.states[1].tonic_volatility, -1.5);
+
+    // Only the parameters of the node's kind can be set.
+    assert!(matches!(
+        network.set_attribute(1, "observed", 0.0),
+        Err(NetworkError::InvalidParameter { .. })
+    ));
+    assert!(matches!(
+        network.get_attribute(1, "not_an_attribute"),
+        Err(NetworkError::InvalidParameter { .. })
+    ));
+    assert!(matches!(
+        network.set_attribute(2, "mean", 0.0),
+        Err(NetworkError::MissingNode { node_idx: 2, .. })
+    ));
+}
+
+/// A pyhgf continuous node with the default parameters and `overrides`.
+fn pyhgf_continuous_node(overrides: &[(&str, f64)]) -> PyhgfNode {
+    let mut parameters: HashMap<String, f64> = [
+        ("mean", 0.0),
+        ("expected_mean", 0.0),
+        ("precision", 1.0),
+        ("expected_precision", 1.0),
+        ("tonic_volatility", -4.0),
+        ("tonic_drift", 0.0),
+        ("autoconnection_strength", 1.0),
+        ("observed", 1.0),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value))
+    .collect();
+    for &(key, value) in overrides {
+        parameters.insert(key.to_string(), value);
+    }
+    PyhgfNode {
+        node_type: 2,
+        parameters,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_from_pyhgf_spec() {
+    // A 3-level continuous HGF as exposed by pyhgf: input, value parent and
+    // volatility parent.
+    let spec = vec![
+        PyhgfNode {
+            value_parents: vec![1],
+            ..pyhgf_continuous_node(&[])
+        },
+        PyhgfNode {
+            value_children: vec![0],
+            value_coupling_children: vec![1.0],
+            volatility_parents: vec![2],
+            ..pyhgf_continuous_node(&[("tonic_volatility", -3.0)])
+        },
+        PyhgfNode {
+            volatility_children: vec![1],
+            volatility_coupling_children: vec![0.5],
+            ..pyhgf_continuous_node(&[("mean", 1.0)])
+        },
+    ];
+    let data = [0.1, 0.5, -0.3, 1.2, 0.8, -0.6];
+    let observations: Vec<Vec<f64>> = data.iter().map(|&x| vec![x]).collect();
+
+    let mut imported = Network::from_pyhgf_spec(&spec, "eHGF").unwrap();
+    let mut expected =
+        Network::continuous_hgf("eHGF", 2, &[0.0, 1.0], &[1.0, 1.0], &[-3.0, -4.0], &[0.5])
+            .unwrap();
+    assert_eq!(imported.inputs, vec![0]);
+    imported
+        .input_data(
+            observations.clone(),
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+    expected
+        .input_data(
+            observations.clone(),
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+    assert!(imported == expected);
+
+    // The same network with the nodes listed top-down: the parents come
+    // before their children, and the input is the last node.
+    let reversed = vec![
+        PyhgfNode {
+            volatility_children: vec![1],
+            volatility_coupling_children: vec![0.5],
+            ..pyhgf_continuous_node(&[("mean", 1.0)])
+        },
+        PyhgfNode {
+            value_children: vec![2],
+            value_coupling_children: vec![1.0],
+            volatility_parents: vec![0],
+            ..pyhgf_continuous_node(&[("tonic_volatility", -3.0)])
+        },
+        PyhgfNode {
+            value_parents: vec![1],
+            ..pyhgf_continuous_node(&[])
+        },
+    ];
+    let mut imported = Network::from_pyhgf_spec(&reversed, "eHGF").unwrap();
+    assert_eq!(imported.inputs, vec![2]);
+    imported
+        .input_data(observations, None, true, InputDataOptions::default())
+        .unwrap();
+    for (imported_idx, expected_idx) in [(2, 0), (1, 1), (0, 2)] {
+        assert_eq!(
+            imported.node_trajectories.nodes[imported_idx]
+                .mean()
+                .to_vec(),
+            expected.node_trajectories.nodes[expected_idx]
+                .mean()
+                .to_vec(),
+            "node {} mean",
+            imported_idx
+        );
+    }
+}
+
+#[test]
+fn test_to_pyhgf_spec_round_trip() {
+    // Input (0) under a sigmoid-coupled value parent (1) and a volatility
+    // parent (2), with non-default couplings.
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                coupling_fn: Some("sigmoid".into()),
+                coupling: Some(0.8.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                volatility_children: Some(1.into()),
+                additional_parameters: Some(HashMap::from([(
+                    "tonic_volatility".to_string(),
+                    -3.0,
+                )])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network.set_volatility_coupling(2, 1, 0.5).unwrap();
+    network.set_update_sequence();
+
+    let spec = network.to_pyhgf_spec().unwrap();
+    assert_eq!(spec[1].coupling_fns, vec![Some("sigmoid".to_string())]);
+    assert_eq!(spec[1].value_coupling_children, vec![0.8]);
+    assert_eq!(spec[2].volatility_coupling_children, vec![0.5]);
+    assert_eq!(spec[2].parameters["tonic_volatility"], -3.0);
+
+    let imported = Network::from_pyhgf_spec(&spec, "eHGF").unwrap();
+    assert!(imported == network);
+}
+
+#[test]
+fn test_from_spec_str() {
+    let spec = r#"{
+        "update_type": "standard",
+        "precision_clipping_value": 1e-4,
+        "nodes": [
+            {"kind": "continuous-state", "metadata": {"label": "reward"}},
+            {"kind": "continuous-state", "coupling_fn": "sigmoid",
+             "parameters": {"mean": 0.5}},
+            {"kind": "continuous-state", "parameters": {"tonic_volatility": -3.0}},
+            {"kind": "volatile-state"}
+        ],
+        "edges": [
+            {"parent": 1, "child": 0, "coupling": 0.8, "frozen": true},
+            {"parent": 2, "child": 1, "kind": "volatility", "coupling": 0.5},
+            {"parent": 3, "child": 0, "coupling": 0.3}
+        ]
+    }"#;
+    let network = Network::from_spec_str(spec).unwrap();
+    assert_eq!(network.volatility_updates, "standard");
+    assert_eq!(network.precision_clipping_value, 1e-4);
+    assert_eq!(
+        network.attributes.vectors[0].value_coupling_parents,
+        vec![0.8, 0.3]
+    );
+    assert_eq!(
+        network.attributes.vectors[1].volatility_coupling_parents,
+        vec![0.5]
+    );
+    assert_eq!(network.attributes.states[1].mean, 0.5);
+    assert_eq!(network.attributes.states[2].tonic_volatility, -3.0);
+    // Parents declared before their children still get the defaults of a
+    // node with children.
+    assert_eq!(network.attributes.states[1].autoconnection_strength, 1.0);
+    assert_eq!(network.attributes.states[0].tonic_volatility, 0.0);
+    assert_eq!(network.get_metadata(0, "label"), Some("reward"));
+    assert_eq!(network.inputs, vec![0]);
+}
+
+#[test]
+fn test_spec_str_round_trip() {
+    // Every per-node and per-edge option, set through the API.
+    let mut network = Network::new("eHGF");
+    network.mean_field_updates = true;
+    network.max_posterior_precision = 1e8;
+    network.set_negative_precision("reject").unwrap();
+    network.set_entropy_source("prediction").unwrap();
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                coupling_fn: Some("sigmoid".into()),
+                coupling: Some(0.8.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                volatility_children: Some(1.into()),
+                volatility_coupling: Some(0.5.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes(
+            "volatile-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                coupling: Some(0.3.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(1.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network.add_precision_parent(4, 0).unwrap();
+    network.set_drift_coupling(5, 1, true).unwrap();
+    network.set_likelihood(0, "laplace").unwrap();
+    network.set_metadata(0, "label", "reward").unwrap();
+    network.freeze_coupling(1, 0).unwrap();
+    network.set_child_coupling_fn(3, 0, "tanh").unwrap();
+    network.set_coupling_lag(3, 0, 1).unwrap();
+    network.set_volatility_coupling_fn(2, "softplus").unwrap();
+    network.set_attribute(1, "mean", 0.5).unwrap();
+    network.set_attribute(1, "pe_asymmetry", 0.2).unwrap();
+    network.set_attribute(2, "tonic_volatility", -3.0).unwrap();
+    network.set_autoconnection_vol(3, 0.9).unwrap();
+    network
+        .add_nodes("aggregate-input", 1, AddNodesOptions::default())
+        .unwrap();
+    network.set_input_precision(6, vec![2.0, 0.5]).unwrap();
+
+    let rebuilt = Network::from_spec_str(&network.to_spec_str()).unwrap();
+    assert!(rebuilt.approx_eq(&network, 1e-12));
+    assert_eq!(rebuilt.edges[0].likelihood, "laplace");
+    assert_eq!(rebuilt.entropy_source, EntropySource::Prediction);
+    assert_eq!(rebuilt.get_metadata(0, "label"), Some("reward"));
+    assert_eq!(rebuilt.attributes.vectors[6].input_precision, [2.0, 0.5]);
+    assert_eq!(rebuilt.to_spec_str(), network.to_spec_str());
+}
+
+#[test]
+fn test_to_pyhgf_spec_expands_volatile_nodes() {
+    // Input (0) under a volatile-state value parent (1), whose implicit
+    // volatility level becomes node 2.
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "volatile-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                additional_parameters: Some(HashMap::from([
+                    ("mean_vol".to_string(), 0.3),
+                    ("tonic_volatility_vol".to_string(), -2.0),
+                ])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    let spec = network.to_pyhgf_spec().unwrap();
+    assert_eq!(spec.len(), 3);
+    assert!(spec.iter().all(|node| node.node_type == 2));
+    assert_eq!(spec[1].value_children, vec![0]);
+    assert_eq!(spec[1].volatility_parents, vec![2]);
+    assert_eq!(spec[1].parameters["tonic_volatility"], 0.0);
+    assert_eq!(spec[2].volatility_children, vec![1]);
+    assert_eq!(spec[2].volatility_coupling_children, vec![1.0]);
+    assert_eq!(spec[2].parameters["mean"], 0.3);
+    assert_eq!(spec[2].parameters["tonic_volatility"], -2.0);
+
+    let imported = Network::from_pyhgf_spec(&spec, "eHGF").unwrap();
+    assert_eq!(imported.edges[2].volatility_children, Some(vec![1]));
+    assert_eq!(imported.attributes.states[2].mean, 0.3);
+    assert_eq!(imported.attributes.states[1].tonic_volatility, 0.0);
+}
+
+/// Input observing a continuous random walk with noise of precision 4; the
+/// input's own volatility is negligible so that sampled observations match
+/// what the filter assumes.
+fn build_noisy_random_walk(omega: f64) -> Network {
+    let mut network = Network::new("standard");
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                additional_parameters: Some(HashMap::from([
+                    ("precision".into(), 4.0),
+                    ("tonic_volatility".into(), -30.0),
+                ])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                additional_parameters: Some(HashMap::from([("tonic_volatility".into(), omega)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+}
+
+#[test]
+fn test_cv_surprise_prefers_the_generating_model() {
+    let mut generating = build_noisy_random_walk(-4.0);
+    generating.set_update_sequence();
+    let data = generating
+        .sample(200, None, Some(1), 0.0)
+        .unwrap()
+        .observations;
+    let train_sizes: Vec<usize> = (2..20).map(|k| k * 10).collect();
+
+    let before = format!("{:?}", generating.attributes);
+    let true_folds = generating
+        .cv_surprise(&data, None, &train_sizes, 10)
+        .unwrap();
+    assert_eq!(format!("{:?}", generating.attributes), before);
+    assert_eq!(true_folds.len(), train_sizes.len());
+
+    // An over-volatile model chases the noise and predicts the held-out
+    // blocks worse.
+    let mut over_volatile = build_noisy_random_walk(2.0);
+    let volatile_folds = over_volatile
+        .cv_surprise(&data, None, &train_sizes, 10)
+        .unwrap();
+    let true_total: f64 = true_folds.iter().sum();
+    let volatile_total: f64 = volatile_folds.iter().sum();
+    assert!(
+        true_total < volatile_total,
+        "{} vs {}",
+        true_total,
+        volatile_total
+    );
+
+    // A fold scoring a single step matches the surprise of that step in a
+    // filtered run: the training block is filtered as usual.
+    let single = generating.cv_surprise(&data, None, &[50], 1).unwrap();
+    generating
+        .input_data(data[..51].to_vec(), None, true, InputDataOptions::default())
+        .unwrap();
+    assert_close(
+        single[0],
+        generating.node_trajectories.nodes[0].surprise()[50],
+        "one-step-ahead surprise",
+    );
+
+    assert_eq!(
+        generating.cv_surprise(&data, None, &[200], 10).unwrap_err(),
+        NetworkError::InvalidTimeRange {
+            start: 200,
+            stop: 210,
+            step: 1,
+            n_time: 200,
+        }
+    );
+}
+
+fn single_node_network() -> Network {
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+}
+
+#[test]
+fn test_merge_networks() {
+    // Node 0 of `sensory` observes the data; the node of `volatility`
+    // becomes node 1, its value parent.
+    let mut merged = single_node_network();
+    merged.set_metadata(0, "role", "sensory").unwrap();
+    let mut volatility = single_node_network();
+    volatility.set_metadata(0, "role", "volatility").unwrap();
+    merged.merge(&volatility, vec![(1, 0, 1.0)]).unwrap();
+    assert_eq!(merged.n_nodes(), 2);
+    assert_eq!(merged.inputs, vec![0]);
+    assert_eq!(merged.edges[0].value_parents, Some(vec![1]));
+    assert_eq!(merged.get_metadata(1, "role"), Some("volatility"));
+
+    let mut direct = single_node_network();
+    direct
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let data: Vec<Vec<f64>> = (0..20).map(|t| vec![(t as f64 * 0.4).sin()]).collect();
+    merged
+        .input_data(data.clone(), None, true, InputDataOptions::default())
+        .unwrap();
+    direct
+        .input_data(data, None, true, InputDataOptions::default())
+        .unwrap();
+    for node_idx in 0..2 {
+        assert_eq!(
+            merged.node_trajectories.nodes[node_idx].mean().to_vec(),
+            direct.node_trajectories.nodes[node_idx].mean().to_vec()
+        );
+    }
+
+    // Nothing is merged when an edge is invalid.
+    let mut network = single_node_network();
+    assert_eq!(
+        network
+            .merge(&volatility, vec![(1, 0, 1.0), (2, 0, 1.0)])
+            .unwrap_err(),
+        NetworkError::MissingNode {
+            node_idx: 2,
+            step: "merge",
+        }
+    );
+    assert!(matches!(
+        network.merge(&volatility, vec![(1, 0, 1.0), (1, 0, 0.5)]),
+        Err(NetworkError::UnsupportedStructure { node_idx: 0, .. })
+    ));
+    assert_eq!(network.n_nodes(), 1);
+
+    // Nor when an edge closes a cycle: node 1 is already a parent of node 0
+    // in `two`, so node 0 cannot become a parent of node 1.
+    let two = || {
+        let mut network = single_node_network();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+    };
+    let mut network = two();
+    let err = network.merge(&two(), vec![(0, 1, 1.0)]).unwrap_err();
+    assert_eq!(
+        err,
+        NetworkError::InvalidEdge {
+            node_idx: 0,
+            edge: "value_children",
+            target_idx: 1,
+            step: "merge",
+            reason: "would close a cycle through node",
+        }
+    );
+    // ... also through the new edges themselves.
+    assert!(matches!(
+        network.merge(&two(), vec![(0, 2, 1.0), (2, 1, 1.0)]),
+        Err(NetworkError::InvalidEdge { target_idx: 1, .. })
+    ));
+    assert_eq!(network.n_nodes(), 2);
+    assert_eq!(network.inputs, vec![0]);
+}
+
+#[test]
+fn test_input_data_conditions_switch_parameters() {
+    let mut network = single_node_network();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                additional_parameters: Some(HashMap::from([("tonic_volatility".into(), -2.0)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let data: Vec<Vec<f64>> = (0..20).map(|t| vec![(t as f64 * 0.4).sin()]).collect();
+    let conditions: Vec<usize> = (0..20).map(|t| t % 2).collect();
+    // Condition 0 keeps the network's own value.
+    let parameters = HashMap::from([(
+        1,
+        HashMap::from([((1, "tonic_volatility".to_string()), 1.0)]),
+    )]);
+    let with_conditions = |conditions: &[usize], parameters: &HashMap<_, _>| InputDataOptions {
+        conditions: Some((conditions.to_vec(), parameters.clone())),
+        ..Default::default()
+    };
+    network
+        .input_data(
+            data.clone(),
+            None,
+            true,
+            with_conditions(&conditions, &parameters),
+        )
+        .unwrap();
+
+    let parent = &network.node_trajectories.nodes[1];
+    for t in 1..data.len() {
+        let omega = if t % 2 == 0 { -2.0 } else { 1.0 };
+        assert_eq!(parent.tonic_volatility()[t], omega);
+        // Δt = 1: the prediction adds exp(ω) to the posterior variance.
+        assert_close(
+            1.0 / parent.expected_precision()[t] - 1.0 / parent.precision()[t - 1],
+            f64::exp(omega),
+            "prediction variance",
+        );
+    }
+    assert_eq!(network.get_attribute(1, "tonic_volatility").unwrap(), -2.0);
+
+    assert_eq!(
+        network
+            .input_data(
+                data.clone(),
+                None,
+                true,
+                with_conditions(&conditions[..5], &parameters),
+            )
+            .unwrap_err(),
+        NetworkError::DimensionMismatch {
+            what: "conditions (one per observation)",
+            expected: 20,
+            found: 5,
+        }
+    );
+    let unknown = HashMap::from([(0, HashMap::from([((1, "omega".to_string()), 1.0)]))]);
+    assert!(matches!(
+        network.input_data(data, None, true, with_conditions(&conditions, &unknown)),
+        Err(NetworkError::InvalidParameter { .. })
+    ));
+}
+
+#[test]
+fn test_initial_current_variance_seeds_the_first_unbounded_step() {
+    let mut network = Network::continuous_hgf(
+        "unbounded",
+        2,
+        &[0.0, 1.0],
+        &[1.0, 1.0],
+        &[-3.0, -4.0],
+        &[1.0],
+    )
+    .unwrap();
+    network.set_attribute(1, "precision", 1e200).unwrap();
+    network
+        .set_attribute(1, "initial_current_variance", 1.0)
+        .unwrap();
+    network
+        .input_data(
+            vec![vec![0.5], vec![0.3], vec![0.8]],
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+
+    let child = &network.node_trajectories.nodes[1];
+    assert_eq!(child.current_variance()[0], 1.0);
+    assert_eq!(child.current_variance()[1], 1.0 / child.precision()[0]);
+    for node in &network.node_trajectories.nodes {
+        assert!(node.mean().iter().all(|mean| mean.is_finite()));
+    }
+    assert!(network
+        .get_attribute(1, "initial_current_variance")
+        .unwrap()
+        .is_nan());
+}
+
+#[test]
+fn test_covariates_drive_the_drift() {
+    // The observations ramp up by half the stimulus intensity at every step.
+    let intensity: Vec<f64> = (0..40).map(|t| 1.0 + (t as f64 * 0.3).sin()).collect();
+    let data: Vec<Vec<f64>> = intensity
+        .iter()
+        .scan(0.0, |level, &c| {
+            *level += 0.5 * c;
+            Some(vec![*level])
+        })
+        .collect();
+    let covariates: Vec<Vec<f64>> = intensity.iter().map(|&c| vec![c]).collect();
+    let build = || {
+        let mut network = single_node_network();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+    };
+    let squared_error = |network: &Network| -> f64 {
+        let input = &network.node_trajectories.nodes[0];
+        (10..data.len())
+            .map(|t| (input.expected_mean()[t] - data[t][0]).powi(2))
+            .sum()
+    };
+
+    let mut plain = build();
+    plain
+        .input_data(data.clone(), None, true, InputDataOptions::default())
+        .unwrap();
+    let mut driven = build();
+    let weights = HashMap::from([(0, (1, 0.5))]);
+    let with_covariates = |rows: &[Vec<f64>], weights: &HashMap<_, _>| InputDataOptions {
+        covariates: Some((rows.to_vec(), weights.clone())),
+        ..Default::default()
+    };
+    driven
+        .input_data(
+            data.clone(),
+            None,
+            true,
+            with_covariates(&covariates, &weights),
+        )
+        .unwrap();
+    assert!(
+        squared_error(&driven) < 0.1 * squared_error(&plain),
+        "{} vs {}",
+        squared_error(&driven),
+        squared_error(&plain)
+    );
+    assert_eq!(driven.get_attribute(1, "covariate_drift").unwrap(), 0.0);
+
+    assert_eq!(
+        driven
+            .input_data(
+                data.clone(),
+                None,
+                true,
+                with_covariates(&covariates[..3], &weights),
+            )
+            .unwrap_err(),
+        NetworkError::DimensionMismatch {
+            what: "covariate rows (one per observation)",
+            expected: 40,
+            found: 3,
+        }
+    );
+    let beyond = HashMap::from([(1, (1, 0.5))]);
+    assert!(matches!(
+        driven.input_data(data, None, true, with_covariates(&covariates, &beyond)),
+        Err(NetworkError::DimensionMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_pe_gains_scale_the_parent_updates() {
+    let data: Vec<Vec<f64>> = (0..30).map(|t| vec![(t as f64 * 0.4).sin()]).collect();
+    // Unattended for the middle block, attended elsewhere.
+    let gains: Vec<f64> = (0..30)
+        .map(|t| if (10..20).contains(&t) { 0.0 } else { 1.0 })
+        .collect();
+    let build = || {
+        let mut network = single_node_network();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+    };
+
+    let mut plain = build();
+    plain
+        .input_data(data.clone(), None, true, InputDataOptions::default())
+        .unwrap();
+    let with_gains = |pe_gains: HashMap<usize, Vec<f64>>| InputDataOptions {
+        pe_gains: Some(pe_gains),
+        ..Default::default()
+    };
+    let mut gated = build();
+    gated
+        .input_data(
+            data.clone(),
+            None,
+            true,
+            with_gains(HashMap::from([(0, gains.clone())])),
+        )
+        .unwrap();
+
+    let parent = &gated.node_trajectories.nodes[1];
+    for t in 10..20 {
+        assert_eq!(parent.mean()[t], parent.expected_mean()[t]);
+        assert_eq!(parent.precision()[t], parent.expected_precision()[t]);
+    }
+    for t in 0..10 {
+        assert_eq!(parent.mean()[t], plain.node_trajectories.nodes[1].mean()[t]);
+    }
+    assert!(parent.precision()[21] > parent.expected_precision()[21]);
+    assert_eq!(gated.node_trajectories.nodes[0].pe_gain().to_vec(), gains);
+    match gated.get_trajectory(0, "pe_gain").unwrap() {
+        RecordedTrajectory::Scalar(column) => assert_eq!(column.to_vec(), gains),
+        RecordedTrajectory::Vector(_) => panic!("pe_gain is a scalar"),
+    }
+    assert_eq!(gated.get_attribute(0, "pe_gain").unwrap(), 1.0);
+
+    assert_eq!(
+        gated
+            .input_data(
+                data.clone(),
+                None,
+                true,
+                with_gains(HashMap::from([(1, gains.clone())]))
+            )
+            .unwrap_err(),
+        NetworkError::UnsupportedStructure {
+            node_idx: 1,
+            step: "input_data",
+            reason: "prediction-error gains apply to input nodes",
+        }
+    );
+    assert!(matches!(
+        gated.input_data(
+            data,
+            None,
+            true,
+            with_gains(HashMap::from([(0, vec![1.5; 30])]))
+        ),
+        Err(NetworkError::InvalidValue {
+            parameter: "gain",
+            ..
+        })
+    ));
+}
+
+#[test]
+fn test_autoconnection_strength_scales_the_first_prediction() {
+    let first_expected_mean = |parameters: HashMap<String, f64>| {
+        let mut network = single_node_network();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    additional_parameters: Some(parameters),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+            .input_data(vec![vec![0.0]], None, true, InputDataOptions::default())
+            .unwrap();
+        network.node_trajectories.nodes[1].expected_mean()[0]
+    };
+
+    let default = first_expected_mean(HashMap::from([("mean".into(), 2.0)]));
+    let halved = first_expected_mean(HashMap::from([
+        ("mean".into(), 2.0),
+        ("autoconnection_strength".into(), 0.5),
+    ]));
+    assert_eq!(default, 2.0);
+    assert_eq!(halved, 1.0);
+
+    // Strengths outside [0, 1] are rejected, as by `set_autoconnection`.
+    for value in [5.0, -0.5, f64::NAN] {
+        let mut network = single_node_network();
+        let err = network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    additional_parameters: Some(HashMap::from([(
+                        "autoconnection_strength".into(),
+                        value,
+                    )])),
+                    ..Default::default()
+                },
+            )
+            .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                NetworkError::InvalidValue {
+                    parameter: "autoconnection_strength",
+                    ..
+                }
+            ),
+            "{:?}",
+            err
+        );
+        assert_eq!(network.n_nodes(), 1);
+    }
+}
+
+#[test]
+fn test_lagged_coupling_shifts_the_child_by_one_step() {
+    // Node 2 is the value parent of two inputs: node 0 observes it, node 1
+    // only reads its prediction (gain 0, so it teaches the parent nothing).
+    let n_time = 40;
+    let data: Vec<Vec<f64>> = (0..n_time)
+        .map(|t| vec![(t as f64 * 0.3).sin(), 0.0])
+        .collect();
+    let gains = HashMap::from([(1, vec![0.0; n_time])]);
+    let run = |lag: usize| {
+        let mut network = Network::new("eHGF");
+        for _ in 0..2 {
+            network
+                .add_nodes("continuous-state", 1, AddNodesOptions::default())
+                .unwrap();
+        }
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(vec![0, 1].into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network.set_coupling_lag(2, 1, lag).unwrap();
+        network
+            .input_data(
+                data.clone(),
+                None,
+                true,
+                InputDataOptions {
+                    pe_gains: Some(gains.clone()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let parent = network.node_trajectories.nodes[2].mean().to_vec();
+        let child = network.node_trajectories.nodes[1].expected_mean().to_vec();
+        (parent, child)
+    };
+    // Shift k at which the child's predictions best correlate with the
+    // parent's past posterior means.
+    let best_shift = |parent: &[f64], child: &[f64]| {
+        let correlation = |k: usize| {
+            let (x, y) = (&parent[4 - k..n_time - k], &child[4..]);
+            let mean = |v: &[f64]| v.iter().sum::<f64>() / v.len() as f64;
+            let (mx, my) = (mean(x), mean(y));
+            let cov: f64 = x.iter().zip(y).map(|(a, b)| (a - mx) * (b - my)).sum();
+            let var = |v: &[f64], m: f64| v.iter().map(|a| (a - m).powi(2)).sum::<f64>();
+            cov / (var(x, mx) * var(y, my)).sqrt()
+        };
+        (0..4)
+            .max_by(|&a, &b| correlation(a).total_cmp(&correlation(b)))
+            .unwrap()
+    };
+
+    let (parent, child) = run(0);
+    let (lagged_parent, lagged_child) = run(1);
+    assert_eq!(parent, lagged_parent);
+    for t in 2..n_time {
+        assert_eq!(child[t], parent[t - 1]);
+        assert_eq!(lagged_child[t], parent[t - 2]);
+    }
+    assert_eq!(best_shift(&parent, &child), 1);
+    assert_eq!(best_shift(&parent, &lagged_child), 2);
+
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    assert!(network.set_coupling_lag(0, 0, 1).is_err());
+}
+
+#[test]
+fn test_dp_state_opens_a_cluster_per_mode() {
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes(
+            "dp-state",
+            1,
+            AddNodesOptions {
+                additional_parameters: Some(HashMap::from([("precision".into(), 4.0)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    // Two modes, around 0 and 10: the first observation opens a cluster, the
+    // first one near 10 opens a second, and the rest are explained by these:
+    // each cluster counts its five observations.
+    let data = [0.1, -0.2, 0.3, 10.2, 9.8, 0.0, 10.1, -0.1, 9.9, 10.0];
+    network
+        .input_data(
+            data.iter().map(|&x| vec![x]).collect(),
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(network.n_nodes(), 3);
+    assert_eq!(network.edges[0].value_parents, Some(vec![1, 2]));
+    let states = &network.attributes.states;
+    assert!(states[1].mean.abs() < 0.5, "{}", states[1].mean);
+    assert!((states[2].mean - 10.0).abs() < 0.5, "{}", states[2].mean);
+    assert_eq!(network.attributes.vectors[0].cluster_counts, vec![5.0, 5.0]);
+
+    // Every node records one value per time step; the second cluster was
+    // opened at step 3 and has no history before it.
+    for trajectories in &network.node_trajectories.nodes {
+        assert_eq!(trajectories.mean().len(), data.len());
+    }
+    let second = network.node_trajectories.nodes[2].mean().to_vec();
+    assert!(second[..3].iter().all(|m| m.is_nan()));
+    assert!(second[3..].iter().all(|m| m.is_finite()));
+    assert_eq!(network.node_trajectories.nodes[2].born, 3);
+
+    // The aggregates over the trajectories start at each node's birth.
+    let free_energy = network.get_free_energy().unwrap();
+    assert!(free_energy.complexity.is_finite(), "{:?}", free_energy);
+    assert!(free_energy.total.is_finite(), "{:?}", free_energy);
+    let by_node = network.surprise_by_node().unwrap();
+    let recorded = network.node_trajectories.nodes[2].surprise().to_vec();
+    assert_eq!(by_node[2], recorded[3..].iter().sum::<f64>());
+    let summary = network.summary();
+    assert_eq!(summary[&2]["average_mean"], second[3..].iter().sum::<f64>() / 7.0);
+    for key in ["variance_mean", "average_precision"] {
+        assert!(summary[&2][key].is_finite(), "{}", key);
+    }
+    let sliced = network.get_node_trajectories_slice(2, None, 2).unwrap();
+    assert_eq!(sliced.nodes[2].born, 1);
+
+    // Replays from a checkpoint cannot take the clusters back.
+    let observations: Vec<Vec<f64>> = data.iter().map(|&x| vec![x]).collect();
+    assert!(matches!(
+        network.cv_surprise(&observations, None, &[5], 2),
+        Err(NetworkError::UnsupportedStructure { node_idx: 0, .. })
+    ));
+    assert!(matches!(
+        network.coupling_sensitivity(&observations, None, 1e-4),
+        Err(NetworkError::UnsupportedStructure { node_idx: 0, .. })
+    ));
+
+    // A single mode keeps a single cluster.
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("dp-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .input_data(
+            vec![vec![0.1], vec![-0.1], vec![0.2]],
+            None,
+            false,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(network.n_nodes(), 2);
+}
+
+#[test]
+fn test_input_data_without_time_steps() {
+    let mut network =
+        Network::continuous_hgf("eHGF", 2, &[0.0, 1.0], &[1.0, 1.0], &[-3.0, -4.0], &[0.5])
+            .unwrap();
+    network
+        .input_data(Vec::new(), None, true, InputDataOptions::default())
+        .unwrap();
+
+    // Every node records all of its keys, zero time steps long.
+    assert_eq!(network.node_trajectories.nodes.len(), network.n_nodes());
+    for node_idx in 0..network.n_nodes() {
+        let keys = network.recorded_keys(node_idx).unwrap();
+        let node_type = &network.edges[node_idx].node_type;
+        for &field in trajectory_fields_for_type(node_type) {
+            assert!(keys.contains(&field), "{field} of node {node_idx}");
+        }
+        for key in keys {
+            match network.get_trajectory(node_idx, key).unwrap() {
+                RecordedTrajectory::Scalar(column) => assert!(column.is_empty()),
+                RecordedTrajectory::Vector(vector) => {
+                    assert_eq!(vector.len(), 0);
+                    assert!(vector.stride > 0);
+                }
+            }
+        }
+    }
+
+    assert!(network
+        .recorded_keys(1)
+        .unwrap()
+        .contains(&"value_coupling_children"));
+
+    // A run with time steps afterwards records as usual.
+    network
+        .input_data(vec![vec![0.2]], None, true, InputDataOptions::default())
+        .unwrap();
+    assert_eq!(network.node_trajectories.nodes[1].mean().len(), 1);
+}
+
+#[test]
+fn test_drift_chain_tracks_constant_acceleration() {
+    // Input ← position ← velocity (← acceleration), each level a drift parent
+    // of the one below.
+    let drift_chain = |n_levels: usize| {
+        let mut network = Network::new("eHGF");
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    additional_parameters: Some(HashMap::from([(
+                        "expected_precision".into(),
+                        1e2,
+                    )])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        for level in 1..=n_levels {
+            network
+                .add_nodes(
+                    "continuous-state",
+                    1,
+                    AddNodesOptions {
+                        value_children: Some((level - 1).into()),
+                        additional_parameters: Some(HashMap::from([(
+                            "tonic_volatility".into(),
+                            -6.0,
+                        )])),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+            if level > 1 {
+                network.set_drift_coupling(level, level - 1, true).unwrap();
+            }
+        }
+        network
+    };
+
+    // Discretised constant acceleration: x_t = a t² / 2.
+    let acceleration = 0.2;
+    let data: Vec<Vec<f64>> = (0..40)
+        .map(|t| vec![0.5 * acceleration * (t as f64).powi(2)])
+        .collect();
+    let total_surprise = |n_levels: usize| {
+        let mut network = drift_chain(n_levels);
+        network
+            .input_data(data.clone(), None, true, InputDataOptions::default())
+            .unwrap();
+        network.surprise_by_node().unwrap()[0]
+    };
+    let (two_levels, three_levels) = (total_surprise(2), total_surprise(3));
+    assert!(three_levels < two_levels, "{three_levels} vs {two_levels}");
+
+    // The chain predicts top-down: acceleration, velocity, then position.
+    let mut network = drift_chain(3);
+    network.set_update_sequence();
+    let order: Vec<usize> = network
+        .update_sequence
+        .predictions
+        .iter()
+        .map(|&(idx, _)| idx)
+        .collect();
+    assert_eq!(order, vec![3, 2, 1, 0]);
+    network
+        .input_data(data, None, true, InputDataOptions::default())
+        .unwrap();
+    let acceleration_mean = network.node_trajectories.nodes[3].mean();
+    assert!((acceleration_mean[39] - acceleration).abs() < 0.05);
+
+    // Over a time step Δt the prediction errors of a drift child reach the
+    // parent through Δt · κ.
+    let time_step = 2.0;
+    let mut network = Network::new("standard");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                coupling: Some(0.5.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network.set_drift_coupling(1, 0, true).unwrap();
+    network
+        .input_data(
+            vec![vec![1.5]],
+            Some(vec![time_step]),
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+    let (input, velocity) = (
+        &network.node_trajectories.nodes[0],
+        &network.node_trajectories.nodes[1],
+    );
+    let slope = time_step * 0.5;
+    let precision =
+        velocity.expected_precision()[0] + slope.powi(2) * input.expected_precision()[0];
+    assert_close(velocity.precision()[0], precision, "velocity precision");
+    assert_close(
+        velocity.mean()[0],
+        velocity.expected_mean()[0]
+            + slope * input.expected_precision()[0] * input.value_prediction_error()[0] / precision,
+        "velocity mean",
+    );
+}
+
+#[test]
+fn test_steady_state_precision_matches_the_plateau() {
+    // Two inputs of precision 4 under one parent, with a stationary volatility.
+    let mut network = Network::new("eHGF");
+    let input_precision = HashMap::from([
+        ("precision".into(), 4.0),
+        ("expected_precision".into(), 4.0),
+    ]);
+    network
+        .add_nodes(
+            "continuous-state",
+            2,
+            AddNodesOptions {
+                additional_parameters: Some(input_precision),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(vec![0, 1].into()),
+                coupling: Some(vec![1.0, 0.5].into()),
+                additional_parameters: Some(HashMap::from([("tonic_volatility".into(), -3.0)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    // P = 4 + 0.25 · 4 = 5 and Ω = e⁻³: π* = P/2 + sqrt(P²/4 + P/Ω).
+    let (children_precision, volatility) = (5.0_f64, (-3.0_f64).exp());
+    let expected = children_precision / 2.0
+        + (children_precision.powi(2) / 4.0 + children_precision / volatility).sqrt();
+    let analytic = network.steady_state_precision(2).unwrap();
+    assert_close(analytic, expected, "closed form");
+
+    let data: Vec<Vec<f64>> = (0..200)
+        .map(|t| vec![(t as f64 * 0.3).sin(), (t as f64 * 0.7).cos()])
+        .collect();
+    network
+        .input_data(data, None, true, InputDataOptions::default())
+        .unwrap();
+    let precision = network.node_trajectories.nodes[2].precision();
+    assert_close(precision[199], analytic, "plateau");
+
+    assert_close(network.steady_state_precision(0).unwrap(), 4.0, "input");
+    assert!(matches!(
+        network.steady_state_precision(7),
+        Err(NetworkError::MissingNode { node_idx: 7, .. })
+    ));
+}
+
+#[test]
+fn test_bounded_state_is_invariant_under_an_affine_change_of_bounds() {
+    let run = |lower: f64, upper: f64| {
+        let mut network = Network::new("eHGF");
+        network
+            .add_nodes(
+                "bounded-state",
+                1,
+                AddNodesOptions {
+                    additional_parameters: Some(HashMap::from([
+                        ("lower_bound".into(), lower),
+                        ("upper_bound".into(), upper),
+                    ])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        // Proportions, including one on the lower bound.
+        let data: Vec<Vec<f64>> = [0.2, 0.35, 0.0, 0.6, 0.9, 0.75, 0.5]
+            .iter()
+            .map(|&u| vec![lower + (upper - lower) * u])
+            .collect();
+        network
+            .input_data(data, None, true, InputDataOptions::default())
+            .unwrap();
+        network
+    };
+
+    let unit = run(0.0, 1.0);
+    let scaled = run(3.0, 5.0);
+    let (unit_input, scaled_input) = (
+        &unit.node_trajectories.nodes[0],
+        &scaled.node_trajectories.nodes[0],
+    );
+    for t in 0..7 {
+        // The latent filtering is the same; only the scale of the predictions
+        // and the Jacobian of the surprise change.
+        assert_close(
+            unit.node_trajectories.nodes[1].mean()[t],
+            scaled.node_trajectories.nodes[1].mean()[t],
+            "parent mean",
+        );
+        assert_close(unit_input.mean()[t], scaled_input.mean()[t], "latent mean");
+        assert!(unit_input.mean()[t].is_finite());
+        let prediction = unit_input.expected_bounded_mean()[t];
+        assert!((0.0..=1.0).contains(&prediction));
+        assert_close(
+            scaled_input.expected_bounded_mean()[t],
+            3.0 + 2.0 * prediction,
+            "bounded prediction",
+        );
+        assert_close(
+            scaled_input.surprise()[t] - unit_input.surprise()[t],
+            2.0_f64.ln(),
+            "jacobian",
+        );
+    }
+
+    // The surprise is that of the observation on its own scale:
+    // −ln N(y; μ̂, 1/π̂) + ln(u (1 − u)) for y = logit(u).
+    let (u, y) = (0.6_f64, (0.6_f64 / 0.4).ln());
+    let expected = 0.5 * (2.0 * std::f64::consts::PI / unit_input.expected_precision()[3]).ln()
+        + 0.5 * unit_input.expected_precision()[3] * (y - unit_input.expected_mean()[3]).powi(2)
+        + (u * (1.0 - u)).ln();
+    assert_close(unit_input.surprise()[3], expected, "surprise");
+
+    let mut network = run(0.0, 1.0);
+    assert!(matches!(
+        network.input_data(vec![vec![1.5]], None, false, InputDataOptions::default()),
+        Err(NetworkError::InvalidObservation { node_idx: 0, .. })
+    ));
+}
+
+/// Lognormal input (negligible own volatility, precision 4 on the log scale)
+/// under a value parent with tonic volatility `omega`.
+fn build_lognormal_random_walk(omega: f64) -> Network {
+    let mut network = Network::new("standard");
+    network
+        .add_nodes(
+            "lognormal-state",
+            1,
+            AddNodesOptions {
+                additional_parameters: Some(HashMap::from([
+                    ("precision".into(), 4.0),
+                    ("tonic_volatility".into(), -30.0),
+                ])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                additional_parameters: Some(HashMap::from([("tonic_volatility".into(), omega)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+}
+
+#[test]
+fn test_lognormal_state_recovers_the_generating_volatility() {
+    // Log-normal data: the exponential of a noisy random walk.
+    let mut generating = build_noisy_random_walk(-2.0);
+    generating.set_update_sequence();
+    let log_data = generating
+        .sample(400, None, Some(5), 0.0)
+        .unwrap()
+        .observations;
+    let data: Vec<Vec<f64>> = log_data.iter().map(|o| vec![o[0].exp()]).collect();
+
+    let surprise = |omega: f64| {
+        let mut network = build_lognormal_random_walk(omega);
+        network
+            .input_data(data.clone(), None, true, InputDataOptions::default())
+            .unwrap();
+        network.node_trajectories.nodes[0]
+            .surprise()
+            .iter()
+            .sum::<f64>()
+    };
+    let grid = [-6.0, -4.0, -2.0, 0.0, 2.0];
+    let surprises: Vec<f64> = grid.iter().map(|&omega| surprise(omega)).collect();
+    let best = (0..grid.len())
+        .min_by(|&a, &b| surprises[a].total_cmp(&surprises[b]))
+        .unwrap();
+    assert_eq!(grid[best], -2.0, "{:?}", surprises);
+
+    // Filtering is that of a continuous input on the log data; the surprise
+    // adds the log-Jacobian ln x of each observation.
+    let mut lognormal = build_lognormal_random_walk(-2.0);
+    lognormal
+        .input_data(data.clone(), None, true, InputDataOptions::default())
+        .unwrap();
+    let mut gaussian = build_noisy_random_walk(-2.0);
+    gaussian
+        .input_data(log_data.clone(), None, true, InputDataOptions::default())
+        .unwrap();
+    let (input, log_input) = (
+        &lognormal.node_trajectories.nodes[0],
+        &gaussian.node_trajectories.nodes[0],
+    );
+    for (t, log_observation) in log_data.iter().enumerate() {
+        assert_close(input.mean()[t], log_input.mean()[t], "log mean");
+        assert_close(
+            lognormal.node_trajectories.nodes[1].mean()[t],
+            gaussian.node_trajectories.nodes[1].mean()[t],
+            "parent mean",
+        );
+        assert_close(
+            input.surprise()[t],
+            log_input.surprise()[t] + log_observation[0],
+            "surprise",
+        );
+        let (mu, pi) = (input.expected_mean()[t], input.expected_precision()[t]);
+        assert_close(input.lognormal_median()[t], mu.exp(), "median");
+        assert_close(input.lognormal_mean()[t], (mu + 0.5 / pi).exp(), "mean");
+        assert!(input.lognormal_mean()[t] > input.lognormal_median()[t]);
+    }
+
+    assert!(matches!(
+        lognormal.input_data(vec![vec![0.0]], None, false, InputDataOptions::default()),
+        Err(NetworkError::InvalidObservation { node_idx: 0, .. })
+    ));
+}
+
+#[test]
+fn test_input_data_with_callback_runs_after_each_step() {
+    let input_data: Vec<Vec<f64>> = (0..20).map(|t| vec![(t as f64 * 0.3).sin()]).collect();
+    let mut network = build_noisy_random_walk(-2.0);
+
+    // The callback sees each step once it is done, in order.
+    let mut calls = Vec::new();
+    let mut parent_means = Vec::new();
+    network
+        .input_data_with_callback(input_data.clone(), None, true, &mut |network, time_idx| {
+            calls.push(time_idx);
+            parent_means.push(network.attributes.states[1].mean);
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(calls, (0..20).collect::<Vec<_>>());
+    assert_eq!(
+        parent_means,
+        network.node_trajectories.nodes[1].mean().to_vec()
+    );
+
+    let mut reference = build_noisy_random_walk(-2.0);
+    reference
+        .input_data(input_data.clone(), None, true, InputDataOptions::default())
+        .unwrap();
+    for node_idx in 0..2 {
+        assert_eq!(
+            network.node_trajectories.nodes[node_idx]
+                .surprise()
+                .to_vec(),
+            reference.node_trajectories.nodes[node_idx]
+                .surprise()
+                .to_vec()
+        );
+    }
+
+    // Breaking stops the run after that step.
+    let mut early = build_noisy_random_walk(-2.0);
+    let mut n_calls = 0;
+    early
+        .input_data_with_callback(input_data, None, true, &mut |_, time_idx| {
+            n_calls += 1;
+            if time_idx == 6 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap();
+    assert_eq!(n_calls, 7);
+    assert_eq!(early.node_trajectories.time_steps.len(), 7);
+    assert_eq!(
+        early.node_trajectories.nodes[1].mean().to_vec(),
+        reference.node_trajectories.nodes[1].mean().to_vec()[..7]
+    );
+}
+
+/// Input of `kind` observing a rotating angle through a value parent of the
+/// same kind.
+fn build_angle_tracker(kind: &str) -> Network {
+    let mut network = Network::new("standard");
+    network
+        .add_nodes(
+            kind,
+            1,
+            AddNodesOptions {
+                additional_parameters: Some(HashMap::from([("precision".into(), 10.0)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes(
+            kind,
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                additional_parameters: Some(HashMap::from([("tonic_volatility".into(), -2.0)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+}
+
+#[test]
+fn test_circular_state_tracks_an_angle_across_wraps() {
+    use std::f64::consts::PI;
+    // An angle rotating by 0.1 rad per step wraps five times in 300 steps.
+    let angles: Vec<f64> = (0..300)
+        .map(|t| 0.1 * t as f64 + 0.05 * (1.3 * t as f64).sin())
+        .collect();
+    let observations: Vec<Vec<f64>> = angles
+        .iter()
+        .map(|&a| vec![rshgf::math::wrap_angle(a)])
+        .collect();
+    let tracking_errors = |network: &Network| -> Vec<f64> {
+        let input = &network.node_trajectories.nodes[0];
+        (0..300)
+            .map(|t| rshgf::math::wrap_angle(observations[t][0] - input.expected_mean()[t]).abs())
+            .collect()
+    };
+
+    let mut circular = build_angle_tracker("circular-state");
+    circular
+        .input_data(
+            observations.clone(),
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+    let errors = tracking_errors(&circular);
+    // The prediction lags the rotation by a steady arc through every wrap,
+    // and all beliefs stay on (−π, π].
+    assert!(errors.iter().all(|&e| e < 0.5), "{:?}", errors);
+    for node in &circular.node_trajectories.nodes {
+        assert!(node.mean().iter().all(|&m| m > -PI && m <= PI));
+        assert!(node.expected_mean().iter().all(|&m| m > -PI && m <= PI));
+    }
+    let input = &circular.node_trajectories.nodes[0];
+    assert!(input
+        .value_prediction_error()
+        .iter()
+        .all(|&d| d.abs() <= PI));
+
+    // The linear node reads each wrap as a jump of 2π and loses the angle
+    // for a few steps every time.
+    let mut linear = build_angle_tracker("continuous-state");
+    linear
+        .input_data(
+            observations.clone(),
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+    let linear_errors = tracking_errors(&linear);
+    let n_lost = |errors: &[f64]| errors.iter().filter(|&&e| e > 1.0).count();
+    assert_eq!(n_lost(&errors), 0);
+    assert!(n_lost(&linear_errors) >= 10, "{:?}", linear_errors);
+    let accuracy = |network: &Network| network.get_free_energy().unwrap().accuracy;
+    assert!(accuracy(&circular) > accuracy(&linear) + 100.0);
+}
+
+#[test]
+fn test_volatility_coupling_scales_the_predicted_precision() {
+    // input (0) <- value parent (1) <- volatility parent (2), with κ = 0.5.
+    let build = |volatility_coupling: Option<FloatOrList>| {
+        let mut network = Network::new("standard");
+        network
+            .add_nodes("continuous-state", 1, AddNodesOptions::default())
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(vec![0].into()),
+                    additional_parameters: Some(HashMap::from([("precision".into(), 2.0)])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    volatility_children: Some(vec![1].into()),
+                    volatility_coupling,
+                    additional_parameters: Some(HashMap::from([("mean".into(), 1.0)])),
+                    ..Default::default()
+                },
+            )
+            .map(|_| network)
+    };
+
+    let mut network = build(Some(FloatOrList::Single(0.5))).unwrap();
+    assert_eq!(
+        network.attributes.vectors[2].volatility_coupling_children,
+        [0.5]
+    );
+    assert_eq!(
+        network.attributes.vectors[1].volatility_coupling_parents,
+        [0.5]
+    );
+    network
+        .input_data(vec![vec![0.3]], None, true, InputDataOptions::default())
+        .unwrap();
+
+    // Ω = Δt · exp(ω + κ μ₂ + κ² / (2 π̂₂)) with κ = 0.5, μ₂ = 1, ω = -4 and Δt = 1.
+    let parent_expected_precision = network.node_trajectories.nodes[2].expected_precision()[0];
+    let predicted_volatility = (-4.0 + 0.5 + 0.25 / (2.0 * parent_expected_precision)).exp();
+    let expected_precision = network.node_trajectories.nodes[1].expected_precision()[0];
+    assert_close(
+        expected_precision,
+        1.0 / (1.0 / 2.0 + predicted_volatility),
+        "expected precision",
+    );
+
+    let mut unit = build(None).unwrap();
+    unit.input_data(vec![vec![0.3]], None, true, InputDataOptions::default())
+        .unwrap();
+    assert!(unit.node_trajectories.nodes[1].expected_precision()[0] < expected_precision);
+
+    // One strength per volatility edge.
+    assert!(matches!(
+        build(Some(FloatOrList::List(vec![0.5, 0.5]))),
+        Err(NetworkError::DimensionMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_aggregate_input_fuses_the_streams_by_precision() {
+    // aggregate input (0) of three streams <- value parent (1)
+    let mut network = Network::new("standard");
+    network
+        .add_nodes(
+            "aggregate-input",
+            1,
+            AddNodesOptions {
+                additional_parameters: Some(HashMap::from([("n_streams".into(), 3.0)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network.set_input_precision(0, vec![1.0, 2.0, 4.0]).unwrap();
+    let data = vec![
+        vec![1.0, 2.0, 3.0],
+        vec![f64::NAN, 2.0, 3.0],
+        vec![f64::NAN, f64::NAN, f64::NAN],
+    ];
+    network
+        .input_data(data, None, true, InputDataOptions::default())
+        .unwrap();
+
+    // x = Σ π_i x_i / Σ π_i of precision Σ π_i, over the observed streams.
+    let input = &network.node_trajectories.nodes[0];
+    assert_close(input.mean()[0], 17.0 / 7.0, "fused mean");
+    assert_close(input.expected_precision()[0], 7.0, "fused precision");
+    assert_close(input.mean()[1], 16.0 / 6.0, "renormalised mean");
+    assert_close(input.expected_precision()[1], 6.0, "renormalised precision");
+    assert_eq!(input.observed().to_vec(), [1.0, 1.0, 0.0]);
+    let parent = &network.node_trajectories.nodes[1];
+    assert_eq!(parent.mean()[2], parent.expected_mean()[2]);
+
+    // The parent sees a continuous input of the fused precision.
+    let mut single = Network::new("standard");
+    single
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                additional_parameters: Some(HashMap::from([
+                    ("precision".into(), 7.0),
+                    ("expected_precision".into(), 7.0),
+                ])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    single
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    single
+        .input_data(
+            vec![vec![17.0 / 7.0]],
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+    assert_close(
+        parent.mean()[0],
+        single.node_trajectories.nodes[1].mean()[0],
+        "parent mean",
+    );
+
+    // One column per stream.
+    assert!(matches!(
+        network.input_data(vec![vec![1.0; 4]], None, true, InputDataOptions::default()),
+        Err(NetworkError::DimensionMismatch { .. })
+    ));
+
+    // Every stream needs a finite positive precision, and there must be one.
+    for precisions in [vec![1.0, 0.0], vec![f64::NAN], vec![f64::INFINITY], vec![]] {
+        assert!(matches!(
+            network.set_input_precision(0, precisions),
+            Err(NetworkError::InvalidValue {
+                parameter: "input_precision",
+                ..
+            })
+        ));
+    }
+    assert_eq!(
+        network.attributes.vectors[0].input_precision,
+        [1.0, 2.0, 4.0]
+    );
+
+    // The stream keys only shape the node when it is built: they are not
+    // parameters of the built node, which changes streams through
+    // `set_input_precision` alone.
+    for key in ["n_streams", "input_precision"] {
+        assert!(matches!(
+            network.set_attribute(0, key, 2.0),
+            Err(NetworkError::InvalidParameter { accepted, .. }) if !accepted.contains(&key)
+        ));
+        assert!(!network.node_view(0).unwrap().parameters.contains_key(key));
+    }
+    for (parameter, value) in [
+        ("input_precision", -1.0),
+        ("input_precision", f64::INFINITY),
+        ("n_streams", 0.0),
+        ("n_streams", 2.5),
+    ] {
+        let err = Network::new("standard")
+            .add_nodes(
+                "aggregate-input",
+                1,
+                AddNodesOptions {
+                    additional_parameters: Some(HashMap::from([(parameter.into(), value)])),
+                    ..Default::default()
+                },
+            )
+            .unwrap_err();
+        assert!(
+            matches!(err, NetworkError::InvalidValue { parameter: p, .. } if p == parameter),
+            "{:?}",
+            err
+        );
+    }
 }
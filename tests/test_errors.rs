@@ -0,0 +1,627 @@
+use rshgf::error::NetworkError;
+use rshgf::model::network::{AddNodesOptions, FitOptions, InputDataOptions, Network};
+use rshgf::utils::function_pointer::UpdateStep;
+use rshgf::utils::pyhgf_spec::PyhgfNode;
+
+/// Input node 0 with a value parent (1) and a volatility parent (2).
+fn three_node_network() -> Network {
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                volatility_children: Some(0.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+}
+
+#[test]
+fn test_missing_attribute_error() {
+    let mut network = three_node_network();
+    network.attributes.vectors[0].value_coupling_parents.clear();
+
+    let err = network
+        .input_data(
+            vec![vec![0.1], vec![0.2]],
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        NetworkError::MissingAttribute {
+            node_idx: 0,
+            attribute: "value_coupling_parents",
+            step: "prediction_continuous_state_node",
+        }
+    );
+    let msg = err.to_string();
+    assert!(msg.contains("node 0"), "{}", msg);
+    assert!(msg.contains("value_coupling_parents"), "{}", msg);
+    assert!(msg.contains("prediction_continuous_state_node"), "{}", msg);
+
+    // The update sequence survives the failed call.
+    assert!(!network.update_sequence.predictions.is_empty());
+
+    // A kernel called outside a checked sequence reports the missing
+    // volatility child instead of panicking.
+    let network = &mut three_node_network();
+    assert_eq!(
+        UpdateStep::PosteriorContinuousUnbounded.call(network, 1, 1.0),
+        Err(NetworkError::MissingAttribute {
+            node_idx: 1,
+            attribute: "volatility_children",
+            step: "posterior_update_continuous_state_node_unbounded",
+        })
+    );
+}
+
+#[test]
+fn test_missing_node_error() {
+    let mut network = three_node_network();
+    let err = network
+        .fit(
+            &[vec![1.0]],
+            &[vec![0.5]],
+            &[7],
+            &[0],
+            Some(0.1),
+            false,
+            "standard",
+            FitOptions::default(),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        NetworkError::MissingNode {
+            node_idx: 7,
+            step: "set_predictors",
+        }
+    );
+    assert!(err.to_string().contains("node 7"));
+}
+
+#[test]
+fn test_invalid_edge_error() {
+    let mut network = three_node_network();
+    network.set_update_sequence();
+    network.edges[2].volatility_children = Some(vec![0, 9]);
+    network.attributes.vectors[2]
+        .volatility_coupling_children
+        .push(1.0);
+
+    let err = network.step(&[0.3], 1.0).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            NetworkError::InvalidEdge {
+                node_idx: 2,
+                edge: "volatility_children",
+                target_idx: 9,
+                ..
+            }
+        ),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+fn test_forward_reference_error() {
+    // Node 5 does not exist yet: the edge is rejected and nothing is added.
+    let mut network = three_node_network();
+    let err = network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_parents: Some(vec![2, 5].into()),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        NetworkError::InvalidEdge {
+            node_idx: 3,
+            edge: "value_parents",
+            target_idx: 5,
+            step: "add_nodes",
+            reason: "refers to missing node",
+        }
+    );
+    assert_eq!(network.edges.len(), 3);
+    assert_eq!(network.attributes.states.len(), 3);
+    assert_eq!(network.edges[2].value_children, None);
+
+    let err = network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                volatility_children: Some(vec![3].into()),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+    assert!(
+        matches!(
+            err,
+            NetworkError::InvalidEdge {
+                edge: "volatility_children",
+                target_idx: 3,
+                ..
+            }
+        ),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+fn test_unknown_node_kind_error() {
+    // An unknown kind is rejected before any node or edge is added.
+    let mut network = three_node_network();
+    let err = network
+        .add_nodes(
+            "continuous-sate",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        NetworkError::InvalidValue {
+            parameter: "kind",
+            value: "'continuous-sate'".to_string(),
+            accepted: "a node kind listed in `NODE_KINDS`",
+        }
+    );
+    assert_eq!(network.edges.len(), 3);
+    assert_eq!(network.attributes.states.len(), 3);
+    assert_eq!(network.edges[0].value_parents, Some(vec![1]));
+}
+
+#[test]
+fn test_not_runnable_errors() {
+    let mut network = Network::new("eHGF");
+    let err = network
+        .input_data(vec![vec![1.0]], None, true, InputDataOptions::default())
+        .unwrap_err();
+    assert!(
+        matches!(err, NetworkError::NotRunnable { reason } if reason.contains("no nodes")),
+        "{:?}",
+        err
+    );
+
+    let mut network = three_node_network();
+    network.inputs.clear();
+    let err = network.step(&[1.0], 1.0).unwrap_err();
+    assert!(
+        matches!(err, NetworkError::NotRunnable { reason } if reason.contains("no input node")),
+        "{:?}",
+        err
+    );
+
+    // A lone constant node is an input with nothing to update.
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("constant-state", 1, AddNodesOptions::default())
+        .unwrap();
+    let err = network
+        .input_data(vec![vec![1.0]], None, true, InputDataOptions::default())
+        .unwrap_err();
+    assert!(
+        matches!(err, NetworkError::NotRunnable { reason } if reason.contains("update sequence is empty")),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+fn test_from_pyhgf_spec_errors() {
+    // Categorical and Dirichlet process nodes have no Rust counterpart.
+    for node_type in [4, 5, 9] {
+        let spec = vec![PyhgfNode {
+            node_type,
+            ..Default::default()
+        }];
+        match Network::from_pyhgf_spec(&spec, "eHGF") {
+            Err(NetworkError::UnsupportedStructure { node_idx: 0, .. }) => {}
+            other => panic!(
+                "unexpected result for node type {}: {:?}",
+                node_type,
+                other.err()
+            ),
+        }
+    }
+
+    // A parent with two value children coupled through different functions.
+    let spec = vec![
+        PyhgfNode {
+            node_type: 2,
+            ..Default::default()
+        },
+        PyhgfNode {
+            node_type: 2,
+            ..Default::default()
+        },
+        PyhgfNode {
+            node_type: 2,
+            value_children: vec![0, 1],
+            value_coupling_children: vec![1.0, 1.0],
+            coupling_fns: vec![Some("sigmoid".into()), None],
+            ..Default::default()
+        },
+    ];
+    match Network::from_pyhgf_spec(&spec, "eHGF") {
+        Err(NetworkError::UnsupportedStructure {
+            node_idx: 2,
+            reason,
+            ..
+        }) => {
+            assert!(reason.contains("coupling functions"), "{}", reason)
+        }
+        other => panic!("unexpected result: {:?}", other.err()),
+    }
+}
+
+#[test]
+fn test_too_many_observations_error() {
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 2, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(vec![0, 1].into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let mean_before = network.attributes.states[2].mean;
+
+    // The third row is too long: the first two must not run either.
+    let err = network
+        .input_data(
+            vec![vec![0.1, 0.2], vec![0.3, 0.4], vec![0.5, 0.6, 0.7]],
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap_err();
+    let msg = err.to_string();
+    assert!(msg.contains("at most one per input node"), "{}", msg);
+    assert!(msg.contains("expected 2, got 3"), "{}", msg);
+    assert_eq!(network.attributes.states[2].mean, mean_before);
+
+    let err = network.step(&[0.1, 0.2, 0.3], 1.0).unwrap_err();
+    assert!(matches!(
+        err,
+        NetworkError::DimensionMismatch {
+            expected: 2,
+            found: 3,
+            ..
+        }
+    ));
+    assert!(network.node_trajectories.time_steps.is_empty());
+}
+
+#[test]
+fn test_dimension_mismatch_errors() {
+    let mut network = three_node_network();
+
+    // Two observations for a single input node.
+    let err = network
+        .input_data(
+            vec![vec![0.1, 0.2]],
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap_err();
+    assert_eq!(
+        err,
+        NetworkError::DimensionMismatch {
+            what:
+                "observations per time step (at most one per input node or aggregate-input stream)",
+            expected: 1,
+            found: 2,
+        }
+    );
+
+    let err = network
+        .input_data(
+            vec![vec![0.1], vec![0.2]],
+            Some(vec![1.0]),
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        NetworkError::DimensionMismatch {
+            expected: 2,
+            found: 1,
+            ..
+        }
+    ));
+
+    let err = network
+        .fit(
+            &[vec![1.0], vec![2.0]],
+            &[vec![0.5]],
+            &[1],
+            &[0],
+            Some(0.1),
+            false,
+            "standard",
+            FitOptions::default(),
+        )
+        .unwrap_err();
+    assert!(matches!(err, NetworkError::DimensionMismatch { .. }));
+
+    // Nothing was recorded by the failed calls.
+    assert!(network.node_trajectories.nodes.is_empty());
+}
+
+#[test]
+fn test_divergence_error() {
+    // A huge tonic volatility on the volatility parent makes the predicted
+    // precision of the input collapse after a few steps.
+    let data: Vec<Vec<f64>> = (0..30)
+        .map(|t| vec![(t as f64 * 0.7).sin() * 3.0])
+        .collect();
+    let mut network = three_node_network();
+    network.attributes.states[2].tonic_volatility = 6.0;
+    network
+        .input_data(data.clone(), None, true, InputDataOptions::default())
+        .unwrap();
+    let silent = network.node_trajectories.nodes[0]
+        .expected_precision()
+        .to_vec();
+    assert!(silent.iter().any(|x| x.is_nan()));
+
+    // Checked for one run, the network setting staying off.
+    let mut network = three_node_network();
+    network.attributes.states[2].tonic_volatility = 6.0;
+    let options = InputDataOptions {
+        check_divergence: true,
+        ..Default::default()
+    };
+    assert!(matches!(
+        network.input_data(data.clone(), None, true, options),
+        Err(NetworkError::Diverged { time_idx: 6, .. })
+    ));
+    assert!(!network.check_divergence);
+
+    // Likewise for a fit, which leaves the setting off even when it fails.
+    let mut network = three_node_network();
+    network.attributes.states[2].tonic_volatility = 6.0;
+    let x = vec![vec![]; data.len()];
+    let options = FitOptions {
+        check_divergence: true,
+        ..Default::default()
+    };
+    assert!(matches!(
+        network.fit(&x, &data, &[], &[0], Some(0.0), true, "standard", options),
+        Err(NetworkError::Diverged { .. })
+    ));
+    assert!(!network.check_divergence);
+
+    let mut network = three_node_network();
+    network.attributes.states[2].tonic_volatility = 6.0;
+    network.check_divergence = true;
+    let err = network
+        .input_data(data, None, true, InputDataOptions::default())
+        .unwrap_err();
+    let NetworkError::Diverged {
+        time_idx,
+        node_idx,
+        attribute,
+        ref recent_values,
+    } = err
+    else {
+        panic!("{:?}", err);
+    };
+    assert_eq!(
+        (time_idx, node_idx, attribute),
+        (6, 0, "expected_precision")
+    );
+    // The last recorded values, then the offending one.
+    assert_eq!(recent_values[..4], silent[2..6]);
+    assert!(recent_values[4].is_nan());
+    let msg = err.to_string();
+    assert!(msg.contains("time step 6"), "{}", msg);
+    assert!(msg.contains("node 0 `expected_precision`"), "{}", msg);
+    // The failed run keeps the previous trajectories.
+    assert!(network.node_trajectories.nodes.is_empty());
+}
+
+/// Input node 0 under a sigmoid value parent (1): an outlier observation
+/// makes the curvature term of the coupling drive the parent's posterior
+/// precision negative.
+fn negative_precision_network(kind: &str) -> Network {
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            kind,
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                coupling_fn: Some("sigmoid".into()),
+                additional_parameters: Some(std::collections::HashMap::from([
+                    ("mean".into(), -2.0),
+                    ("autoconnection_strength".into(), 1.0),
+                ])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+}
+
+#[test]
+fn test_negative_precision_policies() {
+    let data = vec![vec![0.1], vec![50.0], vec![0.2]];
+    for kind in ["continuous-state", "volatile-state"] {
+        // Floored, the update goes through with a tiny precision.
+        let mut network = negative_precision_network(kind);
+        network
+            .input_data(data.clone(), None, true, InputDataOptions::default())
+            .unwrap();
+        assert_eq!(network.node_trajectories.nodes[1].precision()[1], 1e-128);
+
+        // Rejected, the parent keeps its previous posterior.
+        let mut network = negative_precision_network(kind);
+        network.set_negative_precision("reject").unwrap();
+        network
+            .input_data(data.clone(), None, true, InputDataOptions::default())
+            .unwrap();
+        let parent = &network.node_trajectories.nodes[1];
+        assert_eq!(parent.precision()[1], parent.precision()[0]);
+        assert_eq!(parent.mean()[1], parent.mean()[0]);
+        assert!(parent.precision()[2] > 0.0);
+
+        // As an error, the run stops and names the node and the children.
+        let mut network = negative_precision_network(kind);
+        network.set_negative_precision("error").unwrap();
+        let err = network
+            .input_data(data.clone(), None, true, InputDataOptions::default())
+            .unwrap_err();
+        let NetworkError::NegativePrecision {
+            node_idx,
+            step,
+            expected_precision,
+            precision,
+            ref contributions,
+        } = err
+        else {
+            panic!("{:?}", err);
+        };
+        assert_eq!(node_idx, 1);
+        assert!(step.starts_with("posterior_update"), "{}", step);
+        assert!(precision < 0.0);
+        assert_eq!(contributions.len(), 1);
+        assert_eq!(contributions[0].0, 0);
+        assert!((expected_precision + contributions[0].1 - precision).abs() < 1e-12);
+        let msg = err.to_string();
+        assert!(msg.contains("node 1"), "{}", msg);
+        assert!(msg.contains("child 0"), "{}", msg);
+    }
+
+    assert_eq!(
+        Network::new("eHGF")
+            .set_negative_precision("clip")
+            .unwrap_err()
+            .to_string(),
+        "invalid `negative_precision` 'clip' (expected 'floor', 'error' or 'reject')"
+    );
+}
+
+#[test]
+fn test_from_spec_str_lists_every_problem() {
+    let spec = r#"{
+        "update_type": "fast",
+        "entropy_source": "marginal",
+        "nodes": [
+            {"kind": "continuous-state", "parameters": {"mean": 1.0, "p_target": 0.5}},
+            {"kind": "gaussian", "coupling_fn": "relu"},
+            {"kind": "continuous-state", "coupling_fn": "cubic", "likelihood": "cauchy",
+             "input_precision": [1.0]}
+        ],
+        "edges": [
+            {"parent": 1, "child": 0},
+            {"parent": 1, "child": 0},
+            {"parent": 3, "child": 2, "lag": 2},
+            {"parent": 2, "child": 2, "kind": "volatility", "frozen": true,
+             "drift": true, "child_coupling_fn": "sigmoid"},
+            {"parent": 0, "child": 1, "kind": "volatility"}
+        ]
+    }"#;
+    let problems = match Network::from_spec_str(spec) {
+        Err(NetworkError::InvalidSpec { problems }) => problems,
+        other => panic!("unexpected result: {:?}", other.err()),
+    };
+    let expected = [
+        "update_type:",
+        "entropy_source:",
+        "nodes[0]: 'continuous-state' nodes have no parameter 'p_target'",
+        "nodes[1]: unknown kind 'gaussian'",
+        "nodes[2].coupling_fn:",
+        "nodes[2].likelihood: unknown likelihood 'cauchy'",
+        "nodes[2].input_precision: only aggregate-input nodes have per-stream precisions",
+        "edges[1]: duplicate of edges[0] (1 -> 0)",
+        "edges[2]: parent 3 does not exist (3 nodes)",
+        "edges[2]: only one-step lags are supported, got 2",
+        "edges[3]: node 2 cannot be its own parent",
+        "edges[3]: only value couplings can be frozen",
+        "edges[3]: only value edges can be drift edges",
+        "edges[3]: only value edges take a child_coupling_fn",
+        "edges[4]: 0 -> 1 closes a cycle",
+    ];
+    assert_eq!(problems.len(), expected.len(), "{:#?}", problems);
+    for (problem, start) in problems.iter().zip(expected) {
+        assert!(problem.starts_with(start), "{} vs {}", problem, start);
+    }
+
+    // A cycle is reported even when every node has an input below it.
+    let spec = r#"{
+        "nodes": [
+            {"kind": "continuous-state"},
+            {"kind": "continuous-state"},
+            {"kind": "continuous-state"}
+        ],
+        "edges": [
+            {"parent": 1, "child": 0},
+            {"parent": 2, "child": 1},
+            {"parent": 1, "child": 2}
+        ]
+    }"#;
+    assert_eq!(
+        Network::from_spec_str(spec).unwrap_err(),
+        NetworkError::InvalidSpec {
+            problems: vec!["edges[2]: 1 -> 2 closes a cycle".to_string()]
+        }
+    );
+
+    // Syntax errors and unknown fields report where they are.
+    for (spec, needle) in [
+        ("{\"nodes\": [\n  {\"kind\": }]}", "line 2"),
+        (r#"{"nodes": [], "edge": []}"#, "unknown field `edge`"),
+    ] {
+        match Network::from_spec_str(spec) {
+            Err(NetworkError::InvalidSpec { problems }) => {
+                assert!(problems[0].contains(needle), "{:?}", problems)
+            }
+            other => panic!("unexpected result: {:?}", other.err()),
+        }
+    }
+}
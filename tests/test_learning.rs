@@ -0,0 +1,368 @@
+use rshgf::error::NetworkError;
+use rshgf::model::network::{AddNodesOptions, FitOptions, InputDataOptions, Network};
+use std::collections::HashMap;
+
+/// Child node 0 with two value parents (1 and 2) used as predictors.
+fn two_parent_network() -> Network {
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            2,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+}
+
+#[test]
+fn test_frozen_coupling_is_not_learned() {
+    let mut network = two_parent_network();
+    network.freeze_coupling(1, 0).unwrap();
+
+    let x: Vec<Vec<f64>> = (0..20).map(|t| vec![1.0, (t as f64 * 0.3).sin()]).collect();
+    let y: Vec<Vec<f64>> = (0..20).map(|t| vec![2.0 + t as f64 * 0.1]).collect();
+    network
+        .fit(
+            &x,
+            &y,
+            &[1, 2],
+            &[0],
+            Some(0.05),
+            false,
+            "standard",
+            FitOptions::default(),
+        )
+        .unwrap();
+
+    let child = &network.attributes.vectors[0];
+    assert_eq!(child.value_coupling_parents[0], 1.0);
+    assert_eq!(
+        network.attributes.vectors[1].value_coupling_children[0],
+        1.0
+    );
+    assert_ne!(child.value_coupling_parents[1], 1.0);
+    assert_eq!(
+        network.attributes.vectors[2].value_coupling_children[0],
+        child.value_coupling_parents[1]
+    );
+
+    // Unfreezing lets the edge learn again.
+    network.unfreeze_coupling(1, 0).unwrap();
+    network
+        .fit(
+            &x,
+            &y,
+            &[1, 2],
+            &[0],
+            Some(0.05),
+            false,
+            "standard",
+            FitOptions::default(),
+        )
+        .unwrap();
+    assert_ne!(network.attributes.vectors[0].value_coupling_parents[0], 1.0);
+}
+
+#[test]
+fn test_coupling_grad_clip_bounds_outlier_updates() {
+    // One wild target among small ones: without clipping the coupling of
+    // the constant predictor jumps, with clipping it moves by at most
+    // lr * coupling_grad_clip per step.
+    let x: Vec<Vec<f64>> = (0..5).map(|_| vec![1.0, 0.0]).collect();
+    let y: Vec<Vec<f64>> = [0.5, 0.4, 500.0, 0.6, 0.5]
+        .iter()
+        .map(|&y| vec![y])
+        .collect();
+    let (lr, clip) = (0.1, 2.0);
+
+    let mut unclipped = two_parent_network();
+    unclipped
+        .fit(
+            &x,
+            &y,
+            &[1, 2],
+            &[0],
+            Some(lr),
+            true,
+            "standard",
+            FitOptions::default(),
+        )
+        .unwrap();
+    let mut clipped = two_parent_network();
+    clipped.attributes.states[0].coupling_grad_clip = clip;
+    clipped
+        .fit(
+            &x,
+            &y,
+            &[1, 2],
+            &[0],
+            Some(lr),
+            true,
+            "standard",
+            FitOptions::default(),
+        )
+        .unwrap();
+
+    let jumps = |network: &Network| -> Vec<f64> {
+        let mut couplings = vec![1.0];
+        couplings.extend(
+            network.node_trajectories.nodes[0]
+                .value_coupling_parents
+                .data
+                .chunks(2)
+                .map(|parents| parents[0]),
+        );
+        couplings.windows(2).map(|w| (w[1] - w[0]).abs()).collect()
+    };
+    assert!(jumps(&unclipped)[2] > 10.0 * lr * clip);
+    for jump in jumps(&clipped) {
+        assert!(jump <= lr * clip + 1e-12, "{}", jump);
+    }
+    assert!((jumps(&clipped)[2] - lr * clip).abs() < 1e-12);
+}
+
+#[test]
+fn test_fit_from_spec_matches_add_nodes() {
+    let spec = r#"{
+        "update_type": "eHGF",
+        "nodes": [
+            {"kind": "continuous-state"},
+            {"kind": "continuous-state"},
+            {"kind": "continuous-state"}
+        ],
+        "edges": [
+            {"parent": 1, "child": 0, "frozen": true},
+            {"parent": 2, "child": 0}
+        ]
+    }"#;
+    let mut from_spec = Network::from_spec_str(spec).unwrap();
+    let mut built = two_parent_network();
+    built.freeze_coupling(1, 0).unwrap();
+
+    let x: Vec<Vec<f64>> = (0..20).map(|t| vec![1.0, (t as f64 * 0.3).sin()]).collect();
+    let y: Vec<Vec<f64>> = (0..20).map(|t| vec![2.0 + t as f64 * 0.1]).collect();
+    for network in [&mut from_spec, &mut built] {
+        network
+            .fit(
+                &x,
+                &y,
+                &[1, 2],
+                &[0],
+                Some(0.05),
+                true,
+                "standard",
+                FitOptions::default(),
+            )
+            .unwrap();
+    }
+
+    assert_eq!(
+        from_spec.attributes.vectors[0].value_coupling_parents,
+        built.attributes.vectors[0].value_coupling_parents
+    );
+    assert_eq!(
+        from_spec.attributes.vectors[0].value_coupling_parents[0],
+        1.0
+    );
+    for node_idx in 0..3 {
+        assert_eq!(
+            from_spec.node_trajectories.nodes[node_idx].mean().to_vec(),
+            built.node_trajectories.nodes[node_idx].mean().to_vec()
+        );
+    }
+}
+
+#[test]
+fn test_freeze_missing_edge_errors() {
+    let mut network = two_parent_network();
+    assert_eq!(
+        network.freeze_coupling(0, 1),
+        Err(NetworkError::InvalidEdge {
+            node_idx: 1,
+            edge: "value_parents",
+            target_idx: 0,
+            step: "freeze_coupling",
+            reason: "does not list node",
+        })
+    );
+    assert!(matches!(
+        network.unfreeze_coupling(2, 1),
+        Err(NetworkError::InvalidEdge {
+            step: "unfreeze_coupling",
+            ..
+        })
+    ));
+    assert!(matches!(
+        network.freeze_coupling(1, 7),
+        Err(NetworkError::MissingNode { node_idx: 7, .. })
+    ));
+}
+
+#[test]
+fn test_fit_sets_learning_rates() {
+    let mut network = two_parent_network();
+    assert!(network.get_learning_rates().is_empty());
+
+    let x: Vec<Vec<f64>> = (0..5).map(|t| vec![1.0, t as f64]).collect();
+    let y: Vec<Vec<f64>> = (0..5).map(|t| vec![t as f64 * 0.5]).collect();
+    network
+        .fit(
+            &x,
+            &y,
+            &[1, 2],
+            &[0],
+            Some(0.05),
+            false,
+            "standard",
+            FitOptions::default(),
+        )
+        .unwrap();
+    // The predictors (1 and 2) get no learning rate.
+    assert_eq!(network.get_learning_rates(), vec![(0, 0.05)]);
+
+    // With Adam, the nodes hold the default fixed rate.
+    network
+        .fit(
+            &x,
+            &y,
+            &[1, 2],
+            &[0],
+            None,
+            false,
+            "standard",
+            FitOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(network.get_learning_rates(), vec![(0, 1e-3)]);
+}
+
+/// Input 0 with a single, nearly fixed value parent 1 (mean 1) and the
+/// given coupling.
+fn single_edge_network(coupling: f64) -> Network {
+    let mut network = Network::new("standard");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                coupling: Some(coupling.into()),
+                additional_parameters: Some(HashMap::from([
+                    ("mean".into(), 1.0),
+                    ("precision".into(), 1e4),
+                    ("tonic_volatility".into(), -20.0),
+                ])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network
+}
+
+#[test]
+fn test_coupling_sensitivity_sign() {
+    // Observations of 2 predicted as coupling · 1: the surprise falls as a
+    // coupling below 2 grows, and rises as a coupling above 2 grows.
+    let data = vec![vec![2.0]; 10];
+    for (coupling, expected_sign) in [(1.0, -1.0), (3.0, 1.0)] {
+        let mut network = single_edge_network(coupling);
+        network
+            .input_data(vec![vec![0.5]; 3], None, true, InputDataOptions::default())
+            .unwrap();
+        let before = format!("{:?}", network.attributes);
+        let sensitivity = network.coupling_sensitivity(&data, None, 1e-4).unwrap();
+        assert_eq!(sensitivity.keys().collect::<Vec<_>>(), vec![&(1, 0)]);
+        assert_eq!(sensitivity[&(1, 0)].signum(), expected_sign);
+
+        // The network is left as it was, trajectories included.
+        assert_eq!(format!("{:?}", network.attributes), before);
+        assert_eq!(network.node_trajectories.time_steps.len(), 3);
+    }
+
+    // Recording without the columns of the free energy is reported, not a
+    // panic.
+    let mut network = single_edge_network(1.0);
+    network.set_record_keys(Some(vec!["mean".into()])).unwrap();
+    assert_eq!(
+        network.coupling_sensitivity(&data, None, 1e-4),
+        Err(NetworkError::UnrecordedKey {
+            node_idx: 0,
+            key: "observed".into()
+        })
+    );
+
+    // A zero, negative or non-finite step is rejected.
+    let network = single_edge_network(1.0);
+    for eps in [0.0, -1e-4, f64::NAN, f64::INFINITY] {
+        assert!(matches!(
+            network.coupling_sensitivity(&data, None, eps),
+            Err(NetworkError::InvalidValue {
+                parameter: "eps",
+                ..
+            })
+        ));
+    }
+}
+
+#[test]
+fn test_tonic_drift_is_learned_from_drifting_data() {
+    // Observed node 0 under a value parent whose drift is learned.
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                additional_parameters: Some(HashMap::from([("tonic_drift_lr".into(), 0.05)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+    // A known positive drift of 0.5 per step, with some deterministic noise.
+    let n_time = 200;
+    let x: Vec<Vec<f64>> = vec![vec![]; n_time];
+    let y: Vec<Vec<f64>> = (0..n_time)
+        .map(|t| vec![0.5 * t as f64 + 0.3 * (t as f64 * 1.7).sin()])
+        .collect();
+    network
+        .fit(
+            &x,
+            &y,
+            &[],
+            &[0],
+            Some(0.0),
+            true,
+            "standard",
+            FitOptions::default(),
+        )
+        .unwrap();
+
+    let drift = network.node_trajectories.nodes[1].tonic_drift().to_vec();
+    assert_eq!(
+        drift[0],
+        0.05 * (network.node_trajectories.nodes[1].mean()[0]
+            - network.node_trajectories.nodes[1].expected_mean()[0])
+    );
+    assert!(drift[n_time - 1] > 0.25, "{:?}", &drift[n_time - 10..]);
+    assert_eq!(network.attributes.states[1].tonic_drift, drift[n_time - 1]);
+    // Nodes without a drift rate keep theirs.
+    assert!(network.node_trajectories.nodes[0]
+        .tonic_drift()
+        .iter()
+        .all(|&d| d == 0.0));
+}
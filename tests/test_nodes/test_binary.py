@@ -1,6 +1,7 @@
 # Author: Nicolas Legrand <nicolas.legrand@cas.au.dk>
 
 import numpy as np
+import pytest
 from pyhgf.rshgf import Network as RsNetwork
 
 from pyhgf import load_data
@@ -148,3 +149,23 @@ def test_binary_3_levels():
         assert np.all(ref.node_trajectories[node_idx]["precision"] > 0), (
             f"Node {node_idx} precision must remain positive"
         )
+
+
+def test_binary_input():
+    """Test the binary input node on a 0/1 sequence."""
+    u = np.tile([1.0, 1.0, 1.0, 1.0, 0.0], 40)
+    network = (
+        RsNetwork()
+        .add_nodes(kind="binary-input")
+        .add_nodes(kind="continuous-state", value_children=0, tonic_volatility=-2.0)
+        .input_data(u)
+    )
+
+    # The parent's belief moves toward the observed proportion of ones
+    p = 1 / (1 + np.exp(-network.node_trajectories[1]["mean"][-1]))
+    assert abs(p - 0.8) < 0.1
+
+    with pytest.raises(ValueError, match="invalid observation 0.5"):
+        RsNetwork().add_nodes(kind="binary-input").add_nodes(
+            value_children=0
+        ).input_data(np.array([1.0, 0.5]))
@@ -104,3 +104,1124 @@ def test_continuous_nonlinear_coupling(mean_field_updates):
     )
 
     _assert_backends_match(py_net, rs_net, 2, "standard", label)
+
+
+def _prediction_error_continuous(network, node_idx, time_step):
+    """Python port of ``prediction_error_continuous_state_node``."""
+    mean = network.get(node_idx, "mean")
+    expected_mean = network.get(node_idx, "expected_mean")
+    precision = network.get(node_idx, "precision")
+    expected_precision = network.get(node_idx, "expected_precision")
+
+    volatility_prediction_error = (
+        expected_precision / precision
+        + expected_precision * (mean - expected_mean) ** 2
+        - 1.0
+    )
+    volatility_parents = network.edges(node_idx)["volatility_parents"]
+    if volatility_parents:
+        volatility_prediction_error /= len(volatility_parents)
+
+    network.set(node_idx, "value_prediction_error", mean - expected_mean)
+    network.set(node_idx, "volatility_prediction_error", volatility_prediction_error)
+
+
+def test_python_update_step():
+    """Test a Python callable replacing a Rust step in the update sequence.
+
+    The continuous prediction error written in Python must reproduce the Rust
+    trajectories and keep its place when the sequence is rebuilt, and an
+    exception raised by a Python step must surface as a ``RuntimeError``.
+    """
+    timeseries = load_data("continuous")
+
+    rs_net = _build_network(RsNetwork, "standard", False, 3, timeseries)
+
+    py_step_net = (
+        RsNetwork(volatility_updates="standard")
+        .add_nodes()
+        .add_nodes(value_children=0)
+        .add_nodes(volatility_children=0)
+        .set_update_sequence()
+    )
+    for node_idx, name in list(py_step_net.update_sequence):
+        if name == "prediction_error_continuous_state_node":
+            py_step_net.replace_update_step(
+                node_idx, name, _prediction_error_continuous
+            )
+    assert (0, "_prediction_error_continuous") in py_step_net.update_sequence
+    py_step_net.set_update_sequence()
+    assert (0, "_prediction_error_continuous") in py_step_net.update_sequence
+    py_step_net.input_data(input_data=timeseries)
+
+    for node_idx in range(3):
+        for key in ["mean", "expected_mean", "precision", "expected_precision"]:
+            assert np.array_equal(
+                rs_net.node_trajectories[node_idx][key],
+                py_step_net.node_trajectories[node_idx][key],
+            ), f"node {node_idx}, key '{key}' mismatch"
+
+    with pytest.raises(RuntimeError, match="no such step"):
+        py_step_net.replace_update_step(0, "unknown_step", _prediction_error_continuous)
+
+    def resizing_step(network, node_idx, time_step):
+        network.set(node_idx, "value_coupling_parents", [1.0, 1.0])
+
+    resizing_net = RsNetwork().add_nodes().add_nodes(value_children=0)
+    resizing_net.insert_update_step(0, resizing_step)
+    with pytest.raises(RuntimeError, match="holds 1 values, got 2"):
+        resizing_net.input_data(input_data=timeseries)
+
+    def failing_step(network, node_idx, time_step):
+        raise ZeroDivisionError("failing step")
+
+    py_step_net.insert_update_step(0, failing_step)
+    with pytest.raises(RuntimeError, match="failing step"):
+        py_step_net.input_data(input_data=timeseries)
+
+    # A step kind must match its phase; the graph stays readable.
+    mismatched_net = RsNetwork().add_nodes().add_nodes(value_children=0)
+    with pytest.raises(ValueError, match="prediction_error"):
+        mismatched_net.insert_update_step(
+            0, resizing_step, phase="predictions", kind="prediction_error"
+        )
+    assert len(mismatched_net.get_update_sequence_graph()) == 4
+
+
+def test_get_state_load_state():
+    """Test saving the numeric state mid-sequence and resuming from it.
+
+    The state returned by ``get_state`` must survive a JSON round trip, and
+    replaying the last steps after ``load_state`` must reproduce the beliefs.
+    """
+    import json
+
+    timeseries = load_data("continuous")[:10]
+
+    net = (
+        RsNetwork(volatility_updates="standard")
+        .add_nodes()
+        .add_nodes(value_children=0)
+        .add_nodes(volatility_children=1)
+    )
+    for observation in timeseries[:5]:
+        net.step([observation])
+    state = json.loads(json.dumps(net.get_state()))
+
+    for observation in timeseries[5:]:
+        net.step([observation])
+    original = [net.get_state()["floats"][idx]["mean"] for idx in range(3)]
+
+    net.load_state(state)
+    for observation in timeseries[5:]:
+        net.step([observation])
+    assert [net.get_state()["floats"][idx]["mean"] for idx in range(3)] == original
+
+    smaller = RsNetwork().add_nodes()
+    with pytest.raises(ValueError, match="nodes in the state"):
+        smaller.load_state(state)
+
+
+def test_record_keys():
+    """Test that only the requested keys end up in the trajectories."""
+    timeseries = load_data("continuous")[:20]
+
+    net = (
+        RsNetwork(volatility_updates="eHGF")
+        .add_nodes()
+        .add_nodes(value_children=0)
+        .input_data(timeseries, record_keys=["mean", "expected_precision"])
+    )
+    for trajectory in net.node_trajectories:
+        assert list(trajectory) == ["mean", "expected_precision"]
+        assert trajectory["mean"].shape == (20,)
+
+    # The keyword only applies to its call.
+    net.input_data(timeseries)
+    assert "effective_precision" in net.node_trajectories[1]
+
+    net.set_record_keys(["value_coupling_children"])
+    net.input_data(timeseries)
+    assert list(net.node_trajectories[0]) == []
+    assert list(net.node_trajectories[1]) == ["value_coupling_children"]
+
+    with pytest.raises(TypeError, match="`means` is not a parameter"):
+        net.set_record_keys(["means"])
+
+
+def test_metadata():
+    """Test node metadata through a JSON round trip of the state."""
+    import json
+
+    net = (
+        RsNetwork(volatility_updates="eHGF")
+        .add_nodes()
+        .add_nodes(value_children=0)
+        .set_metadata(1, "region", "insula")
+        .set_metadata(1, "condition", "A")
+    )
+    assert net.get_metadata(1, "region") == "insula"
+    assert net.get_metadata(1) == {"condition": "A", "region": "insula"}
+    assert net.get_metadata(0) == {}
+    with pytest.raises(KeyError):
+        net.get_metadata(0, "region")
+    with pytest.raises(KeyError):
+        net.set_metadata(5, "region", "V1")
+
+    state = json.loads(json.dumps(net.get_state()))
+    restored = RsNetwork(volatility_updates="eHGF").add_nodes().add_nodes(value_children=0)
+    restored.load_state(state)
+    assert restored.get_metadata(1) == {"condition": "A", "region": "insula"}
+
+
+
+def test_spec(tmp_path):
+    """Test building a network from a JSON spec, as a string or a file."""
+    import json
+
+    spec = {
+        "update_type": "eHGF",
+        "nodes": [
+            {"kind": "continuous-state"},
+            {"kind": "continuous-state", "parameters": {"tonic_volatility": -3.0}},
+        ],
+        "edges": [{"parent": 1, "child": 0, "coupling": 0.5}],
+    }
+    net = RsNetwork.from_spec(json.dumps(spec))
+    built = (
+        RsNetwork(volatility_updates="eHGF")
+        .add_nodes()
+        .add_nodes(value_children=0, coupling=0.5, tonic_volatility=-3.0)
+    )
+    assert json.loads(net.to_spec()) == json.loads(built.to_spec())
+
+    path = tmp_path / "network.json"
+    path.write_text(net.to_spec())
+    assert RsNetwork.from_spec(str(path)).to_spec() == net.to_spec()
+
+    spec["edges"].append({"parent": 2, "child": 0})
+    with pytest.raises(ValueError, match="parent 2 does not exist"):
+        RsNetwork.from_spec(json.dumps(spec))
+
+
+def test_summary():
+    """Test the per-node summary against numpy on the exported trajectories."""
+    timeseries = load_data("continuous")[:50]
+
+    net = RsNetwork(volatility_updates="eHGF").add_nodes()
+    assert net.summary() == [{}]
+
+    net = (
+        RsNetwork(volatility_updates="eHGF")
+        .add_nodes()
+        .add_nodes(value_children=0)
+        .add_nodes(volatility_children=1)
+        .input_data(timeseries)
+    )
+    summary = net.summary()
+    assert len(summary) == len(net.node_trajectories)
+    for stats, trajectory in zip(summary, net.node_trajectories):
+        assert np.isclose(stats["average_mean"], np.mean(trajectory["mean"]))
+        assert np.isclose(stats["variance_mean"], np.var(trajectory["mean"]))
+        assert np.isclose(stats["average_precision"], np.mean(trajectory["precision"]))
+        assert stats["final_mean"] == trajectory["mean"][-1]
+        assert stats["final_precision"] == trajectory["precision"][-1]
+    assert np.isclose(
+        summary[0]["total_surprise"], -net.get_free_energy()["accuracy"]
+    )
+    assert "total_surprise" not in summary[1]
+
+    net.input_data(timeseries, record_keys=["mean"])
+    assert sorted(net.summary()[1]) == ["average_mean", "final_mean", "variance_mean"]
+
+
+
+def test_surprise_by_node():
+    """Test the per-node surprise against the recorded trajectories."""
+    timeseries = load_data("continuous")[:50]
+
+    net = (
+        RsNetwork(volatility_updates="eHGF")
+        .add_nodes()
+        .add_nodes(value_children=0)
+        .add_nodes(volatility_children=1)
+        .input_data(timeseries)
+    )
+    by_node = net.surprise_by_node()
+    assert len(by_node) == net.n_nodes
+    for total, trajectory in zip(by_node, net.node_trajectories):
+        assert np.isclose(total, np.sum(trajectory["surprise"]))
+    assert np.isclose(by_node[0], net.summary()[0]["total_surprise"])
+
+    net.input_data(timeseries, record_keys=["mean"])
+    with pytest.raises(KeyError):
+        net.surprise_by_node()
+
+
+def test_entropy():
+    """Test the recorded entropy against the closed-form Gaussian entropy."""
+    timeseries = load_data("continuous")[:50]
+
+    net = (
+        RsNetwork(volatility_updates="eHGF")
+        .add_nodes()
+        .add_nodes(value_children=0)
+        .input_data(timeseries)
+    )
+    precision = net.node_trajectories[1]["precision"]
+    assert np.allclose(net.get_entropy(1), 0.5 * np.log(2 * np.pi * np.e / precision))
+
+    net.set_entropy_source("prediction").input_data(timeseries)
+    expected_precision = net.node_trajectories[1]["expected_precision"]
+    assert np.allclose(
+        net.get_entropy(1), 0.5 * np.log(2 * np.pi * np.e / expected_precision)
+    )
+    with pytest.raises(ValueError):
+        net.set_entropy_source("likelihood")
+
+
+def test_cv_surprise():
+    """Test the leave-future-out surprise against a filtered run."""
+    timeseries = load_data("continuous")[:100]
+
+    net = RsNetwork().add_nodes().add_nodes(value_children=0)
+    folds = net.cv_surprise(timeseries, train_sizes=[20, 50, 80], horizon=10)
+    assert len(folds) == 3
+    assert all(np.isfinite(folds))
+
+    # One held-out step is scored as in a filtered run.
+    (one_step,) = net.cv_surprise(timeseries, train_sizes=[50], horizon=1)
+    net.input_data(timeseries[:51])
+    assert np.isclose(one_step, net.node_trajectories[0]["surprise"][50])
+
+    with pytest.raises(IndexError):
+        net.cv_surprise(timeseries, train_sizes=[100], horizon=10)
+
+
+def test_merge():
+    """Test merging two networks against the same network built at once."""
+    timeseries = load_data("continuous")[:50]
+
+    merged = RsNetwork().add_nodes().merge(RsNetwork().add_nodes(), [(1, 0, 1.0)])
+    assert merged.n_nodes == 2
+    merged.input_data(timeseries)
+
+    direct = RsNetwork().add_nodes().add_nodes(value_children=0).input_data(timeseries)
+    assert np.allclose(
+        merged.node_trajectories[1]["mean"], direct.node_trajectories[1]["mean"]
+    )
+
+    with pytest.raises(KeyError):
+        RsNetwork().add_nodes().merge(RsNetwork().add_nodes(), [(2, 0, 1.0)])
+
+
+def test_input_data_conditions():
+    """Test that condition labels switch the tonic volatility trial by trial."""
+    timeseries = load_data("continuous")[:20]
+    conditions = [t % 2 for t in range(20)]
+
+    net = (
+        RsNetwork()
+        .add_nodes()
+        .add_nodes(value_children=0)
+        .input_data(
+            timeseries,
+            conditions=conditions,
+            condition_parameters={
+                0: {(1, "tonic_volatility"): -4.0},
+                1: {(1, "tonic_volatility"): 0.0},
+            },
+        )
+    )
+    trajectory = net.node_trajectories[1]
+    assert np.array_equal(
+        trajectory["tonic_volatility"], np.where(conditions, 0.0, -4.0)
+    )
+    added_variance = 1 / trajectory["expected_precision"][1:] - (
+        1 / trajectory["precision"][:-1]
+    )
+    assert np.allclose(added_variance, np.exp(trajectory["tonic_volatility"][1:]))
+
+    with pytest.raises(ValueError):
+        net.input_data(timeseries, conditions=conditions)
+
+
+def test_input_data_covariates():
+    """Test that a covariate-driven drift tracks a ramp the plain model misses."""
+    intensity = 1.0 + np.sin(np.arange(40) * 0.3)
+    ramp = np.cumsum(0.5 * intensity)
+
+    def prediction_error(**kwargs):
+        net = RsNetwork().add_nodes().add_nodes(value_children=0)
+        net.input_data(ramp, **kwargs)
+        expected_mean = net.node_trajectories[0]["expected_mean"]
+        return np.sum((expected_mean[10:] - ramp[10:]) ** 2)
+
+    driven = prediction_error(
+        covariates=intensity[:, None], covariate_weights={0: (1, 0.5)}
+    )
+    assert driven < 0.1 * prediction_error()
+
+    with pytest.raises(ValueError):
+        prediction_error(covariates=intensity[:, None])
+
+
+def test_input_data_pe_gains():
+    """Test that an unattended observation leaves the parent at its prediction."""
+    data = np.sin(np.arange(30) * 0.4)
+    gains = np.where((np.arange(30) >= 10) & (np.arange(30) < 20), 0.0, 1.0)
+    net = RsNetwork().add_nodes().add_nodes(value_children=0)
+    net.input_data(data, pe_gains={0: list(gains)})
+
+    parent = net.node_trajectories[1]
+    assert np.array_equal(parent["mean"][10:20], parent["expected_mean"][10:20])
+    assert np.array_equal(
+        parent["precision"][10:20], parent["expected_precision"][10:20]
+    )
+    assert np.array_equal(net.node_trajectories[0]["pe_gain"], gains)
+
+    with pytest.raises(ValueError, match="gain"):
+        net.input_data(data, pe_gains={0: [2.0] * 30})
+
+
+def test_add_nodes_autoconnection_strength():
+    """Test that the autoconnection strength set at build time scales the first prediction."""
+
+    def first_expected_mean(**kwargs):
+        net = RsNetwork().add_nodes().add_nodes(value_children=0, mean=2.0, **kwargs)
+        net.input_data(np.zeros(1))
+        return net.node_trajectories[1]["expected_mean"][0]
+
+    assert first_expected_mean() == 2.0
+    assert first_expected_mean(autoconnection_strength=0.5) == 1.0
+
+    with pytest.raises(TypeError):
+        RsNetwork().add_nodes(kind="binary-state", autoconnection_strength=0.5)
+    for value in [5.0, -0.5, float("nan")]:
+        with pytest.raises(ValueError):
+            RsNetwork().add_nodes().add_nodes(
+                value_children=0, autoconnection_strength=value
+            )
+
+
+def test_set_coupling_lag():
+    """Test that a lagged edge feeds the child the parent's mean one step later."""
+    data = np.stack([np.sin(np.arange(40) * 0.3), np.zeros(40)], axis=1)
+
+    def trajectories(lag):
+        net = RsNetwork().add_nodes().add_nodes().add_nodes(value_children=[0, 1])
+        net.set_coupling_lag(2, 1, lag)
+        net.input_data(data, pe_gains={1: [0.0] * 40})
+        return (
+            net.node_trajectories[2]["mean"],
+            net.node_trajectories[1]["expected_mean"],
+        )
+
+    parent, child = trajectories(0)
+    _, lagged_child = trajectories(1)
+    assert np.array_equal(child[2:], parent[1:-1])
+    assert np.array_equal(lagged_child[2:], parent[:-2])
+
+    with pytest.raises(ValueError):
+        RsNetwork().add_nodes().add_nodes(value_children=0).set_coupling_lag(1, 0, 2)
+
+
+def test_n_nodes_and_is_input():
+    """Test the node count and input lookup on a mixed network."""
+    net = (
+        RsNetwork()
+        .add_nodes(kind="binary-state")
+        .add_nodes(value_children=0)
+        .add_nodes()
+        .add_nodes(value_children=2, volatility_children=1)
+    )
+    assert net.n_nodes == 4 == len(net.edges)
+    assert [net.is_input(idx) for idx in range(net.n_nodes)] == [
+        True,
+        False,
+        True,
+        False,
+    ]
+    assert not net.is_input(10)
+
+
+def test_warm_restart():
+    """Test resuming a run in a new network from the state of a previous one.
+
+    Two 100-step runs with a ``get_state``/``set_state`` round trip in between
+    must match a single 200-step run.
+    """
+    timeseries = load_data("continuous")[:200]
+
+    def build():
+        return (
+            RsNetwork(volatility_updates="eHGF")
+            .add_nodes()
+            .add_nodes(value_children=0)
+            .add_nodes(volatility_children=1)
+        )
+
+    single_run = build().input_data(timeseries)
+    first_half = build().input_data(timeseries[:100])
+    second_half = build().set_state(first_half.get_state())
+    second_half.input_data(timeseries[100:])
+
+    for single, resumed in zip(
+        single_run.node_trajectories, second_half.node_trajectories
+    ):
+        for key in ["mean", "precision", "expected_mean", "expected_precision"]:
+            assert np.array_equal(single[key][100:], resumed[key])
+
+
+def test_node_trajectories_slice():
+    """Test slicing the recorded time steps against slicing the full export."""
+    timeseries = load_data("continuous")[:50]
+    network = (
+        RsNetwork(volatility_updates="eHGF")
+        .add_nodes()
+        .add_nodes(value_children=0)
+        .add_nodes(volatility_children=1)
+    ).input_data(timeseries)
+
+    full = network.node_trajectories
+    for start, stop, step in [(0, None, 1), (10, 30, 3), (-20, -5, 2), (7, 7, 1)]:
+        sliced = network.get_node_trajectories_slice(start, stop, step)
+        for full_node, sliced_node in zip(full, sliced):
+            assert sliced_node.keys() == full_node.keys()
+            for key, values in full_node.items():
+                assert np.array_equal(
+                    values[start:stop:step], sliced_node[key], equal_nan=True
+                )
+
+    for start, stop in [(0, 51), (-51, None), (30, 10)]:
+        with pytest.raises(IndexError):
+            network.get_node_trajectories_slice(start, stop)
+
+
+def test_too_many_observations():
+    """Test that more observations than input nodes raise a clear error."""
+    network = RsNetwork().add_nodes(n_nodes=2).add_nodes(value_children=[0, 1])
+
+    with pytest.raises(ValueError, match="at most one per input node.*expected 2, got 3"):
+        network.input_data(np.ones((5, 3)))
+    with pytest.raises(ValueError, match="expected 2, got 3"):
+        network.step(np.ones(3))
+
+
+def test_get_trajectory():
+    """Test the single-trajectory getter against the full export."""
+    timeseries = load_data("continuous")[:50]
+    network = (
+        RsNetwork(volatility_updates="eHGF")
+        .add_nodes()
+        .add_nodes(value_children=0)
+        .add_nodes(volatility_children=1)
+    ).input_data(timeseries)
+
+    for node_idx, full in enumerate(network.node_trajectories):
+        assert network.list_recorded_keys(node_idx) == list(full.keys())
+        for key, values in full.items():
+            assert np.array_equal(
+                network.get_trajectory(node_idx, key), values, equal_nan=True
+            )
+    assert network.get_trajectory(1, "value_coupling_children").ndim == 2
+
+    with pytest.raises(KeyError, match="no recorded trajectory"):
+        network.get_trajectory(1, "not_a_key")
+    with pytest.raises(KeyError, match="does not exist"):
+        network.get_trajectory(5, "mean")
+
+    network.set_record_keys(["mean"]).input_data(timeseries)
+    assert network.list_recorded_keys(1) == ["mean"]
+    with pytest.raises(KeyError):
+        network.get_trajectory(1, "expected_mean")
+    with pytest.raises(KeyError, match="no recorded trajectory for `observed`"):
+        network.get_free_energy()
+
+
+def test_check_divergence():
+    """Test that a diverging run stops with a diagnostic when checked."""
+    timeseries = np.sin(np.arange(30) * 0.7) * 3.0
+
+    def build():
+        return (
+            RsNetwork(volatility_updates="eHGF")
+            .add_nodes()
+            .add_nodes(value_children=0)
+            .add_nodes(volatility_children=1, tonic_volatility=6.0)
+        )
+
+    # Unchecked, the NaN values end up in the trajectories.
+    network = build().input_data(timeseries)
+    assert np.isnan(network.node_trajectories[1]["expected_precision"]).any()
+
+    with pytest.raises(
+        FloatingPointError, match="time step 4: node 1 `expected_precision`"
+    ):
+        build().input_data(timeseries, check_divergence=True)
+
+
+def test_predictive_intervals():
+    """Test the predictive intervals against the recorded predictions."""
+    timeseries = load_data("continuous")[:50]
+    network = (
+        RsNetwork(volatility_updates="eHGF")
+        .add_nodes()
+        .add_nodes(value_children=0)
+        .add_nodes(volatility_children=1)
+    ).input_data(timeseries)
+
+    lower, upper = network.predictive_intervals(1, z=2.0)
+    trajectories = network.node_trajectories[1]
+    half_width = 2.0 / np.sqrt(trajectories["expected_precision"])
+    assert np.allclose(upper - trajectories["expected_mean"], half_width)
+    assert np.allclose(trajectories["expected_mean"] - lower, half_width)
+
+
+@pytest.mark.parametrize("negative_precision", ["floor", "reject", "error"])
+def test_negative_precision(negative_precision):
+    """Test the policies for a posterior precision driven negative.
+
+    The curvature of a sigmoid value coupling turns an outlier observation
+    into a negative precision update for the parent.
+    """
+    network = (
+        RsNetwork(volatility_updates="eHGF", negative_precision=negative_precision)
+        .add_nodes()
+        .add_nodes(value_children=0, coupling_fn="sigmoid", mean=-2.0)
+    )
+    timeseries = np.array([0.1, 50.0, 0.2])
+
+    if negative_precision == "error":
+        with pytest.raises(
+            FloatingPointError, match="node 1: non-positive posterior precision"
+        ):
+            network.input_data(timeseries)
+        return
+
+    network.input_data(timeseries)
+    precision = network.node_trajectories[1]["precision"]
+    mean = network.node_trajectories[1]["mean"]
+    if negative_precision == "floor":
+        assert precision[1] == 1e-128
+    else:
+        assert precision[1] == precision[0]
+        assert mean[1] == mean[0]
+
+
+def test_forward_reference():
+    """Test that add_nodes rejects edges to nodes that do not exist yet."""
+    network = RsNetwork().add_nodes()
+    with pytest.raises(RuntimeError, match="`value_parents` refers to missing node 5"):
+        network.add_nodes(value_parents=5)
+    assert len(network.edges) == 1
+
+
+def test_describe_updates():
+    """Test the update functions reported before running."""
+    network = (
+        RsNetwork(volatility_updates="eHGF")
+        .add_nodes()
+        .add_nodes(value_children=0)
+        .add_nodes(volatility_children=0)
+    )
+    assert network.describe_updates() == [
+        (0, "prediction_error_continuous_state_node"),
+        (1, "posterior_update_continuous_state_node"),
+        (2, "posterior_update_continuous_state_node_ehgf"),
+    ]
+
+
+def test_not_runnable():
+    """Test the errors raised by input_data on networks that cannot run."""
+    with pytest.raises(RuntimeError, match="the network has no nodes"):
+        RsNetwork().input_data(np.array([1.0, 2.0]))
+
+    with pytest.raises(ValueError, match="at most one per input node"):
+        RsNetwork().add_nodes().input_data(np.array([[1.0, 2.0]]))
+
+    with pytest.raises(RuntimeError, match="the update sequence is empty"):
+        RsNetwork().add_nodes(kind="constant-state").input_data(np.array([1.0]))
+
+
+def test_network_introspection():
+    """Test repr, len and node iteration."""
+    network = (
+        RsNetwork(volatility_updates="eHGF")
+        .add_nodes()
+        .add_nodes(value_children=0, tonic_volatility=-2.0)
+        .add_nodes(kind="binary-state")
+    )
+    assert len(network) == 3
+    assert repr(network) == (
+        "Network(n_nodes=3, kinds={'continuous-state': 2, 'binary-state': 1}, "
+        "inputs=[0, 2], volatility_updates='eHGF')"
+    )
+
+    nodes = list(network)
+    assert [node.idx for node in nodes] == [0, 1, 2]
+    assert [node.kind for node in network.nodes()] == [
+        "continuous-state",
+        "continuous-state",
+        "binary-state",
+    ]
+    assert nodes[0].value_parents == [1]
+    assert nodes[1].value_children == [0]
+    assert nodes[1].parameters["tonic_volatility"] == -2.0
+    assert repr(nodes[1]).startswith("NodeView(idx=1, kind='continuous-state'")
+
+
+def test_trajectory_matrix():
+    """Test the time-major matrix of the scalar trajectories."""
+    network = (
+        RsNetwork()
+        .add_nodes()
+        .add_nodes(value_children=0)
+        .add_nodes(volatility_children=1)
+        .input_data(np.array([0.2, -0.1, 0.4, 0.0]))
+    )
+    matrix, names = network.get_trajectory_matrix()
+
+    trajectories = network.node_trajectories
+    scalar_keys = [
+        (node_idx, key)
+        for node_idx, node in enumerate(trajectories)
+        for key, values in node.items()
+        if np.ndim(values) == 1
+    ]
+    assert matrix.shape == (4, len(scalar_keys))
+    assert names == [f"node{idx}::{key}" for idx, key in sorted(scalar_keys)]
+    for col, name in enumerate(names):
+        node, key = name.removeprefix("node").split("::")
+        assert np.array_equal(matrix[:, col], trajectories[int(node)][key])
+
+
+def test_node_handle():
+    """Test the live node views returned by indexing the network."""
+    u = np.array([0.2, -0.1, 0.5, 0.3])
+
+    def network():
+        return RsNetwork().add_nodes().add_nodes(value_children=0)
+
+    reference = network().input_data(u)
+
+    hgf = network()
+    node = hgf[1]
+    assert (node.idx, node.kind) == (1, "continuous-state")
+    assert node.children == [0]
+    assert hgf[-1].idx == 1
+    assert node.tonic_volatility == -4.0
+
+    node.tonic_volatility = -1.0
+    assert hgf.nodes()[1].parameters["tonic_volatility"] == -1.0
+    hgf.input_data(u)
+    assert not np.allclose(
+        hgf.node_trajectories[1]["precision"],
+        reference.node_trajectories[1]["precision"],
+    )
+    # The view reads the live attributes.
+    assert node.mean == hgf.node_trajectories[1]["mean"][-1]
+
+    with pytest.raises(TypeError, match="`observed` is not a parameter"):
+        node["observed"] = 0.0
+    with pytest.raises(IndexError):
+        hgf[2]
+
+
+def test_sample_observation_noise():
+    """Test that observation noise widens the observations only."""
+    network = RsNetwork().add_nodes().add_nodes(value_children=0)
+    clean_obs, clean_states = network.sample(500, seed=3)
+    noisy_obs, noisy_states = network.sample(500, seed=3, observation_noise=4.0)
+
+    assert np.array_equal(clean_states, noisy_states)
+    assert np.var(noisy_obs) > np.var(clean_obs)
+    assert abs(np.std(noisy_obs - clean_obs) - 4.0) < 0.5
+
+    with pytest.raises(ValueError, match="observation_noise"):
+        network.sample(10, observation_noise=-1.0)
+
+
+def test_network_equality():
+    """Test network comparison and structural hashing."""
+
+    def network():
+        return RsNetwork().add_nodes().add_nodes(value_children=0)
+
+    a, b = network(), network()
+    assert a == b
+    assert a.structure_hash() == b.structure_hash()
+
+    b.set_coupling(1, 0, 0.5)
+    assert a != b
+    assert not a.equals(b, tol=0.1)
+    assert a.equals(b, tol=1.0)
+    assert a.structure_hash() == b.structure_hash()
+
+    c = network().add_nodes(volatility_children=1)
+    assert a != c
+    assert a.structure_hash() != c.structure_hash()
+
+
+def test_get_coupling():
+    """Test reading back the couplings written by set_coupling."""
+    network = (
+        RsNetwork()
+        .add_nodes()
+        .add_nodes(value_children=0)
+        .add_nodes(volatility_children=1)
+        .set_coupling(1, 0, 0.5)
+        .set_volatility_coupling(2, 1, 0.25)
+    )
+    assert network.get_coupling(1, 0) == 0.5
+    assert network.get_volatility_coupling(2, 1) == 0.25
+    assert network.get_coupling(0, 1) is None
+    assert network.get_coupling(2, 1) is None
+    assert network.get_volatility_coupling(1, 0) is None
+
+
+def test_model_summary():
+    """Test the structural description of a three-level network."""
+    network = (
+        RsNetwork(volatility_updates="eHGF")
+        .add_nodes()
+        .add_nodes(value_children=0, coupling_fn="sigmoid")
+        .add_nodes(volatility_children=1)
+    )
+    assert network.get_model_summary(as_dict=True) == {
+        "n_nodes": 3,
+        "nodes_by_kind": {"continuous-state": 3},
+        "n_value_edges": 1,
+        "n_volatility_edges": 1,
+        "update_type": "eHGF",
+        "inputs": [0],
+        "value_coupling_fns": ["sigmoid"],
+        "volatility_coupling_fns": ["linear"],
+    }
+    summary = network.get_model_summary()
+    assert "Nodes: 3 (continuous-state: 3)" in summary
+    assert "value [sigmoid], volatility [linear]" in summary
+
+
+def test_update_type():
+    """Test switching the posterior updates on an existing network."""
+    timeseries = load_data("continuous")[:100]
+
+    def network(update_type):
+        return (
+            RsNetwork(volatility_updates=update_type)
+            .add_nodes()
+            .add_nodes(value_children=0)
+            .add_nodes(volatility_children=1)
+        )
+
+    flipped = network("eHGF")
+    initial_state = flipped.get_state()
+    for update_type in ["standard", "unbounded", "eHGF"]:
+        flipped.load_state(initial_state)
+        flipped.update_type = update_type
+        assert flipped.update_type == update_type
+        flipped.input_data(timeseries)
+
+        fresh = network(update_type).input_data(timeseries)
+        for node_idx in range(3):
+            for key in ["mean", "precision"]:
+                assert np.array_equal(
+                    flipped.node_trajectories[node_idx][key],
+                    fresh.node_trajectories[node_idx][key],
+                )
+
+    with pytest.raises(ValueError, match="invalid `update_type`"):
+        flipped.update_type = "ehgf"
+    with pytest.raises(ValueError, match="invalid `update_type`"):
+        RsNetwork(volatility_updates="nope")
+
+
+def test_edges_coupling_fns():
+    """Test the coupling function names reported per value parent."""
+    network = (
+        RsNetwork(volatility_updates="eHGF")
+        .add_nodes(n_nodes=2)
+        .add_nodes(value_children=0, coupling_fn="sigmoid")
+        .add_nodes(value_children=[0, 1])
+    )
+    edges = network.edges
+    assert edges[0]["coupling_fns"] == {2: "sigmoid", 3: "linear"}
+    assert edges[1]["coupling_fns"] == {3: "linear"}
+    assert edges[2]["coupling_fns"] == {}
+
+
+@pytest.mark.parametrize("volatility_updates", VOLATILITY_UPDATES)
+def test_from_pyhgf_spec(volatility_updates):
+    """Test importing a 3-level continuous HGF defined with the JAX backend."""
+    timeseries = load_data("continuous")[:200]
+
+    py_net = (
+        PyNetwork(volatility_updates=volatility_updates)
+        .add_nodes()
+        .add_nodes(value_children=0, tonic_volatility=-3.0)
+        .add_nodes(volatility_children=1, mean=1.0)
+    )
+    rs_net = RsNetwork.from_pyhgf_spec(py_net)
+    assert rs_net.update_type == volatility_updates
+    # The tuple returned by get_network() builds the same network.
+    assert rs_net == RsNetwork.from_pyhgf_spec(
+        py_net.get_network(), volatility_updates=volatility_updates
+    )
+
+    py_net.input_data(input_data=timeseries)
+    rs_net.input_data(timeseries)
+    _assert_backends_match(py_net, rs_net, 3, volatility_updates, volatility_updates)
+
+    with pytest.raises(RuntimeError, match="categorical"):
+        RsNetwork.from_pyhgf_spec(PyNetwork().add_nodes(kind="categorical-state"))
+
+
+def test_to_pyhgf_spec():
+    """Test exporting a network in the pyhgf layout and importing it back."""
+    network = (
+        RsNetwork(volatility_updates="eHGF")
+        .add_nodes()
+        .add_nodes(value_children=0, coupling_fn="sigmoid")
+        .add_nodes(volatility_children=1, tonic_volatility=-3.0)
+    )
+    attributes, edges, update_sequence = network.to_pyhgf_spec()
+    assert update_sequence is None
+    assert [edge.node_type for edge in edges] == [2, 2, 2]
+    assert edges[1].value_children == (0,)
+    assert edges[1].coupling_fn == ("sigmoid",)
+    assert edges[2].volatility_children == (1,)
+    assert attributes[2]["tonic_volatility"] == -3.0
+    assert attributes[2]["volatility_coupling_children"] == (1.0,)
+
+    assert RsNetwork.from_pyhgf_spec(
+        (attributes, edges, update_sequence), volatility_updates="eHGF"
+    ) == network
+
+    # The volatility level of a volatile node becomes an explicit parent.
+    volatile = RsNetwork().add_nodes().add_nodes(kind="volatile-state", value_children=0)
+    _, edges, _ = volatile.to_pyhgf_spec()
+    assert len(edges) == 3
+    assert edges[1].volatility_parents == (2,)
+    assert edges[2].volatility_children == (1,)
+
+
+def test_dp_state():
+    """Test that a dp-state node opens one cluster per mode of its inputs."""
+    network = RsNetwork().add_nodes(kind="dp-state", precision=4.0)
+    network.input_data([0.1, -0.2, 10.2, 0.3, 9.8, 10.1])
+    assert network.n_nodes == 3
+    assert network[0].parents == [1, 2]
+    assert abs(network[1].mean) < 0.5
+    assert abs(network[2].mean - 10.0) < 0.5
+
+    with pytest.raises(TypeError):
+        RsNetwork().add_nodes(kind="dp-state", tonic_volatility=-2.0)
+
+
+def test_input_data_empty():
+    """Test that an empty run records every key, zero time steps long."""
+    network = RsNetwork().add_nodes().add_nodes(value_children=0)
+    network.input_data([])
+    trajectories = network.node_trajectories
+    assert len(trajectories) == 2
+    for node_idx, trajectory in enumerate(trajectories):
+        assert list(trajectory) == network.list_recorded_keys(node_idx)
+        assert all(len(values) == 0 for values in trajectory.values())
+    assert "mean" in trajectories[1]
+    assert "value_coupling_children" in trajectories[1]
+
+
+def test_set_drift_coupling():
+    """Test that a chain of drift parents tracks a constant acceleration."""
+    data = 0.5 * 0.2 * np.arange(40.0) ** 2
+
+    def total_surprise(n_levels):
+        net = RsNetwork(volatility_updates="eHGF").add_nodes(expected_precision=1e2)
+        for level in range(1, n_levels + 1):
+            net.add_nodes(value_children=level - 1, tonic_volatility=-6.0)
+            if level > 1:
+                net.set_drift_coupling(level, level - 1)
+        net.input_data(data)
+        return net.surprise_by_node()[0]
+
+    assert total_surprise(3) < total_surprise(2)
+
+    with pytest.raises(RuntimeError, match="does not list node 1"):
+        RsNetwork().add_nodes().set_drift_coupling(1, 0)
+
+
+def test_steady_state_precision():
+    """Test the closed-form steady state against the plateau of a long run."""
+    network = (
+        RsNetwork(volatility_updates="eHGF")
+        .add_nodes()
+        .add_nodes(value_children=0, tonic_volatility=-2.0)
+    )
+    analytic = network.steady_state_precision(1)
+    assert np.isclose(analytic, 0.5 + np.sqrt(0.25 + np.exp(2.0)))
+
+    network.input_data(np.sin(np.arange(200) * 0.3))
+    assert np.isclose(network.node_trajectories[1]["precision"][-1], analytic)
+
+    with pytest.raises(KeyError):
+        network.steady_state_precision(5)
+
+
+def test_bounded_state():
+    """Test that a bounded input filters the logit of its observations."""
+    proportions = np.array([0.2, 0.35, 0.0, 0.6, 0.9, 0.75, 0.5])
+
+    def run(lower, upper):
+        network = (
+            RsNetwork(volatility_updates="eHGF")
+            .add_nodes(kind="bounded-state", lower_bound=lower, upper_bound=upper)
+            .add_nodes(value_children=0)
+        )
+        network.input_data(lower + (upper - lower) * proportions)
+        return network.node_trajectories
+
+    unit, scaled = run(0.0, 1.0), run(3.0, 5.0)
+    assert np.allclose(unit[1]["mean"], scaled[1]["mean"])
+    assert np.allclose(
+        scaled[0]["expected_bounded_mean"], 3.0 + 2.0 * unit[0]["expected_bounded_mean"]
+    )
+    assert np.allclose(scaled[0]["surprise"] - unit[0]["surprise"], np.log(2.0))
+
+    with pytest.raises(ValueError):
+        RsNetwork().add_nodes(kind="bounded-state").input_data([1.5])
+
+
+def test_lognormal_state():
+    """Test that a lognormal input filters the log of its observations."""
+    np.random.seed(1)
+    log_data = np.cumsum(np.random.normal(0.0, 0.3, 200))
+
+    def run(kind, data):
+        network = (
+            RsNetwork()
+            .add_nodes(kind=kind, precision=4.0)
+            .add_nodes(value_children=0, tonic_volatility=-2.0)
+        )
+        network.input_data(data)
+        return network.node_trajectories
+
+    lognormal = run("lognormal-state", np.exp(log_data))
+    gaussian = run("continuous-state", log_data)
+    assert np.allclose(lognormal[1]["mean"], gaussian[1]["mean"])
+    assert np.allclose(lognormal[0]["surprise"], gaussian[0]["surprise"] + log_data)
+    assert np.allclose(
+        lognormal[0]["lognormal_median"], np.exp(lognormal[0]["expected_mean"])
+    )
+    assert (lognormal[0]["lognormal_mean"] > lognormal[0]["lognormal_median"]).all()
+
+    with pytest.raises(ValueError):
+        RsNetwork().add_nodes(kind="lognormal-state").input_data([-1.0])
+
+
+def test_input_data_with_callback():
+    """Test the per-step callback of input_data."""
+    timeseries = load_data("continuous")[:50]
+
+    calls, means = [], []
+
+    def record(time_idx, attributes):
+        calls.append(time_idx)
+        means.append(attributes[1]["mean"])
+
+    network = RsNetwork().add_nodes().add_nodes(value_children=0)
+    network.input_data_with_callback(timeseries, record, snapshot=True)
+    assert calls == list(range(50))
+    assert np.allclose(means, network.node_trajectories[1]["mean"])
+
+    # returning True stops the run after that step
+    network = RsNetwork().add_nodes().add_nodes(value_children=0)
+    network.input_data_with_callback(timeseries, lambda time_idx: time_idx == 9)
+    assert len(network.node_trajectories[1]["mean"]) == 10
+
+    # the network cannot be used while it runs
+    def mutate(time_idx):
+        network.set_attribute(0, "mean", 1.0)
+
+    with pytest.raises(RuntimeError):
+        network.input_data_with_callback(timeseries, mutate)
+
+
+def test_circular_state():
+    """Test that a circular node tracks a rotating angle across wraps."""
+    angles = np.angle(np.exp(1j * 0.1 * np.arange(300)))
+
+    def run(kind):
+        network = (
+            RsNetwork(volatility_updates="standard")
+            .add_nodes(kind=kind, precision=10.0)
+            .add_nodes(kind=kind, value_children=0, tonic_volatility=-2.0)
+        )
+        network.input_data(angles)
+        predictions = network.node_trajectories[0]["expected_mean"]
+        return np.abs(np.angle(np.exp(1j * (angles - predictions))))
+
+    assert (run("circular-state") < 0.5).all()
+    assert (run("continuous-state") > 1.0).sum() >= 10
+
+
+def test_volatility_coupling():
+    """Test that the volatility coupling strength reaches the child's prediction."""
+
+    def expected_precision(volatility_coupling):
+        network = (
+            RsNetwork(volatility_updates="standard")
+            .add_nodes()
+            .add_nodes(value_children=0, precision=2.0)
+            .add_nodes(
+                volatility_children=1,
+                volatility_coupling=volatility_coupling,
+                mean=1.0,
+            )
+        )
+        network.input_data(np.array([0.3]))
+        return network.node_trajectories[1]["expected_precision"][0]
+
+    assert expected_precision(0.5) > expected_precision(1.0)
+    assert expected_precision([0.5]) == expected_precision(0.5)
+
+    with pytest.raises(ValueError):
+        RsNetwork().add_nodes().add_nodes(
+            volatility_children=0, volatility_coupling=[0.5, 0.5]
+        )
+
+
+def test_aggregate_input():
+    """Test that an aggregate input fuses its streams by precision weighting."""
+    data = np.array(
+        [
+            [1.0, 2.0, 3.0],
+            [np.nan, 2.0, 3.0],
+            [np.nan, np.nan, np.nan],
+        ]
+    )
+    network = (
+        RsNetwork()
+        .add_nodes(kind="aggregate-input", n_streams=3)
+        .add_nodes(value_children=0)
+        .set_input_precision(0, [1.0, 2.0, 4.0])
+    )
+    network.input_data(data)
+
+    input_node = network.node_trajectories[0]
+    assert np.allclose(input_node["mean"][:2], [17.0 / 7.0, 16.0 / 6.0])
+    assert np.allclose(input_node["expected_precision"][:2], [7.0, 6.0])
+    assert np.array_equal(input_node["observed"], [1.0, 1.0, 0.0])
+    parent = network.node_trajectories[1]
+    assert parent["mean"][2] == parent["expected_mean"][2]
+
+    with pytest.raises(ValueError):
+        network.input_data(np.ones((1, 4)))
+    with pytest.raises(ValueError, match="input_precision"):
+        network.set_input_precision(0, [1.0, 0.0])
+    with pytest.raises(ValueError, match="input_precision"):
+        network.set_input_precision(0, [np.inf])
+    with pytest.raises(ValueError, match="input_precision"):
+        RsNetwork().add_nodes(kind="aggregate-input", input_precision=-1.0)
+    with pytest.raises(ValueError, match="n_streams"):
+        RsNetwork().add_nodes(kind="aggregate-input", n_streams=1.5)
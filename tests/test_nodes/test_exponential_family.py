@@ -92,3 +92,25 @@ def test_multivariate_gaussian():
             dtype="float32",
         ),
     ).all()
+
+
+def test_welford_variance():
+    """Test the Welford variance tracker of the ef-state node."""
+    noise = np.sin(1.7 * np.arange(5000))
+
+    centred = (
+        RsNetwork()
+        .add_nodes(kind="ef-state", welford=1.0)
+        .input_data(input_data=noise)
+    )
+    offset = (
+        RsNetwork()
+        .add_nodes(kind="ef-state", welford=1.0)
+        .input_data(input_data=noise + 1e8)
+    )
+    naive = RsNetwork().add_nodes(kind="ef-state").input_data(input_data=noise + 1e8)
+
+    # the variance is shift-invariant, the raw [x, x²] statistics are not
+    reference = centred.node_trajectories[0]["tracked_variance"][-1]
+    assert np.isclose(offset.node_trajectories[0]["tracked_variance"][-1], reference)
+    assert not np.isclose(naive.node_trajectories[0]["tracked_variance"][-1], reference)
@@ -0,0 +1,223 @@
+use rshgf::error::NetworkError;
+use rshgf::model::network::{AddNodesOptions, InputDataOptions, Network};
+use std::collections::HashMap;
+
+/// Three continuous parents (1, 2, 3) read out by a softmax node (0), with the
+/// given initial means.
+fn softmax_network(means: [f64; 3]) -> Network {
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("softmax-state", 1, AddNodesOptions::default())
+        .unwrap();
+    for mean in means {
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    additional_parameters: Some(HashMap::from([("mean".into(), mean)])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+    }
+    network
+}
+
+#[test]
+fn test_softmax_probabilities() {
+    let mut flat = softmax_network([0.0, 0.0, 0.0]);
+    flat.input_data(vec![vec![1.0]], None, true, InputDataOptions::default())
+        .unwrap();
+    let traj = &flat.node_trajectories.nodes[0];
+    for &p in &traj.probabilities[0] {
+        assert!((p - 1.0 / 3.0).abs() < 1e-12);
+    }
+    assert!((traj.surprise()[0] - 3.0_f64.ln()).abs() < 1e-12);
+
+    // Raising the mean of the second parent raises the probability of the
+    // second alternative, at the expense of the others.
+    let mut raised = softmax_network([0.0, 1.0, 0.0]);
+    raised
+        .input_data(vec![vec![1.0]], None, true, InputDataOptions::default())
+        .unwrap();
+    let traj = &raised.node_trajectories.nodes[0];
+    let probabilities = &traj.probabilities[0];
+    assert!(probabilities[1] > 1.0 / 3.0);
+    assert!(probabilities[0] < 1.0 / 3.0);
+    assert!((probabilities.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+    let expected = 1.0_f64.exp() / (2.0 + 1.0_f64.exp());
+    assert!((probabilities[1] - expected).abs() < 1e-12);
+    assert!((traj.surprise()[0] + expected.ln()).abs() < 1e-12);
+
+    // The readout leaves the parents' beliefs untouched.
+    let parent = &raised.node_trajectories.nodes[2];
+    assert_eq!(parent.mean()[0], parent.expected_mean()[0]);
+
+    // One coupling per parent: a stray extra one is reported rather than
+    // silently dropped.
+    let mut mismatched = softmax_network([0.0, 1.0, 0.0]);
+    mismatched.attributes.vectors[0]
+        .value_coupling_parents
+        .push(1.0);
+    assert_eq!(
+        mismatched
+            .input_data(vec![vec![1.0]], None, true, InputDataOptions::default())
+            .unwrap_err(),
+        NetworkError::DimensionMismatch {
+            what: "value_coupling_parents (one per value parent)",
+            expected: 3,
+            found: 4,
+        }
+    );
+}
+
+#[test]
+fn test_softmax_temperature() {
+    let build = |temperature: f64| {
+        let mut network = Network::new("eHGF");
+        network
+            .add_nodes(
+                "softmax-state",
+                1,
+                AddNodesOptions {
+                    additional_parameters: Some(HashMap::from([(
+                        "temperature".into(),
+                        temperature,
+                    )])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    additional_parameters: Some(HashMap::from([("mean".into(), 1.0)])),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+            .add_nodes(
+                "continuous-state",
+                1,
+                AddNodesOptions {
+                    value_children: Some(0.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        network
+            .input_data(vec![vec![0.0]], None, true, InputDataOptions::default())
+            .unwrap();
+        network.node_trajectories.nodes[0].probabilities[0][0]
+    };
+
+    // A hotter softmax is closer to uniform.
+    assert!(build(1.0) > build(10.0));
+    assert!(build(10.0) > 0.5);
+}
+
+#[test]
+fn test_simulate_responses() {
+    // Softmax response node (0) reading three beliefs (1, 2, 3); belief 1
+    // also predicts the perceptual input (4).
+    let mut network = softmax_network([0.0, 0.5, 0.0]);
+    network
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_parents: Some(vec![1].into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(network.inputs, vec![0, 4]);
+
+    let n_trials = 4000;
+    let observations: Vec<Vec<f64>> = (0..n_trials)
+        .map(|t| vec![(t as f64 * 0.01).sin()])
+        .collect();
+    let responses = network
+        .simulate_responses(&observations, None, Some(7))
+        .unwrap();
+    assert_eq!(responses.len(), n_trials);
+
+    // The choices stand in for the observed responses, and their empirical
+    // frequencies follow the predicted probabilities.
+    let traj = &network.node_trajectories.nodes[0];
+    assert_eq!(traj.n_time, n_trials);
+    let mut frequencies = [0.0; 3];
+    let mut predicted = [0.0; 3];
+    for t in 0..n_trials {
+        assert_eq!(responses[t].len(), 1);
+        assert_eq!(traj.mean()[t], responses[t][0]);
+        frequencies[responses[t][0] as usize] += 1.0 / n_trials as f64;
+        for (k, &p) in traj.probabilities[t].iter().enumerate() {
+            predicted[k] += p / n_trials as f64;
+        }
+    }
+    for k in 0..3 {
+        assert!(
+            (frequencies[k] - predicted[k]).abs() < 0.03,
+            "{:?} vs {:?}",
+            frequencies,
+            predicted
+        );
+    }
+    assert!(predicted[1] > predicted[0]);
+
+    // The same seed simulates the same choices.
+    let mut again = softmax_network([0.0, 0.5, 0.0]);
+    again
+        .add_nodes(
+            "continuous-state",
+            1,
+            AddNodesOptions {
+                value_parents: Some(vec![1].into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        again
+            .simulate_responses(&observations, None, Some(7))
+            .unwrap(),
+        responses
+    );
+
+    // Rows hold the perceptual inputs only.
+    assert!(again
+        .simulate_responses(&[vec![0.0, 1.0]], None, Some(7))
+        .is_err());
+
+    // Every row is checked before the first step runs.
+    let mut positive = softmax_network([0.0, 0.5, 0.0]);
+    positive
+        .add_nodes(
+            "lognormal-state",
+            1,
+            AddNodesOptions {
+                value_parents: Some(vec![1].into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let before = format!("{:?}", positive.attributes);
+    assert_eq!(
+        positive
+            .simulate_responses(&[vec![1.0], vec![2.0], vec![-1.0]], None, Some(7))
+            .unwrap_err(),
+        NetworkError::InvalidObservation {
+            node_idx: 4,
+            value: -1.0,
+            accepted: "a positive value",
+        }
+    );
+    assert_eq!(format!("{:?}", positive.attributes), before);
+}
@@ -1,4 +1,4 @@
-use rshgf::model::network::Network;
+use rshgf::model::network::{AddNodesOptions, InputDataOptions, Network, TrajectoryColumn};
 use std::collections::HashMap;
 
 /// Helper to assert approximate equality of f64 values.
@@ -24,18 +24,18 @@ fn assert_value_level_match(
     let traj_a = &net_a.node_trajectories.nodes[node_a];
     let traj_b = &net_b.node_trajectories.nodes[node_b];
 
-    let fields: Vec<(&str, &Vec<f64>, &Vec<f64>)> = vec![
-        ("mean", &traj_a.mean, &traj_b.mean),
+    let fields: Vec<(&str, TrajectoryColumn, TrajectoryColumn)> = vec![
+        ("mean", traj_a.mean(), traj_b.mean()),
         (
             "expected_mean",
-            &traj_a.expected_mean,
-            &traj_b.expected_mean,
+            traj_a.expected_mean(),
+            traj_b.expected_mean(),
         ),
-        ("precision", &traj_a.precision, &traj_b.precision),
+        ("precision", traj_a.precision(), traj_b.precision()),
         (
             "expected_precision",
-            &traj_a.expected_precision,
-            &traj_b.expected_precision,
+            traj_a.expected_precision(),
+            traj_b.expected_precision(),
         ),
     ];
 
@@ -63,25 +63,25 @@ fn assert_vol_level_match(
     let vol_traj = &volatile_net.node_trajectories.nodes[vol_node];
     let exp_traj = &explicit_net.node_trajectories.nodes[exp_node];
 
-    let fields: Vec<(&str, &Vec<f64>, &str, &Vec<f64>)> = vec![
-        ("mean_vol", &vol_traj.mean_vol, "mean", &exp_traj.mean),
+    let fields: Vec<(&str, TrajectoryColumn, &str, TrajectoryColumn)> = vec![
+        ("mean_vol", vol_traj.mean_vol(), "mean", exp_traj.mean()),
         (
             "expected_mean_vol",
-            &vol_traj.expected_mean_vol,
+            vol_traj.expected_mean_vol(),
             "expected_mean",
-            &exp_traj.expected_mean,
+            exp_traj.expected_mean(),
         ),
         (
             "precision_vol",
-            &vol_traj.precision_vol,
+            vol_traj.precision_vol(),
             "precision",
-            &exp_traj.precision,
+            exp_traj.precision(),
         ),
         (
             "expected_precision_vol",
-            &vol_traj.expected_precision_vol,
+            vol_traj.expected_precision_vol(),
             "expected_precision",
-            &exp_traj.expected_precision,
+            exp_traj.expected_precision(),
         ),
     ];
 
@@ -100,19 +100,26 @@ fn assert_vol_level_match(
 /// Build a volatile network: input (node 0) + volatile-state value parent (node 1).
 fn build_volatile_network(volatility_updates: &str, data: &[f64]) -> Network {
     let mut net = Network::new(volatility_updates);
-    net.add_nodes("continuous-state", 1, None, None, None, None, None, None);
+    net.add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
     net.add_nodes(
         "volatile-state",
         1,
-        None,
-        Some(0.into()),
-        None,
-        None,
-        None,
-        Some(HashMap::from([("autoconnection_strength".into(), 1.0)])),
-    );
+        AddNodesOptions {
+            value_children: Some(0.into()),
+            additional_parameters: Some(HashMap::from([("autoconnection_strength".into(), 1.0)])),
+            ..Default::default()
+        },
+    )
+    .unwrap();
     net.set_update_sequence();
-    net.input_data(data.iter().map(|v| vec![*v]).collect(), None, true);
+    net.input_data(
+        data.iter().map(|v| vec![*v]).collect(),
+        None,
+        true,
+        InputDataOptions::default(),
+    )
+    .unwrap();
     net
 }
 
@@ -120,35 +127,41 @@ fn build_volatile_network(volatility_updates: &str, data: &[f64]) -> Network {
 /// parent of node 1 (node 2).
 fn build_explicit_network(volatility_updates: &str, data: &[f64]) -> Network {
     let mut net = Network::new(volatility_updates);
-    net.add_nodes("continuous-state", 1, None, None, None, None, None, None);
+    net.add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
     net.add_nodes(
         "continuous-state",
         1,
-        None,
-        Some(0.into()),
-        None,
-        None,
-        None,
-        // The fused volatile node's value level has no tonic volatility, so the
-        // explicit value node must set tonic_volatility = 0.0 to match (its
-        // volatility parent keeps the default, mirroring tonic_volatility_vol).
-        Some(std::collections::HashMap::from([(
-            "tonic_volatility".into(),
-            0.0,
-        )])),
-    );
+        AddNodesOptions {
+            value_children: Some(0.into()),
+            // The fused volatile node's value level has no tonic volatility, so the
+            // explicit value node must set tonic_volatility = 0.0 to match (its
+            // volatility parent keeps the default, mirroring tonic_volatility_vol).
+            additional_parameters: Some(std::collections::HashMap::from([(
+                "tonic_volatility".into(),
+                0.0,
+            )])),
+            ..Default::default()
+        },
+    )
+    .unwrap();
     net.add_nodes(
         "continuous-state",
         1,
-        None,
-        None,
-        None,
-        Some(1.into()),
-        None,
-        None,
-    );
+        AddNodesOptions {
+            volatility_children: Some(1.into()),
+            ..Default::default()
+        },
+    )
+    .unwrap();
     net.set_update_sequence();
-    net.input_data(data.iter().map(|v| vec![*v]).collect(), None, true);
+    net.input_data(
+        data.iter().map(|v| vec![*v]).collect(),
+        None,
+        true,
+        InputDataOptions::default(),
+    )
+    .unwrap();
     net
 }
 
@@ -195,3 +208,89 @@ fn test_volatile_ehgf_matches_explicit() {
 fn test_volatile_unbounded_matches_explicit() {
     compare_volatile_and_explicit("unbounded");
 }
+
+#[test]
+fn test_volatile_autoconnection_vol() {
+    // The implicit volatility level is predicted as λ_vol · μ_vol.
+    let mut network = Network::new("eHGF");
+    network
+        .add_nodes("continuous-state", 1, AddNodesOptions::default())
+        .unwrap();
+    network
+        .add_nodes(
+            "volatile-state",
+            1,
+            AddNodesOptions {
+                value_children: Some(0.into()),
+                additional_parameters: Some(HashMap::from([("mean_vol".into(), -2.0)])),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    network.set_autoconnection_vol(1, 0.5).unwrap();
+    assert!(network.set_autoconnection_vol(1, 2.0).is_err());
+
+    network
+        .input_data(
+            vec![vec![0.3], vec![-0.1], vec![0.2]],
+            None,
+            true,
+            InputDataOptions::default(),
+        )
+        .unwrap();
+
+    let traj = &network.node_trajectories.nodes[1];
+    let mut previous_mean_vol = -2.0;
+    for t in 0..3 {
+        assert_close(
+            traj.expected_mean_vol()[t],
+            0.5 * previous_mean_vol,
+            1e-12,
+            &format!("expected_mean_vol t={}", t),
+        );
+        assert_close(
+            traj.autoconnection_strength_vol()[t],
+            0.5,
+            1e-12,
+            "autoconnection_strength_vol",
+        );
+        previous_mean_vol = traj.mean_vol()[t];
+    }
+}
+
+#[test]
+fn test_get_effective_precision() {
+    let data: Vec<f64> = (0..20).map(|i| (i as f64) * 0.1).collect();
+    let explicit_net = build_explicit_network("eHGF", &data);
+    let volatile_net = build_volatile_network("eHGF", &data);
+
+    let (gamma, gamma_vol) = explicit_net.get_effective_precision(1).unwrap();
+    let traj = &explicit_net.node_trajectories.nodes[1];
+    assert_eq!(gamma, traj.effective_precision().to_vec());
+    assert!(gamma_vol.is_none());
+    assert!(explicit_net.get_effective_precision(3).is_err());
+
+    // γ = Ω · π̂, with Ω recovered from π̂ = 1 / (1/π + Ω) (Δt = 1, and node 1
+    // has no value parents, so π̂ carries no parent-uncertainty term).
+    let t = 7;
+    let expected_precision = traj.expected_precision()[t];
+    let omega = 1.0 / expected_precision - 1.0 / traj.precision()[t - 1];
+    assert_close(gamma[t], omega * expected_precision, 1e-10, "explicit γ");
+
+    let (gamma, gamma_vol) = volatile_net.get_effective_precision(1).unwrap();
+    let gamma_vol = gamma_vol.unwrap();
+    let traj = &volatile_net.node_trajectories.nodes[1];
+    assert_eq!(gamma, traj.effective_precision().to_vec());
+    assert_eq!(gamma_vol, traj.effective_precision_vol().to_vec());
+
+    let expected_precision = traj.expected_precision()[t];
+    let omega = 1.0 / expected_precision - 1.0 / traj.precision()[t - 1];
+    assert_close(gamma[t], omega * expected_precision, 1e-10, "volatile γ");
+    let omega_vol = traj.tonic_volatility_vol()[t].exp();
+    assert_close(
+        gamma_vol[t],
+        omega_vol * traj.expected_precision_vol()[t],
+        1e-10,
+        "volatile γ_vol",
+    );
+}